@@ -34,7 +34,6 @@ use std::sync::Arc;
 use std::time::SystemTime;
 
 use bstr::ByteVec as _;
-use chrono::TimeZone as _;
 use clap::builder::MapValueParser;
 use clap::builder::NonEmptyStringValueParser;
 use clap::builder::TypedValueParser as _;
@@ -111,6 +110,7 @@ use jj_lib::revset::RevsetIteratorExt as _;
 use jj_lib::revset::RevsetModifier;
 use jj_lib::revset::RevsetParseContext;
 use jj_lib::revset::RevsetWorkspaceContext;
+use jj_lib::revset::SymbolLocations;
 use jj_lib::revset::SymbolResolverExtension;
 use jj_lib::revset::UserRevsetExpression;
 use jj_lib::rewrite::restore_tree;
@@ -142,6 +142,7 @@ use tracing_chrome::ChromeLayerBuilder;
 use tracing_subscriber::prelude::*;
 
 use crate::command_error::cli_error;
+use crate::command_error::config_error;
 use crate::command_error::config_error_with_message;
 use crate::command_error::handle_command_result;
 use crate::command_error::internal_error;
@@ -180,6 +181,7 @@ use crate::template_parser::TemplateDiagnostics;
 use crate::templater::PropertyPlaceholder;
 use crate::templater::TemplateRenderer;
 use crate::text_util;
+use crate::time_util::Clock;
 use crate::ui::ColorChoice;
 use crate::ui::Ui;
 
@@ -829,13 +831,7 @@ impl WorkspaceCommandEnvironment {
             path_converter: &self.path_converter,
             workspace_name: &self.workspace_name,
         };
-        let now = if let Some(timestamp) = self.settings.commit_timestamp() {
-            chrono::Local
-                .timestamp_millis_opt(timestamp.timestamp.0)
-                .unwrap()
-        } else {
-            chrono::Local::now()
-        };
+        let now = Clock::from_settings(&self.settings).now_local();
         RevsetParseContext {
             aliases_map: &self.revset_aliases_map,
             local_variables: HashMap::new(),
@@ -1344,10 +1340,44 @@ to the current parents may contain changes from multiple commits.
     }
 
     /// Parses the given strings as file patterns.
+    ///
+    /// Functions and patterns which depend on the file contents, such as
+    /// `conflicts()`, are rejected. Use `parse_file_patterns_in_tree()` to
+    /// resolve them.
     pub fn parse_file_patterns(
         &self,
         ui: &Ui,
         values: &[String],
+    ) -> Result<FilesetExpression, CommandError> {
+        let expression = self.parse_file_patterns_unresolved(ui, values)?;
+        if let Some(name) = expression.unresolved_predicate_name() {
+            return Err(user_error_with_hint(
+                format!("{name} can't be used with this command"),
+                "It can be used with `jj diff`, `jj file list`, `jj log`, `jj resolve`, and the \
+                 `files()` revset function",
+            ));
+        }
+        Ok(expression)
+    }
+
+    /// Parses the given strings as file patterns, and resolves the functions
+    /// and patterns which depend on the file contents against the `tree`.
+    pub fn parse_file_patterns_in_tree(
+        &self,
+        ui: &Ui,
+        values: &[String],
+        tree: &MergedTree,
+    ) -> Result<FilesetExpression, CommandError> {
+        let expression = self.parse_file_patterns_unresolved(ui, values)?;
+        Ok(expression.resolve_in_tree(tree)?)
+    }
+
+    /// Parses the given strings as file patterns, leaving the functions and
+    /// patterns which depend on the file contents unresolved.
+    pub fn parse_file_patterns_unresolved(
+        &self,
+        ui: &Ui,
+        values: &[String],
     ) -> Result<FilesetExpression, CommandError> {
         // TODO: This function might be superseded by parse_union_filesets(),
         // but it would be weird if parse_union_*() had a special case for the
@@ -1386,6 +1416,11 @@ to the current parents may contain changes from multiple commits.
             },
         )?;
         print_parse_diagnostics(ui, "In `snapshot.auto-track`", &diagnostics)?;
+        if let Some(name) = expression.unresolved_predicate_name() {
+            return Err(config_error(format!(
+                "{name} can't be used in `snapshot.auto-track`"
+            )));
+        }
         Ok(expression.to_matcher())
     }
 
@@ -1655,10 +1690,17 @@ to the current parents may contain changes from multiple commits.
     ) -> Result<(RevsetExpressionEvaluator<'_>, Option<RevsetModifier>), CommandError> {
         let mut diagnostics = RevsetDiagnostics::new();
         let context = self.env.revset_parse_context();
-        let (expression, modifier) =
-            revset::parse_with_modifier(&mut diagnostics, revision_arg.as_ref(), &context)?;
+        let mut symbol_locations = SymbolLocations::new();
+        let (expression, modifier) = revset::parse_with_symbol_locations(
+            &mut diagnostics,
+            revision_arg.as_ref(),
+            &context,
+            &mut symbol_locations,
+        )?;
+        let evaluator = self.attach_revset_evaluator(expression);
+        self.add_symbol_resolution_diagnostics(&mut diagnostics, symbol_locations, &evaluator)?;
         print_parse_diagnostics(ui, "In revset expression", &diagnostics)?;
-        Ok((self.attach_revset_evaluator(expression), modifier))
+        Ok((evaluator, modifier))
     }
 
     /// Parses the given revset expressions and concatenates them all.
@@ -1669,14 +1711,43 @@ to the current parents may contain changes from multiple commits.
     ) -> Result<RevsetExpressionEvaluator<'_>, CommandError> {
         let mut diagnostics = RevsetDiagnostics::new();
         let context = self.env.revset_parse_context();
+        let mut symbol_locations = SymbolLocations::new();
         let expressions: Vec<_> = revision_args
             .iter()
-            .map(|arg| revset::parse_with_modifier(&mut diagnostics, arg.as_ref(), &context))
+            .map(|arg| {
+                revset::parse_with_symbol_locations(
+                    &mut diagnostics,
+                    arg.as_ref(),
+                    &context,
+                    &mut symbol_locations,
+                )
+            })
             .map_ok(|(expression, None | Some(RevsetModifier::All))| expression)
             .try_collect()?;
-        print_parse_diagnostics(ui, "In revset expression", &diagnostics)?;
         let expression = RevsetExpression::union_all(&expressions);
-        Ok(self.attach_revset_evaluator(expression))
+        let evaluator = self.attach_revset_evaluator(expression);
+        self.add_symbol_resolution_diagnostics(&mut diagnostics, symbol_locations, &evaluator)?;
+        print_parse_diagnostics(ui, "In revset expression", &diagnostics)?;
+        Ok(evaluator)
+    }
+
+    /// Adds warnings about symbols resolved by case-insensitive match. The
+    /// expression is resolved here, and the result is reused by the evaluator.
+    /// Resolution errors are reported later when the expression is evaluated.
+    fn add_symbol_resolution_diagnostics(
+        &self,
+        diagnostics: &mut RevsetDiagnostics,
+        symbol_locations: SymbolLocations,
+        evaluator: &RevsetExpressionEvaluator<'_>,
+    ) -> Result<(), CommandError> {
+        if !self.settings().get_bool("revsets.case-insensitive-refs")? {
+            return Ok(());
+        }
+        let Ok((_, symbol_warnings)) = evaluator.resolve_with_symbol_warnings() else {
+            return Ok(());
+        };
+        revset::add_symbol_warnings(diagnostics, symbol_locations, &symbol_warnings);
+        Ok(())
     }
 
     pub fn attach_revset_evaluator(
@@ -2428,6 +2499,11 @@ impl WorkspaceCommandTransaction<'_> {
         self.tx.repo_mut()
     }
 
+    /// Records a tag in the metadata of the operation to be created.
+    pub fn set_tag(&mut self, key: String, value: String) {
+        self.tx.set_tag(key, value);
+    }
+
     pub fn check_out(&mut self, commit: &Commit) -> Result<Commit, CheckOutCommitError> {
         let name = self.helper.workspace_name().to_owned();
         self.id_prefix_context.take(); // invalidate
@@ -856,6 +856,9 @@ fn revset_parse_error_hint(err: &RevsetParseError) -> Option<String> {
             name: _,
             candidates,
         } => format_similarity_hint(candidates),
+        RevsetParseErrorKind::RenamedFunction { name, new_name } => {
+            Some(format!("`{name}` was renamed to `{new_name}`"))
+        }
         RevsetParseErrorKind::InvalidFunctionArguments { .. }
         | RevsetParseErrorKind::Expression(_) => find_source_parse_error_hint(bottom_err),
         _ => None,
@@ -867,11 +870,28 @@ fn revset_resolution_error_hint(err: &RevsetResolutionError) -> Option<String> {
         RevsetResolutionError::NoSuchRevision {
             name: _,
             candidates,
+        }
+        | RevsetResolutionError::WorkspaceMissingWorkingCopy {
+            name: _,
+            candidates,
         } => format_similarity_hint(candidates),
-        RevsetResolutionError::EmptyString
-        | RevsetResolutionError::WorkspaceMissingWorkingCopy { .. }
-        | RevsetResolutionError::AmbiguousCommitIdPrefix(_)
-        | RevsetResolutionError::AmbiguousChangeIdPrefix(_)
+        RevsetResolutionError::AmbiguousCommitIdPrefix {
+            prefix: _,
+            candidates,
+        }
+        | RevsetResolutionError::AmbiguousChangeIdPrefix {
+            prefix: _,
+            candidates,
+        } => match candidates.as_slice() {
+            [] => None,
+            names => {
+                let quoted_names = names.iter().map(|s| format!("`{s}`")).join(", ");
+                Some(format!("The prefix matches {quoted_names}"))
+            }
+        },
+        RevsetResolutionError::AmbiguousRemoteRef { .. }
+        | RevsetResolutionError::AmbiguousExtensionSymbol { .. }
+        | RevsetResolutionError::EmptyString
         | RevsetResolutionError::StoreError(_)
         | RevsetResolutionError::Other(_) => None,
     }
@@ -1042,11 +1062,14 @@ pub fn print_parse_diagnostics<T: error::Error>(
     context_message: &str,
     diagnostics: &Diagnostics<T>,
 ) -> io::Result<()> {
-    for diag in diagnostics {
+    for (diag, count) in diagnostics.iter_with_counts() {
         writeln!(ui.warning_default(), "{context_message}")?;
         for err in iter::successors(Some(diag as &dyn error::Error), |&err| err.source()) {
             writeln!(ui.stderr(), "{err}")?;
         }
+        if count > 1 {
+            writeln!(ui.stderr(), "(reported {count} times)")?;
+        }
         // If we add support for multiple error diagnostics, we might have to do
         // find_source_parse_error_hint() and print it here.
     }
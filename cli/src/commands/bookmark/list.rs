@@ -25,7 +25,7 @@ use jj_lib::backend;
 use jj_lib::backend::CommitId;
 use jj_lib::config::ConfigValue;
 use jj_lib::ref_name::RefName;
-use jj_lib::repo::Repo as _;
+use jj_lib::repo::Repo;
 use jj_lib::revset::RevsetExpression;
 use jj_lib::str_util::StringPattern;
 
@@ -122,6 +122,9 @@ pub struct BookmarkListArgs {
     /// `--sort name-`). Note that when using multiple keys, the first key is
     /// the most significant.
     ///
+    /// The `ahead` key sorts by the number of commits the local bookmark is
+    /// ahead of its tracked remote bookmarks.
+    ///
     /// This defaults to the `ui.bookmark-list-sort-keys` setting.
     #[arg(long, value_name = "SORT_KEY", value_enum, value_delimiter = ',')]
     sort: Vec<SortKey>,
@@ -189,7 +192,21 @@ pub fn cmd_bookmark_list(
             .labeled("bookmark_list")
     };
 
+    let sort_keys = if args.sort.is_empty() {
+        workspace_command
+            .settings()
+            .get_value_with("ui.bookmark-list-sort-keys", parse_sort_keys)?
+    } else {
+        args.sort.clone()
+    };
+    let needs_ahead_counts = sort_keys
+        .iter()
+        .any(|key| matches!(key, SortKey::Ahead | SortKey::AheadDesc));
+
     let mut bookmark_list_items: Vec<RefListItem> = Vec::new();
+    // (name, local target ids, tracked remote target ids) of the bookmarks
+    // whose ahead counts are needed.
+    let mut ahead_targets: Vec<(&RefName, Vec<CommitId>, Vec<CommitId>)> = Vec::new();
     let bookmarks_to_list = view.bookmarks().filter(|(name, target)| {
         bookmark_names_to_list
             .as_ref()
@@ -219,6 +236,20 @@ pub fn cmd_bookmark_list(
 
         let include_local_only = !args.tracked && args.remotes.is_none();
         if include_local_only && local_target.is_present() || !tracked_remote_refs.is_empty() {
+            if needs_ahead_counts {
+                let local_ids = local_target.added_ids().cloned().collect_vec();
+                let remote_ids = remote_refs
+                    .iter()
+                    .filter(|&&(remote, remote_ref)| {
+                        remote_ref.is_tracked() && !jj_lib::git::is_special_git_remote(remote)
+                    })
+                    .flat_map(|&(_, remote_ref)| remote_ref.target.added_ids().cloned())
+                    .collect_vec();
+                // A bookmark without tracked remotes has nothing to be ahead of.
+                if !remote_ids.is_empty() {
+                    ahead_targets.push((name, local_ids, remote_ids));
+                }
+            }
             let primary = CommitRef::local(
                 name,
                 local_target.clone(),
@@ -243,13 +274,8 @@ pub fn cmd_bookmark_list(
         }
     }
 
-    let sort_keys = if args.sort.is_empty() {
-        workspace_command
-            .settings()
-            .get_value_with("ui.bookmark-list-sort-keys", parse_sort_keys)?
-    } else {
-        args.sort.clone()
-    };
+    let ahead_counts = count_ahead_commits(repo.as_ref(), &ahead_targets)?;
+
     let store = repo.store();
     let mut commits: HashMap<CommitId, Arc<backend::Commit>> = HashMap::new();
     if sort_keys.iter().any(|key| key.is_commit_dependant()) {
@@ -264,7 +290,12 @@ pub fn cmd_bookmark_list(
             })
             .try_collect()?;
     }
-    sort(&mut bookmark_list_items, &sort_keys, &commits);
+    sort(
+        &mut bookmark_list_items,
+        &sort_keys,
+        &commits,
+        &ahead_counts,
+    );
 
     ui.request_pager();
     let mut formatter = ui.stdout_formatter();
@@ -343,12 +374,15 @@ enum SortKey {
     CommitterDate,
     #[value(name = "committer-date-")]
     CommitterDateDesc,
+    Ahead,
+    #[value(name = "ahead-")]
+    AheadDesc,
 }
 
 impl SortKey {
     fn is_commit_dependant(&self) -> bool {
         match self {
-            SortKey::Name | SortKey::NameDesc => false,
+            SortKey::Name | SortKey::NameDesc | SortKey::Ahead | SortKey::AheadDesc => false,
             SortKey::AuthorName
             | SortKey::AuthorNameDesc
             | SortKey::AuthorEmail
@@ -380,15 +414,97 @@ fn parse_sort_keys(value: ConfigValue) -> Result<Vec<SortKey>, String> {
     }
 }
 
+/// Counts the commits each local bookmark is ahead of its tracked remote
+/// bookmarks, walking the history once for all bookmarks.
+///
+/// `targets` lists the name, the local target ids, and the tracked remote
+/// target ids of each bookmark.
+fn count_ahead_commits(
+    repo: &dyn Repo,
+    targets: &[(&RefName, Vec<CommitId>, Vec<CommitId>)],
+) -> Result<HashMap<String, usize>, CommandError> {
+    /// Indices of the bookmarks whose local/remote targets reach a commit.
+    #[derive(Default)]
+    struct Reach {
+        local: HashSet<usize>,
+        remote: HashSet<usize>,
+    }
+
+    impl Reach {
+        fn is_covered(&self) -> bool {
+            self.local.is_subset(&self.remote)
+        }
+    }
+
+    let mut pending: HashMap<CommitId, Reach> = HashMap::new();
+    for (index, (_, local_ids, remote_ids)) in targets.iter().enumerate() {
+        for id in local_ids {
+            pending.entry(id.clone()).or_default().local.insert(index);
+        }
+        for id in remote_ids {
+            pending.entry(id.clone()).or_default().remote.insert(index);
+        }
+    }
+    let mut counts = vec![0; targets.len()];
+    // Once all pending commits are reachable from the remote targets of the
+    // bookmarks reaching them, none of their ancestors can be ahead.
+    let mut num_uncovered = pending.values().filter(|reach| !reach.is_covered()).count();
+    let heads = pending.keys().cloned().collect_vec();
+    let revset = RevsetExpression::commits(heads)
+        .ancestors()
+        .evaluate(repo)?;
+    for node in revset.iter_graph() {
+        if num_uncovered == 0 {
+            break;
+        }
+        let (id, edges) = node?;
+        let Some(reach) = pending.remove(&id) else {
+            continue;
+        };
+        if !reach.is_covered() {
+            num_uncovered -= 1;
+            for &index in reach.local.difference(&reach.remote) {
+                counts[index] += 1;
+            }
+        }
+        for edge in edges {
+            let parent_reach = pending.entry(edge.target).or_default();
+            let was_covered = parent_reach.is_covered();
+            parent_reach.local.extend(&reach.local);
+            parent_reach.remote.extend(&reach.remote);
+            match (was_covered, parent_reach.is_covered()) {
+                (true, false) => num_uncovered += 1,
+                (false, true) => num_uncovered -= 1,
+                _ => {}
+            }
+        }
+    }
+    Ok(targets
+        .iter()
+        .zip(counts)
+        .map(|((name, _, _), count)| (name.as_str().to_owned(), count))
+        .collect())
+}
+
 fn sort(
     bookmark_items: &mut [RefListItem],
     sort_keys: &[SortKey],
     commits: &HashMap<CommitId, Arc<backend::Commit>>,
+    ahead_counts: &HashMap<String, usize>,
 ) {
     let to_commit = |item: &RefListItem| {
         let id = item.primary.target().added_ids().next()?;
         commits.get(id)
     };
+    // Remote-only bookmarks and bookmarks without tracked remotes aren't ahead
+    // of anything.
+    let to_ahead_count = |item: &RefListItem| {
+        if item.primary.is_local() {
+            ahead_counts.get(item.primary.name()).copied().unwrap_or(0)
+        } else {
+            0
+        }
+    };
 
     // Multi-pass sorting, the first key is most significant.
     // Skip first iteration if sort key is `Name`, since bookmarks are already
@@ -445,6 +561,10 @@ fn sort(
             SortKey::CommitterDateDesc => bookmark_items.sort_by_key(|item| {
                 cmp::Reverse(to_commit(item).map(|commit| commit.committer.timestamp))
             }),
+            SortKey::Ahead => bookmark_items.sort_by_key(to_ahead_count),
+            SortKey::AheadDesc => {
+                bookmark_items.sort_by_key(|item| cmp::Reverse(to_ahead_count(item)));
+            }
         }
     }
 }
@@ -465,6 +585,7 @@ mod tests {
         Arc::new(backend::Commit {
             parents: vec![],
             predecessors: vec![],
+            duplicated_from: None,
             root_tree: MergedTreeId::Legacy(TreeId::new(vec![])),
             change_id: ChangeId::new(vec![]),
             description: String::new(),
@@ -606,7 +727,7 @@ mod tests {
         sort_keys: &[SortKey],
         commits: &HashMap<CommitId, Arc<backend::Commit>>,
     ) -> String {
-        sort(items, sort_keys, commits);
+        sort(items, sort_keys, commits, &HashMap::new());
 
         let to_commit = |item: &RefListItem| {
             let id = item.primary.target().added_ids().next()?;
@@ -883,4 +1004,59 @@ mod tests {
         chore               Test User       test.user@g.com  0             eve             test.user@g.com  0
         ");
     }
+
+    #[test]
+    fn test_sort_by_ahead() {
+        let mut new_commit_id = commit_id_generator();
+        let mut items = ["a", "b", "c", "d"]
+            .map(|name| RefListItem {
+                primary: CommitRef::local_only(name, RefTarget::normal(new_commit_id())),
+                tracked: vec![],
+            })
+            .to_vec();
+        items.insert(
+            1,
+            RefListItem {
+                primary: CommitRef::remote_only("a", "origin", RefTarget::normal(new_commit_id())),
+                tracked: vec![],
+            },
+        );
+        // "d" has no tracked remotes.
+        let ahead_counts = HashMap::from([
+            ("a".to_owned(), 1),
+            ("b".to_owned(), 3),
+            ("c".to_owned(), 0),
+        ]);
+        let sorted_names = |sort_keys: &[SortKey]| {
+            let mut items = items.clone();
+            sort(&mut items, sort_keys, &HashMap::new(), &ahead_counts);
+            items
+                .iter()
+                .map(|item| {
+                    [Some(item.primary.name()), item.primary.remote_name()]
+                        .iter()
+                        .flatten()
+                        .join("@")
+                })
+                .collect_vec()
+        };
+        insta::assert_debug_snapshot!(sorted_names(&[SortKey::Ahead, SortKey::Name]), @r#"
+        [
+            "a@origin",
+            "c",
+            "d",
+            "a",
+            "b",
+        ]
+        "#);
+        insta::assert_debug_snapshot!(sorted_names(&[SortKey::AheadDesc, SortKey::Name]), @r#"
+        [
+            "b",
+            "a",
+            "a@origin",
+            "c",
+            "d",
+        ]
+        "#);
+    }
 }
@@ -0,0 +1,78 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use jj_lib::bundle::Bundle;
+use jj_lib::file_util::IoResultExt as _;
+use tracing::instrument;
+
+use super::map_bundle_error;
+use crate::cli_util::CommandHelper;
+use crate::command_error::user_error_with_message;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Import revisions from a bundle file
+///
+/// The imported revisions become visible in the repository. Revisions which
+/// already exist aren't imported again.
+#[derive(clap::Args, Clone, Debug)]
+pub struct BundleApplyArgs {
+    /// Path of the bundle file to read
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    path: PathBuf,
+}
+
+#[instrument(skip_all)]
+pub fn cmd_bundle_apply(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BundleApplyArgs,
+) -> Result<(), CommandError> {
+    let path = command.cwd().join(&args.path);
+    let data = fs::read(&path).context(&path)?;
+    let bundle = Bundle::from_bytes(&data).map_err(|err| {
+        user_error_with_message(
+            format!("Failed to read bundle {}", args.path.display()),
+            err,
+        )
+    })?;
+
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut tx = workspace_command.start_transaction();
+    let applied = bundle.apply(tx.repo_mut()).map_err(map_bundle_error)?;
+    if let Some(mut formatter) = ui.status_formatter() {
+        if !applied.existing_commits.is_empty() {
+            writeln!(
+                formatter,
+                "Skipped {} commits which already exist",
+                applied.existing_commits.len()
+            )?;
+        }
+        if !applied.new_commits.is_empty() {
+            writeln!(formatter, "Imported {} commits:", applied.new_commits.len())?;
+            for commit in applied.new_commits.iter().rev() {
+                write!(formatter, "  ")?;
+                tx.write_commit_summary(formatter.as_mut(), commit)?;
+                writeln!(formatter)?;
+            }
+        }
+    }
+    let num_commits = applied.new_commits.len() + applied.existing_commits.len();
+    tx.finish(ui, format!("apply bundle with {num_commits} commits"))?;
+    Ok(())
+}
@@ -0,0 +1,100 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use clap_complete::ArgValueCandidates;
+use itertools::Itertools as _;
+use jj_lib::bundle::Bundle;
+use jj_lib::commit::Commit;
+use jj_lib::file_util::IoResultExt as _;
+use jj_lib::repo::Repo as _;
+use tracing::instrument;
+
+use super::map_bundle_error;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::command_error::user_error;
+use crate::command_error::CommandError;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Write revisions to a bundle file
+///
+/// Parents of the bundled revisions which aren't included in the bundle are
+/// listed as base commits. They must exist in the repository the bundle is
+/// applied to. If that repository uses a different backend, base commits are
+/// found by change id.
+///
+/// Files which are unchanged since the base commits aren't included in the
+/// bundle. They're read from the base commits when the bundle is applied.
+#[derive(clap::Args, Clone, Debug)]
+pub struct BundleCreateArgs {
+    /// The revisions to include in the bundle
+    #[arg(
+        long,
+        short,
+        required = true,
+        value_name = "REVSETS",
+        add = ArgValueCandidates::new(complete::all_revisions),
+    )]
+    revisions: Vec<RevisionArg>,
+    /// Path of the bundle file to write
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    path: PathBuf,
+}
+
+#[instrument(skip_all)]
+pub fn cmd_bundle_create(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BundleCreateArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let repo = workspace_command.repo();
+    let commits: Vec<Commit> = workspace_command
+        .parse_union_revsets(ui, &args.revisions)?
+        .evaluate_to_commits()?
+        .try_collect()?;
+    if commits.is_empty() {
+        return Err(user_error("No revisions to bundle"));
+    }
+    let bundle = Bundle::create(repo.store(), &commits).map_err(map_bundle_error)?;
+    let path = command.cwd().join(&args.path);
+    fs::write(&path, bundle.to_bytes()).context(&path)?;
+
+    if let Some(mut formatter) = ui.status_formatter() {
+        writeln!(formatter, "Bundled {} commits", commits.len())?;
+        let base_commit_ids = bundle
+            .base_commit_ids()
+            .into_iter()
+            .filter(|id| id != repo.store().root_commit_id())
+            .collect_vec();
+        if !base_commit_ids.is_empty() {
+            writeln!(
+                formatter,
+                "The bundle requires these commits in the destination repository:"
+            )?;
+            for id in &base_commit_ids {
+                let commit = repo.store().get_commit(id)?;
+                write!(formatter, "  ")?;
+                workspace_command.write_commit_summary(formatter.as_mut(), &commit)?;
+                writeln!(formatter)?;
+            }
+        }
+    }
+    Ok(())
+}
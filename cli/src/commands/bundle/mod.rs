@@ -0,0 +1,69 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod apply;
+mod create;
+
+use itertools::Itertools as _;
+use jj_lib::bundle::BundleError;
+
+use self::apply::cmd_bundle_apply;
+use self::apply::BundleApplyArgs;
+use self::create::cmd_bundle_create;
+use self::create::BundleCreateArgs;
+use crate::cli_util::short_commit_hash;
+use crate::cli_util::CommandHelper;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Exchange revisions with another repository through a bundle file
+///
+/// A bundle contains commits and all the files they reference, so it can be
+/// carried to a repository without network access. Bundles don't depend on
+/// Git, and can be applied to a repository using any backend. Change ids are
+/// preserved, but commit ids may differ if the destination repository uses a
+/// different backend. Commit signatures aren't included.
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum BundleCommand {
+    Apply(BundleApplyArgs),
+    Create(BundleCreateArgs),
+}
+
+pub fn cmd_bundle(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &BundleCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        BundleCommand::Apply(args) => cmd_bundle_apply(ui, command, args),
+        BundleCommand::Create(args) => cmd_bundle_create(ui, command, args),
+    }
+}
+
+fn map_bundle_error(err: BundleError) -> CommandError {
+    match err {
+        BundleError::Backend(err) => err.into(),
+        BundleError::MissingBaseCommits(ids) => user_error_with_hint(
+            format!(
+                "The repository doesn't contain the base commits of the bundle: {}",
+                ids.iter().map(short_commit_hash).join(", ")
+            ),
+            "Apply a bundle containing the base commits first, or create the bundle with the \
+             base commits included.",
+        ),
+        _ => user_error(err),
+    }
+}
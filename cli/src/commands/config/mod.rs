@@ -18,6 +18,7 @@ mod list;
 mod path;
 mod set;
 mod unset;
+mod validate;
 
 use std::path::Path;
 
@@ -38,6 +39,8 @@ use self::set::cmd_config_set;
 use self::set::ConfigSetArgs;
 use self::unset::cmd_config_unset;
 use self::unset::ConfigUnsetArgs;
+use self::validate::cmd_config_validate;
+use self::validate::ConfigValidateArgs;
 use crate::cli_util::CommandHelper;
 use crate::command_error::user_error;
 use crate::command_error::CommandError;
@@ -146,6 +149,7 @@ pub(crate) enum ConfigCommand {
     Set(ConfigSetArgs),
     #[command(visible_alias("u"))]
     Unset(ConfigUnsetArgs),
+    Validate(ConfigValidateArgs),
 }
 
 #[instrument(skip_all)]
@@ -161,5 +165,6 @@ pub(crate) fn cmd_config(
         ConfigCommand::Path(args) => cmd_config_path(ui, command, args),
         ConfigCommand::Set(args) => cmd_config_set(ui, command, args),
         ConfigCommand::Unset(args) => cmd_config_unset(ui, command, args),
+        ConfigCommand::Validate(args) => cmd_config_validate(ui, command, args),
     }
 }
@@ -0,0 +1,562 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::Write as _;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use itertools::Itertools as _;
+use jj_lib::config::ConfigItem;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigNamePathBuf;
+use jj_lib::config::ConfigSource;
+use jj_lib::config::StackedConfig;
+use jj_lib::revset;
+use jj_lib::revset::RevsetAliasesMap;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::user_error;
+use crate::command_error::CommandError;
+use crate::config::default_config_layers;
+use crate::config::default_config_migrations;
+use crate::config::CONFIG_SCHEMA;
+use crate::template_parser;
+use crate::template_parser::TemplateAliasesMap;
+use crate::ui::Ui;
+
+/// Check config files for problems
+///
+/// Reports unknown keys, values of unexpected type, revsets and templates
+/// that can't be parsed, and deprecated keys. If no path is specified, all
+/// user, repo, and command-line config layers are checked.
+///
+/// Exits with an error status if any error is found. Unknown and deprecated
+/// keys are reported as warnings.
+#[derive(clap::Args, Clone, Debug)]
+pub struct ConfigValidateArgs {
+    /// Config file to check
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    path: Option<PathBuf>,
+}
+
+#[instrument(skip_all)]
+pub fn cmd_config_validate(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &ConfigValidateArgs,
+) -> Result<(), CommandError> {
+    let layers = if let Some(path) = &args.path {
+        let path = command.cwd().join(path);
+        vec![Arc::new(ConfigLayer::load_from_file(
+            ConfigSource::User,
+            path,
+        )?)]
+    } else {
+        command
+            .raw_config()
+            .as_ref()
+            .layers()
+            .iter()
+            .filter(|layer| layer.source != ConfigSource::Default)
+            .cloned()
+            .collect()
+    };
+
+    let schema: serde_json::Value = serde_json::from_str(CONFIG_SCHEMA).unwrap();
+    let mut defaults = StackedConfig::empty();
+    defaults.extend_layers(default_config_layers());
+    let mut validator = ConfigValidator {
+        schema: &schema,
+        defaults: &defaults,
+        problems: vec![],
+    };
+    for layer in &layers {
+        validator.check_layer(layer);
+    }
+
+    let mut formatter = ui.stdout_formatter();
+    for problem in &validator.problems {
+        let severity = match problem.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(formatter, "{}: ", problem.location)?;
+        write!(formatter.labeled(severity), "{severity}")?;
+        writeln!(formatter, ": {}", problem.message)?;
+    }
+    drop(formatter);
+
+    let num_errors = validator
+        .problems
+        .iter()
+        .filter(|problem| problem.severity == Severity::Error)
+        .count();
+    match num_errors {
+        0 if validator.problems.is_empty() => {
+            writeln!(ui.status(), "No problems found")?;
+            Ok(())
+        }
+        0 => Ok(()),
+        1 => Err(user_error("Found 1 error in config")),
+        n => Err(user_error(format!("Found {n} errors in config"))),
+    }
+}
+
+/// Top-level keys which aren't checked. `$schema` is used by editors, and
+/// `debug` settings are internal.
+const IGNORED_ROOT_KEYS: &[&str] = &["$schema", "debug"];
+
+/// Schema which accepts any value.
+static ANY_SCHEMA: serde_json::Value = serde_json::Value::Bool(true);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Debug)]
+struct Problem {
+    severity: Severity,
+    location: String,
+    message: String,
+}
+
+/// Source text of the layer being checked, used to resolve item locations.
+struct LayerSource<'a> {
+    label: String,
+    text: Option<&'a str>,
+}
+
+impl LayerSource<'_> {
+    /// Formats `path:line:column` of the byte `offset`, or just the label if
+    /// the source text isn't available.
+    fn location(&self, offset: Option<usize>) -> String {
+        let (Some(text), Some(offset)) = (self.text, offset) else {
+            return self.label.clone();
+        };
+        let before = &text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+        let column = before[line_start..].chars().count() + 1;
+        format!("{}:{line}:{column}", self.label)
+    }
+
+    /// Maps `offset` within the `content` of the quoted string at `span` back
+    /// to the source text. Falls back to the start of the string if the
+    /// content contains escape sequences.
+    fn content_offset(
+        &self,
+        span: Option<Range<usize>>,
+        content: &str,
+        offset: usize,
+    ) -> Option<usize> {
+        let span = span?;
+        let text = self.text?;
+        let raw = &text[span.clone()];
+        let delimiter_len = ["\"\"\"", "'''", "\"", "'"]
+            .iter()
+            .find(|delimiter| raw.starts_with(*delimiter))
+            .map_or(0, |delimiter| delimiter.len());
+        let mut start = span.start + delimiter_len;
+        // A newline immediately following the opening delimiter of a
+        // multi-line string is trimmed.
+        if delimiter_len == 3 {
+            if text[start..].starts_with("\r\n") {
+                start += 2;
+            } else if text[start..].starts_with('\n') {
+                start += 1;
+            }
+        }
+        if text[start..].starts_with(content) {
+            Some(start + offset)
+        } else {
+            Some(span.start)
+        }
+    }
+}
+
+struct ConfigValidator<'a> {
+    schema: &'a serde_json::Value,
+    defaults: &'a StackedConfig,
+    problems: Vec<Problem>,
+}
+
+impl<'a> ConfigValidator<'a> {
+    fn check_layer(&mut self, layer: &Arc<ConfigLayer>) {
+        // Re-read the file to obtain the spans of the items. The data of the
+        // loaded layer doesn't retain them.
+        let text = layer
+            .path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok());
+        let document = text
+            .as_deref()
+            .and_then(|text| toml_edit::ImDocument::parse(text).ok());
+        let source = LayerSource {
+            label: match &layer.path {
+                Some(path) => path.display().to_string(),
+                None => format!("<{} config>", layer.source),
+            },
+            text: document.as_ref().and(text.as_deref()),
+        };
+        let table = match &document {
+            Some(document) => document.as_table(),
+            None => layer.data.as_table(),
+        };
+
+        let mut migrated = StackedConfig::empty();
+        migrated.add_layer(layer.clone());
+        match jj_lib::config::migrate(&mut migrated, &default_config_migrations()) {
+            Ok(descriptions) => {
+                for desc in descriptions {
+                    let message = format!("Deprecated config: {desc}");
+                    self.report(Severity::Warning, &source, None, message);
+                }
+            }
+            Err(err) => {
+                self.report(Severity::Error, &source, None, err.to_string());
+            }
+        }
+        self.check_table(
+            &source,
+            &ConfigNamePathBuf::root(),
+            table,
+            Some(self.schema),
+            Some(migrated.layers()[0].as_ref()),
+        );
+    }
+
+    /// Checks items in the `table` at `name`. The `migrated` layer is used to
+    /// suppress unknown-key warnings for deprecated keys, which are reported
+    /// separately.
+    fn check_table(
+        &mut self,
+        source: &LayerSource,
+        name: &ConfigNamePathBuf,
+        table: &dyn toml_edit::TableLike,
+        schema: Option<&'a serde_json::Value>,
+        migrated: Option<&ConfigLayer>,
+    ) {
+        let is_root = name.components().len() == 0;
+        for (key, item) in table.iter() {
+            let key_span = table.key(key).and_then(|key| key.span());
+            let mut name = name.clone();
+            name.push(key);
+            if is_root && (key == "--when" || IGNORED_ROOT_KEYS.contains(&key)) {
+                continue;
+            }
+            if is_root && key == "--scope" {
+                self.check_scopes(source, item);
+                continue;
+            }
+
+            let child_schema = schema.and_then(|schema| self.child_schema(schema, key));
+            let known_by_defaults = self
+                .defaults
+                .layers()
+                .iter()
+                .any(|layer| matches!(layer.look_up_item(&name), Ok(Some(_))));
+            if child_schema.is_none() && !known_by_defaults {
+                let is_deprecated =
+                    migrated.is_some_and(|layer| matches!(layer.look_up_item(&name), Ok(None)));
+                if !is_deprecated {
+                    let location = key_span.map(|span| span.start);
+                    let message = format!("Unknown key `{name}`");
+                    self.report(Severity::Warning, source, location, message);
+                }
+                continue;
+            }
+
+            if let Some(child_table) = item.as_table_like() {
+                match child_schema {
+                    Some(child_schema) if self.is_table_schema(child_schema) => {
+                        self.check_table(source, &name, child_table, Some(child_schema), migrated);
+                        continue;
+                    }
+                    None => {
+                        self.check_table(source, &name, child_table, None, migrated);
+                        continue;
+                    }
+                    Some(_) => {}
+                }
+            }
+            if let Some(child_schema) = child_schema {
+                let value = item_to_json(item);
+                if !self.matches_schema(child_schema, &value) {
+                    let found = match &value {
+                        serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                            item.type_name().to_owned()
+                        }
+                        _ => value.to_string(),
+                    };
+                    let message = format!(
+                        "Invalid value for `{name}`: expected {}, but found {found}",
+                        self.describe_schema(child_schema)
+                    );
+                    let location = item.span().map(|span| span.start);
+                    self.report(Severity::Error, source, location, message);
+                    continue;
+                }
+            }
+            self.check_expression(source, &name, key_span, item);
+        }
+    }
+
+    fn check_scopes(&mut self, source: &LayerSource, item: &ConfigItem) {
+        let root = ConfigNamePathBuf::root();
+        let tables: Vec<&dyn toml_edit::TableLike> = match item {
+            toml_edit::Item::ArrayOfTables(array) => array
+                .iter()
+                .map(|table| table as &dyn toml_edit::TableLike)
+                .collect(),
+            toml_edit::Item::Value(toml_edit::Value::Array(array)) => array
+                .iter()
+                .filter_map(|value| value.as_inline_table())
+                .map(|table| table as &dyn toml_edit::TableLike)
+                .collect(),
+            _ => {
+                let message = "Invalid value for `--scope`: expected array of tables".to_owned();
+                let location = item.span().map(|span| span.start);
+                self.report(Severity::Error, source, location, message);
+                return;
+            }
+        };
+        for table in tables {
+            self.check_table(source, &root, table, Some(self.schema), None);
+        }
+    }
+
+    /// Parses revsets and templates embedded in string values.
+    fn check_expression(
+        &mut self,
+        source: &LayerSource,
+        name: &ConfigNamePathBuf,
+        key_span: Option<Range<usize>>,
+        item: &ConfigItem,
+    ) {
+        let Some(text) = item.as_str() else {
+            return;
+        };
+        let Some((table_key, key)) = name.components().map(|key| key.get()).collect_tuple() else {
+            return;
+        };
+        // (kind, alias declaration error, expression error)
+        let (kind, decl_err, expr_err) = match table_key {
+            "revset-aliases" => {
+                let decl_err = RevsetAliasesMap::new()
+                    .insert(key, text)
+                    .err()
+                    .map(|err| (err.message(), err.span()));
+                let expr_err = revset::parse_program(text)
+                    .err()
+                    .map(|err| (err.message(), err.span()));
+                ("revset", decl_err, expr_err)
+            }
+            "revsets" => {
+                let expr_err = revset::parse_program(text)
+                    .err()
+                    .map(|err| (err.message(), err.span()));
+                ("revset", None, expr_err)
+            }
+            "template-aliases" => {
+                let decl_err = TemplateAliasesMap::new()
+                    .insert(key, text)
+                    .err()
+                    .map(|err| (err.message(), err.span()));
+                let expr_err = template_parser::parse_template(text)
+                    .err()
+                    .map(|err| (err.message(), err.span()));
+                ("template", decl_err, expr_err)
+            }
+            "templates" => {
+                let expr_err = template_parser::parse_template(text)
+                    .err()
+                    .map(|err| (err.message(), err.span()));
+                ("template", None, expr_err)
+            }
+            _ => return,
+        };
+        if let Some((message, span)) = decl_err {
+            let location = source.content_offset(key_span, key, span.start);
+            let message = format!("Invalid {kind} alias declaration `{name}`: {message}");
+            self.report(Severity::Error, source, location, message);
+        }
+        if let Some((message, span)) = expr_err {
+            let location = source.content_offset(item.span(), text, span.start);
+            let message = format!("Invalid {kind} in `{name}`: {message}");
+            self.report(Severity::Error, source, location, message);
+        }
+    }
+
+    fn report(
+        &mut self,
+        severity: Severity,
+        source: &LayerSource,
+        offset: Option<usize>,
+        message: String,
+    ) {
+        self.problems.push(Problem {
+            severity,
+            location: source.location(offset),
+            message,
+        });
+    }
+
+    /// Follows `$ref` to the definition of the `schema` node.
+    fn resolve_schema(&self, schema: &'a serde_json::Value) -> &'a serde_json::Value {
+        let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) else {
+            return schema;
+        };
+        let pointer = reference.strip_prefix('#').unwrap_or(reference);
+        self.schema
+            .pointer(pointer)
+            .map_or(&ANY_SCHEMA, |schema| self.resolve_schema(schema))
+    }
+
+    /// Returns the schema of the `key` in the table described by `schema`, or
+    /// `None` if the key isn't allowed.
+    fn child_schema(
+        &self,
+        schema: &'a serde_json::Value,
+        key: &str,
+    ) -> Option<&'a serde_json::Value> {
+        let schema = self.resolve_schema(schema);
+        if let Some(child) = schema.get("properties").and_then(|props| props.get(key)) {
+            return Some(child);
+        }
+        match schema.get("additionalProperties") {
+            Some(serde_json::Value::Bool(false)) => None,
+            Some(serde_json::Value::Bool(true)) => Some(&ANY_SCHEMA),
+            Some(child) => Some(child),
+            None if schema.get("properties").is_some() => None,
+            None => Some(&ANY_SCHEMA),
+        }
+    }
+
+    /// Whether the `schema` describes a table of known keys.
+    fn is_table_schema(&self, schema: &'a serde_json::Value) -> bool {
+        let schema = self.resolve_schema(schema);
+        schema.get("properties").is_some() || schema.get("additionalProperties").is_some()
+    }
+
+    fn matches_schema(&self, schema: &'a serde_json::Value, value: &serde_json::Value) -> bool {
+        let schema = self.resolve_schema(schema);
+        let Some(object) = schema.as_object() else {
+            return schema.as_bool() != Some(false);
+        };
+        if let Some(types) = object.get("type") {
+            let matches_type = |ty: &serde_json::Value| match ty.as_str() {
+                Some("string") => value.is_string(),
+                Some("boolean") => value.is_boolean(),
+                Some("integer") => value.is_i64() || value.is_u64(),
+                Some("number") => value.is_number(),
+                Some("array") => value.is_array(),
+                Some("object") => value.is_object(),
+                _ => false,
+            };
+            let matched = match types {
+                serde_json::Value::Array(types) => types.iter().any(matches_type),
+                ty => matches_type(ty),
+            };
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(variants) = object.get("enum").and_then(|v| v.as_array()) {
+            if !variants.contains(value) {
+                return false;
+            }
+        }
+        if let Some(branches) = object.get("oneOf").and_then(|v| v.as_array()) {
+            if !branches
+                .iter()
+                .any(|branch| self.matches_schema(branch, value))
+            {
+                return false;
+            }
+        }
+        match value {
+            serde_json::Value::Array(items) => match object.get("items") {
+                Some(item_schema) => items
+                    .iter()
+                    .all(|item| self.matches_schema(item_schema, item)),
+                None => true,
+            },
+            serde_json::Value::Object(entries) => entries.iter().all(|(key, value)| {
+                self.child_schema(schema, key)
+                    .is_some_and(|child| self.matches_schema(child, value))
+            }),
+            _ => true,
+        }
+    }
+
+    /// Describes values accepted by the `schema`.
+    fn describe_schema(&self, schema: &'a serde_json::Value) -> String {
+        let schema = self.resolve_schema(schema);
+        if let Some(variants) = schema.get("enum").and_then(|v| v.as_array()) {
+            format!("one of {}", variants.iter().join(", "))
+        } else if let Some(types) = schema.get("type") {
+            match types {
+                serde_json::Value::Array(types) => {
+                    types.iter().filter_map(|ty| ty.as_str()).join(" or ")
+                }
+                ty => ty.as_str().unwrap_or("value").to_owned(),
+            }
+        } else if let Some(branches) = schema.get("oneOf").and_then(|v| v.as_array()) {
+            branches
+                .iter()
+                .map(|branch| self.describe_schema(branch))
+                .unique()
+                .join(" or ")
+        } else {
+            "valid value".to_owned()
+        }
+    }
+}
+
+/// Converts TOML `item` to JSON value to be checked against the schema.
+fn item_to_json(item: &ConfigItem) -> serde_json::Value {
+    match item {
+        toml_edit::Item::None => serde_json::Value::Null,
+        toml_edit::Item::Value(value) => value_to_json(value),
+        toml_edit::Item::Table(table) => table_to_json(table),
+        toml_edit::Item::ArrayOfTables(array) => {
+            array.iter().map(|table| table_to_json(table)).collect()
+        }
+    }
+}
+
+fn table_to_json(table: &dyn toml_edit::TableLike) -> serde_json::Value {
+    table
+        .iter()
+        .map(|(key, item)| (key.to_owned(), item_to_json(item)))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+fn value_to_json(value: &toml_edit::Value) -> serde_json::Value {
+    match value {
+        toml_edit::Value::String(v) => v.value().as_str().into(),
+        toml_edit::Value::Integer(v) => (*v.value()).into(),
+        toml_edit::Value::Float(v) => (*v.value()).into(),
+        toml_edit::Value::Boolean(v) => (*v.value()).into(),
+        toml_edit::Value::Datetime(v) => v.value().to_string().into(),
+        toml_edit::Value::Array(array) => array.iter().map(value_to_json).collect(),
+        toml_edit::Value::InlineTable(table) => table_to_json(table),
+    }
+}
@@ -20,6 +20,8 @@ use std::io::Write as _;
 
 use clap::Subcommand;
 #[cfg(feature = "watchman")]
+use jj_lib::fsmonitor::FsmonitorErrorKind;
+#[cfg(feature = "watchman")]
 use jj_lib::fsmonitor::FsmonitorSettings;
 #[cfg(feature = "watchman")]
 use jj_lib::fsmonitor::WatchmanConfig;
@@ -28,6 +30,8 @@ use jj_lib::local_working_copy::LocalWorkingCopy;
 
 use crate::cli_util::CommandHelper;
 use crate::command_error::user_error;
+#[cfg(feature = "watchman")]
+use crate::command_error::user_error_with_hint;
 use crate::command_error::CommandError;
 use crate::ui::Ui;
 
@@ -37,6 +41,9 @@ pub enum DebugWatchmanCommand {
     Status,
     QueryClock,
     QueryChangedFiles,
+    /// Discard the stored `watchman` clock so the next snapshot rescans the
+    /// working copy
+    #[command(visible_alias = "reset")]
     ResetClock,
 }
 
@@ -89,7 +96,23 @@ pub fn cmd_debug_watchman(
                 }
             };
             let wc = check_local_disk_wc(workspace_command.working_copy().as_any())?;
-            let _ = wc.query_watchman(&config)?;
+            if let Some(err) = wc.check_watchman(&config)? {
+                let hint = match err.kind {
+                    FsmonitorErrorKind::Connect => {
+                        "Check that the `watchman` server is installed and running."
+                    }
+                    FsmonitorErrorKind::Root => {
+                        "Check that `watchman` is allowed to watch the workspace root."
+                    }
+                    FsmonitorErrorKind::Query | FsmonitorErrorKind::Trigger => {
+                        "Run `jj debug watchman reset` to discard the stored clock."
+                    }
+                };
+                return Err(user_error_with_hint(
+                    format!("Watchman is unavailable: {}", err.message),
+                    hint,
+                ));
+            }
             writeln!(
                 ui.stdout(),
                 "The watchman server seems to be installed and working correctly."
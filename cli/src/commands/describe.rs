@@ -13,27 +13,35 @@
 // limitations under the License.
 
 use std::collections::HashMap;
+use std::fs;
 use std::io;
 use std::io::Read as _;
 use std::iter;
+use std::path::PathBuf;
 
 use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
 use jj_lib::backend::Signature;
 use jj_lib::commit::CommitIteratorExt as _;
 use jj_lib::object_id::ObjectId as _;
+use jj_lib::trailer::Trailer;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
 use crate::cli_util::RevisionArg;
 use crate::command_error::user_error;
+use crate::command_error::user_error_with_message;
 use crate::command_error::CommandError;
 use crate::complete;
+use crate::description_util::add_trailers;
 use crate::description_util::description_template;
-use crate::description_util::edit_description;
+use crate::description_util::edit_description_resumable;
 use crate::description_util::edit_multiple_descriptions;
 use crate::description_util::join_message_paragraphs;
-use crate::description_util::ParsedBulkEditMessage;
+use crate::description_util::parse_description;
+use crate::description_util::parse_trailer;
+use crate::description_util::saved_description_path;
+use crate::text_util;
 use crate::text_util::parse_author;
 use crate::ui::Ui;
 
@@ -72,8 +80,20 @@ pub(crate) struct DescribeArgs {
     ///
     /// If multiple revisions are specified, the same description will be used
     /// for all of them.
-    #[arg(long)]
+    #[arg(long, conflicts_with = "edit")]
     stdin: bool,
+    /// Read the change description from a file
+    ///
+    /// Lines starting with "JJ:" are removed as if the description were
+    /// edited in an editor. If multiple revisions are specified, the same
+    /// description will be used for all of them.
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_hint = clap::ValueHint::FilePath,
+        conflicts_with_all = ["message_paragraphs", "stdin", "edit"]
+    )]
+    message_file: Option<PathBuf>,
     /// Don't open an editor
     ///
     /// This is mainly useful in combination with e.g. `--reset-author`.
@@ -81,8 +101,8 @@ pub(crate) struct DescribeArgs {
     no_edit: bool,
     /// Open an editor
     ///
-    /// Forces an editor to open when using `--stdin` or `--message` to
-    /// allow the message to be edited afterwards.
+    /// Forces an editor to open when using `--message` to allow the
+    /// message to be edited afterwards.
     #[arg(long)]
     edit: bool,
     /// Reset the author to the configured user
@@ -105,6 +125,37 @@ pub(crate) struct DescribeArgs {
         value_parser = parse_author
     )]
     author: Option<(String, String)>,
+    /// Keep the committer of the described revisions unchanged
+    ///
+    /// By default, the committer name, email, and timestamp are updated when
+    /// a revision is rewritten. Descendants are rebased as usual.
+    #[arg(long, conflicts_with = "reset_author")]
+    keep_committer: bool,
+    /// Add a trailer to the description (can be repeated)
+    ///
+    /// The trailer is appended to the trailer block at the end of the
+    /// description, or to a new paragraph if there's no such block. For
+    /// example, `--trailer "Signed-off-by=Alice <alice@example.com>"`.
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_trailer)]
+    trailer: Vec<Trailer>,
+    /// Reopen the editor if the edited descriptions are invalid
+    ///
+    /// When describing multiple revisions, the editor is reopened with the
+    /// edited text and a note about the error instead of discarding the
+    /// text. Exit the editor without making changes to abort.
+    #[arg(long, conflicts_with = "no_edit")]
+    retry: bool,
+    /// Resume editing the description saved by an interrupted edit
+    ///
+    /// If the editor exits with an error after the description was changed,
+    /// the edited text is kept in the repository. This opens the editor with
+    /// that text instead of the current description. Only a single revision
+    /// can be described.
+    #[arg(
+        long,
+        conflicts_with_all = ["message_paragraphs", "stdin", "message_file", "no_edit"]
+    )]
+    resume: bool,
 }
 
 #[instrument(skip_all)]
@@ -128,6 +179,10 @@ pub(crate) fn cmd_describe(
     }
     workspace_command.check_rewritable(commits.iter().ids())?;
     let text_editor = workspace_command.text_editor()?;
+    let saved_paths = commits
+        .iter()
+        .map(|commit| saved_description_path(workspace_command.repo_path(), commit.change_id()))
+        .collect_vec();
 
     let mut tx = workspace_command.start_transaction();
     let tx_description = if commits.len() == 1 {
@@ -141,10 +196,17 @@ pub(crate) fn cmd_describe(
     };
 
     let default_description_to_edit = tx.settings().get_string("ui.default-description")?;
+    let describe_separator = tx.settings().get_string("ui.describe-separator")?;
     let shared_description = if args.stdin {
         let mut buffer = String::new();
         io::stdin().read_to_string(&mut buffer)?;
-        Some(buffer)
+        Some(text_util::complete_newline(buffer))
+    } else if let Some(path) = &args.message_file {
+        let path = command.cwd().join(path);
+        let text = fs::read_to_string(&path).map_err(|err| {
+            user_error_with_message(format!("Failed to read {}", path.display()), err)
+        })?;
+        Some(parse_description(&text))
     } else if !args.message_paragraphs.is_empty() {
         Some(join_message_paragraphs(&args.message_paragraphs))
     } else {
@@ -154,7 +216,12 @@ pub(crate) fn cmd_describe(
     // edit and no_edit are conflicting arguments and therefore it should not
     // be possible for both to be true at the same time.
     assert!(!(args.edit && args.no_edit));
-    let use_editor = args.edit || (shared_description.is_none() && !args.no_edit);
+    let use_editor = args.edit || args.resume || (shared_description.is_none() && !args.no_edit);
+    if args.resume && commits.len() > 1 {
+        return Err(user_error(
+            "--resume can only be used when describing a single revision",
+        ));
+    }
 
     let mut commit_builders = commits
         .iter()
@@ -165,6 +232,10 @@ pub(crate) fn cmd_describe(
             } else if use_editor && commit_builder.description().is_empty() {
                 commit_builder.set_description(&default_description_to_edit);
             }
+            if !args.trailer.is_empty() {
+                let description = add_trailers(commit_builder.description(), &args.trailer);
+                commit_builder.set_description(description);
+            }
             if args.reset_author {
                 let new_author = commit_builder.committer().clone();
                 commit_builder.set_author(new_author);
@@ -177,6 +248,9 @@ pub(crate) fn cmd_describe(
                 };
                 commit_builder.set_author(new_author);
             }
+            if args.keep_committer {
+                commit_builder.set_committer(commit.committer().clone());
+            }
             commit_builder
         })
         .collect_vec();
@@ -194,36 +268,27 @@ pub(crate) fn cmd_describe(
 
         if let [(_, temp_commit)] = &*temp_commits {
             let template = description_template(ui, &tx, "", temp_commit)?;
-            let description = edit_description(&text_editor, &template)?;
+            let saved_path = &saved_paths[0];
+            if !args.resume && saved_path.exists() {
+                writeln!(
+                    ui.hint_default(),
+                    "Found a description saved by an interrupted edit. Use `jj describe \
+                     --resume` to continue editing it. It will be discarded if this edit \
+                     succeeds."
+                )?;
+            }
+            let description =
+                edit_description_resumable(&text_editor, &template, saved_path, args.resume)?;
             commit_builders[0].set_description(description);
         } else {
-            let ParsedBulkEditMessage {
-                descriptions,
-                missing,
-                duplicates,
-                unexpected,
-            } = edit_multiple_descriptions(ui, &text_editor, &tx, &temp_commits)?;
-            if !missing.is_empty() {
-                return Err(user_error(format!(
-                    "The description for the following commits were not found in the edited \
-                     message: {}",
-                    missing.join(", ")
-                )));
-            }
-            if !duplicates.is_empty() {
-                return Err(user_error(format!(
-                    "The following commits were found in the edited message multiple times: {}",
-                    duplicates.join(", ")
-                )));
-            }
-            if !unexpected.is_empty() {
-                return Err(user_error(format!(
-                    "The following commits were not being edited, but were found in the edited \
-                     message: {}",
-                    unexpected.join(", ")
-                )));
-            }
-
+            let descriptions = edit_multiple_descriptions(
+                ui,
+                &text_editor,
+                &tx,
+                &temp_commits,
+                &describe_separator,
+                args.retry,
+            )?;
             for (commit, commit_builder) in iter::zip(&commits, &mut commit_builders) {
                 let description = descriptions.get(commit.id()).unwrap();
                 commit_builder.set_description(description);
@@ -279,5 +344,9 @@ pub(crate) fn cmd_describe(
         writeln!(ui.status(), "Rebased {num_reparented} descendant commits")?;
     }
     tx.finish(ui, tx_description)?;
+    // Descriptions saved by interrupted edits are outdated now.
+    for path in &saved_paths {
+        fs::remove_file(path).ok();
+    }
     Ok(())
 }
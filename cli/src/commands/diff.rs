@@ -103,12 +103,12 @@ pub(crate) fn cmd_diff(
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
     let repo = workspace_command.repo();
-    let fileset_expression = workspace_command.parse_file_patterns(ui, &args.paths)?;
-    let matcher = fileset_expression.to_matcher();
+    let fileset_expression = workspace_command.parse_file_patterns_unresolved(ui, &args.paths)?;
 
     let from_tree;
     let to_tree;
-    let mut copy_records = CopyRecords::default();
+    // Copy records are looked up once the matcher is built for the target tree.
+    let mut copy_record_pairs = vec![];
     if args.from.is_some() || args.to.is_some() {
         let resolve_revision = |r: &Option<RevisionArg>| {
             workspace_command.resolve_single_rev(ui, r.as_ref().unwrap_or(&RevisionArg::AT))
@@ -117,9 +117,7 @@ pub(crate) fn cmd_diff(
         let to = resolve_revision(&args.to)?;
         from_tree = from.tree()?;
         to_tree = to.tree()?;
-
-        let records = get_copy_records(repo.store(), from.id(), to.id(), &matcher)?;
-        copy_records.add_records(records)?;
+        copy_record_pairs.push((from.id().clone(), to.id().clone()));
     } else {
         let revision_args = args
             .revisions
@@ -156,12 +154,19 @@ pub(crate) fn cmd_diff(
 
         for p in &parents {
             for to in &heads {
-                let records = get_copy_records(repo.store(), p.id(), to.id(), &matcher)?;
-                copy_records.add_records(records)?;
+                copy_record_pairs.push((p.id().clone(), to.id().clone()));
             }
         }
     }
 
+    let fileset_expression = fileset_expression.resolve_in_tree(&to_tree)?;
+    let matcher = fileset_expression.to_matcher();
+    let mut copy_records = CopyRecords::default();
+    for (from_id, to_id) in &copy_record_pairs {
+        let records = get_copy_records(repo.store(), from_id, to_id, &matcher)?;
+        copy_records.add_records(records)?;
+    }
+
     let diff_renderer = workspace_command.diff_renderer_for(&args.format)?;
     ui.request_pager();
     diff_renderer.show_diff(
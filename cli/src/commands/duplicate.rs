@@ -17,6 +17,7 @@ use std::io::Write as _;
 use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
 use jj_lib::backend::CommitId;
+use jj_lib::object_id::ObjectId as _;
 use jj_lib::repo::Repo as _;
 use jj_lib::rewrite::duplicate_commits;
 use jj_lib::rewrite::duplicate_commits_onto_parents;
@@ -32,6 +33,9 @@ use crate::command_error::CommandError;
 use crate::complete;
 use crate::ui::Ui;
 
+/// Operation tag listing the `<source>:<duplicate>` commit id pairs.
+const DUPLICATED_OP_TAG: &str = "duplicated";
+
 /// Create new changes with the same content as existing ones
 ///
 /// When none of the `--destination`, `--insert-after`, or `--insert-before`
@@ -192,6 +196,13 @@ pub(crate) fn cmd_duplicate(
             )?;
         }
     }
+    // Record the provenance in the operation too, so it stays discoverable
+    // from the op log.
+    let duplicated_pairs = duplicated_commits
+        .iter()
+        .map(|(old_id, new_commit)| format!("{}:{}", old_id.hex(), new_commit.id().hex()))
+        .join(" ");
+    tx.set_tag(DUPLICATED_OP_TAG.to_owned(), duplicated_pairs);
     tx.finish(ui, format!("duplicate {num_to_duplicate} commit(s)"))?;
     Ok(())
 }
@@ -64,7 +64,7 @@ pub(crate) fn cmd_file_list(
     let commit = workspace_command.resolve_single_rev(ui, &args.revision)?;
     let tree = commit.tree()?;
     let matcher = workspace_command
-        .parse_file_patterns(ui, &args.paths)?
+        .parse_file_patterns_in_tree(ui, &args.paths, &tree)?
         .to_matcher();
     let template = {
         let language = workspace_command.commit_template_language();
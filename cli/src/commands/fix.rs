@@ -321,6 +321,11 @@ fn get_tools_config(ui: &mut Ui, settings: &UserSettings) -> Result<ToolsConfig,
                     .try_collect()?,
             );
             print_parse_diagnostics(ui, &format!("In `fix.tools.{name}`"), &diagnostics)?;
+            if let Some(predicate) = expression.unresolved_predicate_name() {
+                return Err(config_error(format!(
+                    "{predicate} can't be used in `fix.tools.{name}.patterns`"
+                )));
+            }
             Ok(ToolConfig {
                 command: tool.command,
                 matcher: expression.to_matcher(),
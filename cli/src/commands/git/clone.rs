@@ -31,7 +31,9 @@ use super::write_repository_level_trunk_alias;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::WorkspaceCommandHelper;
 use crate::command_error::cli_error;
+use crate::command_error::internal_error;
 use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
 use crate::command_error::user_error_with_message;
 use crate::command_error::CommandError;
 use crate::commands::git::maybe_add_gitignore;
@@ -45,6 +47,12 @@ use crate::ui::Ui;
 /// Create a new repo backed by a clone of a Git repo
 ///
 /// The Git repo will be a bare git repo stored inside the `.jj/` directory.
+///
+/// The default branch is fetched before the other branches. If the connection
+/// drops while fetching, the partially cloned repo is kept in the destination
+/// directory. Running the same command again resumes the clone, and branches
+/// which were completely fetched aren't fetched again. Other failures remove
+/// the destination as usual.
 #[derive(clap::Args, Clone, Debug)]
 pub struct GitCloneArgs {
     /// URL or path of the Git repo to clone
@@ -67,6 +75,80 @@ pub struct GitCloneArgs {
     /// Create a shallow clone of the given depth
     #[arg(long)]
     depth: Option<NonZeroU32>,
+    /// Discard an interrupted clone in the destination instead of resuming it
+    ///
+    /// The destination is also cleaned up if this clone fails.
+    #[arg(long)]
+    no_resume: bool,
+}
+
+/// Name of the file under `.jj/` recording an unfinished clone.
+const CLONE_STATE_FILE_NAME: &str = "git-clone-state.toml";
+
+/// Parameters of a clone, recorded until the initial fetch completes so that
+/// an interrupted clone can be resumed.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct CloneState {
+    source: String,
+    remote_name: String,
+    colocate: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    depth: Option<NonZeroU32>,
+}
+
+impl CloneState {
+    fn load(path: &Path) -> Result<Option<Self>, CommandError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+                ) =>
+            {
+                return Ok(None);
+            }
+            Err(err) => {
+                return Err(user_error_with_message(
+                    format!("Failed to read {}", path.display()),
+                    err,
+                ));
+            }
+        };
+        let state = toml_edit::de::from_str(&text).map_err(|err| {
+            user_error_with_message(format!("Failed to parse {}", path.display()), err)
+        })?;
+        Ok(Some(state))
+    }
+
+    fn save(&self, path: &Path) -> Result<(), CommandError> {
+        let text = toml_edit::ser::to_string(self).map_err(internal_error)?;
+        fs::write(path, text).map_err(|err| {
+            user_error_with_message(format!("Failed to write {}", path.display()), err)
+        })
+    }
+
+    /// Returns true if resuming this clone would do what `args` asks for.
+    fn matches_args(&self, source: &str, args: &GitCloneArgs) -> bool {
+        self.source == source
+            && self.remote_name == args.remote_name.as_str()
+            && self.colocate == args.colocate
+            && self.depth == args.depth
+    }
+
+    /// Formats the arguments to resume this clone with.
+    fn to_args(&self) -> String {
+        let mut args = format!("--remote {}", self.remote_name);
+        if self.colocate {
+            args.push_str(" --colocate");
+        }
+        if let Some(depth) = self.depth {
+            args.push_str(&format!(" --depth {depth}"));
+        }
+        args.push(' ');
+        args.push_str(&self.source);
+        args
+    }
 }
 
 fn clone_destination_for_source(source: &str) -> Option<&str> {
@@ -90,7 +172,6 @@ pub fn cmd_git_clone(
     command: &CommandHelper,
     args: &GitCloneArgs,
 ) -> Result<(), CommandError> {
-    let remote_name = &args.remote_name;
     if command.global_args().at_operation.is_some() {
         return Err(cli_error("--at-op is not respected"));
     }
@@ -102,8 +183,33 @@ pub fn cmd_git_clone(
         .ok_or_else(|| user_error("No destination specified and wasn't able to guess it"))?;
     let wc_path = command.cwd().join(wc_path_str);
 
+    let resume_state = match CloneState::load(&wc_path.join(".jj").join(CLONE_STATE_FILE_NAME))? {
+        Some(state) if args.no_resume => {
+            remove_partial_clone(&wc_path, state.colocate).map_err(|err| {
+                user_error_with_message("Failed to remove the interrupted clone", err)
+            })?;
+            None
+        }
+        Some(state) if !state.matches_args(&source, args) => {
+            return Err(user_error_with_hint(
+                format!(
+                    "Destination path contains an interrupted clone of {} with different options",
+                    state.source
+                ),
+                format!(
+                    "Resume it with `jj git clone {}`, or use --no-resume to discard it and start \
+                     over",
+                    state.to_args()
+                ),
+            ));
+        }
+        state => state,
+    };
+    let remote_name = &*args.remote_name;
+    let colocate = args.colocate;
+
     let wc_path_existed = wc_path.exists();
-    if wc_path_existed && !is_empty_dir(&wc_path) {
+    if resume_state.is_none() && wc_path_existed && !is_empty_dir(&wc_path) {
         return Err(user_error(
             "Destination path exists and is not an empty directory",
         ));
@@ -118,38 +224,66 @@ pub fn cmd_git_clone(
     let canonical_wc_path = dunce::canonicalize(&wc_path)
         .map_err(|err| user_error_with_message(format!("Failed to create {wc_path_str}"), err))?;
 
+    let clone_state_path = canonical_wc_path.join(".jj").join(CLONE_STATE_FILE_NAME);
     let clone_result = (|| -> Result<_, CommandError> {
-        let workspace_command = init_workspace(ui, command, &canonical_wc_path, args.colocate)?;
+        let mut workspace_command = if resume_state.is_some() {
+            writeln!(
+                ui.status(),
+                r#"Resuming interrupted clone in "{}""#,
+                canonical_wc_path.display()
+            )?;
+            load_partial_clone(ui, command, &canonical_wc_path)?
+        } else {
+            let workspace_command = init_workspace(ui, command, &canonical_wc_path, colocate)?;
+            configure_remote(ui, command, workspace_command, remote_name, &source)?
+        };
         #[cfg(feature = "git2")]
         print_git2_deprecation_warning(ui, workspace_command.settings())?;
-        let mut workspace_command =
-            configure_remote(ui, command, workspace_command, remote_name, &source)?;
-        let default_branch = fetch_new_remote(ui, &mut workspace_command, remote_name, args.depth)?;
+        let clone_state = CloneState {
+            source: source.clone(),
+            remote_name: remote_name.as_str().to_owned(),
+            colocate,
+            depth: args.depth,
+        };
+        let default_branch =
+            fetch_new_remote(ui, &mut workspace_command, &clone_state, &clone_state_path)?;
         Ok((workspace_command, default_branch))
     })();
-    if clone_result.is_err() {
-        let clean_up_dirs = || -> io::Result<()> {
-            fs::remove_dir_all(canonical_wc_path.join(".jj"))?;
-            if args.colocate {
-                fs::remove_dir_all(canonical_wc_path.join(".git"))?;
-            }
-            if !wc_path_existed {
-                fs::remove_dir(&canonical_wc_path)?;
-            }
-            Ok(())
-        };
-        if let Err(err) = clean_up_dirs() {
+    let (mut workspace_command, default_branch) = match clone_result {
+        Ok(result) => result,
+        // The state file is only left behind if the transfer was interrupted.
+        // Keep the partially fetched repo so the clone can be resumed.
+        Err(err) if !args.no_resume && clone_state_path.exists() => {
             writeln!(
-                ui.warning_default(),
-                "Failed to clean up {}: {}",
-                canonical_wc_path.display(),
-                err
-            )
-            .ok();
+                ui.status(),
+                r#"Kept the interrupted clone in "{}""#,
+                canonical_wc_path.display()
+            )?;
+            return Err(err.hinted(
+                "Run the same command again to resume the clone, or add --no-resume to start \
+                 over",
+            ));
         }
-    }
-
-    let (mut workspace_command, default_branch) = clone_result?;
+        Err(err) => {
+            let clean_up_dirs = || -> io::Result<()> {
+                remove_partial_clone(&canonical_wc_path, colocate)?;
+                if !wc_path_existed {
+                    fs::remove_dir(&canonical_wc_path)?;
+                }
+                Ok(())
+            };
+            if let Err(err) = clean_up_dirs() {
+                writeln!(
+                    ui.warning_default(),
+                    "Failed to clean up {}: {}",
+                    canonical_wc_path.display(),
+                    err
+                )
+                .ok();
+            }
+            return Err(err);
+        }
+    };
     if let Some(name) = &default_branch {
         let default_symbol = name.to_remote_symbol(remote_name);
         write_repository_level_trunk_alias(ui, workspace_command.repo_path(), default_symbol)?;
@@ -171,6 +305,26 @@ pub fn cmd_git_clone(
     Ok(())
 }
 
+/// Removes the repo created by a failed or interrupted clone.
+fn remove_partial_clone(wc_path: &Path, colocate: bool) -> io::Result<()> {
+    fs::remove_dir_all(wc_path.join(".jj"))?;
+    if colocate {
+        fs::remove_dir_all(wc_path.join(".git"))?;
+    }
+    Ok(())
+}
+
+fn load_partial_clone(
+    ui: &Ui,
+    command: &CommandHelper,
+    wc_path: &Path,
+) -> Result<WorkspaceCommandHelper, CommandError> {
+    let settings = command.settings_for_new_workspace(wc_path)?;
+    let workspace = command.load_workspace_at(wc_path, &settings)?;
+    let repo = workspace.repo_loader().load_at_head()?;
+    command.for_workable_repo(ui, workspace, repo)
+}
+
 fn init_workspace(
     ui: &Ui,
     command: &CommandHelper,
@@ -212,9 +366,10 @@ fn configure_remote(
 fn fetch_new_remote(
     ui: &Ui,
     workspace_command: &mut WorkspaceCommandHelper,
-    remote_name: &RemoteName,
-    depth: Option<NonZeroU32>,
+    clone_state: &CloneState,
+    clone_state_path: &Path,
 ) -> Result<Option<RefNameBuf>, CommandError> {
+    let remote_name = RemoteName::new(&clone_state.remote_name);
     writeln!(
         ui.status(),
         r#"Fetching into new repo in "{}""#,
@@ -223,13 +378,41 @@ fn fetch_new_remote(
     let git_settings = workspace_command.settings().git_settings()?;
     let mut fetch_tx = workspace_command.start_transaction();
     let mut git_fetch = GitFetch::new(fetch_tx.repo_mut(), &git_settings)?;
-    with_remote_git_callbacks(ui, |cb| {
-        git_fetch.fetch(remote_name, &[StringPattern::everything()], cb, depth)
-    })?;
+    // Look up the default branch first to check that the remote is reachable
+    // before fetching potentially large amount of objects.
     let default_branch =
         with_remote_git_callbacks(ui, |cb| git_fetch.get_default_branch(remote_name, cb))?;
+    // Until the fetch completes, the clone can be resumed. This also covers jj
+    // itself being killed mid-transfer.
+    clone_state.save(clone_state_path)?;
+    // Git discards the objects of a fetch that didn't complete, so fetch in
+    // batches. Remote-tracking branches are updated after each batch, and the
+    // next attempt only asks the remote for objects which aren't reachable
+    // from them.
+    let mut batches = vec![];
+    if let Some(name) = &default_branch {
+        batches.push(StringPattern::exact(name.as_str()));
+    }
+    batches.push(StringPattern::everything());
+    for pattern in batches {
+        let fetch_result = with_remote_git_callbacks(ui, |cb| {
+            git_fetch.fetch(remote_name, &[pattern], cb, clone_state.depth)
+        });
+        if let Err(err) = fetch_result {
+            if !err.is_transfer_interrupted() {
+                remove_clone_state(clone_state_path)?;
+            }
+            return Err(err.into());
+        }
+    }
+    remove_clone_state(clone_state_path)?;
     let import_stats = git_fetch.import_refs()?;
     print_git_import_stats(ui, fetch_tx.repo(), &import_stats, true)?;
     fetch_tx.finish(ui, "fetch from git remote into empty repo")?;
     Ok(default_branch)
 }
+
+fn remove_clone_state(path: &Path) -> Result<(), CommandError> {
+    fs::remove_file(path)
+        .map_err(|err| user_error_with_message(format!("Failed to remove {}", path.display()), err))
+}
@@ -12,26 +12,69 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io::Write as _;
+
+use clap_complete::ArgValueCandidates;
 use jj_lib::git;
+use jj_lib::git::GitRefKind;
+use jj_lib::str_util::StringPattern;
 
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
+use crate::complete;
 use crate::git_util::print_git_export_stats;
 use crate::ui::Ui;
 
 /// Update the underlying Git repo with changes made in the repo
 #[derive(clap::Args, Clone, Debug)]
-pub struct GitExportArgs {}
+pub struct GitExportArgs {
+    /// Export only this bookmark, or bookmarks matching a pattern (can be
+    /// repeated)
+    ///
+    /// By default, the specified name matches exactly. Use `glob:` prefix to
+    /// select bookmarks by [wildcard pattern].
+    ///
+    /// [wildcard pattern]:
+    ///     https://jj-vcs.github.io/jj/latest/revsets#string-patterns
+    #[arg(
+        long, short,
+        value_parser = StringPattern::parse,
+        add = ArgValueCandidates::new(complete::local_bookmarks),
+    )]
+    bookmark: Vec<StringPattern>,
+}
 
 pub fn cmd_git_export(
     ui: &mut Ui,
     command: &CommandHelper,
-    _args: &GitExportArgs,
+    args: &GitExportArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
     let mut tx = workspace_command.start_transaction();
-    let stats = git::export_refs(tx.repo_mut())?;
+    let stats = if args.bookmark.is_empty() {
+        git::export_refs(tx.repo_mut())?
+    } else {
+        git::export_some_refs(tx.repo_mut(), |kind, symbol| {
+            kind == GitRefKind::Bookmark
+                && args
+                    .bookmark
+                    .iter()
+                    .any(|pattern| pattern.matches(symbol.name.as_str()))
+        })?
+    };
     tx.finish(ui, "export git refs")?;
     print_git_export_stats(ui, &stats)?;
+    if !stats.conflicted_bookmarks.is_empty() {
+        writeln!(
+            ui.warning_default(),
+            "Conflicted bookmarks were not exported:"
+        )?;
+        let mut formatter = ui.stderr_formatter();
+        for symbol in &stats.conflicted_bookmarks {
+            write!(formatter, "  ")?;
+            write!(formatter.labeled("bookmark"), "{symbol}")?;
+            writeln!(formatter)?;
+        }
+    }
     Ok(())
 }
@@ -12,10 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io;
+use std::io::Write as _;
+
+use bstr::ByteSlice as _;
 use clap_complete::ArgValueCandidates;
 use clap_complete::ArgValueCompleter;
 use itertools::Itertools as _;
 use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
 use jj_lib::config::ConfigGetError;
 use jj_lib::config::ConfigGetResultExt as _;
 use jj_lib::graph::reverse_graph;
@@ -31,17 +36,24 @@ use jj_lib::settings::UserSettings;
 use tracing::instrument;
 
 use crate::cli_util::format_template;
+use crate::cli_util::short_commit_hash;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::LogContentFormat;
 use crate::cli_util::RevisionArg;
+use crate::command_error::user_error_with_hint;
 use crate::command_error::CommandError;
 use crate::commit_templater::CommitTemplateLanguage;
 use crate::complete;
 use crate::diff_util::DiffFormatArgs;
+use crate::formatter::Formatter;
 use crate::graphlog::get_graphlog;
 use crate::graphlog::GraphStyle;
 use crate::ui::Ui;
 
+/// Number of revisions above which a warning is printed if they have to be
+/// read upfront to be shown in reverse order.
+const REVERSED_BUFFERING_WARNING_THRESHOLD: usize = 100_000;
+
 /// Show revision history
 ///
 /// Renders a graphical view of the project's history, ordered with children
@@ -96,6 +108,21 @@ pub(crate) struct LogArgs {
     /// Don't show the graph, show a flat list of revisions
     #[arg(long)]
     no_graph: bool,
+    /// Terminate the output of each revision with the given character
+    ///
+    /// The character defaults to NUL, which allows scripts to split the output
+    /// unambiguously even if descriptions contain newlines. It is an error if
+    /// the rendered output of a revision contains the character. Requires
+    /// `--no-graph`.
+    #[arg(
+        long,
+        value_name = "CHAR",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "\0",
+        requires = "no_graph"
+    )]
+    delimited: Option<char>,
     /// Render each revision using the given template
     ///
     /// Run `jj log -T` to list the built-in templates.
@@ -129,7 +156,7 @@ pub(crate) fn cmd_log(
     let workspace_command = command.workspace_helper(ui)?;
     let settings = workspace_command.settings();
 
-    let fileset_expression = workspace_command.parse_file_patterns(ui, &args.paths)?;
+    let fileset_expression = workspace_command.parse_file_patterns_unresolved(ui, &args.paths)?;
     let revset_expression = {
         // only use default revset if neither revset nor path are specified
         let mut expression = if args.revisions.is_empty() && args.paths.is_empty() {
@@ -153,8 +180,16 @@ pub(crate) fn cmd_log(
     let prio_revset = workspace_command.parse_revset(ui, &RevisionArg::from(prio_revset))?;
 
     let repo = workspace_command.repo();
-    let matcher = fileset_expression.to_matcher();
+    let matcher = fileset_expression.to_superset_matcher();
     let revset = revset_expression.evaluate()?;
+    // Ancestor-closed sets can be emitted from the roots without collecting
+    // them first, unless the forward order has to be truncated by --limit.
+    let stream_reversed = args.reversed && args.limit.is_none() && revset.is_ancestor_closed();
+    let revset = if stream_reversed {
+        revset.reversed()
+    } else {
+        revset
+    };
 
     let store = repo.store();
     let diff_renderer = workspace_command.diff_renderer_for_log(&args.diff_format, args.patch)?;
@@ -197,7 +232,9 @@ pub(crate) fn cmd_log(
         if !args.no_graph {
             let mut raw_output = formatter.raw()?;
             let mut graph = get_graphlog(graph_style, raw_output.as_mut());
-            let iter: Box<dyn Iterator<Item = _>> = {
+            let iter: Box<dyn Iterator<Item = _>> = if stream_reversed {
+                revset.iter_graph()
+            } else {
                 let mut forward_iter = TopoGroupedGraphIterator::new(revset.iter_graph());
 
                 let has_commit = revset.containing_fn();
@@ -213,7 +250,9 @@ pub(crate) fn cmd_log(
                 // because the prioritized commit must exist in the input set.
                 let forward_iter = forward_iter.take(args.limit.unwrap_or(usize::MAX));
                 if args.reversed {
-                    Box::new(reverse_graph(forward_iter, |id| id)?.into_iter().map(Ok))
+                    let nodes = reverse_graph(forward_iter, |id| id)?;
+                    warn_reversed_buffering(ui, nodes.len())?;
+                    Box::new(nodes.into_iter().map(Ok))
                 } else {
                     Box::new(forward_iter)
                 }
@@ -298,22 +337,38 @@ pub(crate) fn cmd_log(
                 }
             }
         } else {
-            let iter: Box<dyn Iterator<Item = Result<CommitId, RevsetEvaluationError>>> = {
-                let forward_iter = revset.iter().take(args.limit.unwrap_or(usize::MAX));
-                if args.reversed {
-                    let entries: Vec<_> = forward_iter.try_collect()?;
-                    Box::new(entries.into_iter().rev().map(Ok))
+            let iter: Box<dyn Iterator<Item = Result<CommitId, RevsetEvaluationError>>> =
+                if stream_reversed {
+                    revset.iter()
                 } else {
-                    Box::new(forward_iter)
+                    let forward_iter = revset.iter().take(args.limit.unwrap_or(usize::MAX));
+                    if args.reversed {
+                        let entries: Vec<_> = forward_iter.try_collect()?;
+                        warn_reversed_buffering(ui, entries.len())?;
+                        Box::new(entries.into_iter().rev().map(Ok))
+                    } else {
+                        Box::new(forward_iter)
+                    }
+                };
+            let write_commit = |formatter: &mut dyn Formatter, commit: &Commit| {
+                with_content_format
+                    .write(formatter, |formatter| template.format(commit, formatter))?;
+                if let Some(renderer) = &diff_renderer {
+                    let width = ui.term_width();
+                    renderer.show_patch(ui, formatter, commit, matcher.as_ref(), width)?;
                 }
+                Ok::<_, CommandError>(())
             };
             for commit_or_error in iter.commits(store) {
                 let commit = commit_or_error?;
-                with_content_format
-                    .write(formatter, |formatter| template.format(&commit, formatter))?;
-                if let Some(renderer) = &diff_renderer {
-                    let width = ui.term_width();
-                    renderer.show_patch(ui, formatter, &commit, matcher.as_ref(), width)?;
+                if let Some(delimiter) = args.delimited {
+                    // Render the whole record first so the delimiter is
+                    // written only at the record boundary.
+                    let mut buffer = vec![];
+                    write_commit(ui.new_formatter(&mut buffer).as_mut(), &commit)?;
+                    write_delimited_record(formatter, &buffer, delimiter, &commit)?;
+                } else {
+                    write_commit(formatter, &commit)?;
                 }
             }
         }
@@ -346,6 +401,47 @@ pub(crate) fn cmd_log(
     Ok(())
 }
 
+/// Warns if many revisions were read upfront to show them in reverse order.
+fn warn_reversed_buffering(ui: &Ui, count: usize) -> io::Result<()> {
+    if count >= REVERSED_BUFFERING_WARNING_THRESHOLD {
+        writeln!(
+            ui.warning_default(),
+            "Read all {count} revisions to show them in reverse order"
+        )?;
+        writeln!(
+            ui.hint_default(),
+            "Revsets including all ancestors of their revisions, such as `::@`, are shown \
+             without reading them all first."
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the rendered `record` followed by the `delimiter`. The record must
+/// not contain the delimiter so that the output can be split unambiguously.
+fn write_delimited_record(
+    formatter: &mut dyn Formatter,
+    record: &[u8],
+    delimiter: char,
+    commit: &Commit,
+) -> Result<(), CommandError> {
+    let mut delimiter_buf = [0; 4];
+    let delimiter = delimiter.encode_utf8(&mut delimiter_buf).as_bytes();
+    if record.find(delimiter).is_some() {
+        return Err(user_error_with_hint(
+            format!(
+                "The output of commit {} contains the delimiter",
+                short_commit_hash(commit.id())
+            ),
+            "Use a different delimiter, or remove it from the template output.",
+        ));
+    }
+    let mut output = formatter.raw()?;
+    output.write_all(record)?;
+    output.write_all(delimiter)?;
+    Ok(())
+}
+
 pub fn get_node_template(
     style: GraphStyle,
     settings: &UserSettings,
@@ -18,6 +18,7 @@ mod backout;
 #[cfg(feature = "bench")]
 mod bench;
 mod bookmark;
+mod bundle;
 mod commit;
 mod config;
 mod debug;
@@ -98,6 +99,8 @@ enum Command {
     // TODO: Remove in jj 0.28+
     #[command(subcommand, hide = true)]
     Branch(bookmark::BookmarkCommand),
+    #[command(subcommand)]
+    Bundle(bundle::BundleCommand),
     Commit(commit::CommitArgs),
     #[command(subcommand)]
     Config(config::ConfigCommand),
@@ -127,6 +130,8 @@ enum Command {
     Parallelize(parallelize::ParallelizeArgs),
     Prev(prev::PrevArgs),
     Rebase(rebase::RebaseArgs),
+    /// Redo the most recently undone operation (shortcut for `jj op redo`)
+    Redo(operation::redo::OperationRedoArgs),
     Resolve(resolve::ResolveArgs),
     Restore(restore::RestoreArgs),
     Revert(revert::RevertArgs),
@@ -172,6 +177,7 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
             let cmd = renamed_cmd("branch", "bookmark", bookmark::cmd_bookmark);
             cmd(ui, command_helper, args)
         }
+        Command::Bundle(args) => bundle::cmd_bundle(ui, command_helper, args),
         Command::Commit(args) => commit::cmd_commit(ui, command_helper, args),
         Command::Config(args) => config::cmd_config(ui, command_helper, args),
         Command::Debug(args) => debug::cmd_debug(ui, command_helper, args),
@@ -194,6 +200,7 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Parallelize(args) => parallelize::cmd_parallelize(ui, command_helper, args),
         Command::Prev(args) => prev::cmd_prev(ui, command_helper, args),
         Command::Rebase(args) => rebase::cmd_rebase(ui, command_helper, args),
+        Command::Redo(args) => operation::redo::cmd_op_redo(ui, command_helper, args),
         Command::Resolve(args) => resolve::cmd_resolve(ui, command_helper, args),
         Command::Restore(args) => restore::cmd_restore(ui, command_helper, args),
         Command::Revert(args) => revert::cmd_revert(ui, command_helper, args),
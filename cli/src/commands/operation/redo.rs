@@ -0,0 +1,88 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::object_id::ObjectId as _;
+
+use super::undo::single_parent;
+use super::undo::undo_state;
+use super::undo::REDO_OP_TAG;
+use super::undo::UNDO_OP_TAG;
+use crate::cli_util::CommandHelper;
+use crate::command_error::user_error_with_hint;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Redo the most recently undone operation
+///
+/// This reapplies the operation reverted by the latest `jj undo`. Consecutive
+/// undos can be redone one by one. Running any other operation after `jj undo`
+/// discards the undone operations from the redo history.
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationRedoArgs {}
+
+pub fn cmd_op_redo(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    _args: &OperationRedoArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let op = workspace_command.repo().operation();
+    let state = undo_state(op)?;
+    let Some((undo_op, undone_op)) = state.redo_stack.last() else {
+        // The redo history is discarded by any operation following an undo,
+        // so which operation to redo would be ambiguous.
+        if op.parent_ids().len() > 1 {
+            return Err(user_error_with_hint(
+                "Cannot redo after concurrent operations",
+                "Use `jj op log` to find the state to restore and `jj op restore` to restore it.",
+            ));
+        }
+        let tags = &op.metadata().tags;
+        let is_undo_or_redo = tags.contains_key(UNDO_OP_TAG) || tags.contains_key(REDO_OP_TAG);
+        if let Some(parent_op) = single_parent(op)?.filter(|_| !is_undo_or_redo) {
+            if !undo_state(&parent_op)?.redo_stack.is_empty() {
+                return Err(user_error_with_hint(
+                    "Cannot redo because another operation was run after `jj undo`",
+                    "Use `jj op log` to find the undone operation and `jj op restore` to \
+                     restore it.",
+                ));
+            }
+        }
+        return Err(user_error_with_hint(
+            "Nothing to redo",
+            "Only operations undone by the latest consecutive `jj undo` commands can be redone. \
+             Use `jj op log` to find an earlier state and `jj op restore` to restore it.",
+        ));
+    };
+
+    let mut tx = workspace_command.start_transaction();
+    let repo_loader = tx.base_repo().loader();
+    let undo_repo = repo_loader.load_at(undo_op)?;
+    let parent_op = undo_op
+        .parents()
+        .next()
+        .transpose()?
+        .expect("undo operation should have a parent");
+    let parent_repo = repo_loader.load_at(&parent_op)?;
+    tx.repo_mut().merge(&undo_repo, &parent_repo)?;
+    if let Some(mut formatter) = ui.status_formatter() {
+        write!(formatter, "Redid operation: ")?;
+        let template = tx.base_workspace_helper().operation_summary_template();
+        template.format(undone_op, formatter.as_mut())?;
+        writeln!(formatter)?;
+    }
+    tx.set_tag(REDO_OP_TAG.to_owned(), undo_op.id().hex());
+    tx.finish(ui, format!("redo operation {}", undo_op.id().hex()))?;
+    Ok(())
+}
@@ -12,10 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+
 use clap_complete::ArgValueCandidates;
-use itertools::Itertools as _;
 use jj_lib::object_id::ObjectId as _;
-use jj_lib::op_store::OpStoreError;
+use jj_lib::op_store::OpStoreResult;
+use jj_lib::op_store::OperationId;
 use jj_lib::operation::Operation;
 use jj_lib::repo::Repo as _;
 
@@ -32,6 +34,10 @@ use crate::ui::Ui;
 ///
 /// This undoes an individual operation by applying the inverse of the
 /// operation.
+///
+/// If no operation is specified, the most recent operation is undone.
+/// Running `jj undo` repeatedly undoes earlier and earlier operations, which
+/// can then be reapplied one by one with `jj redo`.
 #[derive(clap::Args, Clone, Debug)]
 pub struct OperationUndoArgs {
     /// The operation to undo
@@ -47,12 +53,114 @@ pub struct OperationUndoArgs {
     what: Vec<UndoWhatToRestore>,
 }
 
-fn is_undo(op: &Operation, parent_op: &Operation) -> Result<bool, OpStoreError> {
-    let grand_parents: Vec<_> = parent_op.parents().try_collect()?;
-    if let [grand_parent_op] = &grand_parents[..] {
-        Ok(op.view_id() == grand_parent_op.view_id())
+/// Operation tag recording the id of the operation undone by `jj undo`.
+pub(super) const UNDO_OP_TAG: &str = "undo-of";
+/// Operation tag recording the id of the undo operation redone by `jj redo`.
+pub(super) const REDO_OP_TAG: &str = "redo-of";
+
+/// Undo/redo history as of an operation.
+pub(super) struct UndoState {
+    /// Operation to be undone by the next `jj undo`. The repo is in the state
+    /// of this operation.
+    pub op_to_undo: Operation,
+    /// Undo operations that can be redone, paired with the operations they
+    /// undid. The last entry is redone first.
+    pub redo_stack: Vec<(Operation, Operation)>,
+}
+
+impl UndoState {
+    fn ordinary(op: &Operation) -> Self {
+        Self {
+            op_to_undo: op.clone(),
+            redo_stack: vec![],
+        }
+    }
+}
+
+pub(super) fn single_parent(op: &Operation) -> OpStoreResult<Option<Operation>> {
+    let mut parents = op.parents();
+    match (parents.next().transpose()?, parents.next()) {
+        (Some(parent), None) => Ok(Some(parent)),
+        _ => Ok(None),
+    }
+}
+
+fn op_id_from_tag(op: &Operation, key: &str) -> Option<OperationId> {
+    let hex = op.metadata().tags.get(key)?;
+    OperationId::try_from_hex(hex).ok()
+}
+
+/// Reconstructs the undo/redo history from the chain of undo and redo
+/// operations ending at `op`.
+///
+/// An undo or redo operation that doesn't continue the chain (e.g. `jj op undo`
+/// of an arbitrary operation) is treated as an ordinary operation, which
+/// clears the redo history.
+pub(super) fn undo_state(op: &Operation) -> OpStoreResult<UndoState> {
+    // The state of an undo operation depends on the states of its parent and
+    // of the operation it restored, both of which are ancestors. Resolve them
+    // with an explicit stack so long chains don't overflow the call stack.
+    let mut states: HashMap<OperationId, UndoState> = HashMap::new();
+    let mut stack = vec![op.clone()];
+    while let Some(current) = stack.last() {
+        if states.contains_key(current.id()) {
+            stack.pop();
+            continue;
+        }
+        match undo_state_step(current, &states)? {
+            Ok(state) => {
+                let current = stack.pop().unwrap();
+                states.insert(current.id().clone(), state);
+            }
+            Err(dependency) => stack.push(dependency),
+        }
+    }
+    Ok(states.remove(op.id()).unwrap())
+}
+
+/// Computes the undo state of `op` from the already computed `states`, or
+/// returns the operation whose state has to be computed first.
+fn undo_state_step(
+    op: &Operation,
+    states: &HashMap<OperationId, UndoState>,
+) -> OpStoreResult<Result<UndoState, Operation>> {
+    let Some(parent_op) = single_parent(op)? else {
+        return Ok(Ok(UndoState::ordinary(op)));
+    };
+    if let Some(undone_id) = op_id_from_tag(op, UNDO_OP_TAG) {
+        let Some(parent_state) = states.get(parent_op.id()) else {
+            return Ok(Err(parent_op));
+        };
+        if parent_state.op_to_undo.id() != &undone_id {
+            return Ok(Ok(UndoState::ordinary(op)));
+        }
+        let undone_op = &parent_state.op_to_undo;
+        let Some(restored_op) = single_parent(undone_op)? else {
+            return Ok(Ok(UndoState::ordinary(op)));
+        };
+        let Some(restored_state) = states.get(restored_op.id()) else {
+            return Ok(Err(restored_op));
+        };
+        let mut redo_stack = parent_state.redo_stack.clone();
+        redo_stack.push((op.clone(), undone_op.clone()));
+        Ok(Ok(UndoState {
+            op_to_undo: restored_state.op_to_undo.clone(),
+            redo_stack,
+        }))
+    } else if let Some(redone_id) = op_id_from_tag(op, REDO_OP_TAG) {
+        let Some(parent_state) = states.get(parent_op.id()) else {
+            return Ok(Err(parent_op));
+        };
+        let mut redo_stack = parent_state.redo_stack.clone();
+        match redo_stack.pop() {
+            Some((undo_op, undone_op)) if undo_op.id() == &redone_id => Ok(Ok(UndoState {
+                op_to_undo: undone_op,
+                redo_stack,
+            })),
+            _ => Ok(Ok(UndoState::ordinary(op))),
+        }
     } else {
-        Ok(false)
+        Ok(Ok(UndoState::ordinary(op)))
     }
 }
 
@@ -62,7 +170,11 @@ pub fn cmd_op_undo(
     args: &OperationUndoArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    let bad_op = workspace_command.resolve_single_op(&args.operation)?;
+    let bad_op = if args.operation == "@" {
+        undo_state(workspace_command.repo().operation())?.op_to_undo
+    } else {
+        workspace_command.resolve_single_op(&args.operation)?
+    };
     let mut parent_ops = bad_op.parents();
     let Some(parent_op) = parent_ops.next().transpose()? else {
         return Err(user_error("Cannot undo repo initialization"));
@@ -88,20 +200,14 @@ pub fn cmd_op_undo(
         template.format(&bad_op, formatter.as_mut())?;
         writeln!(formatter)?;
     }
-    tx.finish(ui, format!("undo operation {}", bad_op.id().hex()))?;
-
-    if args.operation == "@" && is_undo(&bad_op, &parent_op)? {
-        writeln!(
-            ui.hint_default(),
-            "This action reverted an 'undo' operation. The repository is now in the same state as \
-             it was before the original 'undo'."
-        )?;
-        writeln!(
-            ui.hint_default(),
-            "If your goal is to undo multiple operations, consider using `jj op log` to see past \
-             states, and `jj op restore` to restore one of these states."
-        )?;
+    // Only a full undo can be redone. Undoing just some portions of the view
+    // doesn't continue the undo chain.
+    if DEFAULT_UNDO_WHAT
+        .iter()
+        .all(|what| args.what.contains(what))
+    {
+        tx.set_tag(UNDO_OP_TAG.to_owned(), bad_op.id().hex());
     }
-
+    tx.finish(ui, format!("undo operation {}", bad_op.id().hex()))?;
     Ok(())
 }
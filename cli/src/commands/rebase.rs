@@ -161,6 +161,13 @@ use crate::ui::Ui;
 /// J           J
 /// ```
 ///
+/// When rebasing a merge revision with `-r`, `--keep-parents` can be used to
+/// preserve the merge structure. Only the parents that are ancestors or
+/// descendants of the destination are replaced by the destination; the other
+/// parents are kept. For example, if M is a merge of K and N, and L is a
+/// descendant of K, then `jj rebase -r M -d L --keep-parents` makes M' a merge
+/// of L and N. Parents can be removed from the merge with `--drop-parent`.
+///
 /// `jj rebase -s X` is similar to `jj rebase -r X::` and will behave the same
 /// if X is a single revision. However, if X is a set of multiple revisions,
 /// or if you passed multiple `-s` arguments, then `jj rebase -s` will make each
@@ -315,6 +322,32 @@ pub(crate) struct RebaseArgs {
     #[command(flatten)]
     destination: RebaseDestinationArgs,
 
+    /// Keep the parents of the rebased revision that are unrelated to the
+    /// destination
+    ///
+    /// Parents that are ancestors or descendants of a destination revision
+    /// are replaced by that destination, and the other parents are kept. This
+    /// can be used to move one side of a merge. Only a single revision can be
+    /// rebased with this option.
+    #[arg(
+        long,
+        requires = "revisions",
+        conflicts_with_all = ["insert_after", "insert_before"]
+    )]
+    keep_parents: bool,
+
+    /// Remove the given parent(s) from the rebased revision (can be repeated)
+    ///
+    /// This implies `--keep-parents` for the other parents.
+    #[arg(
+        long,
+        value_name = "REVSETS",
+        requires = "revisions",
+        conflicts_with_all = ["insert_after", "insert_before"],
+        add = ArgValueCandidates::new(complete::all_revisions)
+    )]
+    drop_parent: Vec<RevisionArg>,
+
     /// Deprecated. Use --skip-emptied instead.
     #[arg(long, conflicts_with = "revisions", hide = true)]
     skip_empty: bool,
@@ -386,7 +419,16 @@ pub(crate) fn cmd_rebase(
         simplify_ancestor_merge: false,
     };
     let mut workspace_command = command.workspace_helper(ui)?;
-    if !args.revisions.is_empty() {
+    if args.keep_parents || !args.drop_parent.is_empty() {
+        rebase_revision_keeping_parents(
+            ui,
+            &mut workspace_command,
+            &args.revisions,
+            &args.destination,
+            &args.drop_parent,
+            &rebase_options,
+        )?;
+    } else if !args.revisions.is_empty() {
         rebase_revisions(
             ui,
             &mut workspace_command,
@@ -455,6 +497,87 @@ fn rebase_revisions(
     )
 }
 
+fn rebase_revision_keeping_parents(
+    ui: &mut Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    revisions: &[RevisionArg],
+    rebase_destination: &RebaseDestinationArgs,
+    drop_parents: &[RevisionArg],
+    rebase_options: &RebaseOptions,
+) -> Result<(), CommandError> {
+    let target_commits: Vec<_> = workspace_command
+        .parse_union_revsets(ui, revisions)?
+        .evaluate_to_commits()?
+        .try_collect()?;
+    let [target_commit] = &*target_commits else {
+        return Err(user_error(
+            "--keep-parents and --drop-parent can only be used to rebase a single revision",
+        ));
+    };
+    workspace_command.check_rewritable([target_commit.id()])?;
+
+    let (destination_ids, _) = compute_commit_location(
+        ui,
+        workspace_command,
+        rebase_destination.destination.as_deref(),
+        None,
+        None,
+        "rebased commits",
+    )?;
+    if destination_ids.contains(target_commit.id()) {
+        return Err(user_error(format!(
+            "Cannot rebase {} onto itself",
+            short_commit_hash(target_commit.id()),
+        )));
+    }
+    let drop_parent_ids = if drop_parents.is_empty() {
+        Default::default()
+    } else {
+        workspace_command.resolve_some_revsets_default_single(ui, drop_parents)?
+    };
+    for parent_id in &drop_parent_ids {
+        if !target_commit.parent_ids().contains(parent_id) {
+            return Err(user_error(format!(
+                "Cannot drop {} which is not a parent of {}",
+                short_commit_hash(parent_id),
+                short_commit_hash(target_commit.id()),
+            )));
+        }
+    }
+
+    let index = workspace_command.repo().index();
+    let mut new_parent_ids: Vec<CommitId> = vec![];
+    for parent_id in target_commit.parent_ids() {
+        if drop_parent_ids.contains(parent_id) {
+            continue;
+        }
+        let new_parent_id = destination_ids
+            .iter()
+            .find(|&destination_id| {
+                index.is_ancestor(parent_id, destination_id)
+                    || index.is_ancestor(destination_id, parent_id)
+            })
+            .unwrap_or(parent_id);
+        if !new_parent_ids.contains(new_parent_id) {
+            new_parent_ids.push(new_parent_id.clone());
+        }
+    }
+    for destination_id in &destination_ids {
+        if !new_parent_ids.contains(destination_id) {
+            new_parent_ids.push(destination_id.clone());
+        }
+    }
+
+    rebase_revisions_transaction(
+        ui,
+        workspace_command,
+        &new_parent_ids,
+        &[],
+        vec![target_commit.clone()],
+        rebase_options,
+    )
+}
+
 fn rebase_source(
     ui: &mut Ui,
     workspace_command: &mut WorkspaceCommandHelper,
@@ -81,11 +81,11 @@ pub(crate) fn cmd_resolve(
     args: &ResolveArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    let matcher = workspace_command
-        .parse_file_patterns(ui, &args.paths)?
-        .to_matcher();
     let commit = workspace_command.resolve_single_rev(ui, &args.revision)?;
     let tree = commit.tree()?;
+    let matcher = workspace_command
+        .parse_file_patterns_in_tree(ui, &args.paths, &tree)?
+        .to_matcher();
     let conflicts = tree
         .conflicts()
         .filter(|path| matcher.matches(&path.0))
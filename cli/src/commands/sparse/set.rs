@@ -13,13 +13,22 @@
 // limitations under the License.
 
 use std::collections::HashSet;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
 
 use itertools::Itertools as _;
+use jj_lib::matchers::DifferenceMatcher;
+use jj_lib::matchers::PrefixMatcher;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::repo::Repo as _;
 use jj_lib::repo_path::RepoPathBuf;
 use tracing::instrument;
 
 use super::update_sparse_patterns_with;
 use crate::cli_util::CommandHelper;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_message;
 use crate::command_error::CommandError;
 use crate::ui::Ui;
 
@@ -28,6 +37,10 @@ use crate::ui::Ui;
 /// For example, if all you need is the `README.md` and the `lib/`
 /// directory, use `jj sparse set --clear --add README.md --add lib`.
 /// If you no longer need the `lib` directory, use `jj sparse set --remove lib`.
+///
+/// The patterns are updated in the following order: the current patterns (or
+/// none with `--clear`, or the patterns read by `--from-file`), then the
+/// `--remove` patterns are removed, then the `--add` patterns are added.
 #[derive(clap::Args, Clone, Debug)]
 pub struct SparseSetArgs {
     /// Patterns to add to the working copy
@@ -48,6 +61,21 @@ pub struct SparseSetArgs {
     /// Include no files in the working copy (combine with --add)
     #[arg(long)]
     clear: bool,
+    /// Replace the current patterns with the patterns read from the file
+    ///
+    /// The file should contain one workspace-relative pattern per line. Empty
+    /// lines and lines starting with `#` are ignored.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "clear",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    from_file: Option<PathBuf>,
+    /// Print the resulting patterns and the number of files to be added and
+    /// removed, without updating the working copy
+    #[arg(long)]
+    dry_run: bool,
 }
 
 #[instrument(skip_all)]
@@ -57,17 +85,90 @@ pub fn cmd_sparse_set(
     args: &SparseSetArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    update_sparse_patterns_with(ui, &mut workspace_command, |_ui, old_patterns| {
+    let file_patterns = args
+        .from_file
+        .as_deref()
+        .map(read_sparse_patterns_file)
+        .transpose()?;
+    let compose_patterns = |old_patterns: &[RepoPathBuf]| {
         let mut new_patterns = HashSet::new();
-        if !args.clear {
+        if let Some(file_patterns) = &file_patterns {
+            new_patterns.extend(file_patterns.iter().cloned());
+        } else if !args.clear {
             new_patterns.extend(old_patterns.iter().cloned());
-            for path in &args.remove {
-                new_patterns.remove(path);
-            }
+        }
+        for path in &args.remove {
+            new_patterns.remove(path);
         }
         for path in &args.add {
             new_patterns.insert(path.to_owned());
         }
-        Ok(new_patterns.into_iter().sorted_unstable().collect())
+        new_patterns.into_iter().sorted_unstable().collect_vec()
+    };
+
+    if args.dry_run {
+        let old_patterns = workspace_command.working_copy().sparse_patterns()?;
+        let new_patterns = compose_patterns(old_patterns);
+        let commit_id = workspace_command
+            .get_wc_commit_id()
+            .ok_or_else(|| user_error("This command requires a working copy"))?;
+        let tree = workspace_command
+            .repo()
+            .store()
+            .get_commit(commit_id)?
+            .tree()?;
+        let added_count = count_files_in_difference(&tree, &new_patterns, old_patterns);
+        let removed_count = count_files_in_difference(&tree, old_patterns, &new_patterns);
+        for path in &new_patterns {
+            writeln!(
+                ui.stdout(),
+                "{}",
+                path.to_fs_path_unchecked(Path::new("")).display()
+            )?;
+        }
+        writeln!(
+            ui.status(),
+            "Would add {added_count} files, remove {removed_count} files"
+        )?;
+        return Ok(());
+    }
+
+    update_sparse_patterns_with(ui, &mut workspace_command, |_ui, old_patterns| {
+        Ok(compose_patterns(old_patterns))
     })
 }
+
+/// Reads sparse patterns from the file, one pattern per line.
+fn read_sparse_patterns_file(path: &Path) -> Result<Vec<RepoPathBuf>, CommandError> {
+    let content = std::fs::read_to_string(path).map_err(|err| {
+        user_error_with_message(format!("Failed to read file {}", path.display()), err)
+    })?;
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| {
+            RepoPathBuf::from_relative_path(line).map_err(|err| {
+                user_error_with_message(
+                    format!(
+                        "Failed to parse sparse pattern at {}:{line_number}: {line}",
+                        path.display()
+                    ),
+                    err,
+                )
+            })
+        })
+        .try_collect()
+}
+
+/// Counts files in the `tree` matched by the `wanted` patterns but not by the
+/// `unwanted` patterns.
+fn count_files_in_difference(
+    tree: &MergedTree,
+    wanted: &[RepoPathBuf],
+    unwanted: &[RepoPathBuf],
+) -> usize {
+    let matcher = DifferenceMatcher::new(PrefixMatcher::new(wanted), PrefixMatcher::new(unwanted));
+    tree.entries_matching(&matcher).count()
+}
@@ -59,6 +59,13 @@ pub(crate) fn cmd_status(
         &snapshot_stats,
         workspace_command.env().path_converter(),
     )?;
+    if let Some(err) = &snapshot_stats.fsmonitor_error {
+        writeln!(
+            ui.warning_default(),
+            "Filesystem monitor is configured but unavailable: {}",
+            err.message
+        )?;
+    }
     let repo = workspace_command.repo();
     let maybe_wc_commit = workspace_command
         .get_wc_commit_id()
@@ -14,6 +14,7 @@
 
 use std::any::Any;
 use std::cmp::max;
+use std::cmp::min;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::io;
@@ -824,6 +825,14 @@ fn builtin_commit_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Comm
             Ok(L::wrap_commit_list(out_property))
         },
     );
+    map.insert(
+        "duplicated_from",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.and_then(|commit| Ok(commit.duplicated_from()?));
+            Ok(L::wrap_commit_opt(out_property))
+        },
+    );
     map.insert(
         "author",
         |_language, _diagnostics, _build_ctx, self_property, function| {
@@ -1089,6 +1098,12 @@ fn expect_fileset_literal(
         diagnostics.extend_with(inner_diagnostics, |diag| {
             TemplateParseError::expression("In fileset expression", span).with_source(diag)
         });
+        if let Some(name) = expression.unresolved_predicate_name() {
+            return Err(TemplateParseError::expression(
+                format!("{name} can't be used in templates"),
+                span,
+            ));
+        }
         Ok(expression)
     })
 }
@@ -1104,7 +1119,8 @@ fn evaluate_revset_expression<'repo>(
         repo,
         language.revset_parse_context.extensions.symbol_resolvers(),
         language.id_prefix_context,
-    );
+    )
+    .map_err(|err| make_error().with_source(err))?;
     let revset = expression
         .resolve_user_expression(repo, &symbol_resolver)
         .map_err(|err| make_error().with_source(err))?
@@ -1568,14 +1584,34 @@ impl CommitOrChangeId {
         total_len: usize,
     ) -> ShortestIdPrefix {
         let mut hex = self.hex();
-        let prefix_len = match self {
-            CommitOrChangeId::Commit(id) => index.shortest_commit_prefix_len(repo, id),
-            CommitOrChangeId::Change(id) => index.shortest_change_prefix_len(repo, id),
-        };
+        let prefix_len = self.shortest_prefix_len(repo, index);
         hex.truncate(max(prefix_len, total_len));
         let rest = hex.split_off(prefix_len);
         ShortestIdPrefix { prefix: hex, rest }
     }
+
+    /// The length of the id printed will be exactly `total_len`, so it doesn't
+    /// change as the repo grows. The shortest unique prefix is highlighted if
+    /// it fits in the length.
+    pub fn short_stable(
+        &self,
+        repo: &dyn Repo,
+        index: &IdPrefixIndex,
+        total_len: usize,
+    ) -> ShortestIdPrefix {
+        let mut hex = self.hex();
+        let prefix_len = self.shortest_prefix_len(repo, index);
+        hex.truncate(total_len);
+        let rest = hex.split_off(min(prefix_len, hex.len()));
+        ShortestIdPrefix { prefix: hex, rest }
+    }
+
+    fn shortest_prefix_len(&self, repo: &dyn Repo, index: &IdPrefixIndex) -> usize {
+        match self {
+            CommitOrChangeId::Commit(id) => index.shortest_commit_prefix_len(repo, id),
+            CommitOrChangeId::Change(id) => index.shortest_change_prefix_len(repo, id),
+        }
+    }
 }
 
 impl Template for CommitOrChangeId {
@@ -1659,6 +1695,28 @@ fn builtin_commit_or_change_id_methods<'repo>(
             Ok(L::wrap_shortest_id_prefix(out_property))
         },
     );
+    map.insert(
+        "short_stable",
+        |language, diagnostics, build_ctx, self_property, function| {
+            let [len_node] = function.expect_exact_arguments()?;
+            let len_property = template_builder::expect_usize_expression(
+                language,
+                diagnostics,
+                build_ctx,
+                len_node,
+            )?;
+            let repo = language.repo;
+            // The length doesn't depend on the index, so the failure isn't
+            // worth a warning. The ids are just rendered without highlighting.
+            let index = language
+                .id_prefix_context
+                .populate(repo)
+                .unwrap_or_else(|_| IdPrefixIndex::empty());
+            let out_property = (self_property, len_property)
+                .map(move |(id, len)| id.short_stable(repo, &index, len));
+            Ok(L::wrap_shortest_id_prefix(out_property))
+        },
+    );
     map
 }
 
@@ -1912,8 +1970,11 @@ fn builtin_tree_diff_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, T
                 })
                 .transpose()?;
             let path_converter = language.path_converter;
-            // No user configuration exists for diff stat.
-            let options = diff_util::DiffStatOptions::default();
+            let options =
+                diff_util::DiffStatOptions::from_settings(language.settings()).map_err(|err| {
+                    let message = "Failed to load diff settings";
+                    TemplateParseError::expression(message, function.name_span).with_source(err)
+                })?;
             let conflict_marker_style = language.conflict_marker_style;
             // TODO: cache and reuse stats within the current evaluation?
             let out_property = (self_property, width_property).and_then(move |(diff, width)| {
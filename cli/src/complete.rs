@@ -13,12 +13,19 @@
 // limitations under the License.
 
 use std::io::BufRead as _;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use clap::builder::StyledStr;
 use clap::FromArgMatches as _;
 use clap_complete::CompletionCandidate;
 use itertools::Itertools as _;
 use jj_lib::config::ConfigNamePathBuf;
+use jj_lib::op_walk;
+use jj_lib::repo::ReadonlyRepo;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo::RepoLoader;
+use jj_lib::repo::StoreFactories;
 use jj_lib::settings::UserSettings;
 use jj_lib::workspace::DefaultWorkspaceLoaderFactory;
 use jj_lib::workspace::WorkspaceLoaderFactory as _;
@@ -27,6 +34,7 @@ use crate::cli_util::expand_args;
 use crate::cli_util::find_workspace_dir;
 use crate::cli_util::load_template_aliases;
 use crate::cli_util::GlobalArgs;
+use crate::command_error::internal_error;
 use crate::command_error::user_error;
 use crate::command_error::CommandError;
 use crate::config::config_from_environment;
@@ -236,10 +244,11 @@ fn revisions(revisions: Option<&str>) -> Vec<CompletionCandidate> {
         const LOCAL_BOOKMARK_MINE: usize = 0;
         const LOCAL_BOOKMARK: usize = 1;
         const TAG: usize = 2;
-        const CHANGE_ID: usize = 3;
-        const REMOTE_BOOKMARK_MINE: usize = 4;
-        const REMOTE_BOOKMARK: usize = 5;
-        const REVSET_ALIAS: usize = 6;
+        const WORKING_COPY: usize = 3;
+        const CHANGE_ID: usize = 4;
+        const REMOTE_BOOKMARK_MINE: usize = 5;
+        const REMOTE_BOOKMARK: usize = 6;
+        const REVSET_ALIAS: usize = 7;
 
         let mut candidates = Vec::new();
 
@@ -307,6 +316,18 @@ fn revisions(revisions: Option<&str>) -> Vec<CompletionCandidate> {
             }));
         }
 
+        // working-copy revisions
+
+        candidates.extend(
+            list_workspaces(&jj, settings)?
+                .into_iter()
+                .map(|(name, desc)| {
+                    CompletionCandidate::new(format!("{name}@"))
+                        .help(Some(desc.into()))
+                        .display_order(Some(WORKING_COPY))
+                }),
+        );
+
         // change IDs
 
         let revisions = revisions
@@ -392,27 +413,35 @@ pub fn operations() -> Vec<CompletionCandidate> {
 }
 
 pub fn workspaces() -> Vec<CompletionCandidate> {
-    with_jj(|jj, _| {
-        let output = jj
-            .build()
-            .arg("--config")
-            .arg(r#"templates.commit_summary='if(description, description.first_line(), "(no description set)")'"#)
-            .arg("workspace")
-            .arg("list")
-            .output()
-            .map_err(user_error)?;
-        let stdout = String::from_utf8_lossy(&output.stdout);
-
-        Ok(stdout
-            .lines()
-            .map(|line| {
-                let (name, desc) = line.split_once(": ").unwrap_or((line, ""));
-                CompletionCandidate::new(name).help(Some(desc.to_string().into()))
-            })
+    with_jj(|jj, settings| {
+        Ok(list_workspaces(&jj, settings)?
+            .into_iter()
+            .map(|(name, desc)| CompletionCandidate::new(name).help(Some(desc.into())))
             .collect())
     })
 }
 
+/// Returns the workspace names and the descriptions of their working-copy
+/// commits.
+fn list_workspaces(
+    jj: &JjBuilder,
+    settings: &UserSettings,
+) -> Result<Vec<(String, String)>, CommandError> {
+    let repo = jj.load_repo(settings)?;
+    repo.view()
+        .wc_commit_ids()
+        .iter()
+        .map(|(name, commit_id)| {
+            let commit = repo.store().get_commit(commit_id)?;
+            let desc = match commit.description().lines().next() {
+                Some(line) => line.to_owned(),
+                None => "(no description set)".to_owned(),
+            };
+            Ok((name.as_symbol().to_string(), desc))
+        })
+        .collect()
+}
+
 fn config_keys_rec(
     prefix: ConfigNamePathBuf,
     properties: &serde_json::Map<String, serde_json::Value>,
@@ -841,7 +870,9 @@ fn get_jj_command() -> Result<(JjBuilder, UserSettings), CommandError> {
     let mut config_env = ConfigEnv::from_environment();
     let maybe_cwd_workspace_loader = DefaultWorkspaceLoaderFactory.create(find_workspace_dir(&cwd));
     let _ = config_env.reload_user_config(&mut raw_config);
+    let mut repo_path = None;
     if let Ok(loader) = &maybe_cwd_workspace_loader {
+        repo_path = Some(loader.repo_path().to_owned());
         config_env.reset_repo_path(loader.repo_path());
         let _ = config_env.reload_repo_config(&mut raw_config);
     }
@@ -859,7 +890,9 @@ fn get_jj_command() -> Result<(JjBuilder, UserSettings), CommandError> {
 
     if let Some(repository) = args.repository {
         // Try to update repo-specific config on a best-effort basis.
+        repo_path = None;
         if let Ok(loader) = DefaultWorkspaceLoaderFactory.create(&cwd.join(&repository)) {
+            repo_path = Some(loader.repo_path().to_owned());
             config_env.reset_repo_path(loader.repo_path());
             let _ = config_env.reload_repo_config(&mut raw_config);
             if let Ok(new_config) = config_env.resolve_config(&raw_config) {
@@ -869,6 +902,7 @@ fn get_jj_command() -> Result<(JjBuilder, UserSettings), CommandError> {
         cmd_args.push("--repository".into());
         cmd_args.push(repository);
     }
+    let mut valid_at_operation = None;
     if let Some(at_operation) = args.at_operation {
         // We cannot assume that the value of at_operation is valid, because
         // the user may be requesting completions precisely for this invalid
@@ -891,7 +925,8 @@ fn get_jj_command() -> Result<(JjBuilder, UserSettings), CommandError> {
             Ok(output) if output.status.success() => {
                 // Operation ID is valid, add it to the completion command.
                 cmd_args.push("--at-operation".into());
-                cmd_args.push(at_operation);
+                cmd_args.push(at_operation.clone());
+                valid_at_operation = Some(at_operation);
             }
             _ => {} // Invalid operation ID, ignore.
         }
@@ -908,6 +943,8 @@ fn get_jj_command() -> Result<(JjBuilder, UserSettings), CommandError> {
     let builder = JjBuilder {
         cmd: current_exe,
         args: cmd_args,
+        repo_path,
+        at_operation: valid_at_operation,
     };
     let settings = UserSettings::from_config(config)?;
 
@@ -919,6 +956,8 @@ fn get_jj_command() -> Result<(JjBuilder, UserSettings), CommandError> {
 struct JjBuilder {
     cmd: std::path::PathBuf,
     args: Vec<String>,
+    repo_path: Option<PathBuf>,
+    at_operation: Option<String>,
 }
 
 impl JjBuilder {
@@ -927,6 +966,23 @@ impl JjBuilder {
         cmd.args(&self.args);
         cmd
     }
+
+    /// Loads the repo in-process without touching the working copy.
+    ///
+    /// Unlike the commands run by [`Self::build()`], only the default storage
+    /// backends are supported.
+    fn load_repo(&self, settings: &UserSettings) -> Result<Arc<ReadonlyRepo>, CommandError> {
+        let repo_path = self
+            .repo_path
+            .as_ref()
+            .ok_or_else(|| user_error("There is no jj repo in the working directory"))?;
+        let repo_loader =
+            RepoLoader::init_from_file_system(settings, repo_path, &StoreFactories::default())
+                .map_err(internal_error)?;
+        let op_str = self.at_operation.as_deref().unwrap_or("@");
+        let op = op_walk::resolve_op_for_load(&repo_loader, op_str)?;
+        Ok(repo_loader.load_at(&op)?)
+    }
 }
 
 /// Functions for parsing revisions and revision ranges from the command line.
@@ -618,6 +618,21 @@ pub fn default_config_migrations() -> Vec<ConfigMigrationRule> {
         ),
         // TODO: Delete in jj 0.34+
         ConfigMigrationRule::rename_value("diff.format", "ui.diff.format"),
+        // TODO: Delete in jj 0.35+
+        ConfigMigrationRule::rename_update_value(
+            "git.write-change-id-header",
+            "git.change-id-location",
+            |old_value| {
+                if old_value
+                    .as_bool()
+                    .ok_or("git.write-change-id-header expects a boolean")?
+                {
+                    Ok("header".into())
+                } else {
+                    Ok("none".into())
+                }
+            },
+        ),
         // TODO: Delete with the `git.subprocess` setting.
         #[cfg(not(feature = "git2"))]
         ConfigMigrationRule::custom(
@@ -11,16 +11,25 @@ use indexmap::IndexMap;
 use indoc::indoc;
 use itertools::FoldWhile;
 use itertools::Itertools as _;
+use jj_lib::backend::ChangeId;
 use jj_lib::backend::CommitId;
 use jj_lib::commit::Commit;
 use jj_lib::config::ConfigGetError;
 use jj_lib::file_util::IoResultExt as _;
 use jj_lib::file_util::PathError;
+use jj_lib::object_id::ObjectId as _;
 use jj_lib::settings::UserSettings;
+use jj_lib::trailer;
+use jj_lib::trailer::Trailer;
+use jj_lib::trailer::TrailerDedup;
+use jj_lib::trailer::TrailerEdit;
 use thiserror::Error;
 
 use crate::cli_util::short_commit_hash;
 use crate::cli_util::WorkspaceCommandTransaction;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
+use crate::command_error::user_error_with_message;
 use crate::command_error::CommandError;
 use crate::config::CommandNameAndArgs;
 use crate::formatter::PlainTextFormatter;
@@ -166,30 +175,106 @@ where
     text_util::complete_newline(description.trim_matches('\n'))
 }
 
-pub fn edit_description(editor: &TextEditor, description: &str) -> Result<String, CommandError> {
+/// Parses a description that may contain "JJ:" comment lines, as if it were
+/// edited in an editor.
+pub fn parse_description(text: &str) -> String {
+    cleanup_description_lines(text.lines())
+}
+
+fn description_editor_content(description: &str) -> String {
     let mut description = description.to_owned();
     append_blank_line(&mut description);
     description.push_str("JJ: Lines starting with \"JJ:\" (like this one) will be removed.\n");
+    description
+}
 
+pub fn edit_description(editor: &TextEditor, description: &str) -> Result<String, CommandError> {
     let description = editor
-        .edit_str(description, Some(".jjdescription"))
+        .edit_str(
+            description_editor_content(description),
+            Some(".jjdescription"),
+        )
         .map_err(|err| err.with_name("description"))?;
 
     Ok(cleanup_description_lines(description.lines()))
 }
 
+/// Path to the description of the change saved by a failed edit.
+pub fn saved_description_path(repo_path: &Path, change_id: &ChangeId) -> PathBuf {
+    repo_path.join(format!("editor-describe-{}.jjdescription", change_id.hex()))
+}
+
+/// Edits the description like [`edit_description()`], but keeps the edited
+/// text at `saved_path` if the editor fails after changing it.
+///
+/// If `resume` is true, the editor is opened with the text saved by a
+/// previous failed edit instead of the given `description`. The caller should
+/// remove the saved text once the new description is recorded.
+pub fn edit_description_resumable(
+    editor: &TextEditor,
+    description: &str,
+    saved_path: &Path,
+    resume: bool,
+) -> Result<String, CommandError> {
+    let content = if resume {
+        match fs::read_to_string(saved_path).context(saved_path) {
+            Ok(content) => content,
+            Err(err) if err.error.kind() == io::ErrorKind::NotFound => {
+                return Err(user_error(
+                    "No interrupted edit of the description to resume",
+                ));
+            }
+            Err(err) => {
+                return Err(user_error_with_message(
+                    "Failed to read saved description",
+                    err,
+                ));
+            }
+        }
+    } else {
+        description_editor_content(description)
+    };
+
+    let description = editor
+        .edit_str(&content, Some(".jjdescription"))
+        .map_err(|mut err| {
+            // Move the edited text to a known location so it can be resumed.
+            // There's nothing to resume if the text wasn't changed.
+            if let Some(path) = &err.path {
+                let edited = fs::read_to_string(path).ok();
+                if edited.is_some_and(|edited| edited != content)
+                    && fs::rename(path, saved_path).is_ok()
+                {
+                    err.path = Some(saved_path.to_owned());
+                }
+            }
+            err.with_name("description")
+        })?;
+
+    Ok(cleanup_description_lines(description.lines()))
+}
+
 /// Edits the descriptions of the given commits in a single editor session.
+///
+/// Each description is preceded by a line consisting of the `separator`
+/// prefix and the commit hash. If `retry` is true and the edited message is
+/// invalid, the editor is reopened with the edited message and a note about
+/// the error. Exiting the editor without making changes aborts.
 pub fn edit_multiple_descriptions(
     ui: &Ui,
     editor: &TextEditor,
     tx: &WorkspaceCommandTransaction,
     commits: &[(&CommitId, Commit)],
-) -> Result<ParsedBulkEditMessage<CommitId>, CommandError> {
+    separator: &str,
+    retry: bool,
+) -> Result<HashMap<CommitId, String>, CommandError> {
     let mut commits_map = IndexMap::new();
     let mut bulk_message = String::new();
 
+    bulk_message.push_str(&format!(
+        "JJ: Enter or edit commit descriptions after the `{separator}` lines.\n"
+    ));
     bulk_message.push_str(indoc! {r#"
-        JJ: Enter or edit commit descriptions after the `JJ: describe` lines.
         JJ: Warning:
         JJ: - The text you enter will be lost on a syntax error.
         JJ: - The syntax of the separator lines may change in the future.
@@ -197,7 +282,8 @@ pub fn edit_multiple_descriptions(
     "#});
     for (commit_id, temp_commit) in commits {
         let commit_hash = short_commit_hash(commit_id);
-        bulk_message.push_str("JJ: describe ");
+        bulk_message.push_str(separator);
+        bulk_message.push(' ');
         bulk_message.push_str(&commit_hash);
         bulk_message.push_str(" -------\n");
         commits_map.insert(commit_hash, *commit_id);
@@ -207,11 +293,57 @@ pub fn edit_multiple_descriptions(
     }
     bulk_message.push_str("JJ: Lines starting with \"JJ:\" (like this one) will be removed.\n");
 
-    let bulk_message = editor
-        .edit_str(bulk_message, Some(".jjdescription"))
-        .map_err(|err| err.with_name("description"))?;
+    let mut error_note = String::new();
+    loop {
+        let edited = editor
+            .edit_str(&bulk_message, Some(".jjdescription"))
+            .map_err(|err| err.with_name("description"))?;
+        let err = match parse_bulk_edit_message(&edited, &commits_map, separator)
+            .map_err(InvalidBulkEditMessage::from)
+            .and_then(|parsed| parsed.into_descriptions(separator))
+        {
+            Ok(descriptions) => return Ok(descriptions),
+            Err(err) => err,
+        };
+        if !retry || edited == bulk_message {
+            return Err(err.into());
+        }
+        // Replace the note about the previous error, if any.
+        let content = edited.strip_prefix(&error_note).unwrap_or(&edited);
+        error_note = format!("JJ: Error: {}\n", err.message);
+        if let Some(hint) = &err.hint {
+            error_note.push_str(&format!("JJ: Hint: {hint}\n"));
+        }
+        error_note
+            .push_str("JJ: Fix the error, or exit the editor without making changes to abort.\n");
+        error_note.push_str("JJ:\n");
+        bulk_message = format!("{error_note}{content}");
+    }
+}
+
+/// Edited bulk message which can't be turned into commit descriptions.
+#[derive(Debug)]
+struct InvalidBulkEditMessage {
+    message: String,
+    hint: Option<String>,
+}
+
+impl From<ParseBulkEditMessageError> for InvalidBulkEditMessage {
+    fn from(err: ParseBulkEditMessageError) -> Self {
+        InvalidBulkEditMessage {
+            message: err.to_string(),
+            hint: None,
+        }
+    }
+}
 
-    Ok(parse_bulk_edit_message(&bulk_message, &commits_map)?)
+impl From<InvalidBulkEditMessage> for CommandError {
+    fn from(err: InvalidBulkEditMessage) -> Self {
+        match err.hint {
+            Some(hint) => user_error_with_hint(err.message, hint),
+            None => user_error(err.message),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -229,16 +361,61 @@ pub struct ParsedBulkEditMessage<T> {
     pub unexpected: Vec<String>,
 }
 
+impl<T: Eq + std::hash::Hash> ParsedBulkEditMessage<T> {
+    /// Returns the descriptions if each commit was described exactly once.
+    fn into_descriptions(
+        self,
+        separator: &str,
+    ) -> Result<HashMap<T, String>, InvalidBulkEditMessage> {
+        if !self.missing.is_empty() {
+            return Err(InvalidBulkEditMessage {
+                message: format!(
+                    "The description for the following commits were not found in the edited \
+                     message: {}",
+                    self.missing.join(", ")
+                ),
+                hint: Some(format!(
+                    "Each description must follow a `{separator} <commit id>` line."
+                )),
+            });
+        }
+        if !self.duplicates.is_empty() {
+            return Err(InvalidBulkEditMessage {
+                message: format!(
+                    "The following commits were found in the edited message multiple times: {}",
+                    self.duplicates.join(", ")
+                ),
+                hint: None,
+            });
+        }
+        if !self.unexpected.is_empty() {
+            return Err(InvalidBulkEditMessage {
+                message: format!(
+                    "The following commits were not being edited, but were found in the edited \
+                     message: {}",
+                    self.unexpected.join(", ")
+                ),
+                hint: Some(format!(
+                    "Remove the `{separator}` lines for the commits not being edited."
+                )),
+            });
+        }
+        Ok(self.descriptions)
+    }
+}
+
 #[derive(Debug, Error, PartialEq)]
 pub enum ParseBulkEditMessageError {
     #[error(r#"Found the following line without a commit header: "{0}""#)]
     LineWithoutCommitHeader(String),
 }
 
-/// Parse the bulk message of edited commit descriptions.
+/// Parse the bulk message of edited commit descriptions. Each description
+/// starts with a `separator` line followed by the commit id.
 fn parse_bulk_edit_message<T>(
     message: &str,
     commit_ids_map: &IndexMap<String, &T>,
+    separator: &str,
 ) -> Result<ParsedBulkEditMessage<T>, ParseBulkEditMessageError>
 where
     T: Eq + std::hash::Hash + Clone,
@@ -249,7 +426,10 @@ where
 
     let mut messages: Vec<(&str, Vec<&str>)> = vec![];
     for line in message.lines() {
-        if let Some(commit_id_prefix) = line.strip_prefix("JJ: describe ") {
+        let header = line
+            .strip_prefix(separator)
+            .and_then(|rest| rest.strip_prefix(' '));
+        if let Some(commit_id_prefix) = header {
             let commit_id_prefix =
                 commit_id_prefix.trim_end_matches(|c: char| c.is_ascii_whitespace() || c == '-');
             messages.push((commit_id_prefix, vec![]));
@@ -337,6 +517,31 @@ pub fn join_message_paragraphs(paragraphs: &[String]) -> String {
         .join("\n")
 }
 
+/// Parses a `KEY=VALUE` (or `KEY: VALUE`) trailer argument.
+pub fn parse_trailer(trailer: &str) -> Result<Trailer, &'static str> {
+    let (key, value) = trailer
+        .split_once(['=', ':'])
+        .ok_or("Trailer must be in the form KEY=VALUE")?;
+    let key = key.trim();
+    if !trailer::is_trailer_key(key) {
+        return Err("Trailer key must consist of alphanumeric characters and '-'");
+    }
+    Ok(Trailer::new(key, value.trim()))
+}
+
+/// Appends `trailers` to the trailer block at the end of the `description`.
+///
+/// New trailers are added to the existing block, or to a new paragraph if
+/// there's no such block. Trailers already present in the block are not
+/// duplicated.
+pub fn add_trailers(description: &str, trailers: &[Trailer]) -> String {
+    let edits = trailers
+        .iter()
+        .map(|trailer| TrailerEdit::Add(trailer.clone()))
+        .collect_vec();
+    trailer::apply_trailer_edits(description, &edits, TrailerDedup::Exact)
+}
+
 /// Renders commit description template, which will be edited by user.
 pub fn description_template(
     ui: &Ui,
@@ -370,9 +575,13 @@ pub fn description_template(
 mod tests {
     use indexmap::indexmap;
     use indoc::indoc;
+    use itertools::Itertools as _;
     use maplit::hashmap;
 
+    use super::add_trailers;
     use super::parse_bulk_edit_message;
+    use super::parse_trailer;
+    use super::Trailer;
     use crate::description_util::ParseBulkEditMessageError;
 
     #[test]
@@ -393,6 +602,7 @@ mod tests {
                 "2".to_string() => &2,
                 "3".to_string() => &3,
             },
+            "JJ: describe",
         )
         .unwrap();
         assert_eq!(
@@ -419,6 +629,7 @@ mod tests {
                 "1".to_string() => &1,
                 "2".to_string() => &2,
             },
+            "JJ: describe",
         )
         .unwrap();
         assert_eq!(
@@ -445,6 +656,7 @@ mod tests {
             &indexmap! {
                 "1".to_string() => &1,
             },
+            "JJ: describe",
         )
         .unwrap();
         assert_eq!(
@@ -471,6 +683,7 @@ mod tests {
             &indexmap! {
                 "1".to_string() => &1,
             },
+            "JJ: describe",
         )
         .unwrap();
         assert_eq!(
@@ -484,6 +697,35 @@ mod tests {
         assert_eq!(result.unexpected, vec!["3".to_string()]);
     }
 
+    #[test]
+    fn test_parse_bulk_edit_message_with_custom_separator() {
+        let result = parse_bulk_edit_message(
+            indoc! {"
+                JJ: === 1 -------
+                Description 1
+
+                JJ: describe 2 -------
+                Description 2
+            "},
+            &indexmap! {
+                "1".to_string() => &1,
+                "2".to_string() => &2,
+            },
+            "JJ: ===",
+        )
+        .unwrap();
+        // The default separator is just a comment line
+        assert_eq!(
+            result.descriptions,
+            hashmap! {
+                1 => "Description 1\n\nDescription 2\n".to_string(),
+            }
+        );
+        assert_eq!(result.missing, vec!["2".to_string()]);
+        assert!(result.duplicates.is_empty());
+        assert!(result.unexpected.is_empty());
+    }
+
     #[test]
     fn test_parse_bulk_edit_message_with_no_header() {
         let result = parse_bulk_edit_message(
@@ -493,6 +735,7 @@ mod tests {
             &indexmap! {
                 "1".to_string() => &1,
             },
+            "JJ: describe",
         );
         assert_eq!(
             result.unwrap_err(),
@@ -513,6 +756,7 @@ mod tests {
             &indexmap! {
                 "1".to_string() => &1,
             },
+            "JJ: describe",
         )
         .unwrap();
         assert_eq!(
@@ -525,4 +769,85 @@ mod tests {
         assert!(result.duplicates.is_empty());
         assert!(result.unexpected.is_empty());
     }
+
+    #[test]
+    fn test_parse_trailer() {
+        assert_eq!(
+            parse_trailer("Signed-off-by=Alice <alice@example.com>"),
+            Ok(Trailer::new("Signed-off-by", "Alice <alice@example.com>"))
+        );
+        assert_eq!(
+            parse_trailer("Fixes: #123"),
+            Ok(Trailer::new("Fixes", "#123"))
+        );
+        assert!(parse_trailer("no separator").is_err());
+        assert!(parse_trailer("bad key=value").is_err());
+        assert!(parse_trailer("=value").is_err());
+    }
+
+    #[test]
+    fn test_add_trailers() {
+        let trailers = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|&(key, value)| Trailer::new(key, value))
+                .collect_vec()
+        };
+        let signed_off = trailers(&[("Signed-off-by", "Alice <alice@example.com>")]);
+
+        // Empty description
+        assert_eq!(
+            add_trailers("", &signed_off),
+            "\nSigned-off-by: Alice <alice@example.com>\n"
+        );
+        // Subject line isn't a trailer block even if it looks like a trailer
+        assert_eq!(
+            add_trailers("fix: subject\n", &signed_off),
+            indoc! {"
+                fix: subject
+
+                Signed-off-by: Alice <alice@example.com>
+            "}
+        );
+        // New trailer block after body
+        assert_eq!(
+            add_trailers("subject\n\nbody\n", &signed_off),
+            indoc! {"
+                subject
+
+                body
+
+                Signed-off-by: Alice <alice@example.com>
+            "}
+        );
+        // Existing trailer block is extended
+        assert_eq!(
+            add_trailers(
+                "subject\n\nReviewed-by: Bob <bob@example.com>\n  continued\n",
+                &signed_off
+            ),
+            indoc! {"
+                subject
+
+                Reviewed-by: Bob <bob@example.com>
+                  continued
+                Signed-off-by: Alice <alice@example.com>
+            "}
+        );
+        // Duplicated trailers are ignored
+        assert_eq!(
+            add_trailers(
+                "subject\n\nSigned-off-by: Alice <alice@example.com>\n",
+                &signed_off
+            ),
+            "subject\n\nSigned-off-by: Alice <alice@example.com>\n"
+        );
+        assert_eq!(
+            add_trailers(
+                "subject",
+                &trailers(&[("Fixes", "#1"), ("Fixes", "#2"), ("Fixes", "#1")])
+            ),
+            "subject\n\nFixes: #1\nFixes: #2\n"
+        );
+    }
 }
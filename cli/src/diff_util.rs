@@ -15,6 +15,7 @@
 use std::borrow::Borrow;
 use std::cmp::max;
 use std::io;
+use std::io::Read as _;
 use std::iter;
 use std::mem;
 use std::ops::Range;
@@ -32,6 +33,7 @@ use jj_lib::backend::BackendError;
 use jj_lib::backend::BackendResult;
 use jj_lib::backend::CommitId;
 use jj_lib::backend::CopyRecord;
+use jj_lib::backend::FileId;
 use jj_lib::backend::TreeValue;
 use jj_lib::commit::Commit;
 use jj_lib::config::ConfigGetError;
@@ -68,6 +70,7 @@ use jj_lib::repo_path::InvalidRepoPathError;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathUiConverter;
 use jj_lib::rewrite::rebase_to_dest_parent;
+use jj_lib::settings::HumanByteSize;
 use jj_lib::settings::UserSettings;
 use jj_lib::store::Store;
 use pollster::FutureExt as _;
@@ -133,6 +136,9 @@ pub struct DiffFormatArgs {
     /// Ignore changes in amount of whitespace when comparing lines.
     #[arg(long, conflicts_with = "ignore_all_space")] // short = 'b'
     ignore_space_change: bool,
+    /// Diff files as text even if they are larger than `diff.max-file-size`
+    #[arg(long)]
+    force_text_diff: bool,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -144,7 +150,7 @@ pub enum DiffFormat {
     NameOnly,
     Git(Box<UnifiedDiffOptions>),
     ColorWords(Box<ColorWordsDiffOptions>),
-    Tool(Box<ExternalMergeTool>),
+    Tool(Box<ExternalDiffOptions>),
 }
 
 impl DiffFormat {
@@ -200,7 +206,7 @@ fn diff_formats_from_args(
         formats.push(DiffFormat::Summary);
     }
     if args.stat {
-        let mut options = DiffStatOptions::default();
+        let mut options = DiffStatOptions::from_settings(settings)?;
         options.merge_args(args);
         formats.push(DiffFormat::Stat(Box::new(options)));
     }
@@ -224,7 +230,9 @@ fn diff_formats_from_args(
     if let Some(name) = &args.tool {
         let tool = merge_tools::get_external_tool_config(settings, name)?
             .unwrap_or_else(|| ExternalMergeTool::with_program(name));
-        formats.push(DiffFormat::Tool(Box::new(tool)));
+        let mut options = ExternalDiffOptions::from_settings(settings, tool)?;
+        options.merge_args(args);
+        formats.push(DiffFormat::Tool(Box::new(options)));
     }
     Ok(formats)
 }
@@ -233,20 +241,22 @@ fn default_diff_format(
     settings: &UserSettings,
     args: &DiffFormatArgs,
 ) -> Result<DiffFormat, ConfigGetError> {
-    if let Some(args) = settings.get("ui.diff.tool").optional()? {
+    if let Some(tool_args) = settings.get("ui.diff.tool").optional()? {
         // External "tool" overrides the internal "format" option.
-        let tool = if let CommandNameAndArgs::String(name) = &args {
+        let tool = if let CommandNameAndArgs::String(name) = &tool_args {
             merge_tools::get_external_tool_config(settings, name)?
         } else {
             None
         }
-        .unwrap_or_else(|| ExternalMergeTool::with_diff_args(&args));
-        return Ok(DiffFormat::Tool(Box::new(tool)));
+        .unwrap_or_else(|| ExternalMergeTool::with_diff_args(&tool_args));
+        let mut options = ExternalDiffOptions::from_settings(settings, tool)?;
+        options.merge_args(args);
+        return Ok(DiffFormat::Tool(Box::new(options)));
     }
     match settings.get_string("ui.diff.format")?.as_ref() {
         "summary" => Ok(DiffFormat::Summary),
         "stat" => {
-            let mut options = DiffStatOptions::default();
+            let mut options = DiffStatOptions::from_settings(settings)?;
             options.merge_args(args);
             Ok(DiffFormat::Stat(Box::new(options)))
         }
@@ -396,7 +406,8 @@ impl<'a> DiffRenderer<'a> {
                         self.conflict_marker_style,
                     )?;
                 }
-                DiffFormat::Tool(tool) => {
+                DiffFormat::Tool(options) => {
+                    let tool = &options.tool;
                     match tool.diff_invocation_mode {
                         DiffToolMode::FileByFile => {
                             let tree_diff =
@@ -407,7 +418,7 @@ impl<'a> DiffRenderer<'a> {
                                 store,
                                 tree_diff,
                                 path_converter,
-                                tool,
+                                options,
                                 self.conflict_marker_style,
                             )
                         }
@@ -569,6 +580,8 @@ pub struct ColorWordsDiffOptions {
     pub line_diff: LineDiffOptions,
     /// Maximum number of removed/added word alternation to inline.
     pub max_inline_alternation: Option<usize>,
+    /// Which files are loaded and diffed as text.
+    pub file_content: FileContentOptions,
 }
 
 impl ColorWordsDiffOptions {
@@ -589,6 +602,7 @@ impl ColorWordsDiffOptions {
             context: settings.get("diff.color-words.context")?,
             line_diff: LineDiffOptions::default(),
             max_inline_alternation,
+            file_content: FileContentOptions::from_settings(settings)?,
         })
     }
 
@@ -597,6 +611,7 @@ impl ColorWordsDiffOptions {
             self.context = context;
         }
         self.line_diff.merge_args(args);
+        self.file_content.merge_args(args);
     }
 }
 
@@ -1041,46 +1056,142 @@ fn split_diff_hunks_by_matching_newline<'a, 'b>(
     })
 }
 
+/// Options to decide which files are loaded and diffed as text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileContentOptions {
+    /// Files larger than this many bytes are summarized instead of diffed.
+    pub max_file_size: Option<u64>,
+    /// Whether binary files are detected and summarized instead of diffed.
+    pub detect_binary: bool,
+}
+
+impl FileContentOptions {
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        let HumanByteSize(max_file_size) =
+            settings.get_value_with("diff.max-file-size", TryInto::try_into)?;
+        Ok(FileContentOptions {
+            max_file_size: (max_file_size != 0).then_some(max_file_size),
+            detect_binary: true,
+        })
+    }
+
+    fn merge_args(&mut self, args: &DiffFormatArgs) {
+        if args.force_text_diff {
+            self.max_file_size = None;
+        }
+    }
+}
+
 struct FileContent<T> {
     /// false if this file is likely text; true if it is likely binary.
     is_binary: bool,
+    /// Set if the file is binary or too large, in which case `contents` is
+    /// left empty.
+    omitted: Option<OmittedFileContent>,
     contents: T,
 }
 
+/// File which contents weren't loaded for diffing.
+struct OmittedFileContent {
+    /// Content-addressed id, which can be used to compare the contents.
+    id: FileId,
+    size: u64,
+}
+
+impl<T: PartialEq> FileContent<T> {
+    fn has_same_contents(&self, other: &Self) -> bool {
+        match (&self.omitted, &other.omitted) {
+            (Some(omitted1), Some(omitted2)) => omitted1.id == omitted2.id,
+            (None, None) => self.contents == other.contents,
+            _ => false,
+        }
+    }
+}
+
+impl FileContent<BString> {
+    fn size(&self) -> u64 {
+        self.omitted
+            .as_ref()
+            .map_or(self.contents.len() as u64, |omitted| omitted.size)
+    }
+}
+
 impl FileContent<Merge<BString>> {
     fn is_empty(&self) -> bool {
-        self.contents.as_resolved().is_some_and(|c| c.is_empty())
+        self.omitted.is_none() && self.contents.as_resolved().is_some_and(|c| c.is_empty())
+    }
+
+    fn size(&self) -> u64 {
+        self.omitted.as_ref().map_or_else(
+            || self.contents.iter().map(|c| c.len() as u64).sum(),
+            |omitted| omitted.size,
+        )
     }
 }
 
 fn file_content_for_diff<T>(
     path: &RepoPath,
     file: &mut MaterializedFileValue,
+    options: &FileContentOptions,
     map_resolved: impl FnOnce(BString) -> T,
 ) -> BackendResult<FileContent<T>> {
     // If this is a binary file, don't show the full contents.
     // Determine whether it's binary by whether the first 8k bytes contain a null
     // character; this is the same heuristic used by git as of writing: https://github.com/git/git/blob/eea0e59ffbed6e33d171ace5be13cde9faa41639/xdiff-interface.c#L192-L198
-    const PEEK_SIZE: usize = 8000;
-    // TODO: currently we look at the whole file, even though for binary files we
-    // only need to know the file size. To change that we'd have to extend all
-    // the data backends to support getting the length.
-    let contents = BString::new(file.read_all(path)?);
-    let start = &contents[..PEEK_SIZE.min(contents.len())];
+    const PEEK_SIZE: u64 = 8000;
+    let to_read_error = |err: io::Error| BackendError::ReadFile {
+        path: path.to_owned(),
+        id: file.id.clone(),
+        source: err.into(),
+    };
+    // Look at the leading bytes first so binary files can be detected without
+    // loading the whole contents.
+    let mut contents = Vec::new();
+    (&mut file.reader)
+        .take(PEEK_SIZE)
+        .read_to_end(&mut contents)
+        .map_err(to_read_error)?;
+    let is_binary = options.detect_binary && contents.contains(&b'\0');
+    let max_size = options.max_file_size.unwrap_or(u64::MAX);
+    if !is_binary {
+        // Read one more byte than the limit to tell whether the file is large.
+        let limit = max_size
+            .saturating_sub(contents.len() as u64)
+            .saturating_add(1);
+        (&mut file.reader)
+            .take(limit)
+            .read_to_end(&mut contents)
+            .map_err(to_read_error)?;
+    }
+    if is_binary || contents.len() as u64 > max_size {
+        // The contents won't be shown, so just count the remaining bytes.
+        let remaining_size = io::copy(&mut file.reader, &mut io::sink()).map_err(to_read_error)?;
+        return Ok(FileContent {
+            is_binary,
+            omitted: Some(OmittedFileContent {
+                id: file.id.clone(),
+                size: contents.len() as u64 + remaining_size,
+            }),
+            contents: map_resolved(BString::default()),
+        });
+    }
     Ok(FileContent {
-        is_binary: start.contains(&b'\0'),
-        contents: map_resolved(contents),
+        is_binary,
+        omitted: None,
+        contents: map_resolved(contents.into()),
     })
 }
 
 fn diff_content(
     path: &RepoPath,
     value: MaterializedTreeValue,
+    options: &FileContentOptions,
     conflict_marker_style: ConflictMarkerStyle,
 ) -> BackendResult<FileContent<BString>> {
     diff_content_with(
         path,
         value,
+        options,
         |content| content,
         |contents| materialize_merge_result_to_bytes(&contents, conflict_marker_style),
     )
@@ -1089,35 +1200,41 @@ fn diff_content(
 fn diff_content_as_merge(
     path: &RepoPath,
     value: MaterializedTreeValue,
+    options: &FileContentOptions,
 ) -> BackendResult<FileContent<Merge<BString>>> {
-    diff_content_with(path, value, Merge::resolved, |contents| contents)
+    diff_content_with(path, value, options, Merge::resolved, |contents| contents)
 }
 
 fn diff_content_with<T>(
     path: &RepoPath,
     value: MaterializedTreeValue,
+    options: &FileContentOptions,
     map_resolved: impl FnOnce(BString) -> T,
     map_conflict: impl FnOnce(Merge<BString>) -> T,
 ) -> BackendResult<FileContent<T>> {
     match value {
         MaterializedTreeValue::Absent => Ok(FileContent {
             is_binary: false,
+            omitted: None,
             contents: map_resolved(BString::default()),
         }),
         MaterializedTreeValue::AccessDenied(err) => Ok(FileContent {
             is_binary: false,
+            omitted: None,
             contents: map_resolved(format!("Access denied: {err}").into()),
         }),
         MaterializedTreeValue::File(mut file) => {
-            file_content_for_diff(path, &mut file, map_resolved)
+            file_content_for_diff(path, &mut file, options, map_resolved)
         }
         MaterializedTreeValue::Symlink { id: _, target } => Ok(FileContent {
             // Unix file paths can't contain null bytes.
             is_binary: false,
+            omitted: None,
             contents: map_resolved(target.into()),
         }),
         MaterializedTreeValue::GitSubmodule(id) => Ok(FileContent {
             is_binary: false,
+            omitted: None,
             contents: map_resolved(format!("Git submodule checked out at {id}").into()),
         }),
         // TODO: are we sure this is never binary?
@@ -1127,10 +1244,12 @@ fn diff_content_with<T>(
             executable: _,
         } => Ok(FileContent {
             is_binary: false,
+            omitted: None,
             contents: map_conflict(contents),
         }),
         MaterializedTreeValue::OtherConflict { id } => Ok(FileContent {
             is_binary: false,
+            omitted: None,
             contents: map_resolved(id.describe().into()),
         }),
         MaterializedTreeValue::Tree(id) => {
@@ -1203,11 +1322,15 @@ pub fn show_color_words_diff(
                     formatter.labeled("header"),
                     "Added {description} {right_ui_path}:"
                 )?;
-                let right_content = diff_content_as_merge(right_path, right_value)?;
+                let right_content =
+                    diff_content_as_merge(right_path, right_value, &options.file_content)?;
                 if right_content.is_empty() {
                     writeln!(formatter.labeled("empty"), "    (empty)")?;
                 } else if right_content.is_binary {
                     writeln!(formatter.labeled("binary"), "    (binary)")?;
+                } else if right_content.omitted.is_some() {
+                    let size = right_content.size();
+                    writeln!(formatter.labeled("large"), "    (file added, {size} bytes)")?;
                 } else {
                     show_color_words_diff_hunks(
                         formatter,
@@ -1261,8 +1384,10 @@ pub fn show_color_words_diff(
                         )
                     }
                 };
-                let left_content = diff_content_as_merge(left_path, left_value)?;
-                let right_content = diff_content_as_merge(right_path, right_value)?;
+                let left_content =
+                    diff_content_as_merge(left_path, left_value, &options.file_content)?;
+                let right_content =
+                    diff_content_as_merge(right_path, right_value, &options.file_content)?;
                 if left_path == right_path {
                     writeln!(
                         formatter.labeled("header"),
@@ -1276,13 +1401,22 @@ pub fn show_color_words_diff(
                 }
                 if left_content.is_binary || right_content.is_binary {
                     writeln!(formatter.labeled("binary"), "    (binary)")?;
-                } else if left_content.contents != right_content.contents {
-                    show_color_words_diff_hunks(
-                        formatter,
-                        [&left_content.contents, &right_content.contents],
-                        options,
-                        conflict_marker_style,
-                    )?;
+                } else if !left_content.has_same_contents(&right_content) {
+                    if left_content.omitted.is_some() || right_content.omitted.is_some() {
+                        let left_size = left_content.size();
+                        let right_size = right_content.size();
+                        writeln!(
+                            formatter.labeled("large"),
+                            "    (file changed, {left_size} → {right_size} bytes)"
+                        )?;
+                    } else {
+                        show_color_words_diff_hunks(
+                            formatter,
+                            [&left_content.contents, &right_content.contents],
+                            options,
+                            conflict_marker_style,
+                        )?;
+                    }
                 }
             } else {
                 let description = basic_diff_file_type(&left_value);
@@ -1290,11 +1424,18 @@ pub fn show_color_words_diff(
                     formatter.labeled("header"),
                     "Removed {description} {right_ui_path}:"
                 )?;
-                let left_content = diff_content_as_merge(left_path, left_value)?;
+                let left_content =
+                    diff_content_as_merge(left_path, left_value, &options.file_content)?;
                 if left_content.is_empty() {
                     writeln!(formatter.labeled("empty"), "    (empty)")?;
                 } else if left_content.is_binary {
                     writeln!(formatter.labeled("binary"), "    (binary)")?;
+                } else if left_content.omitted.is_some() {
+                    let size = left_content.size();
+                    writeln!(
+                        formatter.labeled("large"),
+                        "    (file removed, {size} bytes)"
+                    )?;
                 } else {
                     show_color_words_diff_hunks(
                         formatter,
@@ -1310,22 +1451,49 @@ pub fn show_color_words_diff(
     .block_on()
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExternalDiffOptions {
+    /// The diff tool to invoke.
+    pub tool: ExternalMergeTool,
+    /// Which files are passed to the file-by-file diff tool.
+    pub file_content: FileContentOptions,
+}
+
+impl ExternalDiffOptions {
+    pub fn from_settings(
+        settings: &UserSettings,
+        tool: ExternalMergeTool,
+    ) -> Result<Self, ConfigGetError> {
+        Ok(ExternalDiffOptions {
+            tool,
+            file_content: FileContentOptions {
+                // Binary files are left to the tool.
+                detect_binary: false,
+                ..FileContentOptions::from_settings(settings)?
+            },
+        })
+    }
+
+    fn merge_args(&mut self, args: &DiffFormatArgs) {
+        self.file_content.merge_args(args);
+    }
+}
+
 pub fn show_file_by_file_diff(
     ui: &Ui,
     formatter: &mut dyn Formatter,
     store: &Store,
     tree_diff: BoxStream<CopiesTreeDiffEntry>,
     path_converter: &RepoPathUiConverter,
-    tool: &ExternalMergeTool,
+    options: &ExternalDiffOptions,
     conflict_marker_style: ConflictMarkerStyle,
 ) -> Result<(), DiffRenderError> {
     let create_file = |path: &RepoPath,
                        wc_dir: &Path,
-                       value: MaterializedTreeValue|
+                       content: FileContent<BString>|
      -> Result<PathBuf, DiffRenderError> {
         let fs_path = path.to_fs_path(wc_dir)?;
         std::fs::create_dir_all(fs_path.parent().unwrap())?;
-        let content = diff_content(path, value, conflict_marker_style)?;
         std::fs::write(&fs_path, content.contents)?;
         Ok(fs_path)
     };
@@ -1361,14 +1529,43 @@ pub fn show_file_by_file_diff(
                 }
                 _ => {}
             }
-            let left_path = create_file(left_path, &left_wc_dir, left_value)?;
-            let right_path = create_file(right_path, &right_wc_dir, right_value)?;
+            let left_content = diff_content(
+                left_path,
+                left_value,
+                &options.file_content,
+                conflict_marker_style,
+            )?;
+            let right_content = diff_content(
+                right_path,
+                right_value,
+                &options.file_content,
+                conflict_marker_style,
+            )?;
+            if left_content.omitted.is_some() || right_content.omitted.is_some() {
+                let left_size = left_content.size();
+                let right_size = right_content.size();
+                if left_ui_path == right_ui_path {
+                    writeln!(
+                        formatter.labeled("large"),
+                        "Large file {right_ui_path} differs ({left_size} → {right_size} bytes)"
+                    )?;
+                } else {
+                    writeln!(
+                        formatter.labeled("large"),
+                        "Large files {left_ui_path} and {right_ui_path} differ \
+                         ({left_size} → {right_size} bytes)"
+                    )?;
+                }
+                continue;
+            }
+            let left_path = create_file(left_path, &left_wc_dir, left_content)?;
+            let right_path = create_file(right_path, &right_wc_dir, right_content)?;
 
             let mut writer = formatter.raw()?;
             invoke_external_diff(
                 ui,
                 writer.as_mut(),
-                tool,
+                &options.tool,
                 &maplit::hashmap! {
                     "left" => left_path.to_str().expect("temp_dir should be valid utf-8"),
                     "right" => right_path.to_str().expect("temp_dir should be valid utf-8"),
@@ -1391,6 +1588,7 @@ struct GitDiffPart {
 fn git_diff_part(
     path: &RepoPath,
     value: MaterializedTreeValue,
+    options: &FileContentOptions,
     conflict_marker_style: ConflictMarkerStyle,
 ) -> Result<GitDiffPart, DiffRenderError> {
     const DUMMY_HASH: &str = "0000000000";
@@ -1404,6 +1602,7 @@ fn git_diff_part(
                 hash: DUMMY_HASH.to_owned(),
                 content: FileContent {
                     is_binary: false,
+                    omitted: None,
                     contents: BString::default(),
                 },
             });
@@ -1417,7 +1616,7 @@ fn git_diff_part(
         MaterializedTreeValue::File(mut file) => {
             mode = if file.executable { "100755" } else { "100644" };
             hash = file.id.hex();
-            content = file_content_for_diff(path, &mut file, |content| content)?;
+            content = file_content_for_diff(path, &mut file, options, |content| content)?;
         }
         MaterializedTreeValue::Symlink { id, target } => {
             mode = "120000";
@@ -1425,6 +1624,7 @@ fn git_diff_part(
             content = FileContent {
                 // Unix file paths can't contain null bytes.
                 is_binary: false,
+                omitted: None,
                 contents: target.into(),
             };
         }
@@ -1434,6 +1634,7 @@ fn git_diff_part(
             hash = id.hex();
             content = FileContent {
                 is_binary: false,
+                omitted: None,
                 contents: BString::default(),
             };
         }
@@ -1446,6 +1647,7 @@ fn git_diff_part(
             hash = DUMMY_HASH.to_owned();
             content = FileContent {
                 is_binary: false, // TODO: are we sure this is never binary?
+                omitted: None,
                 contents: materialize_merge_result_to_bytes(&contents, conflict_marker_style),
             };
         }
@@ -1454,6 +1656,7 @@ fn git_diff_part(
             hash = DUMMY_HASH.to_owned();
             content = FileContent {
                 is_binary: false,
+                omitted: None,
                 contents: id.describe().into(),
             };
         }
@@ -1475,6 +1678,8 @@ pub struct UnifiedDiffOptions {
     pub context: usize,
     /// How lines are tokenized and compared.
     pub line_diff: LineDiffOptions,
+    /// Which files are loaded and diffed as text.
+    pub file_content: FileContentOptions,
 }
 
 impl UnifiedDiffOptions {
@@ -1482,6 +1687,7 @@ impl UnifiedDiffOptions {
         Ok(UnifiedDiffOptions {
             context: settings.get("diff.git.context")?,
             line_diff: LineDiffOptions::default(),
+            file_content: FileContentOptions::from_settings(settings)?,
         })
     }
 
@@ -1490,6 +1696,7 @@ impl UnifiedDiffOptions {
             self.context = context;
         }
         self.line_diff.merge_args(args);
+        self.file_content.merge_args(args);
     }
 }
 
@@ -1733,8 +1940,18 @@ pub fn show_git_diff(
             let right_path_string = right_path.as_internal_file_string();
             let (left_value, right_value) = values?;
 
-            let left_part = git_diff_part(left_path, left_value, conflict_marker_style)?;
-            let right_part = git_diff_part(right_path, right_value, conflict_marker_style)?;
+            let left_part = git_diff_part(
+                left_path,
+                left_value,
+                &options.file_content,
+                conflict_marker_style,
+            )?;
+            let right_part = git_diff_part(
+                right_path,
+                right_value,
+                &options.file_content,
+                conflict_marker_style,
+            )?;
 
             formatter.with_label("file_header", |formatter| {
                 writeln!(
@@ -1777,7 +1994,7 @@ pub fn show_git_diff(
                 Ok::<(), DiffRenderError>(())
             })?;
 
-            if left_part.content.contents == right_part.content.contents {
+            if left_part.content.has_same_contents(&right_part.content) {
                 continue; // no content hunks
             }
 
@@ -1795,6 +2012,14 @@ pub fn show_git_diff(
                     formatter,
                     "Binary files {left_path} and {right_path} differ"
                 )?;
+            } else if left_part.content.omitted.is_some() || right_part.content.omitted.is_some() {
+                let left_size = left_part.content.size();
+                let right_size = right_part.content.size();
+                writeln!(
+                    formatter,
+                    "Large files {left_path} and {right_path} differ \
+                     ({left_size} → {right_size} bytes)"
+                )?;
             } else {
                 formatter.with_label("file_header", |formatter| {
                     writeln!(formatter, "--- {left_path}")?;
@@ -1855,15 +2080,29 @@ pub fn diff_status_label_and_char(
     }
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct DiffStatOptions {
     /// How lines are tokenized and compared.
     pub line_diff: LineDiffOptions,
+    /// Which files are loaded and diffed as text.
+    pub file_content: FileContentOptions,
 }
 
 impl DiffStatOptions {
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        Ok(DiffStatOptions {
+            line_diff: LineDiffOptions::default(),
+            file_content: FileContentOptions {
+                // Lines in binary files are counted as git does.
+                detect_binary: false,
+                ..FileContentOptions::from_settings(settings)?
+            },
+        })
+    }
+
     fn merge_args(&mut self, args: &DiffFormatArgs) {
         self.line_diff.merge_args(args);
+        self.file_content.merge_args(args);
     }
 }
 
@@ -1883,13 +2122,32 @@ impl DiffStats {
         let entries = materialized_diff_stream(store, tree_diff)
             .map(|MaterializedTreeDiffEntry { path, values }| {
                 let (left, right) = values?;
-                let left_content = diff_content(path.source(), left, conflict_marker_style)?;
-                let right_content = diff_content(path.target(), right, conflict_marker_style)?;
-                let stat = get_diff_stat_entry(
-                    path,
-                    [&left_content.contents, &right_content.contents].map(BStr::new),
-                    options,
-                );
+                let left_content = diff_content(
+                    path.source(),
+                    left,
+                    &options.file_content,
+                    conflict_marker_style,
+                )?;
+                let right_content = diff_content(
+                    path.target(),
+                    right,
+                    &options.file_content,
+                    conflict_marker_style,
+                )?;
+                let stat = if left_content.omitted.is_some() || right_content.omitted.is_some() {
+                    // Lines of large files aren't counted.
+                    DiffStatEntry {
+                        path,
+                        added: 0,
+                        removed: 0,
+                    }
+                } else {
+                    get_diff_stat_entry(
+                        path,
+                        [&left_content.contents, &right_content.contents].map(BStr::new),
+                        options,
+                    )
+                };
                 BackendResult::Ok(stat)
             })
             .try_collect()
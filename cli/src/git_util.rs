@@ -326,6 +326,14 @@ pub fn print_git_import_stats(
         )?;
     }
 
+    if !stats.rewritten_commits.is_empty() {
+        writeln!(
+            formatter,
+            "Replaced {} commits with imported commits of the same change.",
+            stats.rewritten_commits.len()
+        )?;
+    }
+
     if !stats.failed_ref_names.is_empty() {
         writeln!(ui.warning_default(), "Failed to import some Git refs:")?;
         let mut formatter = ui.stderr_formatter();
@@ -303,6 +303,7 @@ fn builtin_operation_methods() -> OperationTemplateBuildMethodFnMap<Operation> {
                 op.metadata()
                     .tags
                     .iter()
+                    .sorted()
                     .map(|(key, value)| format!("{key}: {value}"))
                     .join("\n")
             });
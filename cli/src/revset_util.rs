@@ -14,6 +14,8 @@
 
 //! Utility for parsing and evaluating user-provided revset expressions.
 
+use std::cell::OnceCell;
+use std::collections::HashMap;
 use std::io;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -22,6 +24,7 @@ use itertools::Itertools as _;
 use jj_lib::backend::CommitId;
 use jj_lib::commit::Commit;
 use jj_lib::config::ConfigGetError;
+use jj_lib::config::ConfigGetResultExt as _;
 use jj_lib::config::ConfigNamePathBuf;
 use jj_lib::config::ConfigSource;
 use jj_lib::config::StackedConfig;
@@ -67,6 +70,7 @@ pub struct RevsetExpressionEvaluator<'repo> {
     extensions: Arc<RevsetExtensions>,
     id_prefix_context: &'repo IdPrefixContext,
     expression: Rc<UserRevsetExpression>,
+    resolved: OnceCell<Rc<ResolvedRevsetExpression>>,
 }
 
 impl<'repo> RevsetExpressionEvaluator<'repo> {
@@ -81,6 +85,7 @@ impl<'repo> RevsetExpressionEvaluator<'repo> {
             extensions,
             id_prefix_context,
             expression,
+            resolved: OnceCell::new(),
         }
     }
 
@@ -92,17 +97,38 @@ impl<'repo> RevsetExpressionEvaluator<'repo> {
     /// Intersects the underlying expression with the `other` expression.
     pub fn intersect_with(&mut self, other: &Rc<UserRevsetExpression>) {
         self.expression = self.expression.intersection(other);
+        self.resolved.take();
     }
 
     /// Resolves user symbols in the expression, returns new expression.
     pub fn resolve(&self) -> Result<Rc<ResolvedRevsetExpression>, RevsetResolutionError> {
+        if let Some(expression) = self.resolved.get() {
+            return Ok(expression.clone());
+        }
+        let (expression, _) = self.resolve_with_symbol_warnings()?;
+        Ok(expression)
+    }
+
+    /// Resolves user symbols in the expression, returns new expression and
+    /// warnings recorded by the symbol resolver, keyed by symbol.
+    ///
+    /// The resolved expression is cached, so the symbols aren't resolved
+    /// again when the expression is evaluated.
+    pub fn resolve_with_symbol_warnings(
+        &self,
+    ) -> Result<(Rc<ResolvedRevsetExpression>, HashMap<String, String>), RevsetResolutionError>
+    {
         let symbol_resolver = default_symbol_resolver(
             self.repo,
             self.extensions.symbol_resolvers(),
             self.id_prefix_context,
-        );
-        self.expression
-            .resolve_user_expression(self.repo, &symbol_resolver)
+        )
+        .map_err(|err| RevsetResolutionError::Other(err.into()))?;
+        let expression = self
+            .expression
+            .resolve_user_expression(self.repo, &symbol_resolver)?;
+        let expression = self.resolved.get_or_init(|| expression).clone();
+        Ok((expression, symbol_resolver.take_symbol_warnings()))
     }
 
     /// Evaluates the expression.
@@ -209,8 +235,14 @@ pub fn default_symbol_resolver<'a>(
     repo: &'a dyn Repo,
     extensions: &[impl AsRef<dyn SymbolResolverExtension>],
     id_prefix_context: &'a IdPrefixContext,
-) -> DefaultSymbolResolver<'a> {
-    DefaultSymbolResolver::new(repo, extensions).with_id_prefix_context(id_prefix_context)
+) -> Result<DefaultSymbolResolver<'a>, ConfigGetError> {
+    let settings = repo.base_repo().settings();
+    let suggestion_distance = settings.get("ui.suggestion-distance").optional()?;
+    let case_insensitive_refs = settings.get_bool("revsets.case-insensitive-refs")?;
+    Ok(DefaultSymbolResolver::new(repo, extensions)
+        .with_id_prefix_context(id_prefix_context)
+        .with_suggestion_distance(suggestion_distance)
+        .with_case_insensitive_refs(case_insensitive_refs))
 }
 
 /// Parses user-configured expression defining the heads of the immutable set.
@@ -64,6 +64,7 @@ use crate::templater::TemplateRenderer;
 use crate::templater::TimestampRange;
 use crate::text_util;
 use crate::time_util;
+use crate::time_util::Clock;
 
 /// Callbacks to build language-specific evaluation objects from AST nodes.
 pub trait TemplateLanguage<'a> {
@@ -1127,13 +1128,27 @@ fn builtin_timestamp_methods<'a, L: TemplateLanguage<'a> + ?Sized>(
     let mut map = TemplateBuildMethodFnMap::<L, Timestamp>::new();
     map.insert(
         "ago",
-        |_language, _diagnostics, _build_ctx, self_property, function| {
-            function.expect_no_arguments()?;
-            let now = Timestamp::now();
+        |language, diagnostics, build_ctx, self_property, function| {
+            let ([], [allow_future_node]) = function.expect_arguments()?;
+            let allow_future_property = allow_future_node
+                .map(|node| expect_boolean_expression(language, diagnostics, build_ctx, node))
+                .transpose()?;
+            let now = Clock::from_settings(language.settings()).now();
             let format = timeago::Formatter::new();
-            let out_property = self_property.and_then(move |timestamp| {
-                Ok(time_util::format_duration(&timestamp, &now, &format)?)
-            });
+            // Timestamps slightly in the future are common with skewed clocks.
+            let mut future_format = timeago::Formatter::new();
+            future_format.ago("");
+            let out_property = (self_property, allow_future_property).and_then(
+                move |(timestamp, allow_future)| {
+                    let future_format = allow_future.unwrap_or(false).then_some(&future_format);
+                    Ok(time_util::format_signed_duration(
+                        &timestamp,
+                        &now,
+                        &format,
+                        future_format,
+                    )?)
+                },
+            );
             Ok(L::wrap_string(out_property))
         },
     );
@@ -1184,9 +1199,9 @@ fn builtin_timestamp_methods<'a, L: TemplateLanguage<'a> + ?Sized>(
     );
     map.insert(
         "after",
-        |_language, _diagnostics, _build_ctx, self_property, function| {
+        |language, _diagnostics, _build_ctx, self_property, function| {
             let [date_pattern_node] = function.expect_exact_arguments()?;
-            let now = chrono::Local::now();
+            let now = Clock::from_settings(language.settings()).now_local();
             let date_pattern = template_parser::expect_string_literal_with(
                 date_pattern_node,
                 |date_pattern, span| {
@@ -1485,6 +1500,28 @@ fn builtin_functions<'a, L: TemplateLanguage<'a> + ?Sized>() -> TemplateBuildFun
         });
         Ok(L::wrap_template(Box::new(template)))
     });
+    map.insert(
+        "hanging_indent",
+        |language, diagnostics, build_ctx, function| {
+            let [prefix_node, first_prefix_node, content_node] =
+                function.expect_exact_arguments()?;
+            let prefix = expect_template_expression(language, diagnostics, build_ctx, prefix_node)?;
+            let first_prefix =
+                expect_template_expression(language, diagnostics, build_ctx, first_prefix_node)?;
+            let content =
+                expect_template_expression(language, diagnostics, build_ctx, content_node)?;
+            let template = ReformatTemplate::new(content, move |formatter, recorded| {
+                let rewrap = formatter.rewrap_fn();
+                text_util::write_hanging_indented(
+                    formatter.as_mut(),
+                    recorded,
+                    |formatter| first_prefix.format(&mut rewrap(formatter)),
+                    |formatter| prefix.format(&mut rewrap(formatter)),
+                )
+            });
+            Ok(L::wrap_template(Box::new(template)))
+        },
+    );
     map.insert("pad_start", |language, diagnostics, build_ctx, function| {
         let ([width_node, content_node], [fill_char_node]) =
             function.expect_named_arguments(&["", "", "fill_char"])?;
@@ -2916,6 +2953,17 @@ mod tests {
           |
           = Invalid time format
         "#);
+
+        // Invalid time zone offset is displayed in UTC with a warning
+        env.add_keyword("t_bad_tz", || {
+            L::wrap_timestamp(Literal(new_timestamp(0, 100000)))
+        });
+        insta::assert_snapshot!(
+            env.render_ok("t_bad_tz"),
+            @"1970-01-01 00:00:00.000 +00:00 <Warning: Out-of-range time zone offset: 100000 minutes>");
+        insta::assert_snapshot!(
+            env.render_ok("t_bad_tz.utc()"),
+            @"1970-01-01 00:00:00.000 +00:00");
     }
 
     #[test]
@@ -3055,6 +3103,57 @@ mod tests {
         ");
     }
 
+    #[test]
+    fn test_hanging_indent_function() {
+        let mut env = TestTemplateEnv::new();
+        env.add_color("error", crossterm::style::Color::DarkRed);
+        env.add_color("hint", crossterm::style::Color::DarkCyan);
+
+        // First line is indented by the first prefix, empty lines aren't
+        // indented. Not using insta here because we test whitespace existence.
+        assert_eq!(env.render_ok(r#"hanging_indent("__", "", "")"#), "");
+        assert_eq!(
+            env.render_ok(r#"hanging_indent("__", "", "a\nb\nc")"#),
+            "a\n__b\n__c"
+        );
+        assert_eq!(
+            env.render_ok(r#"hanging_indent("__", "- ", "a\n\nb\n")"#),
+            "- a\n\n__b\n"
+        );
+        assert_eq!(
+            env.render_ok(r#"hanging_indent("__", "- ", "\na")"#),
+            "\n__a"
+        );
+
+        // Word-wrap, then indent
+        insta::assert_snapshot!(
+            env.render_ok(r#""START marker to help insta\n" ++
+                             hanging_indent("    ", "", fill(20, "The quick fox jumps over the " ++
+                                                         label("error", "lazy") ++ " dog\n"))"#),
+            @r"
+        START marker to help insta
+        The quick fox jumps
+            over the [38;5;1mlazy[39m dog
+        ");
+
+        // Labeled content continues across the indented lines
+        insta::assert_snapshot!(
+            env.render_ok(r#"label("error", hanging_indent("    ", "",
+                                                           fill(20, "The quick fox jumps over the lazy dog\n")))"#),
+            @r"
+        [38;5;1mThe quick fox jumps[39m
+        [38;5;1m    over the lazy dog[39m
+        ");
+
+        // Labeled prefix inside labeled content
+        insta::assert_snapshot!(
+            env.render_ok(r#"hanging_indent(label("hint", ">"), "", label("error", "a\nb\n"))"#),
+            @r"
+        [38;5;1ma[39m
+        [38;5;6m>[38;5;1mb[39m
+        ");
+    }
+
     #[test]
     fn test_pad_function() {
         let mut env = TestTemplateEnv::new();
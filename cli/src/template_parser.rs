@@ -15,6 +15,7 @@
 use std::collections::HashMap;
 use std::error;
 use std::mem;
+use std::ops::Range;
 
 use itertools::Itertools as _;
 use jj_lib::dsl_util;
@@ -26,6 +27,7 @@ use jj_lib::dsl_util::AliasExpandError;
 use jj_lib::dsl_util::AliasExpandableExpression;
 use jj_lib::dsl_util::AliasId;
 use jj_lib::dsl_util::AliasesMap;
+use jj_lib::dsl_util::DiagnosticMessage;
 use jj_lib::dsl_util::Diagnostics;
 use jj_lib::dsl_util::ExpressionFolder;
 use jj_lib::dsl_util::FoldableExpression;
@@ -146,6 +148,8 @@ pub enum TemplateParseErrorKind {
     InParameterExpansion(String),
     #[error("Alias `{0}` expanded recursively")]
     RecursiveAlias(String),
+    #[error("Alias expansion exceeded the maximum depth of {0}")]
+    AliasExpansionDepthExceeded(usize),
 }
 
 impl TemplateParseError {
@@ -235,21 +239,47 @@ impl TemplateParseError {
         &self.kind
     }
 
+    /// Error message without the source snippet.
+    pub fn message(&self) -> String {
+        self.pest_error.variant.message().into_owned()
+    }
+
+    /// Byte range in the source text where the error occurred.
+    pub fn span(&self) -> Range<usize> {
+        match self.pest_error.location {
+            pest::error::InputLocation::Pos(pos) => pos..pos,
+            pest::error::InputLocation::Span((start, end)) => start..end,
+        }
+    }
+
     /// Original parsing error which typically occurred in an alias expression.
     pub fn origin(&self) -> Option<&Self> {
         self.source.as_ref().and_then(|e| e.downcast_ref())
     }
 }
 
+impl DiagnosticMessage for TemplateParseError {
+    fn is_same_diagnostic(&self, other: &Self) -> bool {
+        self.kind == other.kind && dsl_util::has_same_error_sources(self, other)
+    }
+}
+
 impl AliasExpandError for TemplateParseError {
     fn invalid_arguments(err: InvalidArguments<'_>) -> Self {
         err.into()
     }
 
-    fn recursive_expansion(id: AliasId<'_>, span: pest::Span<'_>) -> Self {
+    fn recursive_expansion(id: AliasId<'_>, _chain: &[AliasId<'_>], span: pest::Span<'_>) -> Self {
         Self::with_span(TemplateParseErrorKind::RecursiveAlias(id.to_string()), span)
     }
 
+    fn expansion_depth_exceeded(max_depth: usize, span: pest::Span<'_>) -> Self {
+        Self::with_span(
+            TemplateParseErrorKind::AliasExpansionDepthExceeded(max_depth),
+            span,
+        )
+    }
+
     fn within_alias_expansion(self, id: AliasId<'_>, span: pest::Span<'_>) -> Self {
         let kind = match id {
             AliasId::Symbol(_) | AliasId::Function(..) => {
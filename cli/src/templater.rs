@@ -25,6 +25,7 @@ use bstr::BString;
 use jj_lib::backend::Signature;
 use jj_lib::backend::Timestamp;
 use jj_lib::config::ConfigValue;
+use jj_lib::time_util::TimestampOutOfRange;
 
 use crate::formatter::FormatRecorder;
 use crate::formatter::Formatter;
@@ -147,6 +148,23 @@ impl Template for Timestamp {
     fn format(&self, formatter: &mut TemplateFormatter) -> io::Result<()> {
         match time_util::format_absolute_timestamp(self) {
             Ok(formatted) => write!(formatter, "{formatted}"),
+            // Imported commits may have bogus offsets. Display such timestamps
+            // in UTC, but make it clear that this isn't the recorded offset.
+            Err(err @ TimestampOutOfRange::TzOffset(_)) => {
+                let utc = Timestamp {
+                    tz_offset: 0,
+                    ..*self
+                };
+                match time_util::format_absolute_timestamp(&utc) {
+                    Ok(formatted) => write!(formatter, "{formatted}")?,
+                    Err(err) => return formatter.handle_error(err.into()),
+                }
+                formatter.as_mut().with_label("warning", |formatter| {
+                    write!(formatter, " <")?;
+                    write!(formatter.labeled("heading"), "Warning: ")?;
+                    write!(formatter, "{err}>")
+                })
+            }
             Err(err) => formatter.handle_error(err.into()),
         }
     }
@@ -161,7 +179,7 @@ pub struct TimestampRange {
 
 impl TimestampRange {
     // TODO: Introduce duration type, and move formatting to it.
-    pub fn duration(&self) -> Result<String, time_util::TimestampOutOfRange> {
+    pub fn duration(&self) -> Result<String, TimestampOutOfRange> {
         let mut f = timeago::Formatter::new();
         f.min_unit(timeago::TimeUnit::Microseconds).ago("");
         let duration = time_util::format_duration(&self.start, &self.end, &f)?;
@@ -390,18 +390,51 @@ pub fn write_indented(
     formatter: &mut dyn Formatter,
     recorded_content: &FormatRecorder,
     mut write_prefix: impl FnMut(&mut dyn Formatter) -> io::Result<()>,
+) -> io::Result<()> {
+    write_indented_lines(formatter, recorded_content, |formatter, _| {
+        write_prefix(formatter)
+    })
+}
+
+/// Indents the first line by `write_first_prefix`, and the subsequent lines by
+/// `write_prefix`, preserving labels.
+pub fn write_hanging_indented(
+    formatter: &mut dyn Formatter,
+    recorded_content: &FormatRecorder,
+    mut write_first_prefix: impl FnMut(&mut dyn Formatter) -> io::Result<()>,
+    mut write_prefix: impl FnMut(&mut dyn Formatter) -> io::Result<()>,
+) -> io::Result<()> {
+    write_indented_lines(formatter, recorded_content, |formatter, line_index| {
+        if line_index == 0 {
+            write_first_prefix(formatter)
+        } else {
+            write_prefix(formatter)
+        }
+    })
+}
+
+/// Calls `write_prefix` with the line index at the start of each non-empty
+/// line.
+fn write_indented_lines(
+    formatter: &mut dyn Formatter,
+    recorded_content: &FormatRecorder,
+    mut write_prefix: impl FnMut(&mut dyn Formatter, usize) -> io::Result<()>,
 ) -> io::Result<()> {
     let data = recorded_content.data();
+    let mut line_index = 0;
     let mut new_line = true;
     recorded_content.replay_with(formatter, |formatter, range| {
         for line in data[range].split_inclusive(|&c| c == b'\n') {
             if new_line && line != b"\n" {
-                // Prefix inherits the current labels. This is implementation detail
-                // and may be fixed later.
-                write_prefix(formatter)?;
+                // Prefix is written inside the current labels so the style of
+                // the content continues across lines.
+                write_prefix(formatter, line_index)?;
             }
             formatter.write_all(line)?;
             new_line = line.ends_with(b"\n");
+            if new_line {
+                line_index += 1;
+            }
         }
         Ok(())
     })
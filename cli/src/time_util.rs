@@ -1,10 +1,13 @@
+use std::time::Duration;
+
 use chrono::format::StrftimeItems;
 use chrono::DateTime;
-use chrono::FixedOffset;
-use chrono::LocalResult;
+use chrono::Local;
 use chrono::TimeZone as _;
-use chrono::Utc;
 use jj_lib::backend::Timestamp;
+use jj_lib::settings::UserSettings;
+use jj_lib::time_util;
+use jj_lib::time_util::TimestampOutOfRange;
 use once_cell::sync::Lazy;
 use thiserror::Error;
 
@@ -46,28 +49,34 @@ impl<'a> FormattingItems<'a> {
     }
 }
 
-#[derive(Debug, Error)]
-#[error("Out-of-range date")]
-pub struct TimestampOutOfRange;
-
-fn datetime_from_timestamp(
-    context: &Timestamp,
-) -> Result<DateTime<FixedOffset>, TimestampOutOfRange> {
-    let utc = match Utc.timestamp_opt(
-        context.timestamp.0.div_euclid(1000),
-        (context.timestamp.0.rem_euclid(1000)) as u32 * 1000000,
-    ) {
-        LocalResult::None => {
-            return Err(TimestampOutOfRange);
+/// Source of the current time for revset date patterns and template
+/// relative dates.
+///
+/// The time can be pinned by `debug.current-timestamp` so that both agree on
+/// what "now" is. Otherwise, the system time is used.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Clock {
+    pinned: Option<Timestamp>,
+}
+
+impl Clock {
+    pub fn from_settings(settings: &UserSettings) -> Self {
+        Clock {
+            pinned: settings.current_timestamp(),
         }
-        LocalResult::Single(x) => x,
-        LocalResult::Ambiguous(y, _z) => y,
-    };
+    }
+
+    pub fn now(&self) -> Timestamp {
+        self.pinned.unwrap_or_else(Timestamp::now)
+    }
 
-    Ok(utc.with_timezone(
-        &FixedOffset::east_opt(context.tz_offset * 60)
-            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap()),
-    ))
+    /// Returns the current time in the local time zone.
+    pub fn now_local(&self) -> DateTime<Local> {
+        match self.pinned {
+            Some(timestamp) => Local.timestamp_millis_opt(timestamp.timestamp.0).unwrap(),
+            None => Local::now(),
+        }
+    }
 }
 
 pub fn format_absolute_timestamp(timestamp: &Timestamp) -> Result<String, TimestampOutOfRange> {
@@ -80,18 +89,265 @@ pub fn format_absolute_timestamp_with(
     timestamp: &Timestamp,
     format: &FormattingItems,
 ) -> Result<String, TimestampOutOfRange> {
-    let datetime = datetime_from_timestamp(timestamp)?;
+    let datetime = time_util::datetime_from_timestamp(timestamp)?;
     Ok(datetime.format_with_items(format.items.iter()).to_string())
 }
 
+/// Returns the signed duration from `from` to `to`.
+///
+/// The time zone offsets don't affect the duration, so they aren't validated.
+fn signed_duration_between(from: &Timestamp, to: &Timestamp) -> chrono::TimeDelta {
+    chrono::TimeDelta::milliseconds(to.timestamp.0.saturating_sub(from.timestamp.0))
+}
+
 pub fn format_duration(
     from: &Timestamp,
     to: &Timestamp,
     format: &timeago::Formatter,
 ) -> Result<String, TimestampOutOfRange> {
-    let duration = datetime_from_timestamp(to)?
-        .signed_duration_since(datetime_from_timestamp(from)?)
+    let duration = signed_duration_between(from, to)
         .to_std()
-        .map_err(|_: chrono::OutOfRangeError| TimestampOutOfRange)?;
+        .map_err(|_: chrono::OutOfRangeError| TimestampOutOfRange::Date)?;
     Ok(format.convert(duration))
 }
+
+/// Formats the duration from `from` to `to` like [`format_duration()`], but
+/// allows `from` to be after `to` if `future_format` is given.
+///
+/// A duration to the future is formatted as "in X" by the `future_format`,
+/// which should be set up to omit the "ago" word. A future duration shorter
+/// than a second is formatted by `format` as if it were zero.
+pub fn format_signed_duration(
+    from: &Timestamp,
+    to: &Timestamp,
+    format: &timeago::Formatter,
+    future_format: Option<&timeago::Formatter>,
+) -> Result<String, TimestampOutOfRange> {
+    let duration = signed_duration_between(from, to);
+    match (duration.to_std(), future_format) {
+        (Ok(duration), _) => Ok(format.convert(duration)),
+        (Err(_), Some(future_format)) => {
+            let duration = (-duration)
+                .to_std()
+                .map_err(|_: chrono::OutOfRangeError| TimestampOutOfRange::Date)?;
+            if duration.as_secs() == 0 {
+                Ok(format.convert(Duration::ZERO))
+            } else {
+                let formatted = future_format.convert(duration);
+                Ok(format!("in {}", formatted.trim_end()))
+            }
+        }
+        (Err(_), None) => Err(TimestampOutOfRange::Date),
+    }
+}
+
+/// Formats the duration from `from` to `to` if it is within the `threshold`,
+/// or the absolute `from` timestamp otherwise.
+pub fn format_relative_or_absolute(
+    from: &Timestamp,
+    to: &Timestamp,
+    threshold: Duration,
+    formatter: &timeago::Formatter,
+    abs_format: &FormattingItems,
+) -> Result<String, TimestampOutOfRange> {
+    let duration = signed_duration_between(from, to).to_std();
+    match duration {
+        Ok(duration) if duration <= threshold => Ok(formatter.convert(duration)),
+        // Future timestamp or too old
+        _ => format_absolute_timestamp_with(from, abs_format),
+    }
+}
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum DurationParseError {
+    #[error("Duration must start with a number")]
+    MissingNumber,
+    #[error("Duration must end with a unit (s, m, h, d, or w)")]
+    MissingUnit,
+    #[error("Unknown duration unit `{0}`, expected s, m, h, d, or w")]
+    UnknownUnit(String),
+    #[error("Duration is too large")]
+    OutOfRange,
+}
+
+/// Parses user-entered duration such as `30s` or `7d`.
+///
+/// The supported units are `s` (seconds), `m` (minutes), `h` (hours), `d`
+/// (days), and `w` (weeks). A day is always 24 hours.
+pub fn parse_duration(s: &str) -> Result<Duration, DurationParseError> {
+    let s = s.trim();
+    let digit_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if digit_end == 0 {
+        return Err(DurationParseError::MissingNumber);
+    }
+    let (digits, unit) = s.split_at(digit_end);
+    let secs_per_unit: u64 = match unit.trim_start() {
+        "" => return Err(DurationParseError::MissingUnit),
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        unit => return Err(DurationParseError::UnknownUnit(unit.to_owned())),
+    };
+    let secs = digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(secs_per_unit))
+        .ok_or(DurationParseError::OutOfRange)?;
+    Ok(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use jj_lib::backend::MillisSinceEpoch;
+
+    use super::*;
+
+    fn timestamp(secs: i64) -> Timestamp {
+        Timestamp {
+            timestamp: MillisSinceEpoch(secs * 1000),
+            tz_offset: 0,
+        }
+    }
+
+    #[test]
+    fn test_format_absolute_timestamp_bad_tz_offset() {
+        let valid = Timestamp {
+            timestamp: MillisSinceEpoch(0),
+            tz_offset: -480,
+        };
+        assert_eq!(
+            format_absolute_timestamp(&valid).unwrap(),
+            "1969-12-31 16:00:00.000 -08:00"
+        );
+
+        // Invalid offset shouldn't be silently displayed as UTC
+        let invalid = Timestamp {
+            timestamp: MillisSinceEpoch(0),
+            tz_offset: 100000,
+        };
+        assert_eq!(
+            format_absolute_timestamp(&invalid),
+            Err(TimestampOutOfRange::TzOffset(100000))
+        );
+
+        // Duration doesn't depend on the offset
+        let now = timestamp(300);
+        let formatter = timeago::Formatter::new();
+        assert_eq!(
+            format_duration(&invalid, &now, &formatter).unwrap(),
+            "5 minutes ago"
+        );
+    }
+
+    #[test]
+    fn test_format_signed_duration() {
+        let formatter = timeago::Formatter::new();
+        let mut future_formatter = timeago::Formatter::new();
+        future_formatter.ago("");
+        let now = timestamp(1000);
+        let format = |from: Timestamp, future_format: Option<&timeago::Formatter>| {
+            format_signed_duration(&from, &now, &formatter, future_format)
+        };
+
+        assert_eq!(format(timestamp(700), None).unwrap(), "5 minutes ago");
+        assert_eq!(
+            format(timestamp(700), Some(&future_formatter)).unwrap(),
+            "5 minutes ago"
+        );
+        assert_eq!(format(timestamp(1000), None).unwrap(), "now");
+        assert_eq!(
+            format(
+                Timestamp {
+                    timestamp: MillisSinceEpoch(1000 * 1000 + 500),
+                    tz_offset: 0,
+                },
+                Some(&future_formatter)
+            )
+            .unwrap(),
+            "now"
+        );
+
+        // Timestamp after now
+        assert_eq!(
+            format(timestamp(1300), Some(&future_formatter)).unwrap(),
+            "in 5 minutes"
+        );
+        assert_eq!(
+            format(timestamp(1000 + 2 * 60 * 60), Some(&future_formatter)).unwrap(),
+            "in 2 hours"
+        );
+        assert_eq!(
+            format(timestamp(1300), None),
+            Err(TimestampOutOfRange::Date)
+        );
+    }
+
+    #[test]
+    fn test_format_relative_or_absolute() {
+        const DAY: i64 = 24 * 60 * 60;
+        let formatter = timeago::Formatter::new();
+        let abs_format = FormattingItems::parse("%Y-%m-%d").unwrap();
+        let threshold = Duration::from_secs(7 * DAY as u64);
+        let now = timestamp(100 * DAY);
+        let format = |from: Timestamp| {
+            format_relative_or_absolute(&from, &now, threshold, &formatter, &abs_format).unwrap()
+        };
+
+        // Within threshold
+        assert_eq!(format(timestamp(98 * DAY)), "2 days ago");
+        assert_eq!(format(timestamp(93 * DAY)), "1 week ago");
+
+        // Beyond threshold
+        assert_eq!(format(timestamp(93 * DAY - 1)), "1970-04-03");
+        assert_eq!(format(timestamp(0)), "1970-01-01");
+
+        // Future timestamp
+        assert_eq!(format(timestamp(101 * DAY)), "1970-04-12");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("0s"), Ok(Duration::ZERO));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(5 * 60)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(
+            parse_duration("7d"),
+            Ok(Duration::from_secs(7 * 24 * 60 * 60))
+        );
+        assert_eq!(
+            parse_duration("1w"),
+            Ok(Duration::from_secs(7 * 24 * 60 * 60))
+        );
+        assert_eq!(
+            parse_duration(" 3 d "),
+            Ok(Duration::from_secs(3 * 24 * 60 * 60))
+        );
+
+        assert_eq!(
+            parse_duration("7y"),
+            Err(DurationParseError::UnknownUnit("y".to_owned()))
+        );
+        assert_eq!(
+            parse_duration("7days"),
+            Err(DurationParseError::UnknownUnit("days".to_owned()))
+        );
+        assert_eq!(parse_duration("7"), Err(DurationParseError::MissingUnit));
+        assert_eq!(parse_duration("d"), Err(DurationParseError::MissingNumber));
+        assert_eq!(
+            parse_duration("-1d"),
+            Err(DurationParseError::MissingNumber)
+        );
+        assert_eq!(parse_duration(""), Err(DurationParseError::MissingNumber));
+        assert_eq!(
+            parse_duration("99999999999999999999s"),
+            Err(DurationParseError::OutOfRange)
+        );
+        assert_eq!(
+            parse_duration("9999999999999999w"),
+            Err(DurationParseError::OutOfRange)
+        );
+    }
+}
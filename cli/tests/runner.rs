@@ -16,6 +16,7 @@ mod test_alias;
 mod test_backout_command;
 mod test_bookmark_command;
 mod test_builtin_aliases;
+mod test_bundle_command;
 mod test_commit_command;
 mod test_commit_template;
 mod test_completion;
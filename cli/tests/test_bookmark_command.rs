@@ -1877,7 +1877,7 @@ fn test_bookmark_list_filtered() {
     insta::assert_snapshot!(query(&["-rremote-delete"]), @r"
     ------- stderr -------
     Error: Revision `remote-delete` doesn't exist
-    Hint: Did you mean `remote-delete@origin`, `remote-keep`, `remote-rewrite`, `remote-rewrite@origin`?
+    Hint: Did you mean `remote-keep`, `remote-rewrite`, `remote-delete@origin`, `remote-rewrite@origin`?
     [EOF]
     [exit status: 1]
     ");
@@ -2210,6 +2210,14 @@ fn test_bookmark_list_conflicted() {
       + kkmpptxz 06a973bc (empty) b
     [EOF]
     ");
+
+    // A conflicted bookmark is listed if any of its targets matches.
+    insta::assert_snapshot!(work_dir.run_jj(["bookmark", "list", "-r", "description(a)"]), @r"
+    foo (conflicted):
+      + rlvkpnrz d8d5f980 (empty) a
+      + kkmpptxz 06a973bc (empty) b
+    [EOF]
+    ");
 }
 
 #[test]
@@ -2218,10 +2226,10 @@ fn test_bookmark_list_sort_unknown_key_error() {
     test_env.run_jj_in(".", ["git", "init", "repo"]).success();
     let work_dir = test_env.work_dir("repo");
 
-    insta::assert_snapshot!(work_dir.run_jj(["bookmark", "list", "--sort", "date"]), @r"
+    insta::assert_snapshot!(work_dir.run_jj(["bookmark", "list", "--sort", "size"]), @r"
     ------- stderr -------
-    error: invalid value 'date' for '--sort <SORT_KEY>'
-      [possible values: name, name-, author-name, author-name-, author-email, author-email-, author-date, author-date-, committer-name, committer-name-, committer-email, committer-email-, committer-date, committer-date-]
+    error: invalid value 'size' for '--sort <SORT_KEY>'
+      [possible values: name, name-, author-name, author-name-, author-email, author-email-, author-date, author-date-, committer-name, committer-name-, committer-email, committer-email-, committer-date, committer-date-, ahead, ahead-]
 
     For more information, try '--help'.
     [EOF]
@@ -2259,6 +2267,70 @@ fn test_bookmark_list_sort_multiple_keys() {
     ");
 }
 
+#[test]
+fn test_bookmark_list_sort_by_ahead() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config("git.auto-local-bookmark = true");
+
+    // Initialize remote refs
+    test_env.run_jj_in(".", ["git", "init", "remote"]).success();
+    let remote_dir = test_env.work_dir("remote");
+    for bookmark in ["a", "b", "c"] {
+        remote_dir
+            .run_jj(["new", "root()", "-m", bookmark])
+            .success();
+        remote_dir
+            .run_jj(["bookmark", "create", "-r@", bookmark])
+            .success();
+    }
+    remote_dir.run_jj(["git", "export"]).success();
+
+    // Initialize local refs
+    let mut remote_git_path = remote_dir.root().to_owned();
+    remote_git_path.extend([".jj", "repo", "store", "git"]);
+    test_env
+        .run_jj_in(
+            ".",
+            ["git", "clone", remote_git_path.to_str().unwrap(), "local"],
+        )
+        .success();
+    let local_dir = test_env.work_dir("local");
+    // "a" is ahead by 1 commit, "b" is ahead by 2 commits, and "c" is in sync
+    local_dir.run_jj(["new", "a", "-m", "a2"]).success();
+    local_dir
+        .run_jj(["bookmark", "set", "a", "--to=@"])
+        .success();
+    local_dir.run_jj(["new", "b", "-m", "b2"]).success();
+    local_dir.run_jj(["new", "-m", "b3"]).success();
+    local_dir
+        .run_jj(["bookmark", "set", "b", "--to=@"])
+        .success();
+    // "d" has no remote to be ahead of
+    local_dir
+        .run_jj(["bookmark", "create", "d", "-r", "description(b2)"])
+        .success();
+
+    let template = r#"name ++ if(remote, "@" ++ remote) ++ "\n""#;
+    insta::assert_snapshot!(local_dir.run_jj(["bookmark", "list", "-T", template, "--sort", "ahead"]), @r"
+    c
+    d
+    a
+    a@origin
+    b
+    b@origin
+    [EOF]
+    ");
+    insta::assert_snapshot!(local_dir.run_jj(["bookmark", "list", "-T", template, "--sort", "ahead-"]), @r"
+    b
+    b@origin
+    a
+    a@origin
+    c
+    d
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_bookmark_list_sort_using_config() {
     let test_env = TestEnvironment::default();
@@ -0,0 +1,105 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::create_commit;
+use crate::common::TestEnvironment;
+
+#[test]
+fn test_bundle_create_apply() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    create_commit(&work_dir, "a", &[]);
+    create_commit(&work_dir, "b", &[]);
+    create_commit(&work_dir, "c", &["a", "b"]);
+
+    let output = work_dir.run_jj(["bundle", "create", "-r", "none()", "../none.jj"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: No revisions to bundle
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = work_dir.run_jj(["bundle", "create", "-r", "root()", "../root.jj"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: The root commit can't be bundled
+    [EOF]
+    [exit status: 1]
+    ");
+
+    let output = work_dir.run_jj(["bundle", "create", "-r", "a | b", "../ab.jj"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Bundled 2 commits
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["bundle", "create", "-r", "c", "../c.jj"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Bundled 1 commits
+    The bundle requires these commits in the destination repository:
+      rlvkpnrz 2443ea76 a | a
+      zsuskuln d370aee1 b | b
+    [EOF]
+    ");
+
+    test_env.run_jj_in(".", ["git", "init", "repo2"]).success();
+    let work_dir2 = test_env.work_dir("repo2");
+
+    // Base commits must exist in the destination
+    let output = work_dir2.run_jj(["bundle", "apply", "../c.jj"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: The repository doesn't contain the base commits of the bundle: 2443ea76b0b1, d370aee184ba
+    Hint: Apply a bundle containing the base commits first, or create the bundle with the base commits included.
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // Commit ids are preserved since both repos use the same backend
+    let output = work_dir2.run_jj(["bundle", "apply", "../ab.jj"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Imported 2 commits:
+      zsuskuln d370aee1 b
+      rlvkpnrz 2443ea76 a
+    [EOF]
+    ");
+    let output = work_dir2.run_jj(["file", "show", "-r", "2443ea76", "a"]);
+    insta::assert_snapshot!(output, @r"
+    a
+    [EOF]
+    ");
+
+    // Existing commits are skipped
+    let output = work_dir2.run_jj(["bundle", "apply", "../ab.jj"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Skipped 2 commits which already exist
+    Nothing changed.
+    [EOF]
+    ");
+
+    std::fs::write(test_env.env_root().join("invalid.jj"), "invalid").unwrap();
+    let output = work_dir2.run_jj(["bundle", "apply", "../invalid.jj"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to read bundle ../invalid.jj
+    Caused by: Not a bundle file
+    [EOF]
+    [exit status: 1]
+    ");
+}
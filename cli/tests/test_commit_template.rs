@@ -136,6 +136,76 @@ fn test_log_author_timestamp_ago() {
     );
 }
 
+#[test]
+fn test_log_timestamp_ago_future() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m", "first"]).success();
+
+    let run = |template: &str| {
+        work_dir.run_jj([
+            "log",
+            "--no-graph",
+            "--config=debug.current-timestamp=2001-02-03T04:00:00+07:00",
+            "-r@",
+            "-T",
+            template,
+        ])
+    };
+    insta::assert_snapshot!(run(r#"committer.timestamp().ago(true) ++ "\n""#), @r"
+    in 5 minutes
+    [EOF]
+    ");
+    insta::assert_snapshot!(run(r#"committer.timestamp().ago(false) ++ "\n""#), @r"
+    <Error: Out-of-range date>
+    [EOF]
+    ");
+    insta::assert_snapshot!(run(r#"committer.timestamp().ago() ++ "\n""#), @r"
+    <Error: Out-of-range date>
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_log_timestamp_ago_agrees_with_revset_now() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.run_jj(["describe", "-m", "first"]).success();
+    work_dir.run_jj(["new", "-m", "second"]).success();
+
+    // Both the revset and the template should see the same frozen "now".
+    let template = r#"description.first_line() ++ ": " ++ committer.timestamp().ago() ++ "\n""#;
+    let output = work_dir.run_jj([
+        "log",
+        "--no-graph",
+        "--config=debug.current-timestamp=2001-02-03T04:05:09+07:00",
+        "-r=committer_date(before:'now') ~ root()",
+        "-T",
+        template,
+    ]);
+    insta::assert_snapshot!(output, @r"
+    first: 1 second ago
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj([
+        "log",
+        "--no-graph",
+        "--config=debug.current-timestamp=2001-02-03T04:10:08+07:00",
+        "-r=committer_date(after:'5 minutes ago')",
+        "-T",
+        template,
+    ]);
+    insta::assert_snapshot!(output, @r"
+    second: 4 minutes ago
+    first: 5 minutes ago
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_log_author_timestamp_utc() {
     let test_env = TestEnvironment::default();
@@ -1060,6 +1130,13 @@ fn test_short_prefix_in_transaction() {
     let output = work_dir.run_jj(["new", parent_id, "--no-edit", "-m", "test"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
+    Warning: In `revsets.short-prefixes`
+     --> 1:1
+      |
+    1 | ::description(test)
+      | ^-----------------^
+      |
+      = Operator `::` is applied to a filter expression without a set, which is evaluated against all commits. Consider intersecting the filter with a set of revisions first
     Created new commit km[kuslswpqwq] 7[4ac55dd119b] test
     [EOF]
     ");
@@ -1076,6 +1153,22 @@ fn test_short_prefix_in_transaction() {
     q[pvuntsmwlqt] e[0e22b9fae75] initial
     zz[zzzzzzzzzz] 00[0000000000]
     [EOF]
+    ------- stderr -------
+    Warning: In `revsets.short-prefixes`
+     --> 1:1
+      |
+    1 | ::description(test)
+      | ^-----------------^
+      |
+      = Operator `::` is applied to a filter expression without a set, which is evaluated against all commits. Consider intersecting the filter with a set of revisions first
+    Warning: In revset expression
+     --> 1:1
+      |
+    1 | ::description(test)
+      | ^-----------------^
+      |
+      = Operator `::` is applied to a filter expression without a set, which is evaluated against all commits. Consider intersecting the filter with a set of revisions first
+    [EOF]
     ");
 
     test_env.add_config(r#"revsets.short-prefixes = """#);
@@ -1091,6 +1184,15 @@ fn test_short_prefix_in_transaction() {
     qp[vuntsmwlqt] e0[e22b9fae75] initial
     zz[zzzzzzzzzz] 00[0000000000]
     [EOF]
+    ------- stderr -------
+    Warning: In revset expression
+     --> 1:1
+      |
+    1 | ::description(test)
+      | ^-----------------^
+      |
+      = Operator `::` is applied to a filter expression without a set, which is evaluated against all commits. Consider intersecting the filter with a set of revisions first
+    [EOF]
     ");
 }
 
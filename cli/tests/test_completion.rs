@@ -480,6 +480,7 @@ fn test_revisions() {
     insta::assert_snapshot!(output, @r"
     immutable_bookmark	immutable
     mutable_bookmark	mutable
+    default@	working_copy
     k	working_copy
     y	mutable
     q	immutable
@@ -495,6 +496,7 @@ fn test_revisions() {
     let output = work_dir.run_jj(["--", "jj", "squash", "--into", ""]);
     insta::assert_snapshot!(output, @r"
     mutable_bookmark	mutable
+    default@	working_copy
     k	working_copy
     y	mutable
     zq	remote_commit
@@ -509,6 +511,7 @@ fn test_revisions() {
     insta::assert_snapshot!(output, @r"
     immutable_bookmark	immutable
     mutable_bookmark	mutable
+    default@	working_copy
     k	working_copy
     y	mutable
     q	immutable
@@ -533,6 +536,7 @@ fn test_revisions() {
     insta::assert_snapshot!(output, @r"
     a=immutable_bookmark	immutable
     a=mutable_bookmark	mutable
+    a=default@	working_copy
     a=k	working_copy
     a=y	mutable
     a=q	immutable
@@ -674,6 +678,38 @@ fn test_workspaces() {
     ");
 }
 
+#[test]
+fn test_revisions_without_snapshot() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m", "initial"]).success();
+    work_dir.write_file("file", "contents");
+
+    let output = work_dir.run_jj_with(|cmd| {
+        cmd.env("COMPLETE", "fish")
+            .args(["--", "jj", "log", "-r", "def"])
+    });
+    insta::assert_snapshot!(output, @r"
+    default@	initial
+    [EOF]
+    ");
+
+    // The new file isn't snapshotted by the completion
+    let output = work_dir.run_jj([
+        "op",
+        "log",
+        "--ignore-working-copy",
+        "-n1",
+        "-T",
+        "description",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    @  describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_config() {
     let mut test_env = TestEnvironment::default();
@@ -1471,6 +1471,57 @@ fn test_config_author_change_warning_root_env() {
     insta::assert_snapshot!(output, @"");
 }
 
+#[test]
+fn test_config_validate() {
+    let test_env = TestEnvironment::default();
+    std::fs::write(
+        test_env.env_root().join("invalid.toml"),
+        indoc! {r#"
+            ui.colour = "always"
+            ui.paginate = ":builtin"
+            git.auto-local-branch = true
+
+            [revsets]
+            log = "x &"
+
+            [revset-aliases]
+            'badfn(a, a)' = 'root()'
+
+            [templates]
+            log = 'description ()'
+        "#},
+    )
+    .unwrap();
+
+    let output = test_env.run_jj_in(".", ["config", "validate", "invalid.toml"]);
+    insta::assert_snapshot!(output, @r#"
+    $TEST_ENV/invalid.toml: warning: Deprecated config: git.auto-local-branch is renamed to git.auto-local-bookmark
+    $TEST_ENV/invalid.toml:1:4: warning: Unknown key `ui.colour`
+    $TEST_ENV/invalid.toml:2:15: error: Invalid value for `ui.paginate`: expected one of "never", "auto", but found ":builtin"
+    $TEST_ENV/invalid.toml:6:11: error: Invalid revset in `revsets.log`: expected `::`, `..`, `~`, or <primary>
+    $TEST_ENV/invalid.toml:9:8: error: Invalid revset alias declaration `revset-aliases."badfn(a, a)"`: Redefinition of function parameter
+    $TEST_ENV/invalid.toml:12:20: error: Invalid template in `templates.log`: expected <EOI>, `++`, `||`, `&&`, `==`, `!=`, `>=`, `>`, `<=`, or `<`
+    [EOF]
+    ------- stderr -------
+    Error: Found 4 errors in config
+    [EOF]
+    [exit status: 1]
+    "#);
+
+    // All layers are checked by default. Warnings don't fail the command.
+    let output = test_env.run_jj_in(".", ["config", "validate"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    No problems found
+    [EOF]
+    ");
+    let output = test_env.run_jj_in(".", ["config", "validate", "--config=ui.colour=always"]);
+    insta::assert_snapshot!(output, @r"
+    <cli config>: warning: Unknown key `ui.colour`
+    [EOF]
+    ");
+}
+
 fn find_stdout_lines(keyname_pattern: &str, stdout: &str) -> String {
     let key_line_re = Regex::new(&format!(r"(?m)^{keyname_pattern} = .*\n")).unwrap();
     key_line_re.find_iter(stdout).map(|m| m.as_str()).collect()
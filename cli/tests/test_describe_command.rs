@@ -194,6 +194,175 @@ fn test_describe() {
     ");
 }
 
+#[test]
+fn test_describe_stdin() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    // Set a description from stdin, without trailing newline
+    let output = work_dir.run_jj_with(|cmd| {
+        cmd.args(["describe", "--stdin"])
+            .write_stdin("description from CLI")
+    });
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: qpvuntsm 95979928 (empty) description from CLI
+    Parent commit (@-)      : zzzzzzzz 00000000 (empty) (no description set)
+    [EOF]
+    ");
+
+    // Set the same description from stdin, but with explicit newline
+    let output = work_dir.run_jj_with(|cmd| {
+        cmd.args(["describe", "--stdin"])
+            .write_stdin("description from CLI\n")
+    });
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+
+    // The `-m` flag produces the same description
+    let output = work_dir.run_jj(["describe", "-m", "description from CLI"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+
+    // `--stdin` can't be combined with `--edit`
+    let output = work_dir.run_jj_with(|cmd| {
+        cmd.args(["describe", "--stdin", "--edit"])
+            .write_stdin("description from CLI")
+    });
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    error: the argument '--stdin' cannot be used with '--edit'
+
+    Usage: jj describe --stdin [REVSETS]...
+
+    For more information, try '--help'.
+    [EOF]
+    [exit status: 2]
+    ");
+}
+
+#[test]
+fn test_describe_message_file() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    // Keep the message file outside of the working copy
+    let message_file = test_env.env_root().join("message.txt");
+    std::fs::write(
+        &message_file,
+        indoc! {"
+
+            JJ: comment line
+            description from file
+
+            JJ: another comment line
+            body
+            JJ: ignore-rest
+            ignored
+        "},
+    )
+    .unwrap();
+    work_dir
+        .run_jj(["describe", "--message-file", message_file.to_str().unwrap()])
+        .success();
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-Tdescription"]);
+    insta::assert_snapshot!(output, @r"
+    description from file
+
+    body
+    [EOF]
+    ");
+
+    // Fails if the file doesn't exist
+    let output = work_dir.run_jj(["describe", "--message-file", "nonexistent.txt"]);
+    let output = output.normalize_stderr_with(|s| s.split_inclusive('\n').take(1).collect());
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    ------- stderr -------
+    Error: Failed to read $TEST_ENV/repo/nonexistent.txt
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // `--message-file` can't be combined with `--edit`
+    let output = work_dir.run_jj(["describe", "--message-file", "message.txt", "--edit"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    error: the argument '--message-file <PATH>' cannot be used with '--edit'
+
+    Usage: jj describe --message-file <PATH> [REVSETS]...
+
+    For more information, try '--help'.
+    [EOF]
+    [exit status: 2]
+    ");
+}
+
+#[test]
+fn test_describe_trailer() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    // A new trailer block is created
+    work_dir
+        .run_jj([
+            "describe",
+            "-m",
+            "subject\n\nbody",
+            "--trailer",
+            "Signed-off-by=Alice <alice@example.com>",
+        ])
+        .success();
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-Tdescription"]);
+    insta::assert_snapshot!(output, @r"
+    subject
+
+    body
+
+    Signed-off-by: Alice <alice@example.com>
+    [EOF]
+    ");
+
+    // A second trailer joins the existing block
+    work_dir
+        .run_jj([
+            "describe",
+            "--no-edit",
+            "--trailer",
+            "Reviewed-by=Bob <bob@example.com>",
+        ])
+        .success();
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-Tdescription"]);
+    insta::assert_snapshot!(output, @r"
+    subject
+
+    body
+
+    Signed-off-by: Alice <alice@example.com>
+    Reviewed-by: Bob <bob@example.com>
+    [EOF]
+    ");
+
+    // Invalid trailer
+    let output = work_dir.run_jj(["describe", "--no-edit", "--trailer", "foo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    error: invalid value 'foo' for '--trailer <KEY=VALUE>': Trailer must be in the form KEY=VALUE
+
+    For more information, try '--help'.
+    [EOF]
+    [exit status: 2]
+    ");
+}
+
 #[test]
 fn test_describe_editor_env() {
     let test_env = TestEnvironment::default();
@@ -263,6 +432,112 @@ fn test_describe_editor_env() {
     ");
 }
 
+#[test]
+fn test_describe_resume() {
+    let mut test_env = TestEnvironment::default();
+    let edit_script = test_env.set_up_fake_editor();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["new"]).success();
+
+    // Nothing to resume
+    let output = work_dir.run_jj(["describe", "--resume"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: No interrupted edit of the description to resume
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // The edited text is saved if the editor fails
+    std::fs::write(&edit_script, "write\nedited description\n\0fail").unwrap();
+    let output = work_dir.run_jj(["describe"]);
+    insta::with_settings!({
+        filters => [
+            (r"\bEditor '[^']*'", "Editor '<redacted>'"),
+            (r"\b(editor-describe-)[0-9a-f]+(\.jjdescription)\b", "$1<redacted>$2"),
+            ("exit code", "exit status"), // Windows
+        ],
+    }, {
+        insta::assert_snapshot!(output, @r"
+        ------- stderr -------
+        Error: Failed to edit description
+        Caused by: Editor '<redacted>' exited with exit status: 1
+        Hint: Edited description is left in $TEST_ENV/repo/.jj/repo/editor-describe-<redacted>.jjdescription
+        [EOF]
+        [exit status: 1]
+        ");
+    });
+
+    // The saved text is offered on the next edit, and kept if the editor fails
+    // without changes
+    std::fs::write(&edit_script, "fail").unwrap();
+    let output = work_dir.run_jj(["describe"]);
+    insta::with_settings!({
+        filters => [
+            (r"\bEditor '[^']*'", "Editor '<redacted>'"),
+            (r"left in \S+", "left in <redacted>"),
+            ("exit code", "exit status"), // Windows
+        ],
+    }, {
+        insta::assert_snapshot!(output, @r"
+        ------- stderr -------
+        Hint: Found a description saved by an interrupted edit. Use `jj describe --resume` to continue editing it. It will be discarded if this edit succeeds.
+        Error: Failed to edit description
+        Caused by: Editor '<redacted>' exited with exit status: 1
+        Hint: Edited description is left in <redacted>
+        [EOF]
+        [exit status: 1]
+        ");
+    });
+
+    // Can only resume a single revision
+    let output = work_dir.run_jj(["describe", "--resume", "@", "@-"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: --resume can only be used when describing a single revision
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // The editor is opened with the saved text
+    std::fs::write(&edit_script, "dump editor1").unwrap();
+    work_dir.run_jj(["describe", "--resume"]).success();
+    insta::assert_snapshot!(
+        std::fs::read_to_string(test_env.env_root().join("editor1")).unwrap(), @r"
+    edited description
+    ");
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-Tdescription"]);
+    insta::assert_snapshot!(output, @r"
+    edited description
+    [EOF]
+    ");
+
+    // The saved text is removed once resumed
+    let output = work_dir.run_jj(["describe", "--resume"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: No interrupted edit of the description to resume
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // The saved text is also removed by any other successful edit
+    std::fs::write(&edit_script, "write\nedited again\n\0fail").unwrap();
+    let output = work_dir.run_jj(["describe"]);
+    assert!(!output.status.success());
+    work_dir
+        .run_jj(["describe", "-m", "new description"])
+        .success();
+    let output = work_dir.run_jj(["describe", "--resume"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: No interrupted edit of the description to resume
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
 #[test]
 fn test_describe_multiple_commits() {
     let mut test_env = TestEnvironment::default();
@@ -426,6 +701,7 @@ fn test_describe_multiple_commits() {
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
     Error: The following commits were not being edited, but were found in the edited message: 000000000000
+    Hint: Remove the `JJ: describe` lines for the commits not being edited.
     [EOF]
     [exit status: 1]
     ");
@@ -448,6 +724,7 @@ fn test_describe_multiple_commits() {
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
     Error: The description for the following commits were not found in the edited message: 0d76a92ca7cc
+    Hint: Each description must follow a `JJ: describe <commit id>` line.
     [EOF]
     [exit status: 1]
     ");
@@ -534,6 +811,147 @@ fn test_describe_multiple_commits() {
     ");
 }
 
+#[test]
+fn test_describe_multiple_commits_custom_separator() {
+    let mut test_env = TestEnvironment::default();
+    let edit_script = test_env.set_up_fake_editor();
+    test_env.add_config(r#"ui.describe-separator = "JJ: ===""#);
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["new"]).success();
+
+    // The separator lines use the configured prefix
+    std::fs::write(&edit_script, "dump editor0").unwrap();
+    work_dir.run_jj(["describe", "@", "@-"]).success();
+    let content = std::fs::read_to_string(test_env.env_root().join("editor0")).unwrap();
+    assert!(content.starts_with("JJ: Enter or edit commit descriptions after the `JJ: ===` lines."));
+    let commit_hashes: Vec<_> = content
+        .lines()
+        .filter_map(|line| line.strip_prefix("JJ: === "))
+        .map(|rest| rest.trim_end_matches([' ', '-']))
+        .collect();
+    let [parent_hash, child_hash] = commit_hashes[..] else {
+        panic!("unexpected separator lines in:\n{content}");
+    };
+
+    // The edited descriptions are read back by the configured prefix
+    std::fs::write(
+        &edit_script,
+        format!(
+            "write\nJJ: === {parent_hash} -------\ndescription of @-\n\n\
+             JJ: === {child_hash} -------\ndescription of @\n"
+        ),
+    )
+    .unwrap();
+    work_dir.run_jj(["describe", "@", "@-"]).success();
+    let output = work_dir.run_jj(["log", "--no-graph", "-r", "..@", "-T", "description"]);
+    insta::assert_snapshot!(output, @r"
+    description of @
+    description of @-
+    [EOF]
+    ");
+
+    // Errors refer to the configured prefix
+    let short_id = |rev| {
+        let output = work_dir.run_jj(["log", "--no-graph", "-r", rev, "-T", "commit_id.short()"]);
+        output.success().stdout.raw().to_owned()
+    };
+    let (parent_hash, child_hash) = (short_id("@-"), short_id("@"));
+    std::fs::write(
+        &edit_script,
+        format!("write\nJJ: === {parent_hash} -------\ndescription\n"),
+    )
+    .unwrap();
+    let output = work_dir.run_jj(["describe", "@", "@-"]);
+    let output = output.normalize_stderr_with(|s| s.replace(&child_hash, "<child>"));
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: The description for the following commits were not found in the edited message: <child>
+    Hint: Each description must follow a `JJ: === <commit id>` line.
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_describe_multiple_commits_retry() {
+    let mut test_env = TestEnvironment::default();
+    let edit_script = test_env.set_up_fake_editor();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["new"]).success();
+
+    std::fs::write(&edit_script, "dump editor0").unwrap();
+    work_dir.run_jj(["describe", "@", "@-"]).success();
+    let content = std::fs::read_to_string(test_env.env_root().join("editor0")).unwrap();
+    let commit_hashes: Vec<_> = content
+        .lines()
+        .filter_map(|line| line.strip_prefix("JJ: describe "))
+        .map(|rest| rest.trim_end_matches([' ', '-']))
+        .collect();
+    let [parent_hash, child_hash] = commit_hashes[..] else {
+        panic!("unexpected separator lines in:\n{content}");
+    };
+    let normalize = |s: &str| {
+        s.replace(parent_hash, "<parent>")
+            .replace(child_hash, "<child>")
+    };
+    let duplicated = format!(
+        "JJ: describe {parent_hash} -------\nfirst description of @-\n\n\
+         JJ: describe {parent_hash} -------\nsecond description of @-\n\n\
+         JJ: describe {child_hash} -------\ndescription of @\n"
+    );
+    let fixed = format!(
+        "JJ: describe {parent_hash} -------\ndescription of @-\n\n\
+         JJ: describe {child_hash} -------\ndescription of @\n"
+    );
+
+    // Exiting the editor without making changes aborts
+    std::fs::write(
+        &edit_script,
+        format!("write\n{duplicated}\0next invocation\n\0dump editor1"),
+    )
+    .unwrap();
+    let output = work_dir.run_jj(["describe", "--retry", "@", "@-"]);
+    let output = output.normalize_stderr_with(|s| normalize(&s));
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: The following commits were found in the edited message multiple times: <parent>
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // The editor is reopened with the edited text and the error
+    std::fs::write(
+        &edit_script,
+        format!("write\n{duplicated}\0next invocation\n\0dump editor2\0write\n{fixed}"),
+    )
+    .unwrap();
+    work_dir
+        .run_jj(["describe", "--retry", "@", "@-"])
+        .success();
+    let content = std::fs::read_to_string(test_env.env_root().join("editor2")).unwrap();
+    insta::assert_snapshot!(normalize(&content), @r"
+    JJ: Error: The following commits were found in the edited message multiple times: <parent>
+    JJ: Fix the error, or exit the editor without making changes to abort.
+    JJ:
+    JJ: describe <parent> -------
+    first description of @-
+
+    JJ: describe <parent> -------
+    second description of @-
+
+    JJ: describe <child> -------
+    description of @
+    ");
+    let output = work_dir.run_jj(["log", "--no-graph", "-r", "..@", "-T", "description"]);
+    insta::assert_snapshot!(output, @r"
+    description of @
+    description of @-
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_multiple_message_args() {
     let test_env = TestEnvironment::default();
@@ -821,6 +1239,47 @@ fn test_describe_author() {
     "#);
 }
 
+#[test]
+fn test_describe_keep_committer() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.run_jj(["new"]).success();
+    work_dir.run_jj(["new"]).success();
+    let get_log = || {
+        let template = r#"separate(" ", committer.timestamp(), description.first_line())"#;
+        work_dir.run_jj(["log", "-r..", "-T", template])
+    };
+    insta::assert_snapshot!(get_log(), @r"
+    @  2001-02-03 04:05:09.000 +07:00
+    ○  2001-02-03 04:05:08.000 +07:00
+    ○  2001-02-03 04:05:07.000 +07:00
+    ~
+    [EOF]
+    ");
+
+    // Descriptions are updated, but the committers of the described commits
+    // are kept. The descendant is rebased as usual.
+    work_dir
+        .run_jj([
+            "describe",
+            "@-",
+            "@--",
+            "-m",
+            "described",
+            "--keep-committer",
+        ])
+        .success();
+    insta::assert_snapshot!(get_log(), @r"
+    @  2001-02-03 04:05:11.000 +07:00
+    ○  2001-02-03 04:05:08.000 +07:00 described
+    ○  2001-02-03 04:05:07.000 +07:00 described
+    ~
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_describe_avoids_unc() {
     let mut test_env = TestEnvironment::default();
@@ -530,6 +530,122 @@ fn test_diff_name_only() {
     ");
 }
 
+#[test]
+fn test_diff_conflicts_fileset() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.create_dir("src");
+    work_dir.write_file("README", "base\n");
+    work_dir.write_file("src/a", "base\n");
+    work_dir.write_file("src/b", "base\n");
+    work_dir.run_jj(["describe", "-m=base"]).success();
+    work_dir.run_jj(["new", "-m=left"]).success();
+    work_dir.write_file("README", "left\n");
+    work_dir.write_file("src/a", "left\n");
+    work_dir
+        .run_jj(["new", "description(base)", "-m=right"])
+        .success();
+    work_dir.write_file("README", "right\n");
+    work_dir.write_file("src/a", "right\n");
+    work_dir.write_file("src/b", "right\n");
+    work_dir
+        .run_jj(["new", "description(left)", "description(right)"])
+        .success();
+
+    // Intersection with a glob
+    let output = work_dir.run_jj([
+        "diff",
+        "--summary",
+        "--from=description(base)",
+        r#"conflicts() & glob:"src/**""#,
+    ]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    M src/a
+    [EOF]
+    ");
+    let output = work_dir.run_jj([
+        "diff",
+        "--summary",
+        "--from=description(base)",
+        "conflicts()",
+    ]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    M README
+    M src/a
+    [EOF]
+    ");
+
+    // Revision with no conflicts
+    let output = work_dir.run_jj(["diff", "--summary", "-r=description(right)", "conflicts()"]);
+    insta::assert_snapshot!(output, @"");
+
+    // Commands which can't resolve conflicts()
+    let output = work_dir.run_jj(["file", "show", "conflicts()"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: conflicts() can't be used with this command
+    Hint: It can be used with `jj diff`, `jj file list`, `jj log`, `jj resolve`, and the `files()` revset function
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = work_dir.run_jj(["log", "-r=diff_contains(left, conflicts())"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to parse revset: conflicts() can't be used in diff_contains()
+    Caused by:  --> 1:21
+      |
+    1 | diff_contains(left, conflicts())
+      |                     ^---------^
+      |
+      = conflicts() can't be used in diff_contains()
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_diff_file_metadata_fileset() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("a", "a\n");
+    work_dir.write_file("b", "b\n");
+    let output = work_dir.run_jj(["diff", "--summary", "type:file ~ b"]);
+    insta::assert_snapshot!(output, @r"
+    A a
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["diff", "--summary", "type:conflict"]);
+    insta::assert_snapshot!(output, @"");
+    work_dir.write_file("b", "longer\n");
+    let output = work_dir.run_jj(["diff", "--summary", "size(..3) & ~executable()"]);
+    insta::assert_snapshot!(output, @r"
+    A a
+    [EOF]
+    ");
+
+    // Commands which can't resolve the file metadata
+    let output = work_dir.run_jj(["file", "show", "size(..3)"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: size() can't be used with this command
+    Hint: It can be used with `jj diff`, `jj file list`, `jj log`, `jj resolve`, and the `files()` revset function
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = work_dir.run_jj(["file", "show", "type:file"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: type:file can't be used with this command
+    Hint: It can be used with `jj diff`, `jj file list`, `jj log`, `jj resolve`, and the `files()` revset function
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
 #[test]
 fn test_diff_bad_args() {
     let test_env = TestEnvironment::default();
@@ -3412,6 +3528,165 @@ fn test_diff_binary() {
     ");
 }
 
+#[test]
+fn test_diff_large_file() {
+    let mut test_env = TestEnvironment::default();
+    let edit_script = test_env.set_up_fake_diff_editor();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config("diff.max-file-size = 100");
+    let work_dir = test_env.work_dir("repo");
+
+    let large_content: String = (0..30).map(|i| format!("line {i}\n")).collect();
+    let added_content: String = (0..30).map(|i| format!("text {i}\n")).collect();
+    work_dir.write_file("large.txt", &large_content);
+    work_dir.write_file("removed.txt", &large_content);
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file("large.txt", format!("{large_content}extra\n"));
+    work_dir.remove_file("removed.txt");
+    work_dir.write_file("added.txt", &added_content);
+    work_dir.write_file("small.txt", "small\n");
+
+    let output = work_dir.run_jj(["diff"]);
+    insta::assert_snapshot!(output, @r"
+    Added regular file added.txt:
+        (file added, 230 bytes)
+    Modified regular file large.txt:
+        (file changed, 230 → 236 bytes)
+    Removed regular file removed.txt:
+        (file removed, 230 bytes)
+    Added regular file small.txt:
+            1: small
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["diff", "--git"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/added.txt b/added.txt
+    new file mode 100644
+    index 0000000000..dd724d58ab
+    Large files /dev/null and b/added.txt differ (0 → 230 bytes)
+    diff --git a/large.txt b/large.txt
+    index f3c2c5e9bd..cbc96c1118 100644
+    Large files a/large.txt and b/large.txt differ (230 → 236 bytes)
+    diff --git a/removed.txt b/removed.txt
+    deleted file mode 100644
+    index f3c2c5e9bd..0000000000
+    Large files a/removed.txt and /dev/null differ (230 → 0 bytes)
+    diff --git a/small.txt b/small.txt
+    new file mode 100644
+    index 0000000000..ac790413e2
+    --- /dev/null
+    +++ b/small.txt
+    @@ -0,0 +1,1 @@
+    +small
+    [EOF]
+    ");
+
+    // Large files are diffed as text if forced
+    let output = work_dir.run_jj(["diff", "--git", "--force-text-diff", "large.txt"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/large.txt b/large.txt
+    index f3c2c5e9bd..cbc96c1118 100644
+    --- a/large.txt
+    +++ b/large.txt
+    @@ -28,3 +28,4 @@
+     line 27
+     line 28
+     line 29
+    +extra
+    [EOF]
+    ");
+
+    // A limit of 0 disables the check
+    let output = work_dir.run_jj(["diff", "--config=diff.max-file-size=0", "large.txt"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file large.txt:
+        ...
+      28   28: line 27
+      29   29: line 28
+      30   30: line 29
+           31: extra
+    [EOF]
+    ");
+
+    // Lines of large files aren't counted
+    let output = work_dir.run_jj(["diff", "--stat"]);
+    insta::assert_snapshot!(output, @r"
+    added.txt   | 0
+    large.txt   | 0
+    removed.txt | 0
+    small.txt   | 1 +
+    4 files changed, 1 insertion(+), 0 deletions(-)
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["diff", "--stat", "--force-text-diff"]);
+    insta::assert_snapshot!(output, @r"
+    added.txt   | 30 ++++++++++++++++++++++++++++++
+    large.txt   |  1 +
+    removed.txt | 30 ------------------------------
+    small.txt   |  1 +
+    4 files changed, 32 insertions(+), 30 deletions(-)
+    [EOF]
+    ");
+
+    // Large files aren't passed to the file-by-file diff tool
+    std::fs::write(
+        &edit_script,
+        "print ==\0print-files-before\0print --\0print-files-after",
+    )
+    .unwrap();
+    let configs: &[_] = &[
+        "--tool=fake-diff-editor",
+        "--config=merge-tools.fake-diff-editor.diff-invocation-mode=file-by-file",
+    ];
+    let output = work_dir.run_jj_with(|cmd| cmd.arg("diff").args(configs));
+    insta::assert_snapshot!(output, @r"
+    Large file added.txt differs (0 → 230 bytes)
+    Large file large.txt differs (230 → 236 bytes)
+    Large file removed.txt differs (230 → 0 bytes)
+    ==
+    small.txt
+    --
+    small.txt
+    [EOF]
+    ");
+    let output = work_dir.run_jj_with(|cmd| {
+        cmd.args(["diff", "--force-text-diff", "large.txt"])
+            .args(configs)
+    });
+    insta::assert_snapshot!(output, @r"
+    ==
+    large.txt
+    --
+    large.txt
+    [EOF]
+    ");
+
+    // Renamed large file with the same contents has no content diff
+    work_dir.run_jj(["new"]).success();
+    work_dir.remove_file("added.txt");
+    work_dir.write_file("renamed.txt", &added_content);
+    let output = work_dir.run_jj(["diff", "--git"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/added.txt b/renamed.txt
+    rename from added.txt
+    rename to renamed.txt
+    [EOF]
+    ");
+
+    // Binary files are still detected if text diff is forced
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file("binary.bin", b"\0binary");
+    let output = work_dir.run_jj(["diff", "--git", "--force-text-diff"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/binary.bin b/binary.bin
+    new file mode 100644
+    index 0000000000..d33e3942cb
+    Binary files /dev/null and b/binary.bin differ
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_diff_revisions() {
     let test_env = TestEnvironment::default();
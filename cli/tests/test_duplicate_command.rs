@@ -2427,6 +2427,75 @@ fn test_rebase_duplicates() {
     ");
 }
 
+#[test]
+fn test_duplicate_provenance() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    create_commit(&work_dir, "a", &[]);
+    create_commit(&work_dir, "b", &["a"]);
+    work_dir.run_jj(["duplicate", "a"]).success();
+    work_dir.run_jj(["duplicate", "a"]).success();
+    // Provenance is preserved when the duplicate is rewritten
+    work_dir
+        .run_jj(["describe", "-r", "latest(duplicates_of(a))", "-m", "a2"])
+        .success();
+    // Duplicates are still found after the source is rewritten
+    work_dir
+        .run_jj(["describe", "-r", "a", "-m", "a1"])
+        .success();
+
+    let template = r#"
+    separate(" <- ", description.first_line(), duplicated_from.description().first_line()) ++ "\n"
+    "#;
+    let output = work_dir.run_jj([
+        "log",
+        "--no-graph",
+        "-r",
+        "duplicates_of(a)",
+        "-T",
+        template,
+    ]);
+    insta::assert_snapshot!(output, @r"
+    a2 <- a
+    a <- a
+    [EOF]
+    ");
+    let output = work_dir.run_jj([
+        "log",
+        "--no-graph",
+        "-r",
+        "duplicates_of(b)",
+        "-T",
+        template,
+    ]);
+    insta::assert_snapshot!(output, @"");
+
+    // The duplication is recorded in the operation
+    let output = work_dir.run_jj([
+        "op",
+        "log",
+        "--no-graph",
+        "-n4",
+        "-T",
+        r#"description ++ "\n" ++ indent("  ", tags) ++ "\n""#,
+    ]);
+    insta::assert_snapshot!(output, @r"
+    describe commit 2443ea76b0b1c531326908326aab7020abab8e6c
+      args: jj describe -r a -m a1
+    describe commit c4a317cb175b4609e19a617d91b363ef28b0c7ee
+      args: jj describe -r 'latest(duplicates_of(a))' -m a2
+    duplicate 1 commit(s)
+      args: jj duplicate a
+      duplicated: 2443ea76b0b1c531326908326aab7020abab8e6c:c4a317cb175b4609e19a617d91b363ef28b0c7ee
+    duplicate 1 commit(s)
+      args: jj duplicate a
+      duplicated: 2443ea76b0b1c531326908326aab7020abab8e6c:d244568d7a2885ea1459248010733158e8215099
+    [EOF]
+    ");
+}
+
 #[must_use]
 fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
     let template = r#"commit_id.short() ++ "   " ++ description.first_line()"#;
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt as _;
 use std::path;
 
 use indoc::formatdoc;
@@ -950,6 +952,211 @@ fn test_git_clone_with_depth_subprocess() {
     ");
 }
 
+#[cfg(unix)]
+#[test]
+fn test_git_clone_resume() {
+    let test_env = TestEnvironment::default();
+    let root_dir = test_env.work_dir("");
+    test_env.add_config("git.auto-local-bookmark = true");
+    let git_repo_path = test_env.env_root().join("source");
+    let git_repo = git::init(git_repo_path);
+    let main_commit = git::add_commit(
+        &git_repo,
+        "refs/heads/main",
+        "file",
+        b"content",
+        "message",
+        &[],
+    );
+    // Incompressible content, so the pack is large enough to be cut off
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let big_content = (0..200_000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect::<Vec<_>>();
+    git::add_commit(
+        &git_repo,
+        "refs/heads/big",
+        "big",
+        &big_content,
+        "big",
+        &[main_commit.commit_id],
+    );
+    git::set_symbolic_reference(&git_repo, "HEAD", "refs/heads/main");
+
+    // Drop the connection in the middle of the transfer by truncating the
+    // pack sent by the remote. Only the pack of the second batch, which
+    // includes the big file, is large enough to be cut off.
+    let hook_path = test_env.env_root().join("truncate-pack");
+    std::fs::write(&hook_path, "#!/bin/sh\n\"$@\" | head -c 50000\n").unwrap();
+    std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let git_path = test_env.env_root().join("git-with-truncated-pack");
+    std::fs::write(
+        &git_path,
+        formatdoc! {r#"
+            #!/bin/sh
+            exec git \
+                -c "remote.origin.uploadpack=git -c uploadpack.packObjectsHook={hook} upload-pack" \
+                "$@"
+            "#,
+            hook = hook_path.display(),
+        },
+    )
+    .unwrap();
+    std::fs::set_permissions(&git_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let git_path_arg = format!(
+        "--config=git.executable-path={}",
+        to_toml_value(git_path.to_str().unwrap())
+    );
+    let run_interrupted_clone = |args: &[&str]| {
+        root_dir.run_jj(itertools::chain(
+            [git_path_arg.as_str()],
+            args.iter().copied(),
+        ))
+    };
+
+    let output = run_interrupted_clone(&["git", "clone", "source", "clone"]);
+    assert!(!output.status.success());
+    insta::assert_snapshot!(output.normalize_stderr_with(|s| {
+        s.lines()
+            .filter(|line| !line.starts_with("fatal:"))
+            .map(|line| format!("{line}\n"))
+            .collect()
+    }), @r#"
+    ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/clone"
+    Kept the interrupted clone in "$TEST_ENV/clone"
+    Error: Git process failed: External git program failed:
+
+    Hint: Run the same command again to resume the clone, or add --no-resume to start over
+    [EOF]
+    [exit status: 1]
+    "#);
+    let clone_dir = test_env.work_dir("clone");
+    assert!(clone_dir.root().join(".jj/git-clone-state.toml").exists());
+
+    // The interrupted clone can't be resumed from another source or with
+    // other options
+    root_dir.create_dir("other");
+    let output = root_dir.run_jj(["git", "clone", "other", "clone"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Destination path contains an interrupted clone of $TEST_ENV/source with different options
+    Hint: Resume it with `jj git clone --remote origin $TEST_ENV/source`, or use --no-resume to discard it and start over
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = root_dir.run_jj(["git", "clone", "--colocate", "source", "clone"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Destination path contains an interrupted clone of $TEST_ENV/source with different options
+    Hint: Resume it with `jj git clone --remote origin $TEST_ENV/source`, or use --no-resume to discard it and start over
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // --no-resume discards the interrupted clone and starts over
+    let output = run_interrupted_clone(&["git", "clone", "source", "clone2"]);
+    assert!(!output.status.success());
+    let clone2_dir = test_env.work_dir("clone2");
+    assert!(clone2_dir.root().join(".jj").exists());
+    let output = root_dir.run_jj(["git", "clone", "--no-resume", "source", "clone2"]);
+    assert!(output.status.success(), "{output}");
+    assert!(output.stderr.raw().starts_with("Fetching into new repo in"));
+    assert!(clone2_dir.root().join("file").exists());
+
+    // With --no-resume, failed clone is cleaned up
+    let output = run_interrupted_clone(&["git", "clone", "--no-resume", "source", "clone3"]);
+    assert!(!output.status.success());
+    assert!(
+        !output.stderr.raw().contains("resume the clone"),
+        "{output}"
+    );
+    assert!(!test_env.env_root().join("clone3").exists());
+
+    // The main branch was fetched before the connection dropped. Remove its
+    // file from the remote, so the clone can only be resumed if the fetched
+    // objects are reused.
+    let blob_hex = git_repo
+        .find_commit(main_commit.commit_id)
+        .unwrap()
+        .tree()
+        .unwrap()
+        .find_entry("file")
+        .unwrap()
+        .object_id()
+        .to_string();
+    let object_path = git_repo
+        .git_dir()
+        .join("objects")
+        .join(&blob_hex[..2])
+        .join(&blob_hex[2..]);
+    std::fs::remove_file(object_path).unwrap();
+
+    // Resume the clone once the connection is back
+    let output = root_dir.run_jj(["git", "clone", "source", "clone"]);
+    insta::assert_snapshot!(output, @r#"
+    ------- stderr -------
+    Resuming interrupted clone in "$TEST_ENV/clone"
+    Fetching into new repo in "$TEST_ENV/clone"
+    bookmark: big@origin  [new] tracked
+    bookmark: main@origin [new] tracked
+    Setting the revset alias `trunk()` to `main@origin`
+    Working copy  (@) now at: yqosqzyt b30ef62d (empty) (no description set)
+    Parent commit (@-)      : qomsplrm ebeb70d8 main | message
+    Added 1 files, modified 0 files, removed 0 files
+    [EOF]
+    "#);
+    assert_eq!(
+        std::fs::read(clone_dir.root().join("file")).unwrap(),
+        b"content"
+    );
+    assert!(!clone_dir.root().join(".jj/git-clone-state.toml").exists());
+    clone_dir.run_jj(["new", "big@origin"]).success();
+    assert_eq!(
+        std::fs::read(clone_dir.root().join("big")).unwrap(),
+        big_content
+    );
+}
+
+#[test]
+fn test_git_clone_failure_not_kept() {
+    let test_env = TestEnvironment::default();
+    let root_dir = test_env.work_dir("");
+    let git_repo_path = test_env.env_root().join("source");
+    let git_repo = git::init(git_repo_path);
+    let commit_result = git::add_commit(
+        &git_repo,
+        "refs/heads/main",
+        "file",
+        b"content",
+        "message",
+        &[],
+    );
+    git::set_symbolic_reference(&git_repo, "HEAD", "refs/heads/main");
+
+    // A corrupt remote fails the same way every time, so the destination
+    // isn't kept for resuming
+    let tree_hex = commit_result.tree_id.to_string();
+    let object_path = git_repo
+        .git_dir()
+        .join("objects")
+        .join(&tree_hex[..2])
+        .join(&tree_hex[2..]);
+    std::fs::remove_file(object_path).unwrap();
+    let output = root_dir.run_jj(["git", "clone", "source", "clone"]);
+    assert!(!output.status.success());
+    assert!(
+        !output.stderr.raw().contains("resume the clone"),
+        "{output}"
+    );
+    assert!(!test_env.env_root().join("clone").exists());
+}
+
 #[cfg_attr(feature = "git2", test_case(false; "use git2 for remote calls"))]
 #[test_case(true; "spawn a git subprocess for remote calls")]
 fn test_git_clone_invalid_immutable_heads(subprocess: bool) {
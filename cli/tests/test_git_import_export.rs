@@ -88,6 +88,66 @@ fn test_git_export_conflicting_git_refs() {
     });
 }
 
+#[test]
+fn test_git_export_bookmark_pattern() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    let git_repo = git::open(work_dir.root().join(".jj/repo/store/git"));
+
+    work_dir
+        .run_jj(["new", "--no-edit", "root()", "-mother"])
+        .success();
+    work_dir
+        .run_jj(["bookmark", "create", "-r@", "feature1", "feature2", "main"])
+        .success();
+    // Make "main" conflicted by concurrent operation
+    work_dir
+        .run_jj([
+            "bookmark",
+            "create",
+            "--at-op=@-",
+            "-rdescription(other)",
+            "main",
+        ])
+        .success();
+
+    // Only the matching bookmarks are exported
+    let output = work_dir.run_jj(["git", "export", "--bookmark=glob:feature*"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Concurrent modification detected, resolving automatically.
+    [EOF]
+    ");
+    insta::assert_debug_snapshot!(get_git_repo_refs(&git_repo), @r#"
+    [
+        (
+            "refs/heads/feature1",
+            CommitId(
+                "230dd059e1b059aefc0da06a2e5a7dbf22362f22",
+            ),
+        ),
+        (
+            "refs/heads/feature2",
+            CommitId(
+                "230dd059e1b059aefc0da06a2e5a7dbf22362f22",
+            ),
+        ),
+    ]
+    "#);
+
+    // Conflicted bookmark is reported
+    let output = work_dir.run_jj(["git", "export"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    Warning: Conflicted bookmarks were not exported:
+      main@git
+    [EOF]
+    ");
+    assert!(git_repo.find_reference("refs/heads/main").is_err());
+}
+
 #[test]
 fn test_git_export_undo() {
     let test_env = TestEnvironment::default();
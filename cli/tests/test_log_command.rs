@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use itertools::Itertools as _;
+
 use crate::common::to_toml_value;
 use crate::common::TestEnvironment;
 
@@ -718,7 +720,10 @@ fn test_log_prefix_highlight_counts_hidden_commits() {
     ◆  Change zzz[zzzzzzzzz] 00[0000000000]
     [EOF]
     ");
-    insta::assert_snapshot!(work_dir.run_jj(["log", "-r", "4", "-T", prefix_format]), @r"
+    // The hint lists the unambiguous prefixes of the hidden commits, too.
+    let output = work_dir.run_jj(["log", "-r", "4", "-T", prefix_format]);
+    assert!(output.stderr.raw().contains("`44`"), "{output}");
+    insta::assert_snapshot!(output.strip_stderr_last_line(), @r"
     ------- stderr -------
     Error: Commit ID prefix `4` is ambiguous
     [EOF]
@@ -765,6 +770,44 @@ fn test_log_short_shortest_length_parameter() {
     ");
 }
 
+#[test]
+fn test_log_fixed_length_short_id() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["new", "-m", "second"]).success();
+
+    // Unlike shortest(), the rendered ids don't get longer as the repo grows,
+    // so they can be used as stable references.
+    let template = r#"change_id.short_stable(4) ++ " " ++ commit_id.short_stable(6) ++ "\n""#;
+    let output = work_dir.run_jj(["log", "--no-graph", "--color=always", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    [1m[38;5;5mr[0m[38;5;8mlvk[39m [1m[38;5;4m5[0m[38;5;8m8331a[39m
+    [1m[38;5;5mq[0m[38;5;8mpvu[39m [1m[38;5;4m2[0m[38;5;8m30dd0[39m
+    [1m[38;5;5mz[0m[38;5;8mzzz[39m [1m[38;5;4m0[0m[38;5;8m00000[39m
+    [EOF]
+    ");
+
+    // The default templates can be configured to render fixed-length ids
+    test_env.add_config("ui.fixed-id-length = 4");
+    let output = work_dir.run_jj(["log"]);
+    insta::assert_snapshot!(output, @r"
+    @  rlvk test.user@example.com 2001-02-03 08:05:08 5833
+    │  (empty) second
+    ○  qpvu test.user@example.com 2001-02-03 08:05:07 230d
+    │  (empty) (no description set)
+    ◆  zzzz root() 0000
+    [EOF]
+    ");
+
+    // The fixed-length prefix can be resolved
+    let output = work_dir.run_jj(["log", "--no-graph", "-T", template, "-r", "qpvu"]);
+    insta::assert_snapshot!(output, @r"
+    qpvu 230dd0
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_log_author_format() {
     let test_env = TestEnvironment::default();
@@ -853,6 +896,117 @@ fn test_log_reversed() {
     ");
 }
 
+#[test]
+fn test_log_reversed_ancestors() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.run_jj(["describe", "-m", "a"]).success();
+    work_dir.run_jj(["new", "-m", "b"]).success();
+    work_dir.run_jj(["new", "-m", "c", "@-"]).success();
+    work_dir
+        .run_jj(["new", "-m", "d", "description(b)", "description(c)"])
+        .success();
+    work_dir
+        .run_jj(["new", "-m", "e", "description(a)"])
+        .success();
+
+    // Ancestors are shown from the roots without reading the whole set first,
+    // which should give the same output as the buffered path used with --limit
+    let template = r#"if(root, "root", description.first_line()) ++ "\n""#;
+    let output = work_dir.run_jj(["log", "-r::", "-T", template, "--reversed", "--no-graph"]);
+    insta::assert_snapshot!(output, @r"
+    root
+    a
+    b
+    c
+    d
+    e
+    [EOF]
+    ");
+    let buffered_output = work_dir.run_jj([
+        "log",
+        "-r::",
+        "-T",
+        template,
+        "--reversed",
+        "--no-graph",
+        "--limit=100",
+    ]);
+    assert_eq!(output.stdout.raw(), buffered_output.stdout.raw());
+    let buffered_output = work_dir.run_jj([
+        "log",
+        "-r",
+        r#"description(regex:"^[a-e]") | root()"#,
+        "-T",
+        template,
+        "--reversed",
+        "--no-graph",
+    ]);
+    assert_eq!(output.stdout.raw(), buffered_output.stdout.raw());
+
+    // The graph is emitted from the roots, too
+    let output = work_dir.run_jj(["log", "-r::", "-T", template, "--reversed"]);
+    let buffered_output =
+        work_dir.run_jj(["log", "-r::", "-T", template, "--reversed", "--limit=100"]);
+    assert_eq!(output.stdout.raw(), buffered_output.stdout.raw());
+}
+
+#[test]
+fn test_log_delimited() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir
+        .run_jj(["describe", "-m", "first\n\nwith body"])
+        .success();
+    work_dir.run_jj(["new", "-m", "second"]).success();
+
+    // Records are terminated by NUL by default, so descriptions containing
+    // newlines can be split unambiguously
+    let output = work_dir.run_jj(["log", "-T", "description", "--no-graph", "--delimited"]);
+    let output = output.success();
+    let records = output.stdout.raw().split('\0').collect_vec();
+    assert_eq!(records, ["second\n", "first\n\nwith body\n", "", ""]);
+
+    // Custom delimiter
+    let output = work_dir.run_jj([
+        "log",
+        "-r=@-::",
+        "-T",
+        "description.first_line()",
+        "--no-graph",
+        "--delimited=;",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    second;first;[EOF]
+    ");
+
+    // The delimiter must not appear in the output
+    let output = work_dir.run_jj([
+        "log",
+        "-r=root()",
+        "-T",
+        r#""a\nb""#,
+        "--no-graph",
+        "--delimited=\n",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: The output of commit 000000000000 contains the delimiter
+    Hint: Use a different delimiter, or remove it from the template output.
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // --no-graph is required
+    let output = work_dir.run_jj(["log", "--delimited"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stderr.raw().contains("--no-graph"));
+}
+
 #[test]
 fn test_log_filtered_by_path() {
     let test_env = TestEnvironment::default();
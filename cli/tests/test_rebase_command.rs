@@ -456,6 +456,66 @@ fn test_rebase_single_revision_merge_parent() {
     ");
 }
 
+#[test]
+fn test_rebase_single_revision_merge_keep_parents() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    create_commit(&work_dir, "a", &[]);
+    create_commit(&work_dir, "b", &["a"]);
+    create_commit(&work_dir, "c", &["a"]);
+    create_commit(&work_dir, "d", &["b"]);
+    create_commit(&work_dir, "m", &["b", "c"]);
+    let parents_template = r#"parents.map(|c| c.bookmarks()) ++ "\n""#;
+
+    // Only a single revision can be rebased
+    let output = work_dir.run_jj(["rebase", "-r", "c|d", "-d", "a", "--keep-parents"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: --keep-parents and --drop-parent can only be used to rebase a single revision
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // Only the parent related to the destination should be replaced
+    work_dir
+        .run_jj(["rebase", "-r", "m", "-d", "d", "--keep-parents"])
+        .success();
+    let output = work_dir.run_jj(["log", "--no-graph", "-r", "m", "-T", parents_template]);
+    insta::assert_snapshot!(output, @r"
+    d c
+    [EOF]
+    ");
+
+    // The rebased merge should have the same tree as a fresh merge of the new
+    // parents, plus its own change
+    work_dir.run_jj(["new", "d", "c"]).success();
+    let output = work_dir.run_jj(["diff", "--summary", "--from", "@", "--to", "m"]);
+    insta::assert_snapshot!(output, @r"
+    A m
+    [EOF]
+    ");
+
+    // A parent can be dropped from the merge
+    work_dir
+        .run_jj(["rebase", "-r", "m", "-d", "d", "--drop-parent", "c"])
+        .success();
+    let output = work_dir.run_jj(["log", "--no-graph", "-r", "m", "-T", parents_template]);
+    insta::assert_snapshot!(output, @r"
+    d
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "list", "-r", "m"]);
+    insta::assert_snapshot!(output, @r"
+    a
+    b
+    d
+    m
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_rebase_multiple_revisions() {
     let test_env = TestEnvironment::default();
@@ -129,16 +129,16 @@ fn test_bad_function_call() {
     [exit status: 1]
     ");
 
-    let output = work_dir.run_jj(["log", "-r", "heads(foo, bar)"]);
+    let output = work_dir.run_jj(["log", "-r", "heads()"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Error: Failed to parse revset: Function `heads`: Expected 1 arguments
+    Error: Failed to parse revset: Function `heads`: Expected at least 1 arguments
     Caused by:  --> 1:7
       |
-    1 | heads(foo, bar)
-      |       ^------^
+    1 | heads()
+      |       ^
       |
-      = Function `heads`: Expected 1 arguments
+      = Function `heads`: Expected at least 1 arguments
     [EOF]
     [exit status: 1]
     ");
@@ -290,6 +290,37 @@ fn test_bad_function_call() {
     [exit status: 1]
     ");
 
+    let output = work_dir.run_jj(["log", "-r", "branches()"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to parse revset: Function `branches` doesn't exist
+    Caused by:  --> 1:1
+      |
+    1 | branches()
+      | ^------^
+      |
+      = Function `branches` doesn't exist
+    Hint: `branches` was renamed to `bookmarks`
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // A typo of an existing function isn't reported as a rename
+    let output = work_dir.run_jj(["log", "-r", "conflict()"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to parse revset: Function `conflict` doesn't exist
+    Caused by:  --> 1:1
+      |
+    1 | conflict()
+      | ^------^
+      |
+      = Function `conflict` doesn't exist
+    Hint: Did you mean `conflicts`, `connected`?
+    [EOF]
+    [exit status: 1]
+    ");
+
     let output = work_dir.run_jj(["log", "-r", "remote_bookmarks(a, b, remote=c)"]);
     insta::assert_snapshot!(output, @r#"
     ------- stderr -------
@@ -540,7 +571,7 @@ fn test_alias() {
     1 | recurse
       | ^-----^
       |
-      = Alias `recurse` expanded recursively
+      = Alias `recurse` expanded recursively: recurse -> recurse1 -> recurse2() -> recurse
     [EOF]
     [exit status: 1]
     ");
@@ -570,6 +601,134 @@ fn test_alias_override() {
     ");
 }
 
+#[test]
+fn test_bad_suggestion_distance_config() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    let output = work_dir.run_jj(["log", "-r", "mian", "--config=ui.suggestion-distance='x'"]);
+    insta::assert_snapshot!(output, @r#"
+    ------- stderr -------
+    Error: Invalid type or value for ui.suggestion-distance
+    Caused by: invalid type: string "x", expected usize
+
+    [EOF]
+    [exit status: 1]
+    "#);
+}
+
+#[test]
+fn test_case_insensitive_refs() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config("revsets.case-insensitive-refs = true");
+    test_env.add_config("revset-aliases.'my-main' = 'root()..Main'");
+    test_env.add_config("revset-aliases.'since_root(x)' = 'root()..x'");
+    work_dir
+        .run_jj(["bookmark", "create", "-r@", "main"])
+        .success();
+
+    let template = r#"bookmarks ++ "\n""#;
+    let output = work_dir.run_jj(["log", "--no-graph", "-T", template, "-r", "root()..Main"]);
+    insta::assert_snapshot!(output, @r"
+    main
+    [EOF]
+    ------- stderr -------
+    Warning: In revset expression
+     --> 1:9
+      |
+    1 | root()..Main
+      |         ^--^
+      |
+      = Resolved `Main` to `main` by case-insensitive match
+    [EOF]
+    ");
+
+    // Warnings for symbols in aliases point at the alias
+    let output = work_dir.run_jj(["log", "--no-graph", "-T", template, "-r", "my-main"]);
+    insta::assert_snapshot!(output, @r"
+    main
+    [EOF]
+    ------- stderr -------
+    Warning: In revset expression
+     --> 1:1
+      |
+    1 | my-main
+      | ^-----^
+      |
+      = In alias `my-main`
+     --> 1:9
+      |
+    1 | root()..Main
+      |         ^--^
+      |
+      = Resolved `Main` to `main` by case-insensitive match
+    [EOF]
+    ");
+
+    // Warnings for symbols passed to alias functions point at the argument
+    let output = work_dir.run_jj([
+        "log",
+        "--no-graph",
+        "-T",
+        template,
+        "-r",
+        "since_root(Main)",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    main
+    [EOF]
+    ------- stderr -------
+    Warning: In revset expression
+     --> 1:1
+      |
+    1 | since_root(Main)
+      | ^--------------^
+      |
+      = In alias `since_root(x)`
+     --> 1:9
+      |
+    1 | root()..x
+      |         ^
+      |
+      = In function parameter `x`
+     --> 1:12
+      |
+    1 | since_root(Main)
+      |            ^--^
+      |
+      = Resolved `Main` to `main` by case-insensitive match
+    [EOF]
+    ");
+
+    // Warnings are reported for each argument
+    let output = work_dir.run_jj([
+        "log",
+        "--no-graph",
+        "-T",
+        template,
+        "-r",
+        "main",
+        "-r",
+        "MAIN",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    main
+    [EOF]
+    ------- stderr -------
+    Warning: In revset expression
+     --> 1:1
+      |
+    1 | MAIN
+      | ^--^
+      |
+      = Resolved `MAIN` to `main` by case-insensitive match
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_bad_alias_decl() {
     let test_env = TestEnvironment::default();
@@ -182,6 +182,117 @@ fn test_sparse_manage_patterns() {
     ");
 }
 
+#[test]
+fn test_sparse_set_from_file() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("file1", "contents");
+    work_dir.write_file("file2", "contents");
+    work_dir.write_file("dir/file3", "contents");
+    work_dir.write_file("dir/file4", "contents");
+
+    std::fs::write(
+        test_env.env_root().join("patterns"),
+        "# Comment\ndir\n\n  file1  \n",
+    )
+    .unwrap();
+
+    // Dry run prints the patterns and the number of files to be updated
+    let output = work_dir.run_jj(["sparse", "set", "--from-file=../patterns", "--dry-run"]);
+    insta::assert_snapshot!(output, @r"
+    dir
+    file1
+    [EOF]
+    ------- stderr -------
+    Would add 0 files, remove 1 files
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["sparse", "list"]);
+    insta::assert_snapshot!(output, @r"
+    .
+    [EOF]
+    ");
+
+    // Patterns from file can be combined with --add/--remove
+    let output = work_dir.run_jj([
+        "sparse",
+        "set",
+        "--from-file=../patterns",
+        "--remove=file1",
+        "--add=file2",
+        "--dry-run",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    dir
+    file2
+    [EOF]
+    ------- stderr -------
+    Would add 0 files, remove 1 files
+    [EOF]
+    ");
+    let output = work_dir.run_jj([
+        "sparse",
+        "set",
+        "--from-file=../patterns",
+        "--remove=file1",
+        "--add=file2",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Added 0 files, modified 0 files, removed 1 files
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["sparse", "list"]);
+    insta::assert_snapshot!(output, @r"
+    dir
+    file2
+    [EOF]
+    ");
+
+    // Reapplying the same patterns is a no-op
+    let output = work_dir.run_jj([
+        "sparse",
+        "set",
+        "--from-file=../patterns",
+        "--remove=file1",
+        "--add=file2",
+    ]);
+    insta::assert_snapshot!(output, @"");
+
+    // Patterns from file replace the current patterns
+    let output = work_dir.run_jj(["sparse", "set", "--from-file=../patterns", "--dry-run"]);
+    insta::assert_snapshot!(output, @r"
+    dir
+    file1
+    [EOF]
+    ------- stderr -------
+    Would add 1 files, remove 1 files
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["sparse", "set", "--from-file=../patterns"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Added 1 files, modified 0 files, removed 1 files
+    [EOF]
+    ");
+    assert!(work_dir.root().join("file1").exists());
+    assert!(!work_dir.root().join("file2").exists());
+    assert!(work_dir.root().join("dir").join("file3").exists());
+
+    // Invalid pattern
+    std::fs::write(test_env.env_root().join("patterns"), "dir\n../file1\n").unwrap();
+    let output = work_dir.run_jj(["sparse", "set", "--from-file=../patterns"]);
+    insta::assert_snapshot!(output, @r#"
+    ------- stderr -------
+    Error: Failed to parse sparse pattern at ../patterns:2: ../file1
+    Caused by: Invalid component ".." in repo-relative path "../file1"
+    [EOF]
+    [exit status: 1]
+    "#);
+}
+
 #[test]
 fn test_sparse_editor_avoids_unc() {
     use std::path::PathBuf;
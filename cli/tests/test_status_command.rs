@@ -41,6 +41,32 @@ fn test_status_copies() {
     ");
 }
 
+#[cfg(all(feature = "watchman", unix))]
+#[test]
+fn test_status_fsmonitor_unavailable() {
+    let mut test_env = TestEnvironment::default();
+    // Point the Watchman client to a socket that doesn't exist
+    let sock_path = test_env.env_root().join("watchman.sock");
+    test_env.add_env_var("WATCHMAN_SOCK", sock_path.to_str().unwrap());
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"core.fsmonitor = "watchman""#);
+    let work_dir = test_env.work_dir("repo");
+
+    // The working copy is still snapshotted by crawling it
+    work_dir.write_file("file", "contents");
+    let output = work_dir.run_jj(["status"]).success();
+    assert!(output.stdout.raw().contains("A file\n"));
+    // The underlying connection error is platform-dependent
+    let stderr = output.stderr.normalize_with(|s| {
+        let (head, _) = s.split_once("Watchman: ").unwrap();
+        format!("{head}Watchman: <error>\n")
+    });
+    insta::assert_snapshot!(stderr, @r"
+    Warning: Filesystem monitor is configured but unavailable: Could not connect to Watchman: <error>
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_status_merge() {
     let test_env = TestEnvironment::default();
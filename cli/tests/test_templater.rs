@@ -70,7 +70,7 @@ fn test_templater_parse_error() {
       |           ^-----^
       |
       = Method `shorter` doesn't exist for type `CommitOrChangeId`
-    Hint: Did you mean `short`, `shortest`?
+    Hint: Did you mean `short`, `short_stable`, `shortest`?
     [EOF]
     [exit status: 1]
     ");
@@ -116,7 +116,7 @@ fn test_templater_parse_error() {
       |    ^--^
       |
       = Method `sort` doesn't exist for type `CommitOrChangeId`
-    Hint: Did you mean `short`, `shortest`?
+    Hint: Did you mean `short`, `short_stable`, `shortest`?
     [EOF]
     [exit status: 1]
     ");
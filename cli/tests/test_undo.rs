@@ -407,7 +407,7 @@ fn test_bookmark_track_untrack_undo() {
     [EOF]
     ");
 
-    work_dir.run_jj(["undo"]).success();
+    work_dir.run_jj(["redo"]).success();
     insta::assert_snapshot!(get_bookmark_output(&work_dir), @r"
     feature1: qpvuntsm 8da1cfc8 (empty) commit
     feature1@origin: qpvuntsm 8da1cfc8 (empty) commit
@@ -435,37 +435,89 @@ fn test_bookmark_track_untrack_undo() {
 }
 
 #[test]
-fn test_shows_a_warning_when_undoing_an_undo_operation_as_bare_jj_undo() {
+fn test_undo_redo_consecutive() {
     let test_env = TestEnvironment::default();
     test_env.run_jj_in(".", ["git", "init", "repo"]).success();
     let work_dir = test_env.work_dir("repo");
 
-    // Double-undo creation of child
-    work_dir.run_jj(["new"]).success();
+    let state0 = get_log_output(&work_dir);
+    work_dir.run_jj(["new", "-m", "first"]).success();
+    let state1 = get_log_output(&work_dir);
+    work_dir.run_jj(["new", "-m", "second"]).success();
+    let state2 = get_log_output(&work_dir);
+    work_dir.run_jj(["new", "-m", "third"]).success();
+    let state3 = get_log_output(&work_dir);
+
+    // Consecutive undos walk back the history
     work_dir.run_jj(["undo"]).success();
-    let output = work_dir.run_jj(["undo"]);
-    insta::assert_snapshot!(output, @r"
+    assert_eq!(get_log_output(&work_dir), state2);
+    work_dir.run_jj(["undo"]).success();
+    assert_eq!(get_log_output(&work_dir), state1);
+
+    // Consecutive redos reapply the undone operations in reverse order
+    let output = work_dir.run_jj(["redo"]).success();
+    assert!(output.stderr.raw().starts_with("Redid operation: "));
+    assert!(output.stderr.raw().contains("new empty commit"));
+    assert_eq!(get_log_output(&work_dir), state2);
+    work_dir.run_jj(["redo"]).success();
+    assert_eq!(get_log_output(&work_dir), state3);
+    insta::assert_snapshot!(work_dir.run_jj(["redo"]), @r"
     ------- stderr -------
-    Undid operation: 2d5b73a97567 (2001-02-03 08:05:09) undo operation 289cb69a8458456474a77cc432e8009b99f039cdcaf19ba4526753e97d70fee3fd0f410ff2b7c1d10cf0c2501702e7a85d58f9d813cdca567c377431ec4d2b97
-    Working copy  (@) now at: rlvkpnrz 65b6b74e (empty) (no description set)
-    Parent commit (@-)      : qpvuntsm 230dd059 (empty) (no description set)
-    Hint: This action reverted an 'undo' operation. The repository is now in the same state as it was before the original 'undo'.
-    Hint: If your goal is to undo multiple operations, consider using `jj op log` to see past states, and `jj op restore` to restore one of these states.
+    Error: Nothing to redo
+    Hint: Only operations undone by the latest consecutive `jj undo` commands can be redone. Use `jj op log` to find an earlier state and `jj op restore` to restore it.
     [EOF]
+    [exit status: 1]
     ");
 
-    // Double-undo creation of sibling
-    work_dir.run_jj(["new", "@-"]).success();
+    // Undo and redo can be interleaved
     work_dir.run_jj(["undo"]).success();
-    let output = work_dir.run_jj(["undo"]);
-    insta::assert_snapshot!(output, @r"
+    work_dir.run_jj(["undo"]).success();
+    work_dir.run_jj(["redo"]).success();
+    assert_eq!(get_log_output(&work_dir), state2);
+    work_dir.run_jj(["undo"]).success();
+    assert_eq!(get_log_output(&work_dir), state1);
+    work_dir.run_jj(["undo"]).success();
+    assert_eq!(get_log_output(&work_dir), state0);
+    work_dir.run_jj(["redo"]).success();
+    work_dir.run_jj(["redo"]).success();
+    work_dir.run_jj(["redo"]).success();
+    assert_eq!(get_log_output(&work_dir), state3);
+
+    // Another operation after undo discards the redo history
+    work_dir.run_jj(["undo"]).success();
+    work_dir
+        .run_jj(["describe", "-m", "second (edited)"])
+        .success();
+    insta::assert_snapshot!(work_dir.run_jj(["redo"]), @r"
     ------- stderr -------
-    Undid operation: b16799358b33 (2001-02-03 08:05:12) undo operation b14487c6d6d98f7f575ea03c48ed92d899c2a0ecbe9458221b6fc11af2bf6d918c9620cae1f8268012b0e25c7dd6f78b19ec628d0504a0830dc562d6625ba9ec
-    Working copy  (@) now at: mzvwutvl 167f90e7 (empty) (no description set)
-    Parent commit (@-)      : qpvuntsm 230dd059 (empty) (no description set)
-    Hint: This action reverted an 'undo' operation. The repository is now in the same state as it was before the original 'undo'.
-    Hint: If your goal is to undo multiple operations, consider using `jj op log` to see past states, and `jj op restore` to restore one of these states.
+    Error: Cannot redo because another operation was run after `jj undo`
+    Hint: Use `jj op log` to find the undone operation and `jj op restore` to restore it.
+    [EOF]
+    [exit status: 1]
+    ");
+    work_dir.run_jj(["undo"]).success();
+    assert_eq!(get_log_output(&work_dir), state2);
+}
+
+#[test]
+fn test_undo_redo_partial() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.run_jj(["new", "-m", "first"]).success();
+    let state1 = get_log_output(&work_dir);
+    work_dir.run_jj(["new", "-m", "second"]).success();
+
+    // An undo of some portions of the view doesn't join the undo chain
+    work_dir.run_jj(["undo", "--what=repo"]).success();
+    assert_eq!(get_log_output(&work_dir), state1);
+    insta::assert_snapshot!(work_dir.run_jj(["redo"]), @r"
+    ------- stderr -------
+    Error: Nothing to redo
+    Hint: Only operations undone by the latest consecutive `jj undo` commands can be redone. Use `jj op log` to find an earlier state and `jj op restore` to restore it.
     [EOF]
+    [exit status: 1]
     ");
 }
 
@@ -482,15 +534,45 @@ fn test_shows_no_warning_when_undoing_a_specific_undo_change() {
     let output = work_dir.run_jj(["undo", &op_id_hex]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: 2d5b73a97567 (2001-02-03 08:05:09) undo operation 289cb69a8458456474a77cc432e8009b99f039cdcaf19ba4526753e97d70fee3fd0f410ff2b7c1d10cf0c2501702e7a85d58f9d813cdca567c377431ec4d2b97
+    Undid operation: 3e21bf30fe49 (2001-02-03 08:05:09) undo operation 289cb69a8458456474a77cc432e8009b99f039cdcaf19ba4526753e97d70fee3fd0f410ff2b7c1d10cf0c2501702e7a85d58f9d813cdca567c377431ec4d2b97
     Working copy  (@) now at: rlvkpnrz 65b6b74e (empty) (no description set)
     Parent commit (@-)      : qpvuntsm 230dd059 (empty) (no description set)
     [EOF]
     ");
 }
 
+#[must_use]
+fn get_log_output(work_dir: &TestWorkDir) -> String {
+    let template = r#"commit_id ++ " " ++ description"#;
+    let output = work_dir.run_jj(["log", "-r", "all()", "-T", template]);
+    output.success().stdout.into_raw()
+}
+
 #[must_use]
 fn get_bookmark_output(work_dir: &TestWorkDir) -> CommandOutput {
     // --quiet to suppress deleted bookmarks hint
     work_dir.run_jj(["bookmark", "list", "--all-remotes", "--quiet"])
 }
+
+#[test]
+fn test_redo_after_concurrent_operations() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.run_jj(["new", "-m", "first"]).success();
+    work_dir.run_jj(["undo"]).success();
+    // An operation concurrent to the undo makes the redo history ambiguous
+    work_dir
+        .run_jj(["new", "--at-op=@-", "-m", "concurrent"])
+        .success();
+    insta::assert_snapshot!(work_dir.run_jj(["redo"]), @r"
+    ------- stderr -------
+    Concurrent modification detected, resolving automatically.
+    Rebased 1 descendant commits onto commits rewritten by other operation
+    Error: Cannot redo after concurrent operations
+    Hint: Use `jj op log` to find the state to restore and `jj op restore` to restore it.
+    [EOF]
+    [exit status: 1]
+    ");
+}
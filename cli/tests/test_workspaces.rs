@@ -1358,6 +1358,52 @@ fn test_workspaces_rename_workspace() {
     ");
 }
 
+#[test]
+fn test_workspaces_rename_workspace_revsets() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "main"]).success();
+    let main_dir = test_env.work_dir("main");
+    main_dir
+        .run_jj(["workspace", "add", "--name", "feature", "../secondary"])
+        .success();
+    let secondary_dir = test_env.work_dir("secondary");
+    secondary_dir
+        .run_jj(["workspace", "rename", "feature2"])
+        .success();
+
+    // The working-copy commit is kept under the new name
+    let template = r#"working_copies ++ "\n""#;
+    let output = main_dir.run_jj(["log", "--no-graph", "-r=feature2@", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    feature2@
+    [EOF]
+    ");
+
+    // The old name no longer resolves
+    let output = main_dir.run_jj(["log", "--no-graph", "-r=feature@", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Workspace `feature` doesn't have a working-copy commit
+    Hint: Did you mean `feature2@`?
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // The old name still resolves at the operation before the rename
+    let output = main_dir.run_jj([
+        "log",
+        "--no-graph",
+        "--at-op=@-",
+        "-r=feature@",
+        "-T",
+        template,
+    ]);
+    insta::assert_snapshot!(output, @r"
+    feature@
+    [EOF]
+    ");
+}
+
 #[must_use]
 fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
     let template = r#"
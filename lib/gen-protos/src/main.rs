@@ -17,6 +17,7 @@ use std::path::Path;
 
 fn main() -> Result<()> {
     let input = [
+        "bundle.proto",
         "git_store.proto",
         "simple_store.proto",
         "op_store.proto",
@@ -139,10 +139,21 @@ impl MergedTreeId {
     }
 }
 
+/// Identifies the commit another commit was duplicated from.
+#[derive(ContentHash, Debug, PartialEq, Eq, Clone)]
+pub struct DuplicateSource {
+    pub commit_id: CommitId,
+    /// Change ID of the source commit, which is kept valid when the source is
+    /// rewritten.
+    pub change_id: ChangeId,
+}
+
 #[derive(ContentHash, Debug, PartialEq, Eq, Clone)]
 pub struct Commit {
     pub parents: Vec<CommitId>,
     pub predecessors: Vec<CommitId>,
+    /// Commit this commit (or one of its predecessors) was duplicated from.
+    pub duplicated_from: Option<DuplicateSource>,
     pub root_tree: MergedTreeId,
     pub change_id: ChangeId,
     pub description: String,
@@ -382,6 +393,7 @@ pub fn make_root_commit(root_change_id: ChangeId, empty_tree_id: TreeId) -> Comm
     Commit {
         parents: vec![],
         predecessors: vec![],
+        duplicated_from: None,
         root_tree: MergedTreeId::resolved(empty_tree_id),
         change_id: root_change_id,
         description: String::new(),
@@ -0,0 +1,748 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Portable bundle of commits and the objects they reference.
+//!
+//! A bundle can be created from a set of commits and applied to another repo,
+//! which may use a different backend. Objects are rewritten in the destination
+//! store, so commit ids may change, but change ids are preserved. Commit
+//! signatures aren't included.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io::Read as _;
+use std::sync::Arc;
+
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use prost::Message as _;
+use thiserror::Error;
+
+use crate::backend;
+use crate::backend::BackendError;
+use crate::backend::ChangeId;
+use crate::backend::CommitId;
+use crate::backend::ConflictId;
+use crate::backend::ConflictTerm;
+use crate::backend::DuplicateSource;
+use crate::backend::FileId;
+use crate::backend::MergedTreeId;
+use crate::backend::SymlinkId;
+use crate::backend::TreeId;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::dag_walk;
+use crate::merge::Merge;
+use crate::object_id::ObjectId;
+use crate::repo::MutableRepo;
+use crate::repo::Repo as _;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::simple_backend;
+use crate::store::Store;
+
+/// Magic bytes and format version preceding the encoded bundle.
+const BUNDLE_HEADER: &[u8] = b"JJ BUNDLE 1\n";
+
+/// Error that may occur while creating, reading, or applying a bundle.
+#[derive(Debug, Error)]
+pub enum BundleError {
+    /// The data doesn't start with the bundle header.
+    #[error("Not a bundle file")]
+    InvalidHeader,
+    /// The bundle content couldn't be decoded.
+    #[error("Failed to decode bundle")]
+    Decode(#[source] prost::DecodeError),
+    /// An object referenced by a bundled commit or tree isn't included.
+    #[error("Bundle doesn't include {object_type} {hash}")]
+    MissingObject {
+        /// Kind of the missing object.
+        object_type: &'static str,
+        /// Hex hash of the missing object.
+        hash: String,
+    },
+    /// The root commit was requested to be bundled.
+    #[error("The root commit can't be bundled")]
+    RootCommit,
+    /// Git submodules can't be represented in a bundle.
+    #[error("Git submodule at {} can't be bundled", .0.as_internal_file_string())]
+    UnsupportedSubmodule(RepoPathBuf),
+    /// Parents of the bundled commits aren't present in the destination repo.
+    #[error(
+        "The repository doesn't contain the base commits of the bundle: {}",
+        .0.iter().map(|id| id.hex()).join(", ")
+    )]
+    MissingBaseCommits(Vec<CommitId>),
+    /// Failed to read or write objects.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+}
+
+/// Commits and the objects they reference, which can be applied to another
+/// repo.
+#[derive(Clone, Debug)]
+pub struct Bundle {
+    proto: crate::protos::bundle::Bundle,
+}
+
+/// Commits of an applied bundle as they exist in the destination repo.
+#[derive(Clone, Debug)]
+pub struct AppliedBundle {
+    /// Commits written to the destination store.
+    pub new_commits: Vec<Commit>,
+    /// Commits which already existed in the destination repo.
+    pub existing_commits: Vec<Commit>,
+}
+
+impl Bundle {
+    /// Collects the `commits` and all trees and files they reference. Parents
+    /// not in `commits` are recorded as base commits, which must exist in the
+    /// repo the bundle is applied to.
+    ///
+    /// Trees and files found at the same path in the trees of the base commits
+    /// aren't included, so the bundle size depends on the changes made by the
+    /// bundled commits, not on the size of the history they're based on.
+    pub fn create(store: &Arc<Store>, commits: &[Commit]) -> Result<Self, BundleError> {
+        let commits_by_id: HashMap<&CommitId, &Commit> =
+            commits.iter().map(|commit| (commit.id(), commit)).collect();
+        if commits_by_id.contains_key(store.root_commit_id()) {
+            return Err(BundleError::RootCommit);
+        }
+        let sorted_commits = dag_walk::topo_order_forward(
+            commits.iter(),
+            |commit| commit.id().clone(),
+            |commit| {
+                commit
+                    .parent_ids()
+                    .iter()
+                    .filter_map(|id| commits_by_id.get(id).copied())
+                    .collect_vec()
+            },
+        );
+
+        let mut collector = ObjectCollector::new(store);
+        collector.proto.backend_name = store.backend_name().to_owned();
+        collector.proto.root_commit_id = store.root_commit_id().to_bytes();
+        for commit in &sorted_commits {
+            for parent_id in commit.parent_ids() {
+                if !commits_by_id.contains_key(parent_id)
+                    && collector.base_commit_ids.insert(parent_id.clone())
+                {
+                    let parent = store.get_commit(parent_id)?;
+                    collector.add_base_commit(&parent);
+                }
+            }
+        }
+        let base_roots = collector.base_roots.clone();
+        for commit in sorted_commits {
+            for tree_id in root_tree_ids(commit.tree_id()) {
+                collector.add_tree(RepoPath::root(), tree_id, &base_roots)?;
+            }
+            collector.proto.commits.push(crate::protos::bundle::Commit {
+                id: commit.id().to_bytes(),
+                commit: Some(simple_backend::commit_to_proto(commit.store_commit())),
+            });
+        }
+        Ok(Bundle {
+            proto: collector.proto,
+        })
+    }
+
+    /// Decodes bundle from the file content.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, BundleError> {
+        let data = data
+            .strip_prefix(BUNDLE_HEADER)
+            .ok_or(BundleError::InvalidHeader)?;
+        let proto = crate::protos::bundle::Bundle::decode(data).map_err(BundleError::Decode)?;
+        Ok(Bundle { proto })
+    }
+
+    /// Encodes bundle to the file content.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = BUNDLE_HEADER.to_vec();
+        data.extend(self.proto.encode_to_vec());
+        data
+    }
+
+    /// Ids of the bundled commits in the source repo. Parents come before
+    /// children.
+    pub fn commit_ids(&self) -> Vec<CommitId> {
+        self.proto
+            .commits
+            .iter()
+            .map(|commit| CommitId::new(commit.id.clone()))
+            .collect()
+    }
+
+    /// Ids of the parent commits which aren't included in the bundle.
+    pub fn base_commit_ids(&self) -> Vec<CommitId> {
+        self.proto
+            .base_commits
+            .iter()
+            .map(|base| CommitId::new(base.id.clone()))
+            .collect()
+    }
+
+    /// Writes the bundled objects to the store of `mut_repo`, and makes the
+    /// bundled commits visible.
+    ///
+    /// Commits and trees which already exist in the destination repo aren't
+    /// written again. Base commits are looked up by commit id if the backend
+    /// is the same, and by change id otherwise. Fails if any of the base
+    /// commits is missing or if its change is divergent. Objects which weren't
+    /// bundled because they exist in a base commit are read from the tree of
+    /// the base commit in the destination repo.
+    pub fn apply(&self, mut_repo: &mut MutableRepo) -> Result<AppliedBundle, BundleError> {
+        let store = mut_repo.store().clone();
+        let same_backend = self.proto.backend_name == store.backend_name();
+        let source_root_id = CommitId::new(self.proto.root_commit_id.clone());
+
+        let mut commit_ids: HashMap<CommitId, CommitId> = HashMap::new();
+        commit_ids.insert(source_root_id, store.root_commit_id().clone());
+        let mut missing_base_ids = vec![];
+        for base in &self.proto.base_commits {
+            let id = CommitId::new(base.id.clone());
+            if commit_ids.contains_key(&id) {
+                continue;
+            }
+            if same_backend && mut_repo.index().has_id(&id) {
+                commit_ids.insert(id.clone(), id);
+                continue;
+            }
+            let change_id = ChangeId::new(base.change_id.clone());
+            match mut_repo.resolve_change_id(&change_id).as_deref() {
+                Some([dest_id]) => {
+                    commit_ids.insert(id, dest_id.clone());
+                }
+                _ => missing_base_ids.push(id),
+            }
+        }
+        if !missing_base_ids.is_empty() {
+            return Err(BundleError::MissingBaseCommits(missing_base_ids));
+        }
+
+        let base_commit_ids = self
+            .proto
+            .base_commits
+            .iter()
+            .map(|base| commit_ids[&CommitId::new(base.id.clone())].clone())
+            .collect();
+        let mut importer = ObjectImporter::new(&store, &self.proto, same_backend, base_commit_ids);
+        let mut new_commits = vec![];
+        let mut existing_commits = vec![];
+        for proto_commit in &self.proto.commits {
+            let id = CommitId::new(proto_commit.id.clone());
+            if same_backend && mut_repo.index().has_id(&id) {
+                let commit = store.get_commit(&id)?;
+                commit_ids.insert(id, commit.id().clone());
+                existing_commits.push(commit);
+                continue;
+            }
+            let mut data =
+                simple_backend::commit_from_proto(proto_commit.commit.clone().unwrap_or_default());
+            data.parents = data
+                .parents
+                .iter()
+                .map(|parent_id| {
+                    commit_ids
+                        .get(parent_id)
+                        .cloned()
+                        .ok_or_else(|| missing_object("commit", parent_id))
+                })
+                .try_collect()?;
+            // Predecessors are optional. Drop the ones unknown to the
+            // destination repo.
+            let map_predecessor = |id: &CommitId| {
+                commit_ids
+                    .get(id)
+                    .cloned()
+                    .or_else(|| (same_backend && mut_repo.index().has_id(id)).then(|| id.clone()))
+            };
+            data.predecessors = data
+                .predecessors
+                .iter()
+                .filter_map(map_predecessor)
+                .collect();
+            data.duplicated_from = data.duplicated_from.take().and_then(|source| {
+                Some(DuplicateSource {
+                    commit_id: map_predecessor(&source.commit_id)?,
+                    change_id: source.change_id,
+                })
+            });
+            data.root_tree = match &data.root_tree {
+                MergedTreeId::Legacy(tree_id) => {
+                    MergedTreeId::Legacy(importer.import_tree(RepoPath::root(), tree_id)?)
+                }
+                MergedTreeId::Merge(tree_ids) => MergedTreeId::Merge(
+                    tree_ids.try_map(|tree_id| importer.import_tree(RepoPath::root(), tree_id))?,
+                ),
+            };
+            let commit = store.write_commit(data, None).block_on()?;
+            commit_ids.insert(id, commit.id().clone());
+            new_commits.push(commit);
+        }
+
+        let commits = itertools::chain(&new_commits, &existing_commits)
+            .cloned()
+            .collect_vec();
+        mut_repo.add_heads(&commits)?;
+        Ok(AppliedBundle {
+            new_commits,
+            existing_commits,
+        })
+    }
+}
+
+fn missing_object(object_type: &'static str, id: &impl ObjectId) -> BundleError {
+    BundleError::MissingObject {
+        object_type,
+        hash: id.hex(),
+    }
+}
+
+fn root_tree_ids(tree_id: &MergedTreeId) -> Vec<&TreeId> {
+    match tree_id {
+        MergedTreeId::Legacy(tree_id) => vec![tree_id],
+        MergedTreeId::Merge(tree_ids) => tree_ids.iter().collect(),
+    }
+}
+
+fn tree_value_id(value: &TreeValue) -> &[u8] {
+    match value {
+        TreeValue::File { id, .. } => id.as_bytes(),
+        TreeValue::Symlink(id) => id.as_bytes(),
+        TreeValue::Tree(id) => id.as_bytes(),
+        TreeValue::GitSubmodule(id) => id.as_bytes(),
+        TreeValue::Conflict(id) => id.as_bytes(),
+    }
+}
+
+/// Value at some path in the tree of a base commit.
+#[derive(Clone, Debug)]
+struct BaseValue {
+    base_commit: u32,
+    term: u32,
+    value: TreeValue,
+}
+
+impl BaseValue {
+    fn to_proto(&self, id: &impl ObjectId, path: &RepoPath) -> crate::protos::bundle::BaseObject {
+        crate::protos::bundle::BaseObject {
+            id: id.to_bytes(),
+            base_commit: self.base_commit,
+            term: self.term,
+            path: path.as_internal_file_string().to_owned(),
+        }
+    }
+}
+
+/// Finds the base value which refers to the same object as `value`. The
+/// executable bit of files doesn't matter.
+fn find_base<'a>(bases: &'a [BaseValue], value: &TreeValue) -> Option<&'a BaseValue> {
+    bases.iter().find(|base| match (&base.value, value) {
+        (TreeValue::File { id: base_id, .. }, TreeValue::File { id, .. }) => base_id == id,
+        (base_value, value) => base_value == value,
+    })
+}
+
+/// Reads objects from the source store, each object only once.
+struct ObjectCollector<'a> {
+    store: &'a Arc<Store>,
+    proto: crate::protos::bundle::Bundle,
+    base_commit_ids: HashSet<CommitId>,
+    /// Root trees of the base commits.
+    base_roots: Vec<BaseValue>,
+    tree_ids: HashSet<TreeId>,
+    file_ids: HashSet<FileId>,
+    symlink_ids: HashSet<SymlinkId>,
+    conflict_ids: HashSet<ConflictId>,
+}
+
+impl<'a> ObjectCollector<'a> {
+    fn new(store: &'a Arc<Store>) -> Self {
+        ObjectCollector {
+            store,
+            proto: Default::default(),
+            base_commit_ids: HashSet::new(),
+            base_roots: vec![],
+            tree_ids: HashSet::new(),
+            file_ids: HashSet::new(),
+            symlink_ids: HashSet::new(),
+            conflict_ids: HashSet::new(),
+        }
+    }
+
+    fn add_base_commit(&mut self, commit: &Commit) {
+        let base_commit = u32::try_from(self.proto.base_commits.len()).unwrap();
+        self.proto
+            .base_commits
+            .push(crate::protos::bundle::BaseCommit {
+                id: commit.id().to_bytes(),
+                change_id: commit.change_id().to_bytes(),
+            });
+        for (term, tree_id) in root_tree_ids(commit.tree_id()).into_iter().enumerate() {
+            self.base_roots.push(BaseValue {
+                base_commit,
+                term: u32::try_from(term).unwrap(),
+                value: TreeValue::Tree(tree_id.clone()),
+            });
+        }
+    }
+
+    /// Adds the tree unless it's one of the `bases`, which are the values at
+    /// `dir` in the trees of the base commits.
+    fn add_tree(
+        &mut self,
+        dir: &RepoPath,
+        id: &TreeId,
+        bases: &[BaseValue],
+    ) -> Result<(), BundleError> {
+        if !self.tree_ids.insert(id.clone()) {
+            return Ok(());
+        }
+        if let Some(base) = find_base(bases, &TreeValue::Tree(id.clone())) {
+            self.proto.base_trees.push(base.to_proto(id, dir));
+            return Ok(());
+        }
+        let tree = self.store.get_tree(dir.to_owned(), id)?;
+        let mut base_trees = vec![];
+        for base in bases {
+            if let TreeValue::Tree(base_id) = &base.value {
+                base_trees.push((base, self.store.get_tree(dir.to_owned(), base_id)?));
+            }
+        }
+        for entry in tree.data().entries() {
+            let path = dir.join(entry.name());
+            let entry_bases = base_trees
+                .iter()
+                .filter_map(|(base, base_tree)| {
+                    Some(BaseValue {
+                        base_commit: base.base_commit,
+                        term: base.term,
+                        value: base_tree.value(entry.name())?.clone(),
+                    })
+                })
+                .collect_vec();
+            self.add_tree_value(&path, entry.value(), &entry_bases)?;
+        }
+        self.proto.trees.push(crate::protos::bundle::Tree {
+            id: id.to_bytes(),
+            tree: Some(simple_backend::tree_to_proto(tree.data())),
+        });
+        Ok(())
+    }
+
+    fn add_tree_value(
+        &mut self,
+        path: &RepoPath,
+        value: &TreeValue,
+        bases: &[BaseValue],
+    ) -> Result<(), BundleError> {
+        match value {
+            TreeValue::File { id, .. } => {
+                if !self.file_ids.insert(id.clone()) {
+                    return Ok(());
+                }
+                if let Some(base) = find_base(bases, value) {
+                    self.proto.base_files.push(base.to_proto(id, path));
+                } else {
+                    let mut content = vec![];
+                    self.store
+                        .read_file(path, id)?
+                        .read_to_end(&mut content)
+                        .map_err(|err| BackendError::ReadFile {
+                            path: path.to_owned(),
+                            id: id.clone(),
+                            source: err.into(),
+                        })?;
+                    self.proto.files.push(crate::protos::bundle::File {
+                        id: id.to_bytes(),
+                        content,
+                    });
+                }
+            }
+            TreeValue::Symlink(id) => {
+                if !self.symlink_ids.insert(id.clone()) {
+                    return Ok(());
+                }
+                if let Some(base) = find_base(bases, value) {
+                    self.proto.base_symlinks.push(base.to_proto(id, path));
+                } else {
+                    let target = self.store.read_symlink(path, id)?;
+                    self.proto.symlinks.push(crate::protos::bundle::Symlink {
+                        id: id.to_bytes(),
+                        target,
+                    });
+                }
+            }
+            TreeValue::Tree(id) => self.add_tree(path, id, bases)?,
+            TreeValue::GitSubmodule(_) => {
+                return Err(BundleError::UnsupportedSubmodule(path.to_owned()));
+            }
+            TreeValue::Conflict(id) => {
+                if !self.conflict_ids.insert(id.clone()) {
+                    return Ok(());
+                }
+                if let Some(base) = find_base(bases, value) {
+                    self.proto.base_conflicts.push(base.to_proto(id, path));
+                } else {
+                    let conflict = self.store.read_conflict(path, id)?;
+                    for value in conflict.iter().flatten() {
+                        self.add_tree_value(path, value, bases)?;
+                    }
+                    let conflict = conflict.into_backend_conflict();
+                    self.proto.conflicts.push(crate::protos::bundle::Conflict {
+                        id: id.to_bytes(),
+                        conflict: Some(simple_backend::conflict_to_proto(&conflict)),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes bundled objects to the destination store, each object only once.
+struct ObjectImporter<'a> {
+    store: &'a Arc<Store>,
+    /// Whether the source object ids can be looked up in the destination store.
+    same_backend: bool,
+    /// Ids of the base commits in the destination repo.
+    base_commit_ids: Vec<CommitId>,
+    trees: HashMap<TreeId, &'a crate::protos::bundle::Tree>,
+    files: HashMap<FileId, &'a crate::protos::bundle::File>,
+    symlinks: HashMap<SymlinkId, &'a crate::protos::bundle::Symlink>,
+    conflicts: HashMap<ConflictId, &'a crate::protos::bundle::Conflict>,
+    base_trees: HashMap<TreeId, &'a crate::protos::bundle::BaseObject>,
+    base_files: HashMap<FileId, &'a crate::protos::bundle::BaseObject>,
+    base_symlinks: HashMap<SymlinkId, &'a crate::protos::bundle::BaseObject>,
+    base_conflicts: HashMap<ConflictId, &'a crate::protos::bundle::BaseObject>,
+    tree_ids: HashMap<TreeId, TreeId>,
+    file_ids: HashMap<FileId, FileId>,
+    symlink_ids: HashMap<SymlinkId, SymlinkId>,
+    conflict_ids: HashMap<ConflictId, ConflictId>,
+}
+
+impl<'a> ObjectImporter<'a> {
+    fn new(
+        store: &'a Arc<Store>,
+        proto: &'a crate::protos::bundle::Bundle,
+        same_backend: bool,
+        base_commit_ids: Vec<CommitId>,
+    ) -> Self {
+        ObjectImporter {
+            store,
+            same_backend,
+            base_commit_ids,
+            trees: proto
+                .trees
+                .iter()
+                .map(|tree| (TreeId::new(tree.id.clone()), tree))
+                .collect(),
+            files: proto
+                .files
+                .iter()
+                .map(|file| (FileId::new(file.id.clone()), file))
+                .collect(),
+            symlinks: proto
+                .symlinks
+                .iter()
+                .map(|symlink| (SymlinkId::new(symlink.id.clone()), symlink))
+                .collect(),
+            conflicts: proto
+                .conflicts
+                .iter()
+                .map(|conflict| (ConflictId::new(conflict.id.clone()), conflict))
+                .collect(),
+            base_trees: proto
+                .base_trees
+                .iter()
+                .map(|base| (TreeId::new(base.id.clone()), base))
+                .collect(),
+            base_files: proto
+                .base_files
+                .iter()
+                .map(|base| (FileId::new(base.id.clone()), base))
+                .collect(),
+            base_symlinks: proto
+                .base_symlinks
+                .iter()
+                .map(|base| (SymlinkId::new(base.id.clone()), base))
+                .collect(),
+            base_conflicts: proto
+                .base_conflicts
+                .iter()
+                .map(|base| (ConflictId::new(base.id.clone()), base))
+                .collect(),
+            tree_ids: HashMap::new(),
+            file_ids: HashMap::new(),
+            symlink_ids: HashMap::new(),
+            conflict_ids: HashMap::new(),
+        }
+    }
+
+    /// Looks up the base object in the tree of the base commit in the
+    /// destination repo.
+    fn resolve_base_object(
+        &self,
+        base: &crate::protos::bundle::BaseObject,
+    ) -> Result<Option<TreeValue>, BundleError> {
+        let Some(commit_id) = self.base_commit_ids.get(base.base_commit as usize) else {
+            return Ok(None);
+        };
+        let commit = self.store.get_commit(commit_id)?;
+        let Some(tree_id) = root_tree_ids(commit.tree_id())
+            .get(base.term as usize)
+            .copied()
+        else {
+            return Ok(None);
+        };
+        let tree = self.store.get_tree(RepoPathBuf::root(), tree_id)?;
+        let path = RepoPathBuf::from_internal_string(base.path.as_str());
+        let value = tree.path_value(&path)?;
+        // If the base commit was found by change id, its content may differ.
+        // This can only be detected if the object ids are comparable.
+        if self.same_backend && value.as_ref().map(tree_value_id) != Some(&base.id) {
+            return Ok(None);
+        }
+        Ok(value)
+    }
+
+    /// Writes the tree and its descendants, returns the id in the destination
+    /// store.
+    fn import_tree(&mut self, dir: &RepoPath, id: &TreeId) -> Result<TreeId, BundleError> {
+        if let Some(new_id) = self.tree_ids.get(id) {
+            return Ok(new_id.clone());
+        }
+        // Subtrees of an existing tree should also exist.
+        if self.same_backend {
+            match self.store.get_tree(dir.to_owned(), id) {
+                Ok(_) => {
+                    self.tree_ids.insert(id.clone(), id.clone());
+                    return Ok(id.clone());
+                }
+                Err(BackendError::ObjectNotFound { .. }) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        if let Some(&base) = self.base_trees.get(id) {
+            let Some(TreeValue::Tree(new_id)) = self.resolve_base_object(base)? else {
+                return Err(missing_object("tree", id));
+            };
+            self.tree_ids.insert(id.clone(), new_id.clone());
+            return Ok(new_id);
+        }
+        let proto = self
+            .trees
+            .get(id)
+            .ok_or_else(|| missing_object("tree", id))?;
+        let source_tree = simple_backend::tree_from_proto(proto.tree.clone().unwrap_or_default());
+        let mut tree = backend::Tree::default();
+        for entry in source_tree.entries() {
+            let path = dir.join(entry.name());
+            let value = self.import_tree_value(&path, entry.value())?;
+            tree.set(entry.name().to_owned(), value);
+        }
+        let new_id = self.store.write_tree(dir, tree).block_on()?.id().clone();
+        self.tree_ids.insert(id.clone(), new_id.clone());
+        Ok(new_id)
+    }
+
+    fn import_tree_value(
+        &mut self,
+        path: &RepoPath,
+        value: &TreeValue,
+    ) -> Result<TreeValue, BundleError> {
+        match value {
+            TreeValue::File { id, executable } => {
+                let new_id = if let Some(new_id) = self.file_ids.get(id) {
+                    new_id.clone()
+                } else if let Some(&base) = self.base_files.get(id) {
+                    let Some(TreeValue::File { id: new_id, .. }) =
+                        self.resolve_base_object(base)?
+                    else {
+                        return Err(missing_object("file", id));
+                    };
+                    self.file_ids.insert(id.clone(), new_id.clone());
+                    new_id
+                } else {
+                    let file = self
+                        .files
+                        .get(id)
+                        .ok_or_else(|| missing_object("file", id))?;
+                    let new_id = self
+                        .store
+                        .write_file(path, &mut file.content.as_slice())
+                        .block_on()?;
+                    self.file_ids.insert(id.clone(), new_id.clone());
+                    new_id
+                };
+                Ok(TreeValue::File {
+                    id: new_id,
+                    executable: *executable,
+                })
+            }
+            TreeValue::Symlink(id) => {
+                let new_id = if let Some(new_id) = self.symlink_ids.get(id) {
+                    new_id.clone()
+                } else if let Some(&base) = self.base_symlinks.get(id) {
+                    let Some(TreeValue::Symlink(new_id)) = self.resolve_base_object(base)? else {
+                        return Err(missing_object("symlink", id));
+                    };
+                    self.symlink_ids.insert(id.clone(), new_id.clone());
+                    new_id
+                } else {
+                    let symlink = self
+                        .symlinks
+                        .get(id)
+                        .ok_or_else(|| missing_object("symlink", id))?;
+                    let new_id = self.store.write_symlink(path, &symlink.target).block_on()?;
+                    self.symlink_ids.insert(id.clone(), new_id.clone());
+                    new_id
+                };
+                Ok(TreeValue::Symlink(new_id))
+            }
+            TreeValue::Tree(id) => Ok(TreeValue::Tree(self.import_tree(path, id)?)),
+            TreeValue::GitSubmodule(_) => Err(BundleError::UnsupportedSubmodule(path.to_owned())),
+            TreeValue::Conflict(id) => {
+                if let Some(new_id) = self.conflict_ids.get(id) {
+                    return Ok(TreeValue::Conflict(new_id.clone()));
+                }
+                if let Some(&base) = self.base_conflicts.get(id) {
+                    let Some(TreeValue::Conflict(new_id)) = self.resolve_base_object(base)? else {
+                        return Err(missing_object("conflict", id));
+                    };
+                    self.conflict_ids.insert(id.clone(), new_id.clone());
+                    return Ok(TreeValue::Conflict(new_id));
+                }
+                let proto = self
+                    .conflicts
+                    .get(id)
+                    .ok_or_else(|| missing_object("conflict", id))?;
+                let mut conflict =
+                    simple_backend::conflict_from_proto(proto.conflict.clone().unwrap_or_default());
+                for term in itertools::chain(&mut conflict.removes, &mut conflict.adds) {
+                    *term = ConflictTerm {
+                        value: self.import_tree_value(path, &term.value)?,
+                    };
+                }
+                let new_id = self
+                    .store
+                    .write_conflict(path, &Merge::from_backend_conflict(conflict))?;
+                self.conflict_ids.insert(id.clone(), new_id.clone());
+                Ok(TreeValue::Conflict(new_id))
+            }
+        }
+    }
+}
@@ -25,6 +25,7 @@ use std::sync::Arc;
 use itertools::Itertools as _;
 
 use crate::backend;
+use crate::backend::BackendError;
 use crate::backend::BackendResult;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
@@ -108,6 +109,39 @@ impl Commit {
             .map(|id| self.store.get_commit(id))
     }
 
+    /// Returns the ID of the commit this commit was duplicated from, if any.
+    ///
+    /// The provenance is preserved when the duplicate is rewritten further.
+    pub fn duplicated_from_id(&self) -> Option<&CommitId> {
+        self.data
+            .duplicated_from
+            .as_ref()
+            .map(|source| &source.commit_id)
+    }
+
+    /// Returns the change ID of the commit this commit was duplicated from, if
+    /// any.
+    pub fn duplicated_from_change_id(&self) -> Option<&ChangeId> {
+        self.data
+            .duplicated_from
+            .as_ref()
+            .map(|source| &source.change_id)
+    }
+
+    /// Returns the commit this commit was duplicated from, if any.
+    ///
+    /// Returns `None` if the source commit no longer exists in the store.
+    pub fn duplicated_from(&self) -> BackendResult<Option<Commit>> {
+        let Some(id) = self.duplicated_from_id() else {
+            return Ok(None);
+        };
+        match self.store.get_commit(id) {
+            Ok(commit) => Ok(Some(commit)),
+            Err(BackendError::ObjectNotFound { .. }) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
     pub fn tree(&self) -> BackendResult<MergedTree> {
         self.store.get_root_tree(&self.data.root_tree)
     }
@@ -22,6 +22,7 @@ use crate::backend;
 use crate::backend::BackendResult;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
+use crate::backend::DuplicateSource;
 use crate::backend::MergedTreeId;
 use crate::backend::Signature;
 use crate::commit::is_backend_commit_empty;
@@ -65,6 +66,15 @@ impl CommitBuilder<'_> {
         self
     }
 
+    pub fn duplicated_from(&self) -> Option<&DuplicateSource> {
+        self.inner.duplicated_from()
+    }
+
+    pub fn set_duplicated_from(mut self, duplicated_from: Option<DuplicateSource>) -> Self {
+        self.inner.set_duplicated_from(duplicated_from);
+        self
+    }
+
     pub fn tree_id(&self) -> &MergedTreeId {
         self.inner.tree_id()
     }
@@ -181,6 +191,7 @@ impl DetachedCommitBuilder {
         let commit = backend::Commit {
             parents,
             predecessors: vec![],
+            duplicated_from: None,
             root_tree: tree_id,
             change_id,
             description: String::new(),
@@ -267,6 +278,15 @@ impl DetachedCommitBuilder {
         self
     }
 
+    pub fn duplicated_from(&self) -> Option<&DuplicateSource> {
+        self.commit.duplicated_from.as_ref()
+    }
+
+    pub fn set_duplicated_from(&mut self, duplicated_from: Option<DuplicateSource>) -> &mut Self {
+        self.commit.duplicated_from = duplicated_from;
+        self
+    }
+
     pub fn tree_id(&self) -> &MergedTreeId {
         &self.commit.root_tree
     }
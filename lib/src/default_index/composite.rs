@@ -49,6 +49,7 @@ use crate::object_id::ObjectId as _;
 use crate::object_id::PrefixResolution;
 use crate::revset::ResolvedExpression;
 use crate::revset::Revset;
+use crate::revset::RevsetEvaluationBudget;
 use crate::revset::RevsetEvaluationError;
 use crate::store::Store;
 
@@ -421,6 +422,16 @@ impl CompositeIndex {
         let revset_impl = revset_engine::evaluate(expression, store, self)?;
         Ok(Box::new(revset_impl))
     }
+
+    pub(super) fn evaluate_revset_with_budget(
+        &self,
+        expression: &ResolvedExpression,
+        store: &Arc<Store>,
+        budget: &RevsetEvaluationBudget,
+    ) -> Result<Box<dyn Revset + '_>, RevsetEvaluationError> {
+        let revset_impl = revset_engine::evaluate_with_budget(expression, store, self, budget)?;
+        Ok(Box::new(revset_impl))
+    }
 }
 
 impl AsCompositeIndex for CompositeIndex {
@@ -510,6 +521,15 @@ impl Index for &CompositeIndex {
     ) -> Result<Box<dyn Revset + 'index>, RevsetEvaluationError> {
         CompositeIndex::evaluate_revset(self, expression, store)
     }
+
+    fn evaluate_revset_with_budget<'index>(
+        &'index self,
+        expression: &ResolvedExpression,
+        store: &Arc<Store>,
+        budget: &RevsetEvaluationBudget,
+    ) -> Result<Box<dyn Revset + 'index>, RevsetEvaluationError> {
+        CompositeIndex::evaluate_revset_with_budget(self, expression, store, budget)
+    }
 }
 
 pub(super) struct ChangeIdIndexImpl<I> {
@@ -59,6 +59,7 @@ use crate::object_id::ObjectId;
 use crate::object_id::PrefixResolution;
 use crate::revset::ResolvedExpression;
 use crate::revset::Revset;
+use crate::revset::RevsetEvaluationBudget;
 use crate::revset::RevsetEvaluationError;
 use crate::store::Store;
 
@@ -349,6 +350,26 @@ impl MutableIndexSegment {
     }
 
     pub(super) fn save_in(self, dir: &Path) -> io::Result<Arc<ReadonlyIndexSegment>> {
+        self.save_with(|index_file_id_hex, buf| {
+            let index_file_path = dir.join(index_file_id_hex);
+            let mut temp_file = NamedTempFile::new_in(dir)?;
+            let file = temp_file.as_file_mut();
+            file.write_all(buf)?;
+            persist_content_addressed_temp_file(temp_file, index_file_path)?;
+            Ok(())
+        })
+    }
+
+    /// Converts this segment to a readonly segment without writing it to disk.
+    pub(super) fn into_readonly(self) -> Arc<ReadonlyIndexSegment> {
+        self.save_with(|_, _| Ok(()))
+            .expect("in-memory index segment should not fail to save")
+    }
+
+    fn save_with(
+        self,
+        write: impl FnOnce(&str, &[u8]) -> io::Result<()>,
+    ) -> io::Result<Arc<ReadonlyIndexSegment>> {
         if self.num_local_commits() == 0 && self.parent_file.is_some() {
             return Ok(self.parent_file.unwrap());
         }
@@ -361,12 +382,7 @@ impl MutableIndexSegment {
         let mut hasher = Blake2b512::new();
         hasher.update(&buf);
         let index_file_id_hex = hex::encode(hasher.finalize());
-        let index_file_path = dir.join(&index_file_id_hex);
-
-        let mut temp_file = NamedTempFile::new_in(dir)?;
-        let file = temp_file.as_file_mut();
-        file.write_all(&buf)?;
-        persist_content_addressed_temp_file(temp_file, index_file_path)?;
+        write(&index_file_id_hex, &buf)?;
 
         Ok(ReadonlyIndexSegment::load_with_parent_file(
             &mut &buf[local_entries_offset..],
@@ -482,6 +498,10 @@ impl DefaultMutableIndex {
     pub(super) fn squash_and_save_in(self, dir: &Path) -> io::Result<Arc<ReadonlyIndexSegment>> {
         self.0.maybe_squash_with_ancestors().save_in(dir)
     }
+
+    pub(super) fn squash_into_readonly(self) -> Arc<ReadonlyIndexSegment> {
+        self.0.maybe_squash_with_ancestors().into_readonly()
+    }
 }
 
 impl AsCompositeIndex for DefaultMutableIndex {
@@ -532,6 +552,16 @@ impl Index for DefaultMutableIndex {
     ) -> Result<Box<dyn Revset + 'index>, RevsetEvaluationError> {
         self.as_composite().evaluate_revset(expression, store)
     }
+
+    fn evaluate_revset_with_budget<'index>(
+        &'index self,
+        expression: &ResolvedExpression,
+        store: &Arc<Store>,
+        budget: &RevsetEvaluationBudget,
+    ) -> Result<Box<dyn Revset + 'index>, RevsetEvaluationError> {
+        self.as_composite()
+            .evaluate_revset_with_budget(expression, store, budget)
+    }
 }
 
 impl MutableIndex for DefaultMutableIndex {
@@ -49,6 +49,7 @@ use crate::object_id::ObjectId;
 use crate::object_id::PrefixResolution;
 use crate::revset::ResolvedExpression;
 use crate::revset::Revset;
+use crate::revset::RevsetEvaluationBudget;
 use crate::revset::RevsetEvaluationError;
 use crate::store::Store;
 
@@ -651,6 +652,16 @@ impl Index for DefaultReadonlyIndex {
     ) -> Result<Box<dyn Revset + 'index>, RevsetEvaluationError> {
         self.as_composite().evaluate_revset(expression, store)
     }
+
+    fn evaluate_revset_with_budget<'index>(
+        &'index self,
+        expression: &ResolvedExpression,
+        store: &Arc<Store>,
+        budget: &RevsetEvaluationBudget,
+    ) -> Result<Box<dyn Revset + 'index>, RevsetEvaluationError> {
+        self.as_composite()
+            .evaluate_revset_with_budget(expression, store, budget)
+    }
 }
 
 impl ReadonlyIndex for DefaultReadonlyIndex {
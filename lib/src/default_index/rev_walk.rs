@@ -15,6 +15,7 @@
 #![allow(missing_docs)]
 
 use std::cmp::max;
+use std::cmp::min;
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 use std::collections::HashMap;
@@ -795,6 +796,70 @@ impl AncestorsBitSet {
     }
 }
 
+/// Set of index positions stored as a dense bit set.
+///
+/// This is smaller than a list of positions if the set covers a good portion
+/// of the index, and can be iterated in both directions.
+#[derive(Clone, Debug)]
+pub(super) struct PositionsBitSet {
+    bitset: Vec<u64>,
+    /// One past the largest position in the set.
+    end: u32,
+}
+
+impl PositionsBitSet {
+    /// Creates bit set of the specified capacity.
+    pub fn with_capacity(len: u32) -> Self {
+        let bitset_len = usize::try_from(u32::div_ceil(len, u64::BITS)).unwrap();
+        PositionsBitSet {
+            bitset: vec![0; bitset_len], // request zeroed page
+            end: 0,
+        }
+    }
+
+    /// Adds `pos` to the set.
+    ///
+    /// Panics if the `pos` exceeds the capacity.
+    pub fn insert(&mut self, pos: IndexPosition) {
+        let bitset_pos = pos.0 / u64::BITS;
+        let bit = 1_u64 << (pos.0 % u64::BITS);
+        self.bitset[usize::try_from(bitset_pos).unwrap()] |= bit;
+        self.end = max(self.end, pos.0 + 1);
+    }
+
+    /// Returns `true` if the given `pos` is in the set.
+    pub fn contains(&self, pos: IndexPosition) -> bool {
+        let bitset_pos = pos.0 / u64::BITS;
+        let bit = 1_u64 << (pos.0 % u64::BITS);
+        self.bitset
+            .get(usize::try_from(bitset_pos).unwrap())
+            .is_some_and(|bits| bits & bit != 0)
+    }
+
+    /// One past the largest position in the set.
+    pub fn end(&self) -> IndexPosition {
+        IndexPosition(self.end)
+    }
+
+    /// Iterates positions within the `range` in ascending order.
+    pub fn iter_range(
+        &self,
+        range: Range<IndexPosition>,
+    ) -> impl DoubleEndedIterator<Item = IndexPosition> + use<'_> {
+        let end = min(range.end.0, self.end);
+        (range.start.0..end)
+            .map(IndexPosition)
+            .filter(|&pos| self.contains(pos))
+    }
+
+    /// Turns this into an iterator of positions in ascending order.
+    pub fn into_positions(self) -> impl Iterator<Item = IndexPosition> {
+        (0..self.end)
+            .map(IndexPosition)
+            .filter(move |&pos| self.contains(pos))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use itertools::Itertools as _;
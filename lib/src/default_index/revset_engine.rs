@@ -14,18 +14,22 @@
 
 #![allow(missing_docs)]
 
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::cmp::Reverse;
 use std::collections::BTreeSet;
 use std::collections::BinaryHeap;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt;
 use std::iter;
 use std::ops::Range;
+use std::ptr;
 use std::rc::Rc;
 use std::str;
 use std::sync::Arc;
+use std::time::Instant;
 
 use futures::StreamExt as _;
 use itertools::Itertools as _;
@@ -33,9 +37,12 @@ use pollster::FutureExt as _;
 
 use super::rev_walk::EagerRevWalk;
 use super::rev_walk::PeekableRevWalk;
+use super::rev_walk::PositionsBitSet;
 use super::rev_walk::RevWalk;
 use super::rev_walk::RevWalkBuilder;
+use super::revset_graph_iterator::ForwardGraphWalk;
 use super::revset_graph_iterator::RevsetGraphWalk;
+use crate::backend::BackendError;
 use crate::backend::BackendResult;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
@@ -48,17 +55,26 @@ use crate::conflicts::MaterializedTreeValue;
 use crate::default_index::AsCompositeIndex;
 use crate::default_index::CompositeIndex;
 use crate::default_index::IndexPosition;
+use crate::diff::Diff;
+use crate::diff::DiffHunkKind;
+use crate::fileset::FileMetadata;
+use crate::fileset::FileTypeKind;
+use crate::fileset::FilesetExpression;
+use crate::graph::reverse_graph;
 use crate::graph::GraphNode;
 use crate::matchers::Matcher;
 use crate::matchers::Visit;
 use crate::merged_tree::resolve_file_values;
 use crate::object_id::ObjectId as _;
 use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
 use crate::revset::ResolvedExpression;
 use crate::revset::ResolvedPredicateExpression;
 use crate::revset::Revset;
 use crate::revset::RevsetContainingFn;
+use crate::revset::RevsetEvaluationBudget;
 use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetEvaluationProgress;
 use crate::revset::RevsetFilterPredicate;
 use crate::revset::GENERATION_RANGE_FULL;
 use crate::rewrite;
@@ -98,6 +114,12 @@ trait InternalRevset: fmt::Debug + ToPredicateFn {
     fn into_predicate<'a>(self: Box<Self>) -> Box<dyn ToPredicateFn + 'a>
     where
         Self: 'a;
+
+    /// Lower bound and, optionally, upper bound of the number of positions,
+    /// which can be computed without walking.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
 }
 
 impl<T: InternalRevset + ?Sized> InternalRevset for Box<T> {
@@ -114,16 +136,26 @@ impl<T: InternalRevset + ?Sized> InternalRevset for Box<T> {
     {
         <T as InternalRevset>::into_predicate(*self)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        <T as InternalRevset>::size_hint(self)
+    }
 }
 
 pub struct RevsetImpl<I> {
     inner: Box<dyn InternalRevset>,
     index: I,
+    /// Whether the set is known to contain all ancestors of its members.
+    ancestor_closed: bool,
 }
 
 impl<I: AsCompositeIndex + Clone> RevsetImpl<I> {
-    fn new(inner: Box<dyn InternalRevset>, index: I) -> Self {
-        Self { inner, index }
+    fn new(inner: Box<dyn InternalRevset>, index: I, ancestor_closed: bool) -> Self {
+        Self {
+            inner,
+            index,
+            ancestor_closed,
+        }
     }
 
     fn positions(
@@ -147,6 +179,7 @@ impl<I> fmt::Debug for RevsetImpl<I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RevsetImpl")
             .field("inner", &self.inner)
+            .field("ancestor_closed", &self.ancestor_closed)
             .finish_non_exhaustive()
     }
 }
@@ -192,7 +225,23 @@ impl<I: AsCompositeIndex + Clone> Revset for RevsetImpl<I> {
         self.positions().next().is_none()
     }
 
+    fn is_ancestor_closed(&self) -> bool {
+        self.ancestor_closed
+    }
+
+    fn reversed<'a>(self: Box<Self>) -> Box<dyn Revset + 'a>
+    where
+        Self: 'a,
+    {
+        Box::new(ReversedRevsetImpl { inner: *self })
+    }
+
     fn count_estimate(&self) -> Result<(usize, Option<usize>), RevsetEvaluationError> {
+        let (lower, upper) = self.inner.size_hint();
+        if upper == Some(lower) {
+            // Exact count is known without walking.
+            return Ok((lower, upper));
+        }
         if cfg!(feature = "testing") {
             // Exercise the estimation feature in tests. (If we ever have a Revset
             // implementation in production code that returns estimates, we can probably
@@ -204,7 +253,7 @@ impl<I: AsCompositeIndex + Clone> Revset for RevsetImpl<I> {
             if count < 10 {
                 Ok((count, Some(count)))
             } else {
-                Ok((10, None))
+                Ok((lower.max(10), upper))
             }
         } else {
             let count = self.positions().process_results(|iter| iter.count())?;
@@ -221,6 +270,145 @@ impl<I: AsCompositeIndex + Clone> Revset for RevsetImpl<I> {
     }
 }
 
+/// Revset which iterates the `inner` revset in ascending index position order.
+///
+/// Since the inner revset can only be walked in descending order, the positions
+/// are collected upfront. Commit ids are looked up lazily from the index. If
+/// the inner revset is closed under ancestors, the positions are collected
+/// into a bit set, and the graph is emitted incrementally from the roots.
+struct ReversedRevsetImpl<I> {
+    inner: RevsetImpl<I>,
+}
+
+impl<I: AsCompositeIndex + Clone> ReversedRevsetImpl<I> {
+    fn collect_positions(&self) -> Result<Vec<IndexPosition>, RevsetEvaluationError> {
+        let mut positions: Vec<_> = self.inner.positions().try_collect()?;
+        positions.reverse();
+        Ok(positions)
+    }
+
+    fn collect_positions_bitset(&self) -> Result<PositionsBitSet, RevsetEvaluationError> {
+        let index = self.inner.index.as_composite();
+        let mut positions = PositionsBitSet::with_capacity(index.num_commits());
+        for pos in self.inner.positions() {
+            positions.insert(pos?);
+        }
+        Ok(positions)
+    }
+
+    fn map_positions<'a, T: 'a>(
+        &self,
+        mut f: impl FnMut(&CompositeIndex, IndexPosition) -> T + 'a,
+    ) -> Box<dyn Iterator<Item = Result<T, RevsetEvaluationError>> + 'a>
+    where
+        I: 'a,
+    {
+        let index = self.inner.index.clone();
+        if self.inner.ancestor_closed {
+            match self.collect_positions_bitset() {
+                Ok(positions) => Box::new(
+                    positions
+                        .into_positions()
+                        .map(move |pos| Ok(f(index.as_composite(), pos))),
+                ),
+                Err(err) => Box::new(iter::once(Err(err))),
+            }
+        } else {
+            match self.collect_positions() {
+                Ok(positions) => Box::new(
+                    positions
+                        .into_iter()
+                        .map(move |pos| Ok(f(index.as_composite(), pos))),
+                ),
+                Err(err) => Box::new(iter::once(Err(err))),
+            }
+        }
+    }
+}
+
+impl<I> fmt::Debug for ReversedRevsetImpl<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReversedRevsetImpl")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<I: AsCompositeIndex + Clone> Revset for ReversedRevsetImpl<I> {
+    fn iter<'a>(&self) -> Box<dyn Iterator<Item = Result<CommitId, RevsetEvaluationError>> + 'a>
+    where
+        Self: 'a,
+    {
+        self.map_positions(|index, pos| index.entry_by_pos(pos).commit_id())
+    }
+
+    fn commit_change_ids<'a>(
+        &self,
+    ) -> Box<dyn Iterator<Item = Result<(CommitId, ChangeId), RevsetEvaluationError>> + 'a>
+    where
+        Self: 'a,
+    {
+        self.map_positions(|index, pos| {
+            let entry = index.entry_by_pos(pos);
+            (entry.commit_id(), entry.change_id())
+        })
+    }
+
+    fn iter_graph<'a>(
+        &self,
+    ) -> Box<dyn Iterator<Item = Result<GraphNode<CommitId>, RevsetEvaluationError>> + 'a>
+    where
+        Self: 'a,
+    {
+        if self.inner.ancestor_closed {
+            return match self.collect_positions_bitset() {
+                Ok(positions) => {
+                    let index = self.inner.index.clone();
+                    let mut walk = ForwardGraphWalk::new(positions, index.as_composite());
+                    Box::new(iter::from_fn(move || walk.next(index.as_composite())).map(Ok))
+                }
+                Err(err) => Box::new(iter::once(Err(err))),
+            };
+        }
+        let skip_transitive_edges = true;
+        let graph = self.inner.iter_graph_impl(skip_transitive_edges);
+        match reverse_graph(graph, |id| id) {
+            Ok(nodes) => Box::new(nodes.into_iter().map(Ok)),
+            Err(err) => Box::new(iter::once(Err(err))),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    fn is_ancestor_closed(&self) -> bool {
+        self.inner.ancestor_closed
+    }
+
+    fn reversed<'a>(self: Box<Self>) -> Box<dyn Revset + 'a>
+    where
+        Self: 'a,
+    {
+        Box::new(self.inner)
+    }
+
+    fn last(&self) -> Result<Option<CommitId>, RevsetEvaluationError> {
+        self.inner.first()
+    }
+
+    fn count_estimate(&self) -> Result<(usize, Option<usize>), RevsetEvaluationError> {
+        self.inner.count_estimate()
+    }
+
+    fn containing_fn<'a>(&self) -> Box<RevsetContainingFn<'a>>
+    where
+        Self: 'a,
+    {
+        self.inner.containing_fn()
+    }
+}
+
 /// Incrementally consumes `RevWalk` of the revset collecting positions.
 struct PositionsAccumulator<'a, I> {
     index: I,
@@ -314,6 +502,10 @@ impl InternalRevset for EagerRevset {
     {
         self
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.positions.len(), Some(self.positions.len()))
+    }
 }
 
 impl ToPredicateFn for EagerRevset {
@@ -326,6 +518,141 @@ impl ToPredicateFn for EagerRevset {
     }
 }
 
+/// Adapter for a subexpression whose result is shared by multiple consumers.
+///
+/// The underlying walk is advanced lazily as the consumers need more
+/// positions, and the positions emitted so far are cached for the others.
+#[derive(Clone, Debug)]
+struct SharedRevset {
+    state: Rc<SharedRevWalkState>,
+}
+
+struct SharedRevWalkState {
+    walk: RefCell<Option<BoxedRevWalk<'static>>>,
+    positions: RefCell<Vec<IndexPosition>>,
+    /// Error emitted by the underlying walk, to be reported to the other
+    /// consumers once they reach the end of the cached positions.
+    error: RefCell<Option<RevsetEvaluationError>>,
+}
+
+impl fmt::Debug for SharedRevWalkState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedRevWalkState")
+            .field("positions", &self.positions)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SharedRevset {
+    fn new(inner: Box<dyn InternalRevset>) -> Self {
+        let state = SharedRevWalkState {
+            walk: RefCell::new(Some(inner.positions())),
+            positions: RefCell::new(Vec::new()),
+            error: RefCell::new(None),
+        };
+        SharedRevset {
+            state: Rc::new(state),
+        }
+    }
+}
+
+impl InternalRevset for SharedRevset {
+    fn positions<'a>(&self) -> BoxedRevWalk<'a>
+    where
+        Self: 'a,
+    {
+        Box::new(SharedRevWalk {
+            state: self.state.clone(),
+            next_index: 0,
+        })
+    }
+
+    fn into_predicate<'a>(self: Box<Self>) -> Box<dyn ToPredicateFn + 'a>
+    where
+        Self: 'a,
+    {
+        self
+    }
+}
+
+impl ToPredicateFn for SharedRevset {
+    fn to_predicate_fn<'a>(&self) -> BoxedPredicateFn<'a>
+    where
+        Self: 'a,
+    {
+        let mut walk = self.positions().peekable();
+        Box::new(move |index, entry_pos| {
+            while walk
+                .next_if(index, |item| {
+                    item.as_ref().is_ok_and(|&pos| pos > entry_pos)
+                })
+                .is_some()
+            {
+                continue;
+            }
+            match walk.next_if(index, |item| {
+                item.as_ref().map_or(true, |&pos| pos == entry_pos)
+            }) {
+                Some(Ok(_)) => Ok(true),
+                Some(Err(err)) => Err(err),
+                None => Ok(false),
+            }
+        })
+    }
+}
+
+/// Cursor over the positions of [`SharedRevset`].
+struct SharedRevWalk {
+    state: Rc<SharedRevWalkState>,
+    next_index: usize,
+}
+
+impl RevWalk<CompositeIndex> for SharedRevWalk {
+    type Item = Result<IndexPosition, RevsetEvaluationError>;
+
+    fn next(&mut self, index: &CompositeIndex) -> Option<Self::Item> {
+        let state = &self.state;
+        if let Some(&pos) = state.positions.borrow().get(self.next_index) {
+            self.next_index += 1;
+            return Some(Ok(pos));
+        }
+        if let Some(err) = &*state.error.borrow() {
+            return Some(Err(duplicate_evaluation_error(err)));
+        }
+        let mut walk = state.walk.borrow_mut();
+        match walk.as_mut()?.next(index) {
+            Some(Ok(pos)) => {
+                state.positions.borrow_mut().push(pos);
+                self.next_index += 1;
+                Some(Ok(pos))
+            }
+            Some(Err(err)) => {
+                *walk = None;
+                *state.error.borrow_mut() = Some(duplicate_evaluation_error(&err));
+                Some(Err(err))
+            }
+            None => {
+                *walk = None;
+                None
+            }
+        }
+    }
+}
+
+/// Creates a copy of the `err` to be reported by another consumer of the
+/// same shared walk.
+fn duplicate_evaluation_error(err: &RevsetEvaluationError) -> RevsetEvaluationError {
+    match err {
+        RevsetEvaluationError::BudgetExceeded(message) => {
+            RevsetEvaluationError::BudgetExceeded(message.clone())
+        }
+        RevsetEvaluationError::Cancelled => RevsetEvaluationError::Cancelled,
+        RevsetEvaluationError::StoreError(_) | RevsetEvaluationError::Other(_) => {
+            RevsetEvaluationError::Other(err.to_string().into())
+        }
+    }
+}
+
 /// Adapter for infallible `RevWalk` of `IndexPosition`s.
 struct RevWalkRevset<W> {
     walk: W,
@@ -409,6 +736,10 @@ where
     {
         self
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.candidates.size_hint().1)
+    }
 }
 
 impl<S, P> ToPredicateFn for FilterRevset<S, P>
@@ -467,6 +798,13 @@ where
     {
         self
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower1, upper1) = self.set1.size_hint();
+        let (lower2, upper2) = self.set2.size_hint();
+        let upper = upper1.zip(upper2).and_then(|(n1, n2)| n1.checked_add(n2));
+        (lower1.max(lower2), upper)
+    }
 }
 
 impl<S1, S2> ToPredicateFn for UnionRevset<S1, S2>
@@ -562,6 +900,16 @@ where
     {
         self
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (_, upper1) = self.set1.size_hint();
+        let (_, upper2) = self.set2.size_hint();
+        let upper = match (upper1, upper2) {
+            (Some(n1), Some(n2)) => Some(n1.min(n2)),
+            (upper1, upper2) => upper1.or(upper2),
+        };
+        (0, upper)
+    }
 }
 
 impl<S1, S2> ToPredicateFn for IntersectionRevset<S1, S2>
@@ -677,6 +1025,13 @@ where
     {
         self
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower1, upper1) = self.set1.size_hint();
+        let (_, upper2) = self.set2.size_hint();
+        let lower = upper2.map_or(0, |n2| lower1.saturating_sub(n2));
+        (lower, upper1)
+    }
 }
 
 impl<S1, S2> ToPredicateFn for DifferenceRevset<S1, S2>
@@ -766,18 +1121,454 @@ pub fn evaluate<I: AsCompositeIndex + Clone>(
     expression: &ResolvedExpression,
     store: &Arc<Store>,
     index: I,
+) -> Result<RevsetImpl<I>, RevsetEvaluationError> {
+    evaluate_with_budget(expression, store, index, &RevsetEvaluationBudget::default())
+}
+
+pub fn evaluate_with_budget<I: AsCompositeIndex + Clone>(
+    expression: &ResolvedExpression,
+    store: &Arc<Store>,
+    index: I,
+    budget: &RevsetEvaluationBudget,
 ) -> Result<RevsetImpl<I>, RevsetEvaluationError> {
     let context = EvaluationContext {
         store: store.clone(),
         index: index.as_composite(),
+        budget: BudgetTracker::new(budget),
+        shared_keys: collect_shared_memo_keys(expression),
+        shared_revsets: RefCell::new(HashMap::new()),
     };
-    let internal_revset = context.evaluate(expression)?;
-    Ok(RevsetImpl::new(internal_revset, index))
+    let mut internal_revset = context.evaluate(expression)?;
+    if let Some(budget) = &context.budget {
+        if budget.progress.is_some() {
+            internal_revset = Box::new(MatchCountingRevset {
+                inner: internal_revset,
+                budget: budget.clone(),
+            });
+        }
+    }
+    let ancestor_closed = is_ancestor_closed(expression);
+    Ok(RevsetImpl::new(internal_revset, index, ancestor_closed))
+}
+
+/// Returns true if the set contains all ancestors of its members.
+fn is_ancestor_closed(expression: &ResolvedExpression) -> bool {
+    match expression {
+        ResolvedExpression::Ancestors { generation, .. } => generation == &GENERATION_RANGE_FULL,
+        ResolvedExpression::Coalesce(expression1, expression2)
+        | ResolvedExpression::Union(expression1, expression2)
+        | ResolvedExpression::Intersection(expression1, expression2) => {
+            is_ancestor_closed(expression1) && is_ancestor_closed(expression2)
+        }
+        _ => false,
+    }
 }
 
 struct EvaluationContext<'index> {
     store: Arc<Store>,
     index: &'index CompositeIndex,
+    budget: Option<Rc<BudgetTracker>>,
+    /// Keys of the subexpression nodes which appear more than once in the
+    /// expression tree.
+    shared_keys: HashMap<*const ResolvedExpression, MemoKey>,
+    /// Lazily evaluated revsets of the shared subexpressions.
+    shared_revsets: RefCell<HashMap<MemoKey, SharedRevset>>,
+}
+
+/// Tracks resources spent by evaluation against [`RevsetEvaluationBudget`].
+///
+/// Index entries are charged when they are emitted by walks. Filters and set
+/// operations only check the limits, so an entry is charged once no matter
+/// how many operators it flows through.
+#[derive(Debug)]
+struct BudgetTracker {
+    deadline: Option<Instant>,
+    max_visited_entries: Option<usize>,
+    progress: Option<RevsetEvaluationProgress>,
+    visited_entries: Cell<usize>,
+    matched_entries: Cell<usize>,
+    /// Visited count to be reported once the entry is known to match or not.
+    pending_report: Cell<Option<usize>>,
+    cancelled: Cell<bool>,
+}
+
+impl BudgetTracker {
+    /// Returns `None` if the `budget` is unlimited and no progress is reported.
+    fn new(budget: &RevsetEvaluationBudget) -> Option<Rc<Self>> {
+        if budget.is_unlimited() && budget.progress.is_none() {
+            return None;
+        }
+        let tracker = BudgetTracker {
+            deadline: budget
+                .max_duration
+                .and_then(|duration| Instant::now().checked_add(duration)),
+            max_visited_entries: budget.max_visited_entries,
+            progress: budget.progress.clone(),
+            visited_entries: Cell::new(0),
+            matched_entries: Cell::new(0),
+            pending_report: Cell::new(None),
+            cancelled: Cell::new(false),
+        };
+        Some(Rc::new(tracker))
+    }
+
+    /// Charges one index entry visited by a walk to the budget. The limits
+    /// are checked separately by [`Self::check()`].
+    fn count_entry(&self) {
+        if let Some(visited_entries) = self.pending_report.take() {
+            self.report(visited_entries);
+        }
+        let visited_entries = self.visited_entries.get() + 1;
+        self.visited_entries.set(visited_entries);
+        if let Some(progress) = &self.progress {
+            if visited_entries % progress.interval() == 0 {
+                self.pending_report.set(Some(visited_entries));
+            }
+        }
+    }
+
+    /// Records that an entry is emitted by the evaluated revset.
+    fn record_match(&self) {
+        self.matched_entries.set(self.matched_entries.get() + 1);
+        if let Some(visited_entries) = self.pending_report.take() {
+            self.report(visited_entries);
+        }
+    }
+
+    fn report(&self, visited_entries: usize) {
+        let Some(progress) = &self.progress else {
+            return;
+        };
+        if progress
+            .report(visited_entries, self.matched_entries.get())
+            .is_break()
+        {
+            self.cancelled.set(true);
+        }
+    }
+
+    /// Returns error if the evaluation exceeded the budget or was cancelled.
+    fn check(&self) -> Result<(), RevsetEvaluationError> {
+        if self.cancelled.get() {
+            return Err(RevsetEvaluationError::Cancelled);
+        }
+        if let Some(max) = self.max_visited_entries {
+            if self.visited_entries.get() > max {
+                return Err(RevsetEvaluationError::BudgetExceeded(format!(
+                    "visited more than {max} index entries"
+                )));
+            }
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(RevsetEvaluationError::BudgetExceeded(
+                "evaluation took too long".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Walk which charges each visited entry to the evaluation budget.
+struct BudgetedRevWalkRevset<W> {
+    walk: W,
+    budget: Rc<BudgetTracker>,
+}
+
+impl<W> fmt::Debug for BudgetedRevWalkRevset<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BudgetedRevWalkRevset")
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W> InternalRevset for BudgetedRevWalkRevset<W>
+where
+    W: RevWalk<CompositeIndex, Item = IndexPosition> + Clone,
+{
+    fn positions<'a>(&self) -> BoxedRevWalk<'a>
+    where
+        Self: 'a,
+    {
+        let budget = self.budget.clone();
+        Box::new(self.walk.clone().map(move |_index, pos| {
+            budget.count_entry();
+            budget.check()?;
+            Ok(pos)
+        }))
+    }
+
+    fn into_predicate<'a>(self: Box<Self>) -> Box<dyn ToPredicateFn + 'a>
+    where
+        Self: 'a,
+    {
+        self
+    }
+}
+
+impl<W> ToPredicateFn for BudgetedRevWalkRevset<W>
+where
+    W: RevWalk<CompositeIndex, Item = IndexPosition> + Clone,
+{
+    fn to_predicate_fn<'a>(&self) -> BoxedPredicateFn<'a>
+    where
+        Self: 'a,
+    {
+        // The predicate may skip over many entries in one call. Charge them
+        // as they are walked, and check the limits once per call.
+        let walk = self.walk.clone().map({
+            let budget = self.budget.clone();
+            move |_index, pos| {
+                budget.count_entry();
+                pos
+            }
+        });
+        let mut p = predicate_fn_from_rev_walk(walk);
+        let budget = self.budget.clone();
+        Box::new(move |index, pos| {
+            let matched = p(index, pos)?;
+            budget.check()?;
+            Ok(matched)
+        })
+    }
+}
+
+/// Filter predicate which checks the evaluation budget on each test. Tested
+/// entries are charged by the walk producing them.
+#[derive(Debug)]
+struct BudgetedPredicate<P> {
+    inner: P,
+    budget: Rc<BudgetTracker>,
+}
+
+impl<P: ToPredicateFn> ToPredicateFn for BudgetedPredicate<P> {
+    fn to_predicate_fn<'a>(&self) -> BoxedPredicateFn<'a>
+    where
+        Self: 'a,
+    {
+        let budget = self.budget.clone();
+        let mut p = self.inner.to_predicate_fn();
+        Box::new(move |index, pos| {
+            budget.check()?;
+            p(index, pos)
+        })
+    }
+}
+
+/// Adapter that counts the entries emitted by the evaluated revset for
+/// progress reporting.
+#[derive(Debug)]
+struct MatchCountingRevset {
+    inner: Box<dyn InternalRevset>,
+    budget: Rc<BudgetTracker>,
+}
+
+impl InternalRevset for MatchCountingRevset {
+    fn positions<'a>(&self) -> BoxedRevWalk<'a>
+    where
+        Self: 'a,
+    {
+        let budget = self.budget.clone();
+        Box::new(self.inner.positions().map(move |_index, pos| {
+            let pos = pos?;
+            budget.record_match();
+            budget.check()?;
+            Ok(pos)
+        }))
+    }
+
+    fn into_predicate<'a>(self: Box<Self>) -> Box<dyn ToPredicateFn + 'a>
+    where
+        Self: 'a,
+    {
+        self.inner.into_predicate()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ToPredicateFn for MatchCountingRevset {
+    fn to_predicate_fn<'a>(&self) -> BoxedPredicateFn<'a>
+    where
+        Self: 'a,
+    {
+        self.inner.to_predicate_fn()
+    }
+}
+
+/// Returns operands of (possibly nested) `coalesce()` node in evaluation order.
+fn flatten_coalesce_operands(expression: &ResolvedExpression) -> Vec<&ResolvedExpression> {
+    let mut operands = vec![];
+    let mut stack = vec![expression];
+    while let Some(expression) = stack.pop() {
+        if let ResolvedExpression::Coalesce(expression1, expression2) = expression {
+            stack.push(expression2.as_ref());
+            stack.push(expression1.as_ref());
+        } else {
+            operands.push(expression);
+        }
+    }
+    operands
+}
+
+/// Identifier of structurally equal pure subexpressions.
+///
+/// Expressions containing filter predicates have no key. Predicates can't be
+/// hashed, and aren't guaranteed to be pure in general.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct MemoKey(usize);
+
+/// Node of a pure subexpression whose children are identified by their keys.
+///
+/// Since the children are already interned, hashing a node doesn't recurse
+/// into the subtree.
+#[derive(Debug, Eq, Hash, PartialEq)]
+enum MemoNode<'a> {
+    Commits(&'a [CommitId]),
+    Ancestors(MemoKey, &'a Range<u64>),
+    Range(MemoKey, MemoKey, &'a Range<u64>),
+    DagRange(MemoKey, MemoKey, &'a Range<u64>),
+    Reachable(MemoKey, MemoKey),
+    Heads(MemoKey),
+    Roots(MemoKey),
+    ForkPoint(MemoKey),
+    Latest(MemoKey, usize),
+    Coalesce(MemoKey, MemoKey),
+    Union(MemoKey, MemoKey),
+    Intersection(MemoKey, MemoKey),
+    Difference(MemoKey, MemoKey),
+}
+
+/// Returns keys of pure subexpressions which appear more than once in the
+/// `expression` tree, indexed by node address. Plain commit sets are excluded
+/// as they are cheap to evaluate.
+fn collect_shared_memo_keys(
+    expression: &ResolvedExpression,
+) -> HashMap<*const ResolvedExpression, MemoKey> {
+    #[derive(Default)]
+    struct Interner<'a> {
+        keys: HashMap<MemoNode<'a>, MemoKey>,
+        counts: Vec<usize>,
+        node_keys: Vec<(*const ResolvedExpression, MemoKey)>,
+    }
+
+    impl<'a> Interner<'a> {
+        fn intern(&mut self, node: MemoNode<'a>) -> MemoKey {
+            let next_key = MemoKey(self.keys.len());
+            let key = *self.keys.entry(node).or_insert(next_key);
+            if key == next_key {
+                self.counts.push(0);
+            }
+            key
+        }
+
+        /// Visits the `expression` tree bottom up, and returns the key of the
+        /// `expression` if it is pure.
+        fn visit(&mut self, expression: &'a ResolvedExpression) -> Option<MemoKey> {
+            let node = match expression {
+                ResolvedExpression::Commits(commit_ids) => {
+                    // Not counted as it isn't worth sharing.
+                    return Some(self.intern(MemoNode::Commits(commit_ids)));
+                }
+                ResolvedExpression::Ancestors { heads, generation } => {
+                    MemoNode::Ancestors(self.visit(heads)?, generation)
+                }
+                ResolvedExpression::Range {
+                    roots,
+                    heads,
+                    generation,
+                } => {
+                    let (roots, heads) = (self.visit(roots), self.visit(heads));
+                    MemoNode::Range(roots?, heads?, generation)
+                }
+                ResolvedExpression::DagRange {
+                    roots,
+                    heads,
+                    generation_from_roots,
+                } => {
+                    let (roots, heads) = (self.visit(roots), self.visit(heads));
+                    MemoNode::DagRange(roots?, heads?, generation_from_roots)
+                }
+                ResolvedExpression::Reachable { sources, domain } => {
+                    let (sources, domain) = (self.visit(sources), self.visit(domain));
+                    MemoNode::Reachable(sources?, domain?)
+                }
+                ResolvedExpression::Heads(candidates) => MemoNode::Heads(self.visit(candidates)?),
+                ResolvedExpression::Roots(candidates) => MemoNode::Roots(self.visit(candidates)?),
+                ResolvedExpression::ForkPoint(expression) => {
+                    MemoNode::ForkPoint(self.visit(expression)?)
+                }
+                // Reads commit data from the store like filter predicates do.
+                ResolvedExpression::DuplicatesOf {
+                    candidates,
+                    sources,
+                } => {
+                    self.visit(sources);
+                    self.visit(candidates);
+                    return None;
+                }
+                ResolvedExpression::Latest { candidates, count } => {
+                    MemoNode::Latest(self.visit(candidates)?, *count)
+                }
+                ResolvedExpression::Coalesce(expression1, expression2) => {
+                    let (key1, key2) = (self.visit(expression1), self.visit(expression2));
+                    MemoNode::Coalesce(key1?, key2?)
+                }
+                ResolvedExpression::Union(expression1, expression2) => {
+                    let (key1, key2) = (self.visit(expression1), self.visit(expression2));
+                    MemoNode::Union(key1?, key2?)
+                }
+                ResolvedExpression::FilterWithin {
+                    candidates,
+                    predicate,
+                } => {
+                    self.visit(candidates);
+                    self.visit_predicate(predicate);
+                    return None;
+                }
+                ResolvedExpression::Intersection(expression1, expression2) => {
+                    let (key1, key2) = (self.visit(expression1), self.visit(expression2));
+                    MemoNode::Intersection(key1?, key2?)
+                }
+                ResolvedExpression::Difference(expression1, expression2) => {
+                    let (key1, key2) = (self.visit(expression1), self.visit(expression2));
+                    MemoNode::Difference(key1?, key2?)
+                }
+            };
+            let key = self.intern(node);
+            self.counts[key.0] += 1;
+            self.node_keys.push((expression, key));
+            Some(key)
+        }
+
+        fn visit_predicate(&mut self, predicate: &'a ResolvedPredicateExpression) {
+            match predicate {
+                ResolvedPredicateExpression::Filter(_) => {}
+                ResolvedPredicateExpression::Set(expression) => {
+                    self.visit(expression);
+                }
+                ResolvedPredicateExpression::NotIn(predicate) => self.visit_predicate(predicate),
+                ResolvedPredicateExpression::Union(predicate1, predicate2) => {
+                    self.visit_predicate(predicate1);
+                    self.visit_predicate(predicate2);
+                }
+            }
+        }
+    }
+
+    let mut interner = Interner::default();
+    interner.visit(expression);
+    let Interner {
+        counts, node_keys, ..
+    } = interner;
+    node_keys
+        .into_iter()
+        .filter(|&(_, key)| counts[key.0] > 1)
+        .collect()
 }
 
 fn to_u32_generation_range(range: &Range<u64>) -> Result<Range<u32>, RevsetEvaluationError> {
@@ -791,11 +1582,31 @@ fn to_u32_generation_range(range: &Range<u64>) -> Result<Range<u32>, RevsetEvalu
 }
 
 impl EvaluationContext<'_> {
+    /// Evaluates the given `expression`, reusing the result if an identical
+    /// subexpression has already been evaluated.
     fn evaluate(
         &self,
         expression: &ResolvedExpression,
+    ) -> Result<Box<dyn InternalRevset>, RevsetEvaluationError> {
+        let Some(&key) = self.shared_keys.get(&ptr::from_ref(expression)) else {
+            return self.evaluate_uncached(expression);
+        };
+        if let Some(revset) = self.shared_revsets.borrow().get(&key) {
+            return Ok(Box::new(revset.clone()));
+        }
+        let revset = SharedRevset::new(self.evaluate_uncached(expression)?);
+        self.shared_revsets.borrow_mut().insert(key, revset.clone());
+        Ok(Box::new(revset))
+    }
+
+    fn evaluate_uncached(
+        &self,
+        expression: &ResolvedExpression,
     ) -> Result<Box<dyn InternalRevset>, RevsetEvaluationError> {
         let index = self.index;
+        if let Some(budget) = &self.budget {
+            budget.check()?;
+        }
         match expression {
             ResolvedExpression::Commits(commit_ids) => {
                 Ok(Box::new(self.revset_for_commit_ids(commit_ids)?))
@@ -807,13 +1618,13 @@ impl EvaluationContext<'_> {
                     RevWalkBuilder::new(index).wanted_heads(head_positions.try_collect()?);
                 if generation == &GENERATION_RANGE_FULL {
                     let walk = builder.ancestors().detach();
-                    Ok(Box::new(RevWalkRevset { walk }))
+                    Ok(self.walk_revset(walk))
                 } else {
                     let generation = to_u32_generation_range(generation)?;
                     let walk = builder
                         .ancestors_filtered_by_generation(generation)
                         .detach();
-                    Ok(Box::new(RevWalkRevset { walk }))
+                    Ok(self.walk_revset(walk))
                 }
             }
             ResolvedExpression::Range {
@@ -838,13 +1649,13 @@ impl EvaluationContext<'_> {
                     .unwanted_roots(root_positions);
                 if generation == &GENERATION_RANGE_FULL {
                     let walk = builder.ancestors().detach();
-                    Ok(Box::new(RevWalkRevset { walk }))
+                    Ok(self.walk_revset(walk))
                 } else {
                     let generation = to_u32_generation_range(generation)?;
                     let walk = builder
                         .ancestors_filtered_by_generation(generation)
                         .detach();
-                    Ok(Box::new(RevWalkRevset { walk }))
+                    Ok(self.walk_revset(walk))
                 }
             }
             ResolvedExpression::DagRange {
@@ -863,7 +1674,7 @@ impl EvaluationContext<'_> {
                     let walk = builder
                         .ancestors_until_roots(root_positions.iter().copied())
                         .detach();
-                    let candidates = RevWalkRevset { walk };
+                    let candidates = self.walk_revset(walk);
                     let predicate = as_pure_predicate_fn(move |index, pos| {
                         Ok(index
                             .entry_by_pos(pos)
@@ -971,17 +1782,48 @@ impl EvaluationContext<'_> {
                 positions.reverse();
                 Ok(Box::new(EagerRevset { positions }))
             }
+            ResolvedExpression::DuplicatesOf {
+                candidates,
+                sources,
+            } => {
+                // Match on change ids so that duplicates are still found after
+                // their sources have been rewritten.
+                let source_ids: HashSet<ChangeId> = self
+                    .evaluate(sources)?
+                    .positions()
+                    .attach(index)
+                    .map_ok(|pos| index.entry_by_pos(pos).change_id())
+                    .try_collect()?;
+                let source_ids = Rc::new(source_ids);
+                let store = self.store.clone();
+                let predicate = box_pure_predicate_fn(move |index, pos| {
+                    let entry = index.entry_by_pos(pos);
+                    let commit = store.get_commit(&entry.commit_id())?;
+                    Ok(commit
+                        .duplicated_from_change_id()
+                        .is_some_and(|id| source_ids.contains(id)))
+                });
+                Ok(Box::new(FilterRevset {
+                    candidates: self.evaluate(candidates)?,
+                    predicate,
+                }))
+            }
             ResolvedExpression::Latest { candidates, count } => {
                 let candidate_set = self.evaluate(candidates)?;
                 Ok(Box::new(self.take_latest_revset(&*candidate_set, *count)?))
             }
-            ResolvedExpression::Coalesce(expression1, expression2) => {
-                let set1 = self.evaluate(expression1)?;
-                if set1.positions().attach(index).next().is_some() {
-                    Ok(set1)
-                } else {
-                    self.evaluate(expression2)
+            ResolvedExpression::Coalesce(_, _) => {
+                // Evaluate operands one by one so that later (possibly
+                // expensive) operands are skipped once a non-empty set is found.
+                // Nested nodes are flattened to test emptiness only once per
+                // operand.
+                for operand in flatten_coalesce_operands(expression) {
+                    let set = self.evaluate(operand)?;
+                    if set.positions().attach(index).next().is_some() {
+                        return Ok(set);
+                    }
                 }
+                Ok(Box::new(EagerRevset::empty()))
             }
             ResolvedExpression::Union(expression1, expression2) => {
                 let set1 = self.evaluate(expression1)?;
@@ -1014,7 +1856,15 @@ impl EvaluationContext<'_> {
     ) -> Result<Box<dyn ToPredicateFn>, RevsetEvaluationError> {
         match expression {
             ResolvedPredicateExpression::Filter(predicate) => {
-                Ok(build_predicate_fn(self.store.clone(), predicate))
+                let predicate = build_predicate_fn(self.store.clone(), predicate);
+                if let Some(budget) = &self.budget {
+                    Ok(Box::new(BudgetedPredicate {
+                        inner: predicate,
+                        budget: budget.clone(),
+                    }))
+                } else {
+                    Ok(predicate)
+                }
             }
             ResolvedPredicateExpression::Set(expression) => {
                 Ok(self.evaluate(expression)?.into_predicate())
@@ -1031,6 +1881,22 @@ impl EvaluationContext<'_> {
         }
     }
 
+    /// Wraps the `walk` in revset which charges visited entries to the
+    /// budget.
+    fn walk_revset<W>(&self, walk: W) -> Box<dyn InternalRevset>
+    where
+        W: RevWalk<CompositeIndex, Item = IndexPosition> + Clone + 'static,
+    {
+        if let Some(budget) = &self.budget {
+            Box::new(BudgetedRevWalkRevset {
+                walk,
+                budget: budget.clone(),
+            })
+        } else {
+            Box::new(RevWalkRevset { walk })
+        }
+    }
+
     fn revset_for_commit_ids(
         &self,
         commit_ids: &[CommitId],
@@ -1142,6 +2008,80 @@ fn box_pure_predicate_fn<'a>(
     Box::new(PurePredicateFn(f))
 }
 
+/// Predicate matching commits which modify the files, and the files they were
+/// renamed from.
+///
+/// The source paths of the renames detected in matching commits are looked for
+/// in the ancestors of these commits, but not in the other branches of the
+/// history. Since the renames have to be detected in descendants first, the
+/// predicate function must be evaluated in descending index position order.
+#[derive(Debug)]
+struct FollowRenamesPredicate {
+    store: Arc<Store>,
+    expr: FilesetExpression,
+}
+
+impl ToPredicateFn for FollowRenamesPredicate {
+    fn to_predicate_fn<'a>(&self) -> BoxedPredicateFn<'a>
+    where
+        Self: 'a,
+    {
+        let store = self.store.clone();
+        let expr = self.expr.clone();
+        let expr_matcher = expr.to_matcher();
+        // Source paths of the renames keyed by the commit they were found in
+        let mut renames: Vec<(IndexPosition, Vec<RepoPathBuf>)> = vec![];
+        let mut last_pos: Option<IndexPosition> = None;
+        Box::new(move |index, pos| {
+            if last_pos.is_some_and(|last_pos| pos > last_pos) {
+                return Err(RevsetEvaluationError::Other(
+                    "follow() must be evaluated in descending index position order".into(),
+                ));
+            }
+            last_pos = Some(pos);
+            let renamed_paths = renames
+                .iter()
+                .filter(|(descendant_pos, _)| index.is_ancestor_pos(pos, *descendant_pos))
+                .flat_map(|(_, paths)| paths)
+                .map(|path| FilesetExpression::file_path(path.clone()))
+                .collect_vec();
+            let lineage_matcher;
+            let matcher: &dyn Matcher = if renamed_paths.is_empty() {
+                expr_matcher.as_ref()
+            } else {
+                lineage_matcher = FilesetExpression::union_all(
+                    iter::once(expr.clone()).chain(renamed_paths).collect(),
+                )
+                .to_matcher();
+                lineage_matcher.as_ref()
+            };
+            let entry = index.entry_by_pos(pos);
+            let commit = store.get_commit(&entry.commit_id())?;
+            let parents: Vec<_> = commit.parents().try_collect()?;
+            if !has_diff_from_parent(&store, index, &commit, &parents, matcher)? {
+                return Ok(false);
+            }
+            let mut new_sources = vec![];
+            for parent in &parents {
+                let records: Vec<_> = store
+                    .get_copy_records(None, parent.id(), commit.id())?
+                    .collect()
+                    .block_on();
+                for record in records {
+                    let record = record?;
+                    if matcher.matches(&record.target) && !matcher.matches(&record.source) {
+                        new_sources.push(record.source);
+                    }
+                }
+            }
+            if !new_sources.is_empty() {
+                renames.push((pos, new_sources));
+            }
+            Ok(true)
+        })
+    }
+}
+
 fn build_predicate_fn(
     store: Arc<Store>,
     predicate: &RevsetFilterPredicate,
@@ -1167,9 +2107,16 @@ fn build_predicate_fn(
             box_pure_predicate_fn(move |index, pos| {
                 let entry = index.entry_by_pos(pos);
                 let commit = store.get_commit(&entry.commit_id())?;
-                Ok(pattern.matches(commit.description().lines().next().unwrap_or_default()))
+                let subject = commit.description().lines().next().unwrap_or_default();
+                Ok(pattern.matches(subject.trim_end_matches([' ', '\t'])))
             })
         }
+        RevsetFilterPredicate::MalformedSubject => box_pure_predicate_fn(move |index, pos| {
+            let entry = index.entry_by_pos(pos);
+            let commit = store.get_commit(&entry.commit_id())?;
+            let second_line = commit.description().lines().nth(1);
+            Ok(second_line.is_some_and(|line| !line.trim().is_empty()))
+        }),
         RevsetFilterPredicate::AuthorName(pattern) => {
             let pattern = pattern.clone();
             box_pure_predicate_fn(move |index, pos| {
@@ -1220,14 +2167,64 @@ fn build_predicate_fn(
                 Ok(expression.matches(committer_date))
             })
         }
+        RevsetFilterPredicate::TimezoneMismatch => box_pure_predicate_fn(move |index, pos| {
+            let entry = index.entry_by_pos(pos);
+            let commit = store.get_commit(&entry.commit_id())?;
+            let author_tz_offset = commit.author().timestamp.tz_offset;
+            Ok(author_tz_offset != commit.committer().timestamp.tz_offset)
+        }),
+        RevsetFilterPredicate::File(expr)
+            if expr.contains_conflicts() || expr.contains_file_metadata() =>
+        {
+            let expr = expr.clone();
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id())?;
+                let parents: Vec<_> = commit.parents().try_collect()?;
+                Ok(has_diff_with_metadata_from_parent(
+                    &store, index, &commit, &parents, &expr,
+                )?)
+            })
+        }
         RevsetFilterPredicate::File(expr) => {
             let matcher: Rc<dyn Matcher> = expr.to_matcher().into();
             box_pure_predicate_fn(move |index, pos| {
                 let entry = index.entry_by_pos(pos);
                 let commit = store.get_commit(&entry.commit_id())?;
-                Ok(has_diff_from_parent(&store, index, &commit, &*matcher)?)
+                let parents: Vec<_> = commit.parents().try_collect()?;
+                Ok(has_diff_from_parent(
+                    &store, index, &commit, &parents, &*matcher,
+                )?)
+            })
+        }
+        RevsetFilterPredicate::FileFromFirstParent(expr)
+            if expr.contains_conflicts() || expr.contains_file_metadata() =>
+        {
+            let expr = expr.clone();
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id())?;
+                let parents: Vec<_> = commit.parents().take(1).try_collect()?;
+                Ok(has_diff_with_metadata_from_parent(
+                    &store, index, &commit, &parents, &expr,
+                )?)
+            })
+        }
+        RevsetFilterPredicate::FileFromFirstParent(expr) => {
+            let matcher: Rc<dyn Matcher> = expr.to_matcher().into();
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id())?;
+                let parents: Vec<_> = commit.parents().take(1).try_collect()?;
+                Ok(has_diff_from_parent(
+                    &store, index, &commit, &parents, &*matcher,
+                )?)
             })
         }
+        RevsetFilterPredicate::FileFollowingRenames(expr) => Box::new(FollowRenamesPredicate {
+            store,
+            expr: expr.clone(),
+        }),
         RevsetFilterPredicate::DiffContains { text, files } => {
             let text_pattern = text.clone();
             let files_matcher: Rc<dyn Matcher> = files.to_matcher().into();
@@ -1243,11 +2240,39 @@ fn build_predicate_fn(
                 )?)
             })
         }
+        RevsetFilterPredicate::LinesChanged { range, files } => {
+            let range = range.clone();
+            let files_matcher: Rc<dyn Matcher> = files.to_matcher().into();
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id())?;
+                let count = count_changed_lines_from_parent(
+                    &store,
+                    index,
+                    &commit,
+                    &*files_matcher,
+                    range.end,
+                )?;
+                Ok(range.contains(&count))
+            })
+        }
         RevsetFilterPredicate::HasConflict => box_pure_predicate_fn(move |index, pos| {
             let entry = index.entry_by_pos(pos);
             let commit = store.get_commit(&entry.commit_id())?;
             Ok(commit.has_conflict()?)
         }),
+        RevsetFilterPredicate::Signed => box_pure_predicate_fn(move |index, pos| {
+            let entry = index.entry_by_pos(pos);
+            let commit = store.get_commit(&entry.commit_id())?;
+            Ok(commit.is_signed())
+        }),
+        // The remote bookmarks aren't known here. The symbol resolver should
+        // have rewritten this to the ancestors set.
+        RevsetFilterPredicate::Pushed => box_pure_predicate_fn(|_index, _pos| {
+            Err(RevsetEvaluationError::Other(
+                "pushed() must be resolved against a repo".into(),
+            ))
+        }),
         RevsetFilterPredicate::Extension(ext) => {
             let ext = ext.clone();
             box_pure_predicate_fn(move |index, pos| {
@@ -1263,10 +2288,10 @@ fn has_diff_from_parent(
     store: &Arc<Store>,
     index: &CompositeIndex,
     commit: &Commit,
+    parents: &[Commit],
     matcher: &dyn Matcher,
 ) -> BackendResult<bool> {
-    let parents: Vec<_> = commit.parents().try_collect()?;
-    if let [parent] = parents.as_slice() {
+    if let [parent] = parents {
         // Fast path: no need to load the root tree
         let unchanged = commit.tree_id() == parent.tree_id();
         if matcher.visit(RepoPath::root()) == Visit::AllRecursively {
@@ -1277,7 +2302,7 @@ fn has_diff_from_parent(
     }
 
     // Conflict resolution is expensive, try that only for matched files.
-    let from_tree = rewrite::merge_commit_trees_no_resolve_without_repo(store, &index, &parents)?;
+    let from_tree = rewrite::merge_commit_trees_no_resolve_without_repo(store, &index, parents)?;
     let to_tree = commit.tree()?;
     // TODO: handle copy tracking
     let mut tree_diff = from_tree.diff_stream(&to_tree, matcher);
@@ -1296,6 +2321,62 @@ fn has_diff_from_parent(
     .block_on()
 }
 
+/// Like `has_diff_from_parent()`, but also matches `conflicts()`,
+/// `type:<kind>`, `size()`, and `executable()` in `expr` against the entries
+/// in the `commit`.
+fn has_diff_with_metadata_from_parent(
+    store: &Arc<Store>,
+    index: &CompositeIndex,
+    commit: &Commit,
+    parents: &[Commit],
+    expr: &FilesetExpression,
+) -> BackendResult<bool> {
+    // Conflict resolution is expensive, try that only for matched files.
+    let from_tree = rewrite::merge_commit_trees_no_resolve_without_repo(store, &index, parents)?;
+    let to_tree = commit.tree()?;
+    // The file metadata isn't known until the tree values are read, so collect
+    // the changed files which may match first.
+    let matcher = expr.to_superset_matcher();
+    let mut tree_diff = from_tree.diff_stream(&to_tree, matcher.as_ref());
+    // Reading the file contents is expensive, so do that only for size().
+    let read_sizes = expr.contains_file_sizes();
+    let changed_files = async {
+        let mut changed_files = vec![];
+        // TODO: Resolve values concurrently
+        while let Some(entry) = tree_diff.next().await {
+            let (from_value, to_value) = entry.values?;
+            let from_value = resolve_file_values(store, &entry.path, from_value).await?;
+            if from_value == to_value {
+                continue;
+            }
+            let metadata =
+                FileMetadata::from_tree_value(store, &entry.path, &to_value, read_sizes).await?;
+            changed_files.push((entry.path, metadata));
+        }
+        Ok::<_, BackendError>(changed_files)
+    }
+    .block_on()?;
+    let matcher = expr
+        .clone()
+        .resolve_conflicts(|| {
+            changed_files
+                .iter()
+                .filter(|(_, metadata)| {
+                    metadata.is_some_and(|metadata| metadata.file_type == FileTypeKind::Conflict)
+                })
+                .map(|(path, _)| path.clone())
+                .collect()
+        })
+        .resolve_file_metadata(|| {
+            changed_files
+                .iter()
+                .filter_map(|(path, metadata)| Some((path.clone(), (*metadata)?)))
+                .collect()
+        })
+        .to_matcher();
+    Ok(changed_files.iter().any(|(path, _)| matcher.matches(path)))
+}
+
 fn matches_diff_from_parent(
     store: &Arc<Store>,
     index: &CompositeIndex,
@@ -1337,6 +2418,54 @@ fn matches_diff_from_parent(
     .block_on()
 }
 
+/// Counts lines inserted and deleted by the `commit`. Stops counting once the
+/// count reaches the `limit`.
+fn count_changed_lines_from_parent(
+    store: &Arc<Store>,
+    index: &CompositeIndex,
+    commit: &Commit,
+    files_matcher: &dyn Matcher,
+    limit: u64,
+) -> BackendResult<u64> {
+    let parents: Vec<_> = commit.parents().try_collect()?;
+    // Conflict resolution is expensive, try that only for matched files.
+    let from_tree = rewrite::merge_commit_trees_no_resolve_without_repo(store, &index, &parents)?;
+    let to_tree = commit.tree()?;
+    // TODO: handle copy tracking
+    let mut tree_diff = from_tree.diff_stream(&to_tree, files_matcher);
+    async {
+        let mut count = 0;
+        // TODO: Resolve values concurrently
+        while let Some(entry) = tree_diff.next().await {
+            let (left_value, right_value) = entry.values?;
+            let left_value = resolve_file_values(store, &entry.path, left_value).await?;
+            if left_value == right_value {
+                continue;
+            }
+            let left_future = materialize_tree_value(store, &entry.path, left_value);
+            let right_future = materialize_tree_value(store, &entry.path, right_value);
+            let (left_value, right_value) = futures::try_join!(left_future, right_future)?;
+            let left_content = to_file_content(&entry.path, left_value)?;
+            let right_content = to_file_content(&entry.path, right_value)?;
+            let diff = Diff::by_line([&left_content, &right_content]);
+            for hunk in diff.hunks() {
+                if hunk.kind == DiffHunkKind::Different {
+                    count += hunk
+                        .contents
+                        .iter()
+                        .map(|content| content.split_inclusive(|b| *b == b'\n').count() as u64)
+                        .sum::<u64>();
+                }
+            }
+            if count >= limit {
+                break;
+            }
+        }
+        Ok(count)
+    }
+    .block_on()
+}
+
 fn match_lines<'a, 'b>(
     text: &'a [u8],
     pattern: &'b StringPattern,
@@ -1691,4 +2820,52 @@ mod tests {
 
         assert!(positions_accum.contains(&id_1).unwrap());
     }
+
+    #[test]
+    fn test_forward_graph_walk() {
+        let mut new_change_id = change_id_generator();
+        let mut index = DefaultMutableIndex::full(3, 16);
+        // Commit i has the parents listed at parents[i]. 1 has two children,
+        // 4 and 6 are merges.
+        let ids = (0..8_u8).map(|i| CommitId::new(vec![i; 3])).collect_vec();
+        let parents: [&[usize]; 8] = [&[], &[0], &[1], &[1], &[2, 3], &[0], &[4, 5], &[3]];
+        for (id, parents) in iter::zip(&ids, parents) {
+            let parent_ids = parents.iter().map(|&i| ids[i].clone()).collect_vec();
+            index.add_commit_data(id.clone(), new_change_id(), &parent_ids);
+        }
+        let index = index.as_composite();
+        let get_pos = |i: usize| index.commit_id_to_pos(&ids[i]).unwrap();
+
+        let reversed_graph = |members: &[usize]| {
+            let positions = members.iter().rev().map(|&i| get_pos(i)).collect_vec();
+            let set = EagerRevset { positions };
+            let walk = RevsetGraphWalk::new(set.positions(), true).attach(index);
+            reverse_graph(walk, |id| id).unwrap()
+        };
+        let forward_graph = |members: &[usize]| {
+            let mut positions = PositionsBitSet::with_capacity(index.num_commits());
+            for &i in members {
+                positions.insert(get_pos(i));
+            }
+            ForwardGraphWalk::new(positions, index)
+                .attach(index)
+                .collect_vec()
+        };
+
+        // All commits
+        let members = [0, 1, 2, 3, 4, 5, 6, 7];
+        let expected = reversed_graph(&members);
+        assert_eq!(expected[1].1.len(), 2); // children of 1
+        assert_eq!(forward_graph(&members), expected);
+
+        // Ancestors of 5 and 7
+        let members = [0, 1, 3, 5, 7];
+        let expected = reversed_graph(&members);
+        assert_eq!(forward_graph(&members), expected);
+
+        // Ancestors of 6
+        let members = [0, 1, 2, 3, 4, 5, 6];
+        let expected = reversed_graph(&members);
+        assert_eq!(forward_graph(&members), expected);
+    }
 }
@@ -23,6 +23,7 @@ use std::collections::VecDeque;
 use super::composite::CompositeIndex;
 use super::entry::IndexEntry;
 use super::entry::IndexPosition;
+use super::rev_walk::PositionsBitSet;
 use super::rev_walk::RevWalk;
 use super::revset_engine::BoxedRevWalk;
 use crate::backend::CommitId;
@@ -344,3 +345,84 @@ impl RevWalk<CompositeIndex> for RevsetGraphWalk<'_> {
         self.try_next(index).transpose()
     }
 }
+
+/// Yields the revisions of an ancestor-closed set in ascending index position
+/// order, with edges to their children in the set.
+///
+/// The result is the same as reversing the graph produced by
+/// [`RevsetGraphWalk`]. Since every parent of a revision in the set is also in
+/// the set, all edges are direct. The children are indexed upfront by index
+/// position, which is much smaller than the buffered graph nodes.
+pub(super) struct ForwardGraphWalk {
+    members: PositionsBitSet,
+    /// Next position to be visited.
+    next_pos: IndexPosition,
+    /// `children[child_offsets[pos]..child_offsets[pos + 1]]` are the children
+    /// of `pos` in descending order.
+    child_offsets: Vec<u32>,
+    children: Vec<IndexPosition>,
+}
+
+impl ForwardGraphWalk {
+    pub fn new(members: PositionsBitSet, index: &CompositeIndex) -> Self {
+        let end = usize::try_from(members.end().0).unwrap();
+        let in_set_parents = |child_pos| {
+            index
+                .entry_by_pos(child_pos)
+                .parent_positions()
+                .into_iter()
+                .filter(|&parent_pos| members.contains(parent_pos))
+        };
+        // Count the children of each position, then turn the counts into the
+        // end offsets of the ranges. Filling the ranges from their ends in
+        // ascending order of children leaves the offsets pointing at the
+        // starts, and the children in descending order.
+        let mut child_offsets = vec![0_u32; end + 1];
+        let mut num_children = 0;
+        for child_pos in members.iter_range(IndexPosition::MIN..members.end()) {
+            for parent_pos in in_set_parents(child_pos) {
+                child_offsets[usize::try_from(parent_pos.0).unwrap()] += 1;
+                num_children += 1;
+            }
+        }
+        let mut offset = 0;
+        for count in &mut child_offsets {
+            offset += *count;
+            *count = offset;
+        }
+        let mut children = vec![IndexPosition::MIN; num_children];
+        for child_pos in members.iter_range(IndexPosition::MIN..members.end()) {
+            for parent_pos in in_set_parents(child_pos) {
+                let offset = &mut child_offsets[usize::try_from(parent_pos.0).unwrap()];
+                *offset -= 1;
+                children[usize::try_from(*offset).unwrap()] = child_pos;
+            }
+        }
+        ForwardGraphWalk {
+            members,
+            next_pos: IndexPosition::MIN,
+            child_offsets,
+            children,
+        }
+    }
+}
+
+impl RevWalk<CompositeIndex> for ForwardGraphWalk {
+    type Item = GraphNode<CommitId>;
+
+    fn next(&mut self, index: &CompositeIndex) -> Option<Self::Item> {
+        let position = self
+            .members
+            .iter_range(self.next_pos..self.members.end())
+            .next()?;
+        self.next_pos = IndexPosition(position.0 + 1);
+        let i = usize::try_from(position.0).unwrap();
+        let start = usize::try_from(self.child_offsets[i]).unwrap();
+        let end = usize::try_from(self.child_offsets[i + 1]).unwrap();
+        let edges = self.children[start..end]
+            .iter()
+            .map(|&pos| GraphEdge::direct(index.entry_by_pos(pos).commit_id()))
+            .collect();
+        Some((index.entry_by_pos(position).commit_id(), edges))
+    }
+}
@@ -15,6 +15,7 @@
 #![allow(missing_docs)]
 
 use std::any::Any;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs;
 use std::io;
@@ -22,6 +23,8 @@ use std::io::Write as _;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
 
 use itertools::Itertools as _;
 use tempfile::NamedTempFile;
@@ -185,101 +188,21 @@ impl DefaultIndexStore {
         operation: &Operation,
         store: &Arc<Store>,
     ) -> Result<Arc<ReadonlyIndexSegment>, DefaultIndexStoreError> {
-        let view = operation.view()?;
         let operations_dir = self.operations_dir();
-        let commit_id_length = store.commit_id_length();
-        let change_id_length = store.change_id_length();
-        let mut visited_heads: HashSet<CommitId> =
-            view.all_referenced_commit_ids().cloned().collect();
-        let mut historical_heads: Vec<(CommitId, OperationId)> = visited_heads
-            .iter()
-            .map(|commit_id| (commit_id.clone(), operation.id().clone()))
-            .collect();
-        let mut parent_op_id: Option<OperationId> = None;
-        for op in dag_walk::dfs_ok(
-            [Ok(operation.clone())],
-            |op: &Operation| op.id().clone(),
-            |op: &Operation| op.parents().collect_vec(),
-        ) {
-            let op = op?;
-            // Pick the latest existing ancestor operation as the parent
-            // segment. Perhaps, breadth-first search is more appropriate here,
-            // but that wouldn't matter in practice as the operation log is
-            // mostly linear.
-            if parent_op_id.is_none() && operations_dir.join(op.id().hex()).is_file() {
-                parent_op_id = Some(op.id().clone());
-            }
-            // TODO: no need to walk ancestors of the parent_op_id operation
-            for commit_id in op.view()?.all_referenced_commit_ids() {
-                if visited_heads.insert(commit_id.clone()) {
-                    historical_heads.push((commit_id.clone(), op.id().clone()));
-                }
-            }
-        }
-        let maybe_parent_file;
-        let mut mutable_index;
-        match parent_op_id {
-            None => {
-                maybe_parent_file = None;
-                mutable_index = DefaultMutableIndex::full(commit_id_length, change_id_length);
-            }
-            Some(parent_op_id) => {
-                let parent_file = self.load_index_segments_at_operation(
-                    &parent_op_id,
-                    commit_id_length,
-                    change_id_length,
-                )?;
-                maybe_parent_file = Some(parent_file.clone());
-                mutable_index = DefaultMutableIndex::incremental(parent_file);
-            }
-        }
-
-        tracing::info!(
-            ?maybe_parent_file,
-            heads_count = historical_heads.len(),
-            "indexing commits reachable from historical heads"
-        );
-        // Build a list of ancestors of heads where parents and predecessors come after
-        // the commit itself.
-        let parent_file_has_id = |id: &CommitId| {
-            maybe_parent_file
-                .as_ref()
-                .is_some_and(|segment| segment.as_composite().has_id(id))
-        };
-        let get_commit_with_op = |commit_id: &CommitId, op_id: &OperationId| {
-            let op_id = op_id.clone();
-            match store.get_commit(commit_id) {
-                // Propagate head's op_id to report possible source of an error.
-                // The op_id doesn't have to be included in the sort key, but
-                // that wouldn't matter since the commit should be unique.
-                Ok(commit) => Ok((CommitByCommitterTimestamp(commit), op_id)),
-                Err(source) => Err(DefaultIndexStoreError::IndexCommits { op_id, source }),
-            }
-        };
-        let commits = dag_walk::topo_order_reverse_ord_ok(
-            historical_heads
-                .iter()
-                .filter(|&(commit_id, _)| !parent_file_has_id(commit_id))
-                .map(|(commit_id, op_id)| get_commit_with_op(commit_id, op_id)),
-            |(CommitByCommitterTimestamp(commit), _)| commit.id().clone(),
-            |(CommitByCommitterTimestamp(commit), op_id)| {
-                itertools::chain(commit.parent_ids(), commit.predecessor_ids())
-                    .filter(|&id| !parent_file_has_id(id))
-                    .map(|commit_id| get_commit_with_op(commit_id, op_id))
-                    .collect_vec()
+        let mutable_index = build_mutable_index_at_operation(
+            operation,
+            store,
+            |op_id| operations_dir.join(op_id.hex()).is_file(),
+            |op_id| {
+                self.load_index_segments_at_operation(
+                    op_id,
+                    store.commit_id_length(),
+                    store.change_id_length(),
+                )
             },
         )?;
-        for (CommitByCommitterTimestamp(commit), _) in commits.iter().rev() {
-            mutable_index.add_commit(commit);
-        }
-
         let index_file = self.save_mutable_index(mutable_index, operation.id())?;
-        tracing::info!(
-            ?index_file,
-            commits_count = commits.len(),
-            "saved new index file"
-        );
-
+        tracing::info!(?index_file, "saved new index file");
         Ok(index_file)
     }
 
@@ -314,6 +237,101 @@ impl DefaultIndexStore {
     }
 }
 
+/// Builds index of the commits reachable from the given `operation`.
+///
+/// The index will be calculated incrementally from the index of the latest
+/// ancestor operation for which `has_index()` returns true.
+fn build_mutable_index_at_operation(
+    operation: &Operation,
+    store: &Arc<Store>,
+    has_index: impl Fn(&OperationId) -> bool,
+    load_index: impl FnOnce(&OperationId) -> Result<Arc<ReadonlyIndexSegment>, DefaultIndexStoreError>,
+) -> Result<DefaultMutableIndex, DefaultIndexStoreError> {
+    let view = operation.view()?;
+    let commit_id_length = store.commit_id_length();
+    let change_id_length = store.change_id_length();
+    let mut visited_heads: HashSet<CommitId> = view.all_referenced_commit_ids().cloned().collect();
+    let mut historical_heads: Vec<(CommitId, OperationId)> = visited_heads
+        .iter()
+        .map(|commit_id| (commit_id.clone(), operation.id().clone()))
+        .collect();
+    let mut parent_op_id: Option<OperationId> = None;
+    for op in dag_walk::dfs_ok(
+        [Ok(operation.clone())],
+        |op: &Operation| op.id().clone(),
+        |op: &Operation| op.parents().collect_vec(),
+    ) {
+        let op = op?;
+        // Pick the latest existing ancestor operation as the parent
+        // segment. Perhaps, breadth-first search is more appropriate here,
+        // but that wouldn't matter in practice as the operation log is
+        // mostly linear.
+        if parent_op_id.is_none() && has_index(op.id()) {
+            parent_op_id = Some(op.id().clone());
+        }
+        // TODO: no need to walk ancestors of the parent_op_id operation
+        for commit_id in op.view()?.all_referenced_commit_ids() {
+            if visited_heads.insert(commit_id.clone()) {
+                historical_heads.push((commit_id.clone(), op.id().clone()));
+            }
+        }
+    }
+    let maybe_parent_file;
+    let mut mutable_index;
+    match parent_op_id {
+        None => {
+            maybe_parent_file = None;
+            mutable_index = DefaultMutableIndex::full(commit_id_length, change_id_length);
+        }
+        Some(parent_op_id) => {
+            let parent_file = load_index(&parent_op_id)?;
+            maybe_parent_file = Some(parent_file.clone());
+            mutable_index = DefaultMutableIndex::incremental(parent_file);
+        }
+    }
+
+    tracing::info!(
+        ?maybe_parent_file,
+        heads_count = historical_heads.len(),
+        "indexing commits reachable from historical heads"
+    );
+    // Build a list of ancestors of heads where parents and predecessors come after
+    // the commit itself.
+    let parent_file_has_id = |id: &CommitId| {
+        maybe_parent_file
+            .as_ref()
+            .is_some_and(|segment| segment.as_composite().has_id(id))
+    };
+    let get_commit_with_op = |commit_id: &CommitId, op_id: &OperationId| {
+        let op_id = op_id.clone();
+        match store.get_commit(commit_id) {
+            // Propagate head's op_id to report possible source of an error.
+            // The op_id doesn't have to be included in the sort key, but
+            // that wouldn't matter since the commit should be unique.
+            Ok(commit) => Ok((CommitByCommitterTimestamp(commit), op_id)),
+            Err(source) => Err(DefaultIndexStoreError::IndexCommits { op_id, source }),
+        }
+    };
+    let commits = dag_walk::topo_order_reverse_ord_ok(
+        historical_heads
+            .iter()
+            .filter(|&(commit_id, _)| !parent_file_has_id(commit_id))
+            .map(|(commit_id, op_id)| get_commit_with_op(commit_id, op_id)),
+        |(CommitByCommitterTimestamp(commit), _)| commit.id().clone(),
+        |(CommitByCommitterTimestamp(commit), op_id)| {
+            itertools::chain(commit.parent_ids(), commit.predecessor_ids())
+                .filter(|&id| !parent_file_has_id(id))
+                .map(|commit_id| get_commit_with_op(commit_id, op_id))
+                .collect_vec()
+        },
+    )?;
+    for (CommitByCommitterTimestamp(commit), _) in commits.iter().rev() {
+        mutable_index.add_commit(commit);
+    }
+    tracing::info!(commits_count = commits.len(), "indexed commits");
+    Ok(mutable_index)
+}
+
 impl IndexStore for DefaultIndexStore {
     fn as_any(&self) -> &dyn Any {
         self
@@ -379,3 +397,75 @@ impl IndexStore for DefaultIndexStore {
         Ok(Box::new(DefaultReadonlyIndex::from_segment(index_segment)))
     }
 }
+
+/// Commit index store which keeps the index segments in memory.
+///
+/// Nothing is persisted. Clones of the store share the same segments, which
+/// is fine because the index at a given operation never changes.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryIndexStore {
+    segments: Arc<Mutex<HashMap<OperationId, Arc<ReadonlyIndexSegment>>>>,
+}
+
+impl MemoryIndexStore {
+    pub fn name() -> &'static str {
+        "memory"
+    }
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn locked_segments(&self) -> MutexGuard<'_, HashMap<OperationId, Arc<ReadonlyIndexSegment>>> {
+        self.segments.lock().unwrap()
+    }
+}
+
+impl IndexStore for MemoryIndexStore {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        Self::name()
+    }
+
+    fn get_index_at_op(
+        &self,
+        op: &Operation,
+        store: &Arc<Store>,
+    ) -> Result<Box<dyn ReadonlyIndex>, IndexReadError> {
+        let cached_segment = self.locked_segments().get(op.id()).cloned();
+        let index_segment = if let Some(index_segment) = cached_segment {
+            index_segment
+        } else {
+            let mutable_index = build_mutable_index_at_operation(
+                op,
+                store,
+                |op_id| self.locked_segments().contains_key(op_id),
+                |op_id| Ok(self.locked_segments()[op_id].clone()),
+            )
+            .map_err(|err| IndexReadError(err.into()))?;
+            let index_segment = mutable_index.squash_into_readonly();
+            self.locked_segments()
+                .insert(op.id().clone(), index_segment.clone());
+            index_segment
+        };
+        Ok(Box::new(DefaultReadonlyIndex::from_segment(index_segment)))
+    }
+
+    fn write_index(
+        &self,
+        index: Box<dyn MutableIndex>,
+        op: &Operation,
+    ) -> Result<Box<dyn ReadonlyIndex>, IndexWriteError> {
+        let index = index
+            .into_any()
+            .downcast::<DefaultMutableIndex>()
+            .expect("index to merge in must be a DefaultMutableIndex");
+        let index_segment = index.squash_into_readonly();
+        self.locked_segments()
+            .insert(op.id().clone(), index_segment.clone());
+        Ok(Box::new(DefaultReadonlyIndex::from_segment(index_segment)))
+    }
+}
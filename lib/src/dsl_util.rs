@@ -16,7 +16,9 @@
 
 use std::ascii;
 use std::collections::HashMap;
+use std::error;
 use std::fmt;
+use std::iter;
 use std::slice;
 
 use itertools::Itertools as _;
@@ -27,11 +29,14 @@ use pest::RuleType;
 /// Manages diagnostic messages emitted during parsing.
 ///
 /// `T` is usually a parse error type of the language, which contains a message
-/// and source span of 'static lifetime.
+/// and source span of 'static lifetime. Repeated reports of the same problem
+/// are merged into one entry.
 #[derive(Debug)]
 pub struct Diagnostics<T> {
     // This might be extended to [{ kind: Warning|Error, message: T }, ..].
     diagnostics: Vec<T>,
+    // Number of times each diagnostic message was reported.
+    counts: Vec<usize>,
 }
 
 impl<T> Diagnostics<T> {
@@ -39,6 +44,7 @@ impl<T> Diagnostics<T> {
     pub fn new() -> Self {
         Diagnostics {
             diagnostics: Vec::new(),
+            counts: Vec::new(),
         }
     }
 
@@ -47,26 +53,51 @@ impl<T> Diagnostics<T> {
         self.diagnostics.is_empty()
     }
 
-    /// Returns the number of diagnostic messages.
+    /// Returns the number of distinct diagnostic messages.
     pub fn len(&self) -> usize {
         self.diagnostics.len()
     }
 
-    /// Returns iterator over diagnostic messages.
+    /// Returns iterator over distinct diagnostic messages.
     pub fn iter(&self) -> slice::Iter<'_, T> {
         self.diagnostics.iter()
     }
 
+    /// Returns iterator over distinct diagnostic messages and the number of
+    /// times each message was reported.
+    pub fn iter_with_counts(&self) -> impl Iterator<Item = (&T, usize)> {
+        iter::zip(&self.diagnostics, self.counts.iter().copied())
+    }
+}
+
+impl<T: DiagnosticMessage> Diagnostics<T> {
     /// Adds a diagnostic message of warning level.
+    ///
+    /// If the same problem has already been reported, the count of the
+    /// existing message is incremented instead.
     pub fn add_warning(&mut self, diag: T) {
-        self.diagnostics.push(diag);
+        self.add_warning_with_count(diag, 1);
+    }
+
+    fn add_warning_with_count(&mut self, diag: T, count: usize) {
+        if let Some(index) = self
+            .diagnostics
+            .iter()
+            .position(|existing| existing.is_same_diagnostic(&diag))
+        {
+            self.counts[index] += count;
+        } else {
+            self.diagnostics.push(diag);
+            self.counts.push(count);
+        }
     }
 
     /// Moves diagnostic messages of different type (such as fileset warnings
     /// emitted within `file()` revset.)
     pub fn extend_with<U>(&mut self, diagnostics: Diagnostics<U>, mut f: impl FnMut(U) -> T) {
-        self.diagnostics
-            .extend(diagnostics.diagnostics.into_iter().map(&mut f));
+        for (diag, count) in iter::zip(diagnostics.diagnostics, diagnostics.counts) {
+            self.add_warning_with_count(f(diag), count);
+        }
     }
 }
 
@@ -85,6 +116,24 @@ impl<'a, T> IntoIterator for &'a Diagnostics<T> {
     }
 }
 
+/// Diagnostic message which can be compared with the other messages to merge
+/// repeated reports of the same problem.
+pub trait DiagnosticMessage {
+    /// Returns `true` if `self` and `other` report the same problem. The source
+    /// locations may differ.
+    fn is_same_diagnostic(&self, other: &Self) -> bool;
+}
+
+/// Returns `true` if the source errors of `a` and `b` have the same messages.
+pub fn has_same_error_sources(a: &dyn error::Error, b: &dyn error::Error) -> bool {
+    let sources = |err: &dyn error::Error| {
+        iter::successors(err.source(), |&err| err.source())
+            .map(|err| err.to_string())
+            .collect_vec()
+    };
+    sources(a) == sources(b)
+}
+
 /// AST node without type or name checking.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ExpressionNode<'i, T> {
@@ -520,12 +569,17 @@ impl<R: RuleType> FunctionCallParser<R> {
     }
 }
 
+/// Default maximum depth of nested alias substitution.
+pub const DEFAULT_MAX_ALIAS_EXPANSION_DEPTH: usize = 100;
+
 /// Map of symbol and function aliases.
 #[derive(Clone, Debug, Default)]
 pub struct AliasesMap<P, V> {
     symbol_aliases: HashMap<String, V>,
     // name: [(params, defn)] (sorted by arity)
     function_aliases: HashMap<String, Vec<(Vec<String>, V)>>,
+    // None means DEFAULT_MAX_ALIAS_EXPANSION_DEPTH.
+    max_expansion_depth: Option<usize>,
     // Parser type P helps prevent misuse of AliasesMap of different language.
     parser: P,
 }
@@ -539,10 +593,23 @@ impl<P, V> AliasesMap<P, V> {
         Self {
             symbol_aliases: Default::default(),
             function_aliases: Default::default(),
+            max_expansion_depth: None,
             parser: Default::default(),
         }
     }
 
+    /// Maximum depth of nested alias substitution.
+    pub fn max_expansion_depth(&self) -> usize {
+        self.max_expansion_depth
+            .unwrap_or(DEFAULT_MAX_ALIAS_EXPANSION_DEPTH)
+    }
+
+    /// Sets the maximum depth of nested alias substitution. Expanding aliases
+    /// nested deeper than that results in error.
+    pub fn set_max_expansion_depth(&mut self, depth: usize) {
+        self.max_expansion_depth = Some(depth);
+    }
+
     /// Adds new substitution rule `decl = defn`.
     ///
     /// Returns error if `decl` is invalid. The `defn` part isn't checked. A bad
@@ -704,8 +771,11 @@ pub trait AliasExpandableExpression<'i>: FoldableExpression<'i> {
 pub trait AliasExpandError: Sized {
     /// Unexpected number of arguments, or invalid combination of arguments.
     fn invalid_arguments(err: InvalidArguments<'_>) -> Self;
-    /// Recursion detected during alias substitution.
-    fn recursive_expansion(id: AliasId<'_>, span: pest::Span<'_>) -> Self;
+    /// Recursion detected during alias substitution. The `chain` lists the
+    /// aliases being expanded, from the outermost to the recursive `id`.
+    fn recursive_expansion(id: AliasId<'_>, chain: &[AliasId<'_>], span: pest::Span<'_>) -> Self;
+    /// Alias substitution nested deeper than the `max_depth`.
+    fn expansion_depth_exceeded(max_depth: usize, span: pest::Span<'_>) -> Self;
     /// Attaches alias trace to the current error.
     fn within_alias_expansion(self, id: AliasId<'_>, span: pest::Span<'_>) -> Self;
 }
@@ -747,7 +817,12 @@ where
     ) -> Result<T, E> {
         // The stack should be short, so let's simply do linear search.
         if self.states.iter().any(|s| s.id == id) {
-            return Err(E::recursive_expansion(id, span));
+            let chain = self.states.iter().map(|s| s.id).chain([id]).collect_vec();
+            return Err(E::recursive_expansion(id, &chain, span));
+        }
+        let max_depth = self.aliases_map.max_expansion_depth();
+        if self.states.len() >= max_depth {
+            return Err(E::expansion_depth_exceeded(max_depth, span));
         }
         self.states.push(AliasExpandingState { id, locals });
         // Parsed defn could be cached if needed.
@@ -852,6 +927,12 @@ where
     expander.fold_expression(node)
 }
 
+/// Returns true if the `candidate` looks like a typo of the `name`.
+pub fn is_similar(name: &str, candidate: &str) -> bool {
+    // The parameter is borrowed from clap f5540d26
+    strsim::jaro(name, candidate) > 0.7
+}
+
 /// Collects similar names from the `candidates` list.
 pub fn collect_similar<I>(name: &str, candidates: I) -> Vec<String>
 where
@@ -860,10 +941,7 @@ where
 {
     candidates
         .into_iter()
-        .filter(|cand| {
-            // The parameter is borrowed from clap f5540d26
-            strsim::jaro(name, cand.as_ref()) > 0.7
-        })
+        .filter(|cand| is_similar(name, cand.as_ref()))
         .map(|s| s.as_ref().to_owned())
         .sorted_unstable()
         .collect()
@@ -0,0 +1,89 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Utility for commit evolution history.
+
+use std::cmp::Ordering;
+
+use itertools::Itertools as _;
+
+use crate::backend::BackendResult;
+use crate::commit::Commit;
+use crate::dag_walk;
+
+/// Commit in the evolution history, and its distance from the starting
+/// commits.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CommitEvolutionEntry {
+    /// Predecessor (or starting) commit.
+    pub commit: Commit,
+    /// Number of predecessor edges followed from a starting commit to reach
+    /// this commit.
+    pub depth: usize,
+}
+
+impl Ord for CommitEvolutionEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Successors are usually committed after their predecessors, which
+        // keeps the walk (mostly) topologically ordered.
+        let self_timestamp = &self.commit.committer().timestamp.timestamp;
+        let other_timestamp = &other.commit.committer().timestamp.timestamp;
+        self_timestamp
+            .cmp(other_timestamp)
+            .then_with(|| self.commit.cmp(&other.commit))
+            .then_with(|| self.depth.cmp(&other.depth)) // to comply with Eq
+    }
+}
+
+impl PartialOrd for CommitEvolutionEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Walks the evolution history of the `start_commits` lazily, newest first.
+///
+/// Predecessors come after their successors. Linear history, which is the
+/// common case for a change amended many times, is loaded one commit at a
+/// time, so the caller can stop iterating without visiting the whole history.
+/// If `max_depth` is specified, predecessors further than that from the
+/// starting commits aren't visited.
+///
+/// There's no index of the evolution history. Each step reads the predecessor
+/// commits from the store, so the returned iterator is the only cursor to
+/// continue the walk from.
+pub fn walk_predecessors(
+    start_commits: impl IntoIterator<Item = Commit>,
+    max_depth: Option<usize>,
+) -> impl Iterator<Item = BackendResult<CommitEvolutionEntry>> {
+    let start_entries = start_commits
+        .into_iter()
+        .map(|commit| Ok(CommitEvolutionEntry { commit, depth: 0 }))
+        .collect_vec();
+    dag_walk::topo_order_reverse_lazy_ok(
+        start_entries,
+        |entry: &CommitEvolutionEntry| entry.commit.id().clone(),
+        move |entry: &CommitEvolutionEntry| {
+            let depth = entry.depth + 1;
+            let predecessors = if max_depth.is_none_or(|max| depth <= max) {
+                entry.commit.predecessors().collect_vec()
+            } else {
+                vec![]
+            };
+            predecessors
+                .into_iter()
+                .map_ok(move |commit| CommitEvolutionEntry { commit, depth })
+        },
+    )
+}
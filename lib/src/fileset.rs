@@ -15,14 +15,21 @@
 //! Functional language for selecting a set of paths.
 
 use std::collections::HashMap;
+use std::error;
+use std::io;
 use std::iter;
+use std::ops::Range;
 use std::path;
 use std::slice;
 
 use itertools::Itertools as _;
 use once_cell::sync::Lazy;
+use pollster::FutureExt as _;
 use thiserror::Error;
 
+use crate::backend::BackendError;
+use crate::backend::BackendResult;
+use crate::backend::TreeValue;
 use crate::dsl_util::collect_similar;
 use crate::fileset_parser;
 use crate::fileset_parser::BinaryOp;
@@ -43,11 +50,17 @@ use crate::matchers::Matcher;
 use crate::matchers::NothingMatcher;
 use crate::matchers::PrefixMatcher;
 use crate::matchers::UnionMatcher;
+use crate::merge::MergedTreeValue;
+use crate::merged_tree::MergedTree;
 use crate::repo_path::RelativePathParseError;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::repo_path::RepoPathUiConverter;
 use crate::repo_path::UiPathParseError;
+use crate::revset;
+use crate::revset_parser;
+use crate::revset_parser::RevsetDiagnostics;
+use crate::store::Store;
 
 /// Error occurred during file pattern parsing.
 #[derive(Debug, Error)]
@@ -197,6 +210,104 @@ fn split_glob_path(input: &str) -> (&str, &str) {
     input.split_at(prefix_len)
 }
 
+/// Kind of tree entry matched by `type:<kind>`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileTypeKind {
+    /// Regular file.
+    File,
+    /// Symbolic link.
+    Symlink,
+    /// Unresolved conflict.
+    Conflict,
+    /// Git submodule.
+    GitSubmodule,
+}
+
+impl FileTypeKind {
+    /// Returns the kind of the tree entry, or `None` if the entry is absent
+    /// or a directory.
+    pub fn from_tree_value(value: &MergedTreeValue) -> Option<Self> {
+        match value.as_resolved() {
+            Some(Some(TreeValue::File { .. })) => Some(FileTypeKind::File),
+            Some(Some(TreeValue::Symlink(_))) => Some(FileTypeKind::Symlink),
+            Some(Some(TreeValue::GitSubmodule(_))) => Some(FileTypeKind::GitSubmodule),
+            Some(Some(TreeValue::Tree(_) | TreeValue::Conflict(_)) | None) => None,
+            None => Some(FileTypeKind::Conflict),
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "file" => Some(FileTypeKind::File),
+            "symlink" => Some(FileTypeKind::Symlink),
+            "conflict" => Some(FileTypeKind::Conflict),
+            "gitmodule" => Some(FileTypeKind::GitSubmodule),
+            _ => None,
+        }
+    }
+
+    /// Returns the name used in `type:<kind>` patterns.
+    pub fn name(self) -> &'static str {
+        match self {
+            FileTypeKind::File => "file",
+            FileTypeKind::Symlink => "symlink",
+            FileTypeKind::Conflict => "conflict",
+            FileTypeKind::GitSubmodule => "gitmodule",
+        }
+    }
+}
+
+/// Properties of a tree entry which `type:<kind>`, `size()`, and
+/// `executable()` are matched against.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FileMetadata {
+    /// Kind of the entry. `size()` and `executable()` match regular files
+    /// only.
+    pub file_type: FileTypeKind,
+    /// File size in bytes.
+    pub size: u64,
+    /// Whether the executable bit is set.
+    pub executable: bool,
+}
+
+impl FileMetadata {
+    /// Reads the properties of the tree entry at `path`, or returns `None` if
+    /// the entry is absent or a directory.
+    ///
+    /// The file contents are read only if `read_size` is true. Otherwise,
+    /// `size` is 0.
+    pub async fn from_tree_value(
+        store: &Store,
+        path: &RepoPath,
+        value: &MergedTreeValue,
+        read_size: bool,
+    ) -> BackendResult<Option<Self>> {
+        let Some(file_type) = FileTypeKind::from_tree_value(value) else {
+            return Ok(None);
+        };
+        let (size, executable) = match value.as_normal() {
+            Some(TreeValue::File { id, executable }) if read_size => {
+                let mut reader = store.read_file_async(path, id).await?;
+                let size = io::copy(&mut reader, &mut io::sink()).map_err(|err| {
+                    BackendError::ReadFile {
+                        path: path.to_owned(),
+                        id: id.clone(),
+                        source: err.into(),
+                    }
+                })?;
+                (size, *executable)
+            }
+            Some(TreeValue::File { executable, .. }) => (0, *executable),
+            _ => (0, false),
+        };
+        Ok(Some(FileMetadata {
+            file_type,
+            size,
+            executable,
+        }))
+    }
+}
+
 /// AST-level representation of the fileset expression.
 #[derive(Clone, Debug)]
 pub enum FilesetExpression {
@@ -206,6 +317,29 @@ pub enum FilesetExpression {
     All,
     /// Matches basic pattern.
     Pattern(FilePattern),
+    /// Matches conflicted paths in the target revision.
+    ///
+    /// This must be substituted by `FilesetExpression::resolve_conflicts()` or
+    /// `FilesetExpression::resolve_in_tree()` before building a matcher.
+    /// Unresolved, it matches nothing.
+    Conflicts,
+    /// Matches files whose size in bytes is in the range.
+    ///
+    /// This must be substituted by `FilesetExpression::resolve_file_metadata()`
+    /// before building a matcher. Unresolved, it matches nothing.
+    Size(Range<u64>),
+    /// Matches files whose executable bit is set (or unset if `false`.)
+    ///
+    /// This must be substituted by `FilesetExpression::resolve_file_metadata()`
+    /// before building a matcher. Unresolved, it matches nothing.
+    Executable(bool),
+    /// Matches tree entries of the given kind.
+    ///
+    /// This must be substituted by `FilesetExpression::resolve_file_types()`,
+    /// `FilesetExpression::resolve_file_metadata()`, or
+    /// `FilesetExpression::resolve_in_tree()` before building a matcher.
+    /// Unresolved, it matches nothing.
+    FileType(FileTypeKind),
     /// Matches any of the expressions.
     ///
     /// Use `FilesetExpression::union_all()` to construct a union expression.
@@ -278,7 +412,11 @@ impl FilesetExpression {
             match expr {
                 FilesetExpression::None
                 | FilesetExpression::All
-                | FilesetExpression::Pattern(_) => {}
+                | FilesetExpression::Pattern(_)
+                | FilesetExpression::Conflicts
+                | FilesetExpression::Size(_)
+                | FilesetExpression::Executable(_)
+                | FilesetExpression::FileType(_) => {}
                 FilesetExpression::UnionAll(exprs) => stack.extend(exprs.iter().rev()),
                 FilesetExpression::Intersection(expr1, expr2)
                 | FilesetExpression::Difference(expr1, expr2) => {
@@ -290,6 +428,184 @@ impl FilesetExpression {
         })
     }
 
+    /// Returns true if this expression contains `conflicts()`.
+    pub fn contains_conflicts(&self) -> bool {
+        self.dfs_pre()
+            .any(|expr| matches!(expr, FilesetExpression::Conflicts))
+    }
+
+    /// Substitutes `conflicts()` with the paths returned by
+    /// `conflicted_paths`.
+    ///
+    /// The callback is invoked only if the expression contains
+    /// `conflicts()`.
+    pub fn resolve_conflicts(self, conflicted_paths: impl FnOnce() -> Vec<RepoPathBuf>) -> Self {
+        if !self.contains_conflicts() {
+            return self;
+        }
+        let conflicts = FilesetExpression::union_all(
+            conflicted_paths()
+                .into_iter()
+                .map(FilesetExpression::file_path)
+                .collect(),
+        );
+        self.substitute_leaves(&mut |expr| match expr {
+            FilesetExpression::Conflicts => conflicts.clone(),
+            _ => expr,
+        })
+    }
+
+    /// Returns the name of the first function or pattern which has to be
+    /// resolved against a tree before building a matcher.
+    pub fn unresolved_predicate_name(&self) -> Option<String> {
+        self.dfs_pre().find_map(|expr| match expr {
+            FilesetExpression::Conflicts => Some("conflicts()".to_owned()),
+            FilesetExpression::Size(_) => Some("size()".to_owned()),
+            FilesetExpression::Executable(_) => Some("executable()".to_owned()),
+            FilesetExpression::FileType(kind) => Some(format!("type:{}", kind.name())),
+            _ => None,
+        })
+    }
+
+    /// Substitutes the functions and patterns which depend on the `tree`
+    /// contents with the matching paths in it.
+    pub fn resolve_in_tree(self, tree: &MergedTree) -> BackendResult<Self> {
+        let expr = self.resolve_conflicts(|| tree.conflicts().map(|(path, _)| path).collect());
+        if !expr.contains_file_metadata() {
+            return Ok(expr);
+        }
+        let matcher = expr.to_superset_matcher();
+        let read_sizes = expr.contains_file_sizes();
+        let files = async {
+            let mut files = vec![];
+            for (path, value) in tree.entries_matching(matcher.as_ref()) {
+                let metadata =
+                    FileMetadata::from_tree_value(tree.store(), &path, &value?, read_sizes).await?;
+                if let Some(metadata) = metadata {
+                    files.push((path, metadata));
+                }
+            }
+            Ok::<_, BackendError>(files)
+        }
+        .block_on()?;
+        Ok(expr.resolve_file_metadata(|| files))
+    }
+
+    /// Returns true if this expression contains `type:<kind>`.
+    pub fn contains_file_types(&self) -> bool {
+        self.dfs_pre()
+            .any(|expr| matches!(expr, FilesetExpression::FileType(_)))
+    }
+
+    /// Substitutes `type:<kind>` with the paths of the matching `entries`.
+    /// Paths not listed in `entries` match none of the kinds.
+    ///
+    /// The callback is invoked only if the expression contains `type:<kind>`.
+    pub fn resolve_file_types(
+        self,
+        entries: impl FnOnce() -> Vec<(RepoPathBuf, FileTypeKind)>,
+    ) -> Self {
+        if !self.contains_file_types() {
+            return self;
+        }
+        let entries = entries();
+        self.substitute_leaves(&mut |expr| match expr {
+            FilesetExpression::FileType(kind) => FilesetExpression::union_all(
+                entries
+                    .iter()
+                    .filter(|(_, entry_kind)| *entry_kind == kind)
+                    .map(|(path, _)| FilesetExpression::file_path(path.clone()))
+                    .collect(),
+            ),
+            _ => expr,
+        })
+    }
+
+    /// Returns true if this expression contains `type:<kind>`, `size()`, or
+    /// `executable()`, which can't be evaluated by path.
+    pub fn contains_file_metadata(&self) -> bool {
+        self.dfs_pre().any(|expr| {
+            matches!(
+                expr,
+                FilesetExpression::Size(_)
+                    | FilesetExpression::Executable(_)
+                    | FilesetExpression::FileType(_)
+            )
+        })
+    }
+
+    /// Returns true if this expression contains `size()`, which requires the
+    /// file contents to be read.
+    pub fn contains_file_sizes(&self) -> bool {
+        self.dfs_pre()
+            .any(|expr| matches!(expr, FilesetExpression::Size(_)))
+    }
+
+    /// Substitutes `type:<kind>`, `size()`, and `executable()` with the paths
+    /// of the matching `files`. Paths not listed in `files` match none of
+    /// them.
+    ///
+    /// The callback is invoked only if the expression contains `type:<kind>`,
+    /// `size()`, or `executable()`.
+    pub fn resolve_file_metadata(
+        self,
+        files: impl FnOnce() -> Vec<(RepoPathBuf, FileMetadata)>,
+    ) -> Self {
+        if !self.contains_file_metadata() {
+            return self;
+        }
+        let files = files();
+        let matching_paths = |matches: &dyn Fn(&FileMetadata) -> bool| {
+            FilesetExpression::union_all(
+                files
+                    .iter()
+                    .filter(|(_, metadata)| matches(metadata))
+                    .map(|(path, _)| FilesetExpression::file_path(path.clone()))
+                    .collect(),
+            )
+        };
+        let is_file = |metadata: &FileMetadata| metadata.file_type == FileTypeKind::File;
+        self.substitute_leaves(&mut |expr| match expr {
+            FilesetExpression::Size(range) => {
+                matching_paths(&|metadata| is_file(metadata) && range.contains(&metadata.size))
+            }
+            FilesetExpression::Executable(executable) => {
+                matching_paths(&|metadata| is_file(metadata) && metadata.executable == executable)
+            }
+            FilesetExpression::FileType(kind) => {
+                matching_paths(&|metadata| metadata.file_type == kind)
+            }
+            _ => expr,
+        })
+    }
+
+    /// Replaces leaf expressions with the ones returned by `f`.
+    fn substitute_leaves(self, f: &mut impl FnMut(Self) -> Self) -> Self {
+        match self {
+            FilesetExpression::None
+            | FilesetExpression::All
+            | FilesetExpression::Pattern(_)
+            | FilesetExpression::Conflicts
+            | FilesetExpression::Size(_)
+            | FilesetExpression::Executable(_)
+            | FilesetExpression::FileType(_) => f(self),
+            FilesetExpression::UnionAll(exprs) => FilesetExpression::union_all(
+                exprs
+                    .into_iter()
+                    .map(|expr| expr.substitute_leaves(f))
+                    .collect(),
+            ),
+            FilesetExpression::Intersection(expr1, expr2) => FilesetExpression::Intersection(
+                Box::new(expr1.substitute_leaves(f)),
+                Box::new(expr2.substitute_leaves(f)),
+            ),
+            FilesetExpression::Difference(expr1, expr2) => FilesetExpression::Difference(
+                Box::new(expr1.substitute_leaves(f)),
+                Box::new(expr2.substitute_leaves(f)),
+            ),
+        }
+    }
+
     /// Iterates literal paths recursively from this expression.
     ///
     /// For example, `"a", "b", "c"` will be yielded in that order for
@@ -307,6 +623,49 @@ impl FilesetExpression {
     pub fn to_matcher(&self) -> Box<dyn Matcher> {
         build_union_matcher(self.as_union_all())
     }
+
+    /// Transforms the expression tree to `Matcher` object which matches at
+    /// least the paths the expression would match once resolved.
+    ///
+    /// Unresolved functions and patterns such as `conflicts()` are assumed to
+    /// match any path.
+    pub fn to_superset_matcher(&self) -> Box<dyn Matcher> {
+        self.clone().approximate(true).to_matcher()
+    }
+
+    /// Replaces unresolved leaves with `all()` if `superset`, or `none()`
+    /// otherwise. The polarity flips on the right-hand side of a difference.
+    fn approximate(self, superset: bool) -> Self {
+        match self {
+            FilesetExpression::None | FilesetExpression::All | FilesetExpression::Pattern(_) => {
+                self
+            }
+            FilesetExpression::Conflicts
+            | FilesetExpression::Size(_)
+            | FilesetExpression::Executable(_)
+            | FilesetExpression::FileType(_) => {
+                if superset {
+                    FilesetExpression::All
+                } else {
+                    FilesetExpression::None
+                }
+            }
+            FilesetExpression::UnionAll(exprs) => FilesetExpression::union_all(
+                exprs
+                    .into_iter()
+                    .map(|expr| expr.approximate(superset))
+                    .collect(),
+            ),
+            FilesetExpression::Intersection(expr1, expr2) => FilesetExpression::Intersection(
+                Box::new(expr1.approximate(superset)),
+                Box::new(expr2.approximate(superset)),
+            ),
+            FilesetExpression::Difference(expr1, expr2) => FilesetExpression::Difference(
+                Box::new(expr1.approximate(superset)),
+                Box::new(expr2.approximate(!superset)),
+            ),
+        }
+    }
 }
 
 /// Transforms the union `expressions` to `Matcher` object.
@@ -322,6 +681,11 @@ fn build_union_matcher(expressions: &[FilesetExpression]) -> Box<dyn Matcher> {
         let matcher: Box<dyn Matcher> = match expr {
             // None and All are supposed to be simplified by caller.
             FilesetExpression::None => Box::new(NothingMatcher),
+            // Conflicts and file metadata are supposed to be resolved by caller.
+            FilesetExpression::Conflicts
+            | FilesetExpression::Size(_)
+            | FilesetExpression::Executable(_)
+            | FilesetExpression::FileType(_) => Box::new(NothingMatcher),
             FilesetExpression::All => Box::new(EverythingMatcher),
             FilesetExpression::Pattern(pattern) => {
                 match pattern {
@@ -397,9 +761,70 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, FilesetFunction>> = Lazy
         function.expect_no_arguments()?;
         Ok(FilesetExpression::all())
     });
+    map.insert("conflicts", |_diagnostics, _path_converter, function| {
+        function.expect_no_arguments()?;
+        Ok(FilesetExpression::Conflicts)
+    });
+    map.insert("size", |_diagnostics, _path_converter, function| {
+        let [arg] = function.expect_exact_arguments()?;
+        let range = expect_size_range(arg)?;
+        Ok(FilesetExpression::Size(range))
+    });
+    map.insert("executable", |diagnostics, _path_converter, function| {
+        let ([], [opt_arg]) = function.expect_arguments()?;
+        let executable = if let Some(arg) = opt_arg {
+            expect_boolean(diagnostics, arg)?
+        } else {
+            true
+        };
+        Ok(FilesetExpression::Executable(executable))
+    });
     map
 });
 
+/// Returns the text of an identifier or string literal.
+fn expect_literal_text<'a>(
+    node: &'a ExpressionNode,
+    type_name: &str,
+) -> FilesetParseResult<&'a str> {
+    match &node.kind {
+        ExpressionKind::Identifier(text) => Ok(*text),
+        ExpressionKind::String(text) => Ok(text.as_str()),
+        _ => Err(FilesetParseError::expression(
+            format!("Expected expression of type {type_name}"),
+            node.span,
+        )),
+    }
+}
+
+/// Parses `n`, `start..`, `..end`, `start..end`, or `..` as a range of byte
+/// sizes by the same rules as the `commit_size()` revset function.
+fn expect_size_range(node: &ExpressionNode) -> FilesetParseResult<Range<u64>> {
+    revset_parser::parse_program(node.span.as_str())
+        .and_then(|node| revset::expect_size_range(&mut RevsetDiagnostics::new(), &node))
+        .map_err(|err| {
+            let new_err = FilesetParseError::expression(err.message(), node.span);
+            match error::Error::source(&err) {
+                Some(source) => new_err.with_source(source.to_string()),
+                None => new_err,
+            }
+        })
+}
+
+fn expect_boolean(
+    _diagnostics: &mut FilesetDiagnostics,
+    node: &ExpressionNode,
+) -> FilesetParseResult<bool> {
+    match expect_literal_text(node, "boolean")? {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(FilesetParseError::expression(
+            "Expected true or false",
+            node.span,
+        )),
+    }
+}
+
 fn resolve_function(
     diagnostics: &mut FilesetDiagnostics,
     path_converter: &RepoPathUiConverter,
@@ -436,6 +861,18 @@ fn resolve_expression(
                 FilePattern::cwd_prefix_path(path_converter, name).map_err(wrap_pattern_error)?;
             Ok(FilesetExpression::pattern(pattern))
         }
+        ExpressionKind::StringPattern {
+            kind: "type",
+            value,
+        } => {
+            let kind = FileTypeKind::from_name(value).ok_or_else(|| {
+                FilesetParseError::expression(
+                    "Invalid file type, expected one of file, symlink, conflict, gitmodule",
+                    node.span,
+                )
+            })?;
+            Ok(FilesetExpression::FileType(kind))
+        }
         ExpressionKind::StringPattern { kind, value } => {
             let pattern = FilePattern::from_str_kind(path_converter, value, kind)
                 .map_err(wrap_pattern_error)?;
@@ -751,6 +1188,26 @@ mod tests {
 
         insta::assert_debug_snapshot!(parse("all()").unwrap(), @"All");
         insta::assert_debug_snapshot!(parse("none()").unwrap(), @"None");
+        insta::assert_debug_snapshot!(parse("conflicts()").unwrap(), @"Conflicts");
+        insta::assert_debug_snapshot!(
+            parse("size(1MiB..)").unwrap(),
+            @"Size(1048576..18446744073709551615)");
+        insta::assert_debug_snapshot!(parse("size(..4K)").unwrap(), @"Size(0..4096)");
+        insta::assert_debug_snapshot!(parse("size(100..2K)").unwrap(), @"Size(100..2048)");
+        insta::assert_debug_snapshot!(parse("size(512)").unwrap(), @"Size(512..513)");
+        insta::assert_debug_snapshot!(parse("size('4 KB')").unwrap(), @"Size(4096..4097)");
+        insta::assert_debug_snapshot!(parse("size(1XB..)").unwrap_err().kind(), @r#"Expression("Invalid size")"#);
+        insta::assert_debug_snapshot!(parse("size(x|y)").unwrap_err().kind(), @r#"Expression("Expected expression of type size")"#);
+        insta::assert_debug_snapshot!(parse("executable()").unwrap(), @"Executable(true)");
+        insta::assert_debug_snapshot!(parse("executable(false)").unwrap(), @"Executable(false)");
+        insta::assert_debug_snapshot!(parse("executable(x)").unwrap_err().kind(), @r#"Expression("Expected true or false")"#);
+        insta::assert_debug_snapshot!(parse("type:symlink").unwrap(), @"FileType(Symlink)");
+        insta::assert_debug_snapshot!(parse("type:gitmodule").unwrap(), @"FileType(GitSubmodule)");
+        insta::assert_debug_snapshot!(parse("type:dir").unwrap_err().kind(), @r#"
+        Expression(
+            "Invalid file type, expected one of file, symlink, conflict, gitmodule",
+        )
+        "#);
         insta::assert_debug_snapshot!(parse("all(x)").unwrap_err().kind(), @r#"
         InvalidArguments {
             name: "all",
@@ -838,6 +1295,189 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_conflicts() {
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse =
+            |text| parse_maybe_bare(&mut FilesetDiagnostics::new(), text, &path_converter).unwrap();
+
+        // The callback isn't invoked if there's no conflicts()
+        let expr = parse("src");
+        assert!(!expr.contains_conflicts());
+        let expr = expr.resolve_conflicts(|| panic!("shouldn't be called"));
+        insta::assert_debug_snapshot!(expr, @r#"Pattern(PrefixPath("src"))"#);
+
+        let expr = parse("conflicts() & glob:'src/**'");
+        assert!(expr.contains_conflicts());
+        let expr =
+            expr.resolve_conflicts(|| ["README", "src/a", "src/b/c"].map(repo_path_buf).to_vec());
+        assert!(!expr.contains_conflicts());
+        let matcher = expr.to_matcher();
+        assert!(!matcher.matches(RepoPath::from_internal_string("README")));
+        assert!(matcher.matches(RepoPath::from_internal_string("src/a")));
+        assert!(matcher.matches(RepoPath::from_internal_string("src/b/c")));
+        assert!(!matcher.matches(RepoPath::from_internal_string("src/d")));
+
+        // No conflicts
+        let expr = parse("conflicts() | README").resolve_conflicts(Vec::new);
+        insta::assert_debug_snapshot!(expr, @r#"
+        UnionAll(
+            [
+                None,
+                Pattern(PrefixPath("README")),
+            ],
+        )
+        "#);
+
+        // Unresolved conflicts() matches nothing
+        assert_eq!(parse("src").unresolved_predicate_name(), None);
+        assert_eq!(
+            parse("src | ~conflicts()").unresolved_predicate_name(),
+            Some("conflicts()".to_owned())
+        );
+        insta::assert_debug_snapshot!(parse("conflicts()").to_matcher(), @"NothingMatcher");
+
+        // ... but any path may match once resolved
+        let matcher = parse("src & ~(README | conflicts() & ~src/a)").to_superset_matcher();
+        assert!(!matcher.matches(RepoPath::from_internal_string("README")));
+        assert!(matcher.matches(RepoPath::from_internal_string("src/a")));
+        assert!(matcher.matches(RepoPath::from_internal_string("src/b")));
+    }
+
+    #[test]
+    fn test_resolve_file_metadata() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text: &str| {
+            parse_maybe_bare(&mut FilesetDiagnostics::new(), text, &path_converter).unwrap()
+        };
+        let files = || {
+            vec![
+                (
+                    repo_path_buf("small"),
+                    FileMetadata {
+                        file_type: FileTypeKind::File,
+                        size: 10,
+                        executable: false,
+                    },
+                ),
+                (
+                    repo_path_buf("large"),
+                    FileMetadata {
+                        file_type: FileTypeKind::File,
+                        size: 2048,
+                        executable: false,
+                    },
+                ),
+                (
+                    repo_path_buf("script"),
+                    FileMetadata {
+                        file_type: FileTypeKind::File,
+                        size: 100,
+                        executable: true,
+                    },
+                ),
+                (
+                    repo_path_buf("link"),
+                    FileMetadata {
+                        file_type: FileTypeKind::Symlink,
+                        size: 5,
+                        executable: false,
+                    },
+                ),
+            ]
+        };
+        let matched_paths = |text: &str| {
+            let matcher = parse(text).resolve_file_metadata(files).to_matcher();
+            ["small", "large", "script", "link", "other"]
+                .into_iter()
+                .filter(|path| matcher.matches(RepoPath::from_internal_string(path)))
+                .collect::<Vec<_>>()
+        };
+
+        // The callback isn't invoked if there's no size() or executable()
+        let expr = parse("src");
+        assert!(!expr.contains_file_metadata());
+        expr.resolve_file_metadata(|| panic!("shouldn't be called"));
+
+        assert!(parse("~size(1K..)").contains_file_metadata());
+        assert_eq!(matched_paths("size(1K..)"), ["large"]);
+        assert_eq!(matched_paths("size(..100)"), ["small"]);
+        assert_eq!(matched_paths("size(100)"), ["script"]);
+        assert_eq!(matched_paths("executable()"), ["script"]);
+        assert_eq!(matched_paths("executable(false)"), ["small", "large"]);
+        // Non-files and paths without metadata match neither of them
+        assert_eq!(
+            matched_paths("~executable()"),
+            ["small", "large", "link", "other"]
+        );
+        assert_eq!(matched_paths("glob:'s*' & size(..50)"), ["small"]);
+
+        assert!(parse("type:symlink").contains_file_metadata());
+        assert_eq!(matched_paths("type:symlink"), ["link"]);
+        assert_eq!(matched_paths("type:file"), ["small", "large", "script"]);
+        assert_eq!(matched_paths("~type:file"), ["link", "other"]);
+        assert!(matched_paths("type:conflict").is_empty());
+
+        // Unresolved size() and executable() match nothing
+        insta::assert_debug_snapshot!(parse("size(..)").to_matcher(), @"NothingMatcher");
+        insta::assert_debug_snapshot!(parse("executable()").to_matcher(), @"NothingMatcher");
+    }
+
+    #[test]
+    fn test_resolve_file_types() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text: &str| {
+            parse_maybe_bare(&mut FilesetDiagnostics::new(), text, &path_converter).unwrap()
+        };
+        let entries = || {
+            vec![
+                (repo_path_buf("file"), FileTypeKind::File),
+                (repo_path_buf("link"), FileTypeKind::Symlink),
+                (repo_path_buf("conflicted"), FileTypeKind::Conflict),
+                (repo_path_buf("sub"), FileTypeKind::GitSubmodule),
+            ]
+        };
+        let matched_paths = |text: &str| {
+            let matcher = parse(text).resolve_file_types(entries).to_matcher();
+            ["file", "link", "conflicted", "sub", "other"]
+                .into_iter()
+                .filter(|path| matcher.matches(RepoPath::from_internal_string(path)))
+                .collect::<Vec<_>>()
+        };
+
+        // The callback isn't invoked if there's no type:<kind>
+        let expr = parse("size(1K..)");
+        assert!(!expr.contains_file_types());
+        expr.resolve_file_types(|| panic!("shouldn't be called"));
+
+        assert_eq!(matched_paths("type:file"), ["file"]);
+        assert_eq!(matched_paths("type:symlink"), ["link"]);
+        assert_eq!(matched_paths("type:conflict"), ["conflicted"]);
+        assert_eq!(matched_paths("type:gitmodule"), ["sub"]);
+        assert_eq!(
+            matched_paths("~type:file"),
+            ["link", "conflicted", "sub", "other"]
+        );
+        assert_eq!(
+            matched_paths("type:conflict | link"),
+            ["link", "conflicted"]
+        );
+
+        // Unresolved type:<kind> matches nothing
+        insta::assert_debug_snapshot!(parse("type:file").to_matcher(), @"NothingMatcher");
+    }
+
     #[test]
     fn test_build_matcher_simple() {
         let settings = insta_settings();
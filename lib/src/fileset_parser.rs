@@ -27,6 +27,7 @@ use pest_derive::Parser;
 use thiserror::Error;
 
 use crate::dsl_util;
+use crate::dsl_util::DiagnosticMessage;
 use crate::dsl_util::Diagnostics;
 use crate::dsl_util::InvalidArguments;
 use crate::dsl_util::StringLiteralParser;
@@ -141,6 +142,12 @@ impl FilesetParseError {
     }
 }
 
+impl DiagnosticMessage for FilesetParseError {
+    fn is_same_diagnostic(&self, other: &Self) -> bool {
+        self.kind == other.kind && dsl_util::has_same_error_sources(self, other)
+    }
+}
+
 impl From<pest::error::Error<Rule>> for FilesetParseError {
     fn from(err: pest::error::Error<Rule>) -> Self {
         FilesetParseError {
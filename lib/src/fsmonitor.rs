@@ -77,11 +77,36 @@ impl FsmonitorSettings {
     }
 }
 
+/// Error reported by a configured filesystem monitor which couldn't be
+/// queried. Snapshots fall back to crawling the entire working copy.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct FsmonitorError {
+    /// The class of the error.
+    pub kind: FsmonitorErrorKind,
+    /// Human-readable description of the error, including its causes.
+    pub message: String,
+}
+
+/// Classification of filesystem monitor errors.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum FsmonitorErrorKind {
+    /// Couldn't connect to the filesystem monitor. It's probably not installed
+    /// or not running.
+    Connect,
+    /// The filesystem monitor couldn't watch the working copy root.
+    Root,
+    /// The filesystem monitor failed to answer a query.
+    Query,
+    /// The filesystem monitor failed to list or register triggers.
+    Trigger,
+}
+
 /// Filesystem monitor integration using Watchman
 /// (<https://facebook.github.io/watchman/>). Requires `watchman` to already be
 /// installed on the system.
 #[cfg(feature = "watchman")]
 pub mod watchman {
+    use std::iter;
     use std::path::Path;
     use std::path::PathBuf;
 
@@ -97,6 +122,9 @@ pub mod watchman {
     use watchman_client::prelude::QueryResult;
     use watchman_client::prelude::TriggerRequest;
 
+    use super::FsmonitorError;
+    use super::FsmonitorErrorKind;
+
     /// Represents an instance in time from the perspective of the filesystem
     /// monitor.
     ///
@@ -165,6 +193,31 @@ pub mod watchman {
         WatchmanTriggerError(#[source] watchman_client::Error),
     }
 
+    impl Error {
+        /// Classifies this error.
+        pub fn kind(&self) -> FsmonitorErrorKind {
+            match self {
+                Error::WatchmanConnectError(_) => FsmonitorErrorKind::Connect,
+                Error::CanonicalizeRootError(_) | Error::ResolveRootError(_) => {
+                    FsmonitorErrorKind::Root
+                }
+                Error::WatchmanQueryError(_) => FsmonitorErrorKind::Query,
+                Error::WatchmanTriggerError(_) => FsmonitorErrorKind::Trigger,
+            }
+        }
+    }
+
+    impl From<&Error> for FsmonitorError {
+        fn from(err: &Error) -> Self {
+            let message = iter::successors(Some(err as &dyn std::error::Error), |err| err.source())
+                .join(": ");
+            FsmonitorError {
+                kind: err.kind(),
+                message,
+            }
+        }
+    }
+
     /// Handle to the underlying Watchman instance.
     pub struct Fsmonitor {
         client: watchman_client::Client,
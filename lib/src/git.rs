@@ -34,6 +34,7 @@ use thiserror::Error;
 
 use crate::backend::BackendError;
 use crate::backend::BackendResult;
+use crate::backend::ChangeId;
 use crate::backend::CommitId;
 use crate::backend::TreeValue;
 use crate::commit::Commit;
@@ -384,6 +385,9 @@ impl GitImportError {
 pub struct GitImportStats {
     /// Commits superseded by newly imported commits.
     pub abandoned_commits: Vec<CommitId>,
+    /// Visible commits `(old_id, new_id)`s rewritten into newly imported
+    /// commits of the same change id, sorted by `old_id`.
+    pub rewritten_commits: Vec<(CommitId, CommitId)>,
     /// Remote bookmark `(symbol, (old_remote_ref, new_target))`s to be merged
     /// in to the local bookmarks, sorted by `symbol`.
     pub changed_remote_bookmarks: Vec<(RemoteRefSymbolBuf, (RemoteRef, RefTarget))>,
@@ -455,6 +459,7 @@ pub fn import_some_refs(
         .flat_map(|(_, new_target)| new_target.added_ids())
         .filter(|&id| !index.has_id(id));
     let heads_imported = git_backend.import_head_commits(missing_head_ids).is_ok();
+    let old_heads = mut_repo.view().heads().iter().cloned().collect_vec();
 
     // Import new remote heads
     let mut head_commits = Vec::new();
@@ -521,14 +526,24 @@ pub fn import_some_refs(
         // TODO: If we add Git-tracking tag, it will be updated here.
     }
 
-    let abandoned_commits = if git_settings.abandon_unreachable_commits {
+    let mut abandoned_commits = if git_settings.abandon_unreachable_commits {
         abandon_unreachable_commits(mut_repo, &changed_remote_bookmarks, &changed_remote_tags)
             .map_err(GitImportError::Backend)?
     } else {
         vec![]
     };
+    // Rewrites take precedence over abandoned commits that were superseded by
+    // the new versions of the same changes.
+    let rewritten_commits = if git_settings.import_rewrites_by_change_id {
+        record_rewrites_by_change_id(mut_repo, &old_heads, &head_commits)
+            .map_err(GitImportError::Backend)?
+    } else {
+        vec![]
+    };
+    abandoned_commits.retain(|id| !rewritten_commits.iter().any(|(old_id, _)| old_id == id));
     let stats = GitImportStats {
         abandoned_commits,
+        rewritten_commits,
         changed_remote_bookmarks,
         changed_remote_tags,
         failed_ref_names,
@@ -575,6 +590,58 @@ fn abandon_unreachable_commits(
     Ok(abandoned_commit_ids)
 }
 
+/// Records visible commits as rewritten into newly imported commits of the
+/// same change id.
+///
+/// Change ids of the imported commits are read from the Git commit headers, so
+/// this makes commits amended by other jj users supersede the local versions
+/// instead of becoming divergent. Commits which have already been hidden are
+/// left alone. Returns `(old_id, new_id)`s sorted by `old_id`.
+fn record_rewrites_by_change_id(
+    mut_repo: &mut MutableRepo,
+    old_heads: &[CommitId],
+    new_heads: &[Commit],
+) -> BackendResult<Vec<(CommitId, CommitId)>> {
+    if new_heads.is_empty() {
+        return Ok(vec![]);
+    }
+    let new_head_ids = new_heads.iter().map(|commit| commit.id().clone()).collect();
+    let new_commit_ids: Vec<CommitId> = RevsetExpression::commits(old_heads.to_vec())
+        .range(&RevsetExpression::commits(new_head_ids))
+        .evaluate(mut_repo)
+        .map_err(|err| err.expect_backend_error())?
+        .iter()
+        .try_collect()
+        .map_err(|err| err.expect_backend_error())?;
+    let mut new_ids_by_change_id: HashMap<ChangeId, Vec<CommitId>> = HashMap::new();
+    for id in &new_commit_ids {
+        let commit = mut_repo.store().get_commit(id)?;
+        new_ids_by_change_id
+            .entry(commit.change_id().clone())
+            .or_default()
+            .push(id.clone());
+    }
+
+    let mut rewritten_commits = vec![];
+    for (change_id, new_ids) in &new_ids_by_change_id {
+        // The change was split or became divergent on the other side, so we
+        // can't tell which one is the successor.
+        let [new_id] = new_ids.as_slice() else {
+            continue;
+        };
+        let old_ids = mut_repo.resolve_change_id(change_id).unwrap_or_default();
+        for old_id in old_ids {
+            if old_id == *new_id || mut_repo.index().is_ancestor(&old_id, new_id) {
+                continue;
+            }
+            mut_repo.set_rewritten_commit(old_id.clone(), new_id.clone());
+            rewritten_commits.push((old_id, new_id.clone()));
+        }
+    }
+    rewritten_commits.sort();
+    Ok(rewritten_commits)
+}
+
 /// Calculates diff of git refs to be imported.
 fn diff_refs_to_import(
     view: &View,
@@ -848,6 +915,9 @@ pub enum FailedRefExportReason {
     /// The ref points to the root commit, which Git doesn't have.
     #[error("Ref cannot point to the root commit in Git")]
     OnRootCommit,
+    /// The ref points to a commit which doesn't exist in the Git repo.
+    #[error("Target commit doesn't exist in Git")]
+    MissingTarget,
     /// We wanted to delete it, but it had been modified in Git.
     #[error("Deleted ref had been modified in Git")]
     DeletedInJjModifiedInGit,
@@ -870,6 +940,9 @@ pub enum FailedRefExportReason {
 pub struct GitExportStats {
     /// Remote bookmarks that couldn't be exported, sorted by `symbol`.
     pub failed_bookmarks: Vec<(RemoteRefSymbolBuf, FailedRefExportReason)>,
+    /// Remote bookmarks that weren't exported because their targets are
+    /// conflicted, sorted by `symbol`.
+    pub conflicted_bookmarks: Vec<RemoteRefSymbolBuf>,
 }
 
 #[derive(Debug)]
@@ -884,6 +957,8 @@ struct RefsToExport {
     bookmarks_to_delete: Vec<(RemoteRefSymbolBuf, gix::ObjectId)>,
     /// Remote bookmarks that couldn't be exported, sorted by `symbol`.
     failed_bookmarks: Vec<(RemoteRefSymbolBuf, FailedRefExportReason)>,
+    /// Remote bookmarks that have conflicted targets, sorted by `symbol`.
+    conflicted_bookmarks: Vec<RemoteRefSymbolBuf>,
 }
 
 /// Export changes to bookmarks made in the Jujutsu repo compared to our last
@@ -917,6 +992,7 @@ pub fn export_some_refs(
         bookmarks_to_update,
         bookmarks_to_delete,
         mut failed_bookmarks,
+        conflicted_bookmarks,
     } = diff_refs_to_export(
         mut_repo.view(),
         mut_repo.store().root_commit_id(),
@@ -977,6 +1053,10 @@ pub fn export_some_refs(
             failed_bookmarks.push((symbol, FailedRefExportReason::InvalidGitName));
             continue;
         };
+        if !git_repo.has_object(new_oid) {
+            failed_bookmarks.push((symbol, FailedRefExportReason::MissingTarget));
+            continue;
+        }
         if let Err(reason) = update_git_ref(&git_repo, &git_ref_name, old_oid, new_oid) {
             failed_bookmarks.push((symbol, reason));
         } else {
@@ -997,7 +1077,10 @@ pub fn export_some_refs(
         },
     );
 
-    Ok(GitExportStats { failed_bookmarks })
+    Ok(GitExportStats {
+        failed_bookmarks,
+        conflicted_bookmarks,
+    })
 }
 
 fn copy_exportable_local_bookmarks_to_remote_view(
@@ -1072,6 +1155,7 @@ fn diff_refs_to_export(
     let mut bookmarks_to_update = Vec::new();
     let mut bookmarks_to_delete = Vec::new();
     let mut failed_bookmarks = Vec::new();
+    let mut conflicted_bookmarks = Vec::new();
     let root_commit_target = RefTarget::normal(root_commit_id.clone());
     for (symbol, (old_target, new_target)) in all_bookmark_targets {
         if new_target == old_target {
@@ -1098,6 +1182,7 @@ fn diff_refs_to_export(
             bookmarks_to_update.push((symbol.to_owned(), (old_oid, new_oid)));
         } else if new_target.has_conflict() {
             // Skip conflicts and leave the old value in git_refs
+            conflicted_bookmarks.push(symbol.to_owned());
             continue;
         } else {
             assert!(new_target.is_absent());
@@ -1109,10 +1194,12 @@ fn diff_refs_to_export(
     bookmarks_to_update.sort_unstable_by(|(sym1, _), (sym2, _)| sym1.cmp(sym2));
     bookmarks_to_delete.sort_unstable_by(|(sym1, _), (sym2, _)| sym1.cmp(sym2));
     failed_bookmarks.sort_unstable_by(|(sym1, _), (sym2, _)| sym1.cmp(sym2));
+    conflicted_bookmarks.sort_unstable();
     RefsToExport {
         bookmarks_to_update,
         bookmarks_to_delete,
         failed_bookmarks,
+        conflicted_bookmarks,
     }
 }
 
@@ -2098,6 +2185,22 @@ pub enum GitFetchError {
     Subprocess(#[from] GitSubprocessError),
 }
 
+impl GitFetchError {
+    /// Returns true if the fetch was cut off while transferring objects, so
+    /// that retrying it may succeed.
+    pub fn is_transfer_interrupted(&self) -> bool {
+        match self {
+            Self::NoSuchRemote(_) | Self::InvalidBranchPattern(_) | Self::RemoteName(_) => false,
+            #[cfg(feature = "git2")]
+            Self::Git2(err) => matches!(
+                err.class(),
+                git2::ErrorClass::Http | git2::ErrorClass::Ssh | git2::ErrorClass::Os
+            ),
+            Self::Subprocess(err) => matches!(err, GitSubprocessError::TransferInterrupted(_)),
+        }
+    }
+}
+
 // TODO: If Git2 implementation is removed, this can be replaced with
 // UnexpectedGitBackendError.
 #[derive(Debug, Error)]
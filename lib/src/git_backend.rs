@@ -58,6 +58,7 @@ use crate::backend::Conflict;
 use crate::backend::ConflictId;
 use crate::backend::ConflictTerm;
 use crate::backend::CopyRecord;
+use crate::backend::DuplicateSource;
 use crate::backend::FileId;
 use crate::backend::MergedTreeId;
 use crate::backend::MillisSinceEpoch;
@@ -81,12 +82,14 @@ use crate::object_id::ObjectId;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::repo_path::RepoPathComponentBuf;
+use crate::settings::ChangeIdLocation;
 use crate::settings::UserSettings;
 use crate::stacked_table::MutableTable;
 use crate::stacked_table::ReadonlyTable;
 use crate::stacked_table::TableSegment as _;
 use crate::stacked_table::TableStore;
 use crate::stacked_table::TableStoreError;
+use crate::trailer;
 
 const HASH_LENGTH: usize = 20;
 const CHANGE_ID_LENGTH: usize = 16;
@@ -96,6 +99,7 @@ const CONFLICT_SUFFIX: &str = ".jjconflict";
 
 pub const JJ_TREES_COMMIT_HEADER: &[u8] = b"jj:trees";
 pub const CHANGE_ID_COMMIT_HEADER: &[u8] = b"change-id";
+pub const CHANGE_ID_COMMIT_TRAILER: &str = "JJ-Change-Id";
 
 #[derive(Debug, Error)]
 pub enum GitBackendInitError {
@@ -164,7 +168,7 @@ pub struct GitBackend {
     empty_tree_id: TreeId,
     extra_metadata_store: TableStore,
     cached_extra_metadata: Mutex<Option<Arc<ReadonlyTable>>>,
-    change_id_setting: bool,
+    change_id_location: ChangeIdLocation,
 }
 
 impl GitBackend {
@@ -175,7 +179,7 @@ impl GitBackend {
     fn new(
         base_repo: gix::ThreadSafeRepository,
         extra_metadata_store: TableStore,
-        change_id_setting: bool,
+        change_id_location: ChangeIdLocation,
     ) -> Self {
         let repo = Mutex::new(base_repo.to_thread_local());
         let root_commit_id = CommitId::from_bytes(&[0; HASH_LENGTH]);
@@ -189,7 +193,7 @@ impl GitBackend {
             empty_tree_id,
             extra_metadata_store,
             cached_extra_metadata: Mutex::new(None),
-            change_id_setting,
+            change_id_location,
         }
     }
 
@@ -206,11 +210,11 @@ impl GitBackend {
         )
         .map_err(GitBackendInitError::InitRepository)?;
 
-        let change_id_setting = settings
+        let change_id_location = settings
             .git_settings()
             .map_err(GitBackendInitError::Config)?
-            .change_id;
-        Self::init_with_repo(store_path, git_repo_path, git_repo, change_id_setting)
+            .change_id_location;
+        Self::init_with_repo(store_path, git_repo_path, git_repo, change_id_location)
     }
 
     /// Initializes backend by creating a new Git repo at the specified
@@ -234,11 +238,11 @@ impl GitBackend {
         )
         .map_err(GitBackendInitError::InitRepository)?;
         let git_repo_path = workspace_root.join(".git");
-        let change_id_setting = settings
+        let change_id_location = settings
             .git_settings()
             .map_err(GitBackendInitError::Config)?
-            .change_id;
-        Self::init_with_repo(store_path, &git_repo_path, git_repo, change_id_setting)
+            .change_id_location;
+        Self::init_with_repo(store_path, &git_repo_path, git_repo, change_id_location)
     }
 
     /// Initializes backend with an existing Git repo at the specified path.
@@ -258,18 +262,18 @@ impl GitBackend {
             gix_open_opts_from_settings(settings),
         )
         .map_err(GitBackendInitError::OpenRepository)?;
-        let change_id_setting = settings
+        let change_id_location = settings
             .git_settings()
             .map_err(GitBackendInitError::Config)?
-            .change_id;
-        Self::init_with_repo(store_path, git_repo_path, git_repo, change_id_setting)
+            .change_id_location;
+        Self::init_with_repo(store_path, git_repo_path, git_repo, change_id_location)
     }
 
     fn init_with_repo(
         store_path: &Path,
         git_repo_path: &Path,
         git_repo: gix::ThreadSafeRepository,
-        change_id_setting: bool,
+        change_id_location: ChangeIdLocation,
     ) -> Result<Self, Box<GitBackendInitError>> {
         let extra_path = store_path.join("extra");
         fs::create_dir(&extra_path)
@@ -299,7 +303,7 @@ impl GitBackend {
         Ok(GitBackend::new(
             git_repo,
             extra_metadata_store,
-            change_id_setting,
+            change_id_location,
         ))
     }
 
@@ -323,11 +327,14 @@ impl GitBackend {
         )
         .map_err(GitBackendLoadError::OpenRepository)?;
         let extra_metadata_store = TableStore::load(store_path.join("extra"), HASH_LENGTH);
-        let change_id_setting = settings.git_settings().unwrap_or_default().change_id;
+        let change_id_location = settings
+            .git_settings()
+            .unwrap_or_default()
+            .change_id_location;
         Ok(GitBackend::new(
             repo,
             extra_metadata_store,
-            change_id_setting,
+            change_id_location,
         ))
     }
 
@@ -425,6 +432,7 @@ impl GitBackend {
             &mut mut_table,
             &table_lock,
             &head_ids,
+            self.change_id_location,
         )?;
         self.save_extra_metadata_table(mut_table, &table_lock)
     }
@@ -540,25 +548,111 @@ fn root_tree_from_header(git_commit: &CommitRef) -> Result<Option<MergedTreeId>,
     Ok(None)
 }
 
+/// Appends the change-id trailer to the trailer block of the `description`.
+///
+/// If there's no trailer block, a new one is started after a blank line. The
+/// text following the trailer block, such as the trailing newline, is kept
+/// so that [`split_change_id_trailer()`] can restore the `description` as is.
+fn append_change_id_trailer(description: &str, change_id: &ChangeId) -> String {
+    let line = format!("{CHANGE_ID_COMMIT_TRAILER}: {}", change_id.reverse_hex());
+    if description.is_empty() {
+        return format!("{line}\n");
+    }
+    let line_ending = if description.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let parsed = trailer::parse_description(description);
+    let (head, separator) = if parsed.trailers.is_empty() {
+        let head = description.trim_end_matches(['\r', '\n']);
+        (head, line_ending.repeat(2))
+    } else {
+        let head = &description[..description.len() - parsed.tail.len()];
+        (head, line_ending.to_owned())
+    };
+    let rest = &description[head.len()..];
+    format!("{head}{separator}{line}{rest}")
+}
+
+/// Splits the change-id trailer added by [`append_change_id_trailer()`] from
+/// the commit `message`.
+///
+/// Returns `None` if the last trailer of the `message` isn't a valid change-id
+/// trailer.
+fn split_change_id_trailer(message: &str) -> Option<(String, ChangeId)> {
+    let parse_change_id = |value: &str| {
+        to_forward_hex(value)
+            .and_then(|hex| ChangeId::try_from_hex(&hex).ok())
+            .filter(|id| id.as_bytes().len() == CHANGE_ID_LENGTH)
+    };
+    // An empty description is stored as the trailer line alone.
+    let sole_value = message
+        .strip_suffix('\n')
+        .and_then(|line| line.strip_prefix(CHANGE_ID_COMMIT_TRAILER))
+        .and_then(|rest| rest.strip_prefix(": "));
+    if let Some(change_id) = sole_value.and_then(parse_change_id) {
+        return Some((String::new(), change_id));
+    }
+
+    let parsed = trailer::parse_description(message);
+    let last = parsed.trailers.last()?;
+    if last.trailer.key != CHANGE_ID_COMMIT_TRAILER {
+        return None;
+    }
+    let change_id = parse_change_id(&last.trailer.value)?;
+    let line_start = message.len() - parsed.tail.len() - last.raw.len();
+    let head = if parsed.trailers.len() > 1 {
+        // Remove the line terminator preceding the trailer.
+        strip_line_terminator(&message[..line_start])
+    } else {
+        // Remove the blank line inserted before the new trailer block.
+        strip_line_terminator(strip_line_terminator(parsed.body))
+    };
+    Some((format!("{head}{}", parsed.tail), change_id))
+}
+
+fn strip_line_terminator(text: &str) -> &str {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    text.strip_suffix('\r').unwrap_or(text)
+}
+
 fn commit_from_git_without_root_parent(
     id: &CommitId,
     git_object: &gix::Object,
     uses_tree_conflict_format: bool,
     is_shallow: bool,
+    change_id_location: ChangeIdLocation,
 ) -> BackendResult<Commit> {
     let commit = git_object
         .try_to_commit_ref()
         .map_err(|err| to_read_object_err(err, id))?;
 
+    // Use lossy conversion as commit message with "mojibake" is still better than
+    // nothing.
+    // TODO: what should we do with commit.encoding?
+    let message = String::from_utf8_lossy(commit.message);
     // If the git header has a change-id field, we attempt to convert that to a
     // valid JJ Change Id
-    let change_id = commit
+    let header_change_id = commit
         .extra_headers()
         .find("change-id")
         .and_then(to_forward_hex)
         .and_then(|change_id_hex| ChangeId::try_from_hex(change_id_hex.as_str()).ok())
-        .filter(|val| val.as_bytes().len() == CHANGE_ID_LENGTH)
-        // Otherwise, we reverse the bits of the commit id to create the change id.
+        .filter(|val| val.as_bytes().len() == CHANGE_ID_LENGTH);
+    // Otherwise, the change id may be stored in the trailer of the message if
+    // configured to do so.
+    let trailer_change_id = match (&header_change_id, change_id_location) {
+        (None, ChangeIdLocation::Trailer) => split_change_id_trailer(&message),
+        _ => None,
+    };
+    let (description, change_id) = match trailer_change_id {
+        Some((description, change_id)) => (description, Some(change_id)),
+        None => (message.into_owned(), header_change_id),
+    };
+    let change_id = change_id
+        // If neither is present, we reverse the bits of the commit id to create
+        // the change id.
         // We don't want to use the first bytes unmodified because then it would be
         // ambiguous if a given hash prefix refers to the commit id or the change id.
         // It would have been enough to pick the last 16 bytes instead of the
@@ -597,10 +691,6 @@ fn commit_from_git_without_root_parent(
             MergedTreeId::Legacy(tree_id)
         }
     });
-    // Use lossy conversion as commit message with "mojibake" is still better than
-    // nothing.
-    // TODO: what should we do with commit.encoding?
-    let description = String::from_utf8_lossy(commit.message).into_owned();
     let author = signature_from_git(commit.author());
     let committer = signature_from_git(commit.committer());
 
@@ -627,6 +717,7 @@ fn commit_from_git_without_root_parent(
     Ok(Commit {
         parents,
         predecessors: vec![],
+        duplicated_from: None,
         // If this commit has associated extra metadata, we may reset this later.
         root_tree,
         change_id,
@@ -704,6 +795,10 @@ fn serialize_extras(commit: &Commit) -> Vec<u8> {
     for predecessor in &commit.predecessors {
         proto.predecessors.push(predecessor.to_bytes());
     }
+    if let Some(source) = &commit.duplicated_from {
+        proto.duplicated_from = Some(source.commit_id.to_bytes());
+        proto.duplicated_from_change_id = Some(source.change_id.to_bytes());
+    }
     proto.encode_to_vec()
 }
 
@@ -732,6 +827,13 @@ fn deserialize_extras(commit: &mut Commit, bytes: &[u8]) {
     for predecessor in &proto.predecessors {
         commit.predecessors.push(CommitId::from_bytes(predecessor));
     }
+    commit.duplicated_from = proto
+        .duplicated_from
+        .zip(proto.duplicated_from_change_id)
+        .map(|(commit_id, change_id)| DuplicateSource {
+            commit_id: CommitId::new(commit_id),
+            change_id: ChangeId::new(change_id),
+        });
 }
 
 /// Returns `RefEdit` that will create a ref in `refs/jj/keep` if not exist.
@@ -899,6 +1001,7 @@ fn import_extra_metadata_entries_from_heads(
     mut_table: &mut MutableTable,
     _table_lock: &FileLock,
     head_ids: &HashSet<&CommitId>,
+    change_id_location: ChangeIdLocation,
 ) -> BackendResult<()> {
     let shallow_commits = git_repo
         .shallow_commits()
@@ -919,7 +1022,13 @@ fn import_extra_metadata_entries_from_heads(
         // TODO(#1624): Should we read the root tree here and check if it has a
         // `.jjconflict-...` entries? That could happen if the user used `git` to e.g.
         // change the description of a commit with tree-level conflicts.
-        let commit = commit_from_git_without_root_parent(&id, &git_object, true, is_shallow)?;
+        let commit = commit_from_git_without_root_parent(
+            &id,
+            &git_object,
+            true,
+            is_shallow,
+            change_id_location,
+        )?;
         mut_table.add_entry(id.to_bytes(), serialize_extras(&commit));
         work_ids.extend(
             commit
@@ -1193,7 +1302,13 @@ impl Backend for GitBackend {
                 .ok()
                 .flatten()
                 .is_some_and(|shallow| shallow.contains(&git_object.id));
-            commit_from_git_without_root_parent(id, &git_object, false, is_shallow)?
+            commit_from_git_without_root_parent(
+                id,
+                &git_object,
+                false,
+                is_shallow,
+                self.change_id_location,
+            )?
         };
         if commit.parents.is_empty() {
             commit.parents.push(self.root_commit_id.clone());
@@ -1233,7 +1348,12 @@ impl Backend for GitBackend {
         };
         let author = signature_to_git(&contents.author);
         let mut committer = signature_to_git(&contents.committer);
-        let message = &contents.description;
+        let message = match self.change_id_location {
+            ChangeIdLocation::Trailer => {
+                append_change_id_trailer(&contents.description, &contents.change_id)
+            }
+            ChangeIdLocation::Header | ChangeIdLocation::None => contents.description.clone(),
+        };
         if contents.parents.is_empty() {
             return Err(BackendError::Other(
                 "Cannot write a commit with no parents".into(),
@@ -1267,7 +1387,7 @@ impl Backend for GitBackend {
                 ));
             }
         }
-        if self.change_id_setting {
+        if self.change_id_location == ChangeIdLocation::Header {
             extra_headers.push((
                 BString::new(CHANGE_ID_COMMIT_HEADER.to_vec()),
                 BString::new(contents.change_id.reverse_hex().into()),
@@ -1285,7 +1405,7 @@ impl Backend for GitBackend {
         let (table, table_lock) = self.read_extra_metadata_table_locked()?;
         let id = loop {
             let mut commit = gix::objs::Commit {
-                message: message.to_owned().into(),
+                message: message.clone().into(),
                 tree: git_tree_id,
                 author: author.into(),
                 committer: committer.into(),
@@ -1876,7 +1996,7 @@ mod tests {
 
     #[test]
     fn round_trip_change_id_via_git_header() {
-        let settings = user_settings_with_change_id();
+        let settings = user_settings_with_change_id_location("header");
         let temp_dir = new_temp_dir();
         let store_path = temp_dir.path();
 
@@ -1891,6 +2011,7 @@ mod tests {
         let commit = Commit {
             parents: vec![backend.root_commit_id().clone()],
             predecessors: vec![],
+            duplicated_from: None,
             root_tree: MergedTreeId::Legacy(backend.empty_tree_id().clone()),
             change_id: original_change_id.clone(),
             description: "initial".to_string(),
@@ -1923,6 +2044,173 @@ mod tests {
         );
     }
 
+    #[test]
+    fn round_trip_change_id_via_git_trailer() {
+        let settings = user_settings_with_change_id_location("trailer");
+        let temp_dir = new_temp_dir();
+        let store_path = temp_dir.path();
+
+        let temp_dir2 = new_temp_dir();
+        let empty_store = temp_dir2.path();
+
+        let git_repo_path = temp_dir.path().join("git");
+        let git_repo = git_init(git_repo_path);
+
+        let backend = GitBackend::init_external(&settings, store_path, git_repo.path()).unwrap();
+        let no_extra_backend =
+            GitBackend::init_external(&settings, empty_store, git_repo.path()).unwrap();
+        let original_change_id = ChangeId::from_hex("1111eeee1111eeee1111eeee1111eeee");
+        for description in [
+            "",
+            "\n",
+            "initial",
+            "initial\n",
+            "initial\n\n",
+            "Key: value\n",
+            "initial\n\nSigned-off-by: Test\n",
+            "initial\n\nSigned-off-by: Test",
+            "initial\r\n\r\nSigned-off-by: Test\r\n",
+        ] {
+            let commit = Commit {
+                parents: vec![backend.root_commit_id().clone()],
+                predecessors: vec![],
+                duplicated_from: None,
+                root_tree: MergedTreeId::Legacy(backend.empty_tree_id().clone()),
+                change_id: original_change_id.clone(),
+                description: description.to_owned(),
+                author: create_signature(),
+                committer: create_signature(),
+                secure_sig: None,
+            };
+            let (commit_id, _) = backend.write_commit(commit, None).block_on().unwrap();
+
+            // The trailer is stored in the Git commit message, not in the header
+            let git_commit = git_repo
+                .find_object(gix::ObjectId::from_bytes_or_panic(commit_id.as_bytes()))
+                .unwrap()
+                .try_into_commit()
+                .unwrap();
+            let git_commit = git_commit.decode().unwrap();
+            assert!(git_commit.extra_headers().find("change-id").is_none());
+            let message = str::from_utf8(git_commit.message).unwrap();
+            let trailers = trailer::parse_trailers(message);
+            let trailer = format!(
+                "{CHANGE_ID_COMMIT_TRAILER}: {}",
+                original_change_id.reverse_hex()
+            );
+            assert!(
+                trailers.is_empty() && message == format!("{trailer}\n")
+                    || trailers.last().unwrap().value == original_change_id.reverse_hex(),
+                "{message:?}"
+            );
+
+            // The change id is derived from the trailer, which is removed from
+            // the description.
+            let commit = no_extra_backend.read_commit(&commit_id).block_on().unwrap();
+            assert_eq!(commit.change_id, original_change_id);
+            assert_eq!(commit.description, description);
+        }
+    }
+
+    #[test]
+    fn append_change_id_trailer_to_message() {
+        let change_id = ChangeId::from_hex("1111eeee1111eeee1111eeee1111eeee");
+        let trailer = format!("{CHANGE_ID_COMMIT_TRAILER}: {}", change_id.reverse_hex());
+        let append = |description: &str| append_change_id_trailer(description, &change_id);
+
+        assert_eq!(append(""), format!("{trailer}\n"));
+        assert_eq!(append("foo"), format!("foo\n\n{trailer}"));
+        assert_eq!(append("foo\n"), format!("foo\n\n{trailer}\n"));
+        assert_eq!(append("foo\n\n"), format!("foo\n\n{trailer}\n\n"));
+        // Appended to an existing trailer block
+        assert_eq!(
+            append("foo\n\nBar: baz\n"),
+            format!("foo\n\nBar: baz\n{trailer}\n")
+        );
+        assert_eq!(
+            append("foo\r\n\r\nBar: baz\r\n"),
+            format!("foo\r\n\r\nBar: baz\r\n{trailer}\r\n")
+        );
+        // The subject isn't a trailer block
+        assert_eq!(append("Bar: baz\n"), format!("Bar: baz\n\n{trailer}\n"));
+    }
+
+    #[test]
+    fn split_change_id_trailer_from_message() {
+        let change_id = ChangeId::from_hex("1111eeee1111eeee1111eeee1111eeee");
+        let trailer = format!("{CHANGE_ID_COMMIT_TRAILER}: {}", change_id.reverse_hex());
+        let split = |message: &str| {
+            split_change_id_trailer(message).map(|(description, id)| {
+                assert_eq!(id, change_id);
+                description
+            })
+        };
+
+        assert_eq!(split(&format!("{trailer}\n")), Some("".to_owned()));
+        assert_eq!(
+            split(&format!("foo\n\n{trailer}\n")),
+            Some("foo\n".to_owned())
+        );
+        assert_eq!(split(&format!("foo\n\n{trailer}")), Some("foo".to_owned()));
+        // Appended to an existing trailer block
+        assert_eq!(
+            split(&format!("foo\n\nBar: baz\n{trailer}\n")),
+            Some("foo\n\nBar: baz\n".to_owned())
+        );
+        // Not the last trailer
+        assert_eq!(split(&format!("foo\n\n{trailer}\nBar: baz\n")), None);
+        // Not in a trailer block
+        assert_eq!(split(&format!("{trailer}\nfoo\n")), None);
+        assert_eq!(split(&format!("foo\n{trailer}\n")), None);
+        // Invalid change id
+        assert_eq!(
+            split(&format!("foo\n\n{CHANGE_ID_COMMIT_TRAILER}: 1234\n")),
+            None
+        );
+        assert_eq!(split("foo\n"), None);
+    }
+
+    #[test]
+    fn change_id_trailer_ignored_unless_configured() {
+        let temp_dir = new_temp_dir();
+        let store_path = temp_dir.path();
+        let temp_dir2 = new_temp_dir();
+        let header_store_path = temp_dir2.path();
+        let git_repo_path = temp_dir.path().join("git");
+        let git_repo = git_init(git_repo_path);
+
+        let settings = user_settings_with_change_id_location("trailer");
+        let backend = GitBackend::init_external(&settings, store_path, git_repo.path()).unwrap();
+        let settings = user_settings_with_change_id_location("header");
+        let header_backend =
+            GitBackend::init_external(&settings, header_store_path, git_repo.path()).unwrap();
+        let change_id = ChangeId::from_hex("1111eeee1111eeee1111eeee1111eeee");
+        let commit = Commit {
+            parents: vec![backend.root_commit_id().clone()],
+            predecessors: vec![],
+            duplicated_from: None,
+            root_tree: MergedTreeId::Legacy(backend.empty_tree_id().clone()),
+            change_id: change_id.clone(),
+            description: "foo\n".to_owned(),
+            author: create_signature(),
+            committer: create_signature(),
+            secure_sig: None,
+        };
+        let (commit_id, _) = backend.write_commit(commit, None).block_on().unwrap();
+
+        // The trailer is kept in the description of the commit read by the
+        // backend not configured to store change ids in trailers.
+        let commit = header_backend.read_commit(&commit_id).block_on().unwrap();
+        assert_ne!(commit.change_id, change_id);
+        assert_eq!(
+            commit.description,
+            format!(
+                "foo\n\n{CHANGE_ID_COMMIT_TRAILER}: {}\n",
+                change_id.reverse_hex()
+            )
+        );
+    }
+
     #[test]
     fn read_empty_string_placeholder() {
         let git_signature1 = gix::actor::SignatureRef {
@@ -1982,6 +2270,7 @@ mod tests {
         let mut commit = Commit {
             parents: vec![],
             predecessors: vec![],
+            duplicated_from: None,
             root_tree: MergedTreeId::Legacy(backend.empty_tree_id().clone()),
             change_id: ChangeId::from_hex("abc123"),
             description: "".to_string(),
@@ -2068,6 +2357,7 @@ mod tests {
         let mut commit = Commit {
             parents: vec![backend.root_commit_id().clone()],
             predecessors: vec![],
+            duplicated_from: None,
             root_tree: MergedTreeId::Merge(root_tree.clone()),
             change_id: ChangeId::from_hex("abc123"),
             description: "".to_string(),
@@ -2166,6 +2456,7 @@ mod tests {
         let commit = Commit {
             parents: vec![backend.root_commit_id().clone()],
             predecessors: vec![],
+            duplicated_from: None,
             root_tree: MergedTreeId::Legacy(backend.empty_tree_id().clone()),
             change_id: ChangeId::new(vec![]),
             description: "initial".to_string(),
@@ -2244,6 +2535,7 @@ mod tests {
         let commit1 = Commit {
             parents: vec![backend.root_commit_id().clone()],
             predecessors: vec![],
+            duplicated_from: None,
             root_tree: MergedTreeId::Legacy(backend.empty_tree_id().clone()),
             change_id: ChangeId::from_hex("7f0a7ce70354b22efcccf7bf144017c4"),
             description: "initial".to_string(),
@@ -2286,6 +2578,7 @@ mod tests {
         let commit = Commit {
             parents: vec![backend.root_commit_id().clone()],
             predecessors: vec![],
+            duplicated_from: None,
             root_tree: MergedTreeId::Legacy(backend.empty_tree_id().clone()),
             change_id: ChangeId::new(vec![]),
             description: "initial".to_string(),
@@ -2362,11 +2655,11 @@ mod tests {
         UserSettings::from_config(config).unwrap()
     }
 
-    fn user_settings_with_change_id() -> UserSettings {
+    fn user_settings_with_change_id_location(location: &str) -> UserSettings {
         let mut config = StackedConfig::with_defaults();
         config.add_layer(ConfigLayer::with_data(
             ConfigSource::Default,
-            DocumentMut::from_str("git.write-change-id-header = true").unwrap(),
+            DocumentMut::from_str(&format!("git.change-id-location = '{location}'")).unwrap(),
         ));
         UserSettings::from_config(config).unwrap()
     }
@@ -72,6 +72,10 @@ pub enum GitSubprocessError {
     UnsupportedGitOption(String),
     #[error("Git process failed: {0}")]
     External(String),
+    /// The transfer was cut off before completing, e.g. because the
+    /// connection dropped or the process was killed.
+    #[error("Git process failed: {0}")]
+    TransferInterrupted(String),
 }
 
 /// Context for creating Git subprocesses
@@ -374,9 +378,36 @@ fn parse_git_fetch_output(output: Output) -> Result<Option<String>, GitSubproces
         return Ok(None);
     }
 
+    // No exit code means that the process was terminated by a signal
+    if output.status.code().is_none() || parse_transfer_interrupted(&output.stderr) {
+        return Err(GitSubprocessError::TransferInterrupted(format!(
+            "External git program failed:\n{}",
+            output.stderr.to_str_lossy()
+        )));
+    }
+
     Err(external_git_error(&output.stderr))
 }
 
+/// Messages printed by git when the connection to the remote is lost in the
+/// middle of a transfer
+const TRANSFER_INTERRUPTED_MESSAGES: &[&str] = &[
+    "early EOF",
+    "the remote end hung up unexpectedly",
+    "unexpected disconnect while reading sideband packet",
+    "RPC failed",
+    "Connection reset by peer",
+    "Connection timed out",
+    "Operation timed out",
+];
+
+/// Whether git lost its connection to the remote during the transfer
+fn parse_transfer_interrupted(stderr: &[u8]) -> bool {
+    TRANSFER_INTERRUPTED_MESSAGES
+        .iter()
+        .any(|message| stderr.contains_str(message))
+}
+
 fn parse_git_branch_prune_output(output: Output) -> Result<(), GitSubprocessError> {
     if output.status.success() {
         return Ok(());
@@ -947,6 +978,20 @@ Done";
         assert_eq!(read_progress_line(b"fatal: this is a git error\n"), None);
     }
 
+    #[test]
+    fn test_parse_transfer_interrupted() {
+        assert!(!parse_transfer_interrupted(SAMPLE_NO_SUCH_REPOSITORY_ERROR));
+        assert!(!parse_transfer_interrupted(SAMPLE_NO_SUCH_REMOTE_ERROR));
+        assert!(!parse_transfer_interrupted(SAMPLE_NO_REMOTE_REF_ERROR));
+        assert!(!parse_transfer_interrupted(SAMPLE_OK_STDERR));
+        assert!(parse_transfer_interrupted(
+            b"fatal: early EOF\nfatal: fetch-pack: invalid index-pack output"
+        ));
+        assert!(parse_transfer_interrupted(
+            b"error: RPC failed; curl 92 HTTP/2 stream 5 was not closed cleanly"
+        ));
+    }
+
     #[test]
     fn test_parse_unknown_option() {
         assert_eq!(
@@ -14,6 +14,7 @@
 
 #![allow(missing_docs)]
 
+use std::collections::BinaryHeap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
@@ -76,6 +77,10 @@ fn reachable_targets<N>(edges: &[GraphEdge<N>]) -> impl DoubleEndedIterator<Item
 }
 
 /// Creates new graph in which nodes and edges are reversed.
+///
+/// Since the first reversed node is the last input node, the whole `input` has
+/// to be consumed before anything can be emitted. See [`reverse_graph_iter()`]
+/// for the streaming variant.
 pub fn reverse_graph<N, ID: Clone + Eq + Hash, E>(
     input: impl Iterator<Item = Result<GraphNode<N, ID>, E>>,
     as_id: impl Fn(&N) -> &ID,
@@ -101,6 +106,122 @@ pub fn reverse_graph<N, ID: Clone + Eq + Hash, E>(
     Ok(items)
 }
 
+/// Creates new graph in which nodes and edges are reversed, yielding the
+/// reversed nodes lazily.
+///
+/// Since the `input` is topologically ordered, the reversed edges of a node are
+/// complete once the node is read. The node is emitted as soon as all of its
+/// parents have been emitted, so the reversed graph of each root can be
+/// streamed without waiting for the rest of the `input`. If the `input` has a
+/// single root, the ordering is the same as [`reverse_graph()`].
+pub fn reverse_graph_iter<N, ID, E, I, F>(input: I, as_id: F) -> ReverseGraphIterator<N, ID, I, F>
+where
+    ID: Clone + Eq + Hash,
+    I: Iterator<Item = Result<GraphNode<N, ID>, E>>,
+    F: Fn(&N) -> &ID,
+{
+    ReverseGraphIterator {
+        input_iter: input,
+        as_id,
+        input_exhausted: false,
+        next_index: 0,
+        indices: HashMap::new(),
+        nodes: HashMap::new(),
+        reverse_edges: HashMap::new(),
+        ready_indices: BinaryHeap::new(),
+    }
+}
+
+/// Iterator returned by [`reverse_graph_iter()`].
+pub struct ReverseGraphIterator<N, ID, I, F> {
+    input_iter: I,
+    as_id: F,
+    input_exhausted: bool,
+    /// Input index of the next node to be read.
+    next_index: usize,
+    /// Input indices of the nodes read but not yet emitted.
+    indices: HashMap<ID, usize>,
+    /// Nodes read but not yet emitted, and the number of their parents not yet
+    /// emitted, keyed by input index.
+    nodes: HashMap<usize, (N, usize)>,
+    /// Reversed edges collected so far, keyed by the parent node.
+    reverse_edges: HashMap<ID, Vec<GraphEdge<ID>>>,
+    /// Input indices of the nodes which can be emitted. The last input node is
+    /// emitted first to preserve the reversed input order where possible.
+    ready_indices: BinaryHeap<usize>,
+}
+
+impl<N, ID, E, I, F> ReverseGraphIterator<N, ID, I, F>
+where
+    ID: Clone + Eq + Hash,
+    I: Iterator<Item = Result<GraphNode<N, ID>, E>>,
+    F: Fn(&N) -> &ID,
+{
+    fn read_node(&mut self, node: N, edges: Vec<GraphEdge<ID>>) {
+        let index = self.next_index;
+        self.next_index += 1;
+        let id = (self.as_id)(&node).clone();
+        let num_parents = reachable_targets(&edges).count();
+        for GraphEdge { target, edge_type } in edges {
+            self.reverse_edges
+                .entry(target)
+                .or_default()
+                .push(GraphEdge {
+                    target: id.clone(),
+                    edge_type,
+                });
+        }
+        if num_parents == 0 {
+            self.ready_indices.push(index);
+        }
+        self.indices.insert(id, index);
+        self.nodes.insert(index, (node, num_parents));
+    }
+
+    fn emit_node(&mut self, index: usize) -> GraphNode<N, ID> {
+        let (node, _) = self.nodes.remove(&index).unwrap();
+        let id = (self.as_id)(&node);
+        self.indices.remove(id);
+        let edges = self.reverse_edges.remove(id).unwrap_or_default();
+        for child_id in reachable_targets(&edges) {
+            let child_index = self.indices[child_id];
+            let (_, num_parents) = self.nodes.get_mut(&child_index).unwrap();
+            *num_parents -= 1;
+            // Once the input is exhausted, all remaining nodes are ready.
+            if *num_parents == 0 && !self.input_exhausted {
+                self.ready_indices.push(child_index);
+            }
+        }
+        (node, edges)
+    }
+}
+
+impl<N, ID, E, I, F> Iterator for ReverseGraphIterator<N, ID, I, F>
+where
+    ID: Clone + Eq + Hash,
+    I: Iterator<Item = Result<GraphNode<N, ID>, E>>,
+    F: Fn(&N) -> &ID,
+{
+    type Item = Result<GraphNode<N, ID>, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.ready_indices.is_empty() && !self.input_exhausted {
+            match self.input_iter.next() {
+                Some(Ok((node, edges))) => self.read_node(node, edges),
+                Some(Err(err)) => return Some(Err(err)),
+                None => {
+                    // Nodes whose parents never appeared in the input can be
+                    // emitted in reverse input order.
+                    self.input_exhausted = true;
+                    self.ready_indices.extend(self.nodes.keys().copied());
+                }
+            }
+        }
+        let index = self.ready_indices.pop()?;
+        Some(Ok(self.emit_node(index)))
+    }
+}
+
 /// Graph iterator adapter to group topological branches.
 ///
 /// Basic idea is DFS from the heads. At fork point, the other descendant
@@ -334,6 +455,7 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
     use std::convert::Infallible;
 
     use itertools::Itertools as _;
@@ -393,6 +515,69 @@ mod tests {
             .collect()
     }
 
+    fn reverse_graph_ids(input: impl IntoIterator<Item = GraphNode<char>>) -> Vec<GraphNode<char>> {
+        let input = input.into_iter().map(Ok::<_, Infallible>);
+        reverse_graph_iter(input, |c| c).try_collect().unwrap()
+    }
+
+    #[test]
+    fn test_reverse_graph_iter_same_as_buffered() {
+        // F
+        // |\
+        // D E
+        // |/
+        // C
+        // :
+        // A ~
+        let input = vec![
+            ('F', vec![direct('D'), direct('E')]),
+            ('D', vec![direct('C')]),
+            ('E', vec![direct('C')]),
+            ('C', vec![indirect('A')]),
+            ('A', vec![missing('X')]),
+        ];
+        let buffered =
+            reverse_graph(input.clone().into_iter().map(Ok::<_, Infallible>), |c| c).unwrap();
+        assert_eq!(reverse_graph_ids(input), buffered);
+    }
+
+    #[test]
+    fn test_reverse_graph_iter_streams_roots() {
+        // D B
+        // | |
+        // C A
+        let input = [
+            Ok(('D', vec![direct('C')])),
+            Ok(('C', vec![])),
+            Ok(('B', vec![direct('A')])),
+            Ok(('A', vec![])),
+        ];
+        let num_read = Cell::new(0);
+        let mut iter = reverse_graph_iter(
+            input
+                .into_iter()
+                .inspect(|_| num_read.set(num_read.get() + 1)),
+            |c: &char| c,
+        )
+        .map(|item: Result<_, Infallible>| item.unwrap());
+        // The first root and its descendant are emitted without reading the
+        // other branch.
+        assert_eq!(iter.next(), Some(('C', vec![direct('D')])));
+        assert_eq!(num_read.get(), 2);
+        assert_eq!(iter.next(), Some(('D', vec![])));
+        assert_eq!(iter.next(), Some(('A', vec![direct('B')])));
+        assert_eq!(num_read.get(), 4);
+        assert_eq!(iter.next(), Some(('B', vec![])));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_reverse_graph_iter_parents_not_in_input() {
+        // Edges to nodes not in the input aren't waited for.
+        let input = vec![('B', vec![direct('A')]), ('C', vec![direct('X')])];
+        assert_eq!(reverse_graph_ids(input), vec![('C', vec![]), ('B', vec![])]);
+    }
+
     #[test]
     fn test_format_graph() {
         let graph = [
@@ -32,6 +32,7 @@ use crate::object_id::PrefixResolution;
 use crate::repo::Repo;
 use crate::revset::DefaultSymbolResolver;
 use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetExpression;
 use crate::revset::RevsetExtensions;
 use crate::revset::RevsetResolutionError;
 use crate::revset::SymbolResolverExtension;
@@ -131,6 +132,15 @@ impl IdPrefixContext {
         self
     }
 
+    /// Disambiguates IDs within the union of the given `expressions`.
+    pub fn disambiguate_within_any(
+        self,
+        expressions: impl IntoIterator<Item = Rc<UserRevsetExpression>>,
+    ) -> Self {
+        let expressions = expressions.into_iter().collect_vec();
+        self.disambiguate_within(RevsetExpression::union_all(&expressions))
+    }
+
     /// Loads disambiguation index once, returns a borrowed index to
     /// disambiguate commit/change IDs.
     pub fn populate(&self, repo: &dyn Repo) -> Result<IdPrefixIndex<'_>, IdPrefixIndexLoadError> {
@@ -141,6 +151,34 @@ impl IdPrefixContext {
         };
         Ok(IdPrefixIndex { indexes })
     }
+
+    /// Returns the shortest length of a prefix of `commit_id` that resolves
+    /// unambiguously within the disambiguation set, or within the whole `repo`
+    /// if the commit isn't in the set.
+    ///
+    /// See [`IdPrefixIndex::shortest_commit_prefix_len()`] for details.
+    pub fn shortest_commit_prefix_len(
+        &self,
+        repo: &dyn Repo,
+        commit_id: &CommitId,
+    ) -> Result<usize, IdPrefixIndexLoadError> {
+        let index = self.populate(repo)?;
+        Ok(index.shortest_commit_prefix_len(repo, commit_id))
+    }
+
+    /// Returns the shortest length of a prefix of `change_id` that resolves
+    /// unambiguously within the disambiguation set, or within the whole `repo`
+    /// if the change isn't in the set.
+    ///
+    /// See [`IdPrefixIndex::shortest_change_prefix_len()`] for details.
+    pub fn shortest_change_prefix_len(
+        &self,
+        repo: &dyn Repo,
+        change_id: &ChangeId,
+    ) -> Result<usize, IdPrefixIndexLoadError> {
+        let index = self.populate(repo)?;
+        Ok(index.shortest_change_prefix_len(repo, change_id))
+    }
 }
 
 /// Loaded index to disambiguate commit/change IDs.
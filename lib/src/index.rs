@@ -28,6 +28,7 @@ use crate::object_id::PrefixResolution;
 use crate::operation::Operation;
 use crate::revset::ResolvedExpression;
 use crate::revset::Revset;
+use crate::revset::RevsetEvaluationBudget;
 use crate::revset::RevsetEvaluationError;
 use crate::store::Store;
 
@@ -134,6 +135,25 @@ pub trait Index: Send + Sync {
         expression: &ResolvedExpression,
         store: &Arc<Store>,
     ) -> Result<Box<dyn Revset + 'index>, RevsetEvaluationError>;
+
+    /// Resolves the revset `expression` like [`Index::evaluate_revset()`],
+    /// but the evaluation fails once it exceeds the `budget`.
+    ///
+    /// The default implementation can't track the budget, and returns an
+    /// error unless the `budget` is unlimited and reports no progress.
+    fn evaluate_revset_with_budget<'index>(
+        &'index self,
+        expression: &ResolvedExpression,
+        store: &Arc<Store>,
+        budget: &RevsetEvaluationBudget,
+    ) -> Result<Box<dyn Revset + 'index>, RevsetEvaluationError> {
+        if !budget.is_unlimited() || budget.progress.is_some() {
+            return Err(RevsetEvaluationError::Other(
+                "Evaluation budget is not supported by the index".into(),
+            ));
+        }
+        self.evaluate_revset(expression, store)
+    }
 }
 
 #[expect(missing_docs)]
@@ -31,6 +31,7 @@ pub mod content_hash;
 pub mod absorb;
 pub mod annotate;
 pub mod backend;
+pub mod bundle;
 pub mod commit;
 pub mod commit_builder;
 pub mod config;
@@ -42,6 +43,7 @@ pub mod default_index;
 pub mod default_submodule_store;
 pub mod diff;
 pub mod dsl_util;
+pub mod evolution;
 pub mod extensions_map;
 pub mod file_util;
 pub mod files;
@@ -78,6 +80,7 @@ pub mod index;
 pub mod local_working_copy;
 pub mod lock;
 pub mod matchers;
+pub mod memory_backend;
 pub mod merge;
 pub mod merged_tree;
 pub mod object_id;
@@ -112,6 +115,7 @@ pub mod submodule_store;
 #[cfg(feature = "testing")]
 pub mod test_signing_backend;
 pub mod time_util;
+pub mod trailer;
 pub mod transaction;
 pub mod tree;
 pub mod tree_builder;
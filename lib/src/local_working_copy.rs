@@ -76,6 +76,7 @@ use crate::file_util::check_symlink_support;
 use crate::file_util::try_symlink;
 #[cfg(feature = "watchman")]
 use crate::fsmonitor::watchman;
+use crate::fsmonitor::FsmonitorError;
 use crate::fsmonitor::FsmonitorSettings;
 #[cfg(feature = "watchman")]
 use crate::fsmonitor::WatchmanConfig;
@@ -720,6 +721,7 @@ fn file_state(metadata: &Metadata) -> Option<FileState> {
 struct FsmonitorMatcher {
     matcher: Option<Box<dyn Matcher>>,
     watchman_clock: Option<crate::protos::working_copy::WatchmanClock>,
+    error: Option<FsmonitorError>,
 }
 
 #[derive(Debug, Error)]
@@ -905,22 +907,33 @@ impl TreeState {
         self.watchman_clock.take();
     }
 
+    #[cfg(feature = "watchman")]
+    pub fn query_watchman(
+        &self,
+        config: &WatchmanConfig,
+    ) -> Result<(watchman::Clock, Option<Vec<PathBuf>>), TreeStateError> {
+        self.query_watchman_changes(config)
+            .map_err(|err| TreeStateError::Fsmonitor(Box::new(err)))
+    }
+
     #[cfg(feature = "watchman")]
     #[tokio::main(flavor = "current_thread")]
     #[instrument(skip(self))]
-    pub async fn query_watchman(
+    async fn query_watchman_changes(
         &self,
         config: &WatchmanConfig,
-    ) -> Result<(watchman::Clock, Option<Vec<PathBuf>>), TreeStateError> {
-        let fsmonitor = watchman::Fsmonitor::init(&self.working_copy_path, config)
-            .await
-            .map_err(|err| TreeStateError::Fsmonitor(Box::new(err)))?;
+    ) -> Result<(watchman::Clock, Option<Vec<PathBuf>>), watchman::Error> {
+        let fsmonitor = watchman::Fsmonitor::init(&self.working_copy_path, config).await?;
         let previous_clock = self.watchman_clock.clone().map(watchman::Clock::from);
-        let changed_files = fsmonitor
-            .query_changed_files(previous_clock)
-            .await
-            .map_err(|err| TreeStateError::Fsmonitor(Box::new(err)))?;
-        Ok(changed_files)
+        fsmonitor.query_changed_files(previous_clock).await
+    }
+
+    /// Queries the Watchman filesystem monitor, and returns the error if it
+    /// can't be queried.
+    #[cfg(feature = "watchman")]
+    pub fn check_watchman(&self, config: &WatchmanConfig) -> Option<FsmonitorError> {
+        let err = self.query_watchman_changes(config).err()?;
+        Some(FsmonitorError::from(&err))
     }
 
     #[cfg(feature = "watchman")]
@@ -965,6 +978,7 @@ impl TreeState {
         let FsmonitorMatcher {
             matcher: fsmonitor_matcher,
             watchman_clock,
+            error: fsmonitor_error,
         } = self.make_fsmonitor_matcher(fsmonitor_settings)?;
         let fsmonitor_matcher = match fsmonitor_matcher.as_ref() {
             None => &EverythingMatcher,
@@ -1016,6 +1030,7 @@ impl TreeState {
 
         let stats = SnapshotStats {
             untracked_paths: untracked_paths_rx.into_iter().collect(),
+            fsmonitor_error,
         };
         let mut tree_builder = MergedTreeBuilder::new(self.tree_id.clone());
         trace_span!("process tree entries").in_scope(|| {
@@ -1071,15 +1086,17 @@ impl TreeState {
         &self,
         fsmonitor_settings: &FsmonitorSettings,
     ) -> Result<FsmonitorMatcher, SnapshotError> {
-        let (watchman_clock, changed_files) = match fsmonitor_settings {
-            FsmonitorSettings::None => (None, None),
-            FsmonitorSettings::Test { changed_files } => (None, Some(changed_files.clone())),
+        let (watchman_clock, changed_files, error) = match fsmonitor_settings {
+            FsmonitorSettings::None => (None, None, None),
+            FsmonitorSettings::Test { changed_files } => (None, Some(changed_files.clone()), None),
             #[cfg(feature = "watchman")]
-            FsmonitorSettings::Watchman(config) => match self.query_watchman(config) {
-                Ok((watchman_clock, changed_files)) => (Some(watchman_clock.into()), changed_files),
+            FsmonitorSettings::Watchman(config) => match self.query_watchman_changes(config) {
+                Ok((watchman_clock, changed_files)) => {
+                    (Some(watchman_clock.into()), changed_files, None)
+                }
                 Err(err) => {
                     tracing::warn!(?err, "Failed to query filesystem monitor");
-                    (None, None)
+                    (None, None, Some(FsmonitorError::from(&err)))
                 }
             },
             #[cfg(not(feature = "watchman"))]
@@ -1108,6 +1125,7 @@ impl TreeState {
         Ok(FsmonitorMatcher {
             matcher,
             watchman_clock,
+            error,
         })
     }
 }
@@ -2164,6 +2182,16 @@ impl LocalWorkingCopy {
             })
     }
 
+    /// Queries the Watchman filesystem monitor, and returns the error if it
+    /// can't be queried.
+    #[cfg(feature = "watchman")]
+    pub fn check_watchman(
+        &self,
+        config: &WatchmanConfig,
+    ) -> Result<Option<FsmonitorError>, WorkingCopyStateError> {
+        Ok(self.tree_state()?.check_watchman(config))
+    }
+
     #[cfg(feature = "watchman")]
     pub fn is_watchman_trigger_registered(
         &self,
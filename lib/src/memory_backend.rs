@@ -0,0 +1,666 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage backends which keep the whole repository in memory.
+//!
+//! Nothing written to these backends is persisted. They are useful for
+//! embedding jj where there's no file system to write to, and for tests which
+//! don't care about the on-disk format. See [`MemoryRepoStorage`] for how to
+//! create a repository backed by them.
+
+use std::any::Any;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::Cursor;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use blake2::Blake2b512;
+use blake2::Digest as _;
+use futures::stream;
+use futures::stream::BoxStream;
+use prost::Message as _;
+
+use crate::backend::make_root_commit;
+use crate::backend::Backend;
+use crate::backend::BackendError;
+use crate::backend::BackendResult;
+use crate::backend::ChangeId;
+use crate::backend::Commit;
+use crate::backend::CommitId;
+use crate::backend::Conflict;
+use crate::backend::ConflictId;
+use crate::backend::CopyRecord;
+use crate::backend::FileId;
+use crate::backend::SecureSig;
+use crate::backend::SigningFn;
+use crate::backend::SymlinkId;
+use crate::backend::Tree;
+use crate::backend::TreeId;
+use crate::content_hash::blake2b_hash;
+use crate::default_index::MemoryIndexStore;
+use crate::index::Index;
+use crate::object_id::HexPrefix;
+use crate::object_id::ObjectId;
+use crate::object_id::PrefixResolution;
+use crate::op_heads_store::OpHeadsStore;
+use crate::op_heads_store::OpHeadsStoreError;
+use crate::op_heads_store::OpHeadsStoreLock;
+use crate::op_store::OpStore;
+use crate::op_store::OpStoreError;
+use crate::op_store::OpStoreResult;
+use crate::op_store::Operation;
+use crate::op_store::OperationId;
+use crate::op_store::RootOperationData;
+use crate::op_store::View;
+use crate::op_store::ViewId;
+use crate::repo::RepoLoader;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::settings::UserSettings;
+use crate::signing::Signer;
+use crate::simple_backend::commit_to_proto;
+use crate::store::Store;
+use crate::submodule_store::SubmoduleStore;
+
+const COMMIT_ID_LENGTH: usize = 64;
+const CHANGE_ID_LENGTH: usize = 16;
+// BLAKE2b-512 hash length in bytes
+const OPERATION_ID_LENGTH: usize = 64;
+const VIEW_ID_LENGTH: usize = 64;
+
+fn not_found_err(id: &impl ObjectId) -> BackendError {
+    BackendError::ObjectNotFound {
+        object_type: id.object_type(),
+        hash: id.hex(),
+        source: "Object not found in memory".into(),
+    }
+}
+
+fn op_not_found_err(id: &impl ObjectId) -> OpStoreError {
+    OpStoreError::ObjectNotFound {
+        object_type: id.object_type(),
+        hash: id.hex(),
+        source: "Object not found in memory".into(),
+    }
+}
+
+// Objects are reference counted so that copying the whole data is cheap.
+#[derive(Clone, Debug, Default)]
+struct MemoryBackendData {
+    commits: HashMap<CommitId, Arc<Commit>>,
+    trees: HashMap<TreeId, Arc<Tree>>,
+    files: HashMap<FileId, Arc<[u8]>>,
+    symlinks: HashMap<SymlinkId, Arc<str>>,
+    conflicts: HashMap<ConflictId, Arc<Conflict>>,
+}
+
+/// Commit backend which stores commits, trees, and files in memory.
+///
+/// Object ids are content hashes as in the simple backend, so the same
+/// contents get the same ids regardless of where they're written.
+pub struct MemoryBackend {
+    root_commit_id: CommitId,
+    root_change_id: ChangeId,
+    empty_tree_id: TreeId,
+    data: Arc<Mutex<MemoryBackendData>>,
+}
+
+impl MemoryBackend {
+    /// Name of this backend.
+    pub fn name() -> &'static str {
+        "memory"
+    }
+
+    /// Creates an empty backend.
+    pub fn new() -> Self {
+        let empty_tree_id = TreeId::new(blake2b_hash(&Tree::default()).to_vec());
+        let mut data = MemoryBackendData::default();
+        data.trees
+            .insert(empty_tree_id.clone(), Arc::new(Tree::default()));
+        MemoryBackend {
+            root_commit_id: CommitId::from_bytes(&[0; COMMIT_ID_LENGTH]),
+            root_change_id: ChangeId::from_bytes(&[0; CHANGE_ID_LENGTH]),
+            empty_tree_id,
+            data: Arc::new(Mutex::new(data)),
+        }
+    }
+
+    /// Returns a copy of this backend. Objects written to the copy aren't
+    /// visible to this backend and vice versa.
+    pub fn snapshot(&self) -> Self {
+        MemoryBackend {
+            root_commit_id: self.root_commit_id.clone(),
+            root_change_id: self.root_change_id.clone(),
+            empty_tree_id: self.empty_tree_id.clone(),
+            data: Arc::new(Mutex::new(self.locked_data().clone())),
+        }
+    }
+
+    /// Returns a backend which shares the objects with this backend.
+    fn share(&self) -> Self {
+        MemoryBackend {
+            root_commit_id: self.root_commit_id.clone(),
+            root_change_id: self.root_change_id.clone(),
+            empty_tree_id: self.empty_tree_id.clone(),
+            data: self.data.clone(),
+        }
+    }
+
+    fn locked_data(&self) -> MutexGuard<'_, MemoryBackendData> {
+        self.data.lock().unwrap()
+    }
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for MemoryBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryBackend").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Backend for MemoryBackend {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        Self::name()
+    }
+
+    fn commit_id_length(&self) -> usize {
+        COMMIT_ID_LENGTH
+    }
+
+    fn change_id_length(&self) -> usize {
+        CHANGE_ID_LENGTH
+    }
+
+    fn root_commit_id(&self) -> &CommitId {
+        &self.root_commit_id
+    }
+
+    fn root_change_id(&self) -> &ChangeId {
+        &self.root_change_id
+    }
+
+    fn empty_tree_id(&self) -> &TreeId {
+        &self.empty_tree_id
+    }
+
+    fn concurrency(&self) -> usize {
+        1
+    }
+
+    async fn read_file(&self, _path: &RepoPath, id: &FileId) -> BackendResult<Box<dyn Read>> {
+        let contents = self
+            .locked_data()
+            .files
+            .get(id)
+            .cloned()
+            .ok_or_else(|| not_found_err(id))?;
+        Ok(Box::new(Cursor::new(contents)))
+    }
+
+    async fn write_file(
+        &self,
+        _path: &RepoPath,
+        contents: &mut (dyn Read + Send),
+    ) -> BackendResult<FileId> {
+        let mut bytes = Vec::new();
+        contents
+            .read_to_end(&mut bytes)
+            .map_err(|err| BackendError::WriteObject {
+                object_type: "file",
+                source: err.into(),
+            })?;
+        let id = FileId::new(Blake2b512::digest(&bytes).to_vec());
+        self.locked_data()
+            .files
+            .entry(id.clone())
+            .or_insert_with(|| bytes.into());
+        Ok(id)
+    }
+
+    async fn read_symlink(&self, _path: &RepoPath, id: &SymlinkId) -> BackendResult<String> {
+        let target = self
+            .locked_data()
+            .symlinks
+            .get(id)
+            .cloned()
+            .ok_or_else(|| not_found_err(id))?;
+        Ok(target.to_string())
+    }
+
+    async fn write_symlink(&self, _path: &RepoPath, target: &str) -> BackendResult<SymlinkId> {
+        let id = SymlinkId::new(Blake2b512::digest(target.as_bytes()).to_vec());
+        self.locked_data()
+            .symlinks
+            .entry(id.clone())
+            .or_insert_with(|| target.into());
+        Ok(id)
+    }
+
+    async fn read_tree(&self, _path: &RepoPath, id: &TreeId) -> BackendResult<Tree> {
+        let tree = self
+            .locked_data()
+            .trees
+            .get(id)
+            .cloned()
+            .ok_or_else(|| not_found_err(id))?;
+        Ok(Tree::clone(&tree))
+    }
+
+    async fn write_tree(&self, _path: &RepoPath, tree: &Tree) -> BackendResult<TreeId> {
+        let id = TreeId::new(blake2b_hash(tree).to_vec());
+        self.locked_data()
+            .trees
+            .entry(id.clone())
+            .or_insert_with(|| Arc::new(tree.clone()));
+        Ok(id)
+    }
+
+    fn read_conflict(&self, _path: &RepoPath, id: &ConflictId) -> BackendResult<Conflict> {
+        let conflict = self
+            .locked_data()
+            .conflicts
+            .get(id)
+            .cloned()
+            .ok_or_else(|| not_found_err(id))?;
+        Ok(Conflict::clone(&conflict))
+    }
+
+    fn write_conflict(&self, _path: &RepoPath, conflict: &Conflict) -> BackendResult<ConflictId> {
+        let id = ConflictId::new(blake2b_hash(conflict).to_vec());
+        self.locked_data()
+            .conflicts
+            .entry(id.clone())
+            .or_insert_with(|| Arc::new(conflict.clone()));
+        Ok(id)
+    }
+
+    async fn read_commit(&self, id: &CommitId) -> BackendResult<Commit> {
+        if *id == self.root_commit_id {
+            return Ok(make_root_commit(
+                self.root_change_id.clone(),
+                self.empty_tree_id.clone(),
+            ));
+        }
+        let commit = self
+            .locked_data()
+            .commits
+            .get(id)
+            .cloned()
+            .ok_or_else(|| not_found_err(id))?;
+        Ok(Commit::clone(&commit))
+    }
+
+    async fn write_commit(
+        &self,
+        mut commit: Commit,
+        sign_with: Option<&mut SigningFn>,
+    ) -> BackendResult<(CommitId, Commit)> {
+        assert!(commit.secure_sig.is_none(), "commit.secure_sig was set");
+
+        if commit.parents.is_empty() {
+            return Err(BackendError::Other(
+                "Cannot write a commit with no parents".into(),
+            ));
+        }
+        if let Some(sign) = sign_with {
+            let data = commit_to_proto(&commit).encode_to_vec();
+            let sig = sign(&data).map_err(|err| BackendError::Other(err.into()))?;
+            commit.secure_sig = Some(SecureSig { data, sig });
+        }
+
+        let id = CommitId::new(blake2b_hash(&commit).to_vec());
+        self.locked_data()
+            .commits
+            .entry(id.clone())
+            .or_insert_with(|| Arc::new(commit.clone()));
+        Ok((id, commit))
+    }
+
+    fn get_copy_records(
+        &self,
+        _paths: Option<&[RepoPathBuf]>,
+        _root: &CommitId,
+        _head: &CommitId,
+    ) -> BackendResult<BoxStream<BackendResult<CopyRecord>>> {
+        Ok(Box::pin(stream::empty()))
+    }
+
+    fn gc(&self, _index: &dyn Index, _keep_newer: SystemTime) -> BackendResult<()> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct MemoryOpStoreData {
+    views: HashMap<ViewId, Arc<View>>,
+    operations: HashMap<OperationId, Arc<Operation>>,
+}
+
+/// Operation store which keeps operations and views in memory.
+#[derive(Debug)]
+pub struct MemoryOpStore {
+    root_data: RootOperationData,
+    root_operation_id: OperationId,
+    root_view_id: ViewId,
+    data: Arc<Mutex<MemoryOpStoreData>>,
+}
+
+impl MemoryOpStore {
+    /// Name of this operation store.
+    pub fn name() -> &'static str {
+        "memory_op_store"
+    }
+
+    /// Creates an empty operation store.
+    pub fn new(root_data: RootOperationData) -> Self {
+        MemoryOpStore {
+            root_data,
+            root_operation_id: OperationId::from_bytes(&[0; OPERATION_ID_LENGTH]),
+            root_view_id: ViewId::from_bytes(&[0; VIEW_ID_LENGTH]),
+            data: Arc::new(Mutex::new(MemoryOpStoreData::default())),
+        }
+    }
+
+    /// Returns a copy of this operation store. Objects written to the copy
+    /// aren't visible to this store and vice versa.
+    pub fn snapshot(&self) -> Self {
+        MemoryOpStore {
+            data: Arc::new(Mutex::new(self.locked_data().clone())),
+            ..self.share()
+        }
+    }
+
+    /// Returns an operation store which shares the objects with this store.
+    fn share(&self) -> Self {
+        MemoryOpStore {
+            root_data: self.root_data.clone(),
+            root_operation_id: self.root_operation_id.clone(),
+            root_view_id: self.root_view_id.clone(),
+            data: self.data.clone(),
+        }
+    }
+
+    fn locked_data(&self) -> MutexGuard<'_, MemoryOpStoreData> {
+        self.data.lock().unwrap()
+    }
+}
+
+impl OpStore for MemoryOpStore {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        Self::name()
+    }
+
+    fn root_operation_id(&self) -> &OperationId {
+        &self.root_operation_id
+    }
+
+    fn read_view(&self, id: &ViewId) -> OpStoreResult<View> {
+        if *id == self.root_view_id {
+            return Ok(View::make_root(self.root_data.root_commit_id.clone()));
+        }
+        let view = self
+            .locked_data()
+            .views
+            .get(id)
+            .cloned()
+            .ok_or_else(|| op_not_found_err(id))?;
+        Ok(View::clone(&view))
+    }
+
+    fn write_view(&self, view: &View) -> OpStoreResult<ViewId> {
+        let id = ViewId::new(blake2b_hash(view).to_vec());
+        self.locked_data()
+            .views
+            .entry(id.clone())
+            .or_insert_with(|| Arc::new(view.clone()));
+        Ok(id)
+    }
+
+    fn read_operation(&self, id: &OperationId) -> OpStoreResult<Operation> {
+        if *id == self.root_operation_id {
+            return Ok(Operation::make_root(self.root_view_id.clone()));
+        }
+        let operation = self
+            .locked_data()
+            .operations
+            .get(id)
+            .cloned()
+            .ok_or_else(|| op_not_found_err(id))?;
+        Ok(Operation::clone(&operation))
+    }
+
+    fn write_operation(&self, operation: &Operation) -> OpStoreResult<OperationId> {
+        assert!(!operation.parents.is_empty());
+        let id = OperationId::new(blake2b_hash(operation).to_vec());
+        self.locked_data()
+            .operations
+            .entry(id.clone())
+            .or_insert_with(|| Arc::new(operation.clone()));
+        Ok(id)
+    }
+
+    fn resolve_operation_id_prefix(
+        &self,
+        prefix: &HexPrefix,
+    ) -> OpStoreResult<PrefixResolution<OperationId>> {
+        let data = self.locked_data();
+        let mut matches = itertools::chain([&self.root_operation_id], data.operations.keys())
+            .filter(|id| prefix.matches(*id));
+        let resolution = match (matches.next(), matches.next()) {
+            (None, _) => PrefixResolution::NoMatch,
+            (Some(id), None) => PrefixResolution::SingleMatch(id.clone()),
+            (Some(_), Some(_)) => PrefixResolution::AmbiguousMatch,
+        };
+        Ok(resolution)
+    }
+
+    fn gc(&self, _head_ids: &[OperationId], _keep_newer: SystemTime) -> OpStoreResult<()> {
+        // Unreachable objects are kept until the store is dropped.
+        Ok(())
+    }
+}
+
+/// Operation heads store which keeps the current heads in memory.
+#[derive(Debug)]
+pub struct MemoryOpHeadsStore {
+    heads: Arc<Mutex<BTreeSet<OperationId>>>,
+    lock: Arc<Mutex<()>>,
+}
+
+impl MemoryOpHeadsStore {
+    /// Name of this operation heads store.
+    pub fn name() -> &'static str {
+        "memory_op_heads_store"
+    }
+
+    /// Creates a store with no operation heads.
+    pub fn new() -> Self {
+        MemoryOpHeadsStore {
+            heads: Arc::new(Mutex::new(BTreeSet::new())),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Returns a copy of this store. Heads updated in the copy don't affect
+    /// this store and vice versa.
+    pub fn snapshot(&self) -> Self {
+        MemoryOpHeadsStore {
+            heads: Arc::new(Mutex::new(self.heads.lock().unwrap().clone())),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Returns a store which shares the heads and the lock with this store.
+    fn share(&self) -> Self {
+        MemoryOpHeadsStore {
+            heads: self.heads.clone(),
+            lock: self.lock.clone(),
+        }
+    }
+}
+
+impl Default for MemoryOpHeadsStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct MemoryOpHeadsStoreLock<'a> {
+    _guard: MutexGuard<'a, ()>,
+}
+
+impl OpHeadsStoreLock for MemoryOpHeadsStoreLock<'_> {}
+
+impl OpHeadsStore for MemoryOpHeadsStore {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        Self::name()
+    }
+
+    fn update_op_heads(
+        &self,
+        old_ids: &[OperationId],
+        new_id: &OperationId,
+    ) -> Result<(), OpHeadsStoreError> {
+        assert!(!old_ids.contains(new_id));
+        let mut heads = self.heads.lock().unwrap();
+        heads.insert(new_id.clone());
+        for old_id in old_ids {
+            heads.remove(old_id);
+        }
+        Ok(())
+    }
+
+    fn get_op_heads(&self) -> Result<Vec<OperationId>, OpHeadsStoreError> {
+        Ok(self.heads.lock().unwrap().iter().cloned().collect())
+    }
+
+    fn lock(&self) -> Result<Box<dyn OpHeadsStoreLock + '_>, OpHeadsStoreError> {
+        let guard = self.lock.lock().unwrap();
+        Ok(Box::new(MemoryOpHeadsStoreLock { _guard: guard }))
+    }
+}
+
+#[derive(Debug)]
+struct MemorySubmoduleStore;
+
+impl SubmoduleStore for MemorySubmoduleStore {
+    fn name(&self) -> &str {
+        "memory"
+    }
+}
+
+/// In-memory storage of a whole repository.
+///
+/// The storage is initialized with the root operation, so a repository can be
+/// loaded from it right away:
+///
+/// ```
+/// # use jj_lib::config::StackedConfig;
+/// # use jj_lib::memory_backend::MemoryRepoStorage;
+/// # use jj_lib::settings::UserSettings;
+/// # use jj_lib::signing::Signer;
+/// let settings = UserSettings::from_config(StackedConfig::with_defaults()).unwrap();
+/// let storage = MemoryRepoStorage::new();
+/// let signer = Signer::from_settings(&settings).unwrap();
+/// let repo = storage.loader(&settings, signer).load_at_head().unwrap();
+/// ```
+///
+/// Nothing is persisted: the data is gone once the storage and all repos
+/// loaded from it are dropped. Cloning the storage takes a snapshot of its
+/// current state. Objects are reference counted, so this is cheap, and later
+/// changes to either copy aren't visible to the other.
+#[derive(Debug)]
+pub struct MemoryRepoStorage {
+    backend: MemoryBackend,
+    op_store: MemoryOpStore,
+    op_heads_store: MemoryOpHeadsStore,
+    index_store: MemoryIndexStore,
+}
+
+impl MemoryRepoStorage {
+    /// Creates storage of an empty repository.
+    pub fn new() -> Self {
+        let backend = MemoryBackend::new();
+        let root_data = RootOperationData {
+            root_commit_id: backend.root_commit_id.clone(),
+        };
+        let op_store = MemoryOpStore::new(root_data);
+        let op_heads_store = MemoryOpHeadsStore::new();
+        op_heads_store
+            .update_op_heads(&[], op_store.root_operation_id())
+            .unwrap();
+        MemoryRepoStorage {
+            backend,
+            op_store,
+            op_heads_store,
+            index_store: MemoryIndexStore::new(),
+        }
+    }
+
+    /// Creates a loader for the repository. Repos loaded from it read and
+    /// write this storage.
+    pub fn loader(&self, settings: &UserSettings, signer: Signer) -> RepoLoader {
+        RepoLoader::new(
+            settings.clone(),
+            Store::new(Box::new(self.backend.share()), signer),
+            Arc::new(self.op_store.share()),
+            Arc::new(self.op_heads_store.share()),
+            // The index at an operation never changes, so it can be shared
+            // between snapshots.
+            Arc::new(self.index_store.clone()),
+            Arc::new(MemorySubmoduleStore),
+        )
+    }
+}
+
+impl Default for MemoryRepoStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for MemoryRepoStorage {
+    fn clone(&self) -> Self {
+        MemoryRepoStorage {
+            backend: self.backend.snapshot(),
+            op_store: self.op_store.snapshot(),
+            op_heads_store: self.op_heads_store.snapshot(),
+            index_store: self.index_store.clone(),
+        }
+    }
+}
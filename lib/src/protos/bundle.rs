@@ -0,0 +1,92 @@
+// This file is @generated by prost-build.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Bundle {
+    /// Name of the backend the objects were read from. Object ids are only
+    /// comparable with the destination repo's if the backend is the same.
+    #[prost(string, tag = "1")]
+    pub backend_name: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub root_commit_id: ::prost::alloc::vec::Vec<u8>,
+    /// Parents of the bundled commits which aren't included in the bundle.
+    #[prost(message, repeated, tag = "3")]
+    pub base_commits: ::prost::alloc::vec::Vec<BaseCommit>,
+    /// Parents come before children.
+    #[prost(message, repeated, tag = "4")]
+    pub commits: ::prost::alloc::vec::Vec<Commit>,
+    #[prost(message, repeated, tag = "5")]
+    pub trees: ::prost::alloc::vec::Vec<Tree>,
+    #[prost(message, repeated, tag = "6")]
+    pub files: ::prost::alloc::vec::Vec<File>,
+    #[prost(message, repeated, tag = "7")]
+    pub symlinks: ::prost::alloc::vec::Vec<Symlink>,
+    #[prost(message, repeated, tag = "8")]
+    pub conflicts: ::prost::alloc::vec::Vec<Conflict>,
+    /// Objects referenced by the bundled trees which exist in the trees of the
+    /// base commits. Their content isn't included.
+    #[prost(message, repeated, tag = "9")]
+    pub base_trees: ::prost::alloc::vec::Vec<BaseObject>,
+    #[prost(message, repeated, tag = "10")]
+    pub base_files: ::prost::alloc::vec::Vec<BaseObject>,
+    #[prost(message, repeated, tag = "11")]
+    pub base_symlinks: ::prost::alloc::vec::Vec<BaseObject>,
+    #[prost(message, repeated, tag = "12")]
+    pub base_conflicts: ::prost::alloc::vec::Vec<BaseObject>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Commit {
+    #[prost(bytes = "vec", tag = "1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub commit: ::core::option::Option<super::simple_store::Commit>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BaseCommit {
+    #[prost(bytes = "vec", tag = "1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    /// Used to find the commit if the destination repo uses another backend.
+    #[prost(bytes = "vec", tag = "2")]
+    pub change_id: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Tree {
+    #[prost(bytes = "vec", tag = "1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub tree: ::core::option::Option<super::simple_store::Tree>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct File {
+    #[prost(bytes = "vec", tag = "1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Symlink {
+    #[prost(bytes = "vec", tag = "1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(string, tag = "2")]
+    pub target: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Conflict {
+    #[prost(bytes = "vec", tag = "1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub conflict: ::core::option::Option<super::simple_store::Conflict>,
+}
+/// Object found in the tree of a base commit, which the destination repo can
+/// look up by path.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BaseObject {
+    #[prost(bytes = "vec", tag = "1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    /// Index into `Bundle.base_commits`.
+    #[prost(uint32, tag = "2")]
+    pub base_commit: u32,
+    /// Index of the tree in the base commit's merged tree.
+    #[prost(uint32, tag = "3")]
+    pub term: u32,
+    #[prost(string, tag = "4")]
+    pub path: ::prost::alloc::string::String,
+}
@@ -5,6 +5,10 @@ pub struct Commit {
     pub predecessors: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
     #[prost(bytes = "vec", tag = "4")]
     pub change_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", optional, tag = "11")]
+    pub duplicated_from: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", optional, tag = "12")]
+    pub duplicated_from_change_id: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
     /// Alternating positive and negative terms. Set only for conflicts.
     /// Resolved trees are stored in the git commit
     #[prost(bytes = "vec", repeated, tag = "1")]
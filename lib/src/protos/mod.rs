@@ -1,4 +1,7 @@
 // This file is @generated by prost-build.
+pub mod bundle {
+    include!("bundle.rs");
+}
 pub mod git_store {
     include!("git_store.rs");
 }
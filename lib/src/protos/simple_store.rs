@@ -46,6 +46,10 @@ pub struct Commit {
     pub parents: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
     #[prost(bytes = "vec", repeated, tag = "2")]
     pub predecessors: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", optional, tag = "10")]
+    pub duplicated_from: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", optional, tag = "11")]
+    pub duplicated_from_change_id: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
     /// Alternating positive and negative terms
     #[prost(bytes = "vec", repeated, tag = "3")]
     pub root_tree: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
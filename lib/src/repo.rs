@@ -1892,6 +1892,49 @@ impl Repo for MutableRepo {
     }
 }
 
+/// Workspaces whose working-copy commits are no longer visible.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StaleWorkspaces {
+    /// Workspaces pointing to hidden (e.g. abandoned or rewritten) commits.
+    pub hidden: Vec<(WorkspaceNameBuf, CommitId)>,
+    /// Workspaces pointing to commits which don't exist in the index.
+    pub missing: Vec<(WorkspaceNameBuf, CommitId)>,
+}
+
+impl StaleWorkspaces {
+    /// Returns true if no stale workspaces were found.
+    pub fn is_empty(&self) -> bool {
+        self.hidden.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// Finds workspaces whose working-copy commits aren't ancestors of the visible
+/// heads of the `repo`.
+///
+/// Workspaces are listed in name order.
+pub fn find_stale_workspaces(repo: &dyn Repo) -> BackendResult<StaleWorkspaces> {
+    let index = repo.index();
+    let (present, missing): (Vec<_>, Vec<_>) = repo
+        .view()
+        .wc_commit_ids()
+        .iter()
+        .map(|(name, commit_id)| (name.clone(), commit_id.clone()))
+        .partition(|(_, commit_id)| index.has_id(commit_id));
+    let hidden_ids: HashSet<CommitId> =
+        RevsetExpression::commits(present.iter().map(|(_, id)| id.clone()).collect())
+            .minus(&RevsetExpression::visible_heads().ancestors())
+            .evaluate(repo)
+            .map_err(|err| err.expect_backend_error())?
+            .iter()
+            .try_collect()
+            .map_err(|err| err.expect_backend_error())?;
+    let hidden = present
+        .into_iter()
+        .filter(|(_, commit_id)| hidden_ids.contains(commit_id))
+        .collect();
+    Ok(StaleWorkspaces { hidden, missing })
+}
+
 /// Error from attempts to check out the root commit for editing
 #[derive(Debug, Error)]
 #[error("Cannot rewrite the root commit")]
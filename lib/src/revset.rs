@@ -15,14 +15,22 @@
 #![allow(missing_docs)]
 
 use std::any::Any;
+use std::cell::RefCell;
+use std::cmp::Reverse;
 use std::collections::hash_map;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::convert::Infallible;
 use std::fmt;
+use std::iter;
+use std::ops::ControlFlow;
 use std::ops::Range;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
+use chrono::NaiveDate;
 use itertools::Itertools as _;
 use once_cell::sync::Lazy;
 use thiserror::Error;
@@ -30,11 +38,15 @@ use thiserror::Error;
 use crate::backend::BackendError;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
+use crate::backend::MillisSinceEpoch;
 use crate::commit::Commit;
 use crate::dsl_util;
 use crate::dsl_util::collect_similar;
+use crate::dsl_util::is_similar;
 use crate::dsl_util::AliasExpandError as _;
+use crate::dsl_util::AliasId;
 use crate::fileset;
+use crate::fileset::FilePattern;
 use crate::fileset::FilesetDiagnostics;
 use crate::fileset::FilesetExpression;
 use crate::graph::GraphNode;
@@ -42,13 +54,16 @@ use crate::hex_util::to_forward_hex;
 use crate::id_prefix::IdPrefixContext;
 use crate::id_prefix::IdPrefixIndex;
 use crate::object_id::HexPrefix;
+use crate::object_id::ObjectId as _;
 use crate::object_id::PrefixResolution;
 use crate::op_store::RemoteRefState;
 use crate::op_walk;
+use crate::ref_name::GitRefNameBuf;
 use crate::ref_name::RemoteRefSymbol;
 use crate::ref_name::RemoteRefSymbolBuf;
 use crate::ref_name::WorkspaceName;
 use crate::ref_name::WorkspaceNameBuf;
+use crate::repo::find_stale_workspaces;
 use crate::repo::ReadonlyRepo;
 use crate::repo::Repo;
 use crate::repo::RepoLoaderError;
@@ -66,10 +81,14 @@ pub use crate::revset_parser::RevsetDiagnostics;
 pub use crate::revset_parser::RevsetParseError;
 pub use crate::revset_parser::RevsetParseErrorKind;
 pub use crate::revset_parser::UnaryOp;
+use crate::settings::HumanByteSize;
 use crate::store::Store;
 use crate::str_util::StringPattern;
+use crate::str_util::StringPatternSet;
+use crate::time_util;
 use crate::time_util::DatePattern;
 use crate::time_util::DatePatternContext;
+use crate::time_util::DatePatternParseError;
 
 /// Error occurred during symbol resolution.
 #[derive(Debug, Error)]
@@ -80,13 +99,32 @@ pub enum RevsetResolutionError {
         candidates: Vec<String>,
     },
     #[error("Workspace `{}` doesn't have a working-copy commit", name.as_symbol())]
-    WorkspaceMissingWorkingCopy { name: WorkspaceNameBuf },
+    WorkspaceMissingWorkingCopy {
+        name: WorkspaceNameBuf,
+        /// Working-copy symbols of workspaces with similar names.
+        candidates: Vec<String>,
+    },
     #[error("An empty string is not a valid revision")]
     EmptyString,
-    #[error("Commit ID prefix `{0}` is ambiguous")]
-    AmbiguousCommitIdPrefix(String),
-    #[error("Change ID prefix `{0}` is ambiguous")]
-    AmbiguousChangeIdPrefix(String),
+    #[error("Commit ID prefix `{prefix}` is ambiguous")]
+    AmbiguousCommitIdPrefix {
+        prefix: String,
+        /// Unambiguous longer prefixes, up to a small limit.
+        candidates: Vec<String>,
+    },
+    #[error("Change ID prefix `{prefix}` is ambiguous")]
+    AmbiguousChangeIdPrefix {
+        prefix: String,
+        /// Unambiguous longer prefixes, up to a small limit.
+        candidates: Vec<String>,
+    },
+    #[error("Name `{name}` is ambiguous: it refers to both a remote bookmark and a remote tag")]
+    AmbiguousRemoteRef { name: String },
+    #[error(
+        "Symbol `{symbol}` is ambiguous: multiple extensions of priority {priority} resolve it to \
+         different revisions"
+    )]
+    AmbiguousExtensionSymbol { symbol: String, priority: i32 },
     #[error("Unexpected error from store")]
     StoreError(#[source] BackendError),
     #[error(transparent)]
@@ -98,6 +136,13 @@ pub enum RevsetResolutionError {
 pub enum RevsetEvaluationError {
     #[error("Unexpected error from store")]
     StoreError(#[from] BackendError),
+    /// Evaluation was aborted because it exceeded the
+    /// [`RevsetEvaluationBudget`].
+    #[error("Revset evaluation exceeded budget: {0}")]
+    BudgetExceeded(String),
+    /// Evaluation was cancelled by the [`RevsetEvaluationProgress`] callback.
+    #[error("Revset evaluation was cancelled")]
+    Cancelled,
     #[error(transparent)]
     Other(Box<dyn std::error::Error + Send + Sync>),
 }
@@ -106,9 +151,78 @@ impl RevsetEvaluationError {
     pub fn expect_backend_error(self) -> BackendError {
         match self {
             Self::StoreError(err) => err,
-            Self::Other(err) => panic!("Unexpected revset error: {err}"),
+            Self::BudgetExceeded(_) | Self::Cancelled | Self::Other(_) => {
+                panic!("Unexpected revset error: {self}")
+            }
+        }
+    }
+}
+
+/// Limits on resources spent by revset evaluation.
+///
+/// The budget covers both the evaluation and the subsequent iteration of the
+/// returned revset. Exceeding any of the limits results in
+/// [`RevsetEvaluationError::BudgetExceeded`]. The default is unlimited.
+#[derive(Clone, Debug, Default)]
+pub struct RevsetEvaluationBudget {
+    /// Maximum wall-clock duration since the evaluation started.
+    pub max_duration: Option<Duration>,
+    /// Maximum number of index entries visited by walks and filters.
+    pub max_visited_entries: Option<usize>,
+    /// Callback to report the progress of the evaluation.
+    pub progress: Option<RevsetEvaluationProgress>,
+}
+
+impl RevsetEvaluationBudget {
+    /// Returns true if no limit is set.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_duration.is_none() && self.max_visited_entries.is_none()
+    }
+}
+
+/// Callback invoked periodically during revset evaluation.
+///
+/// The callback receives the numbers of index entries visited by walks and
+/// filters, and of the entries matched so far. It's invoked every `interval`
+/// visited entries. Returning [`ControlFlow::Break`] cancels the evaluation
+/// with [`RevsetEvaluationError::Cancelled`].
+#[derive(Clone)]
+pub struct RevsetEvaluationProgress {
+    interval: usize,
+    callback: Rc<RefCell<ProgressCallback>>,
+}
+
+type ProgressCallback = dyn FnMut(usize, usize) -> ControlFlow<()>;
+
+impl RevsetEvaluationProgress {
+    /// Creates progress callback invoked every `interval` visited entries.
+    pub fn new(
+        interval: usize,
+        callback: impl FnMut(usize, usize) -> ControlFlow<()> + 'static,
+    ) -> Self {
+        RevsetEvaluationProgress {
+            interval: interval.max(1),
+            callback: Rc::new(RefCell::new(callback)),
         }
     }
+
+    /// Number of visited entries between callback invocations.
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    /// Invokes the callback with the `visited` and `matched` counts.
+    pub fn report(&self, visited: usize, matched: usize) -> ControlFlow<()> {
+        (self.callback.borrow_mut())(visited, matched)
+    }
+}
+
+impl fmt::Debug for RevsetEvaluationProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RevsetEvaluationProgress")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
 }
 
 // assumes index has less than u64::MAX entries.
@@ -130,9 +244,21 @@ pub enum RevsetModifier {
 #[derive(Clone, Debug)]
 pub enum RevsetCommitRef {
     WorkingCopy(WorkspaceNameBuf),
-    WorkingCopies,
+    /// Working-copy commits of the workspaces whose names match the pattern.
+    WorkingCopies(StringPattern),
+    /// Working-copy commits which exist but are no longer visible.
+    StaleWorkspaces,
     Symbol(String),
     RemoteSymbol(RemoteRefSymbolBuf),
+    /// Hexadecimal commit id prefix.
+    CommitIdPrefix(String),
+    /// Reverse-hexadecimal change id prefix.
+    ChangeIdPrefix(String),
+    /// All visible commits whose hexadecimal commit id starts with the prefix.
+    CommitIdPrefixMatches(String),
+    /// All visible commits whose reverse-hexadecimal change id starts with the
+    /// prefix.
+    ChangeIdPrefixMatches(String),
     Bookmarks(StringPattern),
     RemoteBookmarks {
         bookmark_pattern: StringPattern,
@@ -156,31 +282,54 @@ pub trait RevsetFilterExtension: std::fmt::Debug + Any {
 pub enum RevsetFilterPredicate {
     /// Commits with number of parents in the range.
     ParentCount(Range<u32>),
-    /// Commits with description matching the pattern.
-    Description(StringPattern),
-    /// Commits with first line of the description matching the pattern.
-    Subject(StringPattern),
-    /// Commits with author name matching the pattern.
-    AuthorName(StringPattern),
-    /// Commits with author email matching the pattern.
-    AuthorEmail(StringPattern),
+    /// Commits with description matching any of the patterns.
+    Description(StringPatternSet),
+    /// Commits with first line of the description matching any of the
+    /// patterns.
+    Subject(StringPatternSet),
+    /// Commits whose subject isn't followed by a blank line.
+    MalformedSubject,
+    /// Commits with author name matching any of the patterns.
+    AuthorName(StringPatternSet),
+    /// Commits with author email matching any of the patterns.
+    AuthorEmail(StringPatternSet),
     /// Commits with author dates matching the given date pattern.
     AuthorDate(DatePattern),
-    /// Commits with committer name matching the pattern.
-    CommitterName(StringPattern),
-    /// Commits with committer email matching the pattern.
-    CommitterEmail(StringPattern),
+    /// Commits with committer name matching any of the patterns.
+    CommitterName(StringPatternSet),
+    /// Commits with committer email matching any of the patterns.
+    CommitterEmail(StringPatternSet),
     /// Commits with committer dates matching the given date pattern.
     CommitterDate(DatePattern),
+    /// Commits whose author and committer timestamps have different timezone
+    /// offsets.
+    TimezoneMismatch,
     /// Commits modifying the paths specified by the fileset.
     File(FilesetExpression),
+    /// Commits modifying the paths specified by the fileset relative to their
+    /// first parent.
+    FileFromFirstParent(FilesetExpression),
+    /// Commits modifying the paths specified by the fileset, or the paths they
+    /// were renamed from in descendant commits.
+    FileFollowingRenames(FilesetExpression),
     /// Commits containing diffs matching the `text` pattern within the `files`.
     DiffContains {
         text: StringPattern,
         files: FilesetExpression,
     },
+    /// Commits whose number of inserted and deleted lines within the `files`
+    /// is in the range.
+    LinesChanged {
+        range: Range<u64>,
+        files: FilesetExpression,
+    },
     /// Commits with conflicts
     HasConflict,
+    /// Commits with a cryptographic signature.
+    Signed,
+    /// Ancestors of remote bookmarks. This is resolved to a set predicate when
+    /// resolving symbols, so the remote bookmarks are looked up only once.
+    Pushed,
     /// Custom predicates provided by extensions
     Extension(Rc<dyn RevsetFilterExtension>),
 }
@@ -258,6 +407,8 @@ pub enum RevsetExpression<St: ExpressionState> {
     Heads(Rc<Self>),
     Roots(Rc<Self>),
     ForkPoint(Rc<Self>),
+    /// Commits duplicated from any of the given commits.
+    DuplicatesOf(Rc<Self>),
     Latest {
         candidates: Rc<Self>,
         count: usize,
@@ -327,8 +478,12 @@ impl<St: ExpressionState<CommitRef = RevsetCommitRef>> RevsetExpression<St> {
         Rc::new(Self::CommitRef(RevsetCommitRef::WorkingCopy(name)))
     }
 
-    pub fn working_copies() -> Rc<Self> {
-        Rc::new(Self::CommitRef(RevsetCommitRef::WorkingCopies))
+    pub fn working_copies(pattern: StringPattern) -> Rc<Self> {
+        Rc::new(Self::CommitRef(RevsetCommitRef::WorkingCopies(pattern)))
+    }
+
+    pub fn stale_workspaces() -> Rc<Self> {
+        Rc::new(Self::CommitRef(RevsetCommitRef::StaleWorkspaces))
     }
 
     pub fn symbol(value: String) -> Rc<Self> {
@@ -340,6 +495,26 @@ impl<St: ExpressionState<CommitRef = RevsetCommitRef>> RevsetExpression<St> {
         Rc::new(Self::CommitRef(commit_ref))
     }
 
+    pub fn commit_id_prefix(prefix: String) -> Rc<Self> {
+        Rc::new(Self::CommitRef(RevsetCommitRef::CommitIdPrefix(prefix)))
+    }
+
+    pub fn change_id_prefix(prefix: String) -> Rc<Self> {
+        Rc::new(Self::CommitRef(RevsetCommitRef::ChangeIdPrefix(prefix)))
+    }
+
+    pub fn commit_id_prefix_matches(prefix: String) -> Rc<Self> {
+        Rc::new(Self::CommitRef(RevsetCommitRef::CommitIdPrefixMatches(
+            prefix,
+        )))
+    }
+
+    pub fn change_id_prefix_matches(prefix: String) -> Rc<Self> {
+        Rc::new(Self::CommitRef(RevsetCommitRef::ChangeIdPrefixMatches(
+            prefix,
+        )))
+    }
+
     pub fn bookmarks(pattern: StringPattern) -> Rc<Self> {
         Rc::new(Self::CommitRef(RevsetCommitRef::Bookmarks(pattern)))
     }
@@ -441,6 +616,11 @@ impl<St: ExpressionState> RevsetExpression<St> {
         Rc::new(Self::ForkPoint(self.clone()))
     }
 
+    /// Commits that were duplicated from any of the commits in `self`.
+    pub fn duplicates(self: &Rc<Self>) -> Rc<Self> {
+        Rc::new(Self::DuplicatesOf(self.clone()))
+    }
+
     /// Filter all commits by `predicate` in `self`.
     pub fn filtered(self: &Rc<Self>, predicate: RevsetFilterPredicate) -> Rc<Self> {
         self.intersection(&Self::filter(predicate))
@@ -479,6 +659,19 @@ impl<St: ExpressionState> RevsetExpression<St> {
         })
     }
 
+    /// Commits that are ancestors of `heads` but not ancestors of `self`,
+    /// within `depth` generations from `heads`.
+    ///
+    /// For linear history, this is the `depth` commits of the range closest to
+    /// `heads`.
+    pub fn range_with_depth(self: &Rc<Self>, heads: &Rc<Self>, depth: u64) -> Rc<Self> {
+        Rc::new(Self::Range {
+            roots: self.clone(),
+            heads: heads.clone(),
+            generation: 0..depth,
+        })
+    }
+
     /// Suppresses name resolution error within `self`.
     pub fn present(self: &Rc<Self>) -> Rc<Self> {
         Rc::new(Self::Present(self.clone()))
@@ -552,9 +745,33 @@ impl UserRevsetExpression {
     ) -> Result<Rc<ResolvedRevsetExpression>, RevsetResolutionError> {
         resolve_symbols(repo, self, symbol_resolver)
     }
+
+    /// Formats this expression in the revset language.
+    ///
+    /// The returned string doesn't depend on user aliases, and an expression
+    /// built by [`parse()`] is reproduced when the string is parsed back.
+    /// Operators are normalized, and symbols and patterns are quoted as
+    /// needed. Optimized expressions are formatted to equivalent ones. Date
+    /// patterns are rounded up to whole seconds.
+    ///
+    /// Returns `None` if the expression contains a node which has no textual
+    /// representation, such as an extension predicate.
+    pub fn to_revset_string(&self) -> Option<String> {
+        format_expression(self).map(|(text, _)| text)
+    }
 }
 
 impl ResolvedRevsetExpression {
+    /// Formats this expression in a canonical form.
+    ///
+    /// Expressions that are trivially equivalent as sets, such as `a | b` and
+    /// `b | a`, are formatted to the same string, so it can be used as a key
+    /// to cache the evaluation result. The returned string isn't necessarily
+    /// a valid revset expression.
+    pub fn to_canonical_string(&self) -> String {
+        format_canonical(self)
+    }
+
     /// Optimizes and evaluates this expression.
     pub fn evaluate<'index>(
         self: Rc<Self>,
@@ -563,6 +780,34 @@ impl ResolvedRevsetExpression {
         optimize(self).evaluate_unoptimized(repo)
     }
 
+    /// Optimizes and evaluates this expression within the given `budget`.
+    pub fn evaluate_with_budget<'index>(
+        self: Rc<Self>,
+        repo: &'index dyn Repo,
+        budget: &RevsetEvaluationBudget,
+    ) -> Result<Box<dyn Revset + 'index>, RevsetEvaluationError> {
+        let expr = optimize(self).to_backend_expression(repo);
+        repo.index()
+            .evaluate_revset_with_budget(&expr, repo.store(), budget)
+    }
+
+    /// Optimizes and evaluates this expression, invoking the `callback` every
+    /// `interval` index entries visited by walks and filters.
+    ///
+    /// See [`RevsetEvaluationProgress`] for details.
+    pub fn evaluate_with_progress<'index>(
+        self: Rc<Self>,
+        repo: &'index dyn Repo,
+        interval: usize,
+        callback: impl FnMut(usize, usize) -> ControlFlow<()> + 'static,
+    ) -> Result<Box<dyn Revset + 'index>, RevsetEvaluationError> {
+        let budget = RevsetEvaluationBudget {
+            progress: Some(RevsetEvaluationProgress::new(interval, callback)),
+            ..Default::default()
+        };
+        self.evaluate_with_budget(repo, &budget)
+    }
+
     /// Evaluates this expression without optimizing it.
     ///
     /// Use this function if `self` is already optimized, or to debug
@@ -630,6 +875,11 @@ pub enum ResolvedExpression {
     Heads(Box<Self>),
     Roots(Box<Self>),
     ForkPoint(Box<Self>),
+    /// Commits in `candidates` duplicated from any of the `sources`.
+    DuplicatesOf {
+        candidates: Box<Self>,
+        sources: Box<Self>,
+    },
     Latest {
         candidates: Box<Self>,
         count: usize,
@@ -652,6 +902,15 @@ pub type RevsetFunction = fn(
     &LoweringContext,
 ) -> Result<Rc<UserRevsetExpression>, RevsetParseError>;
 
+/// Functions that were removed after being renamed, mapped to their new names.
+const RENAMED_FUNCTIONS: &[(&str, &str)] = &[
+    ("branches", "bookmarks"),
+    ("remote_branches", "remote_bookmarks"),
+    ("tracked_remote_branches", "tracked_remote_bookmarks"),
+    ("untracked_remote_branches", "untracked_remote_bookmarks"),
+    ("file", "files"),
+];
+
 static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy::new(|| {
     // Not using maplit::hashmap!{} or custom declarative macro here because
     // code completion inside macro is quite restricted.
@@ -707,18 +966,25 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
         function.expect_no_arguments()?;
         Ok(RevsetExpression::all())
     });
-    map.insert("working_copies", |_diagnostics, function, _context| {
+    map.insert("working_copies", |diagnostics, function, _context| {
+        let ([], [opt_arg]) = function.expect_arguments()?;
+        let pattern = if let Some(arg) = opt_arg {
+            expect_string_pattern(diagnostics, arg)?
+        } else {
+            StringPattern::everything()
+        };
+        Ok(RevsetExpression::working_copies(pattern))
+    });
+    map.insert("stale_workspaces", |_diagnostics, function, _context| {
         function.expect_no_arguments()?;
-        Ok(RevsetExpression::working_copies())
+        Ok(RevsetExpression::stale_workspaces())
     });
     map.insert("heads", |diagnostics, function, context| {
-        let [arg] = function.expect_exact_arguments()?;
-        let candidates = lower_expression(diagnostics, arg, context)?;
+        let candidates = lower_union_of_arguments(diagnostics, function, context)?;
         Ok(candidates.heads())
     });
     map.insert("roots", |diagnostics, function, context| {
-        let [arg] = function.expect_exact_arguments()?;
-        let candidates = lower_expression(diagnostics, arg, context)?;
+        let candidates = lower_union_of_arguments(diagnostics, function, context)?;
         Ok(candidates.roots())
     });
     map.insert("visible_heads", |_diagnostics, function, _context| {
@@ -738,6 +1004,12 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
         };
         Ok(RevsetExpression::bookmarks(pattern))
     });
+    map.insert("has_no_bookmark", |_diagnostics, function, _context| {
+        function.expect_no_arguments()?;
+        // Bookmark targets are resolved up front, so this is evaluated as a
+        // set difference rather than a per-commit predicate.
+        Ok(RevsetExpression::bookmarks(StringPattern::everything()).negated())
+    });
     map.insert("remote_bookmarks", |diagnostics, function, _context| {
         parse_remote_bookmarks_arguments(diagnostics, function, None)
     });
@@ -781,9 +1053,13 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
         Ok(candidates.latest(count))
     });
     map.insert("fork_point", |diagnostics, function, context| {
+        let expression = lower_union_of_arguments(diagnostics, function, context)?;
+        Ok(RevsetExpression::fork_point(&expression))
+    });
+    map.insert("duplicates_of", |diagnostics, function, context| {
         let [expression_arg] = function.expect_exact_arguments()?;
         let expression = lower_expression(diagnostics, expression_arg, context)?;
-        Ok(RevsetExpression::fork_point(&expression))
+        Ok(expression.duplicates())
     });
     map.insert("merges", |_diagnostics, function, _context| {
         function.expect_no_arguments()?;
@@ -792,84 +1068,107 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
         ))
     });
     map.insert("description", |diagnostics, function, _context| {
-        let [arg] = function.expect_exact_arguments()?;
-        let pattern = expect_string_pattern(diagnostics, arg)?;
+        let patterns = expect_string_pattern_set(diagnostics, function)?;
         Ok(RevsetExpression::filter(
-            RevsetFilterPredicate::Description(pattern),
+            RevsetFilterPredicate::Description(patterns),
         ))
     });
     map.insert("subject", |diagnostics, function, _context| {
-        let [arg] = function.expect_exact_arguments()?;
-        let pattern = expect_string_pattern(diagnostics, arg)?;
-        let predicate = RevsetFilterPredicate::Subject(pattern);
+        let patterns = expect_string_pattern_set(diagnostics, function)?;
+        let predicate = RevsetFilterPredicate::Subject(patterns);
         Ok(RevsetExpression::filter(predicate))
     });
+    map.insert("malformed_subject", |_diagnostics, function, _context| {
+        function.expect_no_arguments()?;
+        Ok(RevsetExpression::filter(
+            RevsetFilterPredicate::MalformedSubject,
+        ))
+    });
     map.insert("author", |diagnostics, function, _context| {
-        let [arg] = function.expect_exact_arguments()?;
-        let pattern = expect_string_pattern(diagnostics, arg)?;
-        let name_predicate = RevsetFilterPredicate::AuthorName(pattern.clone());
-        let email_predicate = RevsetFilterPredicate::AuthorEmail(pattern);
+        let patterns = expect_string_pattern_set(diagnostics, function)?;
+        let name_predicate = RevsetFilterPredicate::AuthorName(patterns.clone());
+        let email_predicate = RevsetFilterPredicate::AuthorEmail(patterns);
         Ok(RevsetExpression::filter(name_predicate)
             .union(&RevsetExpression::filter(email_predicate)))
     });
     map.insert("author_name", |diagnostics, function, _context| {
-        let [arg] = function.expect_exact_arguments()?;
-        let pattern = expect_string_pattern(diagnostics, arg)?;
-        let predicate = RevsetFilterPredicate::AuthorName(pattern);
+        let patterns = expect_string_pattern_set(diagnostics, function)?;
+        let predicate = RevsetFilterPredicate::AuthorName(patterns);
         Ok(RevsetExpression::filter(predicate))
     });
     map.insert("author_email", |diagnostics, function, _context| {
-        let [arg] = function.expect_exact_arguments()?;
-        let pattern = expect_string_pattern(diagnostics, arg)?;
-        let predicate = RevsetFilterPredicate::AuthorEmail(pattern);
+        let patterns = expect_string_pattern_set(diagnostics, function)?;
+        let predicate = RevsetFilterPredicate::AuthorEmail(patterns);
         Ok(RevsetExpression::filter(predicate))
     });
     map.insert("author_date", |diagnostics, function, context| {
         let [arg] = function.expect_exact_arguments()?;
-        let pattern = expect_date_pattern(diagnostics, arg, context.date_pattern_context())?;
+        let pattern = expect_date_pattern_with(diagnostics, arg, |value, kind| {
+            context.parse_date_pattern(value, kind)
+        })?;
         Ok(RevsetExpression::filter(RevsetFilterPredicate::AuthorDate(
             pattern,
         )))
     });
-    map.insert("mine", |_diagnostics, function, context| {
-        function.expect_no_arguments()?;
+    map.insert("mine", |diagnostics, function, context| {
+        let ([], [opt_arg]) = function.expect_arguments()?;
+        let email = if let Some(arg) = opt_arg {
+            expect_literal(diagnostics, "string", arg)?
+        } else {
+            context.user_email.to_owned()
+        };
         // Email address domains are inherently case‐insensitive, and the local‐parts
         // are generally (although not universally) treated as case‐insensitive too, so
         // we use a case‐insensitive match here.
-        let predicate =
-            RevsetFilterPredicate::AuthorEmail(StringPattern::exact_i(context.user_email));
+        let predicate = RevsetFilterPredicate::AuthorEmail(StringPattern::exact_i(email).into());
         Ok(RevsetExpression::filter(predicate))
     });
     map.insert("committer", |diagnostics, function, _context| {
-        let [arg] = function.expect_exact_arguments()?;
-        let pattern = expect_string_pattern(diagnostics, arg)?;
-        let name_predicate = RevsetFilterPredicate::CommitterName(pattern.clone());
-        let email_predicate = RevsetFilterPredicate::CommitterEmail(pattern);
+        let patterns = expect_string_pattern_set(diagnostics, function)?;
+        let name_predicate = RevsetFilterPredicate::CommitterName(patterns.clone());
+        let email_predicate = RevsetFilterPredicate::CommitterEmail(patterns);
         Ok(RevsetExpression::filter(name_predicate)
             .union(&RevsetExpression::filter(email_predicate)))
     });
     map.insert("committer_name", |diagnostics, function, _context| {
-        let [arg] = function.expect_exact_arguments()?;
-        let pattern = expect_string_pattern(diagnostics, arg)?;
-        let predicate = RevsetFilterPredicate::CommitterName(pattern);
+        let patterns = expect_string_pattern_set(diagnostics, function)?;
+        let predicate = RevsetFilterPredicate::CommitterName(patterns);
         Ok(RevsetExpression::filter(predicate))
     });
     map.insert("committer_email", |diagnostics, function, _context| {
-        let [arg] = function.expect_exact_arguments()?;
-        let pattern = expect_string_pattern(diagnostics, arg)?;
-        let predicate = RevsetFilterPredicate::CommitterEmail(pattern);
+        let patterns = expect_string_pattern_set(diagnostics, function)?;
+        let predicate = RevsetFilterPredicate::CommitterEmail(patterns);
         Ok(RevsetExpression::filter(predicate))
     });
     map.insert("committer_date", |diagnostics, function, context| {
         let [arg] = function.expect_exact_arguments()?;
-        let pattern = expect_date_pattern(diagnostics, arg, context.date_pattern_context())?;
+        let pattern = expect_date_pattern_with(diagnostics, arg, |value, kind| {
+            context.parse_date_pattern(value, kind)
+        })?;
         Ok(RevsetExpression::filter(
             RevsetFilterPredicate::CommitterDate(pattern),
         ))
     });
-    map.insert("empty", |_diagnostics, function, _context| {
+    map.insert("tz_mismatch", |_diagnostics, function, _context| {
         function.expect_no_arguments()?;
-        Ok(RevsetExpression::is_empty())
+        Ok(RevsetExpression::filter(
+            RevsetFilterPredicate::TimezoneMismatch,
+        ))
+    });
+    map.insert("empty", |diagnostics, function, context| {
+        let ([], [files_opt_arg]) = function.expect_arguments()?;
+        if let Some(files_arg) = files_opt_arg {
+            let ctx = context.workspace.as_ref().ok_or_else(|| {
+                RevsetParseError::with_span(
+                    RevsetParseErrorKind::FsPathWithoutWorkspace,
+                    files_arg.span,
+                )
+            })?;
+            let files = expect_fileset_expression(diagnostics, files_arg, ctx.path_converter)?;
+            Ok(RevsetExpression::filter(RevsetFilterPredicate::File(files)).negated())
+        } else {
+            Ok(RevsetExpression::is_empty())
+        }
     });
     map.insert("files", |diagnostics, function, context| {
         let ctx = context.workspace.as_ref().ok_or_else(|| {
@@ -878,9 +1177,31 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
                 function.args_span, // TODO: better to use name_span?
             )
         })?;
+        let ([arg], [parents_opt_arg]) = function.expect_arguments()?;
+        let expr = expect_fileset_expression(diagnostics, arg, ctx.path_converter)?;
+        let first_parent_only = parents_opt_arg
+            .map(|node| expect_first_parent_only(diagnostics, node))
+            .transpose()?
+            .unwrap_or(false);
+        let predicate = if first_parent_only {
+            RevsetFilterPredicate::FileFromFirstParent(expr)
+        } else {
+            RevsetFilterPredicate::File(expr)
+        };
+        Ok(RevsetExpression::filter(predicate))
+    });
+    map.insert("follow", |diagnostics, function, context| {
+        let ctx = context.workspace.as_ref().ok_or_else(|| {
+            RevsetParseError::with_span(
+                RevsetParseErrorKind::FsPathWithoutWorkspace,
+                function.args_span,
+            )
+        })?;
         let [arg] = function.expect_exact_arguments()?;
         let expr = expect_fileset_expression(diagnostics, arg, ctx.path_converter)?;
-        Ok(RevsetExpression::filter(RevsetFilterPredicate::File(expr)))
+        reject_unresolved_fileset_predicates(&expr, function, arg)?;
+        let predicate = RevsetFilterPredicate::FileFollowingRenames(expr);
+        Ok(RevsetExpression::filter(predicate))
     });
     map.insert("diff_contains", |diagnostics, function, context| {
         let ([text_arg], [files_opt_arg]) = function.expect_arguments()?;
@@ -892,7 +1213,9 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
                     files_arg.span,
                 )
             })?;
-            expect_fileset_expression(diagnostics, files_arg, ctx.path_converter)?
+            let files = expect_fileset_expression(diagnostics, files_arg, ctx.path_converter)?;
+            reject_unresolved_fileset_predicates(&files, function, files_arg)?;
+            files
         } else {
             // TODO: defaults to CLI path arguments?
             // https://github.com/jj-vcs/jj/issues/2933#issuecomment-1925870731
@@ -902,10 +1225,63 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
             RevsetFilterPredicate::DiffContains { text, files },
         ))
     });
+    map.insert("lines_changed", |diagnostics, function, context| {
+        let ([range_arg], [files_opt_arg]) = function.expect_arguments()?;
+        let range = expect_count_range(diagnostics, range_arg)?;
+        let files = if let Some(files_arg) = files_opt_arg {
+            let ctx = context.workspace.as_ref().ok_or_else(|| {
+                RevsetParseError::with_span(
+                    RevsetParseErrorKind::FsPathWithoutWorkspace,
+                    files_arg.span,
+                )
+            })?;
+            let files = expect_fileset_expression(diagnostics, files_arg, ctx.path_converter)?;
+            reject_unresolved_fileset_predicates(&files, function, files_arg)?;
+            files
+        } else {
+            FilesetExpression::all()
+        };
+        Ok(RevsetExpression::filter(
+            RevsetFilterPredicate::LinesChanged { range, files },
+        ))
+    });
     map.insert("conflicts", |_diagnostics, function, _context| {
         function.expect_no_arguments()?;
         Ok(RevsetExpression::filter(RevsetFilterPredicate::HasConflict))
     });
+    map.insert("signed", |diagnostics, function, context| {
+        let ([], [opt_arg]) = function.expect_arguments()?;
+        let signed = RevsetExpression::filter(RevsetFilterPredicate::Signed);
+        if let Some(arg) = opt_arg {
+            // The signature time isn't recorded separately from the signature
+            // blob. Commits are signed when written, so use the committer date.
+            let pattern = expect_date_pattern_with(diagnostics, arg, |value, kind| {
+                context.parse_date_pattern(value, kind)
+            })?;
+            let date = RevsetExpression::filter(RevsetFilterPredicate::CommitterDate(pattern));
+            Ok(signed.intersection(&date))
+        } else {
+            Ok(signed)
+        }
+    });
+    map.insert("pushed", |_diagnostics, function, _context| {
+        function.expect_no_arguments()?;
+        Ok(RevsetExpression::filter(RevsetFilterPredicate::Pushed))
+    });
+    map.insert("commit_id", |diagnostics, function, _context| {
+        let [arg] = function.expect_exact_arguments()?;
+        match expect_id_prefix_pattern(diagnostics, arg)? {
+            (prefix, false) => Ok(RevsetExpression::commit_id_prefix(prefix)),
+            (prefix, true) => Ok(RevsetExpression::commit_id_prefix_matches(prefix)),
+        }
+    });
+    map.insert("change_id", |diagnostics, function, _context| {
+        let [arg] = function.expect_exact_arguments()?;
+        match expect_id_prefix_pattern(diagnostics, arg)? {
+            (prefix, false) => Ok(RevsetExpression::change_id_prefix(prefix)),
+            (prefix, true) => Ok(RevsetExpression::change_id_prefix_matches(prefix)),
+        }
+    });
     map.insert("present", |diagnostics, function, context| {
         let [arg] = function.expect_exact_arguments()?;
         let expression = lower_expression(diagnostics, arg, context)?;
@@ -935,6 +1311,22 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
     map
 });
 
+/// Rejects fileset functions which can only be evaluated by `files()`, such as
+/// `conflicts()`.
+fn reject_unresolved_fileset_predicates(
+    expression: &FilesetExpression,
+    function: &FunctionCallNode,
+    node: &ExpressionNode,
+) -> Result<(), RevsetParseError> {
+    if let Some(name) = expression.unresolved_predicate_name() {
+        return Err(RevsetParseError::expression(
+            format!("{name} can't be used in {}()", function.name),
+            node.span,
+        ));
+    }
+    Ok(())
+}
+
 /// Parses the given `node` as a fileset expression.
 pub fn expect_fileset_expression(
     diagnostics: &mut RevsetDiagnostics,
@@ -957,6 +1349,52 @@ pub fn expect_fileset_expression(
     })
 }
 
+/// Parses `parents:all` or `parents:first` argument. Returns true if only the
+/// first parent should be compared.
+fn expect_first_parent_only(
+    diagnostics: &mut RevsetDiagnostics,
+    node: &ExpressionNode,
+) -> Result<bool, RevsetParseError> {
+    revset_parser::expect_pattern_with(
+        diagnostics,
+        "parents mode",
+        node,
+        |_diagnostics, value, kind| match (kind, value) {
+            (Some("parents"), "all") => Ok(false),
+            (Some("parents"), "first") => Ok(true),
+            _ => Err("Expected `parents:all` or `parents:first`"),
+        },
+    )
+}
+
+/// Lowers one or more function arguments to the union of them.
+fn lower_union_of_arguments(
+    diagnostics: &mut RevsetDiagnostics,
+    function: &FunctionCallNode,
+    context: &LoweringContext,
+) -> Result<Rc<UserRevsetExpression>, RevsetParseError> {
+    let ([first_arg], rest_args) = function.expect_some_arguments()?;
+    let expressions: Vec<_> = iter::once(first_arg)
+        .chain(rest_args)
+        .map(|arg| lower_expression(diagnostics, arg, context))
+        .try_collect()?;
+    Ok(RevsetExpression::union_all(&expressions))
+}
+
+/// Parses all arguments of the `function` as string patterns, at least one of
+/// which must be specified.
+fn expect_string_pattern_set(
+    diagnostics: &mut RevsetDiagnostics,
+    function: &FunctionCallNode,
+) -> Result<StringPatternSet, RevsetParseError> {
+    let ([first_arg], rest_args) = function.expect_some_arguments()?;
+    let patterns = iter::once(first_arg)
+        .chain(rest_args)
+        .map(|arg| expect_string_pattern(diagnostics, arg))
+        .try_collect()?;
+    Ok(StringPatternSet::new(patterns))
+}
+
 pub fn expect_string_pattern(
     diagnostics: &mut RevsetDiagnostics,
     node: &ExpressionNode,
@@ -972,10 +1410,43 @@ pub fn expect_string_pattern(
     )
 }
 
+/// Parses `prefix` or `glob:"prefix*"` argument of id functions.
+///
+/// Returns the prefix and whether all matching ids should be selected.
+fn expect_id_prefix_pattern(
+    diagnostics: &mut RevsetDiagnostics,
+    node: &ExpressionNode,
+) -> Result<(String, bool), RevsetParseError> {
+    revset_parser::expect_pattern_with(
+        diagnostics,
+        "ID prefix",
+        node,
+        |_diagnostics, value, kind| match kind {
+            None => Ok((value.to_owned(), false)),
+            Some("glob") => value
+                .strip_suffix('*')
+                .filter(|prefix| !prefix.contains(['*', '?', '[', ']', '{', '}', '\\']))
+                .map(|prefix| (prefix.to_owned(), true))
+                .ok_or("Only prefix globs like `glob:\"abc*\"` are supported"),
+            Some(_) => Err("Only prefix globs like `glob:\"abc*\"` are supported"),
+        },
+    )
+}
+
 pub fn expect_date_pattern(
     diagnostics: &mut RevsetDiagnostics,
     node: &ExpressionNode,
     context: &DatePatternContext,
+) -> Result<DatePattern, RevsetParseError> {
+    expect_date_pattern_with(diagnostics, node, |value, kind| {
+        context.parse_relative(value, kind)
+    })
+}
+
+fn expect_date_pattern_with(
+    diagnostics: &mut RevsetDiagnostics,
+    node: &ExpressionNode,
+    parse: impl FnOnce(&str, &str) -> Result<DatePattern, DatePatternParseError>,
 ) -> Result<DatePattern, RevsetParseError> {
     revset_parser::expect_pattern_with(
         diagnostics,
@@ -983,13 +1454,82 @@ pub fn expect_date_pattern(
         node,
         |_diagnostics, value, kind| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
             match kind {
-                None => Err("Date pattern must specify 'after' or 'before'".into()),
-                Some(kind) => Ok(context.parse_relative(value, kind)?),
+                None => Err("Date pattern must specify 'after', 'before', or 'during'".into()),
+                Some(kind) => Ok(parse(value, kind)?),
             }
         },
     )
 }
 
+/// Parses `n`, `start..`, `..end`, `start..end`, or `..` as a range of counts.
+fn expect_count_range(
+    diagnostics: &mut RevsetDiagnostics,
+    node: &ExpressionNode,
+) -> Result<Range<u64>, RevsetParseError> {
+    expect_range_with(diagnostics, node, |diagnostics, node| {
+        revset_parser::expect_literal(diagnostics, "integer", node)
+    })
+}
+
+/// Parses a byte size such as `100`, `4KiB`, or `1M`.
+///
+/// The size is parsed by the same rules as [`HumanByteSize`] config values.
+pub fn expect_size_literal(
+    diagnostics: &mut RevsetDiagnostics,
+    node: &ExpressionNode,
+) -> Result<u64, RevsetParseError> {
+    revset_parser::expect_expression_with(diagnostics, node, |_diagnostics, node| {
+        let text: &str = match &node.kind {
+            ExpressionKind::Identifier(text) => text,
+            ExpressionKind::String(text) => text,
+            _ => {
+                return Err(RevsetParseError::expression(
+                    "Expected expression of type size",
+                    node.span,
+                ));
+            }
+        };
+        let size: HumanByteSize = text.parse().map_err(|err: &str| {
+            RevsetParseError::expression("Invalid size", node.span).with_source(err)
+        })?;
+        Ok(size.0)
+    })
+}
+
+/// Parses `n`, `start..`, `..end`, `start..end`, or `..` as a range of byte
+/// sizes. See [`expect_size_literal()`] for the syntax of the bounds.
+pub fn expect_size_range(
+    diagnostics: &mut RevsetDiagnostics,
+    node: &ExpressionNode,
+) -> Result<Range<u64>, RevsetParseError> {
+    expect_range_with(diagnostics, node, expect_size_literal)
+}
+
+fn expect_range_with(
+    diagnostics: &mut RevsetDiagnostics,
+    node: &ExpressionNode,
+    mut expect_bound: impl FnMut(
+        &mut RevsetDiagnostics,
+        &ExpressionNode,
+    ) -> Result<u64, RevsetParseError>,
+) -> Result<Range<u64>, RevsetParseError> {
+    revset_parser::expect_expression_with(diagnostics, node, |diagnostics, node| {
+        let mut expect_bound = |node: &ExpressionNode| expect_bound(diagnostics, node);
+        match &node.kind {
+            ExpressionKind::RangeAll => Ok(0..u64::MAX),
+            ExpressionKind::Unary(UnaryOp::RangePost, start) => Ok(expect_bound(start)?..u64::MAX),
+            ExpressionKind::Unary(UnaryOp::RangePre, end) => Ok(0..expect_bound(end)?),
+            ExpressionKind::Binary(BinaryOp::Range, start, end) => {
+                Ok(expect_bound(start)?..expect_bound(end)?)
+            }
+            _ => {
+                let bound = expect_bound(node)?;
+                Ok(bound..bound.saturating_add(1))
+            }
+        }
+    })
+}
+
 fn parse_remote_bookmarks_arguments(
     diagnostics: &mut RevsetDiagnostics,
     function: &FunctionCallNode,
@@ -1023,6 +1563,17 @@ fn lower_function_call(
     let function_map = &context.extensions.function_map;
     if let Some(func) = function_map.get(function.name) {
         func(diagnostics, function, context)
+    } else if let Some(&(_, new_name)) = RENAMED_FUNCTIONS
+        .iter()
+        .find(|(old_name, _)| *old_name == function.name)
+    {
+        Err(RevsetParseError::with_span(
+            RevsetParseErrorKind::RenamedFunction {
+                name: function.name.to_owned(),
+                new_name: new_name.to_owned(),
+            },
+            function.name_span,
+        ))
     } else {
         Err(RevsetParseError::with_span(
             RevsetParseErrorKind::NoSuchFunction {
@@ -1034,6 +1585,21 @@ fn lower_function_call(
     }
 }
 
+fn lower_symbol(
+    name: String,
+    span: pest::Span<'_>,
+    context: &LoweringContext,
+) -> Result<Rc<UserRevsetExpression>, RevsetParseError> {
+    if let Some(locations) = context.symbol_locations {
+        let location = SymbolLocation {
+            span: SourceSpan::new(span),
+            expansions: vec![],
+        };
+        locations.borrow_mut().push((name.clone(), location));
+    }
+    Ok(RevsetExpression::symbol(name))
+}
+
 /// Transforms the given AST `node` into expression that describes DAG
 /// operation. Function calls will be resolved at this stage.
 pub fn lower_expression(
@@ -1042,8 +1608,8 @@ pub fn lower_expression(
     context: &LoweringContext,
 ) -> Result<Rc<UserRevsetExpression>, RevsetParseError> {
     match &node.kind {
-        ExpressionKind::Identifier(name) => Ok(RevsetExpression::symbol((*name).to_owned())),
-        ExpressionKind::String(name) => Ok(RevsetExpression::symbol(name.to_owned())),
+        ExpressionKind::Identifier(name) => lower_symbol((*name).to_owned(), node.span, context),
+        ExpressionKind::String(name) => lower_symbol(name.to_owned(), node.span, context),
         ExpressionKind::StringPattern { .. } => Err(RevsetParseError::with_span(
             RevsetParseErrorKind::NotInfixOperator {
                 op: ":".to_owned(),
@@ -1071,6 +1637,23 @@ pub fn lower_expression(
         }
         ExpressionKind::Unary(op, arg_node) => {
             let arg = lower_expression(diagnostics, arg_node, context)?;
+            // Ancestors of a filter are likely to be all commits. Descendants
+            // are fine since `description(x)::` is a common idiom.
+            let range_op = match op {
+                UnaryOp::DagRangePre => Some("::"),
+                UnaryOp::RangePre => Some(".."),
+                _ => None,
+            };
+            if let Some(op_str) = range_op.filter(|_| is_filter_only(&arg)) {
+                diagnostics.add_warning(RevsetParseError::expression(
+                    format!(
+                        "Operator `{op_str}` is applied to a filter expression without a set, \
+                         which is evaluated against all commits. Consider intersecting the \
+                         filter with a set of revisions first"
+                    ),
+                    node.span,
+                ));
+            }
             match op {
                 UnaryOp::Negate => Ok(arg.negated()),
                 UnaryOp::DagRangePre => Ok(arg.ancestors()),
@@ -1108,24 +1691,61 @@ pub fn lower_expression(
                 modifier.name_span,
             ))
         }
+        ExpressionKind::Let(binding) => Err(RevsetParseError::expression(
+            "Variable binding isn't expanded",
+            binding.name_span,
+        )),
         ExpressionKind::AliasExpanded(id, subst) => {
+            if let AliasId::Function(name, _) = id {
+                if context.extensions.function_map.contains_key(name) {
+                    diagnostics.add_warning(RevsetParseError::expression(
+                        format!("Alias `{id}` shadows the built-in function `{name}()`"),
+                        node.span,
+                    ));
+                }
+            }
             let mut inner_diagnostics = RevsetDiagnostics::new();
+            let num_symbols = context
+                .symbol_locations
+                .map_or(0, |locations| locations.borrow().len());
             let expression = lower_expression(&mut inner_diagnostics, subst, context)
                 .map_err(|e| e.within_alias_expansion(*id, node.span))?;
             diagnostics.extend_with(inner_diagnostics, |diag| {
                 diag.within_alias_expansion(*id, node.span)
             });
+            if let Some(locations) = context.symbol_locations {
+                let locations = &mut locations.borrow_mut()[num_symbols..];
+                add_symbol_alias_expansion(locations, *id, node.span);
+            }
             Ok(expression)
         }
     }
 }
 
+/// Returns true if the `expression` consists only of filter predicates, which
+/// select commits from all commits.
+fn is_filter_only(expression: &UserRevsetExpression) -> bool {
+    match expression {
+        RevsetExpression::Filter(_) | RevsetExpression::AsFilter(_) => true,
+        RevsetExpression::NotIn(expression) | RevsetExpression::Present(expression) => {
+            is_filter_only(expression)
+        }
+        RevsetExpression::Union(expression1, expression2)
+        | RevsetExpression::Intersection(expression1, expression2)
+        | RevsetExpression::Difference(expression1, expression2) => {
+            is_filter_only(expression1) && is_filter_only(expression2)
+        }
+        _ => false,
+    }
+}
+
 pub fn parse(
     diagnostics: &mut RevsetDiagnostics,
     revset_str: &str,
     context: &RevsetParseContext,
 ) -> Result<Rc<UserRevsetExpression>, RevsetParseError> {
     let node = parse_program(revset_str)?;
+    let node = revset_parser::expand_let_bindings(diagnostics, node, &context.local_variables)?;
     let node =
         dsl_util::expand_aliases_with_locals(node, context.aliases_map, &context.local_variables)?;
     lower_expression(diagnostics, &node, &context.to_lowering_context())
@@ -1136,14 +1756,43 @@ pub fn parse_with_modifier(
     diagnostics: &mut RevsetDiagnostics,
     revset_str: &str,
     context: &RevsetParseContext,
+) -> Result<(Rc<UserRevsetExpression>, Option<RevsetModifier>), RevsetParseError> {
+    let lowering_context = context.to_lowering_context();
+    parse_with_lowering_context(diagnostics, revset_str, context, &lowering_context)
+}
+
+/// Like [`parse_with_modifier()`], but also records the locations of the
+/// symbols in the expression.
+pub fn parse_with_symbol_locations(
+    diagnostics: &mut RevsetDiagnostics,
+    revset_str: &str,
+    context: &RevsetParseContext,
+    symbol_locations: &mut SymbolLocations,
+) -> Result<(Rc<UserRevsetExpression>, Option<RevsetModifier>), RevsetParseError> {
+    let locations = RefCell::new(vec![]);
+    let lowering_context = LoweringContext {
+        symbol_locations: Some(&locations),
+        ..context.to_lowering_context()
+    };
+    let result = parse_with_lowering_context(diagnostics, revset_str, context, &lowering_context);
+    symbol_locations.locations.extend(locations.into_inner());
+    result
+}
+
+fn parse_with_lowering_context(
+    diagnostics: &mut RevsetDiagnostics,
+    revset_str: &str,
+    context: &RevsetParseContext,
+    lowering_context: &LoweringContext,
 ) -> Result<(Rc<UserRevsetExpression>, Option<RevsetModifier>), RevsetParseError> {
     let node = parse_program(revset_str)?;
+    let node = revset_parser::expand_let_bindings(diagnostics, node, &context.local_variables)?;
     let node =
         dsl_util::expand_aliases_with_locals(node, context.aliases_map, &context.local_variables)?;
-    revset_parser::expect_program_with(
+    let parsed = revset_parser::expect_program_with(
         diagnostics,
         &node,
-        |diagnostics, node| lower_expression(diagnostics, node, &context.to_lowering_context()),
+        |diagnostics, node| lower_expression(diagnostics, node, lowering_context),
         |_diagnostics, name, span| match name {
             "all" => Ok(RevsetModifier::All),
             _ => Err(RevsetParseError::with_span(
@@ -1152,173 +1801,306 @@ pub fn parse_with_modifier(
             )),
         },
     )
-    .map_err(|err| err.extend_function_candidates(context.aliases_map.function_names()))
+    .map_err(|err| err.extend_function_candidates(context.aliases_map.function_names()))?;
+    if let Some(locations) = lowering_context.symbol_locations {
+        // The outermost alias expansions were unwrapped by expect_program_with()
+        let mut expansions = vec![];
+        let mut node = &node;
+        while let ExpressionKind::AliasExpanded(id, subst) = &node.kind {
+            expansions.push((*id, node.span));
+            node = subst;
+        }
+        for (id, span) in expansions.into_iter().rev() {
+            add_symbol_alias_expansion(&mut locations.borrow_mut(), id, span);
+        }
+    }
+    Ok(parsed)
 }
 
-/// `Some` for rewritten expression, or `None` to reuse the original expression.
-type TransformedExpression<St> = Option<Rc<RevsetExpression<St>>>;
+/// Owned copy of [`pest::Span`].
+#[derive(Debug)]
+struct SourceSpan {
+    input: String,
+    start: usize,
+    end: usize,
+}
 
-/// Walks `expression` tree and applies `f` recursively from leaf nodes.
-fn transform_expression_bottom_up<St: ExpressionState>(
-    expression: &Rc<RevsetExpression<St>>,
-    mut f: impl FnMut(&Rc<RevsetExpression<St>>) -> TransformedExpression<St>,
-) -> TransformedExpression<St> {
-    try_transform_expression::<St, Infallible>(
-        expression,
-        |_| Ok(None),
-        |expression| Ok(f(expression)),
-    )
-    .unwrap()
+impl SourceSpan {
+    fn new(span: pest::Span<'_>) -> Self {
+        SourceSpan {
+            input: span.get_input().to_owned(),
+            start: span.start(),
+            end: span.end(),
+        }
+    }
+
+    fn as_span(&self) -> pest::Span<'_> {
+        pest::Span::new(&self.input, self.start, self.end).unwrap()
+    }
 }
 
-/// Walks `expression` tree and applies transformation recursively.
-///
-/// `pre` is the callback to rewrite subtree including children. It is
-/// invoked before visiting the child nodes. If returned `Some`, children
-/// won't be visited.
-///
-/// `post` is the callback to rewrite from leaf nodes. If returned `None`,
-/// the original expression node will be reused.
-///
-/// If no nodes rewritten, this function returns `None`.
-/// `std::iter::successors()` could be used if the transformation needs to be
-/// applied repeatedly until converged.
-fn try_transform_expression<St: ExpressionState, E>(
-    expression: &Rc<RevsetExpression<St>>,
-    mut pre: impl FnMut(&Rc<RevsetExpression<St>>) -> Result<TransformedExpression<St>, E>,
-    mut post: impl FnMut(&Rc<RevsetExpression<St>>) -> Result<TransformedExpression<St>, E>,
-) -> Result<TransformedExpression<St>, E> {
-    fn transform_child_rec<St: ExpressionState, E>(
-        expression: &Rc<RevsetExpression<St>>,
-        pre: &mut impl FnMut(&Rc<RevsetExpression<St>>) -> Result<TransformedExpression<St>, E>,
-        post: &mut impl FnMut(&Rc<RevsetExpression<St>>) -> Result<TransformedExpression<St>, E>,
+/// Location of a symbol, which may be in alias definitions.
+#[derive(Debug)]
+struct SymbolLocation {
+    span: SourceSpan,
+    /// Alias expansions the symbol was found within, innermost first.
+    expansions: Vec<(RevsetParseErrorKind, SourceSpan)>,
+}
+
+impl SymbolLocation {
+    fn into_diagnostic(self, message: &str) -> RevsetParseError {
+        let diag = RevsetParseError::expression(message, self.span.as_span());
+        self.expansions
+            .into_iter()
+            .fold(diag, |diag, (kind, span)| {
+                RevsetParseError::with_span(kind, span.as_span()).with_source(diag)
+            })
+    }
+}
+
+/// Records that the symbols at the `locations` were found within the alias
+/// `id` expanded at the `span`.
+fn add_symbol_alias_expansion(
+    locations: &mut [(String, SymbolLocation)],
+    id: AliasId<'_>,
+    span: pest::Span<'_>,
+) {
+    for (_, location) in locations {
+        let kind = revset_parser::alias_expansion_error_kind(id);
+        location.expansions.push((kind, SourceSpan::new(span)));
+    }
+}
+
+/// Locations of the symbols recorded by [`parse_with_symbol_locations()`].
+#[derive(Debug, Default)]
+pub struct SymbolLocations {
+    locations: Vec<(String, SymbolLocation)>,
+}
+
+impl SymbolLocations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Reports the `symbol_warnings` recorded while resolving the expression at
+/// the locations of the symbols they are keyed by.
+pub fn add_symbol_warnings(
+    diagnostics: &mut RevsetDiagnostics,
+    symbol_locations: SymbolLocations,
+    symbol_warnings: &HashMap<String, String>,
+) {
+    for (symbol, location) in symbol_locations.locations {
+        if let Some(message) = symbol_warnings.get(&symbol) {
+            diagnostics.add_warning(location.into_diagnostic(message));
+        }
+    }
+}
+
+impl<St: ExpressionState> RevsetExpression<St> {
+    /// Calls `f` for each direct child expression.
+    ///
+    /// Leaf nodes such as commit refs and filter predicates have no children.
+    /// Use this to walk the tree without matching every variant.
+    pub fn visit_children(&self, mut f: impl FnMut(&Rc<Self>)) {
+        match self {
+            RevsetExpression::None
+            | RevsetExpression::All
+            | RevsetExpression::VisibleHeads
+            | RevsetExpression::Root
+            | RevsetExpression::Commits(_)
+            | RevsetExpression::CommitRef(_)
+            | RevsetExpression::Filter(_) => {}
+            RevsetExpression::Ancestors { heads, .. } => f(heads),
+            RevsetExpression::Descendants { roots, .. } => f(roots),
+            RevsetExpression::Range { roots, heads, .. }
+            | RevsetExpression::DagRange { roots, heads } => {
+                f(roots);
+                f(heads);
+            }
+            RevsetExpression::Reachable { sources, domain } => {
+                f(sources);
+                f(domain);
+            }
+            RevsetExpression::Heads(candidates)
+            | RevsetExpression::Roots(candidates)
+            | RevsetExpression::ForkPoint(candidates)
+            | RevsetExpression::DuplicatesOf(candidates)
+            | RevsetExpression::Latest { candidates, .. }
+            | RevsetExpression::AsFilter(candidates)
+            | RevsetExpression::AtOperation { candidates, .. }
+            | RevsetExpression::WithinVisibility { candidates, .. }
+            | RevsetExpression::Present(candidates)
+            | RevsetExpression::NotIn(candidates) => f(candidates),
+            RevsetExpression::Coalesce(expression1, expression2)
+            | RevsetExpression::Union(expression1, expression2)
+            | RevsetExpression::Intersection(expression1, expression2)
+            | RevsetExpression::Difference(expression1, expression2) => {
+                f(expression1);
+                f(expression2);
+            }
+        }
+    }
+
+    /// Rebuilds this expression node with the direct children mapped by `f`.
+    ///
+    /// If `f` returns `None` for all children, this function returns `None`
+    /// so the original expression can be reused.
+    pub fn map_children(
+        &self,
+        mut f: impl FnMut(&Rc<Self>) -> TransformedExpression<St>,
+    ) -> TransformedExpression<St> {
+        self.try_map_children::<Infallible>(|child| Ok(f(child)))
+            .unwrap()
+    }
+
+    /// Rebuilds this expression node with the direct children mapped by `f`,
+    /// which may fail.
+    ///
+    /// See [`RevsetExpression::map_children()`] for details.
+    pub fn try_map_children<E>(
+        &self,
+        mut f: impl FnMut(&Rc<Self>) -> Result<TransformedExpression<St>, E>,
     ) -> Result<TransformedExpression<St>, E> {
-        Ok(match expression.as_ref() {
+        #[expect(clippy::type_complexity)]
+        fn map_pair<St: ExpressionState, E>(
+            (expression1, expression2): (&Rc<RevsetExpression<St>>, &Rc<RevsetExpression<St>>),
+            f: &mut impl FnMut(&Rc<RevsetExpression<St>>) -> Result<TransformedExpression<St>, E>,
+        ) -> Result<Option<(Rc<RevsetExpression<St>>, Rc<RevsetExpression<St>>)>, E> {
+            match (f(expression1)?, f(expression2)?) {
+                (Some(new_expression1), Some(new_expression2)) => {
+                    Ok(Some((new_expression1, new_expression2)))
+                }
+                (Some(new_expression1), None) => Ok(Some((new_expression1, expression2.clone()))),
+                (None, Some(new_expression2)) => Ok(Some((expression1.clone(), new_expression2))),
+                (None, None) => Ok(None),
+            }
+        }
+
+        Ok(match self {
             RevsetExpression::None => None,
             RevsetExpression::All => None,
             RevsetExpression::VisibleHeads => None,
             RevsetExpression::Root => None,
             RevsetExpression::Commits(_) => None,
             RevsetExpression::CommitRef(_) => None,
-            RevsetExpression::Ancestors { heads, generation } => transform_rec(heads, pre, post)?
-                .map(|heads| RevsetExpression::Ancestors {
+            RevsetExpression::Ancestors { heads, generation } => {
+                f(heads)?.map(|heads| RevsetExpression::Ancestors {
                     heads,
                     generation: generation.clone(),
-                }),
-            RevsetExpression::Descendants { roots, generation } => transform_rec(roots, pre, post)?
-                .map(|roots| RevsetExpression::Descendants {
+                })
+            }
+            RevsetExpression::Descendants { roots, generation } => {
+                f(roots)?.map(|roots| RevsetExpression::Descendants {
                     roots,
                     generation: generation.clone(),
-                }),
+                })
+            }
             RevsetExpression::Range {
                 roots,
                 heads,
                 generation,
-            } => transform_rec_pair((roots, heads), pre, post)?.map(|(roots, heads)| {
-                RevsetExpression::Range {
-                    roots,
-                    heads,
-                    generation: generation.clone(),
-                }
+            } => map_pair((roots, heads), &mut f)?.map(|(roots, heads)| RevsetExpression::Range {
+                roots,
+                heads,
+                generation: generation.clone(),
             }),
-            RevsetExpression::DagRange { roots, heads } => {
-                transform_rec_pair((roots, heads), pre, post)?
-                    .map(|(roots, heads)| RevsetExpression::DagRange { roots, heads })
-            }
-            RevsetExpression::Reachable { sources, domain } => {
-                transform_rec_pair((sources, domain), pre, post)?
-                    .map(|(sources, domain)| RevsetExpression::Reachable { sources, domain })
-            }
-            RevsetExpression::Heads(candidates) => {
-                transform_rec(candidates, pre, post)?.map(RevsetExpression::Heads)
-            }
-            RevsetExpression::Roots(candidates) => {
-                transform_rec(candidates, pre, post)?.map(RevsetExpression::Roots)
-            }
+            RevsetExpression::DagRange { roots, heads } => map_pair((roots, heads), &mut f)?
+                .map(|(roots, heads)| RevsetExpression::DagRange { roots, heads }),
+            RevsetExpression::Reachable { sources, domain } => map_pair((sources, domain), &mut f)?
+                .map(|(sources, domain)| RevsetExpression::Reachable { sources, domain }),
+            RevsetExpression::Heads(candidates) => f(candidates)?.map(RevsetExpression::Heads),
+            RevsetExpression::Roots(candidates) => f(candidates)?.map(RevsetExpression::Roots),
             RevsetExpression::ForkPoint(expression) => {
-                transform_rec(expression, pre, post)?.map(RevsetExpression::ForkPoint)
+                f(expression)?.map(RevsetExpression::ForkPoint)
+            }
+            RevsetExpression::DuplicatesOf(expression) => {
+                f(expression)?.map(RevsetExpression::DuplicatesOf)
             }
-            RevsetExpression::Latest { candidates, count } => transform_rec(candidates, pre, post)?
-                .map(|candidates| RevsetExpression::Latest {
+            RevsetExpression::Latest { candidates, count } => {
+                f(candidates)?.map(|candidates| RevsetExpression::Latest {
                     candidates,
                     count: *count,
-                }),
+                })
+            }
             RevsetExpression::Filter(_) => None,
             RevsetExpression::AsFilter(candidates) => {
-                transform_rec(candidates, pre, post)?.map(RevsetExpression::AsFilter)
+                f(candidates)?.map(RevsetExpression::AsFilter)
             }
             RevsetExpression::AtOperation {
                 operation,
                 candidates,
-            } => transform_rec(candidates, pre, post)?.map(|candidates| {
-                RevsetExpression::AtOperation {
-                    operation: operation.clone(),
-                    candidates,
-                }
+            } => f(candidates)?.map(|candidates| RevsetExpression::AtOperation {
+                operation: operation.clone(),
+                candidates,
             }),
             RevsetExpression::WithinVisibility {
                 candidates,
                 visible_heads,
-            } => transform_rec(candidates, pre, post)?.map(|candidates| {
-                RevsetExpression::WithinVisibility {
-                    candidates,
-                    visible_heads: visible_heads.clone(),
-                }
+            } => f(candidates)?.map(|candidates| RevsetExpression::WithinVisibility {
+                candidates,
+                visible_heads: visible_heads.clone(),
             }),
-            RevsetExpression::Coalesce(expression1, expression2) => transform_rec_pair(
-                (expression1, expression2),
-                pre,
-                post,
-            )?
-            .map(|(expression1, expression2)| RevsetExpression::Coalesce(expression1, expression2)),
-            RevsetExpression::Present(candidates) => {
-                transform_rec(candidates, pre, post)?.map(RevsetExpression::Present)
-            }
-            RevsetExpression::NotIn(complement) => {
-                transform_rec(complement, pre, post)?.map(RevsetExpression::NotIn)
+            RevsetExpression::Coalesce(expression1, expression2) => {
+                map_pair((expression1, expression2), &mut f)?.map(|(expression1, expression2)| {
+                    RevsetExpression::Coalesce(expression1, expression2)
+                })
             }
+            RevsetExpression::Present(candidates) => f(candidates)?.map(RevsetExpression::Present),
+            RevsetExpression::NotIn(complement) => f(complement)?.map(RevsetExpression::NotIn),
             RevsetExpression::Union(expression1, expression2) => {
-                transform_rec_pair((expression1, expression2), pre, post)?.map(
-                    |(expression1, expression2)| RevsetExpression::Union(expression1, expression2),
-                )
+                map_pair((expression1, expression2), &mut f)?.map(|(expression1, expression2)| {
+                    RevsetExpression::Union(expression1, expression2)
+                })
             }
             RevsetExpression::Intersection(expression1, expression2) => {
-                transform_rec_pair((expression1, expression2), pre, post)?.map(
-                    |(expression1, expression2)| {
-                        RevsetExpression::Intersection(expression1, expression2)
-                    },
-                )
+                map_pair((expression1, expression2), &mut f)?.map(|(expression1, expression2)| {
+                    RevsetExpression::Intersection(expression1, expression2)
+                })
             }
             RevsetExpression::Difference(expression1, expression2) => {
-                transform_rec_pair((expression1, expression2), pre, post)?.map(
-                    |(expression1, expression2)| {
-                        RevsetExpression::Difference(expression1, expression2)
-                    },
-                )
+                map_pair((expression1, expression2), &mut f)?.map(|(expression1, expression2)| {
+                    RevsetExpression::Difference(expression1, expression2)
+                })
             }
         }
         .map(Rc::new))
     }
+}
 
-    #[expect(clippy::type_complexity)]
-    fn transform_rec_pair<St: ExpressionState, E>(
-        (expression1, expression2): (&Rc<RevsetExpression<St>>, &Rc<RevsetExpression<St>>),
-        pre: &mut impl FnMut(&Rc<RevsetExpression<St>>) -> Result<TransformedExpression<St>, E>,
-        post: &mut impl FnMut(&Rc<RevsetExpression<St>>) -> Result<TransformedExpression<St>, E>,
-    ) -> Result<Option<(Rc<RevsetExpression<St>>, Rc<RevsetExpression<St>>)>, E> {
-        match (
-            transform_rec(expression1, pre, post)?,
-            transform_rec(expression2, pre, post)?,
-        ) {
-            (Some(new_expression1), Some(new_expression2)) => {
-                Ok(Some((new_expression1, new_expression2)))
-            }
-            (Some(new_expression1), None) => Ok(Some((new_expression1, expression2.clone()))),
-            (None, Some(new_expression2)) => Ok(Some((expression1.clone(), new_expression2))),
-            (None, None) => Ok(None),
-        }
-    }
+/// `Some` for rewritten expression, or `None` to reuse the original expression.
+pub type TransformedExpression<St> = Option<Rc<RevsetExpression<St>>>;
+
+/// Walks `expression` tree and applies `f` recursively from leaf nodes.
+pub fn transform_expression_bottom_up<St: ExpressionState>(
+    expression: &Rc<RevsetExpression<St>>,
+    mut f: impl FnMut(&Rc<RevsetExpression<St>>) -> TransformedExpression<St>,
+) -> TransformedExpression<St> {
+    try_transform_expression::<St, Infallible>(
+        expression,
+        |_| Ok(None),
+        |expression| Ok(f(expression)),
+    )
+    .unwrap()
+}
 
+/// Walks `expression` tree and applies transformation recursively.
+///
+/// `pre` is the callback to rewrite subtree including children. It is
+/// invoked before visiting the child nodes. If returned `Some`, children
+/// won't be visited.
+///
+/// `post` is the callback to rewrite from leaf nodes. If returned `None`,
+/// the original expression node will be reused.
+///
+/// If no nodes rewritten, this function returns `None`.
+/// `std::iter::successors()` could be used if the transformation needs to be
+/// applied repeatedly until converged.
+pub fn try_transform_expression<St: ExpressionState, E>(
+    expression: &Rc<RevsetExpression<St>>,
+    mut pre: impl FnMut(&Rc<RevsetExpression<St>>) -> Result<TransformedExpression<St>, E>,
+    mut post: impl FnMut(&Rc<RevsetExpression<St>>) -> Result<TransformedExpression<St>, E>,
+) -> Result<TransformedExpression<St>, E> {
     fn transform_rec<St: ExpressionState, E>(
         expression: &Rc<RevsetExpression<St>>,
         pre: &mut impl FnMut(&Rc<RevsetExpression<St>>) -> Result<TransformedExpression<St>, E>,
@@ -1327,7 +2109,9 @@ fn try_transform_expression<St: ExpressionState, E>(
         if let Some(new_expression) = pre(expression)? {
             return Ok(Some(new_expression));
         }
-        if let Some(new_expression) = transform_child_rec(expression, pre, post)? {
+        if let Some(new_expression) =
+            expression.try_map_children(|child| transform_rec(child, pre, post))?
+        {
             // must propagate new expression tree
             Ok(Some(post(&new_expression)?.unwrap_or(new_expression)))
         } else {
@@ -1432,6 +2216,10 @@ where
             let expression = folder.fold_expression(expression)?;
             RevsetExpression::ForkPoint(expression).into()
         }
+        RevsetExpression::DuplicatesOf(expression) => {
+            let expression = folder.fold_expression(expression)?;
+            RevsetExpression::DuplicatesOf(expression).into()
+        }
         RevsetExpression::Latest { candidates, count } => {
             let candidates = folder.fold_expression(candidates)?;
             let count = *count;
@@ -1709,7 +2497,8 @@ fn unfold_difference<St: ExpressionState>(
 }
 
 /// Transforms nested `ancestors()`/`parents()`/`descendants()`/`children()`
-/// like `h---`/`r+++`.
+/// like `h---`/`r+++`, and unions of them like `x-- | y--`, into a single
+/// generation-range query.
 fn fold_generation<St: ExpressionState>(
     expression: &Rc<RevsetExpression<St>>,
 ) -> TransformedExpression<St> {
@@ -1761,11 +2550,76 @@ fn fold_generation<St: ExpressionState>(
                 _ => None,
             }
         }
+        RevsetExpression::Union(expression1, expression2) => {
+            match (expression1.as_ref(), expression2.as_ref()) {
+                // h1- | h2- -> ancestors(h1 | h2, 1)
+                (
+                    RevsetExpression::Ancestors {
+                        heads: heads1,
+                        generation: generation1,
+                    },
+                    RevsetExpression::Ancestors {
+                        heads: heads2,
+                        generation: generation2,
+                    },
+                ) if generation1 == generation2 => Some(Rc::new(RevsetExpression::Ancestors {
+                    heads: heads1.union(heads2),
+                    generation: generation1.clone(),
+                })),
+                // r1+ | r2+ -> descendants(r1 | r2, 1)
+                (
+                    RevsetExpression::Descendants {
+                        roots: roots1,
+                        generation: generation1,
+                    },
+                    RevsetExpression::Descendants {
+                        roots: roots2,
+                        generation: generation2,
+                    },
+                ) if generation1 == generation2 => Some(Rc::new(RevsetExpression::Descendants {
+                    roots: roots1.union(roots2),
+                    generation: generation1.clone(),
+                })),
+                _ => None,
+            }
+        }
         // Range should have been unfolded to intersection of Ancestors.
         _ => None,
     })
 }
 
+/// Transforms `heads()` of ancestor-closed sets into `heads()` of the
+/// generating set, so the ancestors don't have to be enumerated.
+///
+/// For example, `heads(::x)` becomes `heads(x)`, and `heads(all())` becomes
+/// `visible_heads()`. Visible heads are never ancestors of each other, so
+/// `heads(visible_heads())` is also `visible_heads()`.
+fn fold_heads_of_ancestors<St: ExpressionState>(
+    expression: &Rc<RevsetExpression<St>>,
+) -> TransformedExpression<St> {
+    transform_expression_bottom_up(expression, |expression| match expression.as_ref() {
+        RevsetExpression::Heads(candidates) => match candidates.as_ref() {
+            // heads(all()) -> visible_heads()
+            RevsetExpression::All | RevsetExpression::VisibleHeads => {
+                Some(RevsetExpression::visible_heads())
+            }
+            // heads(ancestors(x, 0..n)) -> heads(x)
+            RevsetExpression::Ancestors { heads, generation }
+                if generation.start == 0 && !generation.is_empty() =>
+            {
+                match heads.as_ref() {
+                    RevsetExpression::All | RevsetExpression::VisibleHeads => {
+                        Some(RevsetExpression::visible_heads())
+                    }
+                    _ => Some(heads.heads()),
+                }
+            }
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
 /// Rewrites the given `expression` tree to reduce evaluation cost. Returns new
 /// tree.
 pub fn optimize<St: ExpressionState>(
@@ -1774,6 +2628,7 @@ pub fn optimize<St: ExpressionState>(
     let expression = unfold_difference(&expression).unwrap_or(expression);
     let expression = fold_redundant_expression(&expression).unwrap_or(expression);
     let expression = fold_generation(&expression).unwrap_or(expression);
+    let expression = fold_heads_of_ancestors(&expression).unwrap_or(expression);
     let expression = internalize_filter(&expression).unwrap_or(expression);
     let expression = fold_difference(&expression).unwrap_or(expression);
     fold_not_in_ancestors(&expression).unwrap_or(expression)
@@ -1792,6 +2647,50 @@ pub fn walk_revs<'index>(
         .evaluate(repo)
 }
 
+/// Returns the commits in `commit_ids` which are not ancestors of other
+/// commits in `commit_ids`, in the order of [`Revset::iter()`].
+pub fn compute_heads(
+    repo: &dyn Repo,
+    commit_ids: &[CommitId],
+) -> Result<Vec<CommitId>, RevsetEvaluationError> {
+    ResolvedRevsetExpression::commits(commit_ids.to_vec())
+        .heads()
+        .evaluate(repo)?
+        .iter()
+        .try_collect()
+}
+
+/// Returns the commits in `commit_ids` which are not descendants of other
+/// commits in `commit_ids`, in the order of [`Revset::iter()`].
+pub fn compute_roots(
+    repo: &dyn Repo,
+    commit_ids: &[CommitId],
+) -> Result<Vec<CommitId>, RevsetEvaluationError> {
+    ResolvedRevsetExpression::commits(commit_ids.to_vec())
+        .roots()
+        .evaluate(repo)?
+        .iter()
+        .try_collect()
+}
+
+/// Returns the number of best common ancestors (or merge bases) of the
+/// commits in `set1` and `set2`.
+///
+/// For example, the count is 2 for criss-cross merges.
+pub fn common_ancestor_count(
+    repo: &dyn Repo,
+    set1: &[CommitId],
+    set2: &[CommitId],
+) -> Result<usize, RevsetEvaluationError> {
+    let ancestors1 = ResolvedRevsetExpression::commits(set1.to_vec()).ancestors();
+    let ancestors2 = ResolvedRevsetExpression::commits(set2.to_vec()).ancestors();
+    let revset = ancestors1
+        .intersection(&ancestors2)
+        .heads()
+        .evaluate(repo)?;
+    revset.iter().process_results(|iter| iter.count())
+}
+
 fn reload_repo_at_operation(
     repo: &dyn Repo,
     op_str: &str,
@@ -1818,6 +2717,46 @@ fn resolve_remote_bookmark(repo: &dyn Repo, symbol: RemoteRefSymbol<'_>) -> Opti
         .then(|| target.added_ids().cloned().collect())
 }
 
+/// Looks up the remote tag `symbol`.
+///
+/// Git doesn't namespace tags by remote, so only the special "git" remote,
+/// which mirrors `refs/tags/*` of the backing Git repository, has tags.
+fn resolve_remote_tag(repo: &dyn Repo, symbol: RemoteRefSymbol<'_>) -> Option<Vec<CommitId>> {
+    if !crate::git::is_special_git_remote(symbol.remote) {
+        return None;
+    }
+    let git_ref_name = GitRefNameBuf::from(format!("refs/tags/{}", symbol.name.as_str()));
+    let target = repo.view().get_git_ref(&git_ref_name);
+    target
+        .is_present()
+        .then(|| target.added_ids().cloned().collect())
+}
+
+/// Resolves `name@remote` to the remote bookmark, or to the remote tag if no
+/// such bookmark exists.
+fn resolve_remote_symbol(
+    repo: &dyn Repo,
+    symbol: RemoteRefSymbol<'_>,
+    symbol_resolver: &dyn SymbolResolver,
+) -> Result<Vec<CommitId>, RevsetResolutionError> {
+    match (
+        resolve_remote_bookmark(repo, symbol),
+        resolve_remote_tag(repo, symbol),
+    ) {
+        (Some(bookmark_ids), Some(tag_ids)) if bookmark_ids != tag_ids => {
+            Err(RevsetResolutionError::AmbiguousRemoteRef {
+                name: symbol.to_string(),
+            })
+        }
+        (Some(ids), _) | (None, Some(ids)) => Ok(ids),
+        (None, None) => Err(make_no_such_symbol_error(
+            repo,
+            symbol.to_string(),
+            symbol_resolver.suggestion_distance(),
+        )),
+    }
+}
+
 fn all_formatted_bookmark_symbols(
     repo: &dyn Repo,
     include_synced_remotes: bool,
@@ -1841,13 +2780,72 @@ fn all_formatted_bookmark_symbols(
     })
 }
 
-fn make_no_such_symbol_error(repo: &dyn Repo, name: String) -> RevsetResolutionError {
-    // TODO: include tags?
-    let bookmark_names = all_formatted_bookmark_symbols(repo, name.contains('@'));
-    let candidates = collect_similar(&name, bookmark_names);
+/// Maximum number of symbols suggested for an unknown symbol.
+const MAX_SYMBOL_SUGGESTIONS: usize = 10;
+
+fn make_no_such_symbol_error(
+    repo: &dyn Repo,
+    name: String,
+    max_distance: Option<usize>,
+) -> RevsetResolutionError {
+    let view = repo.view();
+    let bookmark_symbols = all_formatted_bookmark_symbols(repo, name.contains('@'))
+        .map(|symbol| (symbol.clone(), symbol));
+    let tag_symbols = view.tags().keys().map(|name| {
+        let symbol = name.as_symbol().to_string();
+        (symbol.clone(), symbol)
+    });
+    // Compare with the workspace name so "name@" is suggested for "name".
+    let workspace_symbols = view.wc_commit_ids().keys().map(|name| {
+        let symbol = name.as_symbol().to_string();
+        (symbol.clone(), format!("{symbol}@"))
+    });
+    // Git refs are mostly mirrored by bookmarks and tags, so they are only
+    // suggested if the name looks like a ref path.
+    let git_ref_symbols = view
+        .git_refs()
+        .keys()
+        .filter(|_| name.contains('/'))
+        .map(|name| {
+            let name = name.as_str();
+            let symbol = format_symbol(name.strip_prefix("refs/").unwrap_or(name));
+            (symbol.clone(), symbol)
+        });
+    let candidates = collect_similar_symbols(
+        &name,
+        itertools::chain!(
+            bookmark_symbols,
+            tag_symbols,
+            workspace_symbols,
+            git_ref_symbols
+        ),
+        max_distance,
+    );
     RevsetResolutionError::NoSuchRevision { name, candidates }
 }
 
+/// Picks symbols whose keys are similar to the `name`, closest first.
+///
+/// Candidates are `(key, symbol)` pairs, where the key is compared with the
+/// `name`. If `max_distance` is specified, candidates further than that edit
+/// distance are excluded.
+fn collect_similar_symbols(
+    name: &str,
+    candidates: impl IntoIterator<Item = (String, String)>,
+    max_distance: Option<usize>,
+) -> Vec<String> {
+    candidates
+        .into_iter()
+        .filter(|(key, _)| is_similar(name, key))
+        .map(|(key, symbol)| (strsim::levenshtein(name, &key), symbol))
+        .filter(|(distance, _)| max_distance.is_none_or(|max| *distance <= max))
+        .sorted_unstable()
+        .dedup()
+        .take(MAX_SYMBOL_SUGGESTIONS)
+        .map(|(_, symbol)| symbol)
+        .collect()
+}
+
 pub trait SymbolResolver {
     /// Looks up `symbol` in the given `repo`.
     fn resolve_symbol(
@@ -1855,6 +2853,56 @@ pub trait SymbolResolver {
         repo: &dyn Repo,
         symbol: &str,
     ) -> Result<Vec<CommitId>, RevsetResolutionError>;
+
+    /// Looks up commit id `prefix` in the given `repo`. Bookmarks, tags, and
+    /// change ids aren't considered.
+    fn resolve_commit_id_prefix(
+        &self,
+        repo: &dyn Repo,
+        prefix: &str,
+    ) -> Result<Vec<CommitId>, RevsetResolutionError> {
+        let resolver = CommitPrefixResolver {
+            context_repo: repo,
+            context: None,
+        };
+        resolve_id_prefix(&resolver, repo, prefix)
+    }
+
+    /// Looks up change id `prefix` in the given `repo`. Bookmarks, tags, and
+    /// commit ids aren't considered.
+    fn resolve_change_id_prefix(
+        &self,
+        repo: &dyn Repo,
+        prefix: &str,
+    ) -> Result<Vec<CommitId>, RevsetResolutionError> {
+        let resolver = ChangePrefixResolver {
+            context_repo: repo,
+            context: None,
+        };
+        resolve_id_prefix(&resolver, repo, prefix)
+    }
+
+    /// Maximum edit distance of the symbols suggested for an unknown symbol.
+    /// `None` means no limit.
+    fn suggestion_distance(&self) -> Option<usize> {
+        None
+    }
+}
+
+fn resolve_id_prefix(
+    resolver: &dyn PartialSymbolResolver,
+    repo: &dyn Repo,
+    prefix: &str,
+) -> Result<Vec<CommitId>, RevsetResolutionError> {
+    if prefix.is_empty() {
+        return Err(RevsetResolutionError::EmptyString);
+    }
+    resolver
+        .resolve_symbol(repo, prefix)?
+        .ok_or_else(|| RevsetResolutionError::NoSuchRevision {
+            name: format_symbol(prefix),
+            candidates: Default::default(),
+        })
 }
 
 /// Fails on any attempt to resolve a symbol.
@@ -1937,8 +2985,121 @@ impl PartialSymbolResolver for GitRefResolver {
     }
 }
 
-const DEFAULT_RESOLVERS: &[&'static dyn PartialSymbolResolver] =
-    &[&TagResolver, &BookmarkResolver, &GitRefResolver];
+/// Resolves `@<workspace>` symbol to the working-copy commit of the
+/// workspace.
+struct WorkingCopyResolver;
+
+impl PartialSymbolResolver for WorkingCopyResolver {
+    fn resolve_symbol(
+        &self,
+        repo: &dyn Repo,
+        symbol: &str,
+    ) -> Result<Option<Vec<CommitId>>, RevsetResolutionError> {
+        let Some(name) = symbol.strip_prefix('@').filter(|name| !name.is_empty()) else {
+            return Ok(None);
+        };
+        Ok(repo
+            .view()
+            .get_wc_commit_id(name.as_ref())
+            .map(|id| vec![id.clone()]))
+    }
+}
+
+const DEFAULT_RESOLVERS: &[&'static dyn PartialSymbolResolver] = &[
+    &TagResolver,
+    &BookmarkResolver,
+    &GitRefResolver,
+    &WorkingCopyResolver,
+];
+
+/// Maximum number of candidates listed for an ambiguous id prefix.
+const MAX_AMBIGUOUS_PREFIX_CANDIDATES: usize = 10;
+
+/// Hex digits of commit ids in ascending order.
+const COMMIT_ID_DIGITS: &str = "0123456789abcdef";
+/// Reverse hex digits of change ids in ascending order.
+const CHANGE_ID_DIGITS: &str = "zyxwvutsrqponmlk";
+
+/// Finds the shortest unambiguous extensions of the ambiguous `prefix`, and
+/// the values they resolve to.
+///
+/// The prefix is extended by the `digits` in order, so the results are sorted
+/// by prefix. At most `limit` results are returned.
+fn find_unambiguous_prefixes<T>(
+    prefix: &str,
+    digits: &str,
+    resolve: &dyn Fn(&str) -> PrefixResolution<T>,
+    limit: usize,
+) -> Vec<(String, T)> {
+    fn visit<T>(
+        prefix: &str,
+        digits: &str,
+        resolve: &dyn Fn(&str) -> PrefixResolution<T>,
+        limit: usize,
+        found: &mut Vec<(String, T)>,
+    ) {
+        for digit in digits.chars() {
+            if found.len() >= limit {
+                return;
+            }
+            let extended = format!("{prefix}{digit}");
+            match resolve(&extended) {
+                PrefixResolution::AmbiguousMatch => visit(&extended, digits, resolve, limit, found),
+                PrefixResolution::SingleMatch(value) => found.push((extended, value)),
+                PrefixResolution::NoMatch => {}
+            }
+        }
+    }
+
+    let mut found = vec![];
+    visit(prefix, digits, resolve, limit, &mut found);
+    found
+}
+
+/// Resolves `prefix` to all matching values, which may be ambiguous.
+fn resolve_all_prefix_matches<T>(
+    prefix: &str,
+    digits: &str,
+    resolve: &dyn Fn(&str) -> PrefixResolution<T>,
+) -> Result<Vec<T>, RevsetResolutionError> {
+    if prefix.is_empty() {
+        return Err(RevsetResolutionError::EmptyString);
+    }
+    match resolve(prefix) {
+        PrefixResolution::AmbiguousMatch => {
+            let found = find_unambiguous_prefixes(prefix, digits, resolve, usize::MAX);
+            Ok(found.into_iter().map(|(_, value)| value).collect())
+        }
+        PrefixResolution::SingleMatch(value) => Ok(vec![value]),
+        PrefixResolution::NoMatch => Ok(vec![]),
+    }
+}
+
+fn resolve_commit_id_prefix_matches(
+    repo: &dyn Repo,
+    prefix: &str,
+) -> Result<Vec<CommitId>, RevsetResolutionError> {
+    resolve_all_prefix_matches(prefix, COMMIT_ID_DIGITS, &|p| {
+        HexPrefix::new(p).map_or(PrefixResolution::NoMatch, |prefix| {
+            repo.index().resolve_commit_id_prefix(&prefix)
+        })
+    })
+}
+
+fn resolve_change_id_prefix_matches(
+    repo: &dyn Repo,
+    prefix: &str,
+) -> Result<Vec<CommitId>, RevsetResolutionError> {
+    let matches = resolve_all_prefix_matches(prefix, CHANGE_ID_DIGITS, &|p| {
+        to_forward_hex(p)
+            .as_deref()
+            .and_then(HexPrefix::new)
+            .map_or(PrefixResolution::NoMatch, |prefix| {
+                repo.resolve_change_id_prefix(&prefix)
+            })
+    })?;
+    Ok(matches.into_iter().flatten().collect())
+}
 
 struct CommitPrefixResolver<'a> {
     context_repo: &'a dyn Repo,
@@ -1959,9 +3120,26 @@ impl PartialSymbolResolver for CommitPrefixResolver<'_> {
                 .map_err(|err| RevsetResolutionError::Other(err.into()))?
                 .unwrap_or(IdPrefixIndex::empty());
             match index.resolve_commit_prefix(repo, &prefix) {
-                PrefixResolution::AmbiguousMatch => Err(
-                    RevsetResolutionError::AmbiguousCommitIdPrefix(symbol.to_owned()),
-                ),
+                PrefixResolution::AmbiguousMatch => {
+                    let resolve = |p: &str| {
+                        HexPrefix::new(p).map_or(PrefixResolution::NoMatch, |prefix| {
+                            index.resolve_commit_prefix(repo, &prefix)
+                        })
+                    };
+                    let candidates = find_unambiguous_prefixes(
+                        symbol,
+                        COMMIT_ID_DIGITS,
+                        &resolve,
+                        MAX_AMBIGUOUS_PREFIX_CANDIDATES,
+                    )
+                    .into_iter()
+                    .map(|(prefix, _)| prefix)
+                    .collect();
+                    Err(RevsetResolutionError::AmbiguousCommitIdPrefix {
+                        prefix: symbol.to_owned(),
+                        candidates,
+                    })
+                }
                 PrefixResolution::SingleMatch(id) => Ok(Some(vec![id])),
                 PrefixResolution::NoMatch => Ok(None),
             }
@@ -1990,9 +3168,29 @@ impl PartialSymbolResolver for ChangePrefixResolver<'_> {
                 .map_err(|err| RevsetResolutionError::Other(err.into()))?
                 .unwrap_or(IdPrefixIndex::empty());
             match index.resolve_change_prefix(repo, &prefix) {
-                PrefixResolution::AmbiguousMatch => Err(
-                    RevsetResolutionError::AmbiguousChangeIdPrefix(symbol.to_owned()),
-                ),
+                PrefixResolution::AmbiguousMatch => {
+                    let resolve = |p: &str| {
+                        to_forward_hex(p)
+                            .as_deref()
+                            .and_then(HexPrefix::new)
+                            .map_or(PrefixResolution::NoMatch, |prefix| {
+                                index.resolve_change_prefix(repo, &prefix)
+                            })
+                    };
+                    let candidates = find_unambiguous_prefixes(
+                        symbol,
+                        CHANGE_ID_DIGITS,
+                        &resolve,
+                        MAX_AMBIGUOUS_PREFIX_CANDIDATES,
+                    )
+                    .into_iter()
+                    .map(|(prefix, _)| prefix)
+                    .collect();
+                    Err(RevsetResolutionError::AmbiguousChangeIdPrefix {
+                        prefix: symbol.to_owned(),
+                        candidates,
+                    })
+                }
                 PrefixResolution::SingleMatch(ids) => Ok(Some(ids)),
                 PrefixResolution::NoMatch => Ok(None),
             }
@@ -2002,12 +3200,31 @@ impl PartialSymbolResolver for ChangePrefixResolver<'_> {
     }
 }
 
+/// When the resolvers of a [`SymbolResolverExtension`] are invoked relative to
+/// the built-in resolvers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ResolutionStage {
+    /// Before the built-in resolvers. The extension can shadow bookmarks,
+    /// tags, and commit/change IDs.
+    BeforeBuiltin,
+    /// After the built-in resolvers, as a fallback for unresolved symbols.
+    #[default]
+    AfterBuiltin,
+}
+
 /// An extension of the DefaultSymbolResolver.
 ///
-/// Each PartialSymbolResolver will be invoked in order, its result used if one
-/// is provided. Native resolvers are always invoked first. In the future, we
-/// may provide a way for extensions to override native resolvers like tags and
-/// bookmarks.
+/// Each PartialSymbolResolver will be invoked in order, and the first resolver
+/// that returns `Ok(Some(_))` wins. Resolution stops at the first error. The
+/// resolvers of extensions in [`ResolutionStage::BeforeBuiltin`] are invoked
+/// before the native resolvers, and the others are invoked after them.
+///
+/// Within the same stage, extensions of higher [`priority()`] are invoked
+/// first, and extensions of the same priority are invoked in registration
+/// order. If extensions of the same priority resolve a symbol to different
+/// revisions, resolution fails.
+///
+/// [`priority()`]: SymbolResolverExtension::priority
 pub trait SymbolResolverExtension {
     /// PartialSymbolResolvers can initialize some global data by using the
     /// `context_repo`, but the `context_repo` may point to a different
@@ -2017,6 +3234,44 @@ pub trait SymbolResolverExtension {
         &self,
         context_repo: &'a dyn Repo,
     ) -> Vec<Box<dyn PartialSymbolResolver + 'a>>;
+
+    /// Returns when the resolvers should be invoked.
+    fn resolution_stage(&self) -> ResolutionStage {
+        ResolutionStage::AfterBuiltin
+    }
+
+    /// Returns the prefixes of symbols the resolvers can resolve, e.g.
+    /// `["JIRA-"]`. The resolvers are invoked only for symbols starting with
+    /// one of the prefixes. If empty, they are invoked for any symbol.
+    fn resolves_prefixes(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Returns the priority of the resolvers within their stage. Higher
+    /// priority resolvers are invoked first.
+    fn priority(&self) -> i32 {
+        0
+    }
+}
+
+/// Partial symbol resolver instantiated from a [`SymbolResolverExtension`].
+struct ExtensionResolver<'a> {
+    resolver: Box<dyn PartialSymbolResolver + 'a>,
+    stage: ResolutionStage,
+    prefixes: Vec<String>,
+    priority: i32,
+    /// Index of the extension which instantiated this resolver.
+    extension_index: usize,
+}
+
+impl ExtensionResolver<'_> {
+    fn accepts(&self, symbol: &str) -> bool {
+        self.prefixes.is_empty()
+            || self
+                .prefixes
+                .iter()
+                .any(|prefix| symbol.starts_with(prefix.as_str()))
+    }
 }
 
 /// Resolves bookmarks, remote bookmarks, tags, git refs, and full and
@@ -2024,7 +3279,11 @@ pub trait SymbolResolverExtension {
 pub struct DefaultSymbolResolver<'a> {
     commit_id_resolver: CommitPrefixResolver<'a>,
     change_id_resolver: ChangePrefixResolver<'a>,
-    extensions: Vec<Box<dyn PartialSymbolResolver + 'a>>,
+    extensions: Vec<ExtensionResolver<'a>>,
+    suggestion_distance: Option<usize>,
+    case_insensitive_refs: bool,
+    /// Warning messages keyed by the symbol they are about.
+    symbol_warnings: RefCell<HashMap<String, String>>,
 }
 
 impl<'a> DefaultSymbolResolver<'a> {
@@ -2034,6 +3293,27 @@ impl<'a> DefaultSymbolResolver<'a> {
         context_repo: &'a dyn Repo,
         extensions: &[impl AsRef<dyn SymbolResolverExtension>],
     ) -> Self {
+        let mut extensions = extensions
+            .iter()
+            .enumerate()
+            .flat_map(|(extension_index, ext)| {
+                let ext = ext.as_ref();
+                let stage = ext.resolution_stage();
+                let prefixes = ext.resolves_prefixes();
+                let priority = ext.priority();
+                ext.new_resolvers(context_repo)
+                    .into_iter()
+                    .map(move |resolver| ExtensionResolver {
+                        resolver,
+                        stage,
+                        prefixes: prefixes.clone(),
+                        priority,
+                        extension_index,
+                    })
+            })
+            .collect_vec();
+        // Stable sort preserves the registration order within the same priority.
+        extensions.sort_by_key(|ext| Reverse(ext.priority));
         DefaultSymbolResolver {
             commit_id_resolver: CommitPrefixResolver {
                 context_repo,
@@ -2043,10 +3323,10 @@ impl<'a> DefaultSymbolResolver<'a> {
                 context_repo,
                 context: None,
             },
-            extensions: extensions
-                .iter()
-                .flat_map(|ext| ext.as_ref().new_resolvers(context_repo))
-                .collect(),
+            extensions,
+            suggestion_distance: None,
+            case_insensitive_refs: false,
+            symbol_warnings: RefCell::new(HashMap::new()),
         }
     }
 
@@ -2056,14 +3336,131 @@ impl<'a> DefaultSymbolResolver<'a> {
         self
     }
 
-    fn partial_resolvers(&self) -> impl Iterator<Item = &(dyn PartialSymbolResolver + 'a)> {
-        let prefix_resolvers: [&dyn PartialSymbolResolver; 2] =
-            [&self.commit_id_resolver, &self.change_id_resolver];
-        itertools::chain!(
-            DEFAULT_RESOLVERS.iter().copied(),
-            prefix_resolvers,
-            self.extensions.iter().map(|e| e.as_ref())
-        )
+    /// Limits the symbols suggested for an unknown symbol to the ones within
+    /// the given edit distance.
+    pub fn with_suggestion_distance(mut self, distance: Option<usize>) -> Self {
+        self.suggestion_distance = distance;
+        self
+    }
+
+    /// Falls back to case-insensitive match of tag and bookmark names if a
+    /// symbol can't be resolved otherwise. A warning is recorded for each
+    /// symbol resolved in this way.
+    pub fn with_case_insensitive_refs(mut self, enabled: bool) -> Self {
+        self.case_insensitive_refs = enabled;
+        self
+    }
+
+    /// Looks up commits by the full change id `symbol` in reverse hex.
+    ///
+    /// Unlike `resolve_symbol()`, the `symbol` isn't interpreted as a prefix,
+    /// and the disambiguation index isn't loaded.
+    pub fn resolve_change_id_exact(
+        &self,
+        repo: &dyn Repo,
+        symbol: &str,
+    ) -> Result<Vec<CommitId>, RevsetResolutionError> {
+        let change_id = to_forward_hex(symbol)
+            .and_then(|hex| ChangeId::try_from_hex(&hex).ok())
+            .filter(|id| id.as_bytes().len() == repo.store().change_id_length());
+        change_id
+            .and_then(|id| repo.resolve_change_id(&id))
+            .ok_or_else(|| RevsetResolutionError::NoSuchRevision {
+                name: symbol.to_owned(),
+                candidates: vec![],
+            })
+    }
+
+    /// Takes the warnings recorded while resolving symbols, keyed by symbol.
+    ///
+    /// Use [`add_symbol_warnings()`] to report them at the symbol locations.
+    pub fn take_symbol_warnings(&self) -> HashMap<String, String> {
+        self.symbol_warnings.take()
+    }
+
+    /// Looks up tag or bookmark whose name matches `symbol`
+    /// case-insensitively. Fails if there are multiple such names.
+    fn resolve_ref_case_insensitively(
+        &self,
+        repo: &dyn Repo,
+        symbol: &str,
+    ) -> Result<Option<Vec<CommitId>>, RevsetResolutionError> {
+        let view = repo.view();
+        let tag_names = view.tags().keys().map(|name| name.as_str());
+        let bookmark_names = view.local_bookmarks().map(|(name, _)| name.as_str());
+        let names = itertools::chain(tag_names, bookmark_names)
+            .filter(|name| name.eq_ignore_ascii_case(symbol))
+            .sorted_unstable()
+            .dedup()
+            .collect_vec();
+        match names[..] {
+            [] => Ok(None),
+            [name] => {
+                let resolvers: [&dyn PartialSymbolResolver; 2] = [&TagResolver, &BookmarkResolver];
+                for resolver in resolvers {
+                    if let Some(ids) = resolver.resolve_symbol(repo, name)? {
+                        let message = format!(
+                            "Resolved `{}` to `{}` by case-insensitive match",
+                            format_symbol(symbol),
+                            format_symbol(name)
+                        );
+                        self.symbol_warnings
+                            .borrow_mut()
+                            .entry(symbol.to_owned())
+                            .or_insert(message);
+                        return Ok(Some(ids));
+                    }
+                }
+                Ok(None)
+            }
+            _ => Err(RevsetResolutionError::NoSuchRevision {
+                name: format_symbol(symbol),
+                candidates: names.iter().map(|name| format_symbol(name)).collect(),
+            }),
+        }
+    }
+
+    /// Resolves the `symbol` by the extensions of the given `stage` in
+    /// priority order.
+    fn resolve_symbol_with_extensions(
+        &self,
+        repo: &dyn Repo,
+        symbol: &str,
+        stage: ResolutionStage,
+    ) -> Result<Option<Vec<CommitId>>, RevsetResolutionError> {
+        let extensions = self
+            .extensions
+            .iter()
+            .filter(|ext| ext.stage == stage && ext.accepts(symbol));
+        for (priority, group) in &extensions.chunk_by(|ext| ext.priority) {
+            let mut resolved: Option<(usize, Vec<CommitId>)> = None;
+            for ext in group {
+                // The first resolver of each extension wins.
+                if resolved
+                    .as_ref()
+                    .is_some_and(|(index, _)| *index == ext.extension_index)
+                {
+                    continue;
+                }
+                let Some(ids) = ext.resolver.resolve_symbol(repo, symbol)? else {
+                    continue;
+                };
+                match &resolved {
+                    None => resolved = Some((ext.extension_index, ids)),
+                    Some((_, resolved_ids)) if *resolved_ids == ids => {}
+                    Some(_) => {
+                        return Err(RevsetResolutionError::AmbiguousExtensionSymbol {
+                            symbol: symbol.to_owned(),
+                            priority,
+                        });
+                    }
+                }
+            }
+            if let Some((_, ids)) = resolved {
+                return Ok(Some(ids));
+            }
+        }
+        Ok(None)
     }
 }
 
@@ -2077,13 +3474,57 @@ impl SymbolResolver for DefaultSymbolResolver<'_> {
             return Err(RevsetResolutionError::EmptyString);
         }
 
-        for partial_resolver in self.partial_resolvers() {
-            if let Some(ids) = partial_resolver.resolve_symbol(repo, symbol)? {
-                return Ok(ids);
-            }
-        }
+        if let Some(ids) =
+            self.resolve_symbol_with_extensions(repo, symbol, ResolutionStage::BeforeBuiltin)?
+        {
+            return Ok(ids);
+        }
+        let prefix_resolvers: [&dyn PartialSymbolResolver; 2] =
+            [&self.commit_id_resolver, &self.change_id_resolver];
+        for partial_resolver in
+            itertools::chain(DEFAULT_RESOLVERS.iter().copied(), prefix_resolvers)
+        {
+            if let Some(ids) = partial_resolver.resolve_symbol(repo, symbol)? {
+                return Ok(ids);
+            }
+        }
+        if let Some(ids) =
+            self.resolve_symbol_with_extensions(repo, symbol, ResolutionStage::AfterBuiltin)?
+        {
+            return Ok(ids);
+        }
+
+        if self.case_insensitive_refs {
+            if let Some(ids) = self.resolve_ref_case_insensitively(repo, symbol)? {
+                return Ok(ids);
+            }
+        }
+
+        Err(make_no_such_symbol_error(
+            repo,
+            format_symbol(symbol),
+            self.suggestion_distance,
+        ))
+    }
+
+    fn resolve_commit_id_prefix(
+        &self,
+        repo: &dyn Repo,
+        prefix: &str,
+    ) -> Result<Vec<CommitId>, RevsetResolutionError> {
+        resolve_id_prefix(&self.commit_id_resolver, repo, prefix)
+    }
+
+    fn resolve_change_id_prefix(
+        &self,
+        repo: &dyn Repo,
+        prefix: &str,
+    ) -> Result<Vec<CommitId>, RevsetResolutionError> {
+        resolve_id_prefix(&self.change_id_resolver, repo, prefix)
+    }
 
-        Err(make_no_such_symbol_error(repo, format_symbol(symbol)))
+    fn suggestion_distance(&self) -> Option<usize> {
+        self.suggestion_distance
     }
 }
 
@@ -2094,19 +3535,55 @@ fn resolve_commit_ref(
 ) -> Result<Vec<CommitId>, RevsetResolutionError> {
     match commit_ref {
         RevsetCommitRef::Symbol(symbol) => symbol_resolver.resolve_symbol(repo, symbol),
-        RevsetCommitRef::RemoteSymbol(symbol) => resolve_remote_bookmark(repo, symbol.as_ref())
-            .ok_or_else(|| make_no_such_symbol_error(repo, symbol.to_string())),
+        RevsetCommitRef::RemoteSymbol(symbol) => {
+            resolve_remote_symbol(repo, symbol.as_ref(), symbol_resolver)
+        }
+        RevsetCommitRef::CommitIdPrefix(prefix) => {
+            symbol_resolver.resolve_commit_id_prefix(repo, prefix)
+        }
+        RevsetCommitRef::ChangeIdPrefix(prefix) => {
+            symbol_resolver.resolve_change_id_prefix(repo, prefix)
+        }
+        RevsetCommitRef::CommitIdPrefixMatches(prefix) => {
+            resolve_commit_id_prefix_matches(repo, prefix)
+        }
+        RevsetCommitRef::ChangeIdPrefixMatches(prefix) => {
+            resolve_change_id_prefix_matches(repo, prefix)
+        }
         RevsetCommitRef::WorkingCopy(name) => {
             if let Some(commit_id) = repo.view().get_wc_commit_id(name) {
                 Ok(vec![commit_id.clone()])
             } else {
-                Err(RevsetResolutionError::WorkspaceMissingWorkingCopy { name: name.clone() })
+                // The workspace may have been renamed or forgotten.
+                let workspace_symbols = repo.view().wc_commit_ids().keys().map(|other| {
+                    let symbol = other.as_symbol().to_string();
+                    (symbol.clone(), format!("{symbol}@"))
+                });
+                let candidates = collect_similar_symbols(
+                    name.as_str(),
+                    workspace_symbols,
+                    symbol_resolver.suggestion_distance(),
+                );
+                Err(RevsetResolutionError::WorkspaceMissingWorkingCopy {
+                    name: name.clone(),
+                    candidates,
+                })
             }
         }
-        RevsetCommitRef::WorkingCopies => {
-            let wc_commits = repo.view().wc_commit_ids().values().cloned().collect_vec();
+        RevsetCommitRef::WorkingCopies(pattern) => {
+            let wc_commits = repo
+                .view()
+                .wc_commit_ids()
+                .iter()
+                .filter(|(name, _)| pattern.matches(name.as_str()))
+                .map(|(_, commit_id)| commit_id.clone())
+                .collect_vec();
             Ok(wc_commits)
         }
+        RevsetCommitRef::StaleWorkspaces => {
+            let stale = find_stale_workspaces(repo).map_err(RevsetResolutionError::StoreError)?;
+            Ok(stale.hidden.into_iter().map(|(_, id)| id).collect())
+        }
         RevsetCommitRef::Bookmarks(pattern) => {
             let commit_ids = repo
                 .view()
@@ -2195,12 +3672,25 @@ impl ExpressionStateFolder<UserExpressionState, ResolvedExpressionState>
                         Ok(RevsetExpression::none())
                     }
                     RevsetResolutionError::EmptyString
-                    | RevsetResolutionError::AmbiguousCommitIdPrefix(_)
-                    | RevsetResolutionError::AmbiguousChangeIdPrefix(_)
+                    | RevsetResolutionError::AmbiguousCommitIdPrefix { .. }
+                    | RevsetResolutionError::AmbiguousChangeIdPrefix { .. }
+                    | RevsetResolutionError::AmbiguousRemoteRef { .. }
+                    | RevsetResolutionError::AmbiguousExtensionSymbol { .. }
                     | RevsetResolutionError::StoreError(_)
                     | RevsetResolutionError::Other(_) => Err(err),
                 })
             }
+            // 'pushed()' depends on the remote bookmarks of the current repo
+            RevsetExpression::Filter(RevsetFilterPredicate::Pushed) => {
+                let commit_ref = RevsetCommitRef::RemoteBookmarks {
+                    bookmark_pattern: StringPattern::everything(),
+                    remote_pattern: StringPattern::everything(),
+                    remote_ref_state: None,
+                };
+                let heads = resolve_commit_ref(self.repo(), &commit_ref, self.symbol_resolver)?;
+                let ancestors = RevsetExpression::commits(heads).ancestors();
+                Ok(Rc::new(RevsetExpression::AsFilter(ancestors)))
+            }
             _ => fold_child_expression_state(self, expression),
         }
     }
@@ -2210,7 +3700,13 @@ impl ExpressionStateFolder<UserExpressionState, ResolvedExpressionState>
         commit_ref: &RevsetCommitRef,
     ) -> Result<Rc<ResolvedRevsetExpression>, Self::Error> {
         let commit_ids = resolve_commit_ref(self.repo(), commit_ref, self.symbol_resolver)?;
-        Ok(RevsetExpression::commits(commit_ids))
+        match commit_ref {
+            // The commit index includes hidden commits, whereas the change id
+            // index is built from the visible heads.
+            RevsetCommitRef::CommitIdPrefixMatches(_) => Ok(RevsetExpression::commits(commit_ids)
+                .intersection(&RevsetExpression::visible_heads().ancestors())),
+            _ => Ok(RevsetExpression::commits(commit_ids)),
+        }
     }
 
     fn fold_at_operation(
@@ -2313,6 +3809,10 @@ impl VisibilityResolutionContext<'_> {
             RevsetExpression::ForkPoint(expression) => {
                 ResolvedExpression::ForkPoint(self.resolve(expression).into())
             }
+            RevsetExpression::DuplicatesOf(expression) => ResolvedExpression::DuplicatesOf {
+                candidates: self.resolve_all().into(),
+                sources: self.resolve(expression).into(),
+            },
             RevsetExpression::Latest { candidates, count } => ResolvedExpression::Latest {
                 candidates: self.resolve(candidates).into(),
                 count: *count,
@@ -2418,6 +3918,7 @@ impl VisibilityResolutionContext<'_> {
             | RevsetExpression::Heads(_)
             | RevsetExpression::Roots(_)
             | RevsetExpression::ForkPoint(_)
+            | RevsetExpression::DuplicatesOf(_)
             | RevsetExpression::Latest { .. } => {
                 ResolvedPredicateExpression::Set(self.resolve(expression).into())
             }
@@ -2465,6 +3966,26 @@ pub trait Revset: fmt::Debug {
     where
         Self: 'a;
 
+    /// Iterates distinct change ids in the order they first appear in
+    /// topological order.
+    ///
+    /// Divergent commits sharing a change id yield the change id only once.
+    fn iter_change_ids<'a>(
+        &self,
+    ) -> Box<dyn Iterator<Item = Result<ChangeId, RevsetEvaluationError>> + 'a>
+    where
+        Self: 'a,
+    {
+        let mut seen = HashSet::new();
+        Box::new(
+            self.commit_change_ids()
+                .filter_map(move |entry| match entry {
+                    Ok((_, change_id)) => seen.insert(change_id.clone()).then_some(Ok(change_id)),
+                    Err(err) => Some(Err(err)),
+                }),
+        )
+    }
+
     fn iter_graph<'a>(
         &self,
     ) -> Box<dyn Iterator<Item = Result<GraphNode<CommitId>, RevsetEvaluationError>> + 'a>
@@ -2474,6 +3995,37 @@ pub trait Revset: fmt::Debug {
     /// Returns true if iterator will emit no commit nor error.
     fn is_empty(&self) -> bool;
 
+    /// Returns true if the set is known to contain all ancestors of its
+    /// members.
+    ///
+    /// The graph of such a set can be [reversed](Revset::reversed) without
+    /// buffering the nodes and edges.
+    fn is_ancestor_closed(&self) -> bool {
+        false
+    }
+
+    /// Turns this into a revset which iterates commits in the opposite order
+    /// of [`Revset::iter()`], i.e. parents before children.
+    ///
+    /// The implementation may have to walk the whole set before emitting the
+    /// first commit, but should avoid loading commit data upfront.
+    fn reversed<'a>(self: Box<Self>) -> Box<dyn Revset + 'a>
+    where
+        Self: 'a;
+
+    /// Returns the first commit in [`Revset::iter()`] order, without
+    /// evaluating the rest of the set.
+    fn first(&self) -> Result<Option<CommitId>, RevsetEvaluationError> {
+        self.iter().next().transpose()
+    }
+
+    /// Returns the last commit in [`Revset::iter()`] order.
+    ///
+    /// The whole set has to be iterated, but commit ids are not collected.
+    fn last(&self) -> Result<Option<CommitId>, RevsetEvaluationError> {
+        self.iter().try_fold(None, |_, id| Ok(Some(id?)))
+    }
+
     /// Inclusive lower bound and, optionally, inclusive upper bound of how many
     /// commits are in the revset. The implementation can use its discretion as
     /// to how much effort should be put into the estimation, and how accurate
@@ -2488,6 +4040,39 @@ pub trait Revset: fmt::Debug {
     fn containing_fn<'a>(&self) -> Box<RevsetContainingFn<'a>>
     where
         Self: 'a;
+
+    /// Iterates commits grouped by the local day of their committer date.
+    ///
+    /// The day is determined in the time zone recorded in the committer
+    /// timestamp. Buckets are emitted from the newest day to the oldest day,
+    /// and commits within a bucket are in the order of [`Revset::iter()`].
+    /// Since the whole revset has to be scanned, buckets are computed eagerly.
+    #[expect(clippy::type_complexity)]
+    fn iter_bucketed_by_day(
+        &self,
+        repo: &dyn Repo,
+    ) -> Box<dyn Iterator<Item = Result<(NaiveDate, Vec<CommitId>), RevsetEvaluationError>>> {
+        let mut buckets: BTreeMap<NaiveDate, Vec<CommitId>> = BTreeMap::new();
+        for commit in self.iter().commits(repo.store()) {
+            let commit = match commit {
+                Ok(commit) => commit,
+                Err(err) => return Box::new(iter::once(Err(err))),
+            };
+            let timestamp = &commit.committer().timestamp;
+            let date = match time_util::local_date_from_timestamp(timestamp) {
+                Ok(date) => date,
+                Err(err) => {
+                    let message =
+                        format!("Invalid committer date in commit {}: {err}", commit.id());
+                    return Box::new(iter::once(Err(RevsetEvaluationError::Other(
+                        message.into(),
+                    ))));
+                }
+            };
+            buckets.entry(date).or_default().push(commit.id().clone());
+        }
+        Box::new(buckets.into_iter().rev().map(Ok))
+    }
 }
 
 /// Function that checks if a commit is contained within the revset.
@@ -2554,6 +4139,11 @@ impl RevsetExtensions {
         self.symbol_resolvers.push(symbol_resolver);
     }
 
+    /// Registers a revset function callable as `name(...)`.
+    ///
+    /// The function may return a [`RevsetFilterPredicate::Extension`] filter to
+    /// select commits by a custom [`RevsetFilterExtension`]. Panics if a
+    /// function of the same name already exists.
     pub fn add_custom_function(&mut self, name: &'static str, func: RevsetFunction) {
         match self.function_map.entry(name) {
             hash_map::Entry::Occupied(_) => {
@@ -2588,8 +4178,10 @@ impl<'a> RevsetParseContext<'a> {
         LoweringContext {
             user_email,
             date_pattern_context,
+            date_patterns: RefCell::new(HashMap::new()),
             extensions,
             workspace,
+            symbol_locations: None,
         }
     }
 }
@@ -2599,8 +4191,12 @@ impl<'a> RevsetParseContext<'a> {
 pub struct LoweringContext<'a> {
     user_email: &'a str,
     date_pattern_context: DatePatternContext,
+    /// Date patterns parsed so far, keyed by `(value, kind)`.
+    date_patterns: RefCell<HashMap<(String, String), DatePattern>>,
     extensions: &'a RevsetExtensions,
     workspace: Option<RevsetWorkspaceContext<'a>>,
+    /// Locations of the lowered symbols, recorded if set.
+    symbol_locations: Option<&'a RefCell<Vec<(String, SymbolLocation)>>>,
 }
 
 impl<'a> LoweringContext<'a> {
@@ -2615,6 +4211,22 @@ impl<'a> LoweringContext<'a> {
     pub fn symbol_resolvers(&self) -> &'a [impl AsRef<dyn SymbolResolverExtension> + use<>] {
         self.extensions.symbol_resolvers()
     }
+
+    /// Parses date pattern relative to the context time. The result is cached
+    /// so the identical patterns in one expression are parsed only once.
+    pub fn parse_date_pattern(
+        &self,
+        value: &str,
+        kind: &str,
+    ) -> Result<DatePattern, DatePatternParseError> {
+        let key = (value.to_owned(), kind.to_owned());
+        if let Some(pattern) = self.date_patterns.borrow().get(&key) {
+            return Ok(*pattern);
+        }
+        let pattern = self.date_pattern_context.parse_relative(value, kind)?;
+        self.date_patterns.borrow_mut().insert(key, pattern);
+        Ok(pattern)
+    }
 }
 
 /// Workspace information needed to parse revset expression.
@@ -2649,6 +4261,527 @@ pub fn format_remote_symbol(name: &str, remote: &str) -> String {
     format!("{name}@{remote}")
 }
 
+/// Binding strength of formatted expression, from the loosest to the
+/// tightest.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum FormatPrecedence {
+    Union,
+    Intersection,
+    Negate,
+    Range,
+    Neighbors,
+    Primary,
+}
+
+fn format_expression(expression: &UserRevsetExpression) -> Option<(String, FormatPrecedence)> {
+    use FormatPrecedence as Prec;
+    // Parenthesizes the operand if it binds looser than the operator.
+    let operand = |expression: &UserRevsetExpression, min: Prec| {
+        let (text, prec) = format_expression(expression)?;
+        Some(parenthesize(text, prec, min))
+    };
+    let argument = |expression: &UserRevsetExpression| Some(format_expression(expression)?.0);
+    let formatted = match expression {
+        RevsetExpression::None => ("none()".to_owned(), Prec::Primary),
+        RevsetExpression::All => ("all()".to_owned(), Prec::Primary),
+        RevsetExpression::VisibleHeads => ("visible_heads()".to_owned(), Prec::Primary),
+        RevsetExpression::Root => ("root()".to_owned(), Prec::Primary),
+        RevsetExpression::Commits(commit_ids) => match commit_ids.as_slice() {
+            [] => ("none()".to_owned(), Prec::Primary),
+            [commit_id] => (commit_id.hex(), Prec::Primary),
+            _ => (
+                commit_ids.iter().map(|id| id.hex()).join(" | "),
+                Prec::Union,
+            ),
+        },
+        RevsetExpression::CommitRef(commit_ref) => (format_commit_ref(commit_ref), Prec::Primary),
+        RevsetExpression::Ancestors { heads, generation } => {
+            let (base, base_prec) = format_neighbors(heads, generation.start, "-")?;
+            let depth = generation.end.saturating_sub(generation.start);
+            if generation.end == u64::MAX {
+                let base = parenthesize(base, base_prec, Prec::Neighbors);
+                (format!("::{base}"), Prec::Range)
+            } else if generation.start > 0 && depth == 1 {
+                (base, base_prec)
+            } else {
+                (format!("ancestors({base}, {depth})"), Prec::Primary)
+            }
+        }
+        RevsetExpression::Descendants { roots, generation } => {
+            let (base, base_prec) = format_neighbors(roots, generation.start, "+")?;
+            let depth = generation.end.saturating_sub(generation.start);
+            if generation.end == u64::MAX {
+                let base = parenthesize(base, base_prec, Prec::Neighbors);
+                (format!("{base}::"), Prec::Range)
+            } else if generation.start > 0 && depth == 1 {
+                (base, base_prec)
+            } else {
+                (format!("descendants({base}, {depth})"), Prec::Primary)
+            }
+        }
+        RevsetExpression::Range {
+            roots,
+            heads,
+            generation,
+        } => {
+            if *generation != GENERATION_RANGE_FULL {
+                return None;
+            }
+            let roots = operand(roots, Prec::Neighbors)?;
+            let heads = operand(heads, Prec::Neighbors)?;
+            (format!("{roots}..{heads}"), Prec::Range)
+        }
+        RevsetExpression::DagRange { roots, heads } => {
+            let roots = operand(roots, Prec::Neighbors)?;
+            let heads = operand(heads, Prec::Neighbors)?;
+            (format!("{roots}::{heads}"), Prec::Range)
+        }
+        RevsetExpression::Reachable { sources, domain } => {
+            let sources = argument(sources)?;
+            let domain = argument(domain)?;
+            (format!("reachable({sources}, {domain})"), Prec::Primary)
+        }
+        RevsetExpression::Heads(candidates) => {
+            (format!("heads({})", argument(candidates)?), Prec::Primary)
+        }
+        RevsetExpression::Roots(candidates) => {
+            (format!("roots({})", argument(candidates)?), Prec::Primary)
+        }
+        RevsetExpression::ForkPoint(candidates) => (
+            format!("fork_point({})", argument(candidates)?),
+            Prec::Primary,
+        ),
+        RevsetExpression::DuplicatesOf(candidates) => (
+            format!("duplicates_of({})", argument(candidates)?),
+            Prec::Primary,
+        ),
+        RevsetExpression::Latest { candidates, count } => (
+            format!("latest({}, {count})", argument(candidates)?),
+            Prec::Primary,
+        ),
+        RevsetExpression::Filter(predicate) => (format_predicate(predicate)?, Prec::Primary),
+        RevsetExpression::AsFilter(candidates) => format_expression(candidates)?,
+        RevsetExpression::AtOperation {
+            operation,
+            candidates,
+        } => {
+            let candidates = argument(candidates)?;
+            (
+                format!("at_operation({operation}, {candidates})"),
+                Prec::Primary,
+            )
+        }
+        RevsetExpression::WithinVisibility { .. } => return None,
+        RevsetExpression::Coalesce(expression1, expression2) => {
+            let expression1 = argument(expression1)?;
+            let expression2 = argument(expression2)?;
+            (
+                format!("coalesce({expression1}, {expression2})"),
+                Prec::Primary,
+            )
+        }
+        RevsetExpression::Present(candidates) => {
+            (format!("present({})", argument(candidates)?), Prec::Primary)
+        }
+        RevsetExpression::NotIn(complement) => (
+            format!("~{}", operand(complement, Prec::Negate)?),
+            Prec::Negate,
+        ),
+        // Unions are parenthesized on both sides because chained unions would
+        // be parsed into a balanced tree.
+        RevsetExpression::Union(expression1, expression2) => {
+            let expression1 = operand(expression1, Prec::Intersection)?;
+            let expression2 = operand(expression2, Prec::Intersection)?;
+            (format!("{expression1} | {expression2}"), Prec::Union)
+        }
+        RevsetExpression::Intersection(expression1, expression2) => {
+            let expression1 = operand(expression1, Prec::Intersection)?;
+            let expression2 = operand(expression2, Prec::Negate)?;
+            (format!("{expression1} & {expression2}"), Prec::Intersection)
+        }
+        RevsetExpression::Difference(expression1, expression2) => {
+            let expression1 = operand(expression1, Prec::Intersection)?;
+            let expression2 = operand(expression2, Prec::Negate)?;
+            (format!("{expression1} ~ {expression2}"), Prec::Intersection)
+        }
+    };
+    Some(formatted)
+}
+
+fn format_canonical(expression: &ResolvedRevsetExpression) -> String {
+    let argument = |expression: &ResolvedRevsetExpression| format_canonical(expression);
+    let generation = |range: &Range<u64>| format!("{}..{}", range.start, range.end);
+    match expression {
+        RevsetExpression::None => "none()".to_owned(),
+        RevsetExpression::All => "all()".to_owned(),
+        RevsetExpression::VisibleHeads => "visible_heads()".to_owned(),
+        RevsetExpression::Root => "root()".to_owned(),
+        RevsetExpression::Commits(_) | RevsetExpression::Union(..) => {
+            let mut operands = Vec::new();
+            collect_canonical_union_operands(expression, &mut operands);
+            join_canonical_operands("union", operands, "none()")
+        }
+        RevsetExpression::CommitRef(commit_ref) => match *commit_ref {},
+        RevsetExpression::Ancestors {
+            heads,
+            generation: range,
+        } => {
+            format!("ancestors({}, {})", argument(heads), generation(range))
+        }
+        RevsetExpression::Descendants {
+            roots,
+            generation: range,
+        } => {
+            format!("descendants({}, {})", argument(roots), generation(range))
+        }
+        RevsetExpression::Range {
+            roots,
+            heads,
+            generation: range,
+        } => format!(
+            "range({}, {}, {})",
+            argument(roots),
+            argument(heads),
+            generation(range)
+        ),
+        RevsetExpression::DagRange { roots, heads } => {
+            format!("dag_range({}, {})", argument(roots), argument(heads))
+        }
+        RevsetExpression::Reachable { sources, domain } => {
+            format!("reachable({}, {})", argument(sources), argument(domain))
+        }
+        RevsetExpression::Heads(candidates) => format!("heads({})", argument(candidates)),
+        RevsetExpression::Roots(candidates) => format!("roots({})", argument(candidates)),
+        RevsetExpression::ForkPoint(candidates) => {
+            format!("fork_point({})", argument(candidates))
+        }
+        RevsetExpression::DuplicatesOf(candidates) => {
+            format!("duplicates_of({})", argument(candidates))
+        }
+        RevsetExpression::Latest { candidates, count } => {
+            format!("latest({}, {count})", argument(candidates))
+        }
+        RevsetExpression::Filter(predicate) => {
+            format_predicate(predicate).unwrap_or_else(|| format!("{predicate:?}"))
+        }
+        // The filter marker and present() don't change the set.
+        RevsetExpression::AsFilter(candidates) | RevsetExpression::Present(candidates) => {
+            argument(candidates)
+        }
+        RevsetExpression::AtOperation { operation, .. } => match *operation {},
+        RevsetExpression::WithinVisibility {
+            candidates,
+            visible_heads,
+        } => {
+            let visible_heads = visible_heads.iter().map(|id| id.hex()).sorted().join(", ");
+            format!(
+                "within_visibility({}, [{visible_heads}])",
+                argument(candidates)
+            )
+        }
+        RevsetExpression::Coalesce(expression1, expression2) => {
+            format!(
+                "coalesce({}, {})",
+                argument(expression1),
+                argument(expression2)
+            )
+        }
+        RevsetExpression::NotIn(complement) => format!("not({})", argument(complement)),
+        RevsetExpression::Intersection(..) | RevsetExpression::Difference(..) => {
+            let mut operands = Vec::new();
+            collect_canonical_intersection_operands(expression, &mut operands);
+            join_canonical_operands("intersection", operands, "all()")
+        }
+    }
+}
+
+/// Collects canonical strings of the flattened union operands.
+fn collect_canonical_union_operands(
+    expression: &ResolvedRevsetExpression,
+    operands: &mut Vec<String>,
+) {
+    match expression {
+        RevsetExpression::None => {}
+        RevsetExpression::Commits(commit_ids) => {
+            operands.extend(commit_ids.iter().map(|id| id.hex()));
+        }
+        RevsetExpression::AsFilter(candidates) | RevsetExpression::Present(candidates) => {
+            collect_canonical_union_operands(candidates, operands);
+        }
+        RevsetExpression::Union(expression1, expression2) => {
+            collect_canonical_union_operands(expression1, operands);
+            collect_canonical_union_operands(expression2, operands);
+        }
+        _ => operands.push(format_canonical(expression)),
+    }
+}
+
+/// Collects canonical strings of the flattened intersection operands.
+/// Difference `x ~ y` is flattened as `x & ~y`.
+fn collect_canonical_intersection_operands(
+    expression: &ResolvedRevsetExpression,
+    operands: &mut Vec<String>,
+) {
+    match expression {
+        RevsetExpression::All => {}
+        RevsetExpression::AsFilter(candidates) | RevsetExpression::Present(candidates) => {
+            collect_canonical_intersection_operands(candidates, operands);
+        }
+        RevsetExpression::Intersection(expression1, expression2) => {
+            collect_canonical_intersection_operands(expression1, operands);
+            collect_canonical_intersection_operands(expression2, operands);
+        }
+        RevsetExpression::Difference(expression1, expression2) => {
+            collect_canonical_intersection_operands(expression1, operands);
+            operands.push(format!("not({})", format_canonical(expression2)));
+        }
+        _ => operands.push(format_canonical(expression)),
+    }
+}
+
+/// Sorts and deduplicates the `operands`, and joins them by `name` function.
+fn join_canonical_operands(name: &str, mut operands: Vec<String>, empty: &str) -> String {
+    operands.sort_unstable();
+    operands.dedup();
+    match operands.len() {
+        0 => empty.to_owned(),
+        1 => operands.pop().unwrap(),
+        _ => format!("{name}({})", operands.join(", ")),
+    }
+}
+
+/// Formats `expression` followed by `count` parents/children operators.
+fn format_neighbors(
+    expression: &UserRevsetExpression,
+    count: u64,
+    op: &str,
+) -> Option<(String, FormatPrecedence)> {
+    let (text, prec) = format_expression(expression)?;
+    if count == 0 {
+        return Some((text, prec));
+    }
+    let text = parenthesize(text, prec, FormatPrecedence::Neighbors);
+    let count = usize::try_from(count).ok()?;
+    Some((text + &op.repeat(count), FormatPrecedence::Neighbors))
+}
+
+fn parenthesize(text: String, prec: FormatPrecedence, min: FormatPrecedence) -> String {
+    if prec < min {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+fn format_commit_ref(commit_ref: &RevsetCommitRef) -> String {
+    match commit_ref {
+        RevsetCommitRef::WorkingCopy(name) => format!("{}@", format_symbol(name.as_str())),
+        RevsetCommitRef::WorkingCopies(pattern) => {
+            format!("working_copies({})", format_string_pattern(pattern))
+        }
+        RevsetCommitRef::StaleWorkspaces => "stale_workspaces()".to_owned(),
+        RevsetCommitRef::Symbol(name) => format_symbol(name),
+        RevsetCommitRef::RemoteSymbol(symbol) => {
+            format_remote_symbol(symbol.name.as_str(), symbol.remote.as_str())
+        }
+        RevsetCommitRef::CommitIdPrefix(prefix) => format!("commit_id({})", format_symbol(prefix)),
+        RevsetCommitRef::ChangeIdPrefix(prefix) => format!("change_id({})", format_symbol(prefix)),
+        RevsetCommitRef::CommitIdPrefixMatches(prefix) => {
+            format!("commit_id(glob:{})", format_symbol(&format!("{prefix}*")))
+        }
+        RevsetCommitRef::ChangeIdPrefixMatches(prefix) => {
+            format!("change_id(glob:{})", format_symbol(&format!("{prefix}*")))
+        }
+        RevsetCommitRef::Bookmarks(pattern) => {
+            format!("bookmarks({})", format_string_pattern(pattern))
+        }
+        RevsetCommitRef::RemoteBookmarks {
+            bookmark_pattern,
+            remote_pattern,
+            remote_ref_state,
+        } => {
+            let name = match remote_ref_state {
+                None => "remote_bookmarks",
+                Some(RemoteRefState::Tracked) => "tracked_remote_bookmarks",
+                Some(RemoteRefState::New) => "untracked_remote_bookmarks",
+            };
+            let bookmark_pattern = format_string_pattern(bookmark_pattern);
+            let remote_pattern = format_string_pattern(remote_pattern);
+            format!("{name}({bookmark_pattern}, remote={remote_pattern})")
+        }
+        RevsetCommitRef::Tags(pattern) => format!("tags({})", format_string_pattern(pattern)),
+        RevsetCommitRef::GitRefs => "git_refs()".to_owned(),
+        RevsetCommitRef::GitHead => "git_head()".to_owned(),
+    }
+}
+
+fn format_predicate(predicate: &RevsetFilterPredicate) -> Option<String> {
+    let text = match predicate {
+        RevsetFilterPredicate::ParentCount(range) => {
+            if *range != (2..u32::MAX) {
+                return None;
+            }
+            "merges()".to_owned()
+        }
+        RevsetFilterPredicate::Description(patterns) => {
+            format!("description({})", format_string_pattern_set(patterns)?)
+        }
+        RevsetFilterPredicate::Subject(patterns) => {
+            format!("subject({})", format_string_pattern_set(patterns)?)
+        }
+        RevsetFilterPredicate::MalformedSubject => "malformed_subject()".to_owned(),
+        RevsetFilterPredicate::AuthorName(patterns) => {
+            format!("author_name({})", format_string_pattern_set(patterns)?)
+        }
+        RevsetFilterPredicate::AuthorEmail(patterns) => {
+            format!("author_email({})", format_string_pattern_set(patterns)?)
+        }
+        RevsetFilterPredicate::AuthorDate(pattern) => {
+            format!("author_date({})", format_date_pattern(pattern)?)
+        }
+        RevsetFilterPredicate::CommitterName(patterns) => {
+            format!("committer_name({})", format_string_pattern_set(patterns)?)
+        }
+        RevsetFilterPredicate::CommitterEmail(patterns) => {
+            format!("committer_email({})", format_string_pattern_set(patterns)?)
+        }
+        RevsetFilterPredicate::CommitterDate(pattern) => {
+            format!("committer_date({})", format_date_pattern(pattern)?)
+        }
+        RevsetFilterPredicate::TimezoneMismatch => "tz_mismatch()".to_owned(),
+        RevsetFilterPredicate::File(expression) => {
+            format!("files({})", format_fileset_expression(expression))
+        }
+        RevsetFilterPredicate::FileFromFirstParent(expression) => {
+            let files = format_fileset_expression(expression);
+            format!("files({files}, parents:first)")
+        }
+        RevsetFilterPredicate::FileFollowingRenames(expression) => {
+            format!("follow({})", format_fileset_expression(expression))
+        }
+        RevsetFilterPredicate::DiffContains { text, files } => {
+            let text = format_string_pattern(text);
+            let files = format_fileset_expression(files);
+            format!("diff_contains({text}, {files})")
+        }
+        RevsetFilterPredicate::LinesChanged { range, files } => {
+            let range = match (range.start, range.end) {
+                (0, u64::MAX) => "..".to_owned(),
+                (start, u64::MAX) => format!("{start}.."),
+                (0, end) => format!("..{end}"),
+                (start, end) => format!("{start}..{end}"),
+            };
+            let files = format_fileset_expression(files);
+            format!("lines_changed({range}, {files})")
+        }
+        RevsetFilterPredicate::HasConflict => "conflicts()".to_owned(),
+        RevsetFilterPredicate::Signed => "signed()".to_owned(),
+        RevsetFilterPredicate::Pushed => "pushed()".to_owned(),
+        RevsetFilterPredicate::Extension(_) => return None,
+    };
+    Some(text)
+}
+
+fn format_string_pattern_set(patterns: &StringPatternSet) -> Option<String> {
+    let patterns = patterns.patterns();
+    (!patterns.is_empty()).then(|| patterns.iter().map(format_string_pattern).join(", "))
+}
+
+fn format_string_pattern(pattern: &StringPattern) -> String {
+    let kind = match pattern {
+        StringPattern::Exact(_) => "exact",
+        StringPattern::ExactI(_) => "exact-i",
+        StringPattern::Substring(_) => "substring",
+        StringPattern::SubstringI(_) => "substring-i",
+        StringPattern::StartsWith(_) => "starts-with",
+        StringPattern::StartsWithI(_) => "starts-with-i",
+        StringPattern::EndsWith(_) => "ends-with",
+        StringPattern::EndsWithI(_) => "ends-with-i",
+        StringPattern::Glob(_) => "glob",
+        StringPattern::GlobI(_) => "glob-i",
+        StringPattern::Regex(_) => "regex",
+    };
+    format!("{kind}:{}", format_string(pattern.as_str()))
+}
+
+fn format_date_pattern(pattern: &DatePattern) -> Option<String> {
+    let format_millis = |millis: &MillisSinceEpoch| {
+        // The date parser doesn't accept milliseconds. Rounding up doesn't
+        // change the set of matching timestamps unless they have sub-second
+        // precision, which Git commits don't.
+        let seconds = millis.0.div_euclid(1000) + i64::from(millis.0.rem_euclid(1000) != 0);
+        let datetime = chrono::DateTime::from_timestamp(seconds, 0)?;
+        Some(datetime.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    };
+    let (kind, date) = match pattern {
+        DatePattern::AtOrAfter(millis) => ("after", format_millis(millis)?),
+        DatePattern::Before(millis) => ("before", format_millis(millis)?),
+        DatePattern::Between(start, end) => {
+            let range = format!("{}..{}", format_millis(start)?, format_millis(end)?);
+            ("during", range)
+        }
+    };
+    Some(format!("{kind}:{}", format_string(&date)))
+}
+
+fn format_fileset_expression(expression: &FilesetExpression) -> String {
+    let operand = |expression: &FilesetExpression| match expression {
+        FilesetExpression::None
+        | FilesetExpression::All
+        | FilesetExpression::Conflicts
+        | FilesetExpression::Size(_)
+        | FilesetExpression::Executable(_)
+        | FilesetExpression::FileType(_)
+        | FilesetExpression::Pattern(_) => format_fileset_expression(expression),
+        _ => format!("({})", format_fileset_expression(expression)),
+    };
+    match expression {
+        FilesetExpression::None => "none()".to_owned(),
+        FilesetExpression::All => "all()".to_owned(),
+        FilesetExpression::Conflicts => "conflicts()".to_owned(),
+        FilesetExpression::Size(range) => match (range.start, range.end) {
+            (start, u64::MAX) => format!("size({start}..)"),
+            (start, end) if start.checked_add(1) == Some(end) => format!("size({start})"),
+            (start, end) => format!("size({start}..{end})"),
+        },
+        FilesetExpression::Executable(true) => "executable()".to_owned(),
+        FilesetExpression::Executable(false) => "executable(false)".to_owned(),
+        FilesetExpression::FileType(kind) => format!("type:{}", kind.name()),
+        FilesetExpression::Pattern(pattern) => format_file_pattern(pattern),
+        FilesetExpression::UnionAll(expressions) if expressions.is_empty() => "none()".to_owned(),
+        FilesetExpression::UnionAll(expressions) => expressions.iter().map(operand).join(" | "),
+        FilesetExpression::Intersection(expression1, expression2) => {
+            format!("{} & {}", operand(expression1), operand(expression2))
+        }
+        FilesetExpression::Difference(expression1, expression2) => {
+            format!("{} ~ {}", operand(expression1), operand(expression2))
+        }
+    }
+}
+
+fn format_file_pattern(pattern: &FilePattern) -> String {
+    match pattern {
+        FilePattern::FilePath(path) => {
+            format!(
+                "root-file:{}",
+                format_string(path.as_internal_file_string())
+            )
+        }
+        FilePattern::PrefixPath(path) => {
+            format!("root:{}", format_string(path.as_internal_file_string()))
+        }
+        FilePattern::FileGlob { dir, pattern } => {
+            let dir = glob::Pattern::escape(dir.as_internal_file_string());
+            let glob = if dir.is_empty() {
+                pattern.as_str().to_owned()
+            } else {
+                format!("{dir}/{}", pattern.as_str())
+            };
+            format!("root-glob:{}", format_string(&glob))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
@@ -2964,6 +5097,13 @@ mod tests {
         insta::assert_debug_snapshot!(
             parse("bookmarks()").unwrap(),
             @r#"CommitRef(Bookmarks(Substring("")))"#);
+        // Default argument for working_copies() is ""
+        insta::assert_debug_snapshot!(
+            parse("working_copies()").unwrap(),
+            @r#"CommitRef(WorkingCopies(Substring("")))"#);
+        insta::assert_debug_snapshot!(
+            parse("working_copies(exact:ws1)").unwrap(),
+            @r#"CommitRef(WorkingCopies(Exact("ws1")))"#);
         // Default argument for tags() is ""
         insta::assert_debug_snapshot!(
             parse("tags()").unwrap(),
@@ -3192,16 +5332,65 @@ mod tests {
         }
         "#);
         insta::assert_debug_snapshot!(
-            parse("parents(foo,foo)").unwrap_err().kind(), @r#"
-        InvalidFunctionArguments {
-            name: "parents",
-            message: "Expected 1 arguments",
-        }
-        "#);
+            parse("parents(foo,foo)").unwrap_err().kind(), @r#"
+        InvalidFunctionArguments {
+            name: "parents",
+            message: "Expected 1 arguments",
+        }
+        "#);
+        insta::assert_debug_snapshot!(
+            parse("root()").unwrap(),
+            @"Root");
+        assert!(parse("root(a)").is_err());
+        insta::assert_debug_snapshot!(
+            parse("heads(foo, bar)").unwrap(), @r#"
+        Heads(
+            Union(
+                CommitRef(Symbol("foo")),
+                CommitRef(Symbol("bar")),
+            ),
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse("roots(foo, bar, baz)").unwrap(), @r#"
+        Roots(
+            Union(
+                CommitRef(Symbol("foo")),
+                Union(
+                    CommitRef(Symbol("bar")),
+                    CommitRef(Symbol("baz")),
+                ),
+            ),
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse("fork_point(foo, bar)").unwrap(), @r#"
+        ForkPoint(
+            Union(
+                CommitRef(Symbol("foo")),
+                CommitRef(Symbol("bar")),
+            ),
+        )
+        "#);
+        assert!(parse("heads()").is_err());
+        insta::assert_debug_snapshot!(
+            parse("commit_id(abc)").unwrap(),
+            @r#"CommitRef(CommitIdPrefix("abc"))"#);
         insta::assert_debug_snapshot!(
-            parse("root()").unwrap(),
-            @"Root");
-        assert!(parse("root(a)").is_err());
+            parse("change_id('zvz')").unwrap(),
+            @r#"CommitRef(ChangeIdPrefix("zvz"))"#);
+        insta::assert_debug_snapshot!(
+            parse(r#"commit_id(glob:"abc*")"#).unwrap(),
+            @r#"CommitRef(CommitIdPrefixMatches("abc"))"#);
+        insta::assert_debug_snapshot!(
+            parse("change_id(glob:'zvz*')").unwrap(),
+            @r#"CommitRef(ChangeIdPrefixMatches("zvz"))"#);
+        insta::assert_debug_snapshot!(
+            parse("commit_id(glob:'a*c')").unwrap_err().kind(),
+            @r#"Expression("Invalid ID prefix")"#);
+        insta::assert_debug_snapshot!(
+            parse("commit_id(exact:abc)").unwrap_err().kind(),
+            @r#"Expression("Invalid ID prefix")"#);
         insta::assert_debug_snapshot!(
             parse(r#"description("")"#).unwrap(),
             @r#"Filter(Description(Substring("")))"#);
@@ -3214,12 +5403,57 @@ mod tests {
         insta::assert_debug_snapshot!(
             parse("description(\"(foo)\")").unwrap(),
             @r#"Filter(Description(Substring("(foo)")))"#);
-        assert!(parse("mine(foo)").is_err());
+        // Multiple patterns are compiled into a single predicate
+        insta::assert_debug_snapshot!(
+            parse("description(exact:foo, bar)").unwrap(), @r#"
+        Filter(
+            Description(
+                AnyOf(
+                    [
+                        Exact("foo"),
+                        Substring("bar"),
+                    ],
+                ),
+            ),
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse("description()").unwrap_err().kind(), @r#"
+        InvalidFunctionArguments {
+            name: "description",
+            message: "Expected at least 1 arguments",
+        }
+        "#);
+        assert!(parse("mine(foo)").is_ok());
+        assert!(parse("mine(foo, bar)").is_err());
+        insta::assert_debug_snapshot!(
+            parse("branches()").unwrap_err().kind(), @r#"
+        RenamedFunction {
+            name: "branches",
+            new_name: "bookmarks",
+        }
+        "#);
         insta::assert_debug_snapshot!(
             parse_with_workspace("empty()", WorkspaceName::DEFAULT).unwrap(),
             @"NotIn(Filter(File(All)))");
-        assert!(parse_with_workspace("empty(foo)", WorkspaceName::DEFAULT).is_err());
-        assert!(parse_with_workspace("file()", WorkspaceName::DEFAULT).is_err());
+        insta::assert_debug_snapshot!(
+            parse_with_workspace("empty(foo)", WorkspaceName::DEFAULT).unwrap(),
+            @r#"
+        NotIn(
+            Filter(File(Pattern(PrefixPath("foo")))),
+        )
+        "#);
+        assert!(parse_with_workspace("empty(foo, bar)", WorkspaceName::DEFAULT).is_err());
+        insta::assert_debug_snapshot!(
+            parse("empty(foo)").unwrap_err().kind(),
+            @"FsPathWithoutWorkspace");
+        insta::assert_debug_snapshot!(
+            parse_with_workspace("file()", WorkspaceName::DEFAULT).unwrap_err().kind(), @r#"
+        RenamedFunction {
+            name: "file",
+            new_name: "files",
+        }
+        "#);
         insta::assert_debug_snapshot!(
             parse_with_workspace("files(foo)", WorkspaceName::DEFAULT).unwrap(),
             @r#"Filter(File(Pattern(PrefixPath("foo"))))"#);
@@ -3245,6 +5479,78 @@ mod tests {
             ),
         )
         "#);
+        insta::assert_debug_snapshot!(
+            parse_with_workspace("files(foo, parents:all)", WorkspaceName::DEFAULT).unwrap(),
+            @r#"Filter(File(Pattern(PrefixPath("foo"))))"#);
+        insta::assert_debug_snapshot!(
+            parse_with_workspace("files(foo, parents:first)", WorkspaceName::DEFAULT).unwrap(),
+            @r#"Filter(FileFromFirstParent(Pattern(PrefixPath("foo"))))"#);
+        insta::assert_debug_snapshot!(
+            parse_with_workspace("follow(foo)", WorkspaceName::DEFAULT).unwrap(),
+            @r#"Filter(FileFollowingRenames(Pattern(PrefixPath("foo"))))"#);
+        insta::assert_debug_snapshot!(
+            parse_with_workspace("files(foo, parents:last)", WorkspaceName::DEFAULT)
+                .unwrap_err()
+                .kind(),
+            @r#"Expression("Invalid parents mode")"#);
+        insta::assert_debug_snapshot!(
+            parse_with_workspace("files(foo, first)", WorkspaceName::DEFAULT)
+                .unwrap_err()
+                .kind(),
+            @r#"Expression("Invalid parents mode")"#);
+    }
+
+    #[test]
+    fn test_parse_size_range() {
+        let parse_size_range = |text: &str| {
+            let node = parse_program(text).unwrap();
+            let ExpressionKind::FunctionCall(function) = &node.kind else {
+                panic!("unexpected node: {node:?}");
+            };
+            let [arg] = function.expect_exact_arguments()?;
+            expect_size_range(&mut RevsetDiagnostics::new(), arg)
+        };
+
+        assert_eq!(
+            parse_size_range("commit_size(1MiB..)").unwrap(),
+            1024 * 1024..u64::MAX
+        );
+        assert_eq!(
+            parse_size_range("commit_size(..'4 KB')").unwrap(),
+            0..4 * 1024
+        );
+        assert_eq!(
+            parse_size_range("commit_size(100..2K)").unwrap(),
+            100..2 * 1024
+        );
+        assert_eq!(parse_size_range("commit_size(512)").unwrap(), 512..513);
+        assert_eq!(parse_size_range("commit_size(..)").unwrap(), 0..u64::MAX);
+
+        // Malformed sizes are reported with the reason
+        let err = parse_size_range("commit_size(1XB..)").unwrap_err();
+        insta::assert_debug_snapshot!(err.kind(), @r#"
+        Expression(
+            "Invalid size",
+        )
+        "#);
+        assert_eq!(
+            std::error::Error::source(&err).unwrap().to_string(),
+            "unrecognized unit prefix"
+        );
+        insta::assert_debug_snapshot!(
+            parse_size_range("commit_size(MiB)").unwrap_err().kind(),
+            @r#"
+        Expression(
+            "Invalid size",
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse_size_range("commit_size(x|y..)").unwrap_err().kind(),
+            @r#"
+        Expression(
+            "Expected expression of type size",
+        )
+        "#);
     }
 
     #[test]
@@ -3285,6 +5591,47 @@ mod tests {
             @r#"Filter(AuthorEmail(ExactI("test.user@example.com")))"#);
     }
 
+    #[test]
+    fn test_parse_revset_date_pattern_cached() {
+        let aliases_map = RevsetAliasesMap::new();
+        let context = RevsetParseContext {
+            aliases_map: &aliases_map,
+            local_variables: HashMap::new(),
+            user_email: "test.user@example.com",
+            date_pattern_context: chrono::Utc::now().fixed_offset().into(),
+            extensions: &RevsetExtensions::default(),
+            workspace: None,
+        };
+        let lowering_context = context.to_lowering_context();
+        let node =
+            parse_program("author_date(after:'1 day ago') | committer_date(after:'1 day ago')")
+                .unwrap();
+        let expression =
+            lower_expression(&mut RevsetDiagnostics::new(), &node, &lowering_context).unwrap();
+
+        // Identical (value, kind) pairs are parsed once
+        let cached_keys = lowering_context
+            .date_patterns
+            .borrow()
+            .keys()
+            .cloned()
+            .collect_vec();
+        assert_eq!(cached_keys, [("1 day ago".to_owned(), "after".to_owned())]);
+
+        // and the repeated patterns resolve to the same date
+        let RevsetExpression::Union(author, committer) = expression.as_ref() else {
+            panic!("unexpected expression: {expression:?}");
+        };
+        let (
+            RevsetExpression::Filter(RevsetFilterPredicate::AuthorDate(author_pattern)),
+            RevsetExpression::Filter(RevsetFilterPredicate::CommitterDate(committer_pattern)),
+        ) = (author.as_ref(), committer.as_ref())
+        else {
+            panic!("unexpected expressions: {author:?}, {committer:?}");
+        };
+        assert_eq!(author_pattern, committer_pattern);
+    }
+
     #[test]
     fn test_parse_revset_keyword_arguments() {
         let settings = insta_settings();
@@ -3418,6 +5765,179 @@ mod tests {
         "#);
     }
 
+    #[test]
+    fn test_range_operator_on_filter() {
+        let parse_diagnostics = |revset_str: &str| {
+            let context = RevsetParseContext {
+                aliases_map: &RevsetAliasesMap::new(),
+                local_variables: HashMap::new(),
+                user_email: "test.user@example.com",
+                date_pattern_context: chrono::Utc::now().fixed_offset().into(),
+                extensions: &RevsetExtensions::default(),
+                workspace: None,
+            };
+            let mut diagnostics = RevsetDiagnostics::new();
+            super::parse(&mut diagnostics, revset_str, &context).unwrap();
+            diagnostics.iter().map(|diag| diag.message()).collect_vec()
+        };
+
+        assert_eq!(
+            parse_diagnostics("::author('name1')"),
+            [
+                "Operator `::` is applied to a filter expression without a set, which is \
+                 evaluated against all commits. Consider intersecting the filter with a set \
+                 of revisions first"
+            ]
+        );
+        assert_eq!(
+            parse_diagnostics("::(mine() | merges())"),
+            [
+                "Operator `::` is applied to a filter expression without a set, which is \
+                 evaluated against all commits. Consider intersecting the filter with a set \
+                 of revisions first"
+            ]
+        );
+        assert_eq!(
+            parse_diagnostics("..(~description(x))"),
+            [
+                "Operator `..` is applied to a filter expression without a set, which is \
+                 evaluated against all commits. Consider intersecting the filter with a set \
+                 of revisions first"
+            ]
+        );
+        assert_eq!(parse_diagnostics("x & ..conflicts()").len(), 1);
+
+        // Filters intersected with a set are fine
+        assert!(parse_diagnostics("::(x & author('name1'))").is_empty());
+        assert!(parse_diagnostics("::(author(a) | x)").is_empty());
+        assert!(parse_diagnostics("description(a)::").is_empty());
+        assert!(parse_diagnostics("author(a)..").is_empty());
+        assert!(parse_diagnostics("x..author(a)").is_empty());
+        assert!(parse_diagnostics("::x").is_empty());
+        assert!(parse_diagnostics("author(a)-").is_empty());
+        assert!(parse_diagnostics("~author(a)").is_empty());
+    }
+
+    #[test]
+    fn test_alias_shadowing_builtin_function() {
+        let parse_diagnostics = |revset_str: &str, aliases: &[(&str, &str)]| {
+            let mut aliases_map = RevsetAliasesMap::new();
+            for &(decl, defn) in aliases {
+                aliases_map.insert(decl, defn).unwrap();
+            }
+            let context = RevsetParseContext {
+                aliases_map: &aliases_map,
+                local_variables: HashMap::new(),
+                user_email: "test.user@example.com",
+                date_pattern_context: chrono::Utc::now().fixed_offset().into(),
+                extensions: &RevsetExtensions::default(),
+                workspace: None,
+            };
+            let mut diagnostics = RevsetDiagnostics::new();
+            super::parse(&mut diagnostics, revset_str, &context).unwrap();
+            diagnostics.iter().map(|diag| diag.message()).collect_vec()
+        };
+
+        assert_eq!(
+            parse_diagnostics("all()", &[("all()", "root()")]),
+            ["Alias `all()` shadows the built-in function `all()`"]
+        );
+        assert_eq!(
+            parse_diagnostics("heads(a) | b", &[("heads(x)", "x")]),
+            ["Alias `heads(x)` shadows the built-in function `heads()`"]
+        );
+
+        // Warning within alias expansion
+        let diagnostics = parse_diagnostics("A", &[("A", "all()"), ("all()", "root()")]);
+        assert_eq!(diagnostics, ["In alias `A`"]);
+
+        // Symbol alias doesn't shadow function
+        assert!(parse_diagnostics("all", &[("all", "root()")]).is_empty());
+        assert!(parse_diagnostics("foo()", &[("foo()", "root()")]).is_empty());
+        // Unused alias isn't reported
+        assert!(parse_diagnostics("root()", &[("all()", "root()")]).is_empty());
+    }
+
+    #[test]
+    fn test_visit_children() {
+        // Sample visitor which collects bookmark names referenced by the
+        // expression.
+        fn collect_bookmark_names(expression: &UserRevsetExpression, names: &mut Vec<String>) {
+            match expression {
+                RevsetExpression::CommitRef(RevsetCommitRef::Bookmarks(pattern)) => {
+                    names.push(pattern.as_str().to_owned());
+                }
+                RevsetExpression::CommitRef(RevsetCommitRef::RemoteBookmarks {
+                    bookmark_pattern,
+                    ..
+                }) => {
+                    names.push(bookmark_pattern.as_str().to_owned());
+                }
+                RevsetExpression::CommitRef(RevsetCommitRef::RemoteSymbol(symbol)) => {
+                    names.push(symbol.name.as_str().to_owned());
+                }
+                _ => {}
+            }
+            expression.visit_children(|child| collect_bookmark_names(child, names));
+        }
+
+        let collect = |revset_str: &str| {
+            let mut names = vec![];
+            collect_bookmark_names(&parse(revset_str).unwrap(), &mut names);
+            names
+        };
+        assert!(collect("root() | visible_heads()").is_empty());
+        assert_eq!(collect("bookmarks(foo)"), ["foo"]);
+        assert_eq!(
+            collect("::bookmarks(foo) ~ (remote_bookmarks(bar) & baz@origin)"),
+            ["foo", "bar", "baz"]
+        );
+        assert_eq!(
+            collect("latest(heads(present(bookmarks(exact:foo)) | description(bar)))"),
+            ["foo"]
+        );
+    }
+
+    #[test]
+    fn test_map_children() {
+        // Rewrite all bookmarks() to tags() with the same pattern.
+        fn rewrite(expression: &Rc<UserRevsetExpression>) -> Option<Rc<UserRevsetExpression>> {
+            match expression.as_ref() {
+                RevsetExpression::CommitRef(RevsetCommitRef::Bookmarks(pattern)) => Some(Rc::new(
+                    RevsetExpression::CommitRef(RevsetCommitRef::Tags(pattern.clone())),
+                )),
+                _ => expression.map_children(rewrite),
+            }
+        }
+
+        let parsed = parse("root() | visible_heads()").unwrap();
+        assert!(rewrite(&parsed).is_none());
+
+        let parsed = parse("bookmarks(foo)::").unwrap();
+        insta::assert_debug_snapshot!(rewrite(&parsed).unwrap(), @r#"
+        Descendants {
+            roots: CommitRef(
+                Tags(
+                    Substring(
+                        "foo",
+                    ),
+                ),
+            ),
+            generation: 0..18446744073709551615,
+        }
+        "#);
+
+        // Unchanged subtree should be reused.
+        let parsed = parse("bookmarks(foo) | tags(bar)").unwrap();
+        let rewritten = rewrite(&parsed).unwrap();
+        let (RevsetExpression::Union(_, parsed_right), RevsetExpression::Union(_, rewritten_right)) =
+            (parsed.as_ref(), rewritten.as_ref())
+        else {
+            panic!("unexpected expression: {rewritten:?}");
+        };
+        assert!(Rc::ptr_eq(parsed_right, rewritten_right));
+    }
+
     #[test]
     fn test_optimize_subtree() {
         let settings = insta_settings();
@@ -4212,6 +6732,38 @@ mod tests {
         }
         "#);
 
+        // Unions of ancestors with the same generation are walked at once.
+        insta::assert_debug_snapshot!(optimize(parse("foo-- | bar--").unwrap()), @r#"
+        Ancestors {
+            heads: Union(
+                CommitRef(Symbol("foo")),
+                CommitRef(Symbol("bar")),
+            ),
+            generation: 2..3,
+        }
+        "#);
+        insta::assert_debug_snapshot!(optimize(parse("(foo- | bar-)--").unwrap()), @r#"
+        Ancestors {
+            heads: Union(
+                CommitRef(Symbol("foo")),
+                CommitRef(Symbol("bar")),
+            ),
+            generation: 3..4,
+        }
+        "#);
+        insta::assert_debug_snapshot!(optimize(parse("foo- | bar--").unwrap()), @r#"
+        Union(
+            Ancestors {
+                heads: CommitRef(Symbol("foo")),
+                generation: 1..2,
+            },
+            Ancestors {
+                heads: CommitRef(Symbol("bar")),
+                generation: 2..3,
+            },
+        )
+        "#);
+
         // Ancestors of empty generation range should be empty.
         insta::assert_debug_snapshot!(
             optimize(parse("ancestors(ancestors(foo), 0)").unwrap()), @r#"
@@ -4231,6 +6783,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_optimize_heads() {
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+
+        // Heads of all visible commits are the visible heads
+        insta::assert_debug_snapshot!(optimize(parse("heads(all())").unwrap()), @"VisibleHeads");
+        insta::assert_debug_snapshot!(
+            optimize(parse("heads(::visible_heads())").unwrap()), @"VisibleHeads");
+        insta::assert_debug_snapshot!(
+            optimize(parse("heads(visible_heads())").unwrap()), @"VisibleHeads");
+
+        // Ancestors don't have to be enumerated
+        insta::assert_debug_snapshot!(
+            optimize(parse("heads(::foo)").unwrap()), @r#"Heads(CommitRef(Symbol("foo")))"#);
+        insta::assert_debug_snapshot!(
+            optimize(parse("heads(ancestors(foo | bar, 3))").unwrap()), @r#"
+        Heads(
+            Union(
+                CommitRef(Symbol("foo")),
+                CommitRef(Symbol("bar")),
+            ),
+        )
+        "#);
+
+        // 'heads(::foo-)' is not 'heads(foo)'
+        insta::assert_debug_snapshot!(optimize(parse("heads(::foo-)").unwrap()), @r#"
+        Heads(
+            Ancestors {
+                heads: CommitRef(Symbol("foo")),
+                generation: 1..18446744073709551615,
+            },
+        )
+        "#);
+        insta::assert_debug_snapshot!(optimize(parse("heads(foo::)").unwrap()), @r#"
+        Heads(
+            Descendants {
+                roots: CommitRef(Symbol("foo")),
+                generation: 0..18446744073709551615,
+            },
+        )
+        "#);
+    }
+
     #[test]
     fn test_optimize_descendants() {
         let settings = insta_settings();
@@ -4267,6 +6863,26 @@ mod tests {
         }
         "#);
 
+        // Unions of descendants with the same generation are walked at once.
+        insta::assert_debug_snapshot!(optimize(parse("foo++ | bar++").unwrap()), @r#"
+        Descendants {
+            roots: Union(
+                CommitRef(Symbol("foo")),
+                CommitRef(Symbol("bar")),
+            ),
+            generation: 2..3,
+        }
+        "#);
+        insta::assert_debug_snapshot!(optimize(parse("(foo+ | bar+)::").unwrap()), @r#"
+        Descendants {
+            roots: Union(
+                CommitRef(Symbol("foo")),
+                CommitRef(Symbol("bar")),
+            ),
+            generation: 1..18446744073709551615,
+        }
+        "#);
+
         // TODO: Inner Descendants can be folded into DagRange. Perhaps, we can rewrite
         // 'x::y' to 'x:: & ::y' first, so the common substitution rule can handle both
         // 'x+::y' and 'x+ & ::y'.
@@ -4281,6 +6897,241 @@ mod tests {
         "#);
     }
 
+    #[test]
+    fn test_revset_expression_to_string() {
+        let to_string = |revset_str: &str| {
+            let expression = parse_with_workspace(revset_str, WorkspaceName::DEFAULT).unwrap();
+            expression.to_revset_string().unwrap()
+        };
+
+        assert_eq!(to_string("foo | bar & ~baz"), "foo | bar & ~baz");
+        assert_eq!(to_string("(x | y) & ~(z | w)"), "(x | y) & ~(z | w)");
+        assert_eq!(to_string("x | y | z"), "x | (y | z)");
+        assert_eq!(to_string("::x- | x+::"), "::x- | x+::");
+        assert_eq!(to_string("..x"), "root()..x");
+        assert_eq!(to_string("@"), "default@");
+        assert_eq!(
+            to_string(r#""local-remote@origin" | local-remote@origin"#),
+            r#""local-remote@origin" | local-remote@origin"#
+        );
+        assert_eq!(
+            to_string("author(foo)"),
+            r#"author_name(substring:"foo") | author_email(substring:"foo")"#
+        );
+        assert_eq!(to_string("bookmarks('')"), r#"bookmarks(substring:"")"#);
+        assert_eq!(
+            to_string("files(glob:'*.rs')"),
+            r#"files(root-glob:"*.rs")"#
+        );
+        assert_eq!(
+            to_string("author_date(after:'2023-03-25 12:00')"),
+            r#"author_date(after:"2023-03-25T12:00:00Z")"#
+        );
+        assert_eq!(
+            to_string("committer_date(during:'2023-03')"),
+            r#"committer_date(during:"2023-03-01T00:00:00Z..2023-04-01T00:00:00Z")"#
+        );
+
+        // Folded generations are formatted as equivalent expressions
+        let optimized_to_string = |revset_str: &str| {
+            let expression = optimize(parse(revset_str).unwrap());
+            expression.to_revset_string().unwrap()
+        };
+        assert_eq!(optimized_to_string("x--"), "x--");
+        assert_eq!(optimized_to_string("::x--"), "::x--");
+        assert_eq!(
+            optimized_to_string("ancestors(x--, 3)"),
+            "ancestors(x--, 3)"
+        );
+
+        // Extension predicates can't be formatted
+        #[derive(Debug)]
+        struct TestFilter;
+        impl RevsetFilterExtension for TestFilter {
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn matches_commit(&self, _commit: &Commit) -> bool {
+                true
+            }
+        }
+        let expression =
+            RevsetExpression::filter(RevsetFilterPredicate::Extension(Rc::new(TestFilter)));
+        assert_eq!(
+            expression
+                .union(&RevsetExpression::root())
+                .to_revset_string(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_revset_expression_to_string_round_trip() {
+        let parse = |revset_str: &str| parse_with_workspace(revset_str, WorkspaceName::DEFAULT);
+        for revset_str in [
+            "none()",
+            "all()",
+            "root()",
+            "visible_heads()",
+            "working_copies()",
+            "working_copies(glob:'feature-*')",
+            "@",
+            "main@",
+            "'my workspace'@",
+            "foo@origin",
+            r#""local-remote@origin""#,
+            r#""""#,
+            "'foo bar'",
+            "x-",
+            "x+",
+            "x--",
+            "x++",
+            "::x",
+            "x::",
+            "x::y",
+            "x..y",
+            "..x",
+            "x..",
+            "::",
+            "..",
+            "~x",
+            "~~x",
+            "x & ~y",
+            "x ~ y",
+            "x ~ (y ~ z)",
+            "x | y | z",
+            "(x | y) & z",
+            "x & (y | z)",
+            "::x- & y+::",
+            "~(x::y)",
+            "parents(x)",
+            "children(x)",
+            "ancestors(x, 2)",
+            "descendants(x--, 3)",
+            "heads(x)",
+            "roots(x)",
+            "connected(x)",
+            "reachable(x, y)",
+            "fork_point(x | y)",
+            "latest(x, 3)",
+            "present(x)",
+            "coalesce(x, y, z)",
+            "at_operation(@-, x)",
+            "bookmarks()",
+            "bookmarks(exact:main)",
+            "remote_bookmarks(remote=origin)",
+            "tracked_remote_bookmarks(glob:'fe*')",
+            "untracked_remote_bookmarks(foo, remote=exact:'')",
+            "tags(regex:'^v[0-9]+')",
+            "git_refs()",
+            "git_head()",
+            "commit_id(abc0)",
+            "change_id('zvz')",
+            "commit_id(glob:'abc*')",
+            "change_id(glob:'zvz*')",
+            "merges()",
+            "description(exact-i:'')",
+            "description(exact:foo, regex:'a|b', bar)",
+            "subject(substring-i:foo)",
+            "author(glob-i:'*@example.com')",
+            "committer(foo)",
+            "mine()",
+            "author_date(after:'2023-03-25 12:00')",
+            "committer_date(before:'2023-03-25 12:00:30')",
+            "committer_date(during:'2023-03')",
+            "author_date(during:'2023-03-25 12:00..2023-03-26')",
+            "empty()",
+            "conflicts()",
+            "signed()",
+            "signed(after:'2024-01-01')",
+            "pushed()",
+            "files(foo)",
+            "files(root-file:'a/b')",
+            "files(glob:'*.rs' | ~root:lib)",
+            "files((a | b) & c)",
+            "files(foo, parents:first)",
+            "diff_contains(foo)",
+            "diff_contains(regex:'a|b', glob:'src/**/*.rs')",
+            "lines_changed(..)",
+            "lines_changed(100..)",
+            "lines_changed(..10, foo)",
+            "lines_changed(3)",
+        ] {
+            let expression = parse(revset_str).unwrap();
+            let text = expression.to_revset_string().unwrap();
+            let reparsed = parse(&text).unwrap();
+            assert_eq!(
+                format!("{reparsed:?}"),
+                format!("{expression:?}"),
+                "{revset_str:?} was formatted as {text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolved_expression_to_canonical_string() {
+        let commits = |hexes: &[&'static str]| {
+            let commit_ids = hexes.iter().map(|&hex| CommitId::from_hex(hex)).collect();
+            ResolvedRevsetExpression::commits(commit_ids)
+        };
+        let canonical =
+            |expression: &Rc<ResolvedRevsetExpression>| expression.to_canonical_string();
+        let a = commits(&["aaaa"]);
+        let b = commits(&["bbbb"]);
+        let c = commits(&["cccc"]);
+        let conflicts = ResolvedRevsetExpression::filter(RevsetFilterPredicate::HasConflict);
+
+        // Union operands are sorted and deduplicated
+        assert_eq!(canonical(&a.union(&b)), "union(aaaa, bbbb)");
+        assert_eq!(canonical(&b.union(&a)), canonical(&a.union(&b)));
+        assert_eq!(
+            canonical(&commits(&["bbbb", "aaaa", "bbbb"])),
+            canonical(&a.union(&b))
+        );
+        assert_eq!(
+            canonical(&a.union(&b).union(&c)),
+            canonical(&c.union(&b.union(&a)))
+        );
+        assert_eq!(
+            canonical(&conflicts.union(&a.heads())),
+            "union(conflicts(), heads(aaaa))"
+        );
+        assert_eq!(
+            canonical(&a.heads().union(&conflicts)),
+            canonical(&conflicts.union(&a.heads()))
+        );
+        assert_eq!(
+            canonical(&a.union(&ResolvedRevsetExpression::none())),
+            "aaaa"
+        );
+
+        // Intersection operands are sorted, and difference is flattened
+        assert_eq!(
+            canonical(&a.intersection(&b)),
+            canonical(&b.intersection(&a))
+        );
+        assert_eq!(
+            canonical(&a.minus(&b)),
+            canonical(&b.negated().intersection(&a))
+        );
+        assert_eq!(canonical(&a.minus(&b)), "intersection(aaaa, not(bbbb))");
+
+        // Genuinely different expressions
+        assert_ne!(canonical(&a.union(&b)), canonical(&a.intersection(&b)));
+        assert_ne!(canonical(&a.union(&b)), canonical(&a.union(&c)));
+        assert_ne!(canonical(&a.minus(&b)), canonical(&b.minus(&a)));
+        assert_ne!(canonical(&a.union(&b)), canonical(&a.union(&b).heads()));
+        assert_ne!(
+            canonical(&a.ancestors()),
+            canonical(&a.ancestors_range(0..2))
+        );
+        assert_ne!(
+            canonical(&ResolvedRevsetExpression::coalesce(&[a.clone(), b.clone()])),
+            canonical(&ResolvedRevsetExpression::coalesce(&[b.clone(), a.clone()]))
+        );
+    }
+
     #[test]
     fn test_escape_string_literal() {
         // Valid identifiers don't need quoting
@@ -14,9 +14,11 @@
 
 #![allow(missing_docs)]
 
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::error;
 use std::mem;
+use std::ops::Range;
 use std::str::FromStr;
 
 use itertools::Itertools as _;
@@ -39,6 +41,7 @@ use crate::dsl_util::AliasExpandError;
 use crate::dsl_util::AliasExpandableExpression;
 use crate::dsl_util::AliasId;
 use crate::dsl_util::AliasesMap;
+use crate::dsl_util::DiagnosticMessage;
 use crate::dsl_util::Diagnostics;
 use crate::dsl_util::ExpressionFolder;
 use crate::dsl_util::FoldableExpression;
@@ -84,6 +87,7 @@ impl Rule {
         match self {
             Rule::EOI => None,
             Rule::whitespace => None,
+            Rule::comment => None,
             Rule::identifier_part => None,
             Rule::identifier => None,
             Rule::strict_identifier_part => None,
@@ -127,6 +131,7 @@ impl Rule {
             Rule::function_arguments => None,
             Rule::formal_parameters => None,
             Rule::string_pattern => None,
+            Rule::let_expression => None,
             Rule::primary => None,
             Rule::neighbors_expression => None,
             Rule::range_expression => None,
@@ -181,6 +186,8 @@ pub enum RevsetParseErrorKind {
         name: String,
         candidates: Vec<String>,
     },
+    #[error("Function `{name}` doesn't exist")]
+    RenamedFunction { name: String, new_name: String },
     #[error("Function `{name}`: {message}")]
     InvalidFunctionArguments { name: String, message: String },
     #[error("Cannot resolve file pattern without workspace")]
@@ -195,8 +202,10 @@ pub enum RevsetParseErrorKind {
     InAliasExpansion(String),
     #[error("In function parameter `{0}`")]
     InParameterExpansion(String),
-    #[error("Alias `{0}` expanded recursively")]
-    RecursiveAlias(String),
+    #[error("Alias `{name}` expanded recursively: {}", chain.join(" -> "))]
+    RecursiveAlias { name: String, chain: Vec<String> },
+    #[error("Alias expansion exceeded the maximum depth of {0}")]
+    AliasExpansionDepthExceeded(usize),
 }
 
 impl RevsetParseError {
@@ -246,29 +255,63 @@ impl RevsetParseError {
         &self.kind
     }
 
+    /// Error message without the source snippet.
+    pub fn message(&self) -> String {
+        self.pest_error.variant.message().into_owned()
+    }
+
+    /// Byte range in the source text where the error occurred.
+    pub fn span(&self) -> Range<usize> {
+        match self.pest_error.location {
+            pest::error::InputLocation::Pos(pos) => pos..pos,
+            pest::error::InputLocation::Span((start, end)) => start..end,
+        }
+    }
+
     /// Original parsing error which typically occurred in an alias expression.
     pub fn origin(&self) -> Option<&Self> {
         self.source.as_ref().and_then(|e| e.downcast_ref())
     }
 }
 
+impl DiagnosticMessage for RevsetParseError {
+    fn is_same_diagnostic(&self, other: &Self) -> bool {
+        self.kind == other.kind && dsl_util::has_same_error_sources(self, other)
+    }
+}
+
 impl AliasExpandError for RevsetParseError {
     fn invalid_arguments(err: InvalidArguments<'_>) -> Self {
         err.into()
     }
 
-    fn recursive_expansion(id: AliasId<'_>, span: pest::Span<'_>) -> Self {
-        Self::with_span(RevsetParseErrorKind::RecursiveAlias(id.to_string()), span)
+    fn recursive_expansion(id: AliasId<'_>, chain: &[AliasId<'_>], span: pest::Span<'_>) -> Self {
+        let kind = RevsetParseErrorKind::RecursiveAlias {
+            name: id.to_string(),
+            chain: chain.iter().map(|id| id.to_string()).collect(),
+        };
+        Self::with_span(kind, span)
+    }
+
+    fn expansion_depth_exceeded(max_depth: usize, span: pest::Span<'_>) -> Self {
+        Self::with_span(
+            RevsetParseErrorKind::AliasExpansionDepthExceeded(max_depth),
+            span,
+        )
     }
 
     fn within_alias_expansion(self, id: AliasId<'_>, span: pest::Span<'_>) -> Self {
-        let kind = match id {
-            AliasId::Symbol(_) | AliasId::Function(..) => {
-                RevsetParseErrorKind::InAliasExpansion(id.to_string())
-            }
-            AliasId::Parameter(_) => RevsetParseErrorKind::InParameterExpansion(id.to_string()),
-        };
-        Self::with_span(kind, span).with_source(self)
+        Self::with_span(alias_expansion_error_kind(id), span).with_source(self)
+    }
+}
+
+/// Returns the kind of error to be chained to errors within the alias `id`.
+pub(super) fn alias_expansion_error_kind(id: AliasId<'_>) -> RevsetParseErrorKind {
+    match id {
+        AliasId::Symbol(_) | AliasId::Function(..) => {
+            RevsetParseErrorKind::InAliasExpansion(id.to_string())
+        }
+        AliasId::Parameter(_) => RevsetParseErrorKind::InParameterExpansion(id.to_string()),
     }
 }
 
@@ -344,6 +387,8 @@ pub enum ExpressionKind<'i> {
     FunctionCall(Box<FunctionCallNode<'i>>),
     /// `name: body`
     Modifier(Box<ModifierNode<'i>>),
+    /// `let(name = value, body)`
+    Let(Box<LetBindingNode<'i>>),
     /// Identity node to preserve the span in the source text.
     AliasExpanded(AliasId<'i>, Box<ExpressionNode<'i>>),
 }
@@ -384,6 +429,15 @@ impl<'i> FoldableExpression<'i> for ExpressionKind<'i> {
                 });
                 Ok(ExpressionKind::Modifier(modifier))
             }
+            ExpressionKind::Let(binding) => {
+                let binding = Box::new(LetBindingNode {
+                    name: binding.name,
+                    name_span: binding.name_span,
+                    value: folder.fold_expression(binding.value)?,
+                    body: folder.fold_expression(binding.body)?,
+                });
+                Ok(ExpressionKind::Let(binding))
+            }
             ExpressionKind::AliasExpanded(id, subst) => {
                 let subst = Box::new(folder.fold_expression(*subst)?);
                 Ok(ExpressionKind::AliasExpanded(id, subst))
@@ -450,6 +504,19 @@ pub struct ModifierNode<'i> {
     pub body: ExpressionNode<'i>,
 }
 
+/// Expression node of `let(name = value, body)`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LetBindingNode<'i> {
+    /// Variable name.
+    pub name: &'i str,
+    /// Span of the variable name.
+    pub name_span: pest::Span<'i>,
+    /// Expression bound to the variable.
+    pub value: ExpressionNode<'i>,
+    /// Expression in which the variable is visible.
+    pub body: ExpressionNode<'i>,
+}
+
 fn union_nodes<'i>(lhs: ExpressionNode<'i>, rhs: ExpressionNode<'i>) -> ExpressionNode<'i> {
     let span = lhs.span.start_pos().span(&rhs.span.end_pos());
     let expr = match lhs.kind {
@@ -627,10 +694,29 @@ fn parse_primary_node(pair: Pair<Rule>) -> Result<ExpressionNode, RevsetParseErr
             let function = Box::new(FUNCTION_CALL_PARSER.parse(
                 first,
                 |pair| Ok(pair.as_str()),
-                |pair| parse_expression_node(pair.into_inner()),
+                |pair| -> Result<_, RevsetParseError> {
+                    // The span of a parenthesized operand excludes the
+                    // parentheses. Use the whole argument span so the text
+                    // can be reparsed (e.g. as a fileset expression.)
+                    let span = pair.as_span();
+                    let mut node = parse_expression_node(pair.into_inner())?;
+                    node.span = span;
+                    Ok(node)
+                },
             )?);
             ExpressionKind::FunctionCall(function)
         }
+        Rule::let_expression => {
+            let (name_pair, value_pair, body_pair) = first.into_inner().collect_tuple().unwrap();
+            assert_eq!(name_pair.as_rule(), Rule::strict_identifier);
+            let binding = Box::new(LetBindingNode {
+                name: name_pair.as_str(),
+                name_span: name_pair.as_span(),
+                value: parse_expression_node(value_pair.into_inner())?,
+                body: parse_expression_node(body_pair.into_inner())?,
+            });
+            ExpressionKind::Let(binding)
+        }
         Rule::string_pattern => {
             let (lhs, op, rhs) = first.into_inner().collect_tuple().unwrap();
             assert_eq!(lhs.as_rule(), Rule::strict_identifier);
@@ -753,7 +839,94 @@ impl AliasDefinitionParser for RevsetAliasParser {
     type Error = RevsetParseError;
 
     fn parse_definition<'i>(&self, source: &'i str) -> Result<ExpressionNode<'i>, Self::Error> {
-        parse_program(source)
+        let node = parse_program(source)?;
+        // Shadowing of alias parameters isn't reported.
+        let mut diagnostics = RevsetDiagnostics::new();
+        expand_let_bindings(&mut diagnostics, node, &HashMap::new())
+    }
+}
+
+/// Substitutes variables bound by `let(name = value, body)` expressions.
+///
+/// The bound names take precedence over the `outer_locals` and alias symbols.
+/// Substituted values are wrapped in `AliasExpanded` nodes in the same way as
+/// the local variables substituted by the alias expansion.
+pub fn expand_let_bindings<'i>(
+    diagnostics: &mut RevsetDiagnostics,
+    node: ExpressionNode<'i>,
+    outer_locals: &HashMap<&'i str, ExpressionNode<'i>>,
+) -> Result<ExpressionNode<'i>, RevsetParseError> {
+    let mut expander = LetBindingExpander {
+        diagnostics,
+        outer_locals,
+        bindings: Vec::new(),
+    };
+    expander.fold_expression(node)
+}
+
+#[derive(Debug)]
+struct LetBindingExpander<'i, 'a> {
+    diagnostics: &'a mut RevsetDiagnostics,
+    outer_locals: &'a HashMap<&'i str, ExpressionNode<'i>>,
+    /// Stack of variables currently in scope. The innermost binding is last.
+    bindings: Vec<(&'i str, ExpressionNode<'i>)>,
+}
+
+impl<'i> ExpressionFolder<'i, ExpressionKind<'i>> for LetBindingExpander<'i, '_> {
+    type Error = RevsetParseError;
+
+    fn fold_expression(
+        &mut self,
+        node: ExpressionNode<'i>,
+    ) -> Result<ExpressionNode<'i>, Self::Error> {
+        let binding = match node.kind {
+            ExpressionKind::Let(binding) => binding,
+            kind => {
+                let kind = kind.fold(self, node.span)?;
+                return Ok(ExpressionNode::new(kind, node.span));
+            }
+        };
+        let LetBindingNode {
+            name,
+            name_span,
+            value,
+            body,
+        } = *binding;
+        // The value is resolved in the enclosing scope.
+        let value = self.fold_expression(value)?;
+        if self.bindings.iter().any(|&(n, _)| n == name) || self.outer_locals.contains_key(name) {
+            self.diagnostics.add_warning(RevsetParseError::expression(
+                format!("Variable `{name}` shadows an outer variable"),
+                name_span,
+            ));
+        }
+        self.bindings.push((name, value));
+        let body = self.fold_expression(body);
+        self.bindings.pop();
+        body
+    }
+
+    fn fold_identifier(
+        &mut self,
+        name: &'i str,
+        _span: pest::Span<'i>,
+    ) -> Result<ExpressionKind<'i>, Self::Error> {
+        match self.bindings.iter().rev().find(|&&(n, _)| n == name) {
+            Some((_, value)) => {
+                let id = AliasId::Parameter(name);
+                Ok(ExpressionKind::AliasExpanded(id, Box::new(value.clone())))
+            }
+            None => Ok(ExpressionKind::Identifier(name)),
+        }
+    }
+
+    fn fold_function_call(
+        &mut self,
+        function: Box<FunctionCallNode<'i>>,
+        _span: pest::Span<'i>,
+    ) -> Result<ExpressionKind<'i>, Self::Error> {
+        let function = Box::new(dsl_util::fold_function_call_args(self, *function)?);
+        Ok(ExpressionKind::FunctionCall(function))
     }
 }
 
@@ -844,6 +1017,7 @@ pub(super) fn expect_expression_with<T>(
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
+    use std::iter;
 
     use assert_matches::assert_matches;
 
@@ -862,8 +1036,15 @@ mod tests {
             self
         }
 
+        fn set_max_expansion_depth(mut self, depth: usize) -> Self {
+            self.aliases_map.set_max_expansion_depth(depth);
+            self
+        }
+
         fn parse(&'i self, text: &'i str) -> Result<ExpressionNode<'i>, RevsetParseError> {
             let node = parse_program(text)?;
+            let mut diagnostics = RevsetDiagnostics::new();
+            let node = expand_let_bindings(&mut diagnostics, node, &self.locals)?;
             dsl_util::expand_aliases_with_locals(node, &self.aliases_map, &self.locals)
         }
 
@@ -957,6 +1138,15 @@ mod tests {
                 });
                 ExpressionKind::Modifier(modifier)
             }
+            ExpressionKind::Let(binding) => {
+                let binding = Box::new(LetBindingNode {
+                    name: binding.name,
+                    name_span: empty_span(),
+                    value: normalize_tree(binding.value),
+                    body: normalize_tree(binding.body),
+                });
+                ExpressionKind::Let(binding)
+            }
             ExpressionKind::AliasExpanded(_, subst) => normalize_tree(*subst).kind,
         };
         ExpressionNode {
@@ -1182,6 +1372,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_comment() {
+        assert_eq!(
+            parse_normalized("x # comment\n| y"),
+            parse_normalized("x | y"),
+        );
+        assert_eq!(
+            parse_normalized("# leading\nfoo(\n  x, # first\n  y,\n) # trailing"),
+            parse_normalized("foo(x, y)"),
+        );
+        assert_eq!(parse_normalized("x #\r\n& y"), parse_normalized("x & y"),);
+        // "#" in string literal isn't a comment
+        assert_eq!(
+            parse_into_kind("'#x'"),
+            Ok(ExpressionKind::String("#x".to_owned()))
+        );
+        // Everything after "#" is commented out
+        assert!(parse_program("x # | y)").is_ok());
+        assert!(parse_program("(x # )").is_err());
+    }
+
     #[test]
     fn test_parse_identifier() {
         // Integer is a symbol
@@ -1710,6 +1921,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_expand_alias_recursion() {
+        fn bottom_error(err: &RevsetParseError) -> &RevsetParseError {
+            iter::successors(Some(err), |e| e.origin()).last().unwrap()
+        }
+        fn recursive_alias(name: &str, chain: &[&str]) -> RevsetParseErrorKind {
+            RevsetParseErrorKind::RecursiveAlias {
+                name: name.to_owned(),
+                chain: chain.iter().map(|&s| s.to_owned()).collect(),
+            }
+        }
+
+        // Direct cycle
+        let err = with_aliases([("A", "A | foo")]).parse("A").unwrap_err();
+        assert_eq!(*bottom_error(&err).kind, recursive_alias("A", &["A", "A"]));
+        assert_eq!(
+            bottom_error(&err).message(),
+            "Alias `A` expanded recursively: A -> A"
+        );
+
+        // Indirect cycle
+        let err = with_aliases([("A", "B"), ("B", "b|C"), ("C", "c|B")])
+            .parse("A")
+            .unwrap_err();
+        assert_eq!(
+            *bottom_error(&err).kind,
+            recursive_alias("B", &["A", "B", "C", "B"])
+        );
+
+        // Function aliases participate in the same cycle detection
+        let err = with_aliases([("F(x)", "G(x)"), ("G(y)", "x | F(y)")])
+            .parse("F(a)")
+            .unwrap_err();
+        assert_eq!(
+            *bottom_error(&err).kind,
+            recursive_alias("F(x)", &["F(x)", "G(y)", "F(x)"])
+        );
+        let err = with_aliases([("A", "F(a)"), ("F(x)", "x | A")])
+            .parse("A")
+            .unwrap_err();
+        assert_eq!(
+            *bottom_error(&err).kind,
+            recursive_alias("A", &["A", "F(x)", "A"])
+        );
+
+        // Same function alias can be nested in arguments
+        assert_eq!(
+            with_aliases([("F(x)", "x & f")]).parse_normalized("F(F(a))"),
+            parse_normalized("(a & f) & f")
+        );
+
+        // Max expansion depth
+        let aliases = [("A", "B"), ("B", "C"), ("C", "c")];
+        assert_eq!(
+            with_aliases(aliases)
+                .set_max_expansion_depth(3)
+                .parse_normalized("A"),
+            parse_normalized("c")
+        );
+        let err = with_aliases(aliases)
+            .set_max_expansion_depth(2)
+            .parse("A")
+            .unwrap_err();
+        assert_eq!(
+            *bottom_error(&err).kind,
+            RevsetParseErrorKind::AliasExpansionDepthExceeded(2)
+        );
+    }
+
     #[test]
     fn test_expand_function_alias() {
         assert_eq!(
@@ -1870,4 +2150,173 @@ mod tests {
             parse_normalized("a|A|(a&A)")
         );
     }
+
+    #[test]
+    fn test_expand_let_binding() {
+        assert_eq!(
+            with_aliases([("A", "symbol")]).parse_normalized("let(x = a & b, heads(x) | roots(x))"),
+            parse_normalized("heads(a & b) | roots(a & b)")
+        );
+        assert_eq!(
+            with_aliases([("A", "symbol")]).parse_normalized("let( x = a , x , )"),
+            parse_normalized("a")
+        );
+
+        // Bound variable should precede the symbol alias and the outer local
+        // variable, but the bound value is resolved in the enclosing scope.
+        assert_eq!(
+            with_aliases([("A", "symbol")]).parse_normalized("let(A = a, A)"),
+            parse_normalized("a")
+        );
+        assert_eq!(
+            with_aliases([("A", "symbol")]).parse_normalized("let(A = A, A)"),
+            parse_normalized("symbol")
+        );
+        assert_eq!(
+            with_aliases([("A", "symbol")])
+                .set_local("x", "local")
+                .parse_normalized("let(x = x | a, x)"),
+            parse_normalized("local | a")
+        );
+
+        // Nested bindings
+        assert_eq!(
+            with_aliases([("A", "symbol")])
+                .parse_normalized("let(x = a, let(y = x & b, let(x = y, x | y)))"),
+            parse_normalized("(a & b) | (a & b)")
+        );
+        assert_eq!(
+            with_aliases([("A", "symbol")]).parse_normalized("let(x = let(y = a, y), x)"),
+            parse_normalized("a")
+        );
+        assert_eq!(
+            with_aliases([("A", "symbol")]).parse_normalized("let(x = a, let(y = b, x)) | y"),
+            parse_normalized("a | y")
+        );
+
+        // Binding used within function arguments and alias arguments
+        assert_eq!(
+            with_aliases([("A", "symbol"), ("F(p)", "p & A")])
+                .parse_normalized("let(x = a, ancestors(x, depth = x) | F(x))"),
+            parse_normalized("ancestors(a, depth = a) | (a & symbol)")
+        );
+
+        // Bound variable shouldn't be visible within aliases.
+        assert_eq!(
+            with_aliases([("B", "x"), ("F(p)", "p & x")]).parse_normalized("let(x = a, B | F(x))"),
+            parse_normalized("x | (a & x)")
+        );
+        // Binding within alias definition
+        assert_eq!(
+            with_aliases([("F(p)", "let(x = p & a, x | b)")]).parse_normalized("F(c)"),
+            parse_normalized("(c & a) | b")
+        );
+
+        // Not a binding, but a function call
+        assert_matches!(
+            parse_into_kind("let(x, y)"),
+            Ok(ExpressionKind::FunctionCall(_))
+        );
+        assert_matches!(
+            parse_into_kind("let(x = a)"),
+            Ok(ExpressionKind::FunctionCall(_))
+        );
+    }
+
+    #[test]
+    fn test_let_binding_shadowing_diagnostics() {
+        let parse_diagnostics = |text: &str, locals: &[&str]| {
+            let locals: HashMap<_, _> = locals
+                .iter()
+                .map(|&name| (name, parse_program("local").unwrap()))
+                .collect();
+            let mut diagnostics = RevsetDiagnostics::new();
+            let node = parse_program(text).unwrap();
+            expand_let_bindings(&mut diagnostics, node, &locals).unwrap();
+            diagnostics.iter().map(|diag| diag.message()).collect_vec()
+        };
+
+        assert!(parse_diagnostics("let(x = a, let(y = x, y))", &[]).is_empty());
+        assert!(parse_diagnostics("let(x = a, x) | let(x = b, x)", &[]).is_empty());
+        assert_eq!(
+            parse_diagnostics("let(x = a, let(x = b, x))", &[]),
+            ["Variable `x` shadows an outer variable"]
+        );
+        assert_eq!(
+            parse_diagnostics("let(x = a, f(let(x = b, x)))", &[]),
+            ["Variable `x` shadows an outer variable"]
+        );
+        assert_eq!(
+            parse_diagnostics("let(x = a, x)", &["x"]),
+            ["Variable `x` shadows an outer variable"]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_dedup() {
+        let text = "x | y";
+        let span = |start, end| pest::Span::new(text, start, end).unwrap();
+        let mut diagnostics = RevsetDiagnostics::new();
+        for _ in 0..1000 {
+            diagnostics.add_warning(RevsetParseError::expression("same", span(0, 1)));
+        }
+        diagnostics.add_warning(RevsetParseError::expression("same", span(4, 5)));
+        diagnostics.add_warning(RevsetParseError::expression("other", span(0, 1)));
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics
+                .iter_with_counts()
+                .map(|(diag, count)| (diag.message(), diag.span(), count))
+                .collect_vec(),
+            [
+                ("same".to_owned(), 0..1, 1001),
+                ("other".to_owned(), 0..1, 1)
+            ]
+        );
+
+        // Warnings with different sources aren't merged
+        let mut diagnostics = RevsetDiagnostics::new();
+        for source in ["a", "a", "b"] {
+            let diag = RevsetParseError::with_span(
+                RevsetParseErrorKind::InAliasExpansion("f()".to_owned()),
+                span(0, 1),
+            )
+            .with_source(RevsetParseError::expression(source, span(4, 5)));
+            diagnostics.add_warning(diag);
+        }
+        assert_eq!(
+            diagnostics
+                .iter_with_counts()
+                .map(|(_, count)| count)
+                .collect_vec(),
+            [2, 1]
+        );
+
+        // Counts are preserved when moving diagnostics
+        let mut outer_diagnostics = RevsetDiagnostics::new();
+        outer_diagnostics.add_warning(RevsetParseError::expression("f()", span(0, 1)));
+        outer_diagnostics.extend_with(diagnostics, |diag| diag);
+        assert_eq!(
+            outer_diagnostics
+                .iter_with_counts()
+                .map(|(_, count)| count)
+                .collect_vec(),
+            [1, 2, 1]
+        );
+    }
+
+    #[test]
+    fn test_let_binding_shadowing_diagnostics_dedup() {
+        let mut diagnostics = RevsetDiagnostics::new();
+        let text = "let(x = a, let(x = b, x)) | let(x = a, let(x = b, x))";
+        let node = parse_program(text).unwrap();
+        expand_let_bindings(&mut diagnostics, node, &HashMap::new()).unwrap();
+        assert_eq!(
+            diagnostics
+                .iter_with_counts()
+                .map(|(diag, count)| (diag.message(), count))
+                .collect_vec(),
+            [("Variable `x` shadows an outer variable".to_owned(), 2)]
+        );
+    }
 }
@@ -28,6 +28,7 @@ use tracing::instrument;
 use crate::backend::BackendError;
 use crate::backend::BackendResult;
 use crate::backend::CommitId;
+use crate::backend::DuplicateSource;
 use crate::backend::MergedTreeId;
 use crate::commit::Commit;
 use crate::commit::CommitIteratorExt as _;
@@ -847,9 +848,14 @@ pub fn duplicate_commits(
                 })
                 .collect()
         };
+        let source = DuplicateSource {
+            commit_id: original_commit_id.clone(),
+            change_id: original_commit.change_id().clone(),
+        };
         let new_commit = CommitRewriter::new(mut_repo, original_commit, new_parent_ids)
             .rebase()?
             .generate_new_change_id()
+            .set_duplicated_from(Some(source))
             .write()?;
         duplicated_old_to_new.insert(original_commit_id.clone(), new_commit);
     }
@@ -930,6 +936,10 @@ pub fn duplicate_commits_onto_parents(
             .rewrite_commit(&original_commit)
             .generate_new_change_id()
             .set_parents(new_parent_ids)
+            .set_duplicated_from(Some(DuplicateSource {
+                commit_id: original_commit_id.clone(),
+                change_id: original_commit.change_id().clone(),
+            }))
             .write()?;
         duplicated_old_to_new.insert(original_commit_id.clone(), new_commit);
     }
@@ -20,6 +20,9 @@ use std::sync::Arc;
 use std::sync::Mutex;
 
 use chrono::DateTime;
+use chrono::FixedOffset;
+use jiff::tz::Offset;
+use jiff::tz::TimeZone;
 use rand::prelude::*;
 use rand_chacha::ChaCha20Rng;
 use serde::Deserialize;
@@ -50,11 +53,14 @@ struct UserSettingsData {
     user_name: String,
     user_email: String,
     commit_timestamp: Option<Timestamp>,
+    commit_timezone: Option<TimeZone>,
+    current_timestamp: Option<Timestamp>,
     operation_timestamp: Option<Timestamp>,
     operation_hostname: String,
     operation_username: String,
     signing_behavior: SignBehavior,
     signing_key: Option<String>,
+    signing_passphrase_command: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone)]
@@ -66,7 +72,8 @@ pub struct GitSettings {
     #[cfg(feature = "git2")]
     pub subprocess: bool,
     pub executable_path: PathBuf,
-    pub change_id: bool,
+    pub change_id_location: ChangeIdLocation,
+    pub import_rewrites_by_change_id: bool,
 }
 
 impl GitSettings {
@@ -77,7 +84,8 @@ impl GitSettings {
             #[cfg(feature = "git2")]
             subprocess: settings.get_bool("git.subprocess")?,
             executable_path: settings.get("git.executable-path")?,
-            change_id: settings.get("git.write-change-id-header")?,
+            change_id_location: settings.get("git.change-id-location")?,
+            import_rewrites_by_change_id: settings.get_bool("git.import-rewrites-by-change-id")?,
         })
     }
 }
@@ -90,11 +98,24 @@ impl Default for GitSettings {
             #[cfg(feature = "git2")]
             subprocess: true,
             executable_path: PathBuf::from("git"),
-            change_id: false,
+            change_id_location: ChangeIdLocation::None,
+            import_rewrites_by_change_id: false,
         }
     }
 }
 
+/// Where to store the change id in Git commit objects.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeIdLocation {
+    /// Store the change id in the `change-id` commit header.
+    Header,
+    /// Store the change id as a trailer of the commit message.
+    Trailer,
+    /// Don't store the change id.
+    None,
+}
+
 /// Commit signing settings, describes how to and if to sign commits.
 #[derive(Debug, Clone)]
 pub struct SignSettings {
@@ -105,6 +126,9 @@ pub struct SignSettings {
     pub user_email: String,
     /// The signing backend specific key, to be passed to the signing backend.
     pub key: Option<String>,
+    /// Program and arguments to run to get the passphrase of the key. The
+    /// passphrase is read from the standard output of the command.
+    pub passphrase_command: Option<Vec<String>>,
 }
 
 impl SignSettings {
@@ -137,6 +161,21 @@ fn to_timestamp(value: ConfigValue) -> Result<Timestamp, Box<dyn std::error::Err
     }
 }
 
+fn to_timezone(value: ConfigValue) -> Result<TimeZone, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(s) = value.as_str() else {
+        let ty = value.type_name();
+        return Err(format!("invalid type: {ty}, expected a string").into());
+    };
+    if s.starts_with(['+', '-']) {
+        let offset: FixedOffset = s.parse()?;
+        Ok(TimeZone::fixed(Offset::from_seconds(
+            offset.local_minus_utc(),
+        )?))
+    } else {
+        Ok(TimeZone::get(s)?)
+    }
+}
+
 impl UserSettings {
     pub fn from_config(config: StackedConfig) -> Result<Self, ConfigGetError> {
         let rng_seed = config.get::<u64>("debug.randomness-seed").optional()?;
@@ -149,22 +188,34 @@ impl UserSettings {
         let commit_timestamp = config
             .get_value_with("debug.commit-timestamp", to_timestamp)
             .optional()?;
+        let commit_timezone = config
+            .get_value_with("user.timezone", to_timezone)
+            .optional()?;
+        let current_timestamp = config
+            .get_value_with("debug.current-timestamp", to_timestamp)
+            .optional()?;
+        // Operations made while the clock is pinned are stamped with it too.
         let operation_timestamp = config
             .get_value_with("debug.operation-timestamp", to_timestamp)
-            .optional()?;
+            .optional()?
+            .or(current_timestamp);
         let operation_hostname = config.get("operation.hostname")?;
         let operation_username = config.get("operation.username")?;
         let signing_behavior = config.get("signing.behavior")?;
         let signing_key = config.get("signing.key").optional()?;
+        let signing_passphrase_command = config.get("signing.passphrase-command").optional()?;
         let data = UserSettingsData {
             user_name,
             user_email,
             commit_timestamp,
+            commit_timezone,
+            current_timestamp,
             operation_timestamp,
             operation_hostname,
             operation_username,
             signing_behavior,
             signing_key,
+            signing_passphrase_command,
         };
         Ok(UserSettings {
             config: Arc::new(config),
@@ -208,6 +259,12 @@ impl UserSettings {
         self.data.commit_timestamp
     }
 
+    /// Returns the time pinned by `debug.current-timestamp`, which should be
+    /// used in place of the system time when evaluating relative dates.
+    pub fn current_timestamp(&self) -> Option<Timestamp> {
+        self.data.current_timestamp
+    }
+
     pub fn operation_timestamp(&self) -> Option<Timestamp> {
         self.data.operation_timestamp
     }
@@ -220,8 +277,15 @@ impl UserSettings {
         &self.data.operation_username
     }
 
+    /// Returns the user signature with the current (or `debug.commit-timestamp`)
+    /// time. If `user.timezone` is set, the time is expressed in that zone.
     pub fn signature(&self) -> Signature {
-        let timestamp = self.data.commit_timestamp.unwrap_or_else(Timestamp::now);
+        let mut timestamp = self.data.commit_timestamp.unwrap_or_else(Timestamp::now);
+        if let Some(zone) = &self.data.commit_timezone {
+            if let Ok(instant) = jiff::Timestamp::from_millisecond(timestamp.timestamp.0) {
+                timestamp.tz_offset = zone.to_offset(instant).seconds() / 60;
+            }
+        }
         Signature {
             name: self.user_name().to_owned(),
             email: self.user_email().to_owned(),
@@ -252,6 +316,7 @@ impl UserSettings {
             behavior: self.data.signing_behavior,
             user_email: self.data.user_email.clone(),
             key: self.data.signing_key.clone(),
+            passphrase_command: self.data.signing_passphrase_command.clone(),
         }
     }
 }
@@ -405,6 +470,132 @@ mod tests {
     use assert_matches::assert_matches;
 
     use super::*;
+    use crate::config::ConfigLayer;
+    use crate::config::ConfigSource;
+
+    #[test]
+    fn git_settings_change_id_location() {
+        let to_settings = |text: &str| {
+            let mut config = StackedConfig::with_defaults();
+            config.add_layer(ConfigLayer::parse(ConfigSource::User, text).unwrap());
+            UserSettings::from_config(config).unwrap()
+        };
+        let location = |text: &str| to_settings(text).git_settings().unwrap().change_id_location;
+
+        assert_eq!(location(""), ChangeIdLocation::None);
+        assert_eq!(
+            location("git.change-id-location = 'header'"),
+            ChangeIdLocation::Header
+        );
+        assert_eq!(
+            location("git.change-id-location = 'trailer'"),
+            ChangeIdLocation::Trailer
+        );
+        assert_eq!(
+            location("git.change-id-location = 'none'"),
+            ChangeIdLocation::None
+        );
+        assert_matches!(
+            to_settings("git.change-id-location = 'footer'").git_settings(),
+            Err(ConfigGetError::Type { .. })
+        );
+    }
+
+    #[test]
+    fn signature_timezone() {
+        let to_settings = |text: &str| {
+            let mut config = StackedConfig::with_defaults();
+            config.add_layer(ConfigLayer::parse(ConfigSource::User, text).unwrap());
+            UserSettings::from_config(config)
+        };
+
+        let settings = to_settings("debug.commit-timestamp = '2001-02-03T04:05:06+07:00'").unwrap();
+        assert_eq!(settings.signature().timestamp.tz_offset, 420);
+
+        // The instant is preserved, only the offset is replaced
+        let settings = to_settings(
+            "debug.commit-timestamp = '2001-02-03T04:05:06+07:00'\nuser.timezone = '+02:00'",
+        )
+        .unwrap();
+        let timestamp = settings.signature().timestamp;
+        assert_eq!(timestamp.tz_offset, 120);
+        assert_eq!(
+            timestamp.timestamp,
+            Timestamp::from_datetime(
+                DateTime::parse_from_rfc3339("2001-02-03T04:05:06+07:00").unwrap()
+            )
+            .timestamp
+        );
+
+        let settings = to_settings("user.timezone = '-05:30'").unwrap();
+        assert_eq!(settings.signature().timestamp.tz_offset, -330);
+
+        assert_matches!(
+            to_settings("user.timezone = 'No/Such_Zone'"),
+            Err(ConfigGetError::Type { .. })
+        );
+        assert_matches!(
+            to_settings("user.timezone = 120"),
+            Err(ConfigGetError::Type { .. })
+        );
+    }
+
+    #[test]
+    fn operation_timestamp_follows_current_timestamp() {
+        let to_settings = |text: &str| {
+            let mut config = StackedConfig::with_defaults();
+            config.add_layer(ConfigLayer::parse(ConfigSource::User, text).unwrap());
+            UserSettings::from_config(config).unwrap()
+        };
+        let parse =
+            |text: &str| Timestamp::from_datetime(DateTime::parse_from_rfc3339(text).unwrap());
+
+        let settings = to_settings("debug.current-timestamp = '2001-02-03T04:05:06+07:00'");
+        assert_eq!(
+            settings.current_timestamp(),
+            Some(parse("2001-02-03T04:05:06+07:00"))
+        );
+        assert_eq!(
+            settings.operation_timestamp(),
+            Some(parse("2001-02-03T04:05:06+07:00"))
+        );
+
+        // An explicit operation timestamp takes precedence
+        let settings = to_settings(
+            "debug.current-timestamp = '2001-02-03T04:05:06+07:00'\n\
+             debug.operation-timestamp = '2001-02-03T04:05:07+07:00'",
+        );
+        assert_eq!(
+            settings.operation_timestamp(),
+            Some(parse("2001-02-03T04:05:07+07:00"))
+        );
+
+        let settings = to_settings("debug.commit-timestamp = '2001-02-03T04:05:06+07:00'");
+        assert_eq!(settings.current_timestamp(), None);
+        assert_eq!(settings.operation_timestamp(), None);
+    }
+
+    #[test]
+    fn sign_settings_passphrase_command() {
+        let to_settings = |text: &str| {
+            let mut config = StackedConfig::with_defaults();
+            config.add_layer(ConfigLayer::parse(ConfigSource::User, text).unwrap());
+            UserSettings::from_config(config).unwrap()
+        };
+
+        let settings = to_settings("");
+        assert_eq!(settings.sign_settings().passphrase_command, None);
+
+        let settings = to_settings("signing.passphrase-command = ['pass', 'show', 'ssh-key']");
+        assert_eq!(
+            settings.sign_settings().passphrase_command,
+            Some(vec![
+                "pass".to_owned(),
+                "show".to_owned(),
+                "ssh-key".to_owned()
+            ])
+        );
+    }
 
     #[test]
     fn byte_size_parse() {
@@ -44,6 +44,7 @@ use crate::backend::Conflict;
 use crate::backend::ConflictId;
 use crate::backend::ConflictTerm;
 use crate::backend::CopyRecord;
+use crate::backend::DuplicateSource;
 use crate::backend::FileId;
 use crate::backend::MergedTreeId;
 use crate::backend::MillisSinceEpoch;
@@ -361,6 +362,10 @@ pub fn commit_to_proto(commit: &Commit) -> crate::protos::simple_store::Commit {
     for predecessor in &commit.predecessors {
         proto.predecessors.push(predecessor.to_bytes());
     }
+    if let Some(source) = &commit.duplicated_from {
+        proto.duplicated_from = Some(source.commit_id.to_bytes());
+        proto.duplicated_from_change_id = Some(source.change_id.to_bytes());
+    }
     match &commit.root_tree {
         MergedTreeId::Legacy(tree_id) => {
             proto.root_tree = vec![tree_id.to_bytes()];
@@ -377,7 +382,7 @@ pub fn commit_to_proto(commit: &Commit) -> crate::protos::simple_store::Commit {
     proto
 }
 
-fn commit_from_proto(mut proto: crate::protos::simple_store::Commit) -> Commit {
+pub(crate) fn commit_from_proto(mut proto: crate::protos::simple_store::Commit) -> Commit {
     // Note how .take() sets the secure_sig field to None before we encode the data.
     // Needs to be done first since proto is partially moved a bunch below
     let secure_sig = proto.secure_sig.take().map(|sig| SecureSig {
@@ -387,6 +392,13 @@ fn commit_from_proto(mut proto: crate::protos::simple_store::Commit) -> Commit {
 
     let parents = proto.parents.into_iter().map(CommitId::new).collect();
     let predecessors = proto.predecessors.into_iter().map(CommitId::new).collect();
+    let duplicated_from = proto
+        .duplicated_from
+        .zip(proto.duplicated_from_change_id)
+        .map(|(commit_id, change_id)| DuplicateSource {
+            commit_id: CommitId::new(commit_id),
+            change_id: ChangeId::new(change_id),
+        });
     let root_tree = if proto.uses_tree_conflict_format {
         let merge_builder: MergeBuilder<_> = proto.root_tree.into_iter().map(TreeId::new).collect();
         MergedTreeId::Merge(merge_builder.build())
@@ -398,6 +410,7 @@ fn commit_from_proto(mut proto: crate::protos::simple_store::Commit) -> Commit {
     Commit {
         parents,
         predecessors,
+        duplicated_from,
         root_tree,
         change_id,
         description: proto.description,
@@ -407,7 +420,7 @@ fn commit_from_proto(mut proto: crate::protos::simple_store::Commit) -> Commit {
     }
 }
 
-fn tree_to_proto(tree: &Tree) -> crate::protos::simple_store::Tree {
+pub(crate) fn tree_to_proto(tree: &Tree) -> crate::protos::simple_store::Tree {
     let mut proto = crate::protos::simple_store::Tree::default();
     for entry in tree.entries() {
         proto
@@ -420,7 +433,7 @@ fn tree_to_proto(tree: &Tree) -> crate::protos::simple_store::Tree {
     proto
 }
 
-fn tree_from_proto(proto: crate::protos::simple_store::Tree) -> Tree {
+pub(crate) fn tree_from_proto(proto: crate::protos::simple_store::Tree) -> Tree {
     let mut tree = Tree::default();
     for proto_entry in proto.entries {
         let value = tree_value_from_proto(proto_entry.value.unwrap());
@@ -505,7 +518,7 @@ fn signature_from_proto(proto: crate::protos::simple_store::commit::Signature) -
     }
 }
 
-fn conflict_to_proto(conflict: &Conflict) -> crate::protos::simple_store::Conflict {
+pub(crate) fn conflict_to_proto(conflict: &Conflict) -> crate::protos::simple_store::Conflict {
     let mut proto = crate::protos::simple_store::Conflict::default();
     for term in &conflict.removes {
         proto.removes.push(conflict_term_to_proto(term));
@@ -516,7 +529,7 @@ fn conflict_to_proto(conflict: &Conflict) -> crate::protos::simple_store::Confli
     proto
 }
 
-fn conflict_from_proto(proto: crate::protos::simple_store::Conflict) -> Conflict {
+pub(crate) fn conflict_from_proto(proto: crate::protos::simple_store::Conflict) -> Conflict {
     let removes = proto
         .removes
         .into_iter()
@@ -560,6 +573,7 @@ mod tests {
         let mut commit = Commit {
             parents: vec![],
             predecessors: vec![],
+            duplicated_from: None,
             root_tree: MergedTreeId::resolved(backend.empty_tree_id().clone()),
             change_id: ChangeId::from_hex("abc123"),
             description: "".to_string(),
@@ -85,6 +85,11 @@ impl Store {
         self.backend.as_any()
     }
 
+    /// Name of the backend implementation.
+    pub fn backend_name(&self) -> &str {
+        self.backend.name()
+    }
+
     pub fn signer(&self) -> &Signer {
         &self.signer
     }
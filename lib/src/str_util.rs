@@ -22,6 +22,7 @@ use std::fmt::Debug;
 use std::ops::Deref;
 
 use either::Either;
+use itertools::Itertools as _;
 use thiserror::Error;
 
 /// Error occurred during pattern string parsing.
@@ -72,6 +73,14 @@ pub enum StringPattern {
     Substring(String),
     /// Matches strings that case‐insensitively contain a substring.
     SubstringI(String),
+    /// Matches strings that start with a prefix.
+    StartsWith(String),
+    /// Matches strings that case‐insensitively start with a prefix.
+    StartsWithI(String),
+    /// Matches strings that end with a suffix.
+    EndsWith(String),
+    /// Matches strings that case‐insensitively end with a suffix.
+    EndsWithI(String),
     /// Matches with a Unix‐style shell wildcard pattern.
     Glob(GlobPattern),
     /// Matches with a case‐insensitive Unix‐style shell wildcard pattern.
@@ -89,7 +98,8 @@ impl StringPattern {
 
     /// Parses the given string as a [`StringPattern`]. Everything before the
     /// first ":" is considered the string's prefix. If the prefix is
-    /// "exact[-i]:", "glob[-i]:", or "substring[-i]:", a pattern of the
+    /// "exact[-i]:", "glob[-i]:", "substring[-i]:", "starts-with[-i]:", or
+    /// "ends-with[-i]:", a pattern of the
     /// specified kind is returned. Returns an error if the string has an
     /// unrecognized prefix. Otherwise, a `StringPattern::Exact` is
     /// returned.
@@ -121,6 +131,26 @@ impl StringPattern {
         StringPattern::SubstringI(src.into())
     }
 
+    /// Constructs a pattern that matches a prefix.
+    pub fn starts_with(src: impl Into<String>) -> Self {
+        StringPattern::StartsWith(src.into())
+    }
+
+    /// Constructs a pattern that case‐insensitively matches a prefix.
+    pub fn starts_with_i(src: impl Into<String>) -> Self {
+        StringPattern::StartsWithI(src.into())
+    }
+
+    /// Constructs a pattern that matches a suffix.
+    pub fn ends_with(src: impl Into<String>) -> Self {
+        StringPattern::EndsWith(src.into())
+    }
+
+    /// Constructs a pattern that case‐insensitively matches a suffix.
+    pub fn ends_with_i(src: impl Into<String>) -> Self {
+        StringPattern::EndsWithI(src.into())
+    }
+
     /// Parses the given string as a glob pattern.
     pub fn glob(src: &str) -> Result<Self, StringPatternParseError> {
         // TODO: might be better to do parsing and compilation separately since
@@ -140,6 +170,14 @@ impl StringPattern {
         Ok(StringPattern::Regex(pattern))
     }
 
+    /// Parses the given string as a regular expression in multi-line mode,
+    /// where `^` and `$` match at the start and end of each line.
+    pub fn regex_multi_line(src: &str) -> Result<Self, StringPatternParseError> {
+        // The flag is embedded in the source so the compiled pattern can be
+        // reconstructed from its string form.
+        StringPattern::regex(&format!("(?m){src}"))
+    }
+
     /// Parses the given string as a pattern of the specified `kind`.
     pub fn from_str_kind(src: &str, kind: &str) -> Result<Self, StringPatternParseError> {
         match kind {
@@ -147,9 +185,14 @@ impl StringPattern {
             "exact-i" => Ok(StringPattern::exact_i(src)),
             "substring" => Ok(StringPattern::substring(src)),
             "substring-i" => Ok(StringPattern::substring_i(src)),
+            "starts-with" => Ok(StringPattern::starts_with(src)),
+            "starts-with-i" => Ok(StringPattern::starts_with_i(src)),
+            "ends-with" => Ok(StringPattern::ends_with(src)),
+            "ends-with-i" => Ok(StringPattern::ends_with_i(src)),
             "glob" => StringPattern::glob(src),
             "glob-i" => StringPattern::glob_i(src),
             "regex" => StringPattern::regex(src),
+            "regex-m" => StringPattern::regex_multi_line(src),
             _ => Err(StringPatternParseError::InvalidKind(kind.to_owned())),
         }
     }
@@ -179,6 +222,10 @@ impl StringPattern {
             StringPattern::ExactI(literal) => literal,
             StringPattern::Substring(needle) => needle,
             StringPattern::SubstringI(needle) => needle,
+            StringPattern::StartsWith(prefix) => prefix,
+            StringPattern::StartsWithI(prefix) => prefix,
+            StringPattern::EndsWith(suffix) => suffix,
+            StringPattern::EndsWithI(suffix) => suffix,
             StringPattern::Glob(pattern) => pattern.as_str(),
             StringPattern::GlobI(pattern) => pattern.as_str(),
             StringPattern::Regex(pattern) => pattern.as_str(),
@@ -200,9 +247,17 @@ impl StringPattern {
                     Some(format!("*{}*", glob::Pattern::escape(needle)).into())
                 }
             }
+            StringPattern::StartsWith(prefix) => {
+                Some(format!("{}*", glob::Pattern::escape(prefix)).into())
+            }
+            StringPattern::EndsWith(suffix) => {
+                Some(format!("*{}", glob::Pattern::escape(suffix)).into())
+            }
             StringPattern::Glob(pattern) => Some(pattern.as_str().into()),
             StringPattern::ExactI(_) => None,
             StringPattern::SubstringI(_) => None,
+            StringPattern::StartsWithI(_) => None,
+            StringPattern::EndsWithI(_) => None,
             StringPattern::GlobI(_) => None,
             StringPattern::Regex(_) => None,
         }
@@ -235,6 +290,18 @@ impl StringPattern {
             StringPattern::SubstringI(needle) => haystack
                 .to_ascii_lowercase()
                 .contains(&needle.to_ascii_lowercase()),
+            StringPattern::StartsWith(prefix) => haystack.starts_with(prefix.as_str()),
+            StringPattern::StartsWithI(prefix) => haystack
+                .as_bytes()
+                .get(..prefix.len())
+                .is_some_and(|head| head.eq_ignore_ascii_case(prefix.as_bytes())),
+            StringPattern::EndsWith(suffix) => haystack.ends_with(suffix.as_str()),
+            StringPattern::EndsWithI(suffix) => haystack
+                .len()
+                .checked_sub(suffix.len())
+                .is_some_and(|start| {
+                    haystack.as_bytes()[start..].eq_ignore_ascii_case(suffix.as_bytes())
+                }),
             StringPattern::Glob(pattern) => pattern.0.matches(haystack),
             StringPattern::GlobI(pattern) => pattern.0.matches_with(
                 haystack,
@@ -306,6 +373,79 @@ impl fmt::Display for StringPattern {
     }
 }
 
+/// Set of [`StringPattern`]s which matches strings matching any of the
+/// patterns.
+///
+/// Regular expressions in the set are compiled into a single [`RegexSet`] so
+/// that the haystack is scanned once for all of them.
+///
+/// [`RegexSet`]: regex::RegexSet
+#[derive(Clone)]
+pub struct StringPatternSet {
+    patterns: Vec<StringPattern>,
+    regex_set: Option<regex::RegexSet>,
+}
+
+impl StringPatternSet {
+    /// Creates a set of the given patterns.
+    pub fn new(patterns: Vec<StringPattern>) -> Self {
+        let regex_sources = patterns
+            .iter()
+            .filter_map(|pattern| match pattern {
+                StringPattern::Regex(regex) => Some(regex.as_str()),
+                _ => None,
+            })
+            .collect_vec();
+        // Each regex has been compiled individually, so building the combined
+        // set can only fail if the size limit is exceeded. Fall back to
+        // matching them one by one in that case.
+        let regex_set = (regex_sources.len() > 1)
+            .then(|| regex::RegexSet::new(regex_sources).ok())
+            .flatten();
+        StringPatternSet {
+            patterns,
+            regex_set,
+        }
+    }
+
+    /// Patterns in this set.
+    pub fn patterns(&self) -> &[StringPattern] {
+        &self.patterns
+    }
+
+    /// Returns true if any of the patterns matches the `haystack`.
+    pub fn matches(&self, haystack: &str) -> bool {
+        if let Some(regex_set) = &self.regex_set {
+            regex_set.is_match(haystack)
+                || self
+                    .patterns
+                    .iter()
+                    .filter(|pattern| !matches!(pattern, StringPattern::Regex(_)))
+                    .any(|pattern| pattern.matches(haystack))
+        } else {
+            self.patterns
+                .iter()
+                .any(|pattern| pattern.matches(haystack))
+        }
+    }
+}
+
+impl Debug for StringPatternSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Don't clutter the common case of a single pattern.
+        match self.patterns.as_slice() {
+            [pattern] => pattern.fmt(f),
+            patterns => f.debug_tuple("AnyOf").field(&patterns).finish(),
+        }
+    }
+}
+
+impl From<StringPattern> for StringPatternSet {
+    fn from(pattern: StringPattern) -> Self {
+        StringPatternSet::new(vec![pattern])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use assert_matches::assert_matches;
@@ -329,6 +469,15 @@ mod tests {
             StringPattern::Substring("*".into()).to_glob(),
             Some("*[*]*".into())
         );
+        assert_eq!(
+            StringPattern::starts_with("a*").to_glob(),
+            Some("a[*]*".into())
+        );
+        assert_eq!(
+            StringPattern::ends_with("[a]").to_glob(),
+            Some("*[[]a[]]".into())
+        );
+        assert_eq!(StringPattern::starts_with_i("a").to_glob(), None);
     }
 
     #[test]
@@ -366,6 +515,22 @@ mod tests {
             StringPattern::from_str_kind("foo", "substring-i"),
             Ok(StringPattern::SubstringI(s)) if s == "foo"
         );
+        assert_matches!(
+            StringPattern::parse("starts-with:foo"),
+            Ok(StringPattern::StartsWith(s)) if s == "foo"
+        );
+        assert_matches!(
+            StringPattern::parse("starts-with-i:foo"),
+            Ok(StringPattern::StartsWithI(s)) if s == "foo"
+        );
+        assert_matches!(
+            StringPattern::from_str_kind("foo", "ends-with"),
+            Ok(StringPattern::EndsWith(s)) if s == "foo"
+        );
+        assert_matches!(
+            StringPattern::from_str_kind("foo", "ends-with-i"),
+            Ok(StringPattern::EndsWithI(s)) if s == "foo"
+        );
         assert_matches!(
             StringPattern::parse("regex:foo"),
             Ok(StringPattern::Regex(p)) if p.as_str() == "foo"
@@ -374,6 +539,10 @@ mod tests {
             StringPattern::from_str_kind("foo", "regex"),
             Ok(StringPattern::Regex(p)) if p.as_str() == "foo"
         );
+        assert_matches!(
+            StringPattern::parse("regex-m:^foo$"),
+            Ok(StringPattern::Regex(p)) if p.as_str() == "(?m)^foo$"
+        );
 
         // Parse a pattern that contains a : itself.
         assert_matches!(
@@ -393,4 +562,49 @@ mod tests {
             Err(StringPatternParseError::InvalidKind(_))
         );
     }
+
+    #[test]
+    fn test_matches_anchored() {
+        // Glob meta characters are matched literally.
+        assert!(StringPattern::starts_with("a*").matches("a*b"));
+        assert!(!StringPattern::starts_with("a*").matches("ab"));
+        assert!(!StringPattern::starts_with("a*").matches("ba*"));
+        assert!(StringPattern::starts_with("").matches("a"));
+        assert!(StringPattern::ends_with("[b]").matches("a[b]"));
+        assert!(!StringPattern::ends_with("[b]").matches("ab"));
+        assert!(!StringPattern::ends_with("[b]").matches("[b]a"));
+
+        assert!(StringPattern::starts_with_i("A?").matches("a?B"));
+        assert!(!StringPattern::starts_with_i("A?").matches("ab"));
+        assert!(!StringPattern::starts_with_i("abc").matches("ab"));
+        assert!(StringPattern::ends_with_i("?B").matches("a?b"));
+        assert!(!StringPattern::ends_with_i("abc").matches("bc"));
+        // Non-ASCII haystack shouldn't panic at char boundary.
+        assert!(!StringPattern::starts_with_i("a").matches("\u{e9}"));
+        assert!(StringPattern::ends_with_i("\u{e9}").matches("a\u{e9}"));
+        assert!(!StringPattern::ends_with_i("b").matches("\u{e9}"));
+    }
+
+    #[test]
+    fn test_pattern_set_matches() {
+        let set = StringPatternSet::new(vec![
+            StringPattern::exact("foo"),
+            StringPattern::regex("^ba[rz]$").unwrap(),
+            StringPattern::regex("qu+x").unwrap(),
+        ]);
+        assert!(set.regex_set.is_some());
+        assert!(set.matches("foo"));
+        assert!(set.matches("bar"));
+        assert!(set.matches("baz"));
+        assert!(set.matches("aquuux"));
+        assert!(!set.matches("food"));
+        assert!(!set.matches("bars"));
+
+        let set = StringPatternSet::from(StringPattern::regex("a").unwrap());
+        assert!(set.regex_set.is_none());
+        assert!(set.matches("bar"));
+        assert!(!set.matches("foo"));
+
+        assert!(!StringPatternSet::new(vec![]).matches(""));
+    }
 }
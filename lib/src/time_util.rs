@@ -15,8 +15,12 @@
 //! Provides support for parsing and matching date ranges.
 
 use chrono::DateTime;
+use chrono::Days;
 use chrono::FixedOffset;
 use chrono::Local;
+use chrono::Months;
+use chrono::NaiveDate;
+use chrono::NaiveTime;
 use chrono::TimeZone;
 use interim::parse_date_string;
 use interim::DateError;
@@ -70,6 +74,9 @@ pub enum DatePatternParseError {
     /// Failed to parse timestamp.
     #[error(transparent)]
     ParseError(#[from] DateError),
+    /// Failed to parse year, month, or day for `during:`.
+    #[error("Invalid date `{0}`, expected year, month, or day like `2024-02-01`")]
+    InvalidPartialDate(String),
 }
 
 /// Represents an range of dates that may be matched against.
@@ -79,6 +86,9 @@ pub enum DatePattern {
     AtOrAfter(MillisSinceEpoch),
     /// Represents all dates before, but not including, the given instant.
     Before(MillisSinceEpoch),
+    /// Represents all dates at or after the first instant, and before the
+    /// second instant.
+    Between(MillisSinceEpoch, MillisSinceEpoch),
 }
 
 impl DatePattern {
@@ -86,8 +96,10 @@ impl DatePattern {
     ///
     /// * `s` is the string to be parsed.
     ///
-    /// * `kind` must be either "after" or "before". This determines whether the
-    ///   pattern will match dates after or before the parsed date.
+    /// * `kind` must be "after", "before", or "during". This determines whether
+    ///   the pattern will match dates after or before the parsed date, or
+    ///   within the range. The range of "during" is either the year, month, or
+    ///   day like `2024-02`, or `start..end` of two dates.
     ///
     /// * `now` is the user's current time. This is a [`DateTime<Tz>`] because
     ///   knowledge of offset changes is needed to correctly process relative
@@ -103,9 +115,19 @@ impl DatePattern {
     where
         Tz::Offset: Copy,
     {
-        let d =
-            parse_date_string(s, now, Dialect::Us).map_err(DatePatternParseError::ParseError)?;
-        let millis_since_epoch = MillisSinceEpoch(d.timestamp_millis());
+        let parse = |s: &str| -> Result<_, DatePatternParseError> {
+            let d = parse_date_string(s, now, Dialect::Us)?;
+            Ok(MillisSinceEpoch(d.timestamp_millis()))
+        };
+        if kind == "during" {
+            let (start, end) = if let Some((start, end)) = s.split_once("..") {
+                (parse(start)?, parse(end)?)
+            } else {
+                parse_date_range(s, &now.timezone())?
+            };
+            return Ok(DatePattern::Between(start, end));
+        }
+        let millis_since_epoch = parse(s)?;
         match kind {
             "after" => Ok(DatePattern::AtOrAfter(millis_since_epoch)),
             "before" => Ok(DatePattern::Before(millis_since_epoch)),
@@ -118,14 +140,118 @@ impl DatePattern {
         match self {
             DatePattern::AtOrAfter(earliest) => *earliest <= timestamp.timestamp,
             DatePattern::Before(latest) => timestamp.timestamp < *latest,
+            DatePattern::Between(earliest, latest) => {
+                *earliest <= timestamp.timestamp && timestamp.timestamp < *latest
+            }
         }
     }
 }
 
+/// Parses `YYYY`, `YYYY-MM`, or `YYYY-MM-DD` into the range of instants in the
+/// year, month, or day.
+fn parse_date_range<Tz: TimeZone>(
+    s: &str,
+    tz: &Tz,
+) -> Result<(MillisSinceEpoch, MillisSinceEpoch), DatePatternParseError> {
+    let invalid = || DatePatternParseError::InvalidPartialDate(s.to_owned());
+    let numbers: Vec<u32> = s
+        .trim()
+        .split('-')
+        .map(|part| part.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid())?;
+    let ymd = |year: u32, month: u32, day: u32| {
+        NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, month, day)
+    };
+    let (start, months, days) = match *numbers.as_slice() {
+        [year] => (ymd(year, 1, 1), 12, 0),
+        [year, month] => (ymd(year, month, 1), 1, 0),
+        [year, month, day] => (ymd(year, month, day), 0, 1),
+        _ => return Err(invalid()),
+    };
+    let start = start.ok_or_else(invalid)?;
+    let end = start
+        .checked_add_months(Months::new(months))
+        .and_then(|date| date.checked_add_days(Days::new(days)))
+        .ok_or_else(invalid)?;
+    let to_millis = |date: NaiveDate| {
+        let datetime = tz
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()?;
+        Some(MillisSinceEpoch(datetime.timestamp_millis()))
+    };
+    Ok((
+        to_millis(start).ok_or_else(invalid)?,
+        to_millis(end).ok_or_else(invalid)?,
+    ))
+}
+
+/// Error from converting a [`Timestamp`] to a datetime.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum TimestampOutOfRange {
+    /// The date can't be represented.
+    #[error("Out-of-range date")]
+    Date,
+    /// The time zone offset is invalid.
+    #[error("Out-of-range time zone offset: {0} minutes")]
+    TzOffset(i32),
+}
+
+/// Converts the `timestamp` to a datetime in its own time zone.
+///
+/// An invalid time zone offset is reported as an error so the caller can tell
+/// it apart from a legitimate UTC timestamp.
+pub fn datetime_from_timestamp(
+    timestamp: &Timestamp,
+) -> Result<DateTime<FixedOffset>, TimestampOutOfRange> {
+    let utc =
+        DateTime::from_timestamp_millis(timestamp.timestamp.0).ok_or(TimestampOutOfRange::Date)?;
+    let offset = timestamp
+        .tz_offset
+        .checked_mul(60)
+        .and_then(FixedOffset::east_opt)
+        .ok_or(TimestampOutOfRange::TzOffset(timestamp.tz_offset))?;
+    Ok(utc.with_timezone(&offset))
+}
+
+/// Returns the calendar date of the `timestamp` in its own time zone.
+pub fn local_date_from_timestamp(timestamp: &Timestamp) -> Result<NaiveDate, TimestampOutOfRange> {
+    datetime_from_timestamp(timestamp).map(|datetime| datetime.date_naive())
+}
+
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
+
     use super::*;
 
+    #[test]
+    fn test_datetime_from_timestamp() {
+        let timestamp = |millis, tz_offset| Timestamp {
+            timestamp: MillisSinceEpoch(millis),
+            tz_offset,
+        };
+        assert_eq!(
+            datetime_from_timestamp(&timestamp(0, -480))
+                .unwrap()
+                .to_rfc3339(),
+            "1969-12-31T16:00:00-08:00"
+        );
+        // Invalid offset shouldn't be silently interpreted as UTC
+        assert_eq!(
+            datetime_from_timestamp(&timestamp(0, 100000)),
+            Err(TimestampOutOfRange::TzOffset(100000))
+        );
+        assert_eq!(
+            datetime_from_timestamp(&timestamp(0, i32::MAX)),
+            Err(TimestampOutOfRange::TzOffset(i32::MAX))
+        );
+        assert_eq!(
+            datetime_from_timestamp(&timestamp(i64::MAX, 0)),
+            Err(TimestampOutOfRange::Date)
+        );
+    }
+
     fn test_equal<Tz: TimeZone>(now: DateTime<Tz>, expression: &str, should_equal_time: &str)
     where
         Tz::Offset: Copy,
@@ -182,6 +308,87 @@ mod tests {
         test_equal(now, "tomorrow", "2024-01-02T08:00:00Z");
     }
 
+    #[test]
+    fn test_date_pattern_parses_partial_dates_as_ranges() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T00:00:00-08:00").unwrap();
+        let range = |s: &str| match DatePattern::from_str_kind(s, "during", now).unwrap() {
+            DatePattern::Between(start, end) => {
+                let format = |millis: MillisSinceEpoch| {
+                    DateTime::from_timestamp_millis(millis.0)
+                        .unwrap()
+                        .to_rfc3339()
+                };
+                (format(start), format(end))
+            }
+            pattern => panic!("unexpected pattern: {pattern:?}"),
+        };
+        assert_eq!(
+            range("2023"),
+            (
+                "2023-01-01T08:00:00+00:00".to_owned(),
+                "2024-01-01T08:00:00+00:00".to_owned()
+            )
+        );
+        assert_eq!(
+            range("2023-02"),
+            (
+                "2023-02-01T08:00:00+00:00".to_owned(),
+                "2023-03-01T08:00:00+00:00".to_owned()
+            )
+        );
+        assert_eq!(
+            range("2023-12"),
+            (
+                "2023-12-01T08:00:00+00:00".to_owned(),
+                "2024-01-01T08:00:00+00:00".to_owned()
+            )
+        );
+        assert_eq!(
+            range("2024-02-29"),
+            (
+                "2024-02-29T08:00:00+00:00".to_owned(),
+                "2024-03-01T08:00:00+00:00".to_owned()
+            )
+        );
+
+        let parse_err = |s: &str| DatePattern::from_str_kind(s, "during", now).unwrap_err();
+        assert_matches!(
+            parse_err("2023-02-29"),
+            DatePatternParseError::InvalidPartialDate(_)
+        );
+        assert_matches!(
+            parse_err("2023-13"),
+            DatePatternParseError::InvalidPartialDate(_)
+        );
+        assert_matches!(
+            parse_err("2023-01-01-01"),
+            DatePatternParseError::InvalidPartialDate(_)
+        );
+        assert_matches!(
+            parse_err("yesterday"),
+            DatePatternParseError::InvalidPartialDate(_)
+        );
+        assert_matches!(parse_err(""), DatePatternParseError::InvalidPartialDate(_));
+    }
+
+    #[test]
+    fn test_date_pattern_parses_explicit_ranges() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T00:00:00-08:00").unwrap();
+        let millis =
+            |s: &str| MillisSinceEpoch(DateTime::parse_from_rfc3339(s).unwrap().timestamp_millis());
+        assert_eq!(
+            DatePattern::from_str_kind("2023-03-25..2023-03-27 12:00", "during", now).unwrap(),
+            DatePattern::Between(
+                millis("2023-03-25T08:00:00Z"),
+                millis("2023-03-27T20:00:00Z")
+            )
+        );
+        assert_matches!(
+            DatePattern::from_str_kind("2023-03-25..bad", "during", now),
+            Err(DatePatternParseError::ParseError(_))
+        );
+    }
+
     #[test]
     fn test_date_pattern_parses_relative_dates_with_times() {
         let now = DateTime::parse_from_rfc3339("2024-01-01T08:00:00-08:00").unwrap();
@@ -0,0 +1,633 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing and editing of Git-style trailers in commit descriptions.
+//!
+//! The trailer block is the last paragraph of the description if all of its
+//! lines are `Key: value` trailers or their continuation lines. As in Git, the
+//! first paragraph (the subject) is never a trailer block.
+
+use std::borrow::Cow;
+
+use itertools::Itertools as _;
+
+/// Key-value pair in the trailer block, such as
+/// `Signed-off-by: Alice <alice@example.com>`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trailer {
+    /// Trailer key, which consists of ASCII alphanumeric characters and `-`.
+    pub key: String,
+    /// Trailer value. Folded continuation lines are joined by a space.
+    pub value: String,
+}
+
+impl Trailer {
+    /// Creates a new trailer.
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Trailer {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Returns true if this trailer has the `key`, compared
+    /// case-insensitively as Git does.
+    pub fn has_key(&self, key: &str) -> bool {
+        self.key.eq_ignore_ascii_case(key)
+    }
+}
+
+/// Trailer parsed from a description, and its source text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParsedTrailer<'a> {
+    /// Parsed key and value.
+    pub trailer: Trailer,
+    /// Source lines including continuation lines, without the terminator of
+    /// the last line.
+    pub raw: &'a str,
+}
+
+/// Description split into the body and the trailer block.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParsedDescription<'a> {
+    /// Text preceding the trailer block, including the blank line separating
+    /// them. This is the whole description if there's no trailer block.
+    pub body: &'a str,
+    /// Trailers in order of appearance.
+    pub trailers: Vec<ParsedTrailer<'a>>,
+    /// Text following the trailer block, which is the terminator of the last
+    /// trailer line followed by blank lines, if any.
+    pub tail: &'a str,
+}
+
+/// Splits the `description` into the body and the trailer block.
+pub fn parse_description(description: &str) -> ParsedDescription<'_> {
+    let no_trailers = ParsedDescription {
+        body: description,
+        trailers: vec![],
+        tail: "",
+    };
+    // (offset, content) pairs, where the content excludes the line terminator.
+    let lines = description
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, strip_line_terminator(line)))
+        })
+        .collect_vec();
+    let is_blank = |&(_, content): &(usize, &str)| content.trim().is_empty();
+    // The first paragraph is the subject and cannot be trailers.
+    let Some(subject_end) = lines.iter().position(is_blank) else {
+        return no_trailers;
+    };
+    let Some(block_last) = lines.iter().rposition(|line| !is_blank(line)) else {
+        return no_trailers;
+    };
+    if block_last < subject_end {
+        return no_trailers;
+    }
+    // There's a blank line at subject_end at least.
+    let block_start = lines[..block_last].iter().rposition(is_blank).unwrap() + 1;
+
+    // (start, end, trailer) of each trailer
+    let mut trailers: Vec<(usize, usize, Trailer)> = vec![];
+    for &(offset, content) in &lines[block_start..=block_last] {
+        if content.starts_with([' ', '\t']) {
+            let Some((_, end, trailer)) = trailers.last_mut() else {
+                return no_trailers;
+            };
+            let value = content.trim();
+            if !trailer.value.is_empty() {
+                trailer.value.push(' ');
+            }
+            trailer.value.push_str(value);
+            *end = offset + content.len();
+        } else if let Some((key, value)) = split_trailer_line(content) {
+            trailers.push((offset, offset + content.len(), Trailer::new(key, value)));
+        } else {
+            return no_trailers;
+        }
+    }
+
+    let (block_start_offset, _) = lines[block_start];
+    let block_end_offset = trailers.last().unwrap().1;
+    let trailers = trailers
+        .into_iter()
+        .map(|(start, end, trailer)| ParsedTrailer {
+            trailer,
+            raw: &description[start..end],
+        })
+        .collect();
+    ParsedDescription {
+        body: &description[..block_start_offset],
+        trailers,
+        tail: &description[block_end_offset..],
+    }
+}
+
+/// Returns the trailers of the `description`.
+pub fn parse_trailers(description: &str) -> Vec<Trailer> {
+    parse_description(description)
+        .trailers
+        .into_iter()
+        .map(|parsed| parsed.trailer)
+        .collect()
+}
+
+/// Edit to the trailer block.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TrailerEdit {
+    /// Appends the trailer unless it's a duplicate according to the
+    /// [`TrailerDedup`] policy.
+    Add(Trailer),
+    /// Removes all trailers with the `key`, and with the `value` if specified.
+    Remove {
+        /// Key of the trailers to remove.
+        key: String,
+        /// Value of the trailers to remove. If `None`, any value matches.
+        value: Option<String>,
+    },
+    /// Replaces all trailers with the key by the trailer, which is placed at
+    /// the position of the first of them. If there are none, the trailer is
+    /// appended.
+    Replace(Trailer),
+}
+
+/// Policy to deduplicate trailers added by [`TrailerEdit::Add`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TrailerDedup {
+    /// Always add the trailer.
+    #[default]
+    Disabled,
+    /// Don't add the trailer if there's a trailer with the same key and value.
+    Exact,
+    /// Don't add the trailer if there's a trailer with the same key.
+    ByKey,
+}
+
+/// Applies the `edits` to the trailer block of the `description` in order.
+///
+/// The body and the unchanged trailers are preserved as is. If the edits
+/// change nothing, the `description` is returned unchanged. New trailer lines
+/// are terminated by `\r\n` if the `description` uses CRLF line endings.
+pub fn apply_trailer_edits(
+    description: &str,
+    edits: &[TrailerEdit],
+    dedup: TrailerDedup,
+) -> String {
+    let parsed = parse_description(description);
+    // Unchanged trailers are paired with the source text.
+    let original = parsed
+        .trailers
+        .iter()
+        .map(|parsed| (parsed.trailer.clone(), Some(parsed.raw)))
+        .collect_vec();
+    let mut entries = original.clone();
+    for edit in edits {
+        match edit {
+            TrailerEdit::Add(trailer) => {
+                let duplicated = match dedup {
+                    TrailerDedup::Disabled => false,
+                    TrailerDedup::Exact => entries
+                        .iter()
+                        .any(|(t, _)| t.has_key(&trailer.key) && t.value == trailer.value),
+                    TrailerDedup::ByKey => entries.iter().any(|(t, _)| t.has_key(&trailer.key)),
+                };
+                if !duplicated {
+                    entries.push((trailer.clone(), None));
+                }
+            }
+            TrailerEdit::Remove { key, value } => {
+                entries.retain(|(t, _)| {
+                    !(t.has_key(key) && value.as_ref().is_none_or(|value| t.value == *value))
+                });
+            }
+            TrailerEdit::Replace(trailer) => {
+                let Some(pos) = entries.iter().position(|(t, _)| t.has_key(&trailer.key)) else {
+                    entries.push((trailer.clone(), None));
+                    continue;
+                };
+                let raw = if entries[pos].0 == *trailer {
+                    entries[pos].1
+                } else {
+                    None
+                };
+                entries.retain(|(t, _)| !t.has_key(&trailer.key));
+                entries.insert(pos, (trailer.clone(), raw));
+            }
+        }
+    }
+    if entries == original {
+        return description.to_owned();
+    }
+
+    let line_ending = if description.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let lines = entries
+        .iter()
+        .map(|(trailer, raw)| match raw {
+            Some(raw) => Cow::Borrowed(*raw),
+            None => Cow::Owned(format!("{}: {}", trailer.key, trailer.value)),
+        })
+        .collect_vec();
+    if parsed.trailers.is_empty() {
+        // Start a new trailer block after the body.
+        let body = trim_trailing_blank_lines(description);
+        let mut text = body.to_owned();
+        if !body.is_empty() {
+            text.push_str(line_ending);
+        }
+        // Separate from the subject even if it's empty.
+        text.push_str(line_ending);
+        for line in &lines {
+            text.push_str(line);
+            text.push_str(line_ending);
+        }
+        text
+    } else if entries.is_empty() {
+        // Remove the trailer block and the blank lines preceding it.
+        let body = trim_trailing_blank_lines(parsed.body);
+        let mut text = body.to_owned();
+        if !body.is_empty() && description.ends_with('\n') {
+            text.push_str(line_ending);
+        }
+        text
+    } else {
+        let mut text = parsed.body.to_owned();
+        text.push_str(&lines.join(line_ending));
+        text.push_str(parsed.tail);
+        text
+    }
+}
+
+/// Splits `Key: value` line into the key and the trimmed value.
+fn split_trailer_line(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once(':')?;
+    // Whitespace is allowed between the key and the separator.
+    let key = key.trim_end_matches([' ', '\t']);
+    if !is_trailer_key(key) {
+        return None;
+    }
+    Some((key, value.trim()))
+}
+
+/// Returns true if the `key` is a valid trailer key, which consists of ASCII
+/// alphanumeric characters and `-`.
+pub fn is_trailer_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn strip_line_terminator(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Returns the `text` up to the end of the last non-blank line, excluding its
+/// terminator.
+fn trim_trailing_blank_lines(text: &str) -> &str {
+    let mut offset = 0;
+    let mut end = 0;
+    for line in text.split_inclusive('\n') {
+        let content = strip_line_terminator(line);
+        if !content.trim().is_empty() {
+            end = offset + content.len();
+        }
+        offset += line.len();
+    }
+    &text[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trailers(description: &str) -> Vec<String> {
+        parse_trailers(description)
+            .into_iter()
+            .map(|trailer| format!("{}={}", trailer.key, trailer.value))
+            .collect()
+    }
+
+    fn add(key: &str, value: &str) -> TrailerEdit {
+        TrailerEdit::Add(Trailer::new(key, value))
+    }
+
+    fn remove(key: &str, value: Option<&str>) -> TrailerEdit {
+        TrailerEdit::Remove {
+            key: key.to_owned(),
+            value: value.map(str::to_owned),
+        }
+    }
+
+    fn replace(key: &str, value: &str) -> TrailerEdit {
+        TrailerEdit::Replace(Trailer::new(key, value))
+    }
+
+    #[test]
+    fn test_parse_simple() {
+        let description = "subject\n\nbody\n\nFoo: bar\nBaz-Qux:  quux \n";
+        let parsed = parse_description(description);
+        assert_eq!(parsed.body, "subject\n\nbody\n\n");
+        assert_eq!(
+            parsed.trailers,
+            [
+                ParsedTrailer {
+                    trailer: Trailer::new("Foo", "bar"),
+                    raw: "Foo: bar",
+                },
+                ParsedTrailer {
+                    trailer: Trailer::new("Baz-Qux", "quux"),
+                    raw: "Baz-Qux:  quux ",
+                },
+            ]
+        );
+        assert_eq!(parsed.tail, "\n");
+
+        // Subject-only paragraph before the trailer block
+        assert_eq!(trailers("subject\n\nFoo: bar\n"), ["Foo=bar"]);
+        // Empty subject
+        assert_eq!(trailers("\nFoo: bar\n"), ["Foo=bar"]);
+        // Whitespace between the key and the separator
+        assert_eq!(trailers("subject\n\nFoo : bar\n"), ["Foo=bar"]);
+        // Empty value
+        assert_eq!(trailers("subject\n\nFoo:\n"), ["Foo="]);
+        // Git treats URL-looking lines as trailers
+        assert_eq!(
+            trailers("subject\n\nhttps://example.com\n"),
+            ["https=//example.com"]
+        );
+    }
+
+    #[test]
+    fn test_parse_no_trailers() {
+        assert!(trailers("").is_empty());
+        assert!(trailers("\n\n").is_empty());
+        assert!(trailers("subject\n").is_empty());
+        assert!(trailers("subject\n\nbody\n").is_empty());
+
+        // The subject is never a trailer block
+        assert!(trailers("Foo: bar\n").is_empty());
+        assert!(trailers("Foo: bar\n\n").is_empty());
+        assert!(trailers("subject\nFoo: bar\n").is_empty());
+
+        // Trailer-looking lines in the middle of the body
+        assert!(trailers("subject\n\nFoo: bar\n\nbody\n").is_empty());
+        // Mixed with non-trailer lines
+        assert!(trailers("subject\n\nbody\nFoo: bar\n").is_empty());
+        assert!(trailers("subject\n\nFoo: bar\nbody\n").is_empty());
+        // Invalid keys
+        assert!(trailers("subject\n\nFoo bar: baz\n").is_empty());
+        assert!(trailers("subject\n\n: bar\n").is_empty());
+        assert!(trailers("subject\n\nFoo_bar: baz\n").is_empty());
+        // Continuation line without preceding trailer
+        assert!(trailers("subject\n\n  Foo: bar\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_continuation_lines() {
+        let description = "subject\n\nFoo: bar\n  baz\n\tqux\nQuux: x\n";
+        let parsed = parse_description(description);
+        assert_eq!(
+            parsed.trailers,
+            [
+                ParsedTrailer {
+                    trailer: Trailer::new("Foo", "bar baz qux"),
+                    raw: "Foo: bar\n  baz\n\tqux",
+                },
+                ParsedTrailer {
+                    trailer: Trailer::new("Quux", "x"),
+                    raw: "Quux: x",
+                },
+            ]
+        );
+
+        // Value starting on the continuation line
+        assert_eq!(trailers("subject\n\nFoo:\n bar\n"), ["Foo=bar"]);
+    }
+
+    #[test]
+    fn test_parse_line_endings() {
+        // No trailing newline
+        let parsed = parse_description("subject\n\nFoo: bar");
+        assert_eq!(parsed.body, "subject\n\n");
+        assert_eq!(parsed.trailers[0].raw, "Foo: bar");
+        assert_eq!(parsed.tail, "");
+
+        // Trailing blank lines
+        let parsed = parse_description("subject\n\nFoo: bar\n\n \n");
+        assert_eq!(parsed.trailers[0].raw, "Foo: bar");
+        assert_eq!(parsed.tail, "\n\n \n");
+
+        // CRLF
+        let parsed = parse_description("subject\r\n\r\nFoo: bar\r\n  baz\r\nQux: x\r\n");
+        assert_eq!(parsed.body, "subject\r\n\r\n");
+        assert_eq!(
+            parsed.trailers,
+            [
+                ParsedTrailer {
+                    trailer: Trailer::new("Foo", "bar baz"),
+                    raw: "Foo: bar\r\n  baz",
+                },
+                ParsedTrailer {
+                    trailer: Trailer::new("Qux", "x"),
+                    raw: "Qux: x",
+                },
+            ]
+        );
+        assert_eq!(parsed.tail, "\r\n");
+        assert!(trailers("subject\r\n\r\nbody\r\n").is_empty());
+    }
+
+    #[test]
+    fn test_add() {
+        let edit = |description: &str| {
+            apply_trailer_edits(description, &[add("Foo", "bar")], TrailerDedup::Disabled)
+        };
+
+        // New trailer block
+        assert_eq!(edit(""), "\nFoo: bar\n");
+        assert_eq!(edit("subject"), "subject\n\nFoo: bar\n");
+        assert_eq!(edit("subject\n"), "subject\n\nFoo: bar\n");
+        assert_eq!(edit("subject\n\n\n"), "subject\n\nFoo: bar\n");
+        assert_eq!(edit("subject\n\nbody\n"), "subject\n\nbody\n\nFoo: bar\n");
+        assert_eq!(edit("Foo: bar\n"), "Foo: bar\n\nFoo: bar\n");
+        assert_eq!(
+            edit("subject\n\nBaz: qux\n\nbody\n"),
+            "subject\n\nBaz: qux\n\nbody\n\nFoo: bar\n"
+        );
+
+        // Appended to the existing trailer block
+        assert_eq!(
+            edit("subject\n\nFoo: bar\n"),
+            "subject\n\nFoo: bar\nFoo: bar\n"
+        );
+        assert_eq!(
+            edit("subject\n\nBaz:   qux\n  quux\n"),
+            "subject\n\nBaz:   qux\n  quux\nFoo: bar\n"
+        );
+        // Missing trailing newline and trailing blank lines are preserved
+        assert_eq!(edit("subject\n\nBaz: qux"), "subject\n\nBaz: qux\nFoo: bar");
+        assert_eq!(
+            edit("subject\n\nBaz: qux\n\n"),
+            "subject\n\nBaz: qux\nFoo: bar\n\n"
+        );
+
+        // CRLF
+        assert_eq!(edit("subject\r\n"), "subject\r\n\r\nFoo: bar\r\n");
+        assert_eq!(
+            edit("subject\r\n\r\nBaz: qux\r\n"),
+            "subject\r\n\r\nBaz: qux\r\nFoo: bar\r\n"
+        );
+    }
+
+    #[test]
+    fn test_add_dedup() {
+        let description = "subject\n\nfoo: bar\nBaz: qux\n";
+        let edit = |edits: &[TrailerEdit], dedup| apply_trailer_edits(description, edits, dedup);
+
+        assert_eq!(
+            edit(&[add("Foo", "bar")], TrailerDedup::Disabled),
+            "subject\n\nfoo: bar\nBaz: qux\nFoo: bar\n"
+        );
+
+        // Keys are compared case-insensitively, values exactly
+        assert_eq!(edit(&[add("Foo", "bar")], TrailerDedup::Exact), description);
+        assert_eq!(
+            edit(&[add("Foo", "Bar")], TrailerDedup::Exact),
+            "subject\n\nfoo: bar\nBaz: qux\nFoo: Bar\n"
+        );
+        assert_eq!(edit(&[add("Foo", "Bar")], TrailerDedup::ByKey), description);
+        assert_eq!(
+            edit(&[add("Quux", "x")], TrailerDedup::ByKey),
+            "subject\n\nfoo: bar\nBaz: qux\nQuux: x\n"
+        );
+
+        // Trailers added by earlier edits are also deduplicated
+        assert_eq!(
+            edit(&[add("A", "1"), add("A", "1")], TrailerDedup::Exact),
+            "subject\n\nfoo: bar\nBaz: qux\nA: 1\n"
+        );
+        assert_eq!(
+            edit(&[add("A", "1"), add("A", "2")], TrailerDedup::ByKey),
+            "subject\n\nfoo: bar\nBaz: qux\nA: 1\n"
+        );
+
+        // Folded values are compared after unfolding
+        assert_eq!(
+            apply_trailer_edits(
+                "subject\n\nFoo: bar\n baz\n",
+                &[add("Foo", "bar baz")],
+                TrailerDedup::Exact
+            ),
+            "subject\n\nFoo: bar\n baz\n"
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let edit = |description: &str, edits: &[TrailerEdit]| {
+            apply_trailer_edits(description, edits, TrailerDedup::Disabled)
+        };
+
+        let description = "subject\n\nFoo: a\nBar: b\nfoo: c\n";
+        assert_eq!(
+            edit(description, &[remove("FOO", None)]),
+            "subject\n\nBar: b\n"
+        );
+        assert_eq!(
+            edit(description, &[remove("Foo", Some("c"))]),
+            "subject\n\nFoo: a\nBar: b\n"
+        );
+        assert_eq!(edit(description, &[remove("Foo", Some("x"))]), description);
+        assert_eq!(edit(description, &[remove("Baz", None)]), description);
+
+        // Removing all trailers removes the trailer block
+        assert_eq!(
+            edit(description, &[remove("Foo", None), remove("Bar", None)]),
+            "subject\n"
+        );
+        assert_eq!(
+            edit("subject\n\nbody\n\n\nFoo: a\n\n", &[remove("Foo", None)]),
+            "subject\n\nbody\n"
+        );
+        assert_eq!(edit("subject\n\nFoo: a", &[remove("Foo", None)]), "subject");
+        assert_eq!(edit("\nFoo: a\n", &[remove("Foo", None)]), "");
+        assert_eq!(
+            edit("subject\r\n\r\nFoo: a\r\n", &[remove("Foo", None)]),
+            "subject\r\n"
+        );
+
+        // Trailer-looking lines outside of the trailer block are kept
+        assert_eq!(
+            edit("subject\n\nFoo: a\n\nbody\n", &[remove("Foo", None)]),
+            "subject\n\nFoo: a\n\nbody\n"
+        );
+        assert_eq!(edit("Foo: a\n", &[remove("Foo", None)]), "Foo: a\n");
+    }
+
+    #[test]
+    fn test_replace() {
+        let edit = |description: &str, edits: &[TrailerEdit]| {
+            apply_trailer_edits(description, edits, TrailerDedup::Disabled)
+        };
+
+        // Placed at the position of the first trailer with the key
+        let description = "subject\n\nA: 1\nFoo: a\nB: 2\nfoo: b\n";
+        assert_eq!(
+            edit(description, &[replace("Foo", "c")]),
+            "subject\n\nA: 1\nFoo: c\nB: 2\n"
+        );
+        assert_eq!(
+            edit(description, &[replace("Foo", "a")]),
+            "subject\n\nA: 1\nFoo: a\nB: 2\n"
+        );
+
+        // Appended if there's no trailer with the key
+        assert_eq!(
+            edit(description, &[replace("C", "3")]),
+            "subject\n\nA: 1\nFoo: a\nB: 2\nfoo: b\nC: 3\n"
+        );
+        assert_eq!(edit("subject\n", &[replace("C", "3")]), "subject\n\nC: 3\n");
+
+        // Replacing with the same trailer preserves the formatting
+        let description = "subject\n\nFoo:  a\n  b\nBar: c\n";
+        assert_eq!(edit(description, &[replace("Foo", "a b")]), description);
+        assert_eq!(
+            edit(description, &[replace("Foo", "a")]),
+            "subject\n\nFoo: a\nBar: c\n"
+        );
+    }
+
+    #[test]
+    fn test_unchanged() {
+        // Formatting is preserved if nothing changes
+        let description = "subject  \n\n\nbody\r\n\nFoo :  bar\n\tbaz\n\n\n";
+        assert_eq!(
+            apply_trailer_edits(description, &[], TrailerDedup::Disabled),
+            description
+        );
+        assert_eq!(
+            apply_trailer_edits(
+                description,
+                &[add("Qux", "x"), remove("Qux", None)],
+                TrailerDedup::Disabled
+            ),
+            description
+        );
+    }
+}
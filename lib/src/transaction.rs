@@ -146,6 +146,10 @@ impl Transaction {
     }
 }
 
+/// Creates operation metadata from the `user_settings`.
+///
+/// The metadata is deterministic if `debug.operation-timestamp`,
+/// `operation.hostname`, and `operation.username` are configured.
 pub fn create_op_metadata(
     user_settings: &UserSettings,
     description: String,
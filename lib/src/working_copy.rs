@@ -30,6 +30,7 @@ use crate::backend::MergedTreeId;
 use crate::commit::Commit;
 use crate::conflicts::ConflictMarkerStyle;
 use crate::dag_walk;
+use crate::fsmonitor::FsmonitorError;
 use crate::fsmonitor::FsmonitorSettings;
 use crate::gitignore::GitIgnoreError;
 use crate::gitignore::GitIgnoreFile;
@@ -246,6 +247,9 @@ pub type SnapshotProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
 pub struct SnapshotStats {
     /// List of new (previously untracked) files which are still untracked.
     pub untracked_paths: BTreeMap<RepoPathBuf, UntrackedReason>,
+    /// Error reported by the filesystem monitor if it was configured but
+    /// couldn't be queried.
+    pub fsmonitor_error: Option<FsmonitorError>,
 }
 
 /// Reason why the new path isn't tracked.
@@ -8,10 +8,12 @@ fn test_no_forgotten_test_files() {
 
 mod test_annotate;
 mod test_bad_locking;
+mod test_bundle;
 mod test_commit_builder;
 mod test_commit_concurrent;
 mod test_conflicts;
 mod test_default_revset_graph_iterator;
+mod test_evolution;
 mod test_fix;
 mod test_git;
 mod test_git_backend;
@@ -23,6 +25,7 @@ mod test_load_repo;
 mod test_local_working_copy;
 mod test_local_working_copy_concurrent;
 mod test_local_working_copy_sparse;
+mod test_memory_backend;
 mod test_merge_trees;
 mod test_merged_tree;
 mod test_mut_repo;
@@ -0,0 +1,365 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use itertools::Itertools as _;
+use jj_lib::backend::TreeValue;
+use jj_lib::bundle::Bundle;
+use jj_lib::bundle::BundleError;
+use jj_lib::commit::Commit;
+use jj_lib::merge::Merge;
+use jj_lib::repo::ReadonlyRepo;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::repo_path::RepoPathBuf;
+use test_case::test_case;
+use testutils::create_tree;
+use testutils::read_file;
+use testutils::TestRepo;
+use testutils::TestRepoBackend;
+
+/// Returns file contents of the commit's tree, which can be compared across
+/// backends.
+fn tree_contents(commit: &Commit) -> Vec<(RepoPathBuf, Merge<Option<String>>)> {
+    let store = commit.store();
+    commit
+        .tree()
+        .unwrap()
+        .entries()
+        .map(|(path, value)| {
+            let contents = value.unwrap().map(|value| match value {
+                Some(TreeValue::File { id, .. }) => {
+                    Some(String::from_utf8(read_file(store, &path, id)).unwrap())
+                }
+                None => None,
+                _ => panic!("unexpected value: {value:?}"),
+            });
+            (path, contents)
+        })
+        .collect()
+}
+
+/// Creates a linear history of 3 commits. The last one is conflicted.
+fn create_commits(repo: &Arc<ReadonlyRepo>) -> (Arc<ReadonlyRepo>, Vec<Commit>) {
+    let file_path = RepoPath::from_internal_string("file");
+    let dir_file_path = RepoPath::from_internal_string("dir/file");
+    let tree_a = create_tree(repo, &[(file_path, "a\n"), (dir_file_path, "base\n")]);
+    let tree_b = create_tree(repo, &[(file_path, "b\n"), (dir_file_path, "base\n")]);
+    let tree_side = create_tree(repo, &[(file_path, "c\n"), (dir_file_path, "base\n")]);
+    let tree_c = tree_b.merge(&tree_a, &tree_side).unwrap();
+    assert!(tree_c.has_conflict());
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_a = mut_repo
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree_a.id())
+        .set_description("a")
+        .write()
+        .unwrap();
+    let commit_b = mut_repo
+        .new_commit(vec![commit_a.id().clone()], tree_b.id())
+        .set_description("b")
+        .write()
+        .unwrap();
+    let commit_c = mut_repo
+        .new_commit(vec![commit_b.id().clone()], tree_c.id())
+        .set_description("c")
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    (repo, vec![commit_a, commit_b, commit_c])
+}
+
+#[test_case(TestRepoBackend::Test; "same backend")]
+#[test_case(TestRepoBackend::Git; "git backend")]
+fn test_bundle_round_trip(backend: TestRepoBackend) {
+    let source_repo = TestRepo::init();
+    let (source_repo, source_commits) = create_commits(&source_repo.repo);
+    let dest_repo = TestRepo::init_with_backend(backend);
+    let dest_repo = &dest_repo.repo;
+
+    // Commits can be passed in any order
+    let source_commits_rev = source_commits.iter().rev().cloned().collect_vec();
+    let bundle = Bundle::create(source_repo.store(), &source_commits_rev).unwrap();
+    assert_eq!(
+        bundle.commit_ids(),
+        source_commits
+            .iter()
+            .map(|commit| commit.id().clone())
+            .collect_vec()
+    );
+    assert_eq!(
+        bundle.base_commit_ids(),
+        [source_repo.store().root_commit_id().clone()]
+    );
+    let bundle = Bundle::from_bytes(&bundle.to_bytes()).unwrap();
+
+    let mut tx = dest_repo.start_transaction();
+    let applied = bundle.apply(tx.repo_mut()).unwrap();
+    assert!(applied.existing_commits.is_empty());
+    let dest_commits = applied.new_commits;
+    assert_eq!(dest_commits.len(), 3);
+    let dest_repo = tx.commit("apply bundle").unwrap();
+
+    // Change ids, metadata, and contents are preserved
+    for (source_commit, dest_commit) in source_commits.iter().zip(&dest_commits) {
+        assert_eq!(dest_commit.change_id(), source_commit.change_id());
+        assert_eq!(dest_commit.description(), source_commit.description());
+        assert_eq!(dest_commit.author(), source_commit.author());
+        assert_eq!(tree_contents(dest_commit), tree_contents(source_commit));
+    }
+    assert_eq!(
+        dest_commits[0].parent_ids(),
+        [dest_repo.store().root_commit_id().clone()]
+    );
+    assert_eq!(dest_commits[1].parent_ids(), [dest_commits[0].id().clone()]);
+    assert_eq!(dest_commits[2].parent_ids(), [dest_commits[1].id().clone()]);
+    assert!(dest_commits[2].has_conflict().unwrap());
+    assert_eq!(
+        *dest_repo.view().heads(),
+        [dest_commits[2].id().clone()].into()
+    );
+    if backend == TestRepoBackend::Test {
+        assert_eq!(
+            dest_commits.iter().map(Commit::id).collect_vec(),
+            source_commits.iter().map(Commit::id).collect_vec()
+        );
+    }
+}
+
+#[test]
+fn test_bundle_apply_existing_commits() {
+    let source_repo = TestRepo::init();
+    let (source_repo, source_commits) = create_commits(&source_repo.repo);
+    let dest_repo = TestRepo::init();
+    let dest_repo = &dest_repo.repo;
+
+    let bundle = Bundle::create(source_repo.store(), &source_commits[..2]).unwrap();
+    let mut tx = dest_repo.start_transaction();
+    let applied = bundle.apply(tx.repo_mut()).unwrap();
+    assert_eq!(applied.new_commits.len(), 2);
+    let dest_repo = tx.commit("apply bundle").unwrap();
+
+    // Commits which already exist aren't written again
+    let bundle = Bundle::create(source_repo.store(), &source_commits).unwrap();
+    let mut tx = dest_repo.start_transaction();
+    let applied = bundle.apply(tx.repo_mut()).unwrap();
+    assert_eq!(
+        applied
+            .existing_commits
+            .iter()
+            .map(Commit::id)
+            .collect_vec(),
+        source_commits[..2].iter().map(Commit::id).collect_vec()
+    );
+    assert_eq!(
+        applied.new_commits.iter().map(Commit::id).collect_vec(),
+        [source_commits[2].id()]
+    );
+    assert!(applied.new_commits[0].has_conflict().unwrap());
+}
+
+#[test]
+fn test_bundle_missing_base_commits() {
+    let source_repo = TestRepo::init();
+    let (source_repo, source_commits) = create_commits(&source_repo.repo);
+    let dest_repo = TestRepo::init();
+    let dest_repo = &dest_repo.repo;
+
+    let bundle = Bundle::create(source_repo.store(), &source_commits[2..]).unwrap();
+    assert_eq!(bundle.base_commit_ids(), [source_commits[1].id().clone()]);
+    let mut tx = dest_repo.start_transaction();
+    assert_matches!(
+        bundle.apply(tx.repo_mut()),
+        Err(BundleError::MissingBaseCommits(ids)) if ids == [source_commits[1].id().clone()]
+    );
+    assert!(!tx.repo().has_changes());
+}
+
+#[test]
+fn test_bundle_base_commits_other_backend() {
+    let source_repo = TestRepo::init();
+    let (source_repo, source_commits) = create_commits(&source_repo.repo);
+    let dest_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let dest_repo = &dest_repo.repo;
+
+    let bundle = Bundle::create(source_repo.store(), &source_commits[..1]).unwrap();
+    let mut tx = dest_repo.start_transaction();
+    let applied = bundle.apply(tx.repo_mut()).unwrap();
+    let dest_commit_a = applied.new_commits[0].clone();
+    assert_ne!(dest_commit_a.id(), source_commits[0].id());
+    let dest_repo = tx.commit("apply bundle").unwrap();
+
+    // Base commits are found by change id
+    let bundle = Bundle::create(source_repo.store(), &source_commits[1..]).unwrap();
+    assert_eq!(bundle.base_commit_ids(), [source_commits[0].id().clone()]);
+    let mut tx = dest_repo.start_transaction();
+    let applied = bundle.apply(tx.repo_mut()).unwrap();
+    assert_eq!(applied.new_commits.len(), 2);
+    assert_eq!(
+        applied.new_commits[0].change_id(),
+        source_commits[1].change_id()
+    );
+    assert_eq!(
+        applied.new_commits[0].parent_ids(),
+        [dest_commit_a.id().clone()]
+    );
+}
+
+/// Creates a commit with many unchanged files in `dir`, and a child commit
+/// which modifies `file`.
+fn create_commits_with_history(repo: &Arc<ReadonlyRepo>) -> (Arc<ReadonlyRepo>, Vec<Commit>) {
+    let file_path = RepoPath::from_internal_string("file");
+    let dir_files = (0..100)
+        .map(|i| {
+            let path = RepoPathBuf::from_internal_string(format!("dir/file{i}"));
+            (path, format!("{i}{}\n", "x".repeat(1000)))
+        })
+        .collect_vec();
+    let tree = |content| {
+        let path_contents = dir_files
+            .iter()
+            .map(|(path, content)| (path.as_ref(), content.as_str()))
+            .chain([(file_path, content)])
+            .collect_vec();
+        create_tree(repo, &path_contents)
+    };
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_a = mut_repo
+        .new_commit(
+            vec![repo.store().root_commit_id().clone()],
+            tree("a\n").id(),
+        )
+        .write()
+        .unwrap();
+    let commit_b = mut_repo
+        .new_commit(vec![commit_a.id().clone()], tree("b\n").id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    (repo, vec![commit_a, commit_b])
+}
+
+#[test_case(TestRepoBackend::Test; "same backend")]
+#[test_case(TestRepoBackend::Git; "git backend")]
+fn test_bundle_excludes_base_objects(backend: TestRepoBackend) {
+    let source_repo = TestRepo::init();
+    let (source_repo, source_commits) = create_commits_with_history(&source_repo.repo);
+    let dest_repo = TestRepo::init_with_backend(backend);
+    let dest_repo = &dest_repo.repo;
+
+    let bundle = Bundle::create(source_repo.store(), &source_commits[..1]).unwrap();
+    assert!(bundle.to_bytes().len() > 100 * 1000);
+    let mut tx = dest_repo.start_transaction();
+    bundle.apply(tx.repo_mut()).unwrap();
+    let dest_repo = tx.commit("apply bundle").unwrap();
+
+    // Files which are unchanged since the base commit aren't included
+    let bundle = Bundle::create(source_repo.store(), &source_commits[1..]).unwrap();
+    let size = bundle.to_bytes().len();
+    assert!(size < 1000, "bundle size: {size}");
+
+    // They are read from the base commit in the destination repo
+    let bundle = Bundle::from_bytes(&bundle.to_bytes()).unwrap();
+    let mut tx = dest_repo.start_transaction();
+    let applied = bundle.apply(tx.repo_mut()).unwrap();
+    assert_eq!(
+        tree_contents(&applied.new_commits[0]),
+        tree_contents(&source_commits[1])
+    );
+}
+
+#[test]
+fn test_bundle_base_commit_content_differs() {
+    let source_repo = TestRepo::init();
+    let (source_repo, source_commits) = create_commits_with_history(&source_repo.repo);
+    let dest_repo = TestRepo::init();
+    let dest_repo = &dest_repo.repo;
+
+    // The destination repo has another version of the base commit
+    let mut tx = dest_repo.start_transaction();
+    let dir_file_path = RepoPath::from_internal_string("dir/file0");
+    let dest_commit_a = tx
+        .repo_mut()
+        .new_commit(
+            vec![dest_repo.store().root_commit_id().clone()],
+            create_tree(dest_repo, &[(dir_file_path, "y\n")]).id(),
+        )
+        .set_change_id(source_commits[0].change_id().clone())
+        .write()
+        .unwrap();
+    let dest_repo = tx.commit("test").unwrap();
+    assert_ne!(dest_commit_a.id(), source_commits[0].id());
+
+    let bundle = Bundle::create(source_repo.store(), &source_commits[1..]).unwrap();
+    let mut tx = dest_repo.start_transaction();
+    assert_matches!(
+        bundle.apply(tx.repo_mut()),
+        Err(BundleError::MissingObject {
+            object_type: "tree",
+            ..
+        })
+    );
+}
+
+#[test]
+fn test_bundle_root_commit() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    assert_matches!(
+        Bundle::create(repo.store(), &[repo.store().root_commit()]),
+        Err(BundleError::RootCommit)
+    );
+}
+
+#[test]
+fn test_bundle_invalid_data() {
+    assert_matches!(
+        Bundle::from_bytes(b"not a bundle"),
+        Err(BundleError::InvalidHeader)
+    );
+    assert_matches!(
+        Bundle::from_bytes(b"JJ BUNDLE 1\n\xff\xff"),
+        Err(BundleError::Decode(_))
+    );
+}
+
+#[test]
+fn test_bundle_empty_tree() {
+    let source_repo = TestRepo::init();
+    let repo = &source_repo.repo;
+    let mut tx = repo.start_transaction();
+    let commit = tx
+        .repo_mut()
+        .new_commit(
+            vec![repo.store().root_commit_id().clone()],
+            repo.store().empty_merged_tree_id(),
+        )
+        .write()
+        .unwrap();
+    let bundle = Bundle::create(repo.store(), &[commit]).unwrap();
+
+    let dest_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let dest_repo = &dest_repo.repo;
+    let mut tx = dest_repo.start_transaction();
+    let applied = bundle.apply(tx.repo_mut()).unwrap();
+    assert_eq!(
+        *applied.new_commits[0].tree_id(),
+        dest_repo.store().empty_merged_tree_id()
+    );
+}
@@ -18,10 +18,13 @@ use jj_lib::commit::Commit;
 use jj_lib::default_index::revset_engine::evaluate;
 use jj_lib::default_index::revset_engine::RevsetImpl;
 use jj_lib::default_index::DefaultReadonlyIndex;
+use jj_lib::graph::reverse_graph;
 use jj_lib::graph::GraphEdge;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo as _;
 use jj_lib::revset::ResolvedExpression;
+use jj_lib::revset::Revset;
+use jj_lib::revset::GENERATION_RANGE_FULL;
 use test_case::test_case;
 use testutils::CommitGraphBuilder;
 use testutils::TestRepo;
@@ -330,3 +333,86 @@ fn test_graph_iterator_edge_escapes_from_(skip_transitive_edges: bool) {
     assert_eq!(commits[3].1, vec![indirect(&commit_a)]);
     assert_eq!(commits[4].1, vec![missing(&root_commit)]);
 }
+
+#[test]
+fn test_graph_iterator_reversed_ancestors() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    //   J
+    //  /|
+    // | i
+    // | |\
+    // | | H
+    // G | |
+    // | e f
+    // |  \|\
+    // |   D |
+    //  \ /  c
+    //   b  /
+    //   |/
+    //   A
+    //   |
+    //  root
+    let mut tx = repo.start_transaction();
+    let mut graph_builder = CommitGraphBuilder::new(tx.repo_mut());
+    let commit_a = graph_builder.initial_commit();
+    let commit_b = graph_builder.commit_with_parents(&[&commit_a]);
+    let commit_c = graph_builder.commit_with_parents(&[&commit_a]);
+    let commit_d = graph_builder.commit_with_parents(&[&commit_b]);
+    let commit_e = graph_builder.commit_with_parents(&[&commit_d]);
+    let commit_f = graph_builder.commit_with_parents(&[&commit_d, &commit_c]);
+    let commit_g = graph_builder.commit_with_parents(&[&commit_b]);
+    let commit_h = graph_builder.commit_with_parents(&[&commit_f]);
+    let commit_i = graph_builder.commit_with_parents(&[&commit_e, &commit_h]);
+    let commit_j = graph_builder.commit_with_parents(&[&commit_g, &commit_i]);
+    let repo = tx.commit("test").unwrap();
+    let root_commit = repo.store().root_commit();
+
+    let index = repo
+        .readonly_index()
+        .as_any()
+        .downcast_ref::<DefaultReadonlyIndex>()
+        .unwrap();
+    let ancestors = |heads: &[&Commit]| -> Box<dyn Revset> {
+        let expression = ResolvedExpression::Ancestors {
+            heads: Box::new(ResolvedExpression::Commits(
+                heads.iter().map(|commit| commit.id().clone()).collect(),
+            )),
+            generation: GENERATION_RANGE_FULL,
+        };
+        Box::new(evaluate(&expression, repo.store(), index.clone()).unwrap())
+    };
+
+    // The reversed graph is emitted from the roots, and is the same as the one
+    // reversed after walking the whole graph.
+    for heads in [
+        vec![&commit_j],
+        vec![&commit_h, &commit_g],
+        vec![&commit_e, &commit_c],
+    ] {
+        let revset = ancestors(&heads);
+        assert!(revset.is_ancestor_closed());
+        let expected = reverse_graph(revset.iter_graph(), |id| id).unwrap();
+        let reversed = revset.reversed();
+        let commits: Vec<_> = reversed.iter_graph().try_collect().unwrap();
+        assert_eq!(commits, expected);
+        let ids: Vec<_> = reversed.iter().try_collect().unwrap();
+        assert_eq!(ids, expected.iter().map(|(id, _)| id.clone()).collect_vec());
+    }
+
+    let revset = ancestors(&[&commit_j]);
+    let commits: Vec<_> = revset.reversed().iter_graph().try_collect().unwrap();
+    assert_eq!(commits.len(), 11);
+    assert_eq!(commits[0].0, *root_commit.id());
+    assert_eq!(commits[0].1, vec![direct(&commit_a)]);
+    assert_eq!(commits[1].0, *commit_a.id());
+    // Children are ordered as they appear in the forward graph
+    assert_eq!(commits[1].1, vec![direct(&commit_c), direct(&commit_b)]);
+    assert_eq!(commits[10].0, *commit_j.id());
+    assert_eq!(commits[10].1, vec![]);
+
+    // Arbitrary sets aren't known to be closed under ancestors
+    let revset = revset_for_commits(repo.as_ref(), &[&commit_a, &commit_d]);
+    assert!(!revset.is_ancestor_closed());
+}
@@ -0,0 +1,190 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::evolution::walk_predecessors;
+use jj_lib::repo::Repo as _;
+use testutils::test_backend::TestBackend;
+use testutils::write_random_commit;
+use testutils::TestRepo;
+
+fn walk_ids(start_commit: &Commit, max_depth: Option<usize>) -> Vec<(CommitId, usize)> {
+    walk_predecessors([start_commit.clone()], max_depth)
+        .map_ok(|entry| (entry.commit.id().clone(), entry.depth))
+        .try_collect()
+        .unwrap()
+}
+
+#[test]
+fn test_walk_predecessors_linear() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let mut commits = vec![write_random_commit(mut_repo)];
+    for i in 1..200 {
+        let commit = mut_repo
+            .rewrite_commit(commits.last().unwrap())
+            .set_description(format!("amended {i}"))
+            .write()
+            .unwrap();
+        commits.push(commit);
+    }
+    let head = commits.last().unwrap();
+
+    // Newest first, with the distance from the starting commit
+    let expected = commits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(depth, commit)| (commit.id().clone(), depth))
+        .collect_vec();
+    assert_eq!(walk_ids(head, None), expected);
+
+    // Depth is capped
+    assert_eq!(walk_ids(head, Some(0)), expected[..1]);
+    assert_eq!(walk_ids(head, Some(3)), expected[..4]);
+    assert_eq!(walk_ids(head, Some(1000)), expected);
+
+    // Can stop iterating in the middle of a long history
+    let entries: Vec<_> = walk_predecessors([head.clone()], None)
+        .take(5)
+        .try_collect()
+        .unwrap();
+    assert_eq!(
+        entries
+            .iter()
+            .map(|entry| (entry.commit.id().clone(), entry.depth))
+            .collect_vec(),
+        expected[..5]
+    );
+
+    // Starting from the middle of the history
+    assert_eq!(
+        walk_ids(&commits[2], None),
+        [
+            (commits[2].id().clone(), 0),
+            (commits[1].id().clone(), 1),
+            (commits[0].id().clone(), 2),
+        ]
+    );
+}
+
+#[test]
+fn test_walk_predecessors_reads_lazily() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let mut commit = write_random_commit(mut_repo);
+    for i in 1..1000 {
+        commit = mut_repo
+            .rewrite_commit(&commit)
+            .set_description(format!("amended {i}"))
+            .write()
+            .unwrap();
+    }
+    mut_repo.rebase_descendants().unwrap();
+    tx.commit("test").unwrap();
+
+    // Reload the repo so commits aren't served from the store's cache
+    let repo = test_repo
+        .env
+        .load_repo_at_head(&testutils::user_settings(), test_repo.repo_path());
+    let test_backend: &TestBackend = repo.store().backend_impl().downcast_ref().unwrap();
+    let head = repo.store().get_commit(commit.id()).unwrap();
+    let read_count = || test_backend.commit_read_count();
+
+    // Taking the first entries of a long history reads only a few commits
+    let start_count = read_count();
+    let entries: Vec<_> = walk_predecessors([head.clone()], None)
+        .take(10)
+        .try_collect()
+        .unwrap();
+    assert_eq!(entries.len(), 10);
+    assert_eq!(entries[9].depth, 9);
+    assert!(read_count() - start_count <= 10);
+
+    // So does a walk with capped depth
+    let start_count = read_count();
+    let entries: Vec<_> = walk_predecessors([head], Some(20)).try_collect().unwrap();
+    assert_eq!(entries.len(), 21);
+    assert!(read_count() - start_count <= 20);
+}
+
+#[test]
+fn test_walk_predecessors_squashed() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_a1 = write_random_commit(mut_repo);
+    let commit_b1 = write_random_commit(mut_repo);
+    let commit_a2 = mut_repo
+        .rewrite_commit(&commit_a1)
+        .set_description("a2")
+        .write()
+        .unwrap();
+    let commit_b2 = mut_repo
+        .rewrite_commit(&commit_b1)
+        .set_description("b2")
+        .write()
+        .unwrap();
+    // Squash b2 into a2
+    let commit_a3 = mut_repo
+        .rewrite_commit(&commit_a2)
+        .set_predecessors(vec![commit_a2.id().clone(), commit_b2.id().clone()])
+        .write()
+        .unwrap();
+
+    let entries = walk_ids(&commit_a3, None);
+    assert_eq!(entries[0], (commit_a3.id().clone(), 0));
+    assert_eq!(
+        entries.iter().sorted().collect_vec(),
+        [
+            (commit_a1.id().clone(), 2),
+            (commit_a2.id().clone(), 1),
+            (commit_a3.id().clone(), 0),
+            (commit_b1.id().clone(), 2),
+            (commit_b2.id().clone(), 1),
+        ]
+        .iter()
+        .sorted()
+        .collect_vec()
+    );
+    // Successors come before predecessors
+    let position = |id: &CommitId| entries.iter().position(|(i, _)| i == id).unwrap();
+    assert!(position(commit_a2.id()) < position(commit_a1.id()));
+    assert!(position(commit_b2.id()) < position(commit_b1.id()));
+
+    assert_eq!(
+        walk_ids(&commit_a3, Some(1))
+            .into_iter()
+            .sorted()
+            .collect_vec(),
+        [
+            (commit_a2.id().clone(), 1),
+            (commit_a3.id().clone(), 0),
+            (commit_b2.id().clone(), 1),
+        ]
+        .into_iter()
+        .sorted()
+        .collect_vec()
+    );
+}
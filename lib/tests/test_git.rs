@@ -1149,6 +1149,84 @@ fn test_import_refs_reimport_abandoning_disabled() {
     assert!(tx.repo().view().heads().contains(&jj_id(commit2)));
 }
 
+#[test]
+fn test_import_refs_rewrites_by_change_id() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_repo.repo;
+    let git_repo = get_git_repo(repo);
+
+    // My commit, which has also been pushed to the remote, and its child
+    let mut tx = repo.start_transaction();
+    let commit1 = write_random_commit(tx.repo_mut());
+    let commit2 = create_random_commit(tx.repo_mut())
+        .set_parents(vec![commit1.id().clone()])
+        .write()
+        .unwrap();
+    tx.repo_mut()
+        .set_local_bookmark_target("main".as_ref(), RefTarget::normal(commit1.id().clone()));
+    let repo = tx.commit("test").unwrap();
+
+    // Someone else fetches commit1 into their clone, amends it, and pushes it
+    // back. Their jj records the change id in the commit header.
+    let signature = gix::actor::Signature {
+        name: "Someone Else".into(),
+        email: "someone.else@example.com".into(),
+        time: gix::date::Time::new(1000, 0),
+    };
+    let amended_git_commit = gix::objs::Commit {
+        tree: git_repo.empty_tree().id().detach(),
+        parents: Default::default(),
+        author: signature.clone(),
+        committer: signature,
+        encoding: None,
+        message: "amended by someone else\n".into(),
+        extra_headers: vec![("change-id".into(), commit1.change_id().reverse_hex().into())],
+    };
+    let amended_git_id = git_repo.write_object(&amended_git_commit).unwrap().detach();
+    git_ref(&git_repo, "refs/remotes/origin/main", amended_git_id);
+    let amended_id = jj_id(amended_git_id);
+
+    // Without the setting, the fetched commit becomes divergent
+    let mut tx = repo.start_transaction();
+    let stats = git::import_refs(tx.repo_mut(), &GitSettings::default()).unwrap();
+    tx.repo_mut().rebase_descendants().unwrap();
+    assert!(stats.rewritten_commits.is_empty());
+    assert_eq!(
+        tx.repo()
+            .resolve_change_id(commit1.change_id())
+            .unwrap()
+            .len(),
+        2
+    );
+    drop(tx);
+
+    // With the setting, the fetched commit supersedes the local one
+    let git_settings = GitSettings {
+        import_rewrites_by_change_id: true,
+        ..Default::default()
+    };
+    let mut tx = repo.start_transaction();
+    let stats = git::import_refs(tx.repo_mut(), &git_settings).unwrap();
+    tx.repo_mut().rebase_descendants().unwrap();
+    assert_eq!(
+        stats.rewritten_commits,
+        vec![(commit1.id().clone(), amended_id.clone())]
+    );
+    let repo = tx.commit("import").unwrap();
+    assert_eq!(
+        repo.resolve_change_id(commit1.change_id()),
+        Some(vec![amended_id.clone()])
+    );
+    assert_eq!(
+        repo.view().get_local_bookmark("main".as_ref()),
+        &RefTarget::normal(amended_id.clone())
+    );
+    let new_commit2_ids = repo.resolve_change_id(commit2.change_id()).unwrap();
+    assert_eq!(new_commit2_ids.len(), 1);
+    let new_commit2 = repo.store().get_commit(&new_commit2_ids[0]).unwrap();
+    assert_eq!(new_commit2.parent_ids(), [amended_id]);
+}
+
 #[test]
 fn test_import_refs_reimport_conflicted_remote_bookmark() {
     let git_settings = GitSettings {
@@ -1957,6 +2035,10 @@ fn test_export_conflicts() {
     );
     let stats = git::export_refs(mut_repo).unwrap();
     assert!(stats.failed_bookmarks.is_empty());
+    assert_eq!(
+        stats.conflicted_bookmarks,
+        [remote_symbol("feature", "git").to_owned()]
+    );
     assert_eq!(
         git_repo
             .find_reference("refs/heads/feature")
@@ -2013,6 +2095,75 @@ fn test_export_bookmark_on_root_commit() {
     );
 }
 
+#[test]
+fn test_export_bookmark_missing_target() {
+    // We skip export of bookmarks pointing to commits which don't exist in Git
+    let test_data = GitRepoData::create();
+    let git_repo = test_data.git_repo;
+    let mut tx = test_data.repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_a = write_random_commit(mut_repo);
+    let commit_b = write_random_commit(mut_repo);
+    mut_repo.set_local_bookmark_target("a".as_ref(), RefTarget::normal(commit_a.id().clone()));
+    mut_repo.set_local_bookmark_target("b".as_ref(), RefTarget::normal(commit_b.id().clone()));
+
+    // Remove the loose object of commit_b from the Git repo
+    let hex = commit_b.id().hex();
+    std::fs::remove_file(
+        git_repo
+            .path()
+            .join("objects")
+            .join(&hex[..2])
+            .join(&hex[2..]),
+    )
+    .unwrap();
+    assert!(!git_repo.has_object(git_id(&commit_b)));
+
+    let stats = git::export_refs(mut_repo).unwrap();
+    assert_eq!(stats.failed_bookmarks.len(), 1);
+    assert_eq!(
+        stats.failed_bookmarks[0].0.as_ref(),
+        remote_symbol("b", "git")
+    );
+    assert_matches!(
+        stats.failed_bookmarks[0].1,
+        FailedRefExportReason::MissingTarget
+    );
+    assert!(git_repo.find_reference("refs/heads/a").is_ok());
+    assert!(git_repo.find_reference("refs/heads/b").is_err());
+    assert!(mut_repo
+        .get_remote_bookmark(remote_symbol("b", "git"))
+        .is_absent());
+}
+
+#[test]
+fn test_export_some_refs() {
+    let test_data = GitRepoData::create();
+    let git_repo = test_data.git_repo;
+    let mut tx = test_data.repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_a = write_random_commit(mut_repo);
+    let target = RefTarget::normal(commit_a.id().clone());
+    mut_repo.set_local_bookmark_target("feature".as_ref(), target.clone());
+    mut_repo.set_local_bookmark_target("main".as_ref(), target.clone());
+
+    let stats =
+        git::export_some_refs(mut_repo, |_, symbol| symbol.name.as_str() == "main").unwrap();
+    assert!(stats.failed_bookmarks.is_empty());
+    assert!(git_repo.find_reference("refs/heads/feature").is_err());
+    assert!(git_repo.find_reference("refs/heads/main").is_ok());
+    assert!(mut_repo
+        .get_remote_bookmark(remote_symbol("feature", "git"))
+        .is_absent());
+    assert_eq!(
+        mut_repo.get_remote_bookmark(remote_symbol("main", "git")),
+        RemoteRef {
+            target,
+            state: RemoteRefState::Tracked,
+        },
+    );
+}
+
 #[test]
 fn test_export_partial_failure() {
     // Check that we skip bookmarks that fail to export
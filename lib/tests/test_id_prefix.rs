@@ -26,6 +26,7 @@ use jj_lib::object_id::PrefixResolution::NoMatch;
 use jj_lib::object_id::PrefixResolution::SingleMatch;
 use jj_lib::repo::Repo as _;
 use jj_lib::revset::RevsetExpression;
+use testutils::write_random_commit;
 use testutils::TestRepo;
 use testutils::TestRepoBackend;
 
@@ -532,3 +533,112 @@ fn test_id_prefix_hidden() {
         NoMatch
     );
 }
+
+#[test]
+fn test_id_prefix_context_shortest_prefix_len() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let root_commit_id = repo.store().root_commit_id();
+    let root_change_id = repo.store().root_change_id();
+    let prefix = |x: &str| HexPrefix::new(x).unwrap();
+
+    let mut tx = repo.start_transaction();
+    let target_commit = write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+    let expression = RevsetExpression::commit(target_commit.id().clone()).descendants();
+
+    // The only commit in the disambiguation set
+    let context = IdPrefixContext::default().disambiguate_within(expression.clone());
+    assert_eq!(
+        context
+            .shortest_commit_prefix_len(repo.as_ref(), target_commit.id())
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        context
+            .shortest_change_prefix_len(repo.as_ref(), target_commit.change_id())
+            .unwrap(),
+        1
+    );
+
+    // Falls back to the repo-wide length outside of the disambiguation set
+    assert_eq!(
+        context
+            .shortest_commit_prefix_len(repo.as_ref(), root_commit_id)
+            .unwrap(),
+        repo.index()
+            .shortest_unique_commit_id_prefix_len(root_commit_id)
+    );
+    assert_eq!(
+        context
+            .shortest_change_prefix_len(repo.as_ref(), root_change_id)
+            .unwrap(),
+        repo.shortest_unique_change_id_prefix_len(root_change_id)
+    );
+
+    // Add more commits to the disambiguation set than there are hex digits, so
+    // some of the IDs collide in the first digit.
+    let mut tx = repo.start_transaction();
+    let mut commits = vec![target_commit];
+    for _ in 0..32 {
+        let parent = commits.last().unwrap();
+        let commit = tx
+            .repo_mut()
+            .new_commit(vec![parent.id().clone()], parent.tree_id().clone())
+            .write()
+            .unwrap();
+        commits.push(commit);
+    }
+    let repo = tx.commit("test").unwrap();
+
+    // The cached index is loaded from the old repo, so use a new context.
+    let context = IdPrefixContext::default().disambiguate_within(expression);
+    let index = context.populate(repo.as_ref()).unwrap();
+    let mut commit_lens = vec![];
+    let mut change_lens = vec![];
+    for commit in &commits {
+        let commit_hex = commit.id().hex();
+        let len = context
+            .shortest_commit_prefix_len(repo.as_ref(), commit.id())
+            .unwrap();
+        assert_eq!(
+            len,
+            index.shortest_commit_prefix_len(repo.as_ref(), commit.id())
+        );
+        assert!(
+            len <= repo
+                .index()
+                .shortest_unique_commit_id_prefix_len(commit.id())
+        );
+        assert_eq!(
+            index.resolve_commit_prefix(repo.as_ref(), &prefix(&commit_hex[..len])),
+            SingleMatch(commit.id().clone())
+        );
+        if len > 1 {
+            assert_eq!(
+                index.resolve_commit_prefix(repo.as_ref(), &prefix(&commit_hex[..len - 1])),
+                AmbiguousMatch
+            );
+        }
+        commit_lens.push(len);
+
+        let change_hex = commit.change_id().hex();
+        let len = context
+            .shortest_change_prefix_len(repo.as_ref(), commit.change_id())
+            .unwrap();
+        assert_eq!(
+            index.resolve_change_prefix(repo.as_ref(), &prefix(&change_hex[..len])),
+            SingleMatch(vec![commit.id().clone()])
+        );
+        if len > 1 {
+            assert_eq!(
+                index.resolve_change_prefix(repo.as_ref(), &prefix(&change_hex[..len - 1])),
+                AmbiguousMatch
+            );
+        }
+        change_lens.push(len);
+    }
+    assert!(commit_lens.iter().any(|&len| len > 1));
+    assert!(change_lens.iter().any(|&len| len > 1));
+}
@@ -0,0 +1,116 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::sync::Arc;
+
+use jj_lib::backend::TreeValue;
+use jj_lib::memory_backend::MemoryRepoStorage;
+use jj_lib::repo::ReadonlyRepo;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::signing::Signer;
+use testutils::create_tree;
+use testutils::read_file;
+use testutils::write_random_commit;
+use testutils::TestRepo;
+use testutils::TestRepoBackend;
+
+fn load_at_head(storage: &MemoryRepoStorage) -> Arc<ReadonlyRepo> {
+    let settings = testutils::user_settings();
+    let signer = Signer::from_settings(&settings).unwrap();
+    storage.loader(&settings, signer).load_at_head().unwrap()
+}
+
+#[test]
+fn test_memory_repo_transaction_without_file_system() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Memory);
+    let root_dir = test_repo.env.root();
+    let original_permissions = fs::metadata(root_dir).unwrap().permissions();
+    let mut readonly_permissions = original_permissions.clone();
+    readonly_permissions.set_readonly(true);
+    fs::set_permissions(root_dir, readonly_permissions).unwrap();
+
+    let repo = &test_repo.repo;
+    let file_path = RepoPath::from_internal_string("file");
+    let tree = create_tree(repo, &[(file_path, "contents\n")]);
+    let mut tx = repo.start_transaction();
+    let commit = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(*repo.view().heads(), [commit.id().clone()].into());
+
+    // The new operation and its objects can be loaded back
+    let reloaded_repo = repo.reload_at_head().unwrap();
+    assert_eq!(reloaded_repo.op_id(), repo.op_id());
+    let reloaded_commit = reloaded_repo.store().get_commit(commit.id()).unwrap();
+    assert_eq!(reloaded_commit, commit);
+    let value = reloaded_commit
+        .tree()
+        .unwrap()
+        .path_value(file_path)
+        .unwrap();
+    let Some(TreeValue::File { id, .. }) = value.as_normal() else {
+        panic!("unexpected value: {value:?}");
+    };
+    assert_eq!(
+        read_file(reloaded_repo.store(), file_path, id),
+        b"contents\n"
+    );
+
+    fs::set_permissions(root_dir, original_permissions).unwrap();
+    assert!(root_dir.read_dir().unwrap().next().is_none());
+}
+
+#[test]
+fn test_memory_repo_storage_snapshot() {
+    let storage = MemoryRepoStorage::new();
+    let repo = load_at_head(&storage);
+    let mut tx = repo.start_transaction();
+    let commit_a = write_random_commit(tx.repo_mut());
+    tx.commit("a").unwrap();
+
+    let snapshot = storage.clone();
+    let repo = load_at_head(&storage);
+    let mut tx = repo.start_transaction();
+    let commit_b = write_random_commit(tx.repo_mut());
+    tx.commit("b").unwrap();
+
+    // Changes made after the snapshot was taken aren't visible in it
+    let snapshot_repo = load_at_head(&snapshot);
+    assert_eq!(
+        *snapshot_repo.view().heads(),
+        [commit_a.id().clone()].into()
+    );
+    assert!(snapshot_repo.store().get_commit(commit_b.id()).is_err());
+
+    // Changes made in the snapshot aren't visible in the original storage
+    let mut tx = snapshot_repo.start_transaction();
+    let commit_c = write_random_commit(tx.repo_mut());
+    tx.commit("c").unwrap();
+    let repo = load_at_head(&storage);
+    assert_eq!(
+        *repo.view().heads(),
+        [commit_a.id().clone(), commit_b.id().clone()].into()
+    );
+    assert!(repo.store().get_commit(commit_c.id()).is_err());
+    let snapshot_repo = load_at_head(&snapshot);
+    assert_eq!(
+        *snapshot_repo.view().heads(),
+        [commit_a.id().clone(), commit_c.id().clone()].into()
+    );
+}
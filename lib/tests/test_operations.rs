@@ -29,8 +29,11 @@ use jj_lib::op_walk::OpsetResolutionError;
 use jj_lib::operation::Operation;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPath;
 use jj_lib::settings::UserSettings;
 use testutils::create_random_commit;
+use testutils::create_tree;
+use testutils::deterministic_user_settings;
 use testutils::write_random_commit;
 use testutils::TestRepo;
 
@@ -152,6 +155,43 @@ fn test_concurrent_operations() {
     assert_eq!(list_dir(&op_heads_dir), vec![merged_op_id.hex()]);
 }
 
+#[test]
+fn test_deterministic_operation_ids() {
+    // Runs the same operations in a new repo, and returns the operation ids.
+    let run_operations = || {
+        let test_repo = TestRepo::init_with_settings(&deterministic_user_settings());
+        let repo = &test_repo.repo;
+        let mut op_ids = vec![repo.op_id().clone()];
+
+        let mut tx = repo.start_transaction();
+        let tree = create_tree(repo, &[(RepoPath::from_internal_string("file"), "a\n")]);
+        let commit = tx
+            .repo_mut()
+            .new_commit(vec![repo.store().root_commit_id().clone()], tree.id())
+            .set_description("first")
+            .write()
+            .unwrap();
+        let repo = tx.commit("first").unwrap();
+        op_ids.push(repo.op_id().clone());
+
+        let mut tx = repo.start_transaction();
+        tx.repo_mut()
+            .rewrite_commit(&commit)
+            .set_description("second")
+            .write()
+            .unwrap();
+        tx.repo_mut().rebase_descendants().unwrap();
+        let repo = tx.commit("second").unwrap();
+        op_ids.push(repo.op_id().clone());
+        op_ids
+    };
+
+    let op_ids1 = run_operations();
+    let op_ids2 = run_operations();
+    assert_eq!(op_ids1, op_ids2);
+    assert!(op_ids1.iter().all_unique());
+}
+
 fn assert_heads(repo: &dyn Repo, expected: Vec<&CommitId>) {
     let expected = expected.iter().cloned().cloned().collect();
     assert_eq!(*repo.view().heads(), expected);
@@ -12,15 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::any::Any;
+use std::cell::Cell;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::iter;
+use std::ops::ControlFlow;
 use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
 
 use assert_matches::assert_matches;
 use chrono::DateTime;
 use itertools::Itertools as _;
 use jj_lib::backend::ChangeId;
 use jj_lib::backend::CommitId;
+use jj_lib::backend::DuplicateSource;
+use jj_lib::backend::MergedTreeId;
 use jj_lib::backend::MillisSinceEpoch;
 use jj_lib::backend::Signature;
 use jj_lib::backend::Timestamp;
@@ -28,6 +36,7 @@ use jj_lib::commit::Commit;
 use jj_lib::fileset::FilesetExpression;
 use jj_lib::git;
 use jj_lib::graph::reverse_graph;
+use jj_lib::graph::reverse_graph_iter;
 use jj_lib::graph::GraphEdge;
 use jj_lib::id_prefix::IdPrefixContext;
 use jj_lib::object_id::ObjectId as _;
@@ -39,28 +48,54 @@ use jj_lib::ref_name::RemoteName;
 use jj_lib::ref_name::RemoteRefSymbol;
 use jj_lib::ref_name::WorkspaceName;
 use jj_lib::ref_name::WorkspaceNameBuf;
+use jj_lib::repo::find_stale_workspaces;
+use jj_lib::repo::MutableRepo;
 use jj_lib::repo::Repo;
+use jj_lib::repo::StaleWorkspaces;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathUiConverter;
+use jj_lib::revset::common_ancestor_count;
+use jj_lib::revset::compute_heads;
+use jj_lib::revset::compute_roots;
+use jj_lib::revset::expect_literal;
 use jj_lib::revset::parse;
 use jj_lib::revset::DefaultSymbolResolver;
 use jj_lib::revset::FailingSymbolResolver;
+use jj_lib::revset::FunctionCallNode;
+use jj_lib::revset::LoweringContext;
+use jj_lib::revset::PartialSymbolResolver;
+use jj_lib::revset::ResolutionStage;
+use jj_lib::revset::ResolvedRevsetExpression;
 use jj_lib::revset::Revset;
 use jj_lib::revset::RevsetAliasesMap;
 use jj_lib::revset::RevsetDiagnostics;
+use jj_lib::revset::RevsetEvaluationBudget;
+use jj_lib::revset::RevsetEvaluationError;
 use jj_lib::revset::RevsetExpression;
 use jj_lib::revset::RevsetExtensions;
+use jj_lib::revset::RevsetFilterExtension;
 use jj_lib::revset::RevsetFilterPredicate;
 use jj_lib::revset::RevsetParseContext;
+use jj_lib::revset::RevsetParseError;
+use jj_lib::revset::RevsetParseErrorKind;
 use jj_lib::revset::RevsetResolutionError;
 use jj_lib::revset::RevsetWorkspaceContext;
 use jj_lib::revset::SymbolResolver as _;
 use jj_lib::revset::SymbolResolverExtension;
+use jj_lib::revset::UserRevsetExpression;
+use jj_lib::rewrite::duplicate_commits_onto_parents;
+use jj_lib::signing::SignBehavior;
+use jj_lib::signing::Signer;
+use jj_lib::str_util::StringPattern;
+use jj_lib::test_signing_backend::TestSigningBackend;
 use jj_lib::workspace::Workspace;
 use test_case::test_case;
 use testutils::create_random_commit;
 use testutils::create_tree;
+use testutils::write_executable_file;
+use testutils::write_normal_file;
 use testutils::write_random_commit;
+use testutils::write_symlink;
 use testutils::CommitGraphBuilder;
 use testutils::TestRepo;
 use testutils::TestRepoBackend;
@@ -204,13 +239,40 @@ fn test_resolve_symbol_commit_id() {
     );
     assert_matches!(
         resolve_symbol(repo.as_ref(), "04"),
-        Err(RevsetResolutionError::AmbiguousCommitIdPrefix(s)) if s == "04"
+        Err(RevsetResolutionError::AmbiguousCommitIdPrefix { prefix, candidates })
+            if prefix == "04" && candidates == ["0454", "045f", "046"]
     );
     assert_matches!(
         resolve_symbol(repo.as_ref(), "040"),
         Err(RevsetResolutionError::NoSuchRevision{name, candidates}) if name == "040" && candidates.is_empty()
     );
 
+    // Test prefix globs selecting all matches
+    assert_eq!(
+        resolve_commit_ids(repo.as_ref(), "commit_id(glob:'04*')"),
+        vec![
+            commits[2].id().clone(),
+            commits[1].id().clone(),
+            commits[0].id().clone(),
+        ]
+    );
+    assert_eq!(
+        resolve_commit_ids(repo.as_ref(), "commit_id(glob:'045*')"),
+        vec![commits[1].id().clone(), commits[0].id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(repo.as_ref(), "commit_id(glob:'046*')"),
+        vec![commits[2].id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(repo.as_ref(), "commit_id(glob:'040*')"),
+        vec![]
+    );
+    assert_matches!(
+        try_resolve_commit_ids(repo.as_ref(), "commit_id(glob:'*')"),
+        Err(RevsetResolutionError::EmptyString)
+    );
+
     // Test non-hex string
     assert_matches!(
         resolve_symbol(repo.as_ref(), "foo"),
@@ -234,7 +296,7 @@ fn test_resolve_symbol_commit_id() {
     assert_matches!(
         parse(&mut RevsetDiagnostics::new(), "present(04)", &context).unwrap()
             .resolve_user_expression(repo.as_ref(), &symbol_resolver),
-        Err(RevsetResolutionError::AmbiguousCommitIdPrefix(s)) if s == "04"
+        Err(RevsetResolutionError::AmbiguousCommitIdPrefix { prefix, .. }) if prefix == "04"
     );
     assert_eq!(
         resolve_commit_ids(repo.as_ref(), "present(046)"),
@@ -242,6 +304,46 @@ fn test_resolve_symbol_commit_id() {
     );
 }
 
+#[test]
+fn test_resolve_commit_id_prefix_matches_hidden() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let commit1 = write_random_commit(tx.repo_mut());
+    let repo1 = tx.commit("test").unwrap();
+
+    let mut tx = repo1.start_transaction();
+    let commit2 = tx.repo_mut().rewrite_commit(&commit1).write().unwrap();
+    tx.repo_mut().rebase_descendants().unwrap();
+    let repo2 = tx.commit("test").unwrap();
+
+    // The hidden commit can still be looked up by exact prefix
+    let hidden_hex = commit1.id().hex();
+    assert_eq!(
+        resolve_commit_ids(repo2.as_ref(), &format!("commit_id({hidden_hex})")),
+        vec![commit1.id().clone()]
+    );
+    // but isn't matched by prefix globs
+    assert_eq!(
+        resolve_commit_ids(repo2.as_ref(), &format!("commit_id(glob:'{hidden_hex}*')")),
+        vec![]
+    );
+    let visible_hex = commit2.id().hex();
+    assert_eq!(
+        resolve_commit_ids(repo2.as_ref(), &format!("commit_id(glob:'{visible_hex}*')")),
+        vec![commit2.id().clone()]
+    );
+    // unless evaluated at the operation where the commit was visible
+    assert_eq!(
+        resolve_commit_ids(
+            repo2.as_ref(),
+            &format!("at_operation(@-, commit_id(glob:'{hidden_hex}*'))")
+        ),
+        vec![commit1.id().clone()]
+    );
+}
+
 #[test_case(false ; "mutable")]
 #[test_case(true ; "readonly")]
 fn test_resolve_symbol_change_id(readonly: bool) {
@@ -326,6 +428,37 @@ fn test_resolve_symbol_change_id(readonly: bool) {
         vec![commits[2].id().clone()]
     );
 
+    // Full change id can be looked up without loading the prefix index. The
+    // disambiguation revset fails to evaluate, so resolving a prefix fails.
+    let id_prefix_context = IdPrefixContext::default()
+        .disambiguate_within(RevsetExpression::symbol("nonexistent".to_owned()));
+    let symbol_resolver =
+        DefaultSymbolResolver::new(repo, &([] as [&Box<dyn SymbolResolverExtension>; 0]))
+            .with_id_prefix_context(&id_prefix_context);
+    assert_matches!(
+        symbol_resolver.resolve_symbol(repo, "zvlyx"),
+        Err(RevsetResolutionError::Other(_))
+    );
+    assert_eq!(
+        symbol_resolver
+            .resolve_change_id_exact(repo, "zvlyxpuvtsoopsqzlkorrpqrszrqvlnx")
+            .unwrap(),
+        vec![commits[0].id().clone()]
+    );
+    assert_eq!(
+        symbol_resolver
+            .resolve_change_id_exact(repo, "zvlynszrxlvlwvkwkwsymrpypvtsszor")
+            .unwrap(),
+        vec![commits[2].id().clone()]
+    );
+    // Prefixes and invalid ids don't match
+    for symbol in ["zvly", "zvlyx", "foo", "zvlyxpuvtsoopsqzlkorrpqrszrqvlnxz"] {
+        assert_matches!(
+            symbol_resolver.resolve_change_id_exact(repo, symbol),
+            Err(RevsetResolutionError::NoSuchRevision { name, .. }) if name == symbol
+        );
+    }
+
     // Test change id prefix
     assert_eq!(
         resolve_symbol(repo, "zvlyx").unwrap(),
@@ -337,7 +470,8 @@ fn test_resolve_symbol_change_id(readonly: bool) {
     );
     assert_matches!(
         resolve_symbol(repo, "zvly"),
-        Err(RevsetResolutionError::AmbiguousChangeIdPrefix(s)) if s == "zvly"
+        Err(RevsetResolutionError::AmbiguousChangeIdPrefix { prefix, candidates })
+            if prefix == "zvly" && candidates == ["zvlyx", "zvlyn"]
     );
     assert_matches!(
         resolve_symbol(repo, "zvlyw"),
@@ -363,6 +497,48 @@ fn test_resolve_symbol_change_id(readonly: bool) {
             candidates
         }) if name == "foo" && candidates.is_empty()
     );
+
+    // Explicit functions only look up the respective kind of ids
+    assert_eq!(
+        try_resolve_commit_ids(repo, "commit_id('040')").unwrap(),
+        vec![commits[3].id().clone()]
+    );
+    assert_matches!(
+        try_resolve_commit_ids(repo, "change_id('040')"),
+        Err(RevsetResolutionError::NoSuchRevision { name, .. }) if name == "040"
+    );
+    assert_eq!(
+        try_resolve_commit_ids(repo, "change_id(zvz)").unwrap(),
+        vec![commits[1].id().clone()]
+    );
+    assert_matches!(
+        try_resolve_commit_ids(repo, "commit_id(zvz)"),
+        Err(RevsetResolutionError::NoSuchRevision { name, .. }) if name == "zvz"
+    );
+    assert_matches!(
+        try_resolve_commit_ids(repo, "change_id(zvly)"),
+        Err(RevsetResolutionError::AmbiguousChangeIdPrefix { prefix, .. }) if prefix == "zvly"
+    );
+    assert_eq!(
+        try_resolve_commit_ids(repo, "change_id(glob:'zvly*')").unwrap(),
+        vec![commits[2].id().clone(), commits[0].id().clone()]
+    );
+    assert_eq!(
+        try_resolve_commit_ids(repo, "change_id(glob:'zv*')").unwrap(),
+        vec![
+            commits[2].id().clone(),
+            commits[1].id().clone(),
+            commits[0].id().clone(),
+        ]
+    );
+    assert_matches!(
+        try_resolve_commit_ids(repo, "commit_id(0)"),
+        Err(RevsetResolutionError::AmbiguousCommitIdPrefix { prefix, .. }) if prefix == "0"
+    );
+    assert_matches!(
+        try_resolve_commit_ids(repo, "commit_id('')"),
+        Err(RevsetResolutionError::EmptyString)
+    );
 }
 
 #[test]
@@ -373,9 +549,9 @@ fn test_resolve_symbol_in_different_disambiguation_context() {
     let mut tx = repo0.start_transaction();
     let commit1 = write_random_commit(tx.repo_mut());
     // Create more commits that are likely to conflict with 1-char hex prefix.
-    for _ in 0..50 {
-        write_random_commit(tx.repo_mut());
-    }
+    let other_commits = (0..50)
+        .map(|_| write_random_commit(tx.repo_mut()))
+        .collect_vec();
     let repo1 = tx.commit("test").unwrap();
 
     let mut tx = repo1.start_transaction();
@@ -419,6 +595,60 @@ fn test_resolve_symbol_in_different_disambiguation_context() {
         symbol_resolver.resolve_symbol(repo1.as_ref(), &commit2.id().hex()[0..1]),
         Err(RevsetResolutionError::NoSuchRevision { .. })
     );
+
+    // Explicit id lookups also respect the disambiguation index.
+    assert_eq!(
+        symbol_resolver
+            .resolve_change_id_prefix(repo2.as_ref(), &change_hex[0..1])
+            .unwrap(),
+        vec![commit2.id().clone()]
+    );
+    assert_eq!(
+        symbol_resolver
+            .resolve_commit_id_prefix(repo2.as_ref(), &commit2.id().hex()[0..1])
+            .unwrap(),
+        vec![commit2.id().clone()]
+    );
+
+    // Shortest prefix is computed within the disambiguation set, and falls back
+    // to the whole repo outside of the set.
+    assert_eq!(
+        id_prefix_context
+            .shortest_commit_prefix_len(repo2.as_ref(), commit2.id())
+            .unwrap(),
+        1
+    );
+    let other_commit = &other_commits[0];
+    let repo_prefix_len = repo2
+        .index()
+        .shortest_unique_commit_id_prefix_len(other_commit.id());
+    assert_eq!(
+        id_prefix_context
+            .shortest_commit_prefix_len(repo2.as_ref(), other_commit.id())
+            .unwrap(),
+        repo_prefix_len
+    );
+
+    // Union of disambiguation sets
+    let id_prefix_context = IdPrefixContext::new(Default::default()).disambiguate_within_any([
+        RevsetExpression::commit(commit2.id().clone()),
+        RevsetExpression::commit(other_commit.id().clone()),
+    ]);
+    let common_hex_len = commit2
+        .id()
+        .hex()
+        .chars()
+        .zip(other_commit.id().hex().chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    for commit in [&commit2, other_commit] {
+        assert_eq!(
+            id_prefix_context
+                .shortest_commit_prefix_len(repo2.as_ref(), commit.id())
+                .unwrap(),
+            common_hex_len + 1
+        );
+    }
 }
 
 #[test]
@@ -439,7 +669,7 @@ fn test_resolve_working_copy() {
     assert_matches!(
         RevsetExpression::working_copy(ws1.clone())
             .resolve_user_expression(mut_repo, &FailingSymbolResolver),
-        Err(RevsetResolutionError::WorkspaceMissingWorkingCopy { name }) if name == "ws1"
+        Err(RevsetResolutionError::WorkspaceMissingWorkingCopy { name, .. }) if name == "ws1"
     );
 
     // The error can be suppressed by present()
@@ -478,6 +708,24 @@ fn test_resolve_working_copy() {
     assert_eq!(resolve(ws1), vec![commit1.id().clone()]);
     // Can resolve an explicit checkout
     assert_eq!(resolve(ws2), vec![commit2.id().clone()]);
+
+    // Can resolve "@<workspace>" symbol
+    assert_eq!(
+        resolve_symbol(mut_repo, r#""@ws2""#).unwrap(),
+        vec![commit2.id().clone()]
+    );
+    assert_eq!(
+        resolve_symbol(mut_repo, r#""@ws1""#).unwrap(),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, r#""@ws2" | "@ws1""#),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    assert_matches!(
+        resolve_symbol(mut_repo, r#""@ws3""#),
+        Err(RevsetResolutionError::NoSuchRevision { name, .. }) if name == r#""@ws3""#
+    );
 }
 
 #[test]
@@ -503,7 +751,7 @@ fn test_resolve_working_copies() {
         .set_wc_commit(ws2.clone(), commit2.id().clone())
         .unwrap();
     let resolve = || -> Vec<CommitId> {
-        RevsetExpression::working_copies()
+        RevsetExpression::working_copies(StringPattern::everything())
             .resolve_user_expression(mut_repo, &FailingSymbolResolver)
             .unwrap()
             .evaluate(mut_repo)
@@ -515,6 +763,133 @@ fn test_resolve_working_copies() {
 
     // ensure our output has those two commits
     assert_eq!(resolve(), vec![commit2.id().clone(), commit1.id().clone()]);
+
+    // filter by workspace name
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "working_copies(exact:ws1)"),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "working_copies(glob:'ws*')"),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "working_copies(exact:ws)"),
+        vec![]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "working_copies(starts-with:ws2)"),
+        vec![commit2.id().clone()]
+    );
+    // Non-matching pattern isn't an error unlike the exact "<name>@" symbol
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "working_copies(glob:'ci-*')"),
+        vec![]
+    );
+    assert_matches!(
+        RevsetExpression::working_copy(WorkspaceNameBuf::from("ci-1"))
+            .resolve_user_expression(mut_repo, &FailingSymbolResolver),
+        Err(RevsetResolutionError::WorkspaceMissingWorkingCopy { .. })
+    );
+
+    // Move the working copies to children, and add one more workspace at
+    // commit1 so the working-copy commit is also a parent of another one.
+    let commit3 = create_random_commit(mut_repo)
+        .set_parents(vec![commit1.id().clone()])
+        .write()
+        .unwrap();
+    let commit4 = create_random_commit(mut_repo)
+        .set_parents(vec![commit2.id().clone()])
+        .write()
+        .unwrap();
+    let ws3 = WorkspaceNameBuf::from("ws3");
+    mut_repo.set_wc_commit(ws1, commit3.id().clone()).unwrap();
+    mut_repo.set_wc_commit(ws2, commit4.id().clone()).unwrap();
+    mut_repo.set_wc_commit(ws3, commit1.id().clone()).unwrap();
+
+    // parents of each workspace's working-copy commit
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "working_copies()-"),
+        vec![
+            commit2.id().clone(),
+            commit1.id().clone(),
+            repo.store().root_commit_id().clone(),
+        ]
+    );
+    // children of each workspace's working-copy commit
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "working_copies()+"),
+        vec![commit3.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "working_copies()- ~ working_copies()"),
+        vec![commit2.id().clone(), repo.store().root_commit_id().clone()]
+    );
+}
+
+#[test]
+fn test_resolve_stale_workspaces() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let commit1 = write_random_commit(mut_repo);
+    let commit2 = write_random_commit(mut_repo);
+    let ws1 = WorkspaceNameBuf::from("ws1");
+    let ws2 = WorkspaceNameBuf::from("ws2");
+    mut_repo
+        .set_wc_commit(ws1.clone(), commit1.id().clone())
+        .unwrap();
+    mut_repo
+        .set_wc_commit(ws2.clone(), commit2.id().clone())
+        .unwrap();
+
+    // All working-copy commits are visible
+    assert_eq!(resolve_commit_ids(mut_repo, "stale_workspaces()"), vec![]);
+    assert!(find_stale_workspaces(mut_repo).unwrap().is_empty());
+
+    // Rewrite the working-copy commit of ws1 without updating the workspace
+    let commit1b = mut_repo
+        .rewrite_commit(&commit1)
+        .set_description("rewritten")
+        .write()
+        .unwrap();
+    mut_repo.remove_head(commit1.id());
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "visible_heads()"),
+        vec![commit1b.id().clone(), commit2.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "stale_workspaces()"),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        find_stale_workspaces(mut_repo).unwrap(),
+        StaleWorkspaces {
+            hidden: vec![(ws1.clone(), commit1.id().clone())],
+            missing: vec![],
+        }
+    );
+
+    // Working-copy commit which doesn't exist is reported separately
+    let ws3 = WorkspaceNameBuf::from("ws3");
+    let missing_id = CommitId::from_hex("abcdef0123456789abcdef0123456789abcdef01");
+    mut_repo
+        .set_wc_commit(ws3.clone(), missing_id.clone())
+        .unwrap();
+    assert_eq!(
+        find_stale_workspaces(mut_repo).unwrap(),
+        StaleWorkspaces {
+            hidden: vec![(ws1, commit1.id().clone())],
+            missing: vec![(ws3, missing_id)],
+        }
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "stale_workspaces()"),
+        vec![commit1.id().clone()]
+    );
 }
 
 #[test]
@@ -596,12 +971,12 @@ fn test_resolve_symbol_bookmarks() {
     NoSuchRevision {
         name: "local@origin",
         candidates: [
-            "\"local-remote@origin\"",
+            "remote@origin",
             "local",
+            "local-remote@origin",
             "local-remote@git",
+            "\"local-remote@origin\"",
             "local-remote@mirror",
-            "local-remote@origin",
-            "remote@origin",
         ],
     }
     "#);
@@ -612,8 +987,8 @@ fn test_resolve_symbol_bookmarks() {
     NoSuchRevision {
         name: "remote",
         candidates: [
-            "remote-conflicted@origin",
             "remote@origin",
+            "remote-conflicted@origin",
         ],
     }
     "#);
@@ -664,11 +1039,11 @@ fn test_resolve_symbol_bookmarks() {
     NoSuchRevision {
         name: "local-emote",
         candidates: [
-            "\"local-remote@origin\"",
+            "local-remote",
             "local",
             "local-conflicted",
-            "local-remote",
             "local-remote@origin",
+            "\"local-remote@origin\"",
             "local-remote@untracked",
         ],
     }
@@ -678,15 +1053,15 @@ fn test_resolve_symbol_bookmarks() {
     NoSuchRevision {
         name: "local-emote@origin",
         candidates: [
+            "local-remote@origin",
             "\"local-remote@origin\"",
-            "local",
-            "local-remote",
             "local-remote@git",
+            "remote@origin",
             "local-remote@mirror",
-            "local-remote@origin",
+            "local-remote",
             "local-remote@untracked",
+            "local",
             "remote-conflicted@origin",
-            "remote@origin",
         ],
     }
     "#);
@@ -695,15 +1070,15 @@ fn test_resolve_symbol_bookmarks() {
     NoSuchRevision {
         name: "local-remote@origine",
         candidates: [
+            "local-remote@origin",
             "\"local-remote@origin\"",
-            "local",
-            "local-remote",
             "local-remote@git",
             "local-remote@mirror",
-            "local-remote@origin",
+            "remote@origin",
+            "local-remote",
             "local-remote@untracked",
+            "local",
             "remote-conflicted@origin",
-            "remote@origin",
         ],
     }
     "#);
@@ -726,8 +1101,8 @@ fn test_resolve_symbol_bookmarks() {
     NoSuchRevision {
         name: "emote",
         candidates: [
-            "remote-conflicted@origin",
             "remote@origin",
+            "remote-conflicted@origin",
         ],
     }
     "#);
@@ -736,9 +1111,9 @@ fn test_resolve_symbol_bookmarks() {
     NoSuchRevision {
         name: "emote@origin",
         candidates: [
-            "\"local-remote@origin\"",
-            "local-remote@origin",
             "remote@origin",
+            "local-remote@origin",
+            "\"local-remote@origin\"",
         ],
     }
     "#);
@@ -747,19 +1122,23 @@ fn test_resolve_symbol_bookmarks() {
     NoSuchRevision {
         name: "remote@origine",
         candidates: [
-            "\"local-remote@origin\"",
+            "remote@origin",
             "local-remote@origin",
+            "\"local-remote@origin\"",
             "remote-conflicted@origin",
-            "remote@origin",
         ],
     }
     "#);
 }
 
 #[test]
-fn test_resolve_symbol_tags() {
+fn test_resolve_symbol_remote_tags() {
     let test_repo = TestRepo::init();
     let repo = &test_repo.repo;
+    let tracked_remote_ref = |id: &CommitId| RemoteRef {
+        target: RefTarget::normal(id.clone()),
+        state: RemoteRefState::Tracked,
+    };
 
     let mut tx = repo.start_transaction();
     let mut_repo = tx.repo_mut();
@@ -767,23 +1146,223 @@ fn test_resolve_symbol_tags() {
     let commit1 = write_random_commit(mut_repo);
     let commit2 = write_random_commit(mut_repo);
     let commit3 = write_random_commit(mut_repo);
+    let commit4 = write_random_commit(mut_repo);
 
-    mut_repo.set_tag_target(
-        "tag-bookmark".as_ref(),
+    mut_repo.set_git_ref_target(
+        "refs/tags/v1.2.3".as_ref(),
         RefTarget::normal(commit1.id().clone()),
     );
     mut_repo.set_local_bookmark_target(
-        "tag-bookmark".as_ref(),
+        "v1.2.3@git".as_ref(), // not a remote tag
         RefTarget::normal(commit2.id().clone()),
     );
+    // Bookmark and tag of the same name pointing to the same commit
+    mut_repo.set_remote_bookmark(
+        remote_symbol("same", git::REMOTE_NAME_FOR_LOCAL_GIT_REPO),
+        tracked_remote_ref(commit3.id()),
+    );
     mut_repo.set_git_ref_target(
-        "refs/tags/unimported".as_ref(),
+        "refs/tags/same".as_ref(),
         RefTarget::normal(commit3.id().clone()),
     );
+    // Bookmark and tag of the same name pointing to different commits
+    mut_repo.set_remote_bookmark(
+        remote_symbol("different", git::REMOTE_NAME_FOR_LOCAL_GIT_REPO),
+        tracked_remote_ref(commit3.id()),
+    );
+    mut_repo.set_git_ref_target(
+        "refs/tags/different".as_ref(),
+        RefTarget::normal(commit4.id().clone()),
+    );
 
-    // Tag precedes bookmark
+    // Remote tag in the backing Git repo
     assert_eq!(
-        resolve_symbol(mut_repo, "tag-bookmark").unwrap(),
+        resolve_symbol(mut_repo, "v1.2.3@git").unwrap(),
+        vec![commit1.id().clone()],
+    );
+    // Quoted symbol is a local name
+    assert_eq!(
+        resolve_symbol(mut_repo, r#""v1.2.3@git""#).unwrap(),
+        vec![commit2.id().clone()],
+    );
+    // Tags aren't namespaced by the other remotes
+    assert_matches!(
+        resolve_symbol(mut_repo, "v1.2.3@origin"),
+        Err(RevsetResolutionError::NoSuchRevision { .. })
+    );
+
+    // Bookmark and tag agree
+    assert_eq!(
+        resolve_symbol(mut_repo, "same@git").unwrap(),
+        vec![commit3.id().clone()],
+    );
+    // Bookmark and tag conflict
+    insta::assert_debug_snapshot!(
+        resolve_symbol(mut_repo, "different@git").unwrap_err(), @r#"
+    AmbiguousRemoteRef {
+        name: "different@git",
+    }
+    "#);
+}
+
+#[test]
+fn test_resolve_symbol_suggestions() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let commit1 = write_random_commit(mut_repo);
+    let target = RefTarget::normal(commit1.id().clone());
+    mut_repo.set_local_bookmark_target("release".as_ref(), target.clone());
+    mut_repo.set_tag_target("v1.0.0".as_ref(), target.clone());
+    mut_repo.set_git_ref_target("refs/heads/release".as_ref(), target.clone());
+    mut_repo.set_git_ref_target("refs/pull/123/head".as_ref(), target.clone());
+    mut_repo
+        .set_wc_commit(WorkspaceNameBuf::from("feature-ws"), commit1.id().clone())
+        .unwrap();
+    let mut_repo = &*mut_repo;
+
+    let candidates = |symbol: &str| match resolve_symbol(mut_repo, symbol) {
+        Err(RevsetResolutionError::NoSuchRevision { candidates, .. }) => candidates,
+        result => panic!("unexpected result: {result:?}"),
+    };
+
+    // Tags
+    assert_eq!(candidates("v1.0.1"), ["v1.0.0"]);
+    // Working-copy symbol is suggested for workspace name
+    assert_eq!(candidates("feature-sw"), ["feature-ws@"]);
+    // Git refs are suggested only if the name looks like a ref path
+    assert_eq!(candidates("relaese"), ["release"]);
+    assert_eq!(candidates("heads/relaese"), ["heads/release"]);
+    assert_eq!(candidates("pull/132/head"), ["pull/123/head"]);
+
+    // Suggestions can be limited by edit distance
+    let symbol_resolver = |distance| {
+        DefaultSymbolResolver::new(mut_repo, &[] as &[Box<dyn SymbolResolverExtension>])
+            .with_suggestion_distance(distance)
+    };
+    assert_matches!(
+        symbol_resolver(Some(1)).resolve_symbol(mut_repo, "relaese"),
+        Err(RevsetResolutionError::NoSuchRevision { candidates, .. }) if candidates.is_empty()
+    );
+    assert_matches!(
+        symbol_resolver(Some(2)).resolve_symbol(mut_repo, "relaese"),
+        Err(RevsetResolutionError::NoSuchRevision { candidates, .. }) if candidates == ["release"]
+    );
+}
+
+#[test]
+fn test_resolve_symbol_case_insensitive_refs() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let commit1 = write_random_commit(mut_repo);
+    let commit2 = write_random_commit(mut_repo);
+    let commit3 = write_random_commit(mut_repo);
+    mut_repo.set_local_bookmark_target("main".as_ref(), RefTarget::normal(commit1.id().clone()));
+    mut_repo.set_tag_target("v1.0-RC1".as_ref(), RefTarget::normal(commit2.id().clone()));
+    mut_repo.set_local_bookmark_target("feature".as_ref(), RefTarget::normal(commit2.id().clone()));
+    mut_repo.set_local_bookmark_target("Feature".as_ref(), RefTarget::normal(commit3.id().clone()));
+    let mut_repo = &*mut_repo;
+
+    let symbol_resolver = || {
+        DefaultSymbolResolver::new(mut_repo, &[] as &[Box<dyn SymbolResolverExtension>])
+            .with_case_insensitive_refs(true)
+    };
+    let warnings = |symbol_resolver: &DefaultSymbolResolver<'_>| {
+        symbol_resolver
+            .take_symbol_warnings()
+            .into_iter()
+            .sorted()
+            .collect_vec()
+    };
+
+    // Disabled by default
+    assert_matches!(
+        resolve_symbol(mut_repo, "Main"),
+        Err(RevsetResolutionError::NoSuchRevision { .. })
+    );
+
+    // Exact match doesn't emit warning
+    let resolver = symbol_resolver();
+    assert_eq!(
+        resolver.resolve_symbol(mut_repo, "main").unwrap(),
+        vec![commit1.id().clone()]
+    );
+    assert!(warnings(&resolver).is_empty());
+
+    // Unique case-insensitive match of bookmark and tag
+    let resolver = symbol_resolver();
+    assert_eq!(
+        resolver.resolve_symbol(mut_repo, "Main").unwrap(),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolver.resolve_symbol(mut_repo, "v1.0-rc1").unwrap(),
+        vec![commit2.id().clone()]
+    );
+    assert_eq!(
+        warnings(&resolver),
+        [
+            (
+                "Main".to_owned(),
+                "Resolved `Main` to `main` by case-insensitive match".to_owned()
+            ),
+            (
+                "v1.0-rc1".to_owned(),
+                "Resolved `v1.0-rc1` to `v1.0-RC1` by case-insensitive match".to_owned()
+            ),
+        ]
+    );
+    assert!(warnings(&resolver).is_empty());
+
+    // Ambiguous case-insensitive match
+    let resolver = symbol_resolver();
+    assert_eq!(
+        resolver.resolve_symbol(mut_repo, "Feature").unwrap(),
+        vec![commit3.id().clone()]
+    );
+    assert_matches!(
+        resolver.resolve_symbol(mut_repo, "FEATURE"),
+        Err(RevsetResolutionError::NoSuchRevision { name, candidates })
+            if name == "FEATURE" && candidates == ["Feature", "feature"]
+    );
+    assert!(warnings(&resolver).is_empty());
+}
+
+#[test]
+fn test_resolve_symbol_tags() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let commit1 = write_random_commit(mut_repo);
+    let commit2 = write_random_commit(mut_repo);
+    let commit3 = write_random_commit(mut_repo);
+
+    mut_repo.set_tag_target(
+        "tag-bookmark".as_ref(),
+        RefTarget::normal(commit1.id().clone()),
+    );
+    mut_repo.set_local_bookmark_target(
+        "tag-bookmark".as_ref(),
+        RefTarget::normal(commit2.id().clone()),
+    );
+    mut_repo.set_git_ref_target(
+        "refs/tags/unimported".as_ref(),
+        RefTarget::normal(commit3.id().clone()),
+    );
+
+    // Tag precedes bookmark
+    assert_eq!(
+        resolve_symbol(mut_repo, "tag-bookmark").unwrap(),
         vec![commit1.id().clone()],
     );
 
@@ -809,6 +1388,186 @@ fn test_resolve_symbol_tags() {
     );
 }
 
+struct FixedSymbolResolver {
+    symbols: HashMap<String, CommitId>,
+}
+
+impl PartialSymbolResolver for FixedSymbolResolver {
+    fn resolve_symbol(
+        &self,
+        _repo: &dyn Repo,
+        symbol: &str,
+    ) -> Result<Option<Vec<CommitId>>, RevsetResolutionError> {
+        Ok(self.symbols.get(symbol).map(|id| vec![id.clone()]))
+    }
+}
+
+struct FakeExtension {
+    stage: ResolutionStage,
+    priority: i32,
+    prefixes: Vec<String>,
+    symbols: HashMap<String, CommitId>,
+}
+
+impl SymbolResolverExtension for FakeExtension {
+    fn new_resolvers<'a>(
+        &self,
+        _context_repo: &'a dyn Repo,
+    ) -> Vec<Box<dyn PartialSymbolResolver + 'a>> {
+        vec![Box::new(FixedSymbolResolver {
+            symbols: self.symbols.clone(),
+        })]
+    }
+
+    fn resolution_stage(&self) -> ResolutionStage {
+        self.stage
+    }
+
+    fn resolves_prefixes(&self) -> Vec<String> {
+        self.prefixes.clone()
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+}
+
+#[test]
+fn test_resolve_symbol_extension_stage() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit1 = write_random_commit(mut_repo);
+    let commit2 = write_random_commit(mut_repo);
+    let commit3 = write_random_commit(mut_repo);
+    mut_repo.set_local_bookmark_target("main".as_ref(), RefTarget::normal(commit1.id().clone()));
+
+    let fake_extension = |stage, prefixes: &[&str], symbols: &[(&str, &Commit)]| {
+        Box::new(FakeExtension {
+            stage,
+            priority: 0,
+            prefixes: prefixes.iter().map(|&prefix| prefix.to_owned()).collect(),
+            symbols: symbols
+                .iter()
+                .map(|&(symbol, commit)| (symbol.to_owned(), commit.id().clone()))
+                .collect(),
+        })
+    };
+
+    // Built-in resolvers win over fallback extensions
+    let mut extensions = RevsetExtensions::new();
+    extensions.add_symbol_resolver(fake_extension(
+        ResolutionStage::AfterBuiltin,
+        &[],
+        &[("main", &commit2), ("JIRA-123", &commit2)],
+    ));
+    extensions.add_symbol_resolver(fake_extension(
+        ResolutionStage::AfterBuiltin,
+        &[],
+        &[("JIRA-456", &commit3)],
+    ));
+    assert_eq!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "main").unwrap(),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "JIRA-123").unwrap(),
+        vec![commit2.id().clone()]
+    );
+    assert_eq!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "JIRA-456").unwrap(),
+        vec![commit3.id().clone()]
+    );
+
+    // Extension registered later can take precedence over the built-in
+    // resolvers and the other extensions.
+    extensions.add_symbol_resolver(fake_extension(
+        ResolutionStage::BeforeBuiltin,
+        &[],
+        &[("main", &commit3), ("JIRA-123", &commit3)],
+    ));
+    assert_eq!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "main").unwrap(),
+        vec![commit3.id().clone()]
+    );
+    assert_eq!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "JIRA-123").unwrap(),
+        vec![commit3.id().clone()]
+    );
+
+    // Extensions are invoked only for the declared prefixes
+    let mut extensions = RevsetExtensions::new();
+    extensions.add_symbol_resolver(fake_extension(
+        ResolutionStage::BeforeBuiltin,
+        &["JIRA-"],
+        &[("main", &commit2), ("JIRA-123", &commit2)],
+    ));
+    assert_eq!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "main").unwrap(),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "JIRA-123").unwrap(),
+        vec![commit2.id().clone()]
+    );
+}
+
+#[test]
+fn test_resolve_symbol_extension_priority() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit1 = write_random_commit(mut_repo);
+    let commit2 = write_random_commit(mut_repo);
+    let commit3 = write_random_commit(mut_repo);
+
+    let fake_extension = |priority, symbols: &[(&str, &Commit)]| {
+        Box::new(FakeExtension {
+            stage: ResolutionStage::AfterBuiltin,
+            priority,
+            prefixes: vec![],
+            symbols: symbols
+                .iter()
+                .map(|&(symbol, commit)| (symbol.to_owned(), commit.id().clone()))
+                .collect(),
+        })
+    };
+
+    // Higher priority wins regardless of the registration order
+    let mut extensions = RevsetExtensions::new();
+    extensions.add_symbol_resolver(fake_extension(0, &[("foo", &commit1), ("bar", &commit1)]));
+    extensions.add_symbol_resolver(fake_extension(10, &[("foo", &commit2)]));
+    assert_eq!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "foo").unwrap(),
+        vec![commit2.id().clone()]
+    );
+    // Lower priority resolves symbols unknown to the higher priority
+    assert_eq!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "bar").unwrap(),
+        vec![commit1.id().clone()]
+    );
+
+    // Conflict between extensions of the same priority
+    extensions.add_symbol_resolver(fake_extension(10, &[("foo", &commit3), ("bar", &commit3)]));
+    assert_matches!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "foo"),
+        Err(RevsetResolutionError::AmbiguousExtensionSymbol { symbol, priority: 10 })
+            if symbol == "foo"
+    );
+    // The same priority is fine if the symbol resolves to the same revision
+    let mut extensions = RevsetExtensions::new();
+    extensions.add_symbol_resolver(fake_extension(0, &[("foo", &commit1)]));
+    extensions.add_symbol_resolver(fake_extension(0, &[("foo", &commit1)]));
+    assert_eq!(
+        resolve_symbol_with_extensions(mut_repo, &extensions, "foo").unwrap(),
+        vec![commit1.id().clone()]
+    );
+}
+
 #[test]
 fn test_resolve_symbol_git_refs() {
     let test_repo = TestRepo::init();
@@ -1078,11 +1837,69 @@ fn test_evaluate_expression_heads() {
         vec![commit4.id().clone(), commit3.id().clone()]
     );
 
+    // Multiple arguments are unioned
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            &format!("heads({}, {})", commit3.id(), commit4.id())
+        ),
+        resolve_commit_ids(
+            mut_repo,
+            &format!("heads({} | {})", commit3.id(), commit4.id())
+        )
+    );
+
     // Heads of all commits is the set of visible heads in the repo
     assert_eq!(
         resolve_commit_ids(mut_repo, "heads(all())"),
         resolve_commit_ids(mut_repo, "visible_heads()")
     );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "heads(::visible_heads())"),
+        resolve_commit_ids(mut_repo, "visible_heads()")
+    );
+
+    // Heads of ancestors are the heads of the ancestor heads
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            &format!("heads(::{} | ::{})", commit1.id(), commit3.id())
+        ),
+        vec![commit3.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            &format!("heads(ancestors({} | {}, 2))", commit3.id(), commit4.id())
+        ),
+        vec![commit4.id().clone(), commit3.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, &format!("heads(::{}-)", commit3.id())),
+        vec![commit2.id().clone()]
+    );
+
+    // compute_heads() should be equivalent to heads(commits(...))
+    for commits in [
+        vec![],
+        vec![&root_commit],
+        vec![&commit2, &commit3],
+        vec![&commit1, &commit3],
+        vec![&commit3, &commit4],
+        vec![&root_commit, &commit1, &commit2, &commit3, &commit4],
+    ] {
+        let commit_ids = commits
+            .iter()
+            .map(|commit| commit.id().clone())
+            .collect_vec();
+        let expression = iter::once("none()".to_owned())
+            .chain(commit_ids.iter().map(|id| id.to_string()))
+            .join(" | ");
+        assert_eq!(
+            compute_heads(mut_repo, &commit_ids).unwrap(),
+            resolve_commit_ids(mut_repo, &format!("heads({expression})")),
+        );
+    }
 }
 
 #[test]
@@ -1132,11 +1949,41 @@ fn test_evaluate_expression_roots() {
         vec![commit1.id().clone()]
     );
 
+    // Multiple arguments are unioned
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            &format!("roots({}, {})", commit1.id(), commit3.id())
+        ),
+        vec![commit1.id().clone()]
+    );
+
     // Roots of all commits is the root commit
     assert_eq!(
         resolve_commit_ids(mut_repo, "roots(all())"),
         vec![root_commit.id().clone()]
     );
+
+    // compute_roots() should be equivalent to roots(commits(...))
+    for commits in [
+        vec![],
+        vec![&root_commit],
+        vec![&commit2, &commit3],
+        vec![&commit1, &commit3],
+        vec![&commit3, &commit1, &commit2],
+    ] {
+        let commit_ids = commits
+            .iter()
+            .map(|commit| commit.id().clone())
+            .collect_vec();
+        let expression = iter::once("none()".to_owned())
+            .chain(commit_ids.iter().map(|id| id.to_string()))
+            .join(" | ");
+        assert_eq!(
+            compute_roots(mut_repo, &commit_ids).unwrap(),
+            resolve_commit_ids(mut_repo, &format!("roots({expression})")),
+        );
+    }
 }
 
 #[test]
@@ -1291,26 +2138,97 @@ fn test_evaluate_expression_children() {
 }
 
 #[test]
-fn test_evaluate_expression_ancestors() {
+fn test_evaluate_expression_folded_generation_matches_unoptimized() {
     let test_repo = TestRepo::init();
     let repo = &test_repo.repo;
 
-    let root_commit = repo.store().root_commit();
     let mut tx = repo.start_transaction();
     let mut_repo = tx.repo_mut();
     let mut graph_builder = CommitGraphBuilder::new(mut_repo);
     let commit1 = graph_builder.initial_commit();
     let commit2 = graph_builder.commit_with_parents(&[&commit1]);
-    let commit3 = graph_builder.commit_with_parents(&[&commit2]);
-    let commit4 = graph_builder.commit_with_parents(&[&commit1, &commit3]);
+    let commit3 = graph_builder.commit_with_parents(&[&commit1]);
+    let commit4 = graph_builder.commit_with_parents(&[&commit2, &commit3]);
+    let commit5 = graph_builder.commit_with_parents(&[&commit4]);
+    let commit6 = graph_builder.initial_commit();
+    let commit7 = graph_builder.commit_with_parents(&[&commit5, &commit6]);
+
+    let resolve_unoptimized = |revset_str: &str| -> Vec<CommitId> {
+        let settings = testutils::user_settings();
+        let context = RevsetParseContext {
+            aliases_map: &RevsetAliasesMap::default(),
+            local_variables: HashMap::new(),
+            user_email: settings.user_email(),
+            date_pattern_context: chrono::Utc::now().fixed_offset().into(),
+            extensions: &RevsetExtensions::default(),
+            workspace: None,
+        };
+        let expression = parse(&mut RevsetDiagnostics::new(), revset_str, &context).unwrap();
+        let symbol_resolver =
+            DefaultSymbolResolver::new(mut_repo, context.extensions.symbol_resolvers());
+        let expression = expression
+            .resolve_user_expression(mut_repo, &symbol_resolver)
+            .unwrap();
+        expression
+            .evaluate_unoptimized(mut_repo)
+            .unwrap()
+            .iter()
+            .map(Result::unwrap)
+            .collect()
+    };
 
-    // The ancestors of the root commit is just the root commit itself
+    let (x, y) = (commit7.id(), commit3.id());
+    let (r, s) = (commit1.id(), commit6.id());
+    let revset_strs = [
+        format!("{x}----"),
+        format!("::({x}--)"),
+        format!("({x}--)--"),
+        format!("({x} | {y})--"),
+        format!("{x}-- | {y}--"),
+        format!("({x}- | {y}-)-"),
+        format!("{x}- | {y}--"),
+        format!("{r}++++"),
+        format!("({r}++)::"),
+        format!("({r} | {s})++"),
+        format!("{r}++ | {s}++"),
+        format!("({r}+ | {s}+)+"),
+    ];
+    for revset_str in &revset_strs {
+        assert_eq!(
+            resolve_commit_ids(mut_repo, revset_str),
+            resolve_unoptimized(revset_str),
+            "revset: {revset_str}"
+        );
+    }
+
+    // Both parents of the merge are walked through
     assert_eq!(
-        resolve_commit_ids(mut_repo, "::root()"),
-        vec![root_commit.id().clone()]
+        resolve_commit_ids(mut_repo, &format!("{x}-- | {y}--")),
+        vec![commit4.id().clone(), repo.store().root_commit_id().clone()]
     );
+}
 
-    // Can find ancestors of a specific commit. Commits reachable via multiple paths
+#[test]
+fn test_evaluate_expression_ancestors() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let root_commit = repo.store().root_commit();
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let mut graph_builder = CommitGraphBuilder::new(mut_repo);
+    let commit1 = graph_builder.initial_commit();
+    let commit2 = graph_builder.commit_with_parents(&[&commit1]);
+    let commit3 = graph_builder.commit_with_parents(&[&commit2]);
+    let commit4 = graph_builder.commit_with_parents(&[&commit1, &commit3]);
+
+    // The ancestors of the root commit is just the root commit itself
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "::root()"),
+        vec![root_commit.id().clone()]
+    );
+
+    // Can find ancestors of a specific commit. Commits reachable via multiple paths
     // are not repeated.
     assert_eq!(
         resolve_commit_ids(mut_repo, &format!("::{}", commit4.id())),
@@ -1450,6 +2368,70 @@ fn test_evaluate_expression_range() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_range_with_depth() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let root_commit_id = repo.store().root_commit_id();
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let mut graph_builder = CommitGraphBuilder::new(mut_repo);
+    let commit1 = graph_builder.initial_commit();
+    let commit2 = graph_builder.commit_with_parents(&[&commit1]);
+    let commit3 = graph_builder.commit_with_parents(&[&commit2]);
+    let commit4 = graph_builder.commit_with_parents(&[&commit3]);
+    let commit5 = graph_builder.commit_with_parents(&[&commit4]);
+
+    let evaluate = |roots: &CommitId, heads: &CommitId, depth: u64| -> Vec<CommitId> {
+        ResolvedRevsetExpression::commit(roots.clone())
+            .range_with_depth(&ResolvedRevsetExpression::commit(heads.clone()), depth)
+            .evaluate(mut_repo)
+            .unwrap()
+            .iter()
+            .map(Result::unwrap)
+            .collect()
+    };
+
+    // Capped to the commits closest to the heads
+    assert_eq!(
+        evaluate(root_commit_id, commit5.id(), 3),
+        vec![
+            commit5.id().clone(),
+            commit4.id().clone(),
+            commit3.id().clone(),
+        ]
+    );
+    assert_eq!(
+        evaluate(root_commit_id, commit5.id(), 1),
+        vec![commit5.id().clone()]
+    );
+    assert_eq!(evaluate(root_commit_id, commit5.id(), 0), vec![]);
+
+    // Depth larger than the range includes the whole range
+    assert_eq!(
+        evaluate(root_commit_id, commit5.id(), 100),
+        vec![
+            commit5.id().clone(),
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    assert_eq!(
+        evaluate(commit2.id(), commit5.id(), 5),
+        vec![
+            commit5.id().clone(),
+            commit4.id().clone(),
+            commit3.id().clone(),
+        ]
+    );
+
+    // Empty range (descendant first)
+    assert_eq!(evaluate(commit5.id(), commit2.id(), 3), vec![]);
+}
+
 #[test]
 fn test_evaluate_expression_dag_range() {
     let test_repo = TestRepo::init();
@@ -1918,7 +2900,96 @@ fn test_evaluate_expression_all() {
             commit3.id().clone(),
             commit2.id().clone(),
             commit1.id().clone(),
-            root_commit_id,
+            root_commit_id.clone(),
+        ]
+    );
+
+    // Reversed revset iterates parents first
+    let revset = ResolvedRevsetExpression::all().evaluate(mut_repo).unwrap();
+    let forward_ids: Vec<_> = revset.iter().try_collect().unwrap();
+    let reversed = revset.reversed();
+    let reversed_ids: Vec<_> = reversed.iter().try_collect().unwrap();
+    assert_eq!(
+        reversed_ids,
+        forward_ids.iter().rev().cloned().collect_vec()
+    );
+    assert_eq!(
+        reversed_ids,
+        vec![
+            root_commit_id.clone(),
+            commit1.id().clone(),
+            commit2.id().clone(),
+            commit3.id().clone(),
+            commit4.id().clone(),
+        ]
+    );
+    assert_eq!(reversed.last().unwrap(), Some(commit4.id().clone()));
+    assert!(reversed.containing_fn()(commit2.id()).unwrap());
+    let reversed_graph: Vec<_> = reversed.iter_graph().try_collect().unwrap();
+    assert_eq!(
+        reversed_graph
+            .iter()
+            .map(|(id, _)| id.clone())
+            .collect_vec(),
+        reversed_ids
+    );
+    assert_eq!(
+        reversed_graph[0].1,
+        vec![GraphEdge::direct(commit1.id().clone())]
+    );
+    // Reversing twice restores the original order
+    let restored_ids: Vec<_> = reversed.reversed().iter().try_collect().unwrap();
+    assert_eq!(restored_ids, forward_ids);
+}
+
+#[test]
+fn test_revset_iter_change_ids() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let root_commit = repo.store().root_commit();
+    let commit1 = write_random_commit(mut_repo);
+    // Divergent commit sharing the change id with commit1
+    let commit2 = create_random_commit(mut_repo)
+        .set_change_id(commit1.change_id().clone())
+        .write()
+        .unwrap();
+    let commit3 = create_random_commit(mut_repo)
+        .set_parents(vec![commit1.id().clone()])
+        .write()
+        .unwrap();
+
+    let revset = ResolvedRevsetExpression::all().evaluate(mut_repo).unwrap();
+    let commit_change_ids: Vec<_> = revset.commit_change_ids().try_collect().unwrap();
+    assert_eq!(
+        commit_change_ids,
+        vec![
+            (commit3.id().clone(), commit3.change_id().clone()),
+            (commit2.id().clone(), commit1.change_id().clone()),
+            (commit1.id().clone(), commit1.change_id().clone()),
+            (root_commit.id().clone(), root_commit.change_id().clone()),
+        ]
+    );
+    let change_ids: Vec<_> = revset.iter_change_ids().try_collect().unwrap();
+    assert_eq!(
+        change_ids,
+        vec![
+            commit3.change_id().clone(),
+            commit1.change_id().clone(),
+            root_commit.change_id().clone(),
+        ]
+    );
+
+    // Change ids are deduplicated in the order of the reversed revset
+    let change_ids: Vec<_> = revset.reversed().iter_change_ids().try_collect().unwrap();
+    assert_eq!(
+        change_ids,
+        vec![
+            root_commit.change_id().clone(),
+            commit1.change_id().clone(),
+            commit3.change_id().clone(),
         ]
     );
 }
@@ -2039,6 +3110,15 @@ fn test_evaluate_expression_bookmarks() {
 
     // Can get bookmarks when there are none
     assert_eq!(resolve_commit_ids(mut_repo, "bookmarks()"), vec![]);
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "visible_heads() & has_no_bookmark()"),
+        vec![
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone()
+        ]
+    );
     // Can get a few bookmarks
     mut_repo.set_local_bookmark_target(
         "bookmark1".as_ref(),
@@ -2052,6 +3132,11 @@ fn test_evaluate_expression_bookmarks() {
         resolve_commit_ids(mut_repo, "bookmarks()"),
         vec![commit2.id().clone(), commit1.id().clone()]
     );
+    // Bookmarked commits are excluded from has_no_bookmark()
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "visible_heads() & has_no_bookmark()"),
+        vec![commit4.id().clone(), commit3.id().clone()]
+    );
     // Can get bookmarks with matching names
     assert_eq!(
         resolve_commit_ids(mut_repo, "bookmarks(bookmark1)"),
@@ -2087,6 +3172,39 @@ fn test_evaluate_expression_bookmarks() {
         resolve_commit_ids(mut_repo, "bookmarks(exact:ookmark1)"),
         vec![]
     );
+    // Anchored patterns match glob meta characters literally
+    mut_repo.set_local_bookmark_target("fix*[1]".as_ref(), RefTarget::normal(commit3.id().clone()));
+    mut_repo.set_local_bookmark_target("fix?".as_ref(), RefTarget::normal(commit4.id().clone()));
+    assert_eq!(
+        resolve_commit_ids(mut_repo, r#"bookmarks(starts-with:"fix*")"#),
+        vec![commit3.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, r#"bookmarks(starts-with:"fix")"#),
+        vec![commit4.id().clone(), commit3.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, r#"bookmarks(starts-with-i:"FIX?")"#),
+        vec![commit4.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, r#"bookmarks(ends-with:"[1]")"#),
+        vec![commit3.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, r#"bookmarks(ends-with:"1")"#),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, r#"bookmarks(ends-with-i:"X?")"#),
+        vec![commit4.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, r#"bookmarks(ends-with:"ookmark")"#),
+        vec![]
+    );
+    mut_repo.set_local_bookmark_target("fix*[1]".as_ref(), RefTarget::absent());
+    mut_repo.set_local_bookmark_target("fix?".as_ref(), RefTarget::absent());
     // Two bookmarks pointing to the same commit does not result in a duplicate in
     // the revset
     mut_repo.set_local_bookmark_target(
@@ -2121,6 +3239,11 @@ fn test_evaluate_expression_bookmarks() {
             commit2.id().clone()
         ]
     );
+    // All possible targets of conflicted bookmarks are excluded
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "visible_heads() & has_no_bookmark()"),
+        vec![commit1.id().clone()]
+    );
 }
 
 #[test]
@@ -2134,6 +3257,7 @@ fn test_evaluate_expression_remote_bookmarks() {
     let normal_tracked_remote_ref =
         |id: &CommitId| tracked_remote_ref(RefTarget::normal(id.clone()));
 
+    let root_commit_id = repo.store().root_commit_id();
     let mut tx = repo.start_transaction();
     let mut_repo = tx.repo_mut();
 
@@ -2296,6 +3420,40 @@ fn test_evaluate_expression_remote_bookmarks() {
             commit2.id().clone()
         ]
     );
+
+    // pushed() matches visible ancestors of the remote bookmarks
+    let commit5 = create_random_commit(mut_repo)
+        .set_parents(vec![commit4.id().clone()])
+        .write()
+        .unwrap();
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "pushed()"),
+        vec![
+            commit4.id().clone(),
+            commit3.id().clone(),
+            root_commit_id.clone(),
+        ]
+    );
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            &format!(
+                "pushed() & ({} | {} | {})",
+                commit1.id(),
+                commit4.id(),
+                commit5.id()
+            )
+        ),
+        vec![commit4.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "~pushed()"),
+        vec![
+            commit5.id().clone(),
+            commit_git_remote.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
 }
 
 #[test]
@@ -2585,6 +3743,14 @@ fn test_evaluate_expression_fork_point() {
         ),
         vec![commit4.id().clone()]
     );
+    // Multiple arguments are unioned
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            &format!("fork_point({}, {})", commit5.id(), commit6.id())
+        ),
+        vec![commit4.id().clone()]
+    );
 }
 
 #[test]
@@ -2612,6 +3778,23 @@ fn test_evaluate_expression_fork_point_criss_cross() {
         ),
         vec![commit2.id().clone(), commit1.id().clone()]
     );
+    assert_eq!(
+        common_ancestor_count(mut_repo, &[commit3.id().clone()], &[commit4.id().clone()]).unwrap(),
+        2
+    );
+    assert_eq!(
+        common_ancestor_count(
+            mut_repo,
+            &[commit1.id().clone()],
+            &[commit3.id().clone(), commit4.id().clone()]
+        )
+        .unwrap(),
+        1
+    );
+    assert_eq!(
+        common_ancestor_count(mut_repo, &[commit1.id().clone()], &[commit2.id().clone()]).unwrap(),
+        1
+    );
 }
 
 #[test]
@@ -2642,6 +3825,100 @@ fn test_evaluate_expression_fork_point_merge_with_ancestor() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_duplicates_of() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let mut graph_builder = CommitGraphBuilder::new(mut_repo);
+    let commit1 = graph_builder.initial_commit();
+    let commit2 = graph_builder.commit_with_parents(&[&commit1]);
+    let commit3 = graph_builder.commit_with_parents(&[&commit1]);
+
+    // Duplicate commit2 twice
+    let duplicate = |mut_repo: &mut MutableRepo, commit: &Commit| {
+        let stats = duplicate_commits_onto_parents(mut_repo, &[commit.id().clone()]).unwrap();
+        stats.duplicated_commits[commit.id()].clone()
+    };
+    let commit4 = duplicate(mut_repo, &commit2);
+    let commit5 = duplicate(mut_repo, &commit2);
+    assert_eq!(commit4.duplicated_from_id(), Some(commit2.id()));
+    assert_eq!(commit5.duplicated_from_id(), Some(commit2.id()));
+    assert_eq!(
+        resolve_commit_ids(mut_repo, &format!("duplicates_of({})", commit2.id())),
+        vec![commit5.id().clone(), commit4.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, &format!("duplicates_of({})", commit3.id())),
+        vec![]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "duplicates_of(none())"),
+        vec![]
+    );
+
+    // Provenance is preserved when the duplicate is rewritten
+    let commit6 = mut_repo
+        .rewrite_commit(&commit5)
+        .set_description("rewritten")
+        .write()
+        .unwrap();
+    mut_repo.rebase_descendants().unwrap();
+    assert_eq!(commit6.duplicated_from_id(), Some(commit2.id()));
+    assert_eq!(
+        resolve_commit_ids(mut_repo, &format!("duplicates_of({})", commit2.id())),
+        vec![commit6.id().clone(), commit4.id().clone()]
+    );
+
+    // Duplicate of a duplicate points to the immediate source
+    let commit7 = duplicate(mut_repo, &commit4);
+    assert_eq!(commit7.duplicated_from_id(), Some(commit4.id()));
+    assert_eq!(
+        resolve_commit_ids(mut_repo, &format!("duplicates_of({})", commit4.id())),
+        vec![commit7.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            &format!("duplicates_of({} | {})", commit2.id(), commit4.id())
+        ),
+        vec![
+            commit7.id().clone(),
+            commit6.id().clone(),
+            commit4.id().clone()
+        ]
+    );
+
+    // Duplicates are still found after the source is rewritten
+    let commit8 = mut_repo
+        .rewrite_commit(&commit2)
+        .set_description("rewritten source")
+        .write()
+        .unwrap();
+    mut_repo.rebase_descendants().unwrap();
+    assert_eq!(
+        commit4.duplicated_from_change_id(),
+        Some(commit8.change_id())
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, &format!("duplicates_of({})", commit8.id())),
+        vec![commit6.id().clone(), commit4.id().clone()]
+    );
+
+    // A missing source commit isn't an error
+    let commit9 = mut_repo
+        .rewrite_commit(&commit3)
+        .set_duplicated_from(Some(DuplicateSource {
+            commit_id: CommitId::from_hex("abcdef"),
+            change_id: commit1.change_id().clone(),
+        }))
+        .write()
+        .unwrap();
+    assert!(commit9.duplicated_from().unwrap().is_none());
+}
+
 #[test]
 fn test_evaluate_expression_merges() {
     let test_repo = TestRepo::init();
@@ -2744,12 +4021,123 @@ fn test_evaluate_expression_description() {
         resolve_commit_ids(mut_repo, "subject(exact:'')"),
         vec![mut_repo.store().root_commit_id().clone()]
     );
-}
-
-#[test]
-fn test_evaluate_expression_author() {
-    let test_repo = TestRepo::init();
-    let repo = &test_repo.repo;
+
+    // Multiple patterns match if any of them matches
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(exact:'commit 1\n', blah)"),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "subject(regex:'3$', exact:'', regex:'^commit 1')"),
+        vec![
+            commit3.id().clone(),
+            commit1.id().clone(),
+            mut_repo.store().root_commit_id().clone(),
+        ]
+    );
+    for (multi, union) in [
+        (
+            "description(exact:'commit 1\n', blah)",
+            "description(exact:'commit 1\n') | description(blah)",
+        ),
+        (
+            "description(regex:'[13]', regex:'blah', nothing)",
+            "description(regex:'[13]') | description(regex:'blah') | description(nothing)",
+        ),
+        (
+            "subject(glob:'* 2', regex:'^commit [3-9]')",
+            "subject(glob:'* 2') | subject(regex:'^commit [3-9]')",
+        ),
+    ] {
+        assert_eq!(
+            resolve_commit_ids(mut_repo, multi),
+            resolve_commit_ids(mut_repo, union),
+            "{multi:?} should be equivalent to {union:?}"
+        );
+    }
+
+    // Trailing whitespace is ignored when matching subject
+    let commit4 = create_random_commit(mut_repo)
+        .set_parents(vec![commit3.id().clone()])
+        .set_description("commit 2  \t\n\nblah blah...\n")
+        .write()
+        .unwrap();
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "subject(exact:'commit 2')"),
+        vec![commit4.id().clone(), commit2.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "subject(regex:'2$')"),
+        vec![commit4.id().clone(), commit2.id().clone()]
+    );
+
+    // Line anchors match within the description only in multi-line mode
+    let commit5 = create_random_commit(mut_repo)
+        .set_parents(vec![commit4.id().clone()])
+        .set_description("commit 5\n\nFixes: #123\n")
+        .write()
+        .unwrap();
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(regex:'^Fixes:')"),
+        vec![]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(regex-m:'^Fixes:')"),
+        vec![commit5.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(regex-m:'^commit [15]$')"),
+        vec![commit5.id().clone(), commit1.id().clone()]
+    );
+}
+
+#[test]
+fn test_evaluate_expression_malformed_subject() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let commit1 = create_random_commit(mut_repo)
+        .set_description("subject\n\nbody\n")
+        .write()
+        .unwrap();
+    let commit2 = create_random_commit(mut_repo)
+        .set_parents(vec![commit1.id().clone()])
+        .set_description("subject\nbody\n")
+        .write()
+        .unwrap();
+    let commit3 = create_random_commit(mut_repo)
+        .set_parents(vec![commit2.id().clone()])
+        .set_description("subject only\n")
+        .write()
+        .unwrap();
+    let commit4 = create_random_commit(mut_repo)
+        .set_parents(vec![commit3.id().clone()])
+        .set_description("subject\n  \nbody after whitespace-only line\n")
+        .write()
+        .unwrap();
+    let commit5 = create_random_commit(mut_repo)
+        .set_parents(vec![commit4.id().clone()])
+        .set_description("subject\r\nbody\r\n")
+        .write()
+        .unwrap();
+
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "malformed_subject()"),
+        vec![commit5.id().clone(), commit2.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "~malformed_subject() & description(body)"),
+        vec![commit4.id().clone(), commit1.id().clone()]
+    );
+}
+
+#[test]
+fn test_evaluate_expression_author() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
 
     let mut tx = repo.start_transaction();
     let mut_repo = tx.repo_mut();
@@ -2831,6 +4219,20 @@ fn test_evaluate_expression_author() {
         vec![commit2.id().clone()]
     );
 
+    // Multiple patterns match if any of them matches
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author(exact:name1, regex:'3$')"),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author(exact:name1, regex:'3$')"),
+        resolve_commit_ids(mut_repo, "author(exact:name1) | author(regex:'3$')")
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author_email(email1, email2)"),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+
     // Searches only among candidates if specified
     assert_eq!(
         resolve_commit_ids(mut_repo, "visible_heads() & author(\"name2\")"),
@@ -2914,6 +4316,22 @@ fn test_evaluate_expression_author_date() {
         resolve_commit_ids(mut_repo, "author_date(before:'2023-03-25 12:00')"),
         vec![commit1.id().clone(), root_commit.id().clone()]
     );
+
+    // Repeated identical patterns evaluate consistently
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "author_date(after:'2023-03-25 12:00') & ~author_date(after:'2023-03-25 12:00')"
+        ),
+        vec![]
+    );
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "author_date(after:'2023-03-25 12:00') | author_date(after:'2023-03-25 12:00')"
+        ),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
 }
 
 #[test]
@@ -2982,6 +4400,175 @@ fn test_evaluate_expression_committer_date() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_committer_date_during() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let signature = |timestamp: &str| Signature {
+        name: "name1".to_string(),
+        email: "email1".to_string(),
+        timestamp: parse_timestamp(timestamp),
+    };
+    let commit1 = create_random_commit(mut_repo)
+        .set_committer(signature("2023-02-28T23:30:00Z"))
+        .write()
+        .unwrap();
+    let commit2 = create_random_commit(mut_repo)
+        .set_parents(vec![commit1.id().clone()])
+        .set_committer(signature("2023-03-01T00:00:00Z"))
+        .write()
+        .unwrap();
+    let commit3 = create_random_commit(mut_repo)
+        .set_parents(vec![commit2.id().clone()])
+        .set_committer(signature("2023-03-31T23:59:59Z"))
+        .write()
+        .unwrap();
+    let commit4 = create_random_commit(mut_repo)
+        .set_parents(vec![commit3.id().clone()])
+        .set_committer(signature("2023-04-01T00:00:00Z"))
+        .write()
+        .unwrap();
+
+    // Whole month
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "committer_date(during:'2023-03')"),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+    // Whole year
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "committer_date(during:'2023')"),
+        vec![
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    // Whole day
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "committer_date(during:'2023-02-28')"),
+        vec![commit1.id().clone()]
+    );
+    // Explicit range, which excludes the end
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "committer_date(during:'2023-02-28 23:30..2023-04-01')"
+        ),
+        vec![
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    // Same as the intersection of after: and before:
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "committer_date(during:'2023-03')"),
+        resolve_commit_ids(
+            mut_repo,
+            "committer_date(after:'2023-03-01') & committer_date(before:'2023-04-01')"
+        ),
+    );
+}
+
+#[test]
+fn test_evaluate_expression_tz_mismatch() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let signature = |timestamp: &str| Signature {
+        name: "name1".to_string(),
+        email: "email1".to_string(),
+        timestamp: parse_timestamp(timestamp),
+    };
+    // Same offset
+    let commit1 = create_random_commit(mut_repo)
+        .set_author(signature("2023-03-25T11:30:00+09:00"))
+        .set_committer(signature("2023-03-25T12:30:00+09:00"))
+        .write()
+        .unwrap();
+    // Rebased in another timezone
+    let commit2 = create_random_commit(mut_repo)
+        .set_parents(vec![commit1.id().clone()])
+        .set_author(signature("2023-03-25T11:30:00+09:00"))
+        .set_committer(signature("2023-03-25T04:30:00+01:00"))
+        .write()
+        .unwrap();
+    // Same instant, different offset
+    let commit3 = create_random_commit(mut_repo)
+        .set_parents(vec![commit2.id().clone()])
+        .set_author(signature("2023-03-25T11:30:00Z"))
+        .set_committer(signature("2023-03-25T06:30:00-05:00"))
+        .write()
+        .unwrap();
+
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "tz_mismatch()"),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "~tz_mismatch() & ~root()"),
+        vec![commit1.id().clone()]
+    );
+}
+
+#[test]
+fn test_revset_iter_bucketed_by_day() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let mut create_commit = |parents: Vec<CommitId>, timestamp: &str| {
+        create_random_commit(mut_repo)
+            .set_parents(parents)
+            .set_committer(Signature {
+                name: "name1".to_string(),
+                email: "email1".to_string(),
+                timestamp: parse_timestamp(timestamp),
+            })
+            .write()
+            .unwrap()
+    };
+    let root_commit_id = repo.store().root_commit_id().clone();
+    let commit1 = create_commit(vec![root_commit_id], "2024-01-01T10:00:00Z");
+    // Next day in the committer's time zone
+    let commit2 = create_commit(vec![commit1.id().clone()], "2024-01-02T00:30:00+01:00");
+    // Previous day in the committer's time zone
+    let commit3 = create_commit(vec![commit2.id().clone()], "2024-01-01T23:00:00-02:00");
+    let commit4 = create_commit(vec![commit3.id().clone()], "2024-01-05T12:00:00Z");
+
+    let revset = RevsetExpression::commits(vec![
+        commit1.id().clone(),
+        commit2.id().clone(),
+        commit3.id().clone(),
+        commit4.id().clone(),
+    ])
+    .evaluate(mut_repo)
+    .unwrap();
+    let date = |s: &str| s.parse::<chrono::NaiveDate>().unwrap();
+    let buckets: Vec<_> = revset.iter_bucketed_by_day(mut_repo).try_collect().unwrap();
+    assert_eq!(
+        buckets,
+        vec![
+            (date("2024-01-05"), vec![commit4.id().clone()]),
+            (date("2024-01-02"), vec![commit2.id().clone()]),
+            (
+                date("2024-01-01"),
+                vec![commit3.id().clone(), commit1.id().clone()]
+            ),
+        ]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_mine() {
     let settings = testutils::user_settings();
@@ -3033,11 +4620,38 @@ fn test_evaluate_expression_mine() {
         resolve_commit_ids(mut_repo, "mine()"),
         vec![commit3.id().clone(), commit2.id().clone()]
     );
+    // Explicit email address
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "mine('email1')"),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "mine('EMAIL1')"),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, &format!("mine('{}')", settings.user_email())),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+    assert_eq!(resolve_commit_ids(mut_repo, "mine('email')"), vec![]);
     // Searches only among candidates if specified
     assert_eq!(
         resolve_commit_ids(mut_repo, "visible_heads() & mine()"),
         vec![commit3.id().clone()],
     );
+    // Filter-first intersection should be equivalent to set-first one
+    assert_eq!(
+        resolve_commit_ids(mut_repo, &format!("mine() & {}", commit3.id())),
+        vec![commit3.id().clone()],
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, &format!("mine() & {}", commit3.id())),
+        resolve_commit_ids(mut_repo, &format!("{} & mine()", commit3.id())),
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, &format!("mine() & {}", commit1.id())),
+        vec![],
+    );
     // Filter by union of pure predicate and set
     assert_eq!(
         resolve_commit_ids(mut_repo, &format!("root().. & (mine() | {})", commit1.id())),
@@ -3049,6 +4663,65 @@ fn test_evaluate_expression_mine() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_signed() {
+    let settings = testutils::user_settings();
+    let signer = Signer::new(Some(Box::new(TestSigningBackend)), vec![]);
+    let test_workspace =
+        TestWorkspace::init_with_backend_and_signer(TestRepoBackend::Test, signer, &settings);
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let committer = |timestamp| Signature {
+        name: "name1".to_string(),
+        email: "email1".to_string(),
+        timestamp,
+    };
+    let timestamp1 = parse_timestamp("2023-03-25T11:30:00Z");
+    let timestamp2 = parse_timestamp("2023-03-25T12:30:00Z");
+    let commit1 = create_random_commit(mut_repo)
+        .set_committer(committer(timestamp1))
+        .set_sign_behavior(SignBehavior::Force)
+        .write()
+        .unwrap();
+    let commit2 = create_random_commit(mut_repo)
+        .set_parents(vec![commit1.id().clone()])
+        .set_committer(committer(timestamp2))
+        .set_sign_behavior(SignBehavior::Force)
+        .write()
+        .unwrap();
+    let commit3 = create_random_commit(mut_repo)
+        .set_parents(vec![commit2.id().clone()])
+        .set_committer(committer(timestamp2))
+        .set_sign_behavior(SignBehavior::Drop)
+        .write()
+        .unwrap();
+    assert!(commit1.is_signed());
+    assert!(commit2.is_signed());
+    assert!(!commit3.is_signed());
+
+    // Unsigned commits are excluded
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "signed()"),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    // The committer date is used as the signing time
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "signed(after:'2023-03-25 12:00')"),
+        vec![commit2.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "signed(before:'2023-03-25 12:00')"),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "signed(after:'2023-03-25 13:00')"),
+        vec![]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_committer() {
     let test_repo = TestRepo::init();
@@ -3354,11 +5027,192 @@ fn test_evaluate_expression_coalesce() {
 }
 
 #[test]
-fn test_evaluate_expression_union() {
-    let test_repo = TestRepo::init();
-    let repo = &test_repo.repo;
+fn test_evaluate_expression_custom_function() {
+    #[derive(Debug)]
+    struct LabelFilter {
+        label: String,
+    }
 
-    let root_commit = repo.store().root_commit();
+    impl RevsetFilterExtension for LabelFilter {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn matches_commit(&self, commit: &Commit) -> bool {
+            commit
+                .description()
+                .lines()
+                .filter_map(|line| line.strip_prefix("Label: "))
+                .any(|label| label == self.label)
+        }
+    }
+
+    fn has_label(
+        diagnostics: &mut RevsetDiagnostics,
+        function: &FunctionCallNode,
+        _context: &LoweringContext,
+    ) -> Result<Rc<UserRevsetExpression>, RevsetParseError> {
+        let [arg] = function.expect_exact_arguments()?;
+        let label = expect_literal(diagnostics, "string", arg)?;
+        Ok(RevsetExpression::filter(RevsetFilterPredicate::Extension(
+            Rc::new(LabelFilter { label }),
+        )))
+    }
+
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit1 = create_random_commit(mut_repo)
+        .set_description("commit 1\n\nLabel: bug\n")
+        .write()
+        .unwrap();
+    let commit2 = create_random_commit(mut_repo)
+        .set_parents(vec![commit1.id().clone()])
+        .set_description("commit 2\n\nLabel: bug\nLabel: wontfix\n")
+        .write()
+        .unwrap();
+    create_random_commit(mut_repo)
+        .set_parents(vec![commit2.id().clone()])
+        .set_description("commit 3\n")
+        .write()
+        .unwrap();
+
+    let mut extensions = RevsetExtensions::new();
+    extensions.add_custom_function("has_label", has_label);
+    let parse_with_extensions = |extensions: &RevsetExtensions, revset_str: &str| {
+        let context = RevsetParseContext {
+            aliases_map: &RevsetAliasesMap::default(),
+            local_variables: HashMap::new(),
+            user_email: "",
+            date_pattern_context: chrono::Utc::now().fixed_offset().into(),
+            extensions,
+            workspace: None,
+        };
+        parse(&mut RevsetDiagnostics::new(), revset_str, &context)
+    };
+    let evaluate = |revset_str: &str| -> Vec<CommitId> {
+        parse_with_extensions(&extensions, revset_str)
+            .unwrap()
+            .resolve_user_expression(mut_repo, &FailingSymbolResolver)
+            .unwrap()
+            .evaluate(mut_repo)
+            .unwrap()
+            .iter()
+            .map(Result::unwrap)
+            .collect()
+    };
+
+    assert_eq!(
+        evaluate("has_label(bug)"),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(evaluate("has_label('wontfix')"), vec![commit2.id().clone()]);
+    assert_eq!(
+        evaluate("has_label(bug) ~ has_label(wontfix)"),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(evaluate("has_label(unknown)"), vec![]);
+
+    // Arguments are checked by the function
+    assert_matches!(
+        parse_with_extensions(&extensions, "has_label()")
+            .unwrap_err()
+            .kind(),
+        RevsetParseErrorKind::InvalidFunctionArguments { name, .. } if name == "has_label"
+    );
+    // The function isn't available without the extension
+    assert_matches!(
+        parse_with_extensions(&RevsetExtensions::new(), "has_label(bug)")
+            .unwrap_err()
+            .kind(),
+        RevsetParseErrorKind::NoSuchFunction { name, .. } if name == "has_label"
+    );
+}
+
+#[test]
+fn test_evaluate_expression_coalesce_short_circuit() {
+    #[derive(Debug)]
+    struct CountingFilter {
+        count: Rc<Cell<usize>>,
+    }
+
+    impl RevsetFilterExtension for CountingFilter {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn matches_commit(&self, _commit: &Commit) -> bool {
+            self.count.set(self.count.get() + 1);
+            true
+        }
+    }
+
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let mut graph_builder = CommitGraphBuilder::new(mut_repo);
+    let commit1 = graph_builder.initial_commit();
+    let commit2 = graph_builder.commit_with_parents(&[&commit1]);
+
+    let count = Rc::new(Cell::new(0));
+    let counting_expression =
+        RevsetExpression::filter(RevsetFilterPredicate::Extension(Rc::new(CountingFilter {
+            count: count.clone(),
+        })));
+    let evaluate = |expressions: &[Rc<UserRevsetExpression>]| -> Vec<CommitId> {
+        let revset = RevsetExpression::coalesce(expressions)
+            .resolve_user_expression(mut_repo, &FailingSymbolResolver)
+            .unwrap()
+            .evaluate(mut_repo)
+            .unwrap();
+        // Only the evaluation itself should be counted, not the iteration.
+        let evaluated_count = count.replace(0);
+        let ids = revset.iter().map(Result::unwrap).collect_vec();
+        count.set(evaluated_count);
+        ids
+    };
+
+    // The later operand shouldn't be evaluated if the first one is non-empty.
+    assert_eq!(
+        evaluate(&[
+            RevsetExpression::commit(commit1.id().clone()),
+            counting_expression.clone(),
+        ]),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(count.replace(0), 0);
+    assert_eq!(
+        evaluate(&[
+            RevsetExpression::none(),
+            RevsetExpression::commit(commit2.id().clone()),
+            counting_expression.clone(),
+            counting_expression.clone(),
+        ]),
+        vec![commit2.id().clone()]
+    );
+    assert_eq!(count.replace(0), 0);
+
+    // The emptiness of each operand should be tested only once, regardless of
+    // how deeply the operand is nested.
+    evaluate(&[
+        RevsetExpression::none(),
+        counting_expression.clone(),
+        RevsetExpression::none(),
+        RevsetExpression::none(),
+    ]);
+    assert_eq!(count.replace(0), 1);
+}
+
+#[test]
+fn test_evaluate_expression_union() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let root_commit = repo.store().root_commit();
     let mut tx = repo.start_transaction();
     let mut_repo = tx.repo_mut();
     let mut graph_builder = CommitGraphBuilder::new(mut_repo);
@@ -3384,6 +5238,18 @@ fn test_evaluate_expression_union() {
         ]
     );
 
+    // Comments are ignored
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            &format!("::{} # first\n | ::{} # second", commit4.id(), commit5.id())
+        ),
+        resolve_commit_ids(
+            mut_repo,
+            &format!("::{} | ::{}", commit4.id(), commit5.id())
+        )
+    );
+
     // Unioning can add back commits removed by difference
     assert_eq!(
         resolve_commit_ids(
@@ -3445,6 +5311,289 @@ fn test_evaluate_expression_machine_generated_union() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_budget() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let mut graph_builder = CommitGraphBuilder::new(mut_repo);
+    let mut commit = graph_builder.initial_commit();
+    for _ in 0..9 {
+        commit = graph_builder.commit_with_parents(&[&commit]);
+    }
+
+    let evaluate_expression = |expression: Rc<UserRevsetExpression>,
+                               budget: &RevsetEvaluationBudget| {
+        expression
+            .resolve_user_expression(mut_repo, &FailingSymbolResolver)
+            .unwrap()
+            .evaluate_with_budget(mut_repo, budget)
+            .and_then(|revset| revset.iter().collect::<Result<Vec<_>, _>>())
+    };
+    let evaluate =
+        |budget: &RevsetEvaluationBudget| evaluate_expression(RevsetExpression::all(), budget);
+
+    // 10 commits plus the root commit
+    let budget = RevsetEvaluationBudget::default();
+    assert_eq!(evaluate(&budget).unwrap().len(), 11);
+    let budget = RevsetEvaluationBudget {
+        max_visited_entries: Some(11),
+        ..Default::default()
+    };
+    assert_eq!(evaluate(&budget).unwrap().len(), 11);
+
+    // The walk should be aborted in the middle
+    let budget = RevsetEvaluationBudget {
+        max_visited_entries: Some(5),
+        ..Default::default()
+    };
+    assert_matches!(
+        evaluate(&budget),
+        Err(RevsetEvaluationError::BudgetExceeded(_))
+    );
+    let budget = RevsetEvaluationBudget {
+        max_duration: Some(Duration::ZERO),
+        ..Default::default()
+    };
+    assert_matches!(
+        evaluate(&budget),
+        Err(RevsetEvaluationError::BudgetExceeded(_))
+    );
+
+    // Entries walked once are charged once even if they are filtered or
+    // tested against another walk
+    let budget = RevsetEvaluationBudget {
+        max_visited_entries: Some(11),
+        ..Default::default()
+    };
+    let filtered = RevsetExpression::all().intersection(&RevsetExpression::filter(
+        RevsetFilterPredicate::Description(StringPattern::everything().into()),
+    ));
+    assert_eq!(evaluate_expression(filtered, &budget).unwrap().len(), 11);
+    let filtered = RevsetExpression::all().intersection(
+        &RevsetExpression::filter(RevsetFilterPredicate::Description(
+            StringPattern::exact("no match").into(),
+        ))
+        .union(&RevsetExpression::commit(commit.id().clone()).ancestors()),
+    );
+    let budget = RevsetEvaluationBudget {
+        max_visited_entries: Some(22),
+        ..Default::default()
+    };
+    assert_eq!(evaluate_expression(filtered, &budget).unwrap().len(), 11);
+}
+
+#[test]
+fn test_evaluate_expression_heads_budget() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let mut graph_builder = CommitGraphBuilder::new(mut_repo);
+    let mut commit = graph_builder.initial_commit();
+    for _ in 0..9 {
+        commit = graph_builder.commit_with_parents(&[&commit]);
+    }
+
+    // The budget counts index entries visited by walks, so no ancestors should
+    // be enumerated to compute the heads.
+    let budget = RevsetEvaluationBudget {
+        max_visited_entries: Some(0),
+        ..Default::default()
+    };
+    let evaluate = |revset_str: &str| {
+        let context = RevsetParseContext {
+            aliases_map: &RevsetAliasesMap::default(),
+            local_variables: HashMap::new(),
+            user_email: "",
+            date_pattern_context: chrono::Utc::now().fixed_offset().into(),
+            extensions: &RevsetExtensions::default(),
+            workspace: None,
+        };
+        let mut diagnostics = RevsetDiagnostics::new();
+        parse(&mut diagnostics, revset_str, &context)
+            .unwrap()
+            .resolve_user_expression(mut_repo, &FailingSymbolResolver)
+            .unwrap()
+            .evaluate_with_budget(mut_repo, &budget)
+            .and_then(|revset| revset.iter().collect::<Result<Vec<_>, _>>())
+    };
+
+    let expected = vec![commit.id().clone()];
+    assert_eq!(evaluate("heads(all())").unwrap(), expected);
+    assert_eq!(evaluate("heads(::visible_heads())").unwrap(), expected);
+
+    // Heads of a set which isn't closed under ancestors still needs a walk
+    assert_matches!(
+        evaluate("heads(::visible_heads() | root())"),
+        Err(RevsetEvaluationError::BudgetExceeded(_))
+    );
+}
+
+#[test]
+fn test_evaluate_expression_progress() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let mut graph_builder = CommitGraphBuilder::new(mut_repo);
+    let mut commit = graph_builder.initial_commit();
+    for _ in 0..9 {
+        commit = graph_builder.commit_with_parents(&[&commit]);
+    }
+
+    let evaluate = |max_reports: usize| {
+        let reports = Rc::new(RefCell::new(vec![]));
+        let result = RevsetExpression::all()
+            .resolve_user_expression(mut_repo, &FailingSymbolResolver)
+            .unwrap()
+            .evaluate_with_progress(mut_repo, 3, {
+                let reports = reports.clone();
+                move |visited, matched| {
+                    reports.borrow_mut().push((visited, matched));
+                    if reports.borrow().len() < max_reports {
+                        ControlFlow::Continue(())
+                    } else {
+                        ControlFlow::Break(())
+                    }
+                }
+            })
+            .and_then(|revset| revset.iter().collect::<Result<Vec<_>, _>>());
+        (result, reports.take())
+    };
+
+    // 10 commits plus the root commit, reported every 3 entries
+    let (result, reports) = evaluate(usize::MAX);
+    assert_eq!(result.unwrap().len(), 11);
+    assert_eq!(reports, [(3, 3), (6, 6), (9, 9)]);
+
+    // Cancellation should stop the iteration early
+    let (result, reports) = evaluate(2);
+    assert_matches!(result, Err(RevsetEvaluationError::Cancelled));
+    assert_eq!(reports, [(3, 3), (6, 6)]);
+}
+
+#[test]
+fn test_evaluate_expression_count_estimate() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_ids = (0..12)
+        .map(|_| write_random_commit(mut_repo).id().clone())
+        .collect_vec();
+
+    let count_estimate = |expression: Rc<UserRevsetExpression>| {
+        expression
+            .resolve_user_expression(mut_repo, &FailingSymbolResolver)
+            .unwrap()
+            .evaluate(mut_repo)
+            .unwrap()
+            .count_estimate()
+            .unwrap()
+    };
+
+    // Literal commit sets are counted exactly
+    let commits = RevsetExpression::commits(commit_ids.clone());
+    assert_eq!(count_estimate(commits.clone()), (12, Some(12)));
+    assert_eq!(
+        count_estimate(RevsetExpression::commits(commit_ids[..3].to_vec())),
+        (3, Some(3))
+    );
+    assert_eq!(count_estimate(RevsetExpression::none()), (0, Some(0)));
+
+    // Filtered set is bounded by the candidate set
+    let filtered = commits.intersection(&RevsetExpression::filter(
+        RevsetFilterPredicate::Description(StringPattern::everything().into()),
+    ));
+    let (lower, upper) = count_estimate(filtered);
+    assert!((1..=12).contains(&lower));
+    assert_eq!(upper, Some(12));
+
+    // Small filtered set may be counted exactly
+    let filtered = commits.intersection(&RevsetExpression::filter(
+        RevsetFilterPredicate::Description(StringPattern::exact("no match").into()),
+    ));
+    assert_eq!(count_estimate(filtered), (0, Some(0)));
+}
+
+#[test]
+fn test_evaluate_expression_shared_subexpression() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let root_commit = repo.store().root_commit();
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let mut graph_builder = CommitGraphBuilder::new(mut_repo);
+    let commit1 = graph_builder.initial_commit();
+    let commit2 = graph_builder.commit_with_parents(&[&commit1]);
+    let commit3 = graph_builder.commit_with_parents(&[&commit2]);
+
+    // The budget counts index entries visited by ancestor walks, which tells
+    // how many times the walks were evaluated.
+    let budget = RevsetEvaluationBudget {
+        max_visited_entries: Some(4),
+        ..Default::default()
+    };
+    let evaluate = |expression: Rc<UserRevsetExpression>| {
+        expression
+            .resolve_user_expression(mut_repo, &FailingSymbolResolver)
+            .unwrap()
+            .evaluate_with_budget(mut_repo, &budget)
+            .and_then(|revset| revset.iter().collect::<Result<Vec<_>, _>>())
+    };
+
+    // '::commit3' is walked once, and shared by latest() and roots()
+    let ancestors = RevsetExpression::commit(commit3.id().clone()).ancestors();
+    assert_eq!(
+        evaluate(ancestors.latest(1).union(&ancestors.roots())).unwrap(),
+        vec![commit3.id().clone(), root_commit.id().clone()]
+    );
+
+    // Structurally identical expressions are shared as well
+    let ancestors1 = RevsetExpression::commit(commit3.id().clone()).ancestors();
+    let ancestors2 = RevsetExpression::commit(commit3.id().clone()).ancestors();
+    assert_eq!(
+        evaluate(ancestors1.latest(1).union(&ancestors2.roots())).unwrap(),
+        vec![commit3.id().clone(), root_commit.id().clone()]
+    );
+
+    // Different walks are evaluated separately
+    let ancestors1 = RevsetExpression::commit(commit3.id().clone()).ancestors();
+    let ancestors2 = RevsetExpression::commit(commit2.id().clone()).ancestors();
+    assert_matches!(
+        evaluate(ancestors1.latest(1).union(&ancestors2.roots())),
+        Err(RevsetEvaluationError::BudgetExceeded(_))
+    );
+
+    // The shared walk is advanced only as far as the consumers need
+    let budget = RevsetEvaluationBudget {
+        max_visited_entries: Some(2),
+        ..Default::default()
+    };
+    let ancestors = RevsetExpression::commit(commit3.id().clone()).ancestors();
+    let revset = ancestors
+        .union(&ancestors.minus(&RevsetExpression::commit(commit1.id().clone())))
+        .resolve_user_expression(mut_repo, &FailingSymbolResolver)
+        .unwrap()
+        .evaluate_with_budget(mut_repo, &budget)
+        .unwrap();
+    assert_eq!(
+        revset
+            .iter()
+            .take(1)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        vec![commit3.id().clone()]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_intersection() {
     let test_repo = TestRepo::init();
@@ -3723,6 +5872,36 @@ fn test_evaluate_expression_file() {
         vec![commit2.id().clone()]
     );
 
+    // root: paths are relative to the workspace root regardless of cwd
+    for cwd in [
+        test_workspace.workspace.workspace_root().parent().unwrap(),
+        test_workspace.workspace.workspace_root(),
+        &test_workspace.workspace.workspace_root().join("dir"),
+    ] {
+        assert_eq!(
+            resolve_commit_ids_in_workspace(
+                mut_repo,
+                r#"files(root:"added_clean_clean")"#,
+                &test_workspace.workspace,
+                Some(cwd),
+            ),
+            vec![commit1.id().clone()]
+        );
+        assert_eq!(
+            resolve_commit_ids_in_workspace(
+                mut_repo,
+                r#"files(root:"added_modified_clean" | root-file:"added_modified_removed")"#,
+                &test_workspace.workspace,
+                Some(cwd),
+            ),
+            vec![
+                commit3.id().clone(),
+                commit2.id().clone(),
+                commit1.id().clone()
+            ]
+        );
+    }
+
     // empty() revset, which is identical to ~file(".")
     assert_eq!(
         resolve_commit_ids(mut_repo, &format!("{}:: & empty()", commit1.id())),
@@ -3730,6 +5909,369 @@ fn test_evaluate_expression_file() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_file_follow_renames() {
+    // Rename detection is implemented by the Git backend
+    let test_workspace = TestWorkspace::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let added_clean_clean = RepoPath::from_internal_string("added_clean_clean");
+    let renamed = RepoPath::from_internal_string("renamed");
+    let other = RepoPath::from_internal_string("other");
+    let tree1 = create_tree(repo, &[(added_clean_clean, "a\nb\nc\n"), (other, "1")]);
+    let tree2 = create_tree(repo, &[(added_clean_clean, "a\nb\nc\nd\n"), (other, "1")]);
+    let tree3 = create_tree(repo, &[(added_clean_clean, "a\nb\nc\nd\n"), (other, "2")]);
+    let tree4 = create_tree(repo, &[(renamed, "a\nb\nc\nd\n"), (other, "2")]);
+    let tree5 = create_tree(repo, &[(renamed, "a\nb\nc\nd\ne\n"), (other, "2")]);
+    let mut parent_id = repo.store().root_commit_id().clone();
+    let [commit1, commit2, _commit3, commit4, commit5] =
+        [tree1, tree2, tree3, tree4, tree5].map(|tree| {
+            let commit = mut_repo
+                .new_commit(vec![parent_id.clone()], tree.id())
+                .write()
+                .unwrap();
+            parent_id = commit.id().clone();
+            commit
+        });
+
+    let mut_repo = &*mut_repo;
+    let resolve = |revset_str: &str| -> Vec<CommitId> {
+        resolve_commit_ids_in_workspace(
+            mut_repo,
+            revset_str,
+            &test_workspace.workspace,
+            Some(test_workspace.workspace.workspace_root()),
+        )
+    };
+
+    // files() stops at the rename
+    assert_eq!(
+        resolve(r#"files("renamed")"#),
+        vec![commit5.id().clone(), commit4.id().clone()]
+    );
+    // follow() continues matching under the previous name
+    assert_eq!(
+        resolve(r#"follow("renamed")"#),
+        vec![
+            commit5.id().clone(),
+            commit4.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    // Renames are detected only in the commits included in the set
+    assert_eq!(
+        resolve(&format!(r#"::{} & follow("renamed")"#, commit2.id())),
+        vec![]
+    );
+    assert_eq!(
+        resolve(&format!(r#"{}.. & follow("renamed")"#, commit2.id())),
+        vec![commit5.id().clone(), commit4.id().clone()]
+    );
+    // The old name doesn't follow the rename forward
+    assert_eq!(
+        resolve(r#"follow("added_clean_clean")"#),
+        vec![
+            commit4.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+}
+
+#[test]
+fn test_evaluate_expression_file_follow_renames_branches() {
+    // Rename detection is implemented by the Git backend
+    let test_workspace = TestWorkspace::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let old = RepoPath::from_internal_string("old");
+    let new = RepoPath::from_internal_string("new");
+    let tree1 = create_tree(repo, &[(old, "a\nb\nc\n")]);
+    let tree2 = create_tree(repo, &[(old, "a\nb\nc\nd\n")]);
+    let tree3 = create_tree(repo, &[(new, "a\nb\nc\n")]);
+    let tree4 = create_tree(repo, &[(new, "a\nb\nc\ne\n")]);
+    // commit1 - commit3 (rename) - commit4
+    //        \ commit2 (sibling modifying the old path)
+    let commit1 = mut_repo
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree1.id())
+        .write()
+        .unwrap();
+    let commit2 = mut_repo
+        .new_commit(vec![commit1.id().clone()], tree2.id())
+        .write()
+        .unwrap();
+    let commit3 = mut_repo
+        .new_commit(vec![commit1.id().clone()], tree3.id())
+        .write()
+        .unwrap();
+    let commit4 = mut_repo
+        .new_commit(vec![commit3.id().clone()], tree4.id())
+        .write()
+        .unwrap();
+
+    let mut_repo = &*mut_repo;
+    let resolve = |revset_str: &str| -> Vec<CommitId> {
+        resolve_commit_ids_in_workspace(
+            mut_repo,
+            revset_str,
+            &test_workspace.workspace,
+            Some(test_workspace.workspace.workspace_root()),
+        )
+    };
+
+    // The rename is followed only in the ancestors of the renaming commit
+    assert_eq!(
+        resolve(r#"follow("new")"#),
+        vec![
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    assert_eq!(
+        resolve(r#"follow("new") | follow("old")"#),
+        vec![
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+}
+
+#[test]
+fn test_evaluate_expression_file_metadata() {
+    let test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let small = RepoPath::from_internal_string("small");
+    let big = RepoPath::from_internal_string("big");
+    let script = RepoPath::from_internal_string("script");
+    let build_tree = |files: &[(&RepoPath, &str, bool)]| -> MergedTreeId {
+        let store = repo.store();
+        let mut tree_builder = store.tree_builder(store.empty_tree_id().clone());
+        for &(path, contents, executable) in files {
+            if executable {
+                write_executable_file(&mut tree_builder, path, contents);
+            } else {
+                write_normal_file(&mut tree_builder, path, contents);
+            }
+        }
+        MergedTreeId::resolved(tree_builder.write_tree().unwrap())
+    };
+    let big_contents = "x".repeat(100);
+    let big_contents = big_contents.as_str();
+    let tree1 = build_tree(&[(small, "a", false), (big, big_contents, false)]);
+    let tree2 = build_tree(&[
+        (small, "a", false),
+        (big, big_contents, false),
+        (script, "#!/bin/sh\n", true),
+    ]);
+    let tree3 = build_tree(&[
+        (small, "a", false),
+        (big, "b", false),
+        (script, "#!/bin/sh\n", true),
+    ]);
+    let tree4 = build_tree(&[
+        (small, "a", false),
+        (big, "b", false),
+        (script, "#!/bin/sh\n", false),
+    ]);
+    let tree_side = build_tree(&[
+        (small, "abc", false),
+        (big, big_contents, false),
+        (script, "#!/bin/sh\n", true),
+    ]);
+    let tree5 = build_tree(&[
+        (small, "abc", false),
+        (big, "b", false),
+        (script, "#!/bin/sh\n", false),
+    ]);
+    let commit1 = mut_repo
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree1)
+        .write()
+        .unwrap();
+    let commit2 = mut_repo
+        .new_commit(vec![commit1.id().clone()], tree2)
+        .write()
+        .unwrap();
+    let commit3 = mut_repo
+        .new_commit(vec![commit2.id().clone()], tree3)
+        .write()
+        .unwrap();
+    let commit4 = mut_repo
+        .new_commit(vec![commit3.id().clone()], tree4)
+        .write()
+        .unwrap();
+    let commit_side = mut_repo
+        .new_commit(vec![commit2.id().clone()], tree_side)
+        .write()
+        .unwrap();
+    // Clean merge, which has no diff from the merged parents
+    let _commit5 = mut_repo
+        .new_commit(vec![commit4.id().clone(), commit_side.id().clone()], tree5)
+        .write()
+        .unwrap();
+
+    let resolve = |revset_str: &str| -> Vec<CommitId> {
+        let mut_repo = &*mut_repo;
+        resolve_commit_ids_in_workspace(
+            mut_repo,
+            &format!("files({revset_str})"),
+            &test_workspace.workspace,
+            None,
+        )
+    };
+
+    assert_eq!(resolve("size(100)"), vec![commit1.id().clone()]);
+    assert_eq!(
+        resolve("size(..2)"),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve("size(10)"),
+        vec![commit4.id().clone(), commit2.id().clone()]
+    );
+    assert_eq!(resolve("executable()"), vec![commit2.id().clone()]);
+    assert_eq!(
+        resolve("executable(false)"),
+        vec![
+            commit_side.id().clone(),
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit1.id().clone()
+        ]
+    );
+    // Combined with path patterns
+    assert_eq!(resolve("small & size(3)"), vec![commit_side.id().clone()]);
+    assert_eq!(resolve("script ~ executable()"), vec![commit4.id().clone()]);
+}
+
+#[test]
+fn test_evaluate_expression_file_type() {
+    let test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+    let store = repo.store();
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let file_path = RepoPath::from_internal_string("file");
+    let link_path = RepoPath::from_internal_string("link");
+    let build_tree = |contents: &str, with_link: bool| {
+        let mut tree_builder = store.tree_builder(store.empty_tree_id().clone());
+        write_normal_file(&mut tree_builder, file_path, contents);
+        if with_link {
+            write_symlink(&mut tree_builder, link_path, "file");
+        }
+        let id = MergedTreeId::resolved(tree_builder.write_tree().unwrap());
+        store.get_root_tree(&id).unwrap()
+    };
+    let tree1 = build_tree("1", false);
+    let tree2 = build_tree("1", true);
+    let tree3 = build_tree("2", true);
+    let tree_side = build_tree("3", true);
+    let tree4 = tree3.merge(&tree2, &tree_side).unwrap();
+    assert!(tree4.has_conflict());
+
+    let commit1 = mut_repo
+        .new_commit(vec![store.root_commit_id().clone()], tree1.id())
+        .write()
+        .unwrap();
+    let commit2 = mut_repo
+        .new_commit(vec![commit1.id().clone()], tree2.id())
+        .write()
+        .unwrap();
+    let commit3 = mut_repo
+        .new_commit(vec![commit2.id().clone()], tree3.id())
+        .write()
+        .unwrap();
+    let commit4 = mut_repo
+        .new_commit(vec![commit3.id().clone()], tree4.id())
+        .write()
+        .unwrap();
+
+    let resolve = |fileset_str: &str| -> Vec<CommitId> {
+        let mut_repo = &*mut_repo;
+        resolve_commit_ids_in_workspace(
+            mut_repo,
+            &format!("files({fileset_str})"),
+            &test_workspace.workspace,
+            None,
+        )
+    };
+
+    assert_eq!(resolve("type:symlink"), vec![commit2.id().clone()]);
+    assert_eq!(
+        resolve("type:file"),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(resolve("type:conflict"), vec![commit4.id().clone()]);
+    assert_eq!(resolve("type:gitmodule"), vec![]);
+    assert_eq!(
+        resolve("type:file | type:symlink"),
+        vec![
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone()
+        ]
+    );
+    assert_eq!(resolve("link & type:file"), vec![]);
+    assert_eq!(resolve("conflicts()"), vec![commit4.id().clone()]);
+    assert_eq!(resolve("conflicts() & link"), vec![]);
+}
+
+#[test]
+fn test_evaluate_expression_empty_with_files() {
+    let test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let a_file = RepoPath::from_internal_string("a/file");
+    let b_file = RepoPath::from_internal_string("b/file");
+    let tree1 = create_tree(repo, &[(a_file, "1"), (b_file, "1")]);
+    let tree2 = create_tree(repo, &[(a_file, "2"), (b_file, "1")]);
+    let commit1 = mut_repo
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree1.id())
+        .write()
+        .unwrap();
+    let commit2 = mut_repo
+        .new_commit(vec![commit1.id().clone()], tree2.id())
+        .write()
+        .unwrap();
+
+    let resolve = |revset_str: &str| -> Vec<CommitId> {
+        resolve_commit_ids_in_workspace(
+            mut_repo,
+            &format!("{}:: & {revset_str}", commit1.id()),
+            &test_workspace.workspace,
+            Some(test_workspace.workspace.workspace_root()),
+        )
+    };
+
+    // commit2 changes only a/
+    assert_eq!(resolve(r#"empty("b")"#), vec![commit2.id().clone()]);
+    assert_eq!(resolve(r#"empty("a")"#), vec![]);
+    assert_eq!(resolve(r#"empty("a/file" | "b")"#), vec![]);
+    assert_eq!(resolve("empty()"), vec![]);
+    assert_eq!(
+        resolve(r#"empty("c")"#),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_diff_contains() {
     let test_workspace = TestWorkspace::init();
@@ -3869,6 +6411,25 @@ fn test_evaluate_expression_diff_contains() {
         )),
         vec![commit3.id().clone(), commit1.id().clone()]
     );
+
+    // lines_changed() counts both inserted and deleted lines
+    assert_eq!(query("lines_changed(4..)"), vec![commit3.id().clone()]);
+    assert_eq!(query("lines_changed(6)"), vec![commit3.id().clone()]);
+    assert_eq!(
+        query("lines_changed(1..4)"),
+        vec![
+            commit4.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    assert_eq!(query("lines_changed(7..)"), vec![]);
+    assert_eq!(
+        query(&format!(
+            "lines_changed(2.., {normal_inserted_modified_removed:?})",
+        )),
+        vec![commit4.id().clone(), commit3.id().clone()]
+    );
 }
 
 #[test]
@@ -3921,6 +6482,40 @@ fn test_evaluate_expression_file_merged_parents() {
         ]
     );
 
+    // parents:all is the default
+    assert_eq!(
+        query("files('file1', parents:all)"),
+        query("files('file1')")
+    );
+    assert_eq!(
+        query("files('file2', parents:all)"),
+        query("files('file2')")
+    );
+
+    // Compared to the first parent, the merge commit modifies both files.
+    assert_eq!(
+        query("files('file1', parents:first)"),
+        vec![
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    assert_eq!(
+        query("files('file2', parents:first)"),
+        vec![
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    assert_eq!(
+        query("files('file2', parents:first) ~ files('file2', parents:all)"),
+        vec![commit4.id().clone()]
+    );
+
     assert_eq!(
         query("diff_contains(regex:'[1234]', 'file1')"),
         vec![
@@ -4026,6 +6621,12 @@ fn test_reverse_graph() {
     assert_eq!(commits[2].1, vec![GraphEdge::direct(commit_f.id().clone())]);
     assert_eq!(commits[3].1, vec![GraphEdge::direct(commit_f.id().clone())]);
     assert_eq!(commits[4].1, vec![]);
+
+    // The streaming variant should produce the same graph
+    let streamed_commits: Vec<_> = reverse_graph_iter(revset.iter_graph(), |id| id)
+        .try_collect()
+        .unwrap();
+    assert_eq!(streamed_commits, commits);
 }
 
 #[test]
@@ -4073,3 +6674,30 @@ fn test_revset_containing_fn() {
     assert!(!revset_has_commit(commit_c.id()).unwrap());
     assert!(revset_has_commit(commit_d.id()).unwrap());
 }
+
+#[test]
+fn test_revset_first_last() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_a = write_random_commit(mut_repo);
+    let commit_b = write_random_commit(mut_repo);
+    let commit_c = write_random_commit(mut_repo);
+    let repo = tx.commit("test").unwrap();
+
+    let revset = revset_for_commits(repo.as_ref(), &[&commit_a, &commit_b, &commit_c]);
+    let commit_ids: Vec<_> = revset.iter().try_collect().unwrap();
+    assert_eq!(commit_ids.len(), 3);
+    assert_eq!(revset.first().unwrap(), commit_ids.first().cloned());
+    assert_eq!(revset.last().unwrap(), commit_ids.last().cloned());
+
+    let revset = revset_for_commits(repo.as_ref(), &[&commit_b]);
+    assert_eq!(revset.first().unwrap(), Some(commit_b.id().clone()));
+    assert_eq!(revset.last().unwrap(), Some(commit_b.id().clone()));
+
+    let revset = revset_for_commits(repo.as_ref(), &[]);
+    assert_eq!(revset.first().unwrap(), None);
+    assert_eq!(revset.last().unwrap(), None);
+}
@@ -40,6 +40,8 @@ use jj_lib::config::ConfigLayer;
 use jj_lib::config::ConfigSource;
 use jj_lib::config::StackedConfig;
 use jj_lib::git_backend::GitBackend;
+use jj_lib::memory_backend::MemoryBackend;
+use jj_lib::memory_backend::MemoryRepoStorage;
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::object_id::ObjectId as _;
 use jj_lib::repo::MutableRepo;
@@ -127,6 +129,28 @@ pub fn user_settings() -> UserSettings {
     UserSettings::from_config(base_user_config()).unwrap()
 }
 
+/// Returns new low-level config object that pins all inputs of commit and
+/// operation ids: the randomness seed, the operation hostname and username,
+/// and the commit and operation timestamps.
+///
+/// The same sequence of operations will produce the same commit and
+/// operation ids across repositories.
+pub fn deterministic_user_config() -> StackedConfig {
+    let config_text = r#"
+        debug.commit-timestamp = "2001-02-03T04:05:06+07:00"
+        debug.operation-timestamp = "2001-02-03T04:05:06+07:00"
+    "#;
+    let mut config = base_user_config();
+    config.add_layer(ConfigLayer::parse(ConfigSource::User, config_text).unwrap());
+    config
+}
+
+/// Returns new immutable settings object that produces deterministic commit
+/// and operation ids. See [`deterministic_user_config()`].
+pub fn deterministic_user_settings() -> UserSettings {
+    UserSettings::from_config(deterministic_user_config()).unwrap()
+}
+
 /// Panic if `CI` environment variable is set to a non-empty value
 ///
 /// Most CI environments set this variable automatically. See e.g.
@@ -193,6 +217,9 @@ pub enum TestRepoBackend {
     Git,
     Simple,
     Test,
+    /// Keeps the whole repo in memory. `TestRepo` doesn't touch the file
+    /// system at all with this backend.
+    Memory,
 }
 
 impl TestRepoBackend {
@@ -206,6 +233,7 @@ impl TestRepoBackend {
             TestRepoBackend::Git => Ok(Box::new(GitBackend::init_internal(settings, store_path)?)),
             TestRepoBackend::Simple => Ok(Box::new(SimpleBackend::init(store_path))),
             TestRepoBackend::Test => Ok(Box::new(env.test_backend_factory.init(store_path))),
+            TestRepoBackend::Memory => Ok(Box::new(MemoryBackend::new())),
         }
     }
 }
@@ -230,6 +258,18 @@ impl TestRepo {
         let env = TestEnvironment::init();
 
         let repo_dir = env.root().join("repo");
+        if backend == TestRepoBackend::Memory {
+            let signer = Signer::from_settings(settings).unwrap();
+            let repo = MemoryRepoStorage::new()
+                .loader(settings, signer)
+                .load_at_head()
+                .unwrap();
+            return Self {
+                env,
+                repo,
+                repo_path: repo_dir,
+            };
+        }
         fs::create_dir(&repo_dir).unwrap();
 
         let repo = ReadonlyRepo::init(
@@ -445,6 +485,7 @@ pub fn commit_with_tree(store: &Arc<Store>, tree_id: MergedTreeId) -> Commit {
     let commit = backend::Commit {
         parents: vec![store.root_commit_id().clone()],
         predecessors: vec![],
+        duplicated_from: None,
         root_tree: tree_id,
         change_id: ChangeId::from_hex("abcd"),
         description: "description".to_string(),
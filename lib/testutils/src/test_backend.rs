@@ -66,6 +66,7 @@ pub struct TestBackendData {
     files: HashMap<RepoPathBuf, HashMap<FileId, Vec<u8>>>,
     symlinks: HashMap<RepoPathBuf, HashMap<SymlinkId, String>>,
     conflicts: HashMap<RepoPathBuf, HashMap<ConflictId, Conflict>>,
+    commit_read_count: usize,
 }
 
 #[derive(Clone, Default)]
@@ -140,6 +141,12 @@ impl TestBackend {
     pub fn remove_commit_unchecked(&self, id: &CommitId) {
         self.locked_data().commits.remove(id);
     }
+
+    /// Number of commits read from this backend so far. Commits served from
+    /// the store's cache aren't counted.
+    pub fn commit_read_count(&self) -> usize {
+        self.locked_data().commit_read_count
+    }
 }
 
 impl Debug for TestBackend {
@@ -307,7 +314,9 @@ impl Backend for TestBackend {
                 self.empty_tree_id.clone(),
             ));
         }
-        match self.locked_data().commits.get(id).cloned() {
+        let mut data = self.locked_data();
+        data.commit_read_count += 1;
+        match data.commits.get(id).cloned() {
             None => Err(BackendError::ObjectNotFound {
                 object_type: "commit".to_string(),
                 hash: id.hex(),